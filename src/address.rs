@@ -1,21 +1,16 @@
 use crate::error::WalletError;
-use bs58;
 use secp256k1::PublicKey;
 use sha2::{Digest, Sha256};
+use std::fmt;
 
-pub fn generate_address(public_key: &PublicKey, network_prefix: &str) -> String {
-    let pubkey_bytes = public_key.serialize();
-
-    let mut hasher = Sha256::new();
-    hasher.update(&pubkey_bytes);
-    let sha256_hash = hasher.finalize();
+/// Version byte prepended to the public key hash of a P2PKH address.
+pub const ADDRESS_VERSION_P2PKH: u8 = 0x00;
 
-    let mut digest = ripemd::Ripemd160::new();
-    digest.update(&sha256_hash);
-    let pubkey_hash = digest.finalize();
+pub fn generate_address(public_key: &PublicKey, network_prefix: &str) -> String {
+    let pubkey_hash = hash160(&public_key.serialize());
 
     let mut payload = Vec::new();
-    payload.push(0x00);
+    payload.push(ADDRESS_VERSION_P2PKH);
     payload.extend_from_slice(&pubkey_hash);
 
     let checksum = compute_checksum(&payload);
@@ -25,7 +20,17 @@ pub fn generate_address(public_key: &PublicKey, network_prefix: &str) -> String
     format!("{}:{}", network_prefix, address)
 }
 
-fn compute_checksum(payload: &[u8]) -> Vec<u8> {
+/// RIPEMD-160 of the SHA-256 of `data`.
+pub fn hash160(data: &[u8]) -> [u8; 20] {
+    let sha256_hash = Sha256::digest(data);
+
+    let mut digest = ripemd::Ripemd160::new();
+    digest.update(sha256_hash);
+    digest.finalize().into()
+}
+
+/// First four bytes of the double SHA-256 of `payload`.
+pub fn compute_checksum(payload: &[u8]) -> Vec<u8> {
     let mut hasher = Sha256::new();
     hasher.update(payload);
     let first_hash = hasher.finalize();
@@ -37,38 +42,86 @@ fn compute_checksum(payload: &[u8]) -> Vec<u8> {
     second_hash[..4].to_vec()
 }
 
-pub fn validate_address(address: &str) -> Result<bool, WalletError> {
-    if !address.contains(':') {
-        return Ok(false);
-    }
+/// Characters of Bitcoin's Base58 alphabet, which addresses use.
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Why a string is not a well-formed address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressError {
+    MissingPrefix,
+    /// `position` counts the address's characters from 1, prefix included.
+    InvalidCharacter {
+        character: char,
+        position: usize,
+    },
+    TooShort,
+    /// Every character is valid but the checksum does not match. Base58Check
+    /// cannot tell which character is wrong.
+    Checksum,
+}
 
-    let parts: Vec<&str> = address.split(':').collect();
-    if parts.len() != 2 {
-        return Ok(false);
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressError::MissingPrefix => write!(f, "missing the network prefix (kaspa:)"),
+            AddressError::InvalidCharacter {
+                character,
+                position,
+            } => write!(
+                f,
+                "'{}' at character {} is not allowed (0, O, I and l never appear)",
+                character, position
+            ),
+            AddressError::TooShort => write!(f, "too short"),
+            AddressError::Checksum => {
+                write!(f, "checksum failed; a character is mistyped or missing")
+            }
+        }
     }
+}
 
-    let encoded_part = parts[1];
-
-    let decoded = bs58::decode(encoded_part).into_vec();
-    if decoded.is_err() {
-        return Ok(false);
+/// Checks that `address` is `prefix:` followed by a Base58Check payload
+/// holding at least a version byte and a 20-byte hash.
+pub fn check_address_format(address: &str) -> Result<(), AddressError> {
+    let (prefix, encoded) = address.split_once(':').ok_or(AddressError::MissingPrefix)?;
+    if let Some((i, character)) = encoded
+        .chars()
+        .enumerate()
+        .find(|(_, c)| !BASE58_ALPHABET.contains(*c))
+    {
+        return Err(AddressError::InvalidCharacter {
+            character,
+            position: prefix.chars().count() + 2 + i,
+        });
     }
-
-    let decoded_bytes = match decoded {
-        Ok(bytes) => bytes,
-        Err(_) => return Ok(false),
-    };
-
-    if decoded_bytes.len() < 21 {
-        return Ok(false);
+    // Every character is in the alphabet, so decoding cannot fail.
+    let decoded = bs58::decode(encoded).into_vec().unwrap_or_default();
+    if decoded.len() < 21 {
+        return Err(AddressError::TooShort);
     }
+    let (payload, checksum) = decoded.split_at(decoded.len() - 4);
+    if checksum != compute_checksum(payload) {
+        return Err(AddressError::Checksum);
+    }
+    Ok(())
+}
 
-    let payload = &decoded_bytes[..decoded_bytes.len() - 4];
-    let checksum = &decoded_bytes[decoded_bytes.len() - 4..];
-
-    let expected_checksum = compute_checksum(payload);
+/// The P2PKH locking script paying `address`:
+/// `OP_DUP OP_HASH160 <hash> OP_EQUALVERIFY OP_CHECKSIG`.
+pub fn script_public_key(address: &str) -> Result<Vec<u8>, AddressError> {
+    check_address_format(address)?;
+    let (_, encoded) = address.split_once(':').ok_or(AddressError::MissingPrefix)?;
+    let decoded = bs58::decode(encoded).into_vec().unwrap_or_default();
+    let hash = &decoded[1..decoded.len() - 4];
+
+    let mut script = vec![0x76, 0xa9, hash.len() as u8];
+    script.extend_from_slice(hash);
+    script.extend_from_slice(&[0x88, 0xac]);
+    Ok(script)
+}
 
-    Ok(checksum == expected_checksum)
+pub fn validate_address(address: &str) -> Result<bool, WalletError> {
+    Ok(check_address_format(address).is_ok())
 }
 
 #[cfg(test)]
@@ -79,7 +132,7 @@ mod tests {
     #[test]
     fn test_address_generation() {
         let secp = Secp256k1::new();
-        let (secret_key, public_key) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let (_secret_key, public_key) = secp.generate_keypair(&mut rand::rngs::OsRng);
 
         let address = generate_address(&public_key, "kaspa");
         assert!(address.starts_with("kaspa:"));
@@ -88,6 +141,55 @@ mod tests {
     #[test]
     fn test_address_validation() {
         assert!(validate_address("kaspa:abc").is_ok());
-        assert_eq!(validate_address("kaspa:abc").unwrap(), false);
+        assert!(!validate_address("kaspa:abc").unwrap());
+    }
+
+    #[test]
+    fn test_script_public_key() {
+        let secp = Secp256k1::new();
+        let (_, public_key) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let address = generate_address(&public_key, "kaspa");
+
+        let script = script_public_key(&address).unwrap();
+        assert_eq!(&script[..3], &[0x76, 0xa9, 20]);
+        assert_eq!(&script[3..23], &hash160(&public_key.serialize()));
+        assert_eq!(&script[23..], &[0x88, 0xac]);
+        assert_eq!(script_public_key("kaspa:abc"), Err(AddressError::TooShort));
+    }
+
+    #[test]
+    fn test_address_errors() {
+        let secp = Secp256k1::new();
+        let (_, public_key) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let address = generate_address(&public_key, "kaspa");
+        assert_eq!(check_address_format(&address), Ok(()));
+
+        let (prefix, encoded) = address.split_once(':').unwrap();
+        assert_eq!(
+            check_address_format(encoded),
+            Err(AddressError::MissingPrefix)
+        );
+        let mut typo: Vec<char> = address.chars().collect();
+        typo[22] = '0';
+        assert_eq!(
+            check_address_format(&typo.iter().collect::<String>()),
+            Err(AddressError::InvalidCharacter {
+                character: '0',
+                position: 23
+            })
+        );
+        typo[22] = if address.chars().nth(22) == Some('z') {
+            'y'
+        } else {
+            'z'
+        };
+        assert_eq!(
+            check_address_format(&typo.iter().collect::<String>()),
+            Err(AddressError::Checksum)
+        );
+        assert_eq!(
+            check_address_format(&format!("{}:{}", prefix, &encoded[..20])),
+            Err(AddressError::TooShort)
+        );
     }
 }
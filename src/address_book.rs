@@ -0,0 +1,141 @@
+use crate::address::validate_address;
+use crate::error::{WalletError, WalletResult};
+use crate::storage::{self, Overwrite};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const ADDRESS_BOOK_FILE: &str = "address_book.json";
+
+/// A labelled recipient. Contacts belong to one network so a testnet
+/// address can't be picked while sending on mainnet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contact {
+    pub label: String,
+    pub address: String,
+    pub network: String,
+}
+
+/// Contacts persisted in `address_book.json` under the data dir.
+pub struct AddressBook {
+    path: PathBuf,
+    contacts: Vec<Contact>,
+}
+
+impl AddressBook {
+    /// Loads the address book, starting empty when the file is missing.
+    pub fn load(data_dir: &Path) -> WalletResult<Self> {
+        let path = data_dir.join(ADDRESS_BOOK_FILE);
+        let contacts = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?).map_err(|e| {
+                WalletError::Storage(format!("Invalid address book {}: {}", path.display(), e))
+            })?
+        } else {
+            Vec::new()
+        };
+        Ok(Self { path, contacts })
+    }
+
+    pub fn save(&self) -> WalletResult<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(&self.contacts)
+            .map_err(|e| WalletError::Serialization(format!("Address book encode error: {}", e)))?;
+        storage::write_atomic(&self.path, json.as_bytes(), Overwrite::Allow)
+    }
+
+    /// Contacts on `network`, in the order they were added.
+    pub fn contacts<'a>(&'a self, network: &'a str) -> impl Iterator<Item = &'a Contact> + 'a {
+        self.contacts
+            .iter()
+            .filter(move |contact| contact.network == network)
+    }
+
+    pub fn find(&self, network: &str, label: &str) -> Option<&Contact> {
+        self.contacts
+            .iter()
+            .find(|contact| contact.network == network && contact.label == label)
+    }
+
+    /// Adds a contact; labels are unique per network.
+    pub fn add(&mut self, contact: Contact) -> WalletResult<()> {
+        if contact.label.trim().is_empty() {
+            return Err(WalletError::InvalidParameters(
+                "Contact label must not be empty".to_string(),
+            ));
+        }
+        if !validate_address(&contact.address)? {
+            return Err(WalletError::InvalidAddressFormat);
+        }
+        if self.find(&contact.network, &contact.label).is_some() {
+            return Err(WalletError::InvalidParameters(format!(
+                "A contact named {} already exists on {}",
+                contact.label, contact.network
+            )));
+        }
+        self.contacts.push(contact);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, network: &str, label: &str) -> Option<Contact> {
+        let index = self
+            .contacts
+            .iter()
+            .position(|contact| contact.network == network && contact.label == label)?;
+        Some(self.contacts.remove(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::KaspaWallet;
+    use secp256k1::Secp256k1;
+
+    fn address() -> String {
+        let secp = Secp256k1::new();
+        let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        KaspaWallet::with_network(secret_key, "mainnet")
+            .unwrap()
+            .get_address()
+    }
+
+    fn contact(label: &str, network: &str) -> Contact {
+        Contact {
+            label: label.to_string(),
+            address: address(),
+            network: network.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_persists_per_network() {
+        let dir = std::env::temp_dir().join(format!(
+            "kasparustwallet-address-book-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut book = AddressBook::load(&dir).unwrap();
+        book.add(contact("alice", "mainnet")).unwrap();
+        book.add(contact("alice", "testnet-10")).unwrap();
+        assert!(book.add(contact("alice", "mainnet")).is_err());
+        book.save().unwrap();
+
+        let mut book = AddressBook::load(&dir).unwrap();
+        assert_eq!(book.contacts("mainnet").count(), 1);
+        assert!(book.find("simnet", "alice").is_none());
+        assert!(book.remove("testnet-10", "alice").is_some());
+        assert_eq!(book.contacts("testnet-10").count(), 0);
+    }
+
+    #[test]
+    fn test_rejects_invalid_contacts() {
+        let mut book = AddressBook::load(Path::new("/nonexistent")).unwrap();
+        let mut bad = contact("bob", "mainnet");
+        bad.address = "kaspa:notanaddress".to_string();
+        assert!(book.add(bad).is_err());
+        assert!(book.add(contact(" ", "mainnet")).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use crate::error::{WalletError, WalletResult};
+use crate::hd::CHANGE_CHAIN;
+use crate::storage::{self, Overwrite};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+pub const ADDRESS_INDEX_FILE: &str = "address_index.json";
+
+/// How many addresses of each chain have been handed out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainIndexes {
+    pub receive: u32,
+    pub change: u32,
+}
+
+/// Next unused HD address index per wallet fingerprint, persisted in
+/// `address_index.json` under the data dir.
+pub struct AddressIndex {
+    path: PathBuf,
+    wallets: BTreeMap<String, ChainIndexes>,
+}
+
+impl AddressIndex {
+    /// Loads the index, starting empty when the file is missing.
+    pub fn load(data_dir: &Path) -> WalletResult<Self> {
+        let path = data_dir.join(ADDRESS_INDEX_FILE);
+        let wallets = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?).map_err(|e| {
+                WalletError::Storage(format!("Invalid address index {}: {}", path.display(), e))
+            })?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self { path, wallets })
+    }
+
+    pub fn save(&self) -> WalletResult<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(&self.wallets).map_err(|e| {
+            WalletError::Serialization(format!("Address index encode error: {}", e))
+        })?;
+        storage::write_atomic(&self.path, json.as_bytes(), Overwrite::Allow)
+    }
+
+    /// First index on `chain` not yet handed out.
+    pub fn next(&self, fingerprint: &str, chain: u32) -> u32 {
+        let indexes = self.wallets.get(fingerprint).copied().unwrap_or_default();
+        if chain == CHANGE_CHAIN {
+            indexes.change
+        } else {
+            indexes.receive
+        }
+    }
+
+    /// Hands out the next `count` indexes on `chain`.
+    pub fn reserve(
+        &mut self,
+        fingerprint: &str,
+        chain: u32,
+        count: u32,
+    ) -> WalletResult<Range<u32>> {
+        let indexes = self.wallets.entry(fingerprint.to_string()).or_default();
+        let next = if chain == CHANGE_CHAIN {
+            &mut indexes.change
+        } else {
+            &mut indexes.receive
+        };
+        let start = *next;
+        *next = start
+            .checked_add(count)
+            .ok_or_else(|| WalletError::InvalidParameters("Address index overflow".to_string()))?;
+        Ok(start..*next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hd::RECEIVE_CHAIN;
+
+    #[test]
+    fn test_reserve_persists() {
+        let dir = std::env::temp_dir().join(format!(
+            "kasparustwallet-address-index-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut index = AddressIndex::load(&dir).unwrap();
+        assert_eq!(index.reserve("abcd", RECEIVE_CHAIN, 3).unwrap(), 0..3);
+        assert_eq!(index.reserve("abcd", RECEIVE_CHAIN, 2).unwrap(), 3..5);
+        assert_eq!(index.reserve("abcd", CHANGE_CHAIN, 1).unwrap(), 0..1);
+        index.save().unwrap();
+
+        let reloaded = AddressIndex::load(&dir).unwrap();
+        assert_eq!(reloaded.next("abcd", RECEIVE_CHAIN), 5);
+        assert_eq!(reloaded.next("abcd", CHANGE_CHAIN), 1);
+        assert_eq!(reloaded.next("other", RECEIVE_CHAIN), 0);
+    }
+}
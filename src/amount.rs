@@ -0,0 +1,203 @@
+use crate::error::WalletError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+pub const SOMPI_PER_KAS: u64 = 100_000_000;
+/// Number of decimal places in a KAS amount.
+pub const KAS_DECIMALS: usize = 8;
+
+/// An amount of KAS, held in sompi.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Kas(u64);
+
+impl Kas {
+    pub const ZERO: Kas = Kas(0);
+
+    pub fn from_sompi(sompi: u64) -> Self {
+        Kas(sompi)
+    }
+
+    pub fn sompi(self) -> u64 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Kas) -> Option<Kas> {
+        self.0.checked_add(other.0).map(Kas)
+    }
+
+    pub fn checked_sub(self, other: Kas) -> Option<Kas> {
+        self.0.checked_sub(other.0).map(Kas)
+    }
+
+    /// Parses `1.5kas`, `150000000sompi`, or a bare number in `default`
+    /// units. A bare number without a default is an error.
+    pub fn parse_with_unit(s: &str, default: Option<Unit>) -> Result<Kas, WalletError> {
+        let s = s.trim();
+        let lower = s.to_ascii_lowercase();
+        let (number, unit) = if let Some(number) = lower.strip_suffix("sompi") {
+            (number, Some(Unit::Sompi))
+        } else if let Some(number) = lower.strip_suffix("kas") {
+            (number, Some(Unit::Kas))
+        } else {
+            (lower.as_str(), default)
+        };
+        match unit {
+            Some(Unit::Kas) => number.trim().parse(),
+            Some(Unit::Sompi) => {
+                let number = number.trim();
+                if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+                    return Err(WalletError::InvalidParameters(format!(
+                        "Invalid sompi amount {:?}: expected a whole number",
+                        s
+                    )));
+                }
+                number.parse().map(Kas).map_err(|_| {
+                    WalletError::InvalidParameters(format!(
+                        "Invalid sompi amount {:?}: too large",
+                        s
+                    ))
+                })
+            }
+            None => Err(WalletError::InvalidParameters(format!(
+                "Amount {:?} has no unit; add kas or sompi, or pass --from",
+                s
+            ))),
+        }
+    }
+}
+
+/// A unit an amount can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Kas,
+    Sompi,
+}
+
+impl FromStr for Unit {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "kas" => Ok(Unit::Kas),
+            "sompi" => Ok(Unit::Sompi),
+            _ => Err(WalletError::InvalidParameters(format!(
+                "Unknown unit {:?}: expected kas or sompi",
+                s
+            ))),
+        }
+    }
+}
+
+/// Decimal KAS without trailing zeros, e.g. `1.5` or `0.00000001`.
+impl fmt::Display for Kas {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let whole = self.0 / SOMPI_PER_KAS;
+        let fraction = self.0 % SOMPI_PER_KAS;
+        if fraction == 0 {
+            return write!(f, "{}", whole);
+        }
+        let digits = format!("{:0width$}", fraction, width = KAS_DECIMALS);
+        write!(f, "{}.{}", whole, digits.trim_end_matches('0'))
+    }
+}
+
+/// Parses decimal KAS such as `1.5`, `0.00000001` or `150`. Only `.` is
+/// accepted as the decimal separator, and at most eight decimals.
+impl FromStr for Kas {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            WalletError::InvalidParameters(format!("Invalid KAS amount {:?}: {}", s, reason))
+        };
+
+        let s_trimmed = s.trim();
+        if s_trimmed.contains(',') {
+            return Err(invalid(
+                "use '.' as the decimal separator, without grouping",
+            ));
+        }
+        let (whole, fraction) = s_trimmed.split_once('.').unwrap_or((s_trimmed, ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(invalid("empty amount"));
+        }
+        if !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid("expected digits with an optional '.'"));
+        }
+        if fraction.len() > KAS_DECIMALS {
+            return Err(invalid("more than 8 decimal places"));
+        }
+
+        let whole: u64 = if whole.is_empty() {
+            0
+        } else {
+            whole.parse().map_err(|_| invalid("too large"))?
+        };
+        let fraction: u64 = format!("{:0<width$}", fraction, width = KAS_DECIMALS)
+            .parse()
+            .expect("eight ASCII digits");
+
+        whole
+            .checked_mul(SOMPI_PER_KAS)
+            .and_then(|sompi| sompi.checked_add(fraction))
+            .map(Kas)
+            .ok_or_else(|| invalid("too large"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Kas::from_sompi(0).to_string(), "0");
+        assert_eq!(Kas::from_sompi(150_000_000).to_string(), "1.5");
+        assert_eq!(Kas::from_sompi(1).to_string(), "0.00000001");
+        assert_eq!(Kas::from_sompi(587_700).to_string(), "0.005877");
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("1.5".parse::<Kas>().unwrap().sompi(), 150_000_000);
+        assert_eq!("150".parse::<Kas>().unwrap().sompi(), 15_000_000_000);
+        assert_eq!("0.00000001".parse::<Kas>().unwrap().sompi(), 1);
+        assert_eq!(".5".parse::<Kas>().unwrap().sompi(), 50_000_000);
+
+        for bad in [
+            "1,5",
+            "1.000000001",
+            "",
+            ".",
+            "-1",
+            "1e3",
+            "abc",
+            "999999999999999",
+        ] {
+            assert!(bad.parse::<Kas>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_parse_with_unit() {
+        let kas = |s, default| Kas::parse_with_unit(s, default).map(Kas::sompi);
+        assert_eq!(kas("1.5kas", None).unwrap(), 150_000_000);
+        assert_eq!(kas("1.5 KAS", Some(Unit::Sompi)).unwrap(), 150_000_000);
+        assert_eq!(kas("150000000sompi", None).unwrap(), 150_000_000);
+        assert_eq!(kas("42", Some(Unit::Sompi)).unwrap(), 42);
+        assert_eq!(kas("0.001", Some(Unit::Kas)).unwrap(), 100_000);
+
+        assert!(kas("42", None).is_err());
+        assert!(kas("1.5sompi", None).is_err());
+        assert!(kas("18446744073709551616sompi", None).is_err());
+        assert!(kas("0.000000001kas", None).is_err());
+    }
+}
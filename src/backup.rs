@@ -0,0 +1,87 @@
+use crate::crypto::{self, SealedBox};
+use crate::error::{WalletError, WalletResult};
+use bip39::Mnemonic;
+use zeroize::Zeroizing;
+
+/// File header for encrypted seed-phrase backups. Deliberately different
+/// from the keystore header so the two file types can't be mixed up.
+pub const MNEMONIC_BACKUP_MAGIC: &[u8; 8] = b"KRWSEED\0";
+pub const MNEMONIC_BACKUP_VERSION: u8 = 1;
+
+fn header() -> Vec<u8> {
+    let mut header = MNEMONIC_BACKUP_MAGIC.to_vec();
+    header.push(MNEMONIC_BACKUP_VERSION);
+    header
+}
+
+/// Encrypt a seed phrase for digital backup. Weak passwords are refused.
+pub fn export_encrypted_mnemonic(mnemonic: &Mnemonic, password: &str) -> WalletResult<Vec<u8>> {
+    crypto::check_password_strength(password)?;
+
+    let header = header();
+    let phrase = Zeroizing::new(mnemonic.to_string());
+    let sealed = crypto::seal(phrase.as_bytes(), password, &header)?;
+
+    let mut bytes = header;
+    bytes.extend_from_slice(&sealed.to_bytes());
+    Ok(bytes)
+}
+
+pub fn import_encrypted_mnemonic(data: &[u8], password: &str) -> WalletResult<Mnemonic> {
+    let header_len = MNEMONIC_BACKUP_MAGIC.len() + 1;
+    if data.len() < header_len || &data[..MNEMONIC_BACKUP_MAGIC.len()] != MNEMONIC_BACKUP_MAGIC {
+        return Err(WalletError::Storage(
+            "Not an encrypted mnemonic backup".to_string(),
+        ));
+    }
+    let version = data[MNEMONIC_BACKUP_MAGIC.len()];
+    if version != MNEMONIC_BACKUP_VERSION {
+        return Err(WalletError::Storage(format!(
+            "Unsupported mnemonic backup version: {}",
+            version
+        )));
+    }
+
+    let sealed = SealedBox::from_bytes(&data[header_len..])?;
+    let plaintext = crypto::open(&sealed, password, &data[..header_len])?;
+    let phrase = std::str::from_utf8(&plaintext)
+        .map_err(|_| WalletError::Storage("Backup does not contain a phrase".to_string()))?;
+
+    Mnemonic::parse_normalized(phrase)
+        .map_err(|e| WalletError::Storage(format!("Backup contains an invalid phrase: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWORD: &str = "correct horse battery staple";
+
+    #[test]
+    fn test_mnemonic_backup_roundtrip() {
+        let mnemonic = Mnemonic::generate(12).unwrap();
+        let encrypted = export_encrypted_mnemonic(&mnemonic, PASSWORD).unwrap();
+
+        assert!(encrypted.starts_with(MNEMONIC_BACKUP_MAGIC));
+        assert!(!String::from_utf8_lossy(&encrypted).contains(&mnemonic.to_string()));
+        assert_eq!(
+            import_encrypted_mnemonic(&encrypted, PASSWORD).unwrap(),
+            mnemonic
+        );
+        assert!(import_encrypted_mnemonic(&encrypted, "not the password at all").is_err());
+    }
+
+    #[test]
+    fn test_weak_password_refused() {
+        let mnemonic = Mnemonic::generate(12).unwrap();
+        assert!(export_encrypted_mnemonic(&mnemonic, "hunter2").is_err());
+    }
+
+    #[test]
+    fn test_foreign_header_rejected() {
+        let mnemonic = Mnemonic::generate(12).unwrap();
+        let mut encrypted = export_encrypted_mnemonic(&mnemonic, PASSWORD).unwrap();
+        encrypted[..8].copy_from_slice(b"KRWKEYS\0");
+        assert!(import_encrypted_mnemonic(&encrypted, PASSWORD).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use crate::address::validate_address;
+use crate::amount::Kas;
+use crate::error::{WalletError, WalletResult};
+use crate::transaction::Transaction;
+use std::collections::HashMap;
+
+/// One row of a batch payment file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchPayment {
+    pub address: String,
+    pub amount: Kas,
+    pub label: Option<String>,
+    /// 1-based line in the source file.
+    pub line: usize,
+}
+
+/// Parses `address,amount_kas[,label]` rows, validating the whole file and
+/// reporting every bad row at once. A header row starting with `address`
+/// and blank or `#` lines are skipped. Repeated addresses are an error
+/// unless `allow_duplicates` is set, in which case they are merged into the
+/// first row for that address.
+pub fn parse_batch_csv(text: &str, allow_duplicates: bool) -> WalletResult<Vec<BatchPayment>> {
+    let mut payments: Vec<BatchPayment> = Vec::new();
+    let mut first_row: HashMap<String, usize> = HashMap::new();
+    let mut errors = Vec::new();
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let row = raw.trim();
+        if row.is_empty() || row.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = row.split(',').map(str::trim).collect();
+        if line == 1 && fields[0].eq_ignore_ascii_case("address") {
+            continue;
+        }
+        if fields.len() < 2 || fields.len() > 3 {
+            errors.push(format!(
+                "line {}: expected address,amount_kas[,label] but found {} field(s)",
+                line,
+                fields.len()
+            ));
+            continue;
+        }
+
+        let address = fields[0].to_string();
+        if !validate_address(&address).unwrap_or(false) {
+            errors.push(format!("line {}: invalid address {}", line, address));
+            continue;
+        }
+        let amount: Kas = match fields[1].parse() {
+            Ok(amount) if amount > Kas::ZERO => amount,
+            Ok(_) => {
+                errors.push(format!("line {}: amount must be positive", line));
+                continue;
+            }
+            Err(e) => {
+                errors.push(format!("line {}: {}", line, e));
+                continue;
+            }
+        };
+        let label = fields
+            .get(2)
+            .filter(|label| !label.is_empty())
+            .map(|label| label.to_string());
+
+        match first_row.get(&address) {
+            Some(&existing) if allow_duplicates => {
+                let merged = &mut payments[existing];
+                merged.amount = merged.amount.checked_add(amount).ok_or_else(|| {
+                    WalletError::InvalidParameters(format!("line {}: amount overflows", line))
+                })?;
+            }
+            Some(&existing) => errors.push(format!(
+                "line {}: duplicate of line {} ({}); pass --allow-duplicates to merge",
+                line, payments[existing].line, address
+            )),
+            None => {
+                first_row.insert(address.clone(), payments.len());
+                payments.push(BatchPayment {
+                    address,
+                    amount,
+                    label,
+                    line,
+                });
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(WalletError::InvalidParameters(format!(
+            "{} bad row(s) in batch file:\n  {}",
+            errors.len(),
+            errors.join("\n  ")
+        )));
+    }
+    if payments.is_empty() {
+        return Err(WalletError::InvalidParameters(
+            "Batch file has no payments".to_string(),
+        ));
+    }
+    Ok(payments)
+}
+
+/// Groups payments into as few transactions as possible, each spending
+/// `input_count` inputs and staying within `max_mass`.
+pub fn split_into_transactions(
+    payments: &[BatchPayment],
+    input_count: usize,
+    max_mass: u64,
+) -> WalletResult<Vec<Vec<BatchPayment>>> {
+    let mass_of = |outputs: usize| {
+        let mut tx = Transaction::new();
+        for _ in 0..input_count {
+            tx.add_input(String::new(), 0);
+        }
+        for _ in 0..outputs {
+            tx.add_output(String::new(), 0);
+        }
+        tx.mass()
+    };
+
+    let per_tx = (1..=u8::MAX as usize)
+        .take_while(|&outputs| mass_of(outputs) <= max_mass)
+        .last()
+        .ok_or_else(|| {
+            WalletError::Transaction("Not even one output fits within the mass limit".to_string())
+        })?;
+
+    Ok(payments.chunks(per_tx).map(<[_]>::to_vec).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::KaspaWallet;
+    use secp256k1::Secp256k1;
+
+    fn address() -> String {
+        let secp = Secp256k1::new();
+        let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        KaspaWallet::with_network(secret_key, "mainnet")
+            .unwrap()
+            .get_address()
+    }
+
+    #[test]
+    fn test_parse_reports_every_bad_row() {
+        let (a, b) = (address(), address());
+        let csv = format!(
+            "address,amount,label\n{a},1.5,alice\n{b},2\nkaspa:bogus,1\n{a},0.5\n{b},1,5\n"
+        );
+
+        let err = parse_batch_csv(&csv, false).unwrap_err().to_string();
+        assert!(err.contains("line 4: invalid address"), "{}", err);
+        assert!(err.contains("line 5: duplicate of line 2"), "{}", err);
+        assert!(err.contains("line 6"), "{}", err);
+
+        let csv = format!("{a},1.5,alice\n{b},2\n{a},0.5\n");
+        let payments = parse_batch_csv(&csv, true).unwrap();
+        assert_eq!(payments.len(), 2);
+        assert_eq!(payments[0].amount.sompi(), 200_000_000);
+        assert_eq!(payments[0].label.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn test_split_respects_mass_limit() {
+        let payments: Vec<BatchPayment> = (0..10)
+            .map(|line| BatchPayment {
+                address: "kaspa:x".to_string(),
+                amount: Kas::from_sompi(1),
+                label: None,
+                line,
+            })
+            .collect();
+
+        assert_eq!(
+            split_into_transactions(&payments, 1, 100_000)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let groups = split_into_transactions(&payments, 1, 1_200).unwrap();
+        assert!(groups.len() > 1);
+        assert_eq!(groups.iter().map(Vec::len).sum::<usize>(), 10);
+    }
+}
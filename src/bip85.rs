@@ -0,0 +1,109 @@
+use crate::error::{WalletError, WalletResult};
+use crate::hd::{hmac_sha512, ChildNumber, DerivationPath, ExtendedPrivateKey};
+use bip39::Mnemonic;
+use zeroize::Zeroizing;
+
+/// BIP85 purpose, "DRNG" in ASCII digits.
+pub const BIP85_PURPOSE: u32 = 83696968;
+/// BIP85 application number for BIP39 mnemonics.
+pub const BIP85_APP_BIP39: u32 = 39;
+/// BIP39 language code for English, the only wordlist enabled.
+pub const BIP85_LANGUAGE_ENGLISH: u32 = 0;
+
+const ENTROPY_HMAC_KEY: &[u8] = b"bip-entropy-from-k";
+
+/// `m/83696968'/39'/0'/{words}'/{index}'`
+pub fn bip39_path(word_count: u32, index: u32) -> WalletResult<DerivationPath> {
+    [
+        BIP85_PURPOSE,
+        BIP85_APP_BIP39,
+        BIP85_LANGUAGE_ENGLISH,
+        word_count,
+        index,
+    ]
+    .into_iter()
+    .try_fold(DerivationPath::default(), |path, n| {
+        Ok(path.child(ChildNumber::hardened(n)?))
+    })
+}
+
+/// The 64 bytes of BIP85 entropy for the key at `path` below `master`.
+pub fn derive_entropy(
+    master: &ExtendedPrivateKey,
+    path: &DerivationPath,
+) -> WalletResult<Zeroizing<[u8; 64]>> {
+    let key = master.derive_path(path)?;
+    Ok(Zeroizing::new(hmac_sha512(
+        ENTROPY_HMAC_KEY,
+        &key.secret_key().secret_bytes(),
+    )))
+}
+
+/// Child mnemonic number `index` with `word_count` words (12, 18 or 24).
+pub fn derive_mnemonic(
+    master: &ExtendedPrivateKey,
+    word_count: u32,
+    index: u32,
+) -> WalletResult<Mnemonic> {
+    let entropy_len = match word_count {
+        12 => 16,
+        18 => 24,
+        24 => 32,
+        _ => {
+            return Err(WalletError::InvalidParameters(format!(
+                "Child mnemonics must have 12, 18 or 24 words, not {}",
+                word_count
+            )))
+        }
+    };
+
+    let entropy = derive_entropy(master, &bip39_path(word_count, index)?)?;
+    Mnemonic::from_entropy(&entropy[..entropy_len])
+        .map_err(|e| WalletError::KeyGeneration(format!("Mnemonic encode error: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vectors from the BIP85 specification.
+    const MASTER: &str = "xprv9s21ZrQH143K2LBWUUQRFXhucrQqBpKdRRxNVq2zBqsx8HVqFk2uYo8kmbaLLHRdqtQpUm98uKfu3vca1LqdGhUtyoFnCNkfmXRyPXLjbKb";
+
+    fn master() -> ExtendedPrivateKey {
+        MASTER.parse().unwrap()
+    }
+
+    #[test]
+    fn test_entropy_vectors() {
+        let cases = [
+            (
+                "m/83696968'/0'/0'",
+                "efecfbccffea313214232d29e71563d941229afb4338c21f9517c41aaa0d16f00b83d2a09ef747e7a64e8e2bd5a14869e693da66ce94ac2da570ab7ee48618f7",
+            ),
+            (
+                "m/83696968'/0'/1'",
+                "70c6e3e8ebee8dc4c0dbba66076819bb8c09672527c4277ca8729532ad711872218f826919f6b67218adde99018a6df9095ab2b58d803b5b93ec9802085a690e",
+            ),
+        ];
+        for (path, expected) in cases {
+            let entropy = derive_entropy(&master(), &path.parse().unwrap()).unwrap();
+            assert_eq!(hex::encode(&entropy[..]), expected);
+        }
+    }
+
+    #[test]
+    fn test_bip39_vectors() {
+        let cases = [
+            (12, "girl mad pet galaxy egg matter matrix prison refuse sense ordinary nose"),
+            (18, "near account window bike charge season chef number sketch tomorrow excuse sniff circle vital hockey outdoor supply token"),
+            (24, "puppy ocean match cereal symbol another shed magic wrap hammer bulb intact gadget divorce twin tonight reason outdoor destroy simple truth cigar social volcano"),
+        ];
+        for (words, expected) in cases {
+            assert_eq!(
+                derive_mnemonic(&master(), words, 0).unwrap().to_string(),
+                expected
+            );
+        }
+        assert!(derive_mnemonic(&master(), 15, 0).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use crate::error::{WalletError, WalletResult};
+use crate::storage::{self, Overwrite};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_FILE: &str = "config.json";
+
+/// Per-user directory for wallet data such as the event log.
+pub fn default_data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("kasparustwallet")
+}
+
+/// Settings persisted in `config.json` under the data dir.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Config {
+    /// Wallet used when a command is given neither `--wallet` nor a key.
+    #[serde(default)]
+    pub default_wallet: Option<String>,
+    /// Base URL of the REST explorer used by `history`, and by `balance`,
+    /// `utxos` and `restore --scan` when asked to use it.
+    #[serde(default)]
+    pub explorer_url: Option<String>,
+    /// Base URL of a CoinGecko-style price API, for the GUI's approximate
+    /// fiat values.
+    #[serde(default)]
+    pub price_url: Option<String>,
+    /// Node RPC address used when `--node` is not given, as `host:port`
+    /// or `grpc://host:port`.
+    #[serde(default)]
+    pub node_url: Option<String>,
+    /// Node RPC addresses tried in order when `--node` is not given, moving
+    /// on to the next when one fails; takes the place of `node_url`.
+    #[serde(default)]
+    pub node_urls: Option<Vec<String>>,
+    /// Seconds before a node or explorer request times out, when the
+    /// command is not given `--rpc-timeout`.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Retries after a failed node or explorer request, when the command
+    /// is not given `--retries`.
+    #[serde(default)]
+    pub retries: Option<u32>,
+}
+
+impl Config {
+    /// Loads the config, falling back to defaults when the file is missing.
+    pub fn load(data_dir: &Path) -> WalletResult<Self> {
+        let path = data_dir.join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        serde_json::from_str(&fs::read_to_string(&path)?).map_err(|e| {
+            WalletError::Storage(format!("Invalid config file {}: {}", path.display(), e))
+        })
+    }
+
+    pub fn save(&self, data_dir: &Path) -> WalletResult<()> {
+        fs::create_dir_all(data_dir)?;
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| WalletError::Serialization(format!("Config encode error: {}", e)))?;
+        storage::write_atomic(
+            &data_dir.join(CONFIG_FILE),
+            json.as_bytes(),
+            Overwrite::Allow,
+        )
+    }
+}
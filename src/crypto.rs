@@ -0,0 +1,253 @@
+use crate::error::{WalletError, WalletResult};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::RngCore;
+use zeroize::Zeroizing;
+
+pub const SALT_LEN: usize = 16;
+pub const NONCE_LEN: usize = 12;
+
+/// Passwords scoring below this many bits are rejected for new secrets.
+pub const MIN_PASSWORD_ENTROPY_BITS: f64 = 50.0;
+
+/// Argon2id cost parameters, stored alongside each ciphertext so they can be
+/// raised later without breaking existing files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl KdfParams {
+    /// The most a stored file may ask for: 1 GiB of memory, 16 passes and
+    /// 16 lanes. The parameters are read before anything is authenticated,
+    /// so without a bound a crafted file could demand any allocation.
+    pub const MAX: KdfParams = KdfParams {
+        m_cost: 1024 * 1024,
+        t_cost: 16,
+        p_cost: 16,
+    };
+
+    fn check(&self) -> WalletResult<()> {
+        let max = Self::MAX;
+        if self.m_cost > max.m_cost || self.t_cost > max.t_cost || self.p_cost > max.p_cost {
+            return Err(WalletError::Storage(format!(
+                "KDF parameters out of range (memory {} KiB, {} passes, {} lanes; at most {} KiB, {} and {})",
+                self.m_cost, self.t_cost, self.p_cost, max.m_cost, max.t_cost, max.p_cost
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            m_cost: 19 * 1024,
+            t_cost: 2,
+            p_cost: 1,
+        }
+    }
+}
+
+/// Ciphertext plus everything except the password needed to decrypt it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SealedBox {
+    pub params: KdfParams,
+    pub salt: [u8; SALT_LEN],
+    pub nonce: [u8; NONCE_LEN],
+    pub ciphertext: Vec<u8>,
+}
+
+fn derive_key(password: &str, salt: &[u8], params: KdfParams) -> WalletResult<Zeroizing<[u8; 32]>> {
+    params.check()?;
+    let argon_params = Params::new(params.m_cost, params.t_cost, params.p_cost, Some(32))
+        .map_err(|e| WalletError::Storage(format!("Invalid KDF parameters: {}", e)))?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, argon_params);
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    argon2
+        .hash_password_into(password.as_bytes(), salt, key.as_mut())
+        .map_err(|e| WalletError::Storage(format!("Key derivation failed: {}", e)))?;
+    Ok(key)
+}
+
+/// Encrypt `plaintext` under an argon2id-derived key with AES-256-GCM.
+/// `aad` (typically the file header) is authenticated but not encrypted.
+pub fn seal(plaintext: &[u8], password: &str, aad: &[u8]) -> WalletResult<SealedBox> {
+    let params = KdfParams::default();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    rand::rngs::OsRng.fill_bytes(&mut nonce);
+
+    let key = derive_key(password, &salt, params)?;
+    let cipher = Aes256Gcm::new_from_slice(key.as_ref())
+        .map_err(|e| WalletError::Storage(format!("Cipher init failed: {}", e)))?;
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| WalletError::Storage("Encryption failed".to_string()))?;
+
+    Ok(SealedBox {
+        params,
+        salt,
+        nonce,
+        ciphertext,
+    })
+}
+
+/// Decrypt a `SealedBox`; a wrong password and a tampered file are
+/// indistinguishable and both fail authentication.
+pub fn open(sealed: &SealedBox, password: &str, aad: &[u8]) -> WalletResult<Zeroizing<Vec<u8>>> {
+    let key = derive_key(password, &sealed.salt, sealed.params)?;
+    let cipher = Aes256Gcm::new_from_slice(key.as_ref())
+        .map_err(|e| WalletError::Storage(format!("Cipher init failed: {}", e)))?;
+    cipher
+        .decrypt(
+            Nonce::from_slice(&sealed.nonce),
+            Payload {
+                msg: &sealed.ciphertext,
+                aad,
+            },
+        )
+        .map(Zeroizing::new)
+        .map_err(|_| WalletError::Authentication("wrong password or corrupted data".to_string()))
+}
+
+/// Rough entropy estimate: length times log2 of the character pool in use.
+pub fn estimate_password_entropy(password: &str) -> f64 {
+    let mut pool = 0u32;
+    if password.chars().any(|c| c.is_ascii_lowercase()) {
+        pool += 26;
+    }
+    if password.chars().any(|c| c.is_ascii_uppercase()) {
+        pool += 26;
+    }
+    if password.chars().any(|c| c.is_ascii_digit()) {
+        pool += 10;
+    }
+    if password
+        .chars()
+        .any(|c| c.is_ascii_punctuation() || c == ' ')
+    {
+        pool += 33;
+    }
+    if !password.is_ascii() {
+        pool += 100;
+    }
+    if pool == 0 {
+        return 0.0;
+    }
+    password.chars().count() as f64 * f64::from(pool).log2()
+}
+
+pub fn check_password_strength(password: &str) -> WalletResult<()> {
+    let bits = estimate_password_entropy(password);
+    if bits < MIN_PASSWORD_ENTROPY_BITS {
+        return Err(WalletError::InvalidParameters(format!(
+            "Password too weak (~{:.0} bits, need {:.0}); use a longer passphrase",
+            bits, MIN_PASSWORD_ENTROPY_BITS
+        )));
+    }
+    Ok(())
+}
+
+impl SealedBox {
+    /// `params || salt || nonce || ciphertext`, all integers little-endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(12 + SALT_LEN + NONCE_LEN + self.ciphertext.len());
+        bytes.extend_from_slice(&self.params.m_cost.to_le_bytes());
+        bytes.extend_from_slice(&self.params.t_cost.to_le_bytes());
+        bytes.extend_from_slice(&self.params.p_cost.to_le_bytes());
+        bytes.extend_from_slice(&self.salt);
+        bytes.extend_from_slice(&self.nonce);
+        bytes.extend_from_slice(&self.ciphertext);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> WalletResult<Self> {
+        let header_len = 12 + SALT_LEN + NONCE_LEN;
+        if bytes.len() <= header_len {
+            return Err(WalletError::Storage("Encrypted data truncated".to_string()));
+        }
+        let word =
+            |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+
+        let mut salt = [0u8; SALT_LEN];
+        salt.copy_from_slice(&bytes[12..12 + SALT_LEN]);
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce.copy_from_slice(&bytes[12 + SALT_LEN..header_len]);
+
+        Ok(Self {
+            params: KdfParams {
+                m_cost: word(0),
+                t_cost: word(4),
+                p_cost: word(8),
+            },
+            salt,
+            nonce,
+            ciphertext: bytes[header_len..].to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_open_roundtrip() {
+        let sealed = seal(b"secret", "correct horse battery staple", b"hdr").unwrap();
+        let restored = SealedBox::from_bytes(&sealed.to_bytes()).unwrap();
+        assert_eq!(
+            open(&restored, "correct horse battery staple", b"hdr")
+                .unwrap()
+                .as_slice(),
+            b"secret"
+        );
+        assert!(open(&restored, "wrong password", b"hdr").is_err());
+        assert!(open(&restored, "correct horse battery staple", b"other").is_err());
+    }
+
+    #[test]
+    fn test_oversized_kdf_params_are_refused() {
+        let sealed = seal(b"secret", "correct horse battery staple", b"hdr").unwrap();
+        for params in [
+            KdfParams {
+                m_cost: u32::MAX,
+                ..sealed.params
+            },
+            KdfParams {
+                t_cost: u32::MAX,
+                ..sealed.params
+            },
+            KdfParams {
+                p_cost: 17,
+                ..sealed.params
+            },
+        ] {
+            let tampered = SealedBox {
+                params,
+                ..sealed.clone()
+            };
+            let error = open(&tampered, "correct horse battery staple", b"hdr").unwrap_err();
+            assert!(error.to_string().contains("out of range"), "{}", error);
+        }
+        assert!(KdfParams::MAX.check().is_ok());
+    }
+
+    #[test]
+    fn test_password_strength() {
+        assert!(check_password_strength("password").is_err());
+        assert!(check_password_strength("123456789012").is_err());
+        assert!(check_password_strength("correct horse battery staple").is_ok());
+    }
+}
@@ -0,0 +1,182 @@
+use crate::address::compute_checksum;
+use crate::error::{WalletError, WalletResult};
+use crate::network::NetworkConfig;
+use serde::{Deserialize, Serialize};
+
+pub const DESCRIPTOR_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DescriptorKey {
+    Xpub { xpub: String },
+    PublicKey { public_key: String },
+}
+
+/// Secret-free description of a wallet: enough to derive and verify its
+/// addresses, never enough to spend from them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletDescriptor {
+    pub version: u32,
+    pub network: String,
+    pub address_prefix: String,
+    pub address_version: u8,
+    pub key: DescriptorKey,
+    pub derivation_path: Option<String>,
+    pub gap_limit: u32,
+    #[serde(default)]
+    pub checksum: String,
+}
+
+impl WalletDescriptor {
+    pub fn new(
+        network_config: &NetworkConfig,
+        address_version: u8,
+        key: DescriptorKey,
+        derivation_path: Option<String>,
+        gap_limit: u32,
+    ) -> Self {
+        let mut descriptor = Self {
+            version: DESCRIPTOR_VERSION,
+            network: network_config.name.clone(),
+            address_prefix: network_config.get_prefix().to_string(),
+            address_version,
+            key,
+            derivation_path,
+            gap_limit,
+            checksum: String::new(),
+        };
+        descriptor.checksum = descriptor.compute_checksum();
+        descriptor
+    }
+
+    /// Checksum over the JSON encoding of every field except `checksum`.
+    fn compute_checksum(&self) -> String {
+        let mut body = self.clone();
+        body.checksum = String::new();
+        let encoded = serde_json::to_vec(&body).unwrap_or_default();
+        hex::encode(compute_checksum(&encoded))
+    }
+
+    pub fn to_json(&self) -> WalletResult<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| WalletError::Serialization(format!("Descriptor encode error: {}", e)))
+    }
+
+    pub fn from_json(json: &str) -> WalletResult<Self> {
+        let descriptor: Self = serde_json::from_str(json)
+            .map_err(|e| WalletError::Serialization(format!("Descriptor decode error: {}", e)))?;
+
+        if descriptor.version > DESCRIPTOR_VERSION {
+            return Err(WalletError::Serialization(format!(
+                "Unsupported descriptor version: {}",
+                descriptor.version
+            )));
+        }
+        if descriptor.checksum != descriptor.compute_checksum() {
+            return Err(WalletError::Serialization(
+                "Descriptor checksum mismatch".to_string(),
+            ));
+        }
+
+        Ok(descriptor)
+    }
+}
+
+/// A descriptor plus what a person knows the wallet by: its name,
+/// fingerprint and addresses. It reads back as a plain descriptor, which
+/// ignores the extra fields, so it imports as a watch-only wallet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletInfo {
+    #[serde(flatten)]
+    pub descriptor: WalletDescriptor,
+    pub name: String,
+    pub fingerprint: String,
+    pub addresses: Vec<String>,
+}
+
+impl WalletInfo {
+    pub fn to_json(&self) -> WalletResult<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| WalletError::Serialization(format!("Wallet info encode error: {}", e)))
+    }
+
+    /// The same, one fact per line, for reading rather than importing.
+    pub fn to_text(&self) -> String {
+        let descriptor = &self.descriptor;
+        let mut lines = vec![
+            format!("Name: {}", self.name),
+            format!("Fingerprint: {}", self.fingerprint),
+            format!("Network: {}", descriptor.network),
+        ];
+        match &descriptor.key {
+            DescriptorKey::Xpub { xpub } => lines.push(format!("Xpub: {}", xpub)),
+            DescriptorKey::PublicKey { public_key } => {
+                lines.push(format!("Public key: {}", public_key))
+            }
+        }
+        if let Some(path) = &descriptor.derivation_path {
+            lines.push(format!("Derivation path: {}", path));
+        }
+        lines.push("Addresses:".to_string());
+        lines.extend(
+            self.addresses
+                .iter()
+                .map(|address| format!("  {}", address)),
+        );
+        lines.push(String::new());
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::KaspaWallet;
+
+    #[test]
+    fn test_descriptor_json_roundtrip() {
+        let wallet = KaspaWallet::from_seed(&[7u8; 64], "mainnet").unwrap();
+        let descriptor = wallet.export_descriptor();
+        let json = descriptor.to_json().unwrap();
+
+        assert!(!json.contains(&wallet.get_private_key()));
+        assert_eq!(WalletDescriptor::from_json(&json).unwrap(), descriptor);
+    }
+
+    #[test]
+    fn test_descriptor_checksum_detects_tampering() {
+        let wallet = KaspaWallet::from_seed(&[7u8; 64], "mainnet").unwrap();
+        let json = wallet
+            .export_descriptor()
+            .to_json()
+            .unwrap()
+            .replace("\"gap_limit\": 20", "\"gap_limit\": 21");
+
+        assert!(WalletDescriptor::from_json(&json).is_err());
+    }
+
+    #[test]
+    fn test_wallet_info_reads_back_as_a_descriptor() {
+        let wallet = KaspaWallet::from_seed(&[7u8; 64], "mainnet").unwrap();
+        let info = WalletInfo {
+            descriptor: wallet.export_descriptor(),
+            name: "main.keystore".to_string(),
+            fingerprint: wallet.fingerprint(),
+            addresses: vec![wallet.get_address()],
+        };
+        let json = info.to_json().unwrap();
+        assert!(json.contains(&wallet.get_address()) && json.contains("main.keystore"));
+        assert!(!json.contains(&wallet.get_private_key()));
+        assert_eq!(WalletDescriptor::from_json(&json).unwrap(), info.descriptor);
+
+        let text = info.to_text();
+        assert!(text.contains(&format!("Fingerprint: {}", wallet.fingerprint())));
+        assert!(text.contains("Xpub: "));
+        assert!(
+            text.contains("Derivation path: m/44'/111111'/0'"),
+            "{}",
+            text
+        );
+        assert!(!text.contains(&wallet.get_private_key()));
+    }
+}
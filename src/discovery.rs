@@ -0,0 +1,146 @@
+//! Gap-limit discovery: which of an HD wallet's addresses have been used,
+//! so a restored wallet picks up where the old one left off.
+
+use crate::error::WalletResult;
+use crate::hd::CHANGE_CHAIN;
+use crate::node::NodeClient;
+use crate::wallet::KaspaWallet;
+
+/// Tells whether an address has been used.
+pub trait AddressActivityScanner {
+    fn has_activity(&self, address: &str) -> WalletResult<bool>;
+}
+
+/// A node only knows unspent outputs, so an address whose coins were all
+/// spent looks unused; the explorer's scanner sees its whole history.
+impl AddressActivityScanner for dyn NodeClient {
+    fn has_activity(&self, address: &str) -> WalletResult<bool> {
+        Ok(!self
+            .get_utxos_by_addresses(&[address.to_string()])?
+            .is_empty())
+    }
+}
+
+/// The used addresses found on each chain, with their indexes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Discovery {
+    pub receive: Vec<(u32, String)>,
+    pub change: Vec<(u32, String)>,
+}
+
+impl Discovery {
+    /// First index after the last used one on `chain`: where handing out
+    /// addresses resumes.
+    pub fn next(&self, chain: u32) -> u32 {
+        let used = if chain == CHANGE_CHAIN {
+            &self.change
+        } else {
+            &self.receive
+        };
+        used.last().map_or(0, |(index, _)| index + 1)
+    }
+}
+
+/// Scans addresses from index 0 until `gap_limit` in a row are unused.
+pub fn discover_chain(
+    scanner: &(impl AddressActivityScanner + ?Sized),
+    gap_limit: u32,
+    derive: impl Fn(u32) -> WalletResult<String>,
+) -> WalletResult<Vec<(u32, String)>> {
+    let mut used = Vec::new();
+    let mut unused = 0;
+    let mut index = 0;
+    while unused < gap_limit {
+        let address = derive(index)?;
+        if scanner.has_activity(&address)? {
+            tracing::debug!(index, address, "used address");
+            used.push((index, address));
+            unused = 0;
+        } else {
+            unused += 1;
+        }
+        index += 1;
+    }
+    Ok(used)
+}
+
+/// Scans both of `wallet`'s chains with its gap limit.
+pub fn discover(
+    wallet: &KaspaWallet,
+    scanner: &(impl AddressActivityScanner + ?Sized),
+) -> WalletResult<Discovery> {
+    Ok(Discovery {
+        receive: discover_chain(scanner, wallet.gap_limit(), |index| {
+            wallet.receive_address(index)
+        })?,
+        change: discover_chain(scanner, wallet.gap_limit(), |index| {
+            wallet.change_address(index)
+        })?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hd::RECEIVE_CHAIN;
+    use std::cell::RefCell;
+    use std::collections::BTreeSet;
+
+    /// Marks the addresses in `used` as active and records every lookup.
+    struct Scripted {
+        used: BTreeSet<String>,
+        asked: RefCell<Vec<String>>,
+    }
+
+    impl AddressActivityScanner for Scripted {
+        fn has_activity(&self, address: &str) -> WalletResult<bool> {
+            self.asked.borrow_mut().push(address.to_string());
+            Ok(self.used.contains(address))
+        }
+    }
+
+    fn scripted(used: &[&str]) -> Scripted {
+        Scripted {
+            used: used.iter().map(|address| address.to_string()).collect(),
+            asked: RefCell::default(),
+        }
+    }
+
+    #[test]
+    fn test_discovery_stops_after_the_gap() {
+        let scanner = scripted(&["a0", "a2", "a5"]);
+        let used = discover_chain(&scanner, 3, |index| Ok(format!("a{}", index))).unwrap();
+        assert_eq!(
+            used,
+            vec![
+                (0, "a0".to_string()),
+                (2, "a2".to_string()),
+                (5, "a5".to_string())
+            ]
+        );
+        // a5 is found three after a2; a6..a8 close the gap.
+        assert_eq!(scanner.asked.borrow().len(), 9);
+
+        let beyond = scripted(&["a0", "a4"]);
+        let used = discover_chain(&beyond, 3, |index| Ok(format!("a{}", index))).unwrap();
+        assert_eq!(used, vec![(0, "a0".to_string())]);
+    }
+
+    #[test]
+    fn test_discovery_of_a_wallet() {
+        let wallet = KaspaWallet::from_seed(&[7u8; 64], "mainnet").unwrap();
+        let scanner = scripted(&[
+            &wallet.receive_address(0).unwrap(),
+            &wallet.receive_address(4).unwrap(),
+            &wallet.change_address(1).unwrap(),
+        ]);
+        let discovery = discover(&wallet, &scanner).unwrap();
+        assert_eq!(discovery.next(RECEIVE_CHAIN), 5);
+        assert_eq!(discovery.next(CHANGE_CHAIN), 2);
+        assert_eq!(
+            scanner.asked.borrow().len() as u32,
+            5 + wallet.gap_limit() + 2 + wallet.gap_limit()
+        );
+        assert_eq!(Discovery::default().next(RECEIVE_CHAIN), 0);
+    }
+}
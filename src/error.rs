@@ -1,3 +1,5 @@
+use crate::amount::Kas;
+use crate::node::{NodeFailure, RejectReason};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -29,6 +31,36 @@ pub enum WalletError {
     #[error("Invalid address format")]
     InvalidAddressFormat,
 
+    #[error("Watch-only wallet cannot sign")]
+    WatchOnly,
+
+    #[error("Wallet is locked")]
+    Locked,
+
+    #[error("Authentication failed: {0}")]
+    Authentication(String),
+
+    /// `message` is the node's own, kept verbatim.
+    #[error("Transaction rejected by node ({reason}): {message}")]
+    Rejected {
+        reason: RejectReason,
+        message: String,
+    },
+
+    #[error("Node unusable: {0}")]
+    Node(NodeFailure),
+
+    #[error("Timed out: {operation} on {endpoint} took longer than {secs}s")]
+    Timeout {
+        operation: String,
+        endpoint: String,
+        secs: u64,
+    },
+
+    /// A wait (`monitor --timeout`) ran out before the expected amount arrived.
+    #[error("Timed out after receiving {received} KAS")]
+    WaitTimedOut { received: Kas },
+
     #[error("I/O error: {0}")]
     Io(String),
 }
@@ -0,0 +1,222 @@
+use crate::error::{WalletError, WalletResult};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const EVENT_LOG_FILE: &str = "events.jsonl";
+
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Key lifecycle events. None of the variants may carry secret material.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WalletEvent {
+    WalletCreated {
+        network: String,
+        address: String,
+    },
+    AddressDerived {
+        index: u32,
+        address: String,
+    },
+    TransactionSigned {
+        txid: String,
+        total_out: u64,
+        fee: u64,
+    },
+    KeyExported {
+        address: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub timestamp: u64,
+    pub prev_hash: String,
+    #[serde(flatten)]
+    pub event: WalletEvent,
+}
+
+/// Receiver for wallet events; signing and derivation APIs call it when set.
+pub trait EventSink: Send + Sync {
+    fn record(&self, event: WalletEvent);
+}
+
+/// Append-only JSON-lines log where each entry commits to the hash of the
+/// previous line, with the latest hash mirrored in a `.head` file so that
+/// truncation is detectable as well as edits.
+pub struct EventLog {
+    path: PathBuf,
+    last_hash: Mutex<String>,
+}
+
+fn line_hash(line: &str) -> String {
+    hex::encode(Sha256::digest(line.as_bytes()))
+}
+
+fn head_path(path: &Path) -> PathBuf {
+    let mut head = path.as_os_str().to_owned();
+    head.push(".head");
+    PathBuf::from(head)
+}
+
+impl EventLog {
+    pub fn open(path: impl Into<PathBuf>) -> WalletResult<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let last_hash = if path.exists() {
+            Self::verify(&path)?.1
+        } else {
+            GENESIS_HASH.to_string()
+        };
+
+        Ok(Self {
+            path,
+            last_hash: Mutex::new(last_hash),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, event: WalletEvent) -> WalletResult<()> {
+        let mut last_hash = self
+            .last_hash
+            .lock()
+            .map_err(|_| WalletError::Storage("Event log lock poisoned".to_string()))?;
+
+        let entry = LogEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            prev_hash: last_hash.clone(),
+            event,
+        };
+        let line = serde_json::to_string(&entry)
+            .map_err(|e| WalletError::Serialization(format!("Event encode error: {}", e)))?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", line)?;
+        file.sync_all()?;
+
+        *last_hash = line_hash(&line);
+        fs::write(head_path(&self.path), last_hash.as_bytes())?;
+        Ok(())
+    }
+
+    /// Checks the hash chain, returning the number of entries and the hash of
+    /// the last one.
+    pub fn verify(path: &Path) -> WalletResult<(usize, String)> {
+        let file = fs::File::open(path)?;
+        let mut expected = GENESIS_HASH.to_string();
+        let mut count = 0;
+
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            let entry: LogEntry = serde_json::from_str(&line).map_err(|e| {
+                WalletError::Storage(format!("Malformed entry on line {}: {}", number + 1, e))
+            })?;
+            if entry.prev_hash != expected {
+                return Err(WalletError::Storage(format!(
+                    "Hash chain broken on line {}",
+                    number + 1
+                )));
+            }
+            expected = line_hash(&line);
+            count += 1;
+        }
+
+        let head = fs::read_to_string(head_path(path)).unwrap_or_default();
+        if head.trim() != expected {
+            return Err(WalletError::Storage(
+                "Log head does not match last entry (truncated or rewritten)".to_string(),
+            ));
+        }
+
+        Ok((count, expected))
+    }
+}
+
+impl EventSink for EventLog {
+    fn record(&self, event: WalletEvent) {
+        if let Err(e) = self.append(event) {
+            tracing::warn!("could not write event log: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("kasparustwallet-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join(EVENT_LOG_FILE)
+    }
+
+    fn sample_events(log: &EventLog) {
+        log.append(WalletEvent::WalletCreated {
+            network: "mainnet".to_string(),
+            address: "kaspa:abc".to_string(),
+        })
+        .unwrap();
+        log.append(WalletEvent::AddressDerived {
+            index: 1,
+            address: "kaspa:def".to_string(),
+        })
+        .unwrap();
+        log.append(WalletEvent::TransactionSigned {
+            txid: "00".repeat(32),
+            total_out: 1000,
+            fee: 10,
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_chain_verifies_and_reopens() {
+        let path = temp_log("log-ok");
+        sample_events(&EventLog::open(&path).unwrap());
+        assert_eq!(EventLog::verify(&path).unwrap().0, 3);
+
+        let reopened = EventLog::open(&path).unwrap();
+        reopened
+            .append(WalletEvent::KeyExported {
+                address: "kaspa:abc".to_string(),
+            })
+            .unwrap();
+        assert_eq!(EventLog::verify(&path).unwrap().0, 4);
+    }
+
+    #[test]
+    fn test_tampering_and_truncation_detected() {
+        let path = temp_log("log-tamper");
+        sample_events(&EventLog::open(&path).unwrap());
+        let original = fs::read_to_string(&path).unwrap();
+
+        fs::write(&path, original.replace("\"fee\":10", "\"fee\":1")).unwrap();
+        assert!(EventLog::verify(&path).is_err());
+
+        let truncated: String = original
+            .lines()
+            .take(2)
+            .map(|l| format!("{}\n", l))
+            .collect();
+        fs::write(&path, truncated).unwrap();
+        assert!(EventLog::verify(&path).is_err());
+    }
+}
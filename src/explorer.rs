@@ -0,0 +1,344 @@
+//! Balances, UTXOs and history from a REST explorer speaking the
+//! api.kaspa.org API, for users without a node of their own. It is only
+//! used once `explorer_url` is set, and only over plain HTTP, so the public
+//! HTTPS instance needs a local proxy. The data is whatever the explorer
+//! says; nothing checks it against the chain.
+
+use crate::discovery::AddressActivityScanner;
+use crate::error::{WalletError, WalletResult};
+use crate::history::{self, HistoryEntry};
+use crate::http::{HttpUrl, Service};
+use crate::network::NetworkConfig;
+use crate::node::RetryPolicy;
+use crate::utxo::Utxo;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+const EXPLORER: Service = Service {
+    name: "explorer",
+    setting: "explorer_url",
+    operation: "explorer request",
+};
+
+/// What the explorer reports about the network it follows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplorerInfo {
+    pub network: String,
+    pub virtual_daa_score: u64,
+}
+
+/// A REST explorer reached over plain HTTP, in place of a node.
+pub struct ExplorerClient {
+    url: HttpUrl,
+    policy: RetryPolicy,
+}
+
+/// A count the API gives as a number or a decimal string.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Count {
+    Number(u64),
+    Text(String),
+}
+
+impl Count {
+    fn value(&self) -> WalletResult<u64> {
+        match self {
+            Count::Number(n) => Ok(*n),
+            Count::Text(s) => s.parse().map_err(|_| {
+                WalletError::Serialization(format!("Invalid number in explorer response: {}", s))
+            }),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiBalance {
+    balance: Count,
+}
+
+#[derive(Deserialize)]
+struct ApiCount {
+    total: Count,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiBlockDag {
+    network_name: String,
+    virtual_daa_score: Count,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiUtxo {
+    address: String,
+    outpoint: ApiOutpoint,
+    utxo_entry: ApiEntry,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiOutpoint {
+    transaction_id: String,
+    index: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiEntry {
+    amount: Count,
+    #[serde(default)]
+    block_daa_score: Option<Count>,
+    #[serde(default)]
+    is_coinbase: bool,
+}
+
+fn parse<T: DeserializeOwned>(json: &str) -> WalletResult<T> {
+    serde_json::from_str(json)
+        .map_err(|e| WalletError::Serialization(format!("Invalid explorer response: {}", e)))
+}
+
+/// Reads an `/addresses/{address}/utxos` response.
+pub fn parse_utxos(json: &str) -> WalletResult<Vec<Utxo>> {
+    parse::<Vec<ApiUtxo>>(json)?
+        .into_iter()
+        .map(|utxo| {
+            Ok(Utxo {
+                txid: utxo.outpoint.transaction_id,
+                vout: utxo.outpoint.index,
+                address: utxo.address,
+                amount: utxo.utxo_entry.amount.value()?,
+                block_daa_score: match utxo.utxo_entry.block_daa_score {
+                    Some(score) => score.value()?,
+                    None => 0,
+                },
+                is_coinbase: utxo.utxo_entry.is_coinbase,
+            })
+        })
+        .collect()
+}
+
+/// Fails unless the explorer follows `expected`; it names networks
+/// `kaspa-mainnet`, `kaspa-testnet-10` and so on.
+pub fn check_network(info: &ExplorerInfo, expected: &str) -> WalletResult<()> {
+    let expected = NetworkConfig::from_name(expected)?.name;
+    let actual = info.network.strip_prefix("kaspa-").unwrap_or(&info.network);
+    let actual = NetworkConfig::from_name(actual)
+        .map(|config| config.name)
+        .unwrap_or_else(|_| info.network.clone());
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(WalletError::Network(format!(
+            "The explorer follows {} but {} was requested",
+            actual, expected
+        )))
+    }
+}
+
+impl ExplorerClient {
+    pub fn new(url: &str, policy: RetryPolicy) -> WalletResult<Self> {
+        Ok(Self {
+            url: HttpUrl::parse(url, EXPLORER)?,
+            policy,
+        })
+    }
+
+    /// Says where figures came from, for every output built on them.
+    pub fn third_party_note(&self) -> String {
+        format!(
+            "Third-party data from the explorer at http://{}:{}{}; not checked by a node of your own",
+            self.url.host, self.url.port, self.url.base_path
+        )
+    }
+
+    fn address_path(&self, address: &str, resource: &str) -> String {
+        format!(
+            "{}/addresses/{}/{}",
+            self.url.base_path,
+            address.replace(':', "%3A"),
+            resource
+        )
+    }
+
+    fn get(&self, path: &str) -> WalletResult<String> {
+        self.url.fetch(path, &self.policy)
+    }
+
+    /// The network the explorer follows and its virtual DAA score.
+    pub fn info(&self) -> WalletResult<ExplorerInfo> {
+        let dag: ApiBlockDag = parse(&self.get(&format!("{}/info/blockdag", self.url.base_path))?)?;
+        Ok(ExplorerInfo {
+            network: dag.network_name,
+            virtual_daa_score: dag.virtual_daa_score.value()?,
+        })
+    }
+
+    /// `address`'s balance in sompi.
+    pub fn balance(&self, address: &str) -> WalletResult<u64> {
+        let balance: ApiBalance = parse(&self.get(&self.address_path(address, "balance"))?)?;
+        balance.balance.value()
+    }
+
+    /// The unspent outputs of `addresses`, one request per address.
+    pub fn get_utxos_by_addresses(&self, addresses: &[String]) -> WalletResult<Vec<Utxo>> {
+        let mut utxos = Vec::new();
+        for address in addresses {
+            utxos.extend(parse_utxos(
+                &self.get(&self.address_path(address, "utxos"))?,
+            )?);
+        }
+        Ok(utxos)
+    }
+
+    /// The newest `limit` transactions touching `address`.
+    pub fn transactions(&self, address: &str, limit: usize) -> WalletResult<Vec<HistoryEntry>> {
+        let path = history::transactions_path(&self.url.base_path, address, limit, None);
+        history::parse_transactions(&self.get(&path)?, address)
+    }
+
+    /// How many transactions have touched `address`, spent or not.
+    pub fn transaction_count(&self, address: &str) -> WalletResult<u64> {
+        let count: ApiCount = parse(&self.get(&self.address_path(address, "transactions-count"))?)?;
+        count.total.value()
+    }
+}
+
+/// Any transaction counts, so addresses whose coins were all spent are
+/// found too.
+impl AddressActivityScanner for ExplorerClient {
+    fn has_activity(&self, address: &str) -> WalletResult<bool> {
+        Ok(self.transaction_count(address)? > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+    use std::time::{Duration, Instant};
+
+    const ADDRESS: &str = "kaspa:me";
+
+    /// Answers one connection per response, in order, and returns the
+    /// request lines it saw.
+    fn serve(responses: Vec<String>) -> (ExplorerClient, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut chunk = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = stream.read(&mut chunk).unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&chunk[..n]);
+                }
+                let request = String::from_utf8_lossy(&request);
+                requests.push(request.lines().next().unwrap_or_default().to_string());
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        let client = ExplorerClient::new(
+            &format!("http://127.0.0.1:{}/api", port),
+            RetryPolicy {
+                timeout: Duration::from_secs(5),
+                retries: 2,
+            },
+        )
+        .unwrap();
+        (client, server)
+    }
+
+    fn ok(body: serde_json::Value) -> String {
+        format!("HTTP/1.0 200 OK\r\n\r\n{}", body)
+    }
+
+    #[test]
+    fn test_explorer_reads_balance_utxos_and_activity() {
+        let (client, server) = serve(vec![
+            ok(serde_json::json!({"networkName": "kaspa-mainnet", "virtualDaaScore": "9000"})),
+            ok(serde_json::json!({"address": ADDRESS, "balance": 1500})),
+            ok(serde_json::json!([
+                {
+                    "address": ADDRESS,
+                    "outpoint": {"transactionId": "aa", "index": 1},
+                    "utxoEntry": {"amount": "1500", "blockDaaScore": "8000", "isCoinbase": true}
+                }
+            ])),
+            ok(serde_json::json!({"total": 0, "limit_exceeded": false})),
+            ok(serde_json::json!({"total": "3"})),
+        ]);
+        let info = client.info().unwrap();
+        assert_eq!(info.virtual_daa_score, 9000);
+        check_network(&info, "mainnet").unwrap();
+        assert!(check_network(&info, "testnet-10").is_err());
+        assert_eq!(client.balance(ADDRESS).unwrap(), 1500);
+        let utxos = client
+            .get_utxos_by_addresses(&[ADDRESS.to_string()])
+            .unwrap();
+        assert_eq!(
+            utxos,
+            vec![Utxo {
+                txid: "aa".to_string(),
+                vout: 1,
+                address: ADDRESS.to_string(),
+                amount: 1500,
+                block_daa_score: 8000,
+                is_coinbase: true,
+            }]
+        );
+        assert!(!client.has_activity(ADDRESS).unwrap());
+        assert!(client.has_activity(ADDRESS).unwrap());
+        assert_eq!(
+            server.join().unwrap(),
+            [
+                "GET /api/info/blockdag HTTP/1.0",
+                "GET /api/addresses/kaspa%3Ame/balance HTTP/1.0",
+                "GET /api/addresses/kaspa%3Ame/utxos HTTP/1.0",
+                "GET /api/addresses/kaspa%3Ame/transactions-count HTTP/1.0",
+                "GET /api/addresses/kaspa%3Ame/transactions-count HTTP/1.0",
+            ]
+        );
+        assert!(client.third_party_note().contains("Third-party data"));
+    }
+
+    #[test]
+    fn test_rate_limited_requests_wait_and_retry() {
+        let limited = "HTTP/1.0 429 Too Many Requests\r\nRetry-After: 1\r\n\r\n".to_string();
+        let (client, server) = serve(vec![limited.clone(), ok(serde_json::json!({"balance": 7}))]);
+        let started = Instant::now();
+        assert_eq!(client.balance(ADDRESS).unwrap(), 7);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        server.join().unwrap();
+
+        // Out of retries, the 429 is reported as such.
+        let (client, server) = serve(vec![
+            limited.replace("1\r\n", "0\r\n"),
+            limited.replace("1\r\n", "0\r\n"),
+            limited.replace("1\r\n", "0\r\n"),
+        ]);
+        let err = client.balance(ADDRESS).unwrap_err().to_string();
+        assert!(err.contains("rate limiting"), "{}", err);
+        assert_eq!(server.join().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_malformed_utxos_are_rejected() {
+        assert!(parse_utxos("{}").is_err());
+        let bad_amount = serde_json::json!([{
+            "address": ADDRESS,
+            "outpoint": {"transactionId": "aa", "index": 0},
+            "utxoEntry": {"amount": "lots"}
+        }]);
+        assert!(parse_utxos(&bad_amount.to_string()).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+//! Clipboard access for the GUI. iced's clipboard comes first, and arboard
+//! only steps in when that is unavailable. No external programs are run.
+
+use iced::Task;
+use std::sync::Mutex;
+
+/// A clipboard consulted after iced's.
+pub trait Fallback {
+    fn read(&mut self) -> Option<String>;
+    fn write(&mut self, text: &str) -> bool;
+}
+
+/// The arboard clipboard. One handle lives for the whole process because
+/// on X11 text written through a handle disappears when it is dropped.
+pub struct Arboard;
+
+static ARBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+
+impl Arboard {
+    fn with<T>(f: impl FnOnce(&mut arboard::Clipboard) -> Option<T>) -> Option<T> {
+        let mut guard = ARBOARD.lock().unwrap_or_else(|e| e.into_inner());
+        if guard.is_none() {
+            match arboard::Clipboard::new() {
+                Ok(clipboard) => *guard = Some(clipboard),
+                Err(e) => {
+                    tracing::debug!("arboard clipboard unavailable: {}", e);
+                    return None;
+                }
+            }
+        }
+        guard.as_mut().and_then(f)
+    }
+}
+
+impl Fallback for Arboard {
+    fn read(&mut self) -> Option<String> {
+        Self::with(|clipboard| clipboard.get_text().ok())
+    }
+
+    fn write(&mut self, text: &str) -> bool {
+        Self::with(|clipboard| clipboard.set_text(text).ok()).is_some()
+    }
+}
+
+fn non_empty(text: Option<String>) -> Option<String> {
+    text.map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// What iced read, or what `fallback` holds when that was empty.
+pub fn resolve_read(primary: Option<String>, fallback: &mut impl Fallback) -> Option<String> {
+    non_empty(primary).or_else(|| non_empty(fallback.read()))
+}
+
+/// Whether `text` reached a clipboard. iced reports nothing for a write, so
+/// it counts only when reading back returns `text`; otherwise `fallback`
+/// writes it.
+pub fn resolve_write(text: &str, read_back: Option<String>, fallback: &mut impl Fallback) -> bool {
+    read_back.as_deref() == Some(text) || fallback.write(text)
+}
+
+/// Reads trimmed text from the clipboard; `None` when there is none.
+pub fn read() -> Task<Option<String>> {
+    iced::clipboard::read().map(|primary| resolve_read(primary, &mut Arboard))
+}
+
+/// Writes `text`, resolving to whether it was copied.
+pub fn write(text: String) -> Task<bool> {
+    iced::clipboard::write(text.clone())
+        .chain(iced::clipboard::read())
+        .map(move |read_back| resolve_write(&text, read_back, &mut Arboard))
+}
+
+/// Empties the clipboard if it still holds `text`, leaving anything copied
+/// since alone. Resolves to whether it was cleared.
+pub fn clear_if_holds(text: String) -> Task<bool> {
+    read().then(move |current| {
+        if current.as_deref() == Some(text.trim()) {
+            write(String::new())
+        } else {
+            Task::done(false)
+        }
+    })
+}
+
+/// The clipboard as the GUI shell reaches it.
+pub trait Clipboard {
+    fn read(&self) -> Task<Option<String>>;
+    fn write(&self, text: String) -> Task<bool>;
+    fn clear_if_holds(&self, text: String) -> Task<bool>;
+}
+
+/// iced's clipboard, with arboard behind it.
+#[derive(Debug, Default)]
+pub struct SystemClipboard;
+
+impl Clipboard for SystemClipboard {
+    fn read(&self) -> Task<Option<String>> {
+        read()
+    }
+
+    fn write(&self, text: String) -> Task<bool> {
+        write(text)
+    }
+
+    fn clear_if_holds(&self, text: String) -> Task<bool> {
+        clear_if_holds(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Fake {
+        contents: Option<String>,
+        writable: bool,
+        reads: usize,
+        writes: usize,
+    }
+
+    impl Fallback for Fake {
+        fn read(&mut self) -> Option<String> {
+            self.reads += 1;
+            self.contents.clone()
+        }
+
+        fn write(&mut self, text: &str) -> bool {
+            self.writes += 1;
+            if self.writable {
+                self.contents = Some(text.to_string());
+            }
+            self.writable
+        }
+    }
+
+    #[test]
+    fn test_read_prefers_iced() {
+        let mut fake = Fake {
+            contents: Some("fallback".to_string()),
+            ..Fake::default()
+        };
+        assert_eq!(
+            resolve_read(Some(" kaspa:abc\n".to_string()), &mut fake).as_deref(),
+            Some("kaspa:abc")
+        );
+        assert_eq!(fake.reads, 0);
+
+        for primary in [None, Some("  ".to_string())] {
+            assert_eq!(
+                resolve_read(primary, &mut fake).as_deref(),
+                Some("fallback")
+            );
+        }
+        assert_eq!(fake.reads, 2);
+        fake.contents = None;
+        assert_eq!(resolve_read(None, &mut fake), None);
+    }
+
+    #[test]
+    fn test_write_falls_back_when_iced_did_not_take_it() {
+        let mut fake = Fake {
+            writable: true,
+            ..Fake::default()
+        };
+        assert!(resolve_write("txid", Some("txid".to_string()), &mut fake));
+        assert_eq!(fake.writes, 0);
+
+        assert!(resolve_write("txid", Some("older".to_string()), &mut fake));
+        assert_eq!(fake.contents.as_deref(), Some("txid"));
+        fake.writable = false;
+        assert!(!resolve_write("txid", None, &mut fake));
+        assert_eq!(fake.writes, 2);
+    }
+}
@@ -0,0 +1,227 @@
+//! What the GUI's update logic asks of the world outside its state.
+//!
+//! [`super::gui::update`] only changes the state and returns [`Command`]s;
+//! the iced shell carries them out and feeds back the message each one
+//! answers with. Work on keys goes through a [`WalletBackend`] the state
+//! holds, so tests can swap it like the clock.
+
+use super::gui::{
+    BuiltTransaction, LoadedWallet, PasteTarget, Redacted, ScanTarget, SendSummary, WalletFile,
+};
+use super::settings::GuiSettings;
+use crate::descriptor::WalletInfo;
+use crate::error::WalletError;
+use crate::keystore::Keystore;
+use crate::transaction::Transaction;
+use crate::utxo::UtxoEntry;
+use std::fmt;
+use std::path::PathBuf;
+
+/// A side effect, and which message reports its outcome.
+#[derive(Debug)]
+pub enum Command {
+    /// Writes `text` to the clipboard; answered by `Message::Copied`.
+    Copy {
+        what: &'static str,
+        text: String,
+    },
+    /// Writes the private key to the clipboard; answered by
+    /// `Message::PrivateKeyCopied`.
+    CopyKey(Redacted),
+    /// Empties the clipboard if it still holds the text; answered by
+    /// `Message::ClipboardCleared`.
+    ClearClipboard(Redacted),
+    /// Answered by `Message::ClipboardRead`.
+    Paste(PasteTarget),
+    /// Sends `Message::SaveSettings` with the generation after a short
+    /// wait.
+    SaveSettingsLater(u64),
+    /// Answered by `Message::SettingsWritten`.
+    WriteSettings {
+        dir: PathBuf,
+        settings: GuiSettings,
+    },
+    /// Answered by `Message::KeystoreRead`.
+    ReadKeystore(PathBuf),
+    /// Answered by `Message::DroppedFileRead`.
+    ReadDroppedFile(PathBuf),
+    /// Work on keys, off the UI thread.
+    Wallet(WalletJob),
+    /// Answered by `Message::PricesFetched`.
+    FetchPrices {
+        url: String,
+    },
+    /// Answered by `Message::BalanceFetched`.
+    FetchBalance {
+        url: String,
+        network: String,
+        /// The wallet's own address, which the answer is for.
+        address: String,
+        /// Its addresses to sync, on both chains for an HD wallet.
+        addresses: Vec<String>,
+    },
+    /// Answered by `Message::FeeEstimateFetched`.
+    FetchFeeEstimate {
+        url: String,
+    },
+    /// Answered by `Message::DerivedFundsFetched`.
+    FetchFundedAddresses {
+        url: String,
+        network: String,
+        fingerprint: String,
+        addresses: Vec<String>,
+    },
+    /// Answered by `Message::HistoryFetched`.
+    FetchHistory {
+        url: String,
+        address: String,
+        before: Option<u64>,
+        refresh: bool,
+    },
+    /// Submits the transaction, with `force` even through an unsynced
+    /// node; answered by `Message::Broadcasted`.
+    Broadcast {
+        url: String,
+        built: Box<BuiltTransaction>,
+        force: bool,
+    },
+    /// Answered by `Message::TxStatusFetched`.
+    FetchTxStatus {
+        url: String,
+        txid: String,
+    },
+    /// Answered by `Message::PropagationChecked`.
+    CheckPropagation {
+        url: String,
+        txid: String,
+    },
+    /// Asks for a keystore file to open, or with `save` where to save one;
+    /// answered by `Message::OpenPathChosen` or `Message::SavePathChosen`.
+    PickWalletFile {
+        save: bool,
+    },
+    /// Answered by `Message::QrSaved`.
+    SaveQr(Vec<u8>),
+    /// Answered by `Message::QrScanned`.
+    ScanQr(ScanTarget),
+    /// Answered by `Message::ResultSaved`.
+    SaveTransaction {
+        json: String,
+        txid: String,
+    },
+    /// Answered by `Message::WalletInfoExported`.
+    ExportWalletInfo(WalletInfo),
+    /// A desktop notification, where there is a notification server.
+    DesktopNotification {
+        summary: String,
+        body: String,
+    },
+    /// Brings the window back from the tray.
+    ShowWindow,
+    /// Hides the window in the tray.
+    HideWindow(iced::window::Id),
+    Exit,
+}
+
+/// Work on keys, carried out by a [`WalletBackend`].
+#[derive(Debug)]
+pub enum WalletJob {
+    /// A new HD wallet; answered by `Message::WalletLoaded`.
+    Create {
+        network: &'static str,
+        word_count: usize,
+    },
+    /// Answered by `Message::WalletLoaded`.
+    Load {
+        private_key: Redacted,
+        network: &'static str,
+    },
+    /// Signs what `summary` describes; answered by
+    /// `Message::TransactionBuilt`.
+    Build {
+        /// The wallet's secret, as `keystore::export_secret` writes it.
+        secret: Redacted,
+        network: &'static str,
+        /// First unused receive and change index, which bound the
+        /// addresses it signs for.
+        next_index: [u32; 2],
+        inputs: Vec<UtxoEntry>,
+        outputs: Vec<(String, u64)>,
+        fee_rate: u64,
+        summary: Box<SendSummary>,
+    },
+    /// Answered by `Message::WalletFileOpened`.
+    OpenFile {
+        keystore: Box<Keystore>,
+        path: PathBuf,
+        password: Redacted,
+    },
+    /// Answered by `Message::WalletFileSaved`.
+    SaveFile {
+        secret: Redacted,
+        network: &'static str,
+        path: PathBuf,
+        password: Redacted,
+    },
+    /// Takes the keys of the wallet with `address` back from its file;
+    /// answered by `Message::Unlocked`.
+    UnlockFile {
+        path: PathBuf,
+        address: String,
+        password: Redacted,
+    },
+}
+
+/// Creating, loading, signing with and encrypting wallets.
+pub trait WalletBackend: fmt::Debug + Send + Sync {
+    /// A new HD wallet, with its seed phrase for the backup screen.
+    fn create(&self, network: &str, word_count: usize) -> Result<LoadedWallet, WalletError>;
+
+    fn load(&self, private_key: &str, network: &str) -> Result<LoadedWallet, WalletError>;
+
+    /// Signs a transaction paying `outputs` with the wallet of `secret`.
+    fn build_transaction(
+        &self,
+        secret: &str,
+        network: &str,
+        next_index: [u32; 2],
+        inputs: Vec<UtxoEntry>,
+        outputs: Vec<(String, u64)>,
+        fee_rate: u64,
+    ) -> Result<Transaction, WalletError>;
+
+    fn open_file(
+        &self,
+        keystore: &Keystore,
+        path: PathBuf,
+        password: &str,
+    ) -> Result<(LoadedWallet, WalletFile), WalletError>;
+
+    /// Encrypts the wallet of `secret` under `password` into `path`.
+    fn save_file(
+        &self,
+        secret: &str,
+        network: &str,
+        path: PathBuf,
+        password: &str,
+    ) -> Result<WalletFile, WalletError>;
+
+    /// The wallet in the file at `path`, provided it is still the wallet
+    /// with `address`.
+    fn unlock_file(
+        &self,
+        path: PathBuf,
+        address: &str,
+        password: &str,
+    ) -> Result<LoadedWallet, WalletError>;
+
+    /// The wallet of `key`, a private key or seed phrase, if it is the
+    /// wallet with `fingerprint`; for a wallet with no file. Quick enough
+    /// to run on the UI thread.
+    fn unlock_with_key(
+        &self,
+        key: &str,
+        network: &str,
+        fingerprint: &str,
+    ) -> Result<LoadedWallet, WalletError>;
+}
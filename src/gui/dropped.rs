@@ -0,0 +1,182 @@
+//! Files dropped onto the window: what each one is, told from its name and
+//! contents, so the window can open it the right way or say why not.
+
+use crate::keystore::{Keystore, KEYSTORE_EXTENSION, KEYSTORE_FORMAT};
+use crate::pstx::{TxDocument, PSTX_FORMAT};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// Bytes read at most. Keystores and transactions are far smaller, so a
+/// larger file is none of them.
+pub const DROP_LIMIT: u64 = 1024 * 1024;
+
+/// The extension rusty-kaspa gives its wallet files.
+const RUSTY_KASPA_EXTENSION: &str = "wallet";
+
+#[derive(Debug, Clone)]
+pub enum Dropped {
+    Keystore(Box<Keystore>),
+    /// Raw transaction hex or a PSTX file.
+    Transaction(Box<TxDocument>),
+    /// Why the file is not opened, as the notification says it.
+    Unsupported(String),
+}
+
+/// Reads the file at `path` and classifies it. Never fails: a file that
+/// cannot be read is `Unsupported` too.
+pub fn read(path: &Path) -> Dropped {
+    let name = file_name(path);
+    let mut contents = Vec::new();
+    let read =
+        fs::File::open(path).and_then(|file| file.take(DROP_LIMIT + 1).read_to_end(&mut contents));
+    match read {
+        Ok(_) if contents.len() as u64 > DROP_LIMIT => Dropped::Unsupported(format!(
+            "{} is too large to be a wallet or transaction file",
+            name
+        )),
+        Ok(_) => classify(path, &contents),
+        Err(e) => Dropped::Unsupported(format!("Could not read {}: {}", name, e)),
+    }
+}
+
+/// What `contents`, the file at `path`, holds.
+pub fn classify(path: &Path, contents: &[u8]) -> Dropped {
+    let name = file_name(path);
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let text = std::str::from_utf8(contents).map(str::trim).ok();
+
+    let keystore_header = text.is_some_and(|text| {
+        text.starts_with('{') && text.contains(&format!("\"{}\"", KEYSTORE_FORMAT))
+    });
+    if extension == KEYSTORE_EXTENSION || keystore_header {
+        return match text.map(Keystore::from_json) {
+            Some(Ok(keystore)) => Dropped::Keystore(Box::new(keystore)),
+            Some(Err(e)) => Dropped::Unsupported(format!("Could not open {}: {}", name, e)),
+            None => Dropped::Unsupported(format!("{} is not a readable wallet file", name)),
+        };
+    }
+    if extension == RUSTY_KASPA_EXTENSION {
+        return Dropped::Unsupported(format!(
+            "{} looks like a rusty-kaspa wallet, which cannot be imported. Restore its seed phrase with `kasparustwallet restore` instead",
+            name
+        ));
+    }
+    let Some(text) = text.filter(|text| !text.is_empty()) else {
+        return unknown(&name);
+    };
+    match TxDocument::parse(text) {
+        Ok(document) => Dropped::Transaction(Box::new(document)),
+        Err(e) if text.contains(&format!("\"{}\"", PSTX_FORMAT)) => {
+            Dropped::Unsupported(format!("Could not read {}: {}", name, e))
+        }
+        Err(_) => unknown(&name),
+    }
+}
+
+fn unknown(name: &str) -> Dropped {
+    Dropped::Unsupported(format!(
+        "{} is not a wallet or transaction file. Drop a .{} keystore or a file holding transaction hex",
+        name, KEYSTORE_EXTENSION
+    ))
+}
+
+pub fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pstx::PartiallySignedTransaction;
+    use crate::transaction::Transaction;
+    use crate::utxo::Utxo;
+    use crate::wallet::KaspaWallet;
+    use secp256k1::Secp256k1;
+
+    fn wallet() -> KaspaWallet {
+        let (secret_key, _) = Secp256k1::new().generate_keypair(&mut rand::rngs::OsRng);
+        KaspaWallet::with_network(secret_key, "mainnet").unwrap()
+    }
+
+    fn unsupported(dropped: Dropped) -> String {
+        match dropped {
+            Dropped::Unsupported(why) => why,
+            other => panic!("expected Unsupported, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_keystores_are_told_by_extension_or_header() {
+        let json = Keystore::encrypt(&wallet(), "main", "correct horse battery staple drop")
+            .unwrap()
+            .to_json()
+            .unwrap();
+        for path in ["main.keystore", "MAIN.KEYSTORE", "backup.json", "noext"] {
+            assert!(
+                matches!(
+                    classify(Path::new(path), json.as_bytes()),
+                    Dropped::Keystore(_)
+                ),
+                "{}",
+                path
+            );
+        }
+
+        let why = unsupported(classify(Path::new("main.keystore"), b"{\"a\": 1}"));
+        assert!(why.starts_with("Could not open main.keystore"), "{}", why);
+        let why = unsupported(classify(Path::new("main.keystore"), &[0xff, 0xfe]));
+        assert!(why.contains("not a readable wallet file"), "{}", why);
+    }
+
+    #[test]
+    fn test_transactions_and_unknown_files() {
+        let wallet = wallet();
+        let entry = Utxo {
+            txid: "11".repeat(32),
+            vout: 0,
+            address: wallet.get_address(),
+            amount: 5_000,
+            block_daa_score: 10,
+            is_coinbase: false,
+        };
+        let mut tx = Transaction::new();
+        tx.add_input(entry.txid.clone(), 0);
+        tx.add_output(wallet.get_address(), 4_000);
+
+        let hex_text = format!("{}\n", hex::encode(tx.encode().unwrap()));
+        let Dropped::Transaction(document) = classify(Path::new("tx.txt"), hex_text.as_bytes())
+        else {
+            panic!("hex not decoded");
+        };
+        assert_eq!(document.transaction().txid().unwrap(), tx.txid().unwrap());
+
+        let pstx = PartiallySignedTransaction::new("mainnet", tx, vec![entry])
+            .unwrap()
+            .to_json()
+            .unwrap();
+        assert!(matches!(
+            classify(Path::new("send.pstx"), pstx.as_bytes()),
+            Dropped::Transaction(_)
+        ));
+        let damaged = pstx.replace("5000", "5001");
+        let why = unsupported(classify(Path::new("send.pstx"), damaged.as_bytes()));
+        assert!(why.starts_with("Could not read send.pstx"), "{}", why);
+
+        let why = unsupported(classify(Path::new("kaspa.wallet"), b"{}"));
+        assert!(why.contains("rusty-kaspa"), "{}", why);
+        for contents in [&b"hello"[..], b"", &[0u8, 159, 146, 150]] {
+            let why = unsupported(classify(Path::new("notes.txt"), contents));
+            assert!(why.contains("not a wallet or transaction file"), "{}", why);
+        }
+
+        let missing = std::env::temp_dir().join("kasparustwallet-dropped-missing");
+        let why = unsupported(read(&missing));
+        assert!(why.starts_with("Could not read"), "{}", why);
+    }
+}
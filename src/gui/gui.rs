@@ -1,35 +1,787 @@
-use crate::address::validate_address;
+use super::clipboard::{Clipboard, SystemClipboard};
+use super::command::{Command, WalletBackend, WalletJob};
+use super::dropped::{self, Dropped};
+use super::notifications::{self, Clock, Notification, Notifications, Payload, SystemClock};
+use super::settings::{self as gui_settings, GuiSettings, ThemeChoice};
+use super::shortcuts::{self, Modal, SendFocus, Shortcut};
+use super::style::{self, Mono, NetworkTint, Severity};
+use super::tray::{self, TrayAction};
+use super::undo::{self, UndoStack};
+use crate::address::{self, check_address_format, validate_address};
+use crate::address_book::{AddressBook, Contact};
+use crate::address_index::AddressIndex;
+use crate::amount::{Kas, Unit};
+use crate::config::{self, Config};
+use crate::crypto;
+use crate::descriptor::{WalletDescriptor, WalletInfo};
 use crate::error::WalletError;
+use crate::hd::{ExtendedPublicKey, CHANGE_CHAIN, RECEIVE_CHAIN};
+use crate::history::{format_time, Direction, Explorer, HistoryCache, HistoryEntry, HTTP_TIMEOUT};
+use crate::key_source;
+use crate::keystore::{self, Keystore, KEYSTORE_EXTENSION};
+use crate::lockable::LockableWallet;
+use crate::mnemonic::{self, SUPPORTED_WORD_COUNTS};
+use crate::monitor::PaymentMonitor;
+use crate::network::{self, NetworkConfig};
+use crate::node::{
+    self, DaaScoreCache, FeeEstimate, FeeEstimateCache, NodeFailure, Propagation, RetryPolicy,
+    TxStatus, UtxoChanges, DEFAULT_RETRIES,
+};
+use crate::price::{Fiat, PriceApi, Prices};
+use crate::pstx::{PartiallySignedTransaction, TxDocument};
+use crate::qr;
+use crate::storage::Overwrite;
+use crate::transaction::{
+    max_sendable, Transaction, DUST_THRESHOLD, FEE_RATE_PRESETS, MINIMUM_RELAY_FEE_RATE,
+};
+use crate::uri::PaymentUri;
+use crate::utxo::{Utxo, UtxoEntry, UtxoSet};
 use crate::wallet::KaspaWallet;
-use iced::widget::{button, column, pick_list, row, text, text_input, Column, Container};
-use iced::{Element, Length};
+use crate::wallet_state::WalletState;
+use crate::watch_only::WatchOnlyWallet;
+use bip39::Mnemonic;
+use iced::futures::{SinkExt, Stream, StreamExt};
+use iced::widget::{
+    button, center, checkbox, column, container, image, mouse_area, opaque, pick_list, row,
+    scrollable, stack, text, text_editor, text_input, tooltip, Column, Container,
+};
+use iced::{keyboard, system, theme, Color, Element, Length, Subscription, Task, Theme};
 use secp256k1::SecretKey;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use zeroize::Zeroize;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Tab {
     Send,
     Receive,
+    History,
+    Contacts,
+}
+
+impl Tab {
+    /// In the order of the tab row, which Ctrl+1 to Ctrl+4 follow.
+    pub const ALL: [Tab; 4] = [Tab::Send, Tab::Receive, Tab::History, Tab::Contacts];
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
-    PrivateKeyInput(String),
+    PrivateKeyInput(Redacted),
+    TogglePrivateKey,
     NetworkSelected(NetworkOption),
+    /// Switches to the network the recipient's address is on.
+    SwitchNetwork(NetworkOption),
     CreateWallet,
     LoadWallet,
     RecipientInput(String),
     AmountInput(String),
+    AmountUnitSelected(Unit),
+    SendMax,
+    ManualInputChanged(String),
+    AddManualInput,
+    RemoveManualInput(usize),
     AddOutput,
     RemoveOutput(usize),
+    /// Opens the output at this index for editing in place.
+    EditOutput(usize),
+    EditOutputAddress(String),
+    EditOutputAmount(String),
+    SaveOutputEdit,
+    CancelOutputEdit,
     ClearOutputs,
+    /// Puts back the outputs as they were before the last removal.
+    UndoOutputs,
     SendTransaction,
-    GenerateAddress,
+    /// Hands out the HD wallet's next unused receive address.
+    NewAddress,
+    /// Copies the derived receive address at this index.
+    CopyDerivedAddress(u32),
+    /// Which of the derived addresses hold outputs on the node.
+    DerivedFundsFetched {
+        fingerprint: String,
+        result: Result<BTreeSet<String>, String>,
+    },
     ValidateAddressInput(String),
-    PasteFromClipboard,
+    Paste(PasteTarget),
+    ScanQr(ScanTarget),
+    /// The QR code's text, or `None` when the dialog was cancelled.
+    QrScanned(ScanTarget, Result<Option<String>, String>),
+    /// Clipboard text for a Paste button; `None` when there was none.
+    ClipboardRead(PasteTarget, Option<Redacted>),
+    /// A copy finished; `what` names it in the status bar.
+    Copied {
+        what: &'static str,
+        ok: bool,
+    },
     TabSelected(Tab),
     CopyAddress,
     CopyPublicKey,
+    /// Shows or hides the Overview's Advanced section.
+    ToggleAdvanced,
+    ExportWalletInfo,
+    ExportKeyToggled(bool),
+    /// Past the warning, on to saving an encrypted keystore.
+    ConfirmKeyExport,
+    WalletInfoExported(Result<Option<PathBuf>, String>),
+    NodeUrlInput(String),
+    RefreshBalance,
+    /// The timer refetching the balance, so payments show up unasked.
+    PollBalance,
+    /// The node notified a change to `address`'s outputs.
+    UtxosChanged {
+        address: String,
+    },
+    BalanceFetched {
+        address: String,
+        result: Result<Balance, String>,
+    },
+    WalletLoaded(Result<LoadedWallet, String>),
+    TransactionBuilt(Result<BuiltTransaction, String>),
+    ResultHexAction(text_editor::Action),
+    CopyResultHex,
+    /// Writes the built transaction as a PSTX file.
+    SaveResult,
+    ResultSaved(Result<Option<PathBuf>, String>),
+    /// Asks to confirm the broadcast.
+    Broadcast,
+    CancelBroadcast,
+    ConfirmBroadcast,
+    /// Broadcasts through the node even though it said it is not synced.
+    BroadcastAnyway,
+    Broadcasted {
+        txid: String,
+        result: Result<String, String>,
+        /// The node was refused for not being synced.
+        unsynced: bool,
+    },
+    /// Asks where the broadcast transaction is now.
+    PollTxStatus,
+    TxStatusFetched {
+        txid: String,
+        result: Result<TxStatus, String>,
+    },
+    /// Whether the broadcast transaction was still in the node's mempool
+    /// a few seconds later.
+    PropagationChecked {
+        txid: String,
+        result: Result<Propagation, String>,
+    },
+    CloseResult,
+    RequestAmountInput(String),
+    SaveQr,
+    /// The path written, or `None` when the dialog was cancelled.
+    QrSaved(Result<Option<PathBuf>, String>),
+    ExplorerUrlInput(String),
+    LoadMoreHistory,
+    RefreshHistory,
+    HistoryFetched {
+        address: String,
+        /// The page was older than this block time; `None` for the first.
+        before: Option<u64>,
+        result: Result<Vec<HistoryEntry>, String>,
+    },
+    ToggleHistoryEntry(String),
+    CopyTxid(String),
+    FeeChoiceSelected(FeeChoice),
+    CustomFeeRateInput(String),
+    FeeEstimateFetched(Result<Option<FeeEstimate>, String>),
+    OpenWalletFile,
+    SaveWalletFile,
+    WalletChosen(WalletChoice),
+    CloseWallet,
+    OpenPathChosen(Option<PathBuf>),
+    KeystoreRead(PathBuf, Result<Box<Keystore>, String>),
+    FileDropped(PathBuf),
+    DroppedFileRead(PathBuf, Dropped),
+    CloseDecoded,
+    /// Writes the settings if nothing changed since this generation.
+    SaveSettings(u64),
+    SettingsWritten(Result<(), String>),
+    SavePathChosen(Option<PathBuf>),
+    PromptPasswordInput(Redacted),
+    PromptConfirmInput(Redacted),
+    PromptSubmit,
+    PromptCancel,
+    WalletFileOpened(Result<(LoadedWallet, WalletFile), String>),
+    WalletFileSaved(Result<WalletFile, String>),
+    ContactLabelInput(String),
+    ContactAddressInput(String),
+    ContactFilterInput(String),
+    AddContact,
+    DeleteContact(String),
+    CopyContact(String),
+    ContactPicked(ContactChoice),
+    ToggleInputs,
+    InputToggled(String, u32),
+    ConfirmSend,
+    CancelSend,
+    /// The send dialog's "I mean this network" box.
+    NetworkMismatchAcknowledged(bool),
+    /// Escape, or a click beside an open dialog.
+    DismissModal,
+    /// A key press anywhere in the window, for the shortcuts.
+    KeyPressed(keyboard::Key, keyboard::Modifiers),
+    /// Opens the list of keyboard shortcuts.
+    ShowShortcuts,
+    /// Opens the warning that must be acknowledged before copying the key.
+    CopyPrivateKey,
+    KeyCopyAcknowledged(bool),
+    ConfirmKeyCopy,
+    PrivateKeyCopied(bool),
+    /// Clears the copied key from the clipboard before its time is up.
+    ClearClipboard,
+    ClipboardCleared(bool),
+    SeedWordsSelected(usize),
+    /// The pointer moved onto a word of the backup grid, or off the grid.
+    BackupWordHovered(Option<usize>),
+    BackupWrittenDown,
+    /// An answer to the backup quiz, by question.
+    BackupAnswerInput(usize, Redacted),
+    BackupSubmit,
+    /// Back from the quiz to the words.
+    BackupShowWords,
+    BackupSkip,
+    ThemeSelected(ThemeChoice),
+    FiatSelected(Fiat),
+    PriceUrlInput(String),
+    RefreshPrices,
+    PricesFetched(Result<Prices, String>),
+    DismissNotification(u64),
+    CopyNotification(u64),
+    ToggleActivity,
+    ClearActivity,
+    CopyLog,
+    AutoLockSelected(AutoLock),
+    UiScaleSelected(UiScale),
+    ClipboardClearSelected(ClearAfter),
+    Lock,
+    /// The password, or the key for a wallet with no file.
+    UnlockInput(Redacted),
+    UnlockSubmit,
+    Unlocked {
+        address: String,
+        result: Result<LoadedWallet, String>,
+    },
+    /// Once a second while a toast is waiting to expire or auto-lock is
+    /// armed.
+    Tick,
+    /// The desktop switched between light and dark.
+    SystemThemeChanged(theme::Mode),
+    /// The tray icon is up.
+    TrayReady,
+    /// Something was picked in the tray icon's menu.
+    Tray(TrayAction),
+    MinimizeToTrayToggled(bool),
+    /// The window's close button was pressed.
+    CloseRequested(iced::window::Id),
+}
+
+impl Message {
+    /// Whether it uses the shown wallet's key, so it must wait for an
+    /// unlock while the window is locked.
+    fn needs_key(&self) -> bool {
+        matches!(
+            self,
+            Message::TogglePrivateKey
+                | Message::CopyPrivateKey
+                | Message::ConfirmKeyCopy
+                | Message::ConfirmSend
+                | Message::SaveWalletFile
+        )
+    }
+
+    /// Whether it comes from a timer or a finished task rather than the
+    /// user, so it does not hold off auto-lock.
+    fn is_background(&self) -> bool {
+        matches!(
+            self,
+            Message::BalanceFetched { .. }
+                | Message::PollBalance
+                | Message::UtxosChanged { .. }
+                | Message::TrayReady
+                | Message::DerivedFundsFetched { .. }
+                | Message::WalletLoaded(_)
+                | Message::TransactionBuilt(_)
+                | Message::QrSaved(_)
+                | Message::QrScanned(..)
+                | Message::ResultSaved(_)
+                | Message::Broadcasted { .. }
+                | Message::PollTxStatus
+                | Message::TxStatusFetched { .. }
+                | Message::PropagationChecked { .. }
+                | Message::HistoryFetched { .. }
+                | Message::FeeEstimateFetched(_)
+                | Message::SaveSettings(_)
+                | Message::SettingsWritten(_)
+                | Message::KeystoreRead(..)
+                | Message::WalletFileOpened(_)
+                | Message::WalletFileSaved(_)
+                | Message::WalletInfoExported(_)
+                | Message::DroppedFileRead(..)
+                | Message::Copied { .. }
+                | Message::ClipboardRead(..)
+                | Message::PrivateKeyCopied(_)
+                | Message::ClipboardCleared(_)
+                | Message::PricesFetched(_)
+                | Message::Unlocked { .. }
+                | Message::Tick
+                | Message::SystemThemeChanged(_)
+        )
+    }
+}
+
+/// The fields with a Paste button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasteTarget {
+    Recipient,
+    PrivateKey,
+    Validation,
+}
+
+/// The fields with a "Scan from image…" button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanTarget {
+    Recipient,
+    Validation,
+}
+
+/// An auto-lock delay as the Settings picker lists it, in minutes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoLock(pub u32);
+
+impl AutoLock {
+    pub const ALL: [AutoLock; 6] = [
+        AutoLock(1),
+        AutoLock(5),
+        AutoLock(10),
+        AutoLock(30),
+        AutoLock(60),
+        AutoLock(0),
+    ];
+}
+
+impl fmt::Display for AutoLock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            0 => f.write_str("Never"),
+            1 => f.write_str("1 minute"),
+            minutes => write!(f, "{} minutes", minutes),
+        }
+    }
+}
+
+/// A UI scale as the Settings picker lists it, in percent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UiScale(pub u32);
+
+impl UiScale {
+    pub const ALL: [UiScale; 6] = [
+        UiScale(90),
+        UiScale(100),
+        UiScale(110),
+        UiScale(125),
+        UiScale(135),
+        UiScale(150),
+    ];
+}
+
+impl fmt::Display for UiScale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}%", self.0)
+    }
+}
+
+/// How long a copied key stays on the clipboard, as Settings lists it.
+/// There is no "never".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClearAfter(pub u64);
+
+impl ClearAfter {
+    pub const ALL: [ClearAfter; 4] = [
+        ClearAfter(10),
+        ClearAfter(30),
+        ClearAfter(60),
+        ClearAfter(120),
+    ];
+}
+
+impl fmt::Display for ClearAfter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} seconds", self.0)
+    }
+}
+
+/// A contact as the Send tab's picker lists it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContactChoice(pub Contact);
+
+impl fmt::Display for ContactChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.0.label, shorten(&self.0.address))
+    }
+}
+
+/// What the Send confirmation shows. Nothing is signed until it is
+/// confirmed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendSummary {
+    pub network: String,
+    pub recipients: Vec<(String, Kas)>,
+    /// Picked by hand or by automatic coin selection.
+    pub inputs: Vec<Utxo>,
+    pub fee_rate: u64,
+    pub fee: Kas,
+    /// Back to `change_address`; `None` when the inputs match exactly.
+    pub change: Option<Kas>,
+    pub change_address: String,
+    /// Recipients plus fee.
+    pub total: Kas,
+}
+
+/// The encrypted wallet file the current wallet was opened from or saved to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalletFile {
+    pub path: PathBuf,
+    pub fingerprint: String,
+}
+
+impl WalletFile {
+    fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.path.display().to_string())
+    }
+}
+
+/// An open wallet other than the one shown, with what was fetched for it,
+/// so switching back needs no new requests.
+#[derive(Debug, Clone)]
+struct ParkedWallet {
+    private_key: Redacted,
+    secret: Redacted,
+    wallet: KaspaGuiWallet,
+    file: Option<WalletFile>,
+    balance: Option<Balance>,
+    balance_error: Option<String>,
+    selected_inputs: BTreeSet<(String, u32)>,
+    history: Vec<HistoryEntry>,
+    history_loaded: bool,
+    history_complete: bool,
+    history_error: Option<String>,
+    derived_addresses: Vec<DerivedAddress>,
+}
+
+impl ParkedWallet {
+    fn new(private_key: Redacted, secret: Redacted, wallet: KaspaGuiWallet) -> Self {
+        Self {
+            private_key,
+            secret,
+            wallet,
+            derived_addresses: Vec::new(),
+            file: None,
+            balance: None,
+            balance_error: None,
+            selected_inputs: BTreeSet::new(),
+            history: Vec::new(),
+            history_loaded: false,
+            history_complete: false,
+            history_error: None,
+        }
+    }
+}
+
+/// A receive address handed out for an HD wallet, as listed on the
+/// Receive tab.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivedAddress {
+    pub index: u32,
+    pub address: String,
+    /// Whether the node holds outputs for it; `None` until asked.
+    pub funded: Option<bool>,
+}
+
+/// An entry in the header's wallet switcher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalletChoice {
+    Open { fingerprint: String, label: String },
+    Add,
+}
+
+impl fmt::Display for WalletChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalletChoice::Open { label, .. } => f.write_str(label),
+            WalletChoice::Add => f.write_str("Add wallet…"),
+        }
+    }
+}
+
+/// "savings.kaspa-wallet (1a2b3c4d)", or "Unsaved (1a2b3c4d)".
+fn wallet_label(wallet: &KaspaGuiWallet, file: Option<&WalletFile>) -> String {
+    let name = file.map_or_else(|| "Unsaved".to_string(), WalletFile::name);
+    format!("{} ({})", name, wallet.fingerprint)
+}
+
+/// What the password prompt unlocks or writes.
+#[derive(Debug, Clone)]
+pub enum FileAction {
+    Open(Box<Keystore>),
+    Save,
+}
+
+/// The modal password prompt for a wallet file. Stays open on a wrong
+/// password so it can be retyped.
+#[derive(Debug, Clone)]
+pub struct PasswordPrompt {
+    pub action: FileAction,
+    pub path: PathBuf,
+    pub password: Redacted,
+    /// Second entry when saving.
+    pub confirm: Redacted,
+    pub error: Option<String>,
+}
+
+/// How long settings must stay unchanged before they are written.
+const SETTINGS_SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// Smallest window the layout is meant for.
+const MIN_WIDTH: f32 = 640.0;
+const MIN_HEIGHT: f32 = 480.0;
+
+/// Queued outputs listed before the list scrolls.
+const OUTPUTS_SHOWN: usize = 5;
+
+/// How often the open wallet's balance is fetched again, to catch
+/// incoming payments the node did not notify.
+const BALANCE_POLL: Duration = Duration::from_secs(30);
+
+/// How long a wait for the node's next UTXO notification lasts before
+/// checking whether the subscription is still wanted.
+const UTXO_CHANGE_WAIT: Duration = Duration::from_secs(1);
+
+/// How often a broadcast transaction's status is fetched again while its
+/// result panel is open, to count confirmations.
+const TX_STATUS_POLL: Duration = Duration::from_secs(5);
+
+/// How often the fiat price is fetched again.
+const PRICE_REFRESH: Duration = Duration::from_secs(300);
+
+/// The private key copied to the clipboard, and when to clear it.
+#[derive(Debug, Clone)]
+pub struct PendingClear {
+    /// Unix milliseconds.
+    pub at: u64,
+    pub text: Redacted,
+}
+
+/// Which fee rate the Send tab signs with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeChoice {
+    /// Index into `FEE_RATE_PRESETS`.
+    Preset(usize),
+    Custom,
+}
+
+/// Transactions per page in the History tab.
+const HISTORY_PAGE: usize = 20;
+
+/// A key or password held by the GUI. `Debug` never shows it, so states and
+/// messages can be logged safely, and it is zeroized when dropped.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Redacted(String);
+
+impl From<String> for Redacted {
+    fn from(value: String) -> Self {
+        Redacted(value)
+    }
+}
+
+impl std::ops::Deref for Redacted {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<redacted>")
+    }
+}
+
+impl Drop for Redacted {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// A new wallet's seed phrase on its way to the backup screen. `Debug`
+/// hides it, and the words are zeroized when it is dropped.
+#[derive(Clone)]
+pub struct SeedPhrase(Mnemonic);
+
+impl fmt::Debug for SeedPhrase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<redacted>")
+    }
+}
+
+/// A signed transaction, as built off the UI thread, with the
+/// confirmation it was built from.
+#[derive(Debug, Clone)]
+pub struct BuiltTransaction {
+    pub txid: String,
+    pub hex: String,
+    pub transaction: Transaction,
+    pub summary: SendSummary,
+}
+
+impl BuiltTransaction {
+    fn new(transaction: Transaction, summary: SendSummary) -> Result<Self, WalletError> {
+        Ok(Self {
+            txid: transaction.txid()?,
+            hex: hex::encode(transaction.serialize()?),
+            transaction,
+            summary,
+        })
+    }
+
+    /// As a PSTX file, the outputs it spends included.
+    fn pstx_json(&self) -> Result<String, WalletError> {
+        PartiallySignedTransaction::new(
+            &self.summary.network,
+            self.transaction.clone(),
+            self.summary.inputs.clone(),
+        )?
+        .to_json()
+    }
+}
+
+/// The last built transaction, shown until it is closed.
+#[derive(Debug, Clone)]
+pub struct ResultPanel {
+    pub built: BuiltTransaction,
+    /// The hex, selectable but not editable.
+    pub hex: text_editor::Content,
+    /// Broadcast was pressed and waits for its confirmation.
+    pub confirm_broadcast: bool,
+    /// The txid the node accepted, or why it refused.
+    pub broadcast: Option<Result<String, String>>,
+    /// The broadcast was refused because the node is not synced, so
+    /// broadcasting anyway is offered.
+    pub unsynced_node: bool,
+    /// Where the broadcast transaction is, once the node was asked.
+    pub status: Option<TxStatus>,
+    /// Whether the node still had it shortly after the broadcast.
+    pub propagation: Option<Propagation>,
+}
+
+/// A transaction file dropped onto the window, decoded for a look.
+#[derive(Debug, Clone)]
+pub struct DecodedPanel {
+    pub name: String,
+    pub txid: String,
+    pub document: TxDocument,
+}
+
+/// A wallet created or parsed off the UI thread.
+#[derive(Debug, Clone)]
+pub struct LoadedWallet {
+    pub private_key: Redacted,
+    /// What every key of the wallet derives from, as
+    /// `keystore::export_secret` writes it.
+    pub secret: Redacted,
+    pub wallet: KaspaGuiWallet,
+    /// Newly generated rather than loaded from a key.
+    pub created: bool,
+    /// Set for a new HD wallet, whose phrase must be backed up first.
+    pub phrase: Option<SeedPhrase>,
+}
+
+/// What fills the window.
+#[derive(Debug, Clone)]
+pub enum Screen {
+    Wallet,
+    /// No key is held until the shown wallet is unlocked.
+    Locked(LockScreen),
+    /// After an HD wallet is created, until its phrase is confirmed or the
+    /// check is skipped.
+    Backup(Box<Backup>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupStep {
+    /// The numbered words, each shown while the pointer is over it.
+    Words,
+    /// Words asked back by position.
+    Quiz,
+}
+
+/// The unlock screen shown once the window locks.
+#[derive(Debug, Clone, Default)]
+pub struct LockScreen {
+    pub secret: Redacted,
+    pub error: Option<String>,
+    /// What needed the key while locked, carried out once unlocked.
+    pub resume: Option<Box<Message>>,
+}
+
+/// The backup screen. The wallet is held here, unusable, until it ends.
+#[derive(Debug, Clone)]
+pub struct Backup {
+    pub pending: LoadedWallet,
+    pub phrase: SeedPhrase,
+    pub step: BackupStep,
+    pub hovered: Option<usize>,
+    /// 1-based positions asked in the quiz.
+    pub positions: Vec<usize>,
+    pub answers: Vec<Redacted>,
+    pub error: Option<String>,
+}
+
+/// Operations in flight; each keeps its own controls disabled meanwhile.
+/// The message that ends one clears its flag, failed or not.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Busy {
+    pub wallet: bool,
+    /// What the wallet operation is doing, for its progress text.
+    pub wallet_task: &'static str,
+    pub transaction: bool,
+    pub balance: bool,
+    pub history: bool,
+    pub broadcast: bool,
+    pub tx_status: bool,
+}
+
+impl Busy {
+    fn start_wallet(&mut self, task: &'static str) {
+        self.wallet = true;
+        self.wallet_task = task;
+    }
+}
+
+/// The wallet's balance as of `updated`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Balance {
+    /// All outputs, in sompi.
+    pub total: u64,
+    /// Immature coinbase outputs, in sompi.
+    pub pending: u64,
+    pub utxo_count: usize,
+    /// Milliseconds since the Unix epoch.
+    pub updated: u64,
+    /// The outputs themselves, for coin control.
+    pub utxos: UtxoSet,
+    /// None when the node gave none; ages then show as unknown.
+    pub virtual_daa_score: Option<u64>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -52,6 +804,17 @@ impl fmt::Display for NetworkOption {
 }
 
 impl NetworkOption {
+    fn from_name(name: &str) -> Option<Self> {
+        [
+            NetworkOption::Mainnet,
+            NetworkOption::Testnet10,
+            NetworkOption::Testnet11,
+            NetworkOption::Simnet,
+        ]
+        .into_iter()
+        .find(|network| network.to_str() == name)
+    }
+
     fn to_str(&self) -> &'static str {
         match self {
             NetworkOption::Mainnet => "mainnet",
@@ -60,30 +823,177 @@ impl NetworkOption {
             NetworkOption::Simnet => "simnet",
         }
     }
+
+    fn tint(&self) -> NetworkTint {
+        match self {
+            NetworkOption::Mainnet => NetworkTint::Mainnet,
+            NetworkOption::Testnet10 | NetworkOption::Testnet11 => NetworkTint::Testnet,
+            NetworkOption::Simnet => NetworkTint::Simnet,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct OutputRow {
     pub address: String,
+    pub amount: Kas,
+    /// Contact the address was picked from.
+    pub label: Option<String>,
+}
+
+/// An output row being edited in place, as typed so far.
+#[derive(Debug, Clone)]
+pub struct OutputEdit {
+    /// Position in `outputs`, kept in step as rows above it are removed.
+    pub index: usize,
+    pub address: String,
+    /// In the amount field's unit.
     pub amount: String,
 }
 
+impl OutputEdit {
+    /// Both fields checked as Add checks them.
+    fn check(&self, network: &NetworkOption, unit: Unit) -> Result<(String, Kas), String> {
+        let amount = parse_output_amount(&self.amount, unit);
+        match (check_recipient(&self.address, network), amount) {
+            (None, _) | (_, None) => Err("Enter recipient and amount".to_string()),
+            (Some(Err(reason)), _) | (_, Some(Err(reason))) => Err(reason),
+            (Some(Ok(())), Some(Ok(amount))) => Ok((self.address.trim().to_string(), amount)),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
-pub struct WalletGui {
-    private_key: String,
+pub struct WalletGuiState {
+    private_key: Redacted,
+    /// Key of the shown wallet; the field above may have been edited since
+    /// it loaded.
+    wallet_key: Redacted,
+    /// Secret of the shown wallet, which signs for all its addresses.
+    wallet_secret: Redacted,
+    /// The private key field shows its text until the tab changes.
+    show_private_key: bool,
+    /// Whether the Overview's Advanced section, with the keys, is open.
+    show_advanced: bool,
     network: NetworkOption,
     wallet: Option<KaspaGuiWallet>,
     current_tab: Tab,
     recipient: String,
     amount: String,
+    /// Unit the amount field is typed in.
+    amount_unit: Unit,
+    /// The amount field follows Max until it is edited.
+    send_max: bool,
     outputs: Vec<OutputRow>,
-    status_message: String,
+    /// The output row open for editing, if any.
+    output_edit: Option<OutputEdit>,
+    /// The outputs before each Remove or Clear All, until a transaction is
+    /// built from them.
+    output_undo: UndoStack<OutputRow>,
+    notifications: Notifications,
+    /// The address the Receive tab shows: the wallet's own, or the receive
+    /// address handed out last.
     address_result: String,
-    public_key_result: String,
+    /// Receive addresses handed out so far for the shown HD wallet, by
+    /// index.
+    derived_addresses: Vec<DerivedAddress>,
+    /// `watched_addresses` as of the last balance refresh, which the UTXO
+    /// notifications follow.
+    watched: Vec<String>,
     validate_address_input: String,
     validation_result: String,
+    /// Why the last QR image scanned for the recipient was of no use.
+    recipient_scan_error: Option<String>,
     copy_address_text: String,
-    copy_public_key_text: String,
+    node_url: String,
+    balance: Option<Balance>,
+    balance_error: Option<String>,
+    busy: Busy,
+    /// Optional amount (KAS) to put in the receive QR code.
+    request_amount: String,
+    /// The receive address or payment URI as a PNG QR code.
+    qr_png: Option<Vec<u8>>,
+    qr_image: Option<image::Handle>,
+    qr_error: Option<String>,
+    explorer_url: String,
+    /// History pages fetched so far for the loaded wallet, newest first.
+    history: Vec<HistoryEntry>,
+    history_loaded: bool,
+    /// A short page came back, so there is nothing older to load.
+    history_complete: bool,
+    history_error: Option<String>,
+    /// Txid of the entry whose details are shown.
+    history_expanded: Option<String>,
+    fee_choice: FeeChoice,
+    /// Sompi per 1000 grams of mass, as typed.
+    custom_fee_rate: String,
+    /// Rates the node suggested for the presets, if it did.
+    suggested_fees: Option<FeeEstimate>,
+    /// Why the presets fell back to the built-in rates, when asking the
+    /// node failed.
+    fee_estimate_error: Option<String>,
+    wallet_file: Option<WalletFile>,
+    /// Open wallets other than the shown one, by fingerprint.
+    open_wallets: BTreeMap<String, ParkedWallet>,
+    prompt: Option<PasswordPrompt>,
+    /// The transaction waiting for Confirm in the Send dialog.
+    confirm_send: Option<SendSummary>,
+    /// The last built transaction, until it is closed.
+    result: Option<ResultPanel>,
+    /// A dropped transaction file, until it is closed.
+    decoded: Option<DecodedPanel>,
+    /// Whether the key copy warning is open, and if it is acknowledged.
+    key_copy: Option<bool>,
+    /// Whether Export… writes the key, as an encrypted keystore.
+    export_key: bool,
+    /// Whether the warning before exporting the key is open.
+    confirm_key_export: bool,
+    /// Whether the Shortcuts popup is open.
+    show_shortcuts: bool,
+    /// The part of the Send tab used last, for Ctrl+Enter.
+    send_focus: SendFocus,
+    /// Whether the send dialog's network mismatch box is ticked.
+    network_acknowledged: bool,
+    pending_clear: Option<PendingClear>,
+    screen: Screen,
+    /// Length of the seed phrase for Create New Wallet.
+    seed_words: usize,
+    /// Holds the address book.
+    data_dir: PathBuf,
+    /// Address book entries on the selected network.
+    contacts: Vec<Contact>,
+    contact_label: String,
+    contact_address: String,
+    contact_filter: String,
+    contact_error: Option<String>,
+    /// Contact the recipient field was filled from.
+    recipient_label: Option<String>,
+    /// Whether the Send tab's Inputs section is expanded.
+    show_inputs: bool,
+    /// Outpoints picked by hand; empty means automatic selection.
+    selected_inputs: BTreeSet<(String, u32)>,
+    /// `txid:vout:amount` being typed for `manual_inputs`.
+    manual_input: String,
+    /// Outputs to spend entered by hand, for when no balance was fetched.
+    manual_inputs: Vec<Utxo>,
+    settings: GuiSettings,
+    price_url: String,
+    /// Latest fiat prices; `None` until fetched, or once a fetch failed.
+    prices: Option<Prices>,
+    /// Holds `gui.json`.
+    settings_dir: PathBuf,
+    /// Bumped on every settings change so only the last pending save writes.
+    settings_generation: u64,
+    /// Light or dark as the desktop last reported it.
+    system_mode: theme::Mode,
+    /// Unix milliseconds of the last user input, for auto-lock.
+    last_interaction: u64,
+    /// What toasts, Undo, auto-lock and the clipboard countdown time by.
+    clock: Rc<dyn Clock>,
+    /// Does the work on keys.
+    backend: Arc<dyn WalletBackend>,
+    /// Whether the tray icon is up, so closing can hide the window.
+    tray_ready: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -91,538 +1001,7878 @@ pub struct KaspaGuiWallet {
     pub address: String,
     pub public_key: String,
     pub network_name: String,
+    pub fingerprint: String,
+    /// Account extended public key, for HD wallets.
+    pub account_xpub: Option<String>,
+    /// Where the account key sits, for HD wallets.
+    pub derivation_path: Option<String>,
+    /// What a watch-only copy needs, on the current network.
+    pub descriptor: WalletDescriptor,
+    /// The network it was first created, loaded or opened on. Picking
+    /// another in Settings reloads it there but leaves this.
+    pub home_network: String,
 }
 
-impl WalletGui {
-    fn new() -> Self {
-        Self {
-            private_key: String::new(),
-            network: NetworkOption::Mainnet,
+impl KaspaGuiWallet {
+    /// "Single key", or "HD account 0" with its derivation path.
+    pub fn key_type(&self) -> String {
+        match &self.derivation_path {
+            Some(path) => {
+                let account = path.rsplit('/').next().unwrap_or("").trim_end_matches('\'');
+                format!("HD account {} ({})", account, path)
+            }
+            None => "Single key".to_string(),
+        }
+    }
+}
+
+impl WalletGuiState {
+    /// The state with the CLI's config and the wallet's files under
+    /// `data_dir`, and the GUI's own settings under `settings_dir`.
+    fn new(data_dir: PathBuf, settings_dir: PathBuf) -> Self {
+        let saved = Config::load(&data_dir).unwrap_or_default();
+        let (settings, recovered) = GuiSettings::load_or_recover(&settings_dir);
+        let clock: Rc<dyn Clock> = Rc::new(SystemClock);
+        let mut state = Self {
+            private_key: Redacted::default(),
+            wallet_key: Redacted::default(),
+            wallet_secret: Redacted::default(),
+            show_private_key: false,
+            show_advanced: false,
+            network: settings
+                .network
+                .as_deref()
+                .and_then(NetworkOption::from_name)
+                .unwrap_or(NetworkOption::Mainnet),
             wallet: None,
             current_tab: Tab::Send,
             recipient: String::new(),
             amount: String::new(),
+            amount_unit: Unit::Kas,
+            send_max: false,
             outputs: Vec::new(),
-            status_message: String::new(),
+            output_edit: None,
+            output_undo: UndoStack::default(),
+            notifications: Notifications::default(),
             address_result: String::new(),
-            public_key_result: String::new(),
+            derived_addresses: Vec::new(),
+            watched: Vec::new(),
             validate_address_input: String::new(),
             validation_result: String::new(),
+            recipient_scan_error: None,
             copy_address_text: String::new(),
-            copy_public_key_text: String::new(),
+            node_url: settings
+                .node_url
+                .clone()
+                .or(saved.node_url)
+                .unwrap_or_default(),
+            balance: None,
+            balance_error: None,
+            busy: Busy::default(),
+            request_amount: String::new(),
+            qr_png: None,
+            qr_image: None,
+            qr_error: None,
+            explorer_url: settings
+                .explorer_url
+                .clone()
+                .or(saved.explorer_url)
+                .unwrap_or_default(),
+            history: Vec::new(),
+            history_loaded: false,
+            history_complete: false,
+            history_error: None,
+            history_expanded: None,
+            fee_choice: FeeChoice::Preset(1),
+            custom_fee_rate: String::new(),
+            suggested_fees: None,
+            fee_estimate_error: None,
+            wallet_file: None,
+            open_wallets: BTreeMap::new(),
+            prompt: None,
+            confirm_send: None,
+            result: None,
+            decoded: None,
+            key_copy: None,
+            export_key: false,
+            confirm_key_export: false,
+            show_shortcuts: false,
+            send_focus: SendFocus::None,
+            network_acknowledged: false,
+            pending_clear: None,
+            screen: Screen::Wallet,
+            seed_words: SUPPORTED_WORD_COUNTS[0],
+            data_dir,
+            contacts: Vec::new(),
+            contact_label: String::new(),
+            contact_address: String::new(),
+            contact_filter: String::new(),
+            contact_error: None,
+            recipient_label: None,
+            show_inputs: false,
+            selected_inputs: BTreeSet::new(),
+            manual_input: String::new(),
+            manual_inputs: Vec::new(),
+            price_url: settings
+                .price_url
+                .clone()
+                .or(saved.price_url)
+                .unwrap_or_default(),
+            prices: None,
+            settings,
+            settings_dir,
+            settings_generation: 0,
+            system_mode: theme::Mode::None,
+            last_interaction: clock.now(),
+            clock,
+            backend: Arc::new(LocalBackend),
+            tray_ready: false,
+        };
+        if let Some(note) = recovered {
+            state.notify(Severity::Warning, note);
         }
+        state.reload_contacts();
+        state
     }
 
-    fn load_wallet(&mut self) -> Result<(), WalletError> {
-        let secret_key_bytes = hex::decode(&self.private_key)?;
-        let secret_key = SecretKey::from_slice(&secret_key_bytes)?;
-        let wallet = KaspaWallet::with_network(secret_key, self.network.to_str())?;
-        self.wallet = Some(KaspaGuiWallet {
-            address: wallet.get_address(),
-            public_key: wallet.get_public_key(),
-            network_name: wallet.get_network_name().to_string(),
-        });
-        Ok(())
+    /// Fetches the fiat prices, or does nothing without a price API.
+    fn refresh_prices(&self) -> Vec<Command> {
+        let url = self.price_url.trim().to_string();
+        if url.is_empty() {
+            return Vec::new();
+        }
+        vec![Command::FetchPrices { url }]
     }
-}
 
-fn get_clipboard_text() -> Option<String> {
-    if let Ok(output) = std::process::Command::new("sh")
-        .arg("-c")
-        .arg("xclip -selection clipboard -o 2>/dev/null")
-        .output()
-    {
-        if output.status.success() {
-            if let Ok(text) = String::from_utf8(output.stdout) {
-                let trimmed = text.trim().to_string();
-                if !trimmed.is_empty() {
-                    return Some(trimmed);
-                }
-            }
+    /// `amount` in the chosen currency, such as "≈ 1.25 USD"; `None` when
+    /// no price API is set. Display only: nothing signed depends on it.
+    fn fiat_value(&self, amount: Kas) -> Option<String> {
+        if self.price_url.trim().is_empty() {
+            return None;
         }
+        Some(
+            self.prices
+                .and_then(|prices| prices.approximate(amount, self.settings.fiat))
+                .unwrap_or_else(|| "price unavailable".to_string()),
+        )
     }
 
-    if let Ok(output) = std::process::Command::new("sh")
-        .arg("-c")
-        .arg("xsel --clipboard --output 2>/dev/null")
-        .output()
-    {
-        if output.status.success() {
-            if let Ok(text) = String::from_utf8(output.stdout) {
-                let trimmed = text.trim().to_string();
-                if !trimmed.is_empty() {
-                    return Some(trimmed);
-                }
-            }
-        }
+    fn now(&self) -> u64 {
+        self.clock.now()
     }
 
-    if let Ok(mut clipboard) = arboard::Clipboard::new() {
-        if let Ok(text) = clipboard.get_text() {
-            let trimmed = text.trim().to_string();
-            if !trimmed.is_empty() {
-                return Some(trimmed);
-            }
-        }
+    /// Shows `message` as a toast and in the Activity drawer.
+    fn notify(&mut self, severity: Severity, message: impl Into<String>) {
+        let now = self.now();
+        self.notifications.push(severity, message, None, now);
     }
 
-    None
-}
+    /// Schedules a save of the settings, dropping any still pending so a
+    /// burst of typing writes once.
+    fn settings_changed(&mut self) -> Vec<Command> {
+        self.settings_generation += 1;
+        vec![Command::SaveSettingsLater(self.settings_generation)]
+    }
 
-fn set_clipboard_text(text: &str) -> bool {
-    if std::process::Command::new("sh")
-        .arg("-c")
-        .arg(format!(
-            "echo -n '{}' | xclip -selection clipboard",
-            text.replace("'", "'\\''")
-        ))
-        .status()
-        .is_ok()
-    {
-        return true;
+    /// The settings as they stand; blank URLs are left to `config.json`.
+    fn current_settings(&self) -> GuiSettings {
+        let non_empty = |url: &str| (!url.trim().is_empty()).then(|| url.trim().to_string());
+        GuiSettings {
+            network: Some(self.network.to_str().to_string()),
+            node_url: non_empty(&self.node_url),
+            explorer_url: non_empty(&self.explorer_url),
+            price_url: non_empty(&self.price_url),
+            ..self.settings.clone()
+        }
     }
 
-    if let Ok(mut clipboard) = arboard::Clipboard::new() {
-        if clipboard.set_text(text).is_ok() {
-            return true;
+    /// The Activity drawer as text, with every key, password and seed
+    /// phrase the window holds masked.
+    fn activity_log(&self) -> String {
+        let mut secrets: Vec<String> = vec![
+            self.private_key.to_string(),
+            self.wallet_key.to_string(),
+            self.wallet_secret.to_string(),
+        ];
+        secrets.extend(
+            self.open_wallets
+                .values()
+                .flat_map(|parked| [parked.private_key.to_string(), parked.secret.to_string()]),
+        );
+        if let Some(prompt) = &self.prompt {
+            secrets.extend([prompt.password.to_string(), prompt.confirm.to_string()]);
         }
+        if let Screen::Locked(lock) = &self.screen {
+            secrets.push(lock.secret.to_string());
+        }
+        if let Screen::Backup(backup) = &self.screen {
+            secrets.push(backup.pending.private_key.to_string());
+            secrets.push(backup.pending.secret.to_string());
+            secrets.push(backup.phrase.0.to_string());
+        }
+        let secrets: Vec<&str> = secrets.iter().map(|secret| secret.trim()).collect();
+        self.notifications.log(&secrets)
     }
 
-    if std::process::Command::new("sh")
-        .arg("-c")
-        .arg(format!(
-            "echo -n '{}' | xsel --clipboard --input",
-            text.replace("'", "'\\''")
-        ))
-        .status()
-        .is_ok()
-    {
-        return true;
+    /// Like [`WalletGuiState::notify`], with a Copy button for `payload`.
+    fn notify_with(&mut self, severity: Severity, message: impl Into<String>, payload: Payload) {
+        let now = self.now();
+        self.notifications
+            .push(severity, message, Some(payload), now);
     }
 
-    false
-}
+    /// Reads the selected network's contacts from the address book.
+    fn reload_contacts(&mut self) {
+        match AddressBook::load(&self.data_dir) {
+            Ok(book) => {
+                self.contacts = book.contacts(self.network.to_str()).cloned().collect();
+            }
+            Err(e) => {
+                self.contacts.clear();
+                self.contact_error = Some(e.to_string());
+            }
+        }
+    }
 
-pub fn run_gui() -> Result<(), iced::Error> {
-    let settings = iced::Settings {
-        antialiasing: true,
-        ..iced::Settings::default()
-    };
+    /// Applies `change` to the address book on disk and reloads it.
+    fn edit_contacts(
+        &mut self,
+        change: impl FnOnce(&mut AddressBook) -> Result<(), WalletError>,
+    ) -> Result<(), WalletError> {
+        let mut book = AddressBook::load(&self.data_dir)?;
+        change(&mut book)?;
+        book.save()?;
+        self.reload_contacts();
+        Ok(())
+    }
 
-    iced::application(WalletGui::new, update, view)
-        .settings(settings)
-        .run()
-}
+    /// Contacts whose label or address contains the filter, ignoring case.
+    fn filtered_contacts(&self) -> Vec<&Contact> {
+        let filter = self.contact_filter.trim().to_lowercase();
+        self.contacts
+            .iter()
+            .filter(|contact| {
+                contact.label.to_lowercase().contains(&filter)
+                    || contact.address.to_lowercase().contains(&filter)
+            })
+            .collect()
+    }
 
-fn update(state: &mut WalletGui, message: Message) {
-    match message {
-        Message::PrivateKeyInput(key) => {
-            state.private_key = key;
-            if KaspaWallet::validate_private_key(&state.private_key) {
-                state.status_message = "Private key is valid".to_string();
-            } else if state.private_key.len() == 64 {
-                state.status_message = "Invalid private key format".to_string();
-            } else {
-                state.status_message = String::new();
+    /// Fetches the balance in the background, or does nothing without a
+    /// node URL or wallet.
+    fn refresh_balance(&mut self) -> Vec<Command> {
+        if self.wallet.is_none() || self.node_url.trim().is_empty() || self.busy.balance {
+            return Vec::new();
+        }
+        let addresses = match self.watched_addresses() {
+            Ok(addresses) => addresses,
+            Err(e) => {
+                self.notify(
+                    Severity::Error,
+                    format!("Could not list the wallet's addresses: {}", e),
+                );
+                return Vec::new();
             }
+        };
+        let Some(wallet) = &self.wallet else {
+            return Vec::new();
+        };
+        self.busy.balance = true;
+        self.watched = addresses.clone();
+        let url = self.node_url.trim().to_string();
+        vec![
+            Command::FetchBalance {
+                url: url.clone(),
+                network: wallet.network_name.clone(),
+                address: wallet.address.clone(),
+                addresses,
+            },
+            Command::FetchFeeEstimate { url },
+        ]
+    }
+
+    /// First unused receive and change index of the shown HD wallet.
+    fn next_index(&self) -> [u32; 2] {
+        let (Some(wallet), Ok(used)) = (&self.wallet, AddressIndex::load(&self.data_dir)) else {
+            return [0; 2];
+        };
+        [RECEIVE_CHAIN, CHANGE_CHAIN].map(|chain| used.next(&wallet.fingerprint, chain))
+    }
+
+    /// The addresses whose outputs make up the shown wallet's balance: an
+    /// HD wallet's on both chains, up to the gap limit past the next unused
+    /// index, as the CLI syncs them.
+    fn watched_addresses(&self) -> Result<Vec<String>, WalletError> {
+        let Some(wallet) = &self.wallet else {
+            return Ok(Vec::new());
+        };
+        if wallet.account_xpub.is_none() {
+            return Ok(vec![wallet.address.clone()]);
         }
-        Message::NetworkSelected(network) => {
-            state.network = network;
+        let watch_only = WatchOnlyWallet::from_descriptor(&wallet.descriptor)?;
+        let next = self.next_index();
+        let mut addresses = Vec::new();
+        for index in 0..next[0] + watch_only.gap_limit() {
+            addresses.push(watch_only.receive_address(index)?);
         }
-        Message::CreateWallet => {
-            let secp = secp256k1::Secp256k1::new();
-            let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
-            let wallet = KaspaWallet::with_network(secret_key, state.network.to_str()).unwrap();
-            state.private_key = wallet.get_private_key();
-            state.wallet = Some(KaspaGuiWallet {
-                address: wallet.get_address(),
-                public_key: wallet.get_public_key(),
-                network_name: wallet.get_network_name().to_string(),
-            });
-            state.address_result = wallet.get_address();
-            state.public_key_result = wallet.get_public_key();
-            state.copy_address_text = wallet.get_address();
-            state.copy_public_key_text = format!("kaspa:pk:{}", wallet.get_public_key());
-            state.status_message = "New wallet created! Address generated.".to_string();
+        for index in 0..next[1] + watch_only.gap_limit() {
+            addresses.push(watch_only.change_address(index)?);
         }
-        Message::LoadWallet => match state.load_wallet() {
-            Ok(_) => {
-                if let Some(ref wallet) = state.wallet {
-                    state.address_result = wallet.address.clone();
-                    state.public_key_result = wallet.public_key.clone();
-                    state.copy_address_text = wallet.address.clone();
-                    state.copy_public_key_text = format!("kaspa:pk:{}", wallet.public_key.clone());
-                }
-                state.status_message = "Wallet loaded successfully!".to_string();
-            }
+        Ok(addresses)
+    }
+
+    /// Lists the receive addresses handed out for the shown HD wallet, by
+    /// the address index in the data dir. Index 0, the wallet's own
+    /// address, is always among them.
+    fn load_derived_addresses(&mut self) {
+        self.derived_addresses.clear();
+        let Some(wallet) = &self.wallet else {
+            return;
+        };
+        let Some(xpub) = wallet.account_xpub.clone() else {
+            return;
+        };
+        let derived = AddressIndex::load(&self.data_dir).and_then(|used| {
+            let next = used.next(&wallet.fingerprint, RECEIVE_CHAIN).max(1);
+            derive_receive_addresses(&xpub, &wallet.network_name, 0..next)
+        });
+        match derived {
+            Ok(derived) => self.derived_addresses = derived,
+            Err(e) => self.notify(
+                Severity::Error,
+                format!("Could not list derived addresses: {}", e),
+            ),
+        }
+    }
+
+    /// Reserves the next unused receive index of the shown HD wallet and
+    /// shows its address.
+    fn new_address(&mut self) -> Vec<Command> {
+        let Some(wallet) = &self.wallet else {
+            self.notify(Severity::Warning, "Create or load a wallet first");
+            return Vec::new();
+        };
+        let Some(xpub) = wallet.account_xpub.clone() else {
+            self.notify(
+                Severity::Warning,
+                "This wallet has a single key, so its address never changes",
+            );
+            return Vec::new();
+        };
+        let fingerprint = wallet.fingerprint.clone();
+        let network = wallet.network_name.clone();
+        let derived = AddressIndex::load(&self.data_dir).and_then(|mut used| {
+            // Index 0 is the wallet's own address, already on show.
+            let count = if used.next(&fingerprint, RECEIVE_CHAIN) == 0 {
+                2
+            } else {
+                1
+            };
+            let indexes = used.reserve(&fingerprint, RECEIVE_CHAIN, count)?;
+            let derived = derive_receive_addresses(&xpub, &network, indexes)?;
+            used.save()?;
+            Ok(derived)
+        });
+        let derived = match derived {
+            Ok(derived) => derived,
             Err(e) => {
-                state.status_message = format!("Error loading wallet: {}", e);
+                self.notify(
+                    Severity::Error,
+                    format!("Could not derive an address: {}", e),
+                );
+                return Vec::new();
             }
-        },
-        Message::SendTransaction => {
-            if state.outputs.is_empty() {
-                state.status_message = "No outputs to send".to_string();
-                return;
-            }
-            let parsed_outputs: Result<Vec<(String, u64)>, _> = state
-                .outputs
+        };
+        for address in derived {
+            if !self
+                .derived_addresses
                 .iter()
-                .map(|o| {
-                    let amount_kas: f64 = o.amount.parse().unwrap_or(0.0);
-                    let amount_sompi = (amount_kas * 100_000_000.0) as u64;
-                    Ok::<(String, u64), ()>((o.address.clone(), amount_sompi))
-                })
-                .collect();
-            match parsed_outputs {
-                Ok(outputs) => {
-                    if let Err(e) = state.load_wallet() {
-                        state.status_message = format!("Error: {}", e);
-                        return;
-                    }
-                    let secret_key_bytes = hex::decode(&state.private_key).unwrap();
-                    let secret_key = SecretKey::from_slice(&secret_key_bytes).unwrap();
-                    let wallet =
-                        KaspaWallet::with_network(secret_key, state.network.to_str()).unwrap();
-                    match wallet.create_transaction(vec![], outputs, 1000) {
-                        Ok(tx) => {
-                            let serialized = tx.serialize().unwrap();
-                            state.status_message =
-                                format!("Transaction created: {}", hex::encode(&serialized));
-                        }
-                        Err(e) => {
-                            state.status_message = format!("Transaction error: {}", e);
-                        }
-                    }
-                }
-                Err(_) => {
-                    state.status_message = "Invalid output format".to_string();
-                }
+                .any(|known| known.index == address.index)
+            {
+                self.derived_addresses.push(address);
             }
         }
-        Message::RecipientInput(addr) => {
-            state.recipient = addr;
+        let Some(newest) = self.derived_addresses.last().cloned() else {
+            return Vec::new();
+        };
+        self.address_result = newest.address.clone();
+        self.copy_address_text = newest.address.clone();
+        self.refresh_qr();
+        self.notify_with(
+            Severity::Success,
+            format!("New address #{}: {}", newest.index, newest.address),
+            Payload {
+                what: "Address",
+                value: newest.address,
+            },
+        );
+        self.check_derived_funds()
+    }
+
+    /// Asks the node which derived addresses hold outputs, or does nothing
+    /// without a node URL.
+    fn check_derived_funds(&mut self) -> Vec<Command> {
+        let Some(wallet) = &self.wallet else {
+            return Vec::new();
+        };
+        if self.node_url.trim().is_empty() || self.derived_addresses.is_empty() {
+            return Vec::new();
         }
-        Message::AmountInput(amt) => {
-            state.amount = amt;
+        vec![Command::FetchFundedAddresses {
+            url: self.node_url.trim().to_string(),
+            network: wallet.network_name.clone(),
+            fingerprint: wallet.fingerprint.clone(),
+            addresses: self
+                .derived_addresses
+                .iter()
+                .map(|derived| derived.address.clone())
+                .collect(),
+        }]
+    }
+
+    /// Submits the result panel's transaction in the background; with
+    /// `force` even through a node that is not synced.
+    fn broadcast(&mut self, force: bool) -> Vec<Command> {
+        let Some(panel) = &mut self.result else {
+            return Vec::new();
+        };
+        let url = self.node_url.trim().to_string();
+        if url.is_empty() || self.busy.broadcast {
+            return Vec::new();
         }
-        Message::AddOutput => {
-            if !state.recipient.is_empty() && !state.amount.is_empty() {
-                if validate_address(&state.recipient).unwrap_or(false) {
-                    match state.amount.parse::<f64>() {
-                        Ok(amount_kas) if amount_kas > 0.0 => {
-                            let amount_sompi = (amount_kas * 100_000_000.0) as u64;
-                            state.outputs.push(OutputRow {
-                                address: state.recipient.clone(),
-                                amount: format!("{} KAS ({})", amount_kas, amount_sompi),
-                            });
-                            state.recipient.clear();
-                            state.amount.clear();
-                            state.status_message = format!(
-                                "Added output {} ({} outputs total)",
-                                state.outputs.len() - 1,
-                                state.outputs.len()
-                            );
-                        }
-                        Ok(_) => {
-                            state.status_message = "Amount must be greater than 0".to_string();
-                        }
-                        Err(_) => {
-                            state.status_message =
-                                "Invalid amount format. Use decimal (e.g., 1.5)".to_string();
-                        }
-                    }
-                } else {
-                    state.status_message = "Invalid recipient address".to_string();
-                }
-            } else {
-                state.status_message = "Enter recipient and amount".to_string();
-            }
+        panel.confirm_broadcast = false;
+        self.busy.broadcast = true;
+        vec![Command::Broadcast {
+            url,
+            built: Box::new(panel.built.clone()),
+            force,
+        }]
+    }
+
+    /// Fetches the broadcast transaction's status in the background, or
+    /// does nothing unless one was broadcast from the result panel.
+    fn poll_tx_status(&mut self) -> Vec<Command> {
+        let Some(Ok(txid)) = self
+            .result
+            .as_ref()
+            .and_then(|panel| panel.broadcast.as_ref())
+        else {
+            return Vec::new();
+        };
+        if self.node_url.trim().is_empty() || self.busy.tx_status {
+            return Vec::new();
         }
-        Message::RemoveOutput(idx) => {
-            if idx < state.outputs.len() {
-                state.outputs.remove(idx);
-                state.status_message =
-                    format!("Removed output ({} remaining)", state.outputs.len());
-            }
+        self.busy.tx_status = true;
+        vec![Command::FetchTxStatus {
+            url: self.node_url.trim().to_string(),
+            txid: txid.clone(),
+        }]
+    }
+
+    /// Asks the node, after a short wait, whether the transaction just
+    /// broadcast from the result panel is in its mempool.
+    fn check_propagation(&self) -> Vec<Command> {
+        let Some(Ok(txid)) = self
+            .result
+            .as_ref()
+            .and_then(|panel| panel.broadcast.as_ref())
+        else {
+            return Vec::new();
+        };
+        let url = self.node_url.trim().to_string();
+        if url.is_empty() {
+            return Vec::new();
         }
-        Message::ClearOutputs => {
-            state.outputs.clear();
-            state.status_message = "Outputs cleared".to_string();
-        }
-        Message::GenerateAddress => {
-            let secp = secp256k1::Secp256k1::new();
-            let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
-            let wallet = KaspaWallet::with_network(secret_key, state.network.to_str()).unwrap();
-            state.private_key = wallet.get_private_key();
-            state.wallet = Some(KaspaGuiWallet {
-                address: wallet.get_address(),
-                public_key: wallet.get_public_key(),
-                network_name: wallet.get_network_name().to_string(),
-            });
-            state.address_result = wallet.get_address();
-            state.public_key_result = wallet.get_public_key();
-            state.copy_address_text = wallet.get_address();
-            state.copy_public_key_text = format!("kaspa:pk:{}", wallet.get_public_key());
-            state.status_message = "New wallet generated! Save your private key.".to_string();
+        vec![Command::CheckPropagation {
+            url,
+            txid: txid.clone(),
+        }]
+    }
+
+    /// Rate of each preset: the node's suggestion when it gave one, the
+    /// static default otherwise.
+    fn preset_rates(&self) -> [u64; 3] {
+        match self.suggested_fees {
+            Some(estimate) => estimate.rates(),
+            None => FEE_RATE_PRESETS.map(|(_, rate)| rate),
         }
-        Message::ValidateAddressInput(addr) => {
-            state.validate_address_input = addr.clone();
-            match validate_address(&addr) {
-                Ok(true) => state.validation_result = "Valid Kaspa address".to_string(),
-                Ok(false) => state.validation_result = "Invalid address format".to_string(),
-                Err(e) => state.validation_result = format!("Error: {}", e),
-            }
+    }
+
+    /// The chosen fee rate in sompi per 1000 grams.
+    fn fee_rate(&self) -> Result<u64, String> {
+        match self.fee_choice {
+            FeeChoice::Preset(i) => Ok(self.preset_rates()[i]),
+            FeeChoice::Custom => self
+                .custom_fee_rate
+                .trim()
+                .parse::<u64>()
+                .ok()
+                .filter(|rate| *rate >= MINIMUM_RELAY_FEE_RATE)
+                .ok_or_else(|| {
+                    format!(
+                        "Custom fee rate must be a whole number of sompi per 1000 grams, at least {}",
+                        MINIMUM_RELAY_FEE_RATE
+                    )
+                }),
+        }
+    }
+
+    /// The amount field in `amount_unit`, or `None` while it is empty.
+    fn parsed_amount(&self) -> Option<Result<Kas, String>> {
+        parse_output_amount(&self.amount, self.amount_unit)
+    }
+
+    /// Whether the recipient field holds an address on the selected network,
+    /// and if not, why; `None` while it is empty.
+    fn recipient_check(&self) -> Option<Result<(), String>> {
+        check_recipient(&self.recipient, &self.network)
+    }
+
+    /// The network the recipient's address belongs to, when it is well
+    /// formed but not on the selected one.
+    fn recipient_network(&self) -> Option<NetworkOption> {
+        address_network(self.recipient.trim()).filter(|network| *network != self.network)
+    }
+
+    /// Fills the recipient from pasted text. A payment URI's amount goes
+    /// into the amount field; anything else lands as is, for the inline
+    /// check to judge.
+    fn paste_recipient(&mut self, text: &str) {
+        let text = text.trim();
+        self.recipient_label = None;
+        if !text.contains('?') {
+            self.recipient = text.to_string();
+            return;
         }
-        Message::PasteFromClipboard => {
-            if let Some(text) = get_clipboard_text() {
-                state.validate_address_input = text.clone();
-                match validate_address(&text) {
-                    Ok(true) => state.validation_result = "Valid Kaspa address".to_string(),
-                    Ok(false) => state.validation_result = "Invalid address format".to_string(),
-                    Err(e) => state.validation_result = format!("Error: {}", e),
+        match text.parse::<PaymentUri>() {
+            Ok(uri) => {
+                self.recipient = uri.address;
+                if let Some(amount) = uri.amount {
+                    self.amount_unit = Unit::Kas;
+                    self.amount = amount.to_string();
                 }
-                state.status_message = "Pasted from clipboard!".to_string();
-            } else {
-                state.status_message = "Could not access clipboard".to_string();
             }
-        }
-        Message::CopyAddress => {
-            if state.copy_address_text.is_empty() {
-                state.status_message = "No address to copy".to_string();
-            } else if set_clipboard_text(&state.copy_address_text) {
-                state.status_message = "Address copied to clipboard!".to_string();
-            } else {
-                state.status_message = "Copy failed".to_string();
+            Err(e) => {
+                self.recipient = text.to_string();
+                self.notify(Severity::Error, describe(e));
             }
         }
-        Message::CopyPublicKey => {
-            if state.copy_public_key_text.is_empty() {
-                state.status_message = "No public key to copy".to_string();
-            } else {
-                if set_clipboard_text(&state.copy_public_key_text) {
-                    state.status_message = "Public key copied to clipboard!".to_string();
-                } else {
-                    state.status_message = "Copy failed".to_string();
-                }
+    }
+
+    /// Most the amount field can hold: what the inputs hold (the picked
+    /// ones, or every spendable output) less the queued outputs and the fee
+    /// with no change.
+    fn max_amount(&self) -> Result<Kas, String> {
+        let fee_rate = self.fee_rate()?;
+        let inputs: Vec<u64> = match &self.balance {
+            Some(balance) => balance
+                .utxos
+                .iter()
+                .filter(|utxo| {
+                    if self.selected_inputs.is_empty() {
+                        utxo.is_mature(balance.virtual_daa_score)
+                    } else {
+                        self.selected_inputs.contains(&utxo.outpoint())
+                    }
+                })
+                .map(|utxo| utxo.amount)
+                .collect(),
+            None if !self.manual_inputs.is_empty() => {
+                self.manual_inputs.iter().map(|utxo| utxo.amount).collect()
             }
+            None => return Err(self.no_inputs_reason().to_string()),
+        };
+        let outputs: Vec<u64> = self
+            .outputs
+            .iter()
+            .map(|output| output.amount.sompi())
+            .collect();
+        let max = max_sendable(&inputs, &outputs, fee_rate)
+            .ok_or("Nothing is left once the queued outputs and the fee are paid")?;
+        if max < DUST_THRESHOLD {
+            return Err(format!(
+                "Only {} KAS is left after the fee, below the {} KAS dust limit",
+                Kas::from_sompi(max),
+                Kas::from_sompi(DUST_THRESHOLD)
+            ));
         }
-        Message::TabSelected(tab) => {
-            state.current_tab = tab;
-        }
+        Ok(Kas::from_sompi(max))
     }
-}
 
-fn view(state: &WalletGui) -> Element<Message> {
-    let networks = vec![
-        NetworkOption::Mainnet,
-        NetworkOption::Testnet10,
-        NetworkOption::Testnet11,
-        NetworkOption::Simnet,
-    ];
+    /// Refills the amount field while it follows Max, after the fee,
+    /// inputs or outputs changed.
+    fn refresh_max(&mut self) {
+        if !self.send_max {
+            return;
+        }
+        if let Ok(max) = self.max_amount() {
+            self.amount = amount_text(max, self.amount_unit);
+        }
+    }
 
-    let wallet_info = if let Some(wallet) = &state.wallet {
-        let pk_with_prefix = format!("kaspa:pk:{}", &wallet.public_key);
-        let pk_display = pk_with_prefix.clone();
-        column![
-            text("Wallet Information").size(20),
-            text("Address:").size(14),
-            row![
-                text(&wallet.address).size(14).width(Length::Fill),
-                button("Copy").on_press(Message::CopyAddress),
-            ],
-            text("Network:").size(14),
-            text(&wallet.network_name).size(14),
-            text("Public Key:").size(14),
-            row![
-                text(pk_display).size(12).width(Length::Fill),
-                button("Copy").on_press(Message::CopyPublicKey),
-            ],
-        ]
-    } else {
-        column![
-            text("No wallet loaded").size(20),
-            text("Create a new wallet or load an existing one below").size(14),
-        ]
-    };
+    /// Replaces the queued outputs with `outputs`, remembering the old list
+    /// for Undo. The change goes to the Activity drawer; the Undo toast
+    /// stands in for its own toast.
+    fn change_outputs(&mut self, outputs: Vec<OutputRow>, what: String) {
+        let now = self.now();
+        self.output_undo.record(&self.outputs, what.clone(), now);
+        self.outputs = outputs;
+        self.output_edit = None;
+        self.refresh_max();
+        let id = self.notifications.push(Severity::Info, what, None, now);
+        self.notifications.dismiss(id);
+    }
 
-    let settings_info = column![
-        text("Wallet Settings").size(20),
-        text("Network:").size(14),
-        pick_list(
-            networks,
-            Some(state.network.clone()),
-            Message::NetworkSelected
-        ),
-        text("Private Key:").size(14),
-        text_input("Enter private key (hex)", &state.private_key)
-            .on_input(Message::PrivateKeyInput),
-        row![
-            button("Load Wallet").on_press(Message::LoadWallet),
-            button("Create New Wallet").on_press(Message::CreateWallet),
-        ]
-        .spacing(10),
-        text("Warning: Never share your private key!").size(12),
-    ];
+    /// Both fields are valid, so Add can take them.
+    fn can_add_output(&self) -> bool {
+        matches!(self.recipient_check(), Some(Ok(())))
+            && matches!(self.parsed_amount(), Some(Ok(_)))
+    }
 
-    let combined_section = column![wallet_info, text("---").size(12), settings_info,].spacing(15);
+    /// Fee in sompi and mass in grams at `rate` for the outputs so far plus
+    /// change, spending `input_count` inputs (at least one).
+    fn estimated_fee(&self, rate: u64, input_count: usize) -> (u64, u64) {
+        let mut tx = Transaction::new();
+        for _ in 0..input_count.max(1) {
+            tx.add_input(String::new(), 0);
+        }
+        for output in &self.outputs {
+            tx.add_output(output.address.clone(), 0);
+        }
+        tx.add_output(String::new(), 0);
+        let breakdown = tx.mass_breakdown();
+        (breakdown.fee(rate), breakdown.total())
+    }
 
-    let tab_row = row![
-        button("Send")
-            .on_press(Message::TabSelected(Tab::Send))
-            .style(if state.current_tab == Tab::Send {
-                button::primary
-            } else {
-                button::secondary
-            }),
-        button("Receive")
-            .on_press(Message::TabSelected(Tab::Receive))
-            .style(if state.current_tab == Tab::Receive {
-                button::primary
-            } else {
-                button::secondary
-            }),
-    ]
-    .spacing(10);
+    /// Sum of the queued outputs in sompi.
+    fn total_out(&self) -> Result<u64, String> {
+        self.outputs
+            .iter()
+            .try_fold(0u64, |total, output| {
+                total.checked_add(output.amount.sompi())
+            })
+            .ok_or_else(|| "Total amount is too large".to_string())
+    }
 
-    let content: Column<Message> = match state.current_tab {
-        Tab::Send => view_send(state),
-        Tab::Receive => view_receive(state),
-    };
+    /// The hand-picked inputs' total and what they need to cover: the
+    /// outputs plus the fee for that many inputs.
+    fn selected_total(&self) -> Option<(u64, u64)> {
+        let balance = self.balance.as_ref()?;
+        if self.selected_inputs.is_empty() {
+            return None;
+        }
+        let selected = balance
+            .utxos
+            .iter()
+            .filter(|utxo| self.selected_inputs.contains(&utxo.outpoint()))
+            .map(|utxo| utxo.amount)
+            .sum();
+        let fee = self
+            .fee_rate()
+            .map(|rate| self.estimated_fee(rate, self.selected_inputs.len()).0)
+            .unwrap_or(0);
+        let required = self.total_out().unwrap_or(u64::MAX).saturating_add(fee);
+        Some((selected, required))
+    }
 
-    let status_bar = if !state.status_message.is_empty() {
-        text(&state.status_message).size(14)
-    } else {
-        text("")
-    };
+    /// Why Send has nothing to spend yet.
+    fn no_inputs_reason(&self) -> &'static str {
+        if self.node_url.trim().is_empty() {
+            "No node set: add the outputs to spend under Inputs"
+        } else {
+            "Fetch the balance first; its outputs fund the transaction"
+        }
+    }
 
-    Container::new(
-        column![
-            text("KaspaRustWallet").size(24),
-            combined_section,
-            tab_row,
-            content,
-            status_bar,
-        ]
-        .spacing(20)
-        .padding(20),
-    )
-    .width(Length::Fill)
-    .height(Length::Fill)
-    .center_x(Length::Fill)
-    .center_y(Length::Fill)
-    .into()
-}
+    /// Whether Send has outputs to spend, fetched or entered by hand.
+    fn has_inputs(&self) -> bool {
+        self.balance.is_some() || !self.manual_inputs.is_empty()
+    }
 
-fn view_send(state: &WalletGui) -> Column<Message> {
-    let outputs_list: Column<Message> = if state.outputs.is_empty() {
-        column![text("No outputs added yet")]
-    } else {
-        state
-            .outputs
+    /// Inputs funding the outputs: exactly the hand-picked ones, the
+    /// largest spendable outputs when none are picked, or without a
+    /// balance every output entered by hand.
+    fn select_inputs(&self, fee_rate: u64) -> Result<Vec<Utxo>, String> {
+        let total_out = self.total_out()?;
+        let Some(balance) = &self.balance else {
+            if self.manual_inputs.is_empty() {
+                return Err(self.no_inputs_reason().to_string());
+            }
+            return self.covering(self.manual_inputs.clone(), total_out, fee_rate, "Entered");
+        };
+        if self.selected_inputs.is_empty() {
+            let spendable: UtxoSet = balance
+                .utxos
+                .iter()
+                .filter(|utxo| utxo.is_mature(balance.virtual_daa_score))
+                .cloned()
+                .collect();
+            return spendable
+                .select_largest_first(total_out, |count| self.estimated_fee(fee_rate, count).0)
+                .map_err(|e| e.to_string());
+        }
+        let inputs: Vec<Utxo> = balance
+            .utxos
             .iter()
-            .enumerate()
-            .fold(column![], |col, (idx, output)| {
-                col.push(
-                    row![
-                        text(format!("{}: {}", idx, output.address)),
-                        text(&output.amount),
-                        button("Remove").on_press(Message::RemoveOutput(idx)),
-                    ]
-                    .spacing(10),
-                )
-            })
-    };
+            .filter(|utxo| self.selected_inputs.contains(&utxo.outpoint()))
+            .cloned()
+            .collect();
+        self.covering(inputs, total_out, fee_rate, "Selected")
+    }
 
-    column![
-        text("Send Transaction").size(20),
-        text("Add recipients:").size(14),
-        row![
-            text_input("Recipient address", &state.recipient)
-                .on_input(Message::RecipientInput)
-                .width(Length::Fill),
-            text_input("Amount (KAS)", &state.amount)
-                .on_input(Message::AmountInput)
-                .width(Length::Fill),
-            button("Add").on_press(Message::AddOutput),
-        ]
-        .spacing(10),
-        outputs_list,
-        row![
-            button("Send Transaction").on_press(Message::SendTransaction),
-            button("Clear All").on_press(Message::ClearOutputs),
-        ]
-        .spacing(10),
-        text("Note: Amount is in KAS. 1 KAS = 100,000,000 sompi").size(12),
-    ]
-}
+    /// `inputs` when they cover `total_out` plus the fee for spending all
+    /// of them.
+    fn covering(
+        &self,
+        inputs: Vec<Utxo>,
+        total_out: u64,
+        fee_rate: u64,
+        kind: &str,
+    ) -> Result<Vec<Utxo>, String> {
+        let total_in: u64 = inputs.iter().map(|utxo| utxo.amount).sum();
+        let required = total_out.saturating_add(self.estimated_fee(fee_rate, inputs.len()).0);
+        if total_in < required {
+            return Err(format!(
+                "{} inputs hold {} KAS; {} KAS required",
+                kind,
+                Kas::from_sompi(total_in),
+                Kas::from_sompi(required)
+            ));
+        }
+        Ok(inputs)
+    }
 
-fn view_receive(state: &WalletGui) -> Column<Message> {
-    let current_address = if !state.address_result.is_empty() {
-        state.address_result.clone()
-    } else {
-        state
+    /// Parses `manual_input` and queues it as an output to spend.
+    fn add_manual_input(&mut self) -> Result<(), String> {
+        let entry: UtxoEntry = self
+            .manual_input
+            .trim()
+            .parse()
+            .map_err(|e: WalletError| e.to_string())?;
+        let amount = entry
+            .amount
+            .ok_or("Give the amount too: txid:vout:amount")?;
+        if self
+            .manual_inputs
+            .iter()
+            .any(|utxo| utxo.txid == entry.txid && utxo.vout == entry.vout)
+        {
+            return Err(format!("{}:{} is already added", entry.txid, entry.vout));
+        }
+        let address = self
             .wallet
             .as_ref()
-            .map(|w| w.address.clone())
-            .unwrap_or_default()
-    };
+            .map(|wallet| wallet.address.clone())
+            .unwrap_or_default();
+        self.manual_inputs.push(Utxo {
+            txid: entry.txid,
+            vout: entry.vout,
+            address,
+            amount,
+            block_daa_score: 0,
+            is_coinbase: false,
+        });
+        self.manual_input.clear();
+        Ok(())
+    }
 
-    let current_public_key = if !state.public_key_result.is_empty() {
-        state.public_key_result.clone()
-    } else {
-        state
+    /// "~0.00002 KAS, 3,120 grams" for the outputs so far.
+    fn fee_estimate(&self) -> Result<String, String> {
+        let input_count = if self.balance.is_none() {
+            self.manual_inputs.len()
+        } else {
+            self.selected_inputs.len()
+        };
+        let (fee, mass) = self.estimated_fee(self.fee_rate()?, input_count);
+        Ok(format!(
+            "~{} KAS, {} grams",
+            Kas::from_sompi(fee),
+            thousands(mass)
+        ))
+    }
+
+    /// Totals the queued outputs for the Send confirmation.
+    fn send_summary(&self) -> Result<SendSummary, String> {
+        if self.outputs.is_empty() {
+            return Err("No outputs to send".to_string());
+        }
+        let wallet = self
             .wallet
             .as_ref()
-            .map(|w| w.public_key.clone())
-            .unwrap_or_default()
-    };
+            .ok_or("Load or create a wallet first")?;
+        let fee_rate = self.fee_rate()?;
+        let inputs = self.select_inputs(fee_rate)?;
+        let fee = self.estimated_fee(fee_rate, inputs.len()).0;
+        let total = self.total_out()? + fee;
+        let total_in: u64 = inputs.iter().map(|utxo| utxo.amount).sum();
+        let change = Some(Kas::from_sompi(total_in - total)).filter(|&change| change != Kas::ZERO);
+        Ok(SendSummary {
+            network: wallet.network_name.clone(),
+            recipients: self
+                .outputs
+                .iter()
+                .map(|output| (output.address.clone(), output.amount))
+                .collect(),
+            inputs,
+            fee_rate,
+            fee: Kas::from_sompi(fee),
+            change,
+            change_address: wallet.address.clone(),
+            total: Kas::from_sompi(total),
+        })
+    }
 
-    let address_section = if !current_address.is_empty() {
-        let addr = current_address.clone();
-        column![
-            text("Address:").size(14),
-            row![
-                text(addr).size(14).width(Length::Fill),
-                button("Copy").on_press(Message::CopyAddress),
-            ],
-        ]
-    } else {
-        column![text("No address available. Create or load a wallet first.").size(14)]
-    };
+    /// Fetches the first history page, or with `more` the next older one.
+    fn load_history(&mut self, more: bool, refresh: bool) -> Vec<Command> {
+        let Some(wallet) = &self.wallet else {
+            return Vec::new();
+        };
+        if self.explorer_url.trim().is_empty() || self.busy.history {
+            return Vec::new();
+        }
+        let before = if more {
+            self.history.last().map(|entry| entry.time)
+        } else {
+            None
+        };
+        self.busy.history = true;
+        vec![Command::FetchHistory {
+            url: self.explorer_url.trim().to_string(),
+            address: wallet.address.clone(),
+            before,
+            refresh,
+        }]
+    }
 
-    let pk_section = if !current_public_key.is_empty() {
-        let _pk = current_public_key.clone();
-        let pk_with_prefix = format!("kaspa:pk:{}", current_public_key);
-        column![
-            text("Public Key:").size(14),
-            row![
-                text(pk_with_prefix).size(12).width(Length::Fill),
-                button("Copy").on_press(Message::CopyPublicKey),
-            ],
-        ]
-    } else {
-        column![]
-    };
+    fn history_fetched(
+        &mut self,
+        address: String,
+        before: Option<u64>,
+        result: Result<Vec<HistoryEntry>, String>,
+    ) {
+        // A page for a wallet that has since been replaced.
+        if self.wallet.as_ref().map(|wallet| &wallet.address) != Some(&address) {
+            return;
+        }
+        self.busy.history = false;
+        match result {
+            Ok(entries) => {
+                self.history_complete = entries.len() < HISTORY_PAGE;
+                if before.is_none() {
+                    self.history.clear();
+                }
+                self.history.extend(entries);
+                self.history_loaded = true;
+                self.history_error = None;
+            }
+            Err(e) => self.history_error = Some(e),
+        }
+    }
 
-    column![
-        text("Receive").size(20),
-        button("Generate New Address").on_press(Message::GenerateAddress),
-        address_section,
-        pk_section,
-        text("Validate Address:").size(14),
-        row![
-            text_input(
-                "Paste address here to validate",
-                &state.validate_address_input
-            )
-            .on_input(Message::ValidateAddressInput)
-            .width(Length::Fill),
-            button("Paste").on_press(Message::PasteFromClipboard),
-        ],
-        if !state.validation_result.is_empty() {
-            text(&state.validation_result).size(14)
+    /// What the receive QR code encodes: the address, or a payment URI
+    /// when an amount is given.
+    fn receive_uri(&self) -> Result<Option<String>, WalletError> {
+        let Some(wallet) = &self.wallet else {
+            return Ok(None);
+        };
+        let address = match self.address_result.as_str() {
+            "" => wallet.address.clone(),
+            address => address.to_string(),
+        };
+        let amount = match self.request_amount.trim() {
+            "" => None,
+            amount => Some(amount.parse()?),
+        };
+        Ok(Some(
+            PaymentUri {
+                address,
+                amount,
+                label: None,
+                message: None,
+            }
+            .to_string(),
+        ))
+    }
+
+    /// Re-renders the receive QR code after the address or amount changed.
+    fn refresh_qr(&mut self) {
+        let png = self
+            .receive_uri()
+            .and_then(|uri| uri.map(|uri| qr::png(&uri)).transpose());
+        match png {
+            Ok(png) => {
+                self.qr_image = png.clone().map(image::Handle::from_bytes);
+                self.qr_png = png;
+                self.qr_error = None;
+            }
+            // Keep the last code; it still encodes the bare address or an
+            // earlier amount, which the error message makes clear.
+            Err(e) => self.qr_error = Some(e.to_string()),
+        }
+    }
+
+    /// Shows a wallet once its task completes.
+    fn wallet_loaded(&mut self, result: Result<LoadedWallet, String>) -> Vec<Command> {
+        self.busy.wallet = false;
+        let mut loaded = match result {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                self.notify(Severity::Error, format!("Error loading wallet: {}", e));
+                return Vec::new();
+            }
+        };
+        if let Some(phrase) = loaded.phrase.take() {
+            self.screen = Screen::Backup(Box::new(Backup {
+                pending: loaded,
+                phrase,
+                step: BackupStep::Words,
+                hovered: None,
+                positions: Vec::new(),
+                answers: Vec::new(),
+                error: None,
+            }));
+            return Vec::new();
+        }
+        let created = loaded.created;
+        let address = loaded.wallet.address.clone();
+        let commands = self.use_wallet(loaded);
+        if created {
+            self.notify(
+                Severity::Success,
+                format!("New wallet created: {}. Save your private key.", address),
+            );
         } else {
-            text("")
-        },
-    ]
+            self.notify(Severity::Success, format!("Wallet loaded: {}", address));
+        }
+        commands
+    }
+
+    /// Leaves the backup screen, dropping the phrase, and makes its wallet
+    /// the current one.
+    fn finish_backup(&mut self, confirmed: bool) -> Vec<Command> {
+        let Screen::Backup(backup) = std::mem::replace(&mut self.screen, Screen::Wallet) else {
+            return Vec::new();
+        };
+        let commands = self.use_wallet(backup.pending);
+        if confirmed {
+            self.notify(
+                Severity::Success,
+                "Seed phrase confirmed. Your wallet is ready.",
+            );
+        } else {
+            self.notify(
+                Severity::Warning,
+                "Backup check skipped. Without the seed phrase, a lost key cannot be recovered.",
+            );
+        }
+        commands
+    }
+
+    /// Makes `loaded` the current wallet, starting the fetches it needs.
+    /// The wallet shown so far stays open in the switcher, unless `loaded`
+    /// is the same wallet again, say on another network.
+    fn use_wallet(&mut self, loaded: LoadedWallet) -> Vec<Command> {
+        let mut wallet = loaded.wallet;
+        let fingerprint = wallet.fingerprint.clone();
+        // Loaded again, most likely on another network: it stays from
+        // where it was first loaded.
+        let earlier = match &self.wallet {
+            Some(shown) if shown.fingerprint == fingerprint => Some(shown),
+            _ => self
+                .open_wallets
+                .get(&fingerprint)
+                .map(|parked| &parked.wallet),
+        };
+        if let Some(earlier) = earlier {
+            wallet.home_network = earlier.home_network.clone();
+        }
+        if self.wallet.as_ref().map(|wallet| &wallet.fingerprint) != Some(&fingerprint) {
+            self.park_wallet();
+        }
+        self.open_wallets.remove(&fingerprint);
+        self.show_wallet(ParkedWallet::new(loaded.private_key, loaded.secret, wallet));
+        let history = match self.current_tab {
+            Tab::History => self.load_history(false, false),
+            _ => Vec::new(),
+        };
+        if loaded.created {
+            history
+        } else {
+            [self.refresh_balance(), history]
+                .into_iter()
+                .flatten()
+                .collect()
+        }
+    }
+
+    /// Moves the shown wallet into `open_wallets`, leaving none shown.
+    fn park_wallet(&mut self) {
+        let Some(wallet) = self.wallet.take() else {
+            return;
+        };
+        let parked = ParkedWallet {
+            private_key: std::mem::take(&mut self.wallet_key),
+            secret: std::mem::take(&mut self.wallet_secret),
+            wallet,
+            file: self.wallet_file.take(),
+            balance: self.balance.take(),
+            balance_error: self.balance_error.take(),
+            selected_inputs: std::mem::take(&mut self.selected_inputs),
+            history: std::mem::take(&mut self.history),
+            history_loaded: std::mem::take(&mut self.history_loaded),
+            history_complete: std::mem::take(&mut self.history_complete),
+            history_error: self.history_error.take(),
+            derived_addresses: std::mem::take(&mut self.derived_addresses),
+        };
+        self.open_wallets
+            .insert(parked.wallet.fingerprint.clone(), parked);
+        self.clear_wallet_view();
+    }
+
+    /// Shows `parked`, switching to its network.
+    fn show_wallet(&mut self, parked: ParkedWallet) {
+        if let Some(network) = NetworkOption::from_name(&parked.wallet.network_name) {
+            if network != self.network {
+                self.network = network;
+                self.reload_contacts();
+            }
+        }
+        self.private_key = parked.private_key.clone();
+        self.wallet_key = parked.private_key;
+        self.wallet_secret = parked.secret;
+        self.address_result = parked.wallet.address.clone();
+        self.copy_address_text = parked.wallet.address.clone();
+        self.wallet = Some(parked.wallet);
+        self.wallet_file = parked.file;
+        self.balance = parked.balance;
+        self.balance_error = parked.balance_error;
+        self.selected_inputs = parked.selected_inputs;
+        self.history = parked.history;
+        self.history_loaded = parked.history_loaded;
+        self.history_complete = parked.history_complete;
+        self.history_error = parked.history_error;
+        self.history_expanded = None;
+        self.derived_addresses = parked.derived_addresses;
+        if self.derived_addresses.is_empty() {
+            self.load_derived_addresses();
+        }
+        // Fetches still running belong to the wallet they were started for.
+        self.busy.balance = false;
+        self.busy.history = false;
+        // A wallet parked while the window locked comes back locked.
+        if self.wallet_key.is_empty() {
+            self.screen = Screen::Locked(LockScreen::default());
+        } else if matches!(self.screen, Screen::Locked(_)) {
+            self.screen = Screen::Wallet;
+        }
+        self.refresh_qr();
+        self.refresh_max();
+    }
+
+    /// Empties what is derived from the shown wallet.
+    fn clear_wallet_view(&mut self) {
+        self.private_key = Redacted::default();
+        self.show_private_key = false;
+        self.address_result.clear();
+        self.copy_address_text.clear();
+        self.derived_addresses.clear();
+        self.watched.clear();
+        self.history_expanded = None;
+        self.manual_input.clear();
+        self.manual_inputs.clear();
+        if matches!(self.screen, Screen::Locked(_)) {
+            self.screen = Screen::Wallet;
+        }
+        self.refresh_qr();
+    }
+
+    /// When auto-lock is due: the configured minutes after the last input,
+    /// or `None` while it is off or there is nothing to lock.
+    fn auto_lock_at(&self) -> Option<u64> {
+        let minutes = self.settings.auto_lock_minutes;
+        if minutes == 0 || self.wallet.is_none() || !matches!(self.screen, Screen::Wallet) {
+            return None;
+        }
+        Some(self.last_interaction + u64::from(minutes) * 60_000)
+    }
+
+    /// Drops every key the window holds, open wallets' included, along
+    /// with the key field and any key left on the clipboard, and shows the
+    /// unlock screen. Does nothing during a seed phrase backup, which would
+    /// lose the phrase.
+    fn lock(&mut self) -> Vec<Command> {
+        if self.wallet.is_none() || !matches!(self.screen, Screen::Wallet) {
+            return Vec::new();
+        }
+        self.wallet_key = Redacted::default();
+        self.wallet_secret = Redacted::default();
+        self.private_key = Redacted::default();
+        self.show_private_key = false;
+        for parked in self.open_wallets.values_mut() {
+            parked.private_key = Redacted::default();
+            parked.secret = Redacted::default();
+        }
+        self.prompt = None;
+        self.key_copy = None;
+        self.screen = Screen::Locked(LockScreen::default());
+        self.notify(Severity::Info, "Wallet locked");
+        self.clear_clipboard()
+    }
+
+    /// Takes the keys back after an unlock and carries out what was waiting
+    /// for them.
+    fn unlocked(&mut self, address: String, result: Result<LoadedWallet, String>) -> Vec<Command> {
+        self.busy.wallet = false;
+        // The wallet may have been closed while its file was decrypting.
+        if self.wallet.as_ref().map(|wallet| &wallet.address) != Some(&address) {
+            return Vec::new();
+        }
+        let Screen::Locked(lock) = &mut self.screen else {
+            return Vec::new();
+        };
+        let loaded = match result {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                lock.error = Some(e);
+                return Vec::new();
+            }
+        };
+        let resume = lock.resume.take();
+        self.screen = Screen::Wallet;
+        self.private_key = loaded.private_key.clone();
+        self.wallet_key = loaded.private_key;
+        self.wallet_secret = loaded.secret;
+        self.last_interaction = self.now();
+        match resume {
+            Some(message) => update(self, *message),
+            None => Vec::new(),
+        }
+    }
+
+    /// Clears the copied key from the clipboard now, if it is still there.
+    fn clear_clipboard(&mut self) -> Vec<Command> {
+        match self.pending_clear.take() {
+            Some(pending) => vec![Command::ClearClipboard(pending.text)],
+            None => Vec::new(),
+        }
+    }
+
+    /// Tracks the part of the Send tab in use, which decides what
+    /// Ctrl+Enter does.
+    fn follow_send_focus(&mut self, message: &Message) {
+        self.send_focus = match message {
+            Message::RecipientInput(_)
+            | Message::AmountInput(_)
+            | Message::AmountUnitSelected(_)
+            | Message::SendMax
+            | Message::ContactPicked(_)
+            | Message::ClipboardRead(PasteTarget::Recipient, _)
+            | Message::AddOutput => SendFocus::Fields,
+            Message::EditOutput(_)
+            | Message::EditOutputAddress(_)
+            | Message::EditOutputAmount(_)
+            | Message::SaveOutputEdit
+            | Message::CancelOutputEdit
+            | Message::RemoveOutput(_)
+            | Message::UndoOutputs => SendFocus::Outputs,
+            Message::TabSelected(_) | Message::ClearOutputs => SendFocus::None,
+            _ => return,
+        };
+    }
+
+    /// What is in front, for the keyboard shortcuts.
+    /// Asks for the password to `keystore`, read from `path`.
+    fn prompt_open(&mut self, keystore: Keystore, path: PathBuf) {
+        self.prompt = Some(PasswordPrompt {
+            action: FileAction::Open(Box::new(keystore)),
+            path,
+            password: Redacted::default(),
+            confirm: Redacted::default(),
+            error: None,
+        });
+    }
+
+    /// The network the shown wallet was first loaded on, when that is not
+    /// the one selected now.
+    fn network_mismatch(&self) -> Option<String> {
+        let home = &self.wallet.as_ref()?.home_network;
+        (home != self.network.to_str()).then(|| {
+            NetworkOption::from_name(home)
+                .map(|network| network.to_string())
+                .unwrap_or_else(|| home.clone())
+        })
+    }
+
+    /// What Export… writes without the key: the descriptor, the wallet's
+    /// name and every address it has handed out.
+    fn wallet_info(&self) -> Option<WalletInfo> {
+        let wallet = self.wallet.as_ref()?;
+        let mut addresses = vec![wallet.address.clone()];
+        for derived in &self.derived_addresses {
+            if !addresses.contains(&derived.address) {
+                addresses.push(derived.address.clone());
+            }
+        }
+        Some(WalletInfo {
+            descriptor: wallet.descriptor.clone(),
+            name: self
+                .wallet_file
+                .as_ref()
+                .map_or_else(|| "Unsaved".to_string(), WalletFile::name),
+            fingerprint: wallet.fingerprint.clone(),
+            addresses,
+        })
+    }
+
+    fn shortcut_context(&self) -> shortcuts::Context {
+        let dialog = self.confirm_send.is_some()
+            || self.key_copy.is_some()
+            || self.confirm_key_export
+            || self.result.is_some()
+            || self.decoded.is_some()
+            || self.show_shortcuts;
+        let modal = match &self.screen {
+            Screen::Locked(_) | Screen::Backup(_) => Modal::Typing { dismissable: false },
+            Screen::Wallet if self.prompt.is_some() => Modal::Typing { dismissable: true },
+            Screen::Wallet if dialog => Modal::Dialog,
+            Screen::Wallet => Modal::None,
+        };
+        shortcuts::Context {
+            modal,
+            send_tab: self.current_tab == Tab::Send,
+            focus: self.send_focus,
+        }
+    }
+
+    /// Shows the open wallet with `fingerprint`, fetching only what it has
+    /// not fetched before.
+    fn switch_wallet(&mut self, fingerprint: &str) -> Vec<Command> {
+        let Some(parked) = self.open_wallets.remove(fingerprint) else {
+            return Vec::new();
+        };
+        self.park_wallet();
+        let fetched = parked.balance.is_some();
+        self.show_wallet(parked);
+        let history = if self.current_tab == Tab::History && !self.history_loaded {
+            self.load_history(false, false)
+        } else {
+            Vec::new()
+        };
+        if fetched {
+            history
+        } else {
+            [self.refresh_balance(), history]
+                .into_iter()
+                .flatten()
+                .collect()
+        }
+    }
+
+    /// Entries for the wallet switcher, by label.
+    fn wallet_choices(&self) -> Vec<WalletChoice> {
+        let shown = self
+            .wallet
+            .as_ref()
+            .map(|wallet| (wallet, self.wallet_file.as_ref()));
+        let parked = self
+            .open_wallets
+            .values()
+            .map(|parked| (&parked.wallet, parked.file.as_ref()));
+        let mut choices: Vec<WalletChoice> = shown
+            .into_iter()
+            .chain(parked)
+            .map(|(wallet, file)| WalletChoice::Open {
+                fingerprint: wallet.fingerprint.clone(),
+                label: wallet_label(wallet, file),
+            })
+            .collect();
+        choices.sort_by_key(|choice| choice.to_string());
+        choices.push(WalletChoice::Add);
+        choices
+    }
+
+    fn shown_choice(&self) -> Option<WalletChoice> {
+        self.wallet.as_ref().map(|wallet| WalletChoice::Open {
+            fingerprint: wallet.fingerprint.clone(),
+            label: wallet_label(wallet, self.wallet_file.as_ref()),
+        })
+    }
+}
+
+impl From<&KaspaWallet> for KaspaGuiWallet {
+    fn from(wallet: &KaspaWallet) -> Self {
+        Self {
+            address: wallet.get_address(),
+            public_key: wallet.get_public_key(),
+            network_name: wallet.get_network_name().to_string(),
+            home_network: wallet.get_network_name().to_string(),
+            fingerprint: wallet.fingerprint(),
+            account_xpub: wallet.account_xpub().map(|xpub| xpub.to_string()),
+            derivation_path: wallet.derivation_path().map(|path| path.to_string()),
+            descriptor: wallet.export_descriptor(),
+        }
+    }
+}
+
+/// An output amount typed in `unit`, or `None` while it is empty.
+fn parse_output_amount(amount: &str, unit: Unit) -> Option<Result<Kas, String>> {
+    if amount.trim().is_empty() {
+        return None;
+    }
+    let parsed = match Kas::parse_with_unit(amount, Some(unit)) {
+        Ok(amount) if amount == Kas::ZERO => Err("Amount must be greater than 0".to_string()),
+        Ok(amount) if amount.sompi() < DUST_THRESHOLD => Err(format!(
+            "Below the {} KAS dust limit; nodes will not relay it",
+            Kas::from_sompi(DUST_THRESHOLD)
+        )),
+        Ok(amount) => Ok(amount),
+        Err(WalletError::InvalidParameters(reason)) => Err(reason),
+        Err(e) => Err(e.to_string()),
+    };
+    Some(parsed)
+}
+
+/// Whether `recipient` is an address on `network`, and if not, why; `None`
+/// while it is empty.
+fn check_recipient(recipient: &str, network: &NetworkOption) -> Option<Result<(), String>> {
+    let recipient = recipient.trim();
+    if recipient.is_empty() {
+        return None;
+    }
+    let config = NetworkConfig::from_name(network.to_str()).ok()?;
+    let check = match check_address_format(recipient) {
+        Err(e) => Err(format!("Invalid address: {}", e)),
+        Ok(()) if !recipient.starts_with(&format!("{}:", config.prefix)) => {
+            Err(match address_network(recipient) {
+                Some(other) => format!("A valid {} address, but {} is selected", other, network),
+                None => format!(
+                    "Not a {} address (expected the {}: prefix)",
+                    network, config.prefix
+                ),
+            })
+        }
+        Ok(()) => Ok(()),
+    };
+    Some(check)
+}
+
+/// The network a well-formed `address` belongs to, going by its prefix.
+fn address_network(address: &str) -> Option<NetworkOption> {
+    let networks = NetworkConfig::all();
+    network::detect_network(address, &networks)
+        .and_then(|config| NetworkOption::from_name(&config.name))
+}
+
+/// `amount` as typed in `unit`.
+fn amount_text(amount: Kas, unit: Unit) -> String {
+    match unit {
+        Unit::Kas => amount.to_string(),
+        Unit::Sompi => amount.sompi().to_string(),
+    }
+}
+
+/// Parses the private key field, saying what is wrong with it precisely
+/// enough to fix as it is typed. Surrounding whitespace is ignored.
+fn parse_key_field(private_key: &str) -> Result<SecretKey, WalletError> {
+    let invalid = |reason: String| Err(WalletError::InvalidParameters(reason));
+    let key = private_key.trim();
+    if key.is_empty() {
+        return invalid("Enter a private key first".to_string());
+    }
+    if mnemonic::looks_like_phrase(key) {
+        return invalid(
+            "This looks like a seed phrase, not a private key. Restore it with \
+             `kasparustwallet restore` instead"
+                .to_string(),
+        );
+    }
+    if let Some((position, _)) = key
+        .chars()
+        .enumerate()
+        .find(|(_, c)| !c.is_ascii_hexdigit())
+    {
+        return invalid(format!(
+            "Character {} is not hexadecimal (0-9, a-f)",
+            position + 1
+        ));
+    }
+    if key.len() % 2 == 1 {
+        return invalid(format!(
+            "Odd number of hex characters ({}); one is missing or extra",
+            key.len()
+        ));
+    }
+    if key.len() != 64 {
+        return invalid(format!(
+            "The private key must be 64 hex characters, not {}",
+            key.len()
+        ));
+    }
+    key_source::parse_private_key(key).or_else(|_| {
+        invalid("The private key is out of range for secp256k1 (zero or too large)".to_string())
+    })
+}
+
+/// What to say under the private key field: nothing while it is empty,
+/// else whether it parses and, if not, why.
+fn key_feedback(private_key: &str) -> Option<Result<(), String>> {
+    if private_key.trim().is_empty() {
+        return None;
+    }
+    Some(parse_key_field(private_key).map(|_| ()).map_err(describe))
+}
+
+/// `e` as the GUI shows it: input mistakes read better without the error
+/// kind in front.
+fn describe(e: WalletError) -> String {
+    match e {
+        WalletError::InvalidParameters(detail) => detail,
+        WalletError::InvalidAddressFormat => "Invalid recipient address".to_string(),
+        other => other.to_string(),
+    }
+}
+
+impl LoadedWallet {
+    /// `wallet`'s keys and public details, loaded from a key or file.
+    fn new(wallet: &KaspaWallet) -> Result<Self, WalletError> {
+        Ok(Self {
+            private_key: wallet.get_private_key().into(),
+            secret: keystore::export_secret(wallet)?.to_string().into(),
+            wallet: KaspaGuiWallet::from(wallet),
+            created: false,
+            phrase: None,
+        })
+    }
+}
+
+fn load_wallet(private_key: &str, network: &str) -> Result<LoadedWallet, WalletError> {
+    let secret_key = parse_key_field(private_key)?;
+    let wallet = KaspaWallet::with_network(secret_key, network)?;
+    Ok(LoadedWallet {
+        private_key: private_key.to_string().into(),
+        ..LoadedWallet::new(&wallet)?
+    })
+}
+
+/// Runs `work` on a blocking thread, so key derivation and node requests
+/// leave the window responsive. A panic comes back as an error, so the
+/// busy flag its message clears is never left set.
+async fn off_thread<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .unwrap_or_else(|e| Err(format!("The operation failed unexpectedly: {}", e)))
+}
+
+/// Asks for a keystore file to open, or where to save one.
+async fn pick_wallet_file(save: bool) -> Option<PathBuf> {
+    let dialog = rfd::AsyncFileDialog::new().add_filter("Wallet keystore", &[KEYSTORE_EXTENSION]);
+    let file = if save {
+        dialog
+            .set_file_name(format!("wallet.{}", KEYSTORE_EXTENSION))
+            .save_file()
+            .await
+    } else {
+        dialog.pick_file().await
+    };
+    file.map(|file| file.path().to_path_buf())
+}
+
+fn open_wallet_file(
+    keystore: &Keystore,
+    path: PathBuf,
+    password: &str,
+) -> Result<(LoadedWallet, WalletFile), WalletError> {
+    let wallet = keystore.decrypt(password)?;
+    let file = WalletFile {
+        path,
+        fingerprint: wallet.fingerprint(),
+    };
+    Ok((LoadedWallet::new(&wallet)?, file))
+}
+
+/// The wallet in the file at `path`, provided it is still the wallet with
+/// `address`.
+fn unlock_wallet_file(
+    path: PathBuf,
+    address: &str,
+    password: &str,
+) -> Result<LoadedWallet, WalletError> {
+    let keystore = Keystore::load(&path)?;
+    let mut lockable = LockableWallet::from_keystore(keystore, path, None);
+    lockable.unlock(password)?;
+    let wallet = lockable.wallet()?;
+    if wallet.get_address() != address {
+        return Err(WalletError::Storage(
+            "The wallet file now holds a different wallet".to_string(),
+        ));
+    }
+    LoadedWallet::new(wallet)
+}
+
+/// The wallet of `key`, a private key or an HD wallet's seed phrase, if it
+/// is the wallet with `fingerprint`; for a wallet with no file. An HD
+/// wallet's first key has its address, but not its fingerprint.
+fn unlock_with_key(
+    key: &str,
+    network: &str,
+    fingerprint: &str,
+) -> Result<LoadedWallet, WalletError> {
+    let wallet = if mnemonic::looks_like_phrase(key) {
+        let words: Vec<String> = key.split_whitespace().map(str::to_string).collect();
+        KaspaWallet::from_mnemonic(&mnemonic::from_words(&words)?, "", network)?
+    } else {
+        KaspaWallet::with_network(parse_key_field(key)?, network)?
+    };
+    if wallet.fingerprint() != fingerprint {
+        return Err(WalletError::InvalidParameters(format!(
+            "That {} belongs to a different wallet",
+            if wallet.is_hd() { "phrase" } else { "key" }
+        )));
+    }
+    LoadedWallet::new(&wallet)
+}
+
+/// Encrypts the wallet of `secret` under `password` into `path`, named
+/// after the file. An HD wallet keeps its phrase or account key.
+fn save_wallet_file(
+    secret: &str,
+    network: &str,
+    path: PathBuf,
+    password: &str,
+) -> Result<WalletFile, WalletError> {
+    let wallet = keystore::import_secret(secret, network)?;
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let keystore = Keystore::encrypt(&wallet, &name, password)?;
+    // The native dialog already confirmed replacing an existing file.
+    keystore.save(&path, Overwrite::Allow)?;
+    Ok(WalletFile {
+        path,
+        fingerprint: keystore.fingerprint,
+    })
+}
+
+/// A page of `address`'s history older than `before` (block time in ms).
+fn fetch_history(
+    data_dir: &Path,
+    url: &str,
+    address: &str,
+    before: Option<u64>,
+    refresh: bool,
+) -> Result<Vec<HistoryEntry>, WalletError> {
+    let policy = RetryPolicy {
+        timeout: HTTP_TIMEOUT,
+        retries: DEFAULT_RETRIES,
+    };
+    let mut cache = HistoryCache::load(data_dir);
+    let entries = Explorer::new(url, policy)?.transactions(
+        &mut cache,
+        address,
+        HISTORY_PAGE,
+        before,
+        refresh,
+    )?;
+    if let Err(e) = cache.save() {
+        tracing::warn!("could not save history cache: {}", e);
+    }
+    Ok(entries)
+}
+
+/// Asks where to save the QR code and writes it there.
+async fn save_qr(png: Vec<u8>) -> Result<Option<PathBuf>, String> {
+    let Some(file) = rfd::AsyncFileDialog::new()
+        .set_file_name("kaspa-address.png")
+        .add_filter("PNG image", &["png"])
+        .save_file()
+        .await
+    else {
+        return Ok(None);
+    };
+    let path = file.path().to_path_buf();
+    std::fs::write(&path, png).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(Some(path))
+}
+
+/// Asks for a PNG or JPEG image and reads the QR code in it.
+async fn scan_qr() -> Result<Option<String>, String> {
+    let Some(file) = rfd::AsyncFileDialog::new()
+        .add_filter("Image", &["png", "jpg", "jpeg"])
+        .pick_file()
+        .await
+    else {
+        return Ok(None);
+    };
+    let path = file.path().to_path_buf();
+    let bytes = std::fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    qr::decode(&bytes).map(Some).map_err(describe)
+}
+
+/// The address a scanned QR code holds, bare or as a payment URI.
+fn scanned_address(text: &str) -> Result<&str, String> {
+    let text = text.trim();
+    let address = text.split_once('?').map_or(text, |(address, _)| address);
+    match check_address_format(address) {
+        Ok(()) => Ok(address),
+        Err(_) => Err(format!(
+            "The QR code holds no Kaspa address: {}",
+            shorten(text)
+        )),
+    }
+}
+
+/// A new HD wallet with a `word_count`-word seed phrase, which comes back
+/// with it for the backup screen.
+fn create_hd_wallet(network: &str, word_count: usize) -> Result<LoadedWallet, WalletError> {
+    let mnemonic = mnemonic::generate(word_count)?;
+    let wallet = KaspaWallet::from_mnemonic(&mnemonic, "", network)?;
+    Ok(LoadedWallet {
+        created: true,
+        phrase: Some(SeedPhrase(mnemonic)),
+        ..LoadedWallet::new(&wallet)?
+    })
+}
+
+/// Signs a transaction paying `outputs`, each input with the key of its
+/// address among those up to the gap limit past `next_index`.
+fn build_transaction(
+    secret: &str,
+    network: &str,
+    next_index: [u32; 2],
+    inputs: Vec<UtxoEntry>,
+    outputs: Vec<(String, u64)>,
+    fee_rate: u64,
+) -> Result<Transaction, WalletError> {
+    let mut wallet = keystore::import_secret(secret, network)?;
+    wallet.set_next_index(RECEIVE_CHAIN, next_index[0]);
+    wallet.set_next_index(CHANGE_CHAIN, next_index[1]);
+    wallet.create_transaction(inputs, outputs, fee_rate)
+}
+
+/// Submits `transaction` to the node at `url`, returning the txid it
+/// accepted.
+fn broadcast_transaction(
+    url: &str,
+    network: &str,
+    transaction: &Transaction,
+    force: bool,
+) -> Result<String, WalletError> {
+    let client = node::connect(url, RetryPolicy::default())?;
+    node::check_submission(client.as_ref(), network, force)?;
+    if let Some(warning) = transaction.lock_time_warning(client.virtual_daa_score().ok()) {
+        tracing::warn!("{}", warning);
+    }
+    client.submit_transaction(transaction)
+}
+
+/// Where `txid` is, according to the node at `url`.
+fn fetch_tx_status(url: &str, txid: &str) -> Result<TxStatus, WalletError> {
+    node::connect(url, RetryPolicy::default())?.get_transaction_status(txid)
+}
+
+/// Asks where to save the transaction and writes it there.
+/// Asks where to export `info`: JSON that imports as a watch-only wallet,
+/// or plain text when the file is named `.txt`.
+async fn save_wallet_info(info: WalletInfo) -> Result<Option<PathBuf>, String> {
+    let Some(file) = rfd::AsyncFileDialog::new()
+        .set_file_name(format!("kaspa-wallet-{}.json", info.fingerprint))
+        .add_filter("Wallet descriptor", &["json"])
+        .add_filter("Text", &["txt"])
+        .save_file()
+        .await
+    else {
+        return Ok(None);
+    };
+    let path = file.path().to_path_buf();
+    let text = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("txt"));
+    let contents = if text {
+        info.to_text()
+    } else {
+        info.to_json().map_err(describe)?
+    };
+    std::fs::write(&path, contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(Some(path))
+}
+
+async fn save_pstx(json: String, txid: String) -> Result<Option<PathBuf>, String> {
+    let Some(file) = rfd::AsyncFileDialog::new()
+        .set_file_name(format!("kaspa-tx-{}.json", &txid[..txid.len().min(8)]))
+        .add_filter("PSTX", &["json"])
+        .save_file()
+        .await
+    else {
+        return Ok(None);
+    };
+    let path = file.path().to_path_buf();
+    std::fs::write(&path, json).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(Some(path))
+}
+
+fn fetch_prices(url: &str) -> Result<Prices, WalletError> {
+    let policy = RetryPolicy {
+        timeout: HTTP_TIMEOUT,
+        retries: DEFAULT_RETRIES,
+    };
+    PriceApi::new(url, policy)?.prices()
+}
+
+fn fetch_fee_estimate(
+    cache: &FeeEstimateCache,
+    url: &str,
+) -> Result<Option<FeeEstimate>, WalletError> {
+    cache.get_or_fetch(url, Instant::now(), || {
+        node::connect(url, RetryPolicy::default())?.get_fee_estimate()
+    })
+}
+
+/// Receive addresses at `indexes` of the account key `xpub`.
+fn derive_receive_addresses(
+    xpub: &str,
+    network: &str,
+    indexes: Range<u32>,
+) -> Result<Vec<DerivedAddress>, WalletError> {
+    let xpub: ExtendedPublicKey = xpub.parse()?;
+    let network = NetworkConfig::from_name(network)?;
+    indexes
+        .map(|index| {
+            let public_key = xpub.derive_address_key(RECEIVE_CHAIN, index)?;
+            Ok(DerivedAddress {
+                index,
+                address: address::generate_address(&public_key, network.get_prefix()),
+                funded: None,
+            })
+        })
+        .collect()
+}
+
+/// Those of `addresses` the node at `url` holds outputs for.
+fn fetch_funded_addresses(
+    url: &str,
+    network: &str,
+    addresses: Vec<String>,
+) -> Result<BTreeSet<String>, WalletError> {
+    let client = node::connect(url, RetryPolicy::default())?;
+    node::check_network(&client.get_info()?, network)?;
+    Ok(client
+        .get_utxos_by_addresses(&addresses)?
+        .into_iter()
+        .map(|utxo| utxo.address)
+        .collect())
+}
+
+/// The node at `url`'s notifications for `addresses`, or `None` when its
+/// transport has none.
+fn subscribe_utxo_changes(
+    url: &str,
+    network: &str,
+    addresses: Vec<String>,
+) -> Result<Option<UtxoChanges>, WalletError> {
+    let client = node::connect(url, RetryPolicy::default())?;
+    node::check_network(&client.get_info()?, network)?;
+    client.subscribe_utxo_changes(&addresses)
+}
+
+/// A balance refresh for every UTXO notification of the node at `url`
+/// for `addresses`, the first being the wallet's own. Ends when the node
+/// sends none, leaving the poll.
+fn utxo_changes(
+    (url, network, addresses): &(String, String, Vec<String>),
+) -> impl Stream<Item = Message> {
+    let (url, network, addresses) = (url.clone(), network.clone(), addresses.clone());
+    iced::stream::channel(node::UTXO_CHANGE_BUFFER, async move |mut output| {
+        let key = addresses.first().cloned().unwrap_or_default();
+        let subscribed = off_thread(move || {
+            subscribe_utxo_changes(&url, &network, addresses).map_err(|e| e.to_string())
+        })
+        .await;
+        let mut changes = match subscribed {
+            Ok(Some(changes)) => changes,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::debug!("no UTXO notifications; polling the balance: {}", e);
+                return;
+            }
+        };
+        loop {
+            // Waits in short steps, so that the stream, dropped with the
+            // subscription, unsubscribes soon after.
+            let Ok((rest, next)) = off_thread(move || {
+                let next = changes.recv_timeout(UTXO_CHANGE_WAIT);
+                Ok((changes, next))
+            })
+            .await
+            else {
+                return;
+            };
+            changes = rest;
+            match next {
+                Ok(_) => {
+                    let message = Message::UtxosChanged {
+                        address: key.clone(),
+                    };
+                    if output.send(message).await.is_err() {
+                        return;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    tracing::debug!("UTXO notifications ended; polling the balance");
+                    return;
+                }
+            }
+        }
+    })
+}
+
+/// The tray icon's menu picks, after [`Message::TrayReady`]. Ends at once,
+/// leaving the window without a tray, where there is none to put it in.
+fn tray_events() -> impl Stream<Item = Message> {
+    iced::stream::channel(1, async |mut output| {
+        let (actions, mut picks) = iced::futures::channel::mpsc::unbounded();
+        let _tray = match tray::start(actions).await {
+            Ok(tray) => tray,
+            Err(e) => {
+                tracing::debug!("no tray icon: {}", e);
+                return;
+            }
+        };
+        if output.send(Message::TrayReady).await.is_err() {
+            return;
+        }
+        while let Some(action) = picks.next().await {
+            if output.send(Message::Tray(action)).await.is_err() {
+                return;
+            }
+        }
+    })
+}
+
+/// Shows a desktop notification, where there is a notification server.
+fn desktop_notification(summary: String, body: String) -> Task<Message> {
+    Task::future(async move {
+        if let Err(e) = tray::notify(summary, body).await {
+            tracing::debug!("no desktop notification: {}", e);
+        }
+    })
+    .discard()
+}
+
+/// Brings the hidden or minimized window back to the front.
+fn show_window() -> Task<Message> {
+    iced::window::oldest().and_then(|id| {
+        Task::batch([
+            iced::window::set_mode(id, iced::window::Mode::Windowed),
+            iced::window::minimize(id, false),
+            iced::window::gain_focus(id),
+        ])
+    })
+}
+
+/// Syncs the outputs the node at `url` holds for `addresses`, with the
+/// virtual DAA score from `daa_scores` when it is fresh.
+fn fetch_balance(
+    daa_scores: &DaaScoreCache,
+    url: &str,
+    network: &str,
+    addresses: Vec<String>,
+) -> Result<Balance, WalletError> {
+    let client = node::connect(url, RetryPolicy::default())?;
+    node::check_network(&client.get_info()?, network)?;
+    // Read first, as `WalletState::sync` does.
+    let virtual_daa_score =
+        daa_scores.get_or_fetch(url, Instant::now(), || client.virtual_daa_score())?;
+    let utxos = client.get_utxos_by_addresses(&addresses)?;
+    let mut state = WalletState::new(addresses);
+    state.apply_snapshot(utxos, virtual_daa_score);
+    Ok(Balance {
+        total: state.balance(),
+        pending: state.pending(),
+        utxo_count: state.utxos.len(),
+        updated: notifications::now_millis(),
+        virtual_daa_score: state.virtual_daa_score(),
+        utxos: state.utxos,
+    })
+}
+
+/// The wallet work, done on this machine.
+#[derive(Debug)]
+struct LocalBackend;
+
+impl WalletBackend for LocalBackend {
+    fn create(&self, network: &str, word_count: usize) -> Result<LoadedWallet, WalletError> {
+        create_hd_wallet(network, word_count)
+    }
+
+    fn load(&self, private_key: &str, network: &str) -> Result<LoadedWallet, WalletError> {
+        load_wallet(private_key, network)
+    }
+
+    fn build_transaction(
+        &self,
+        secret: &str,
+        network: &str,
+        next_index: [u32; 2],
+        inputs: Vec<UtxoEntry>,
+        outputs: Vec<(String, u64)>,
+        fee_rate: u64,
+    ) -> Result<Transaction, WalletError> {
+        build_transaction(secret, network, next_index, inputs, outputs, fee_rate)
+    }
+
+    fn open_file(
+        &self,
+        keystore: &Keystore,
+        path: PathBuf,
+        password: &str,
+    ) -> Result<(LoadedWallet, WalletFile), WalletError> {
+        open_wallet_file(keystore, path, password)
+    }
+
+    fn save_file(
+        &self,
+        secret: &str,
+        network: &str,
+        path: PathBuf,
+        password: &str,
+    ) -> Result<WalletFile, WalletError> {
+        save_wallet_file(secret, network, path, password)
+    }
+
+    fn unlock_file(
+        &self,
+        path: PathBuf,
+        address: &str,
+        password: &str,
+    ) -> Result<LoadedWallet, WalletError> {
+        unlock_wallet_file(path, address, password)
+    }
+
+    fn unlock_with_key(
+        &self,
+        key: &str,
+        network: &str,
+        fingerprint: &str,
+    ) -> Result<LoadedWallet, WalletError> {
+        unlock_with_key(key, network, fingerprint)
+    }
+}
+
+/// Does `job` with `backend`, answering with the message it reports to.
+fn run_wallet_job(backend: &dyn WalletBackend, job: WalletJob) -> Message {
+    match job {
+        WalletJob::Create {
+            network,
+            word_count,
+        } => Message::WalletLoaded(backend.create(network, word_count).map_err(describe)),
+        WalletJob::Load {
+            private_key,
+            network,
+        } => Message::WalletLoaded(backend.load(&private_key, network).map_err(describe)),
+        WalletJob::Build {
+            secret,
+            network,
+            next_index,
+            inputs,
+            outputs,
+            fee_rate,
+            summary,
+        } => Message::TransactionBuilt(
+            backend
+                .build_transaction(&secret, network, next_index, inputs, outputs, fee_rate)
+                .and_then(|transaction| BuiltTransaction::new(transaction, *summary))
+                .map_err(describe),
+        ),
+        WalletJob::OpenFile {
+            keystore,
+            path,
+            password,
+        } => Message::WalletFileOpened(
+            backend
+                .open_file(&keystore, path, &password)
+                .map_err(|e| e.to_string()),
+        ),
+        WalletJob::SaveFile {
+            secret,
+            network,
+            path,
+            password,
+        } => Message::WalletFileSaved(
+            backend
+                .save_file(&secret, network, path, &password)
+                .map_err(describe),
+        ),
+        WalletJob::UnlockFile {
+            path,
+            address,
+            password,
+        } => {
+            let result = backend
+                .unlock_file(path, &address, &password)
+                .map_err(describe);
+            Message::Unlocked { address, result }
+        }
+    }
+}
+
+/// What `job` reports when it could not run at all.
+fn wallet_job_failed(job: &WalletJob) -> Box<dyn FnOnce(String) -> Message + Send> {
+    match job {
+        WalletJob::Create { .. } | WalletJob::Load { .. } => {
+            Box::new(|e| Message::WalletLoaded(Err(e)))
+        }
+        WalletJob::Build { .. } => Box::new(|e| Message::TransactionBuilt(Err(e))),
+        WalletJob::OpenFile { .. } => Box::new(|e| Message::WalletFileOpened(Err(e))),
+        WalletJob::SaveFile { .. } => Box::new(|e| Message::WalletFileSaved(Err(e))),
+        WalletJob::UnlockFile { address, .. } => {
+            let address = address.clone();
+            Box::new(move |e| Message::Unlocked {
+                address,
+                result: Err(e),
+            })
+        }
+    }
+}
+
+fn write_settings(dir: &Path, settings: &GuiSettings) -> Result<(), String> {
+    settings.save(dir).map_err(|e| e.to_string())
+}
+
+fn read_keystore(path: &Path) -> Result<Box<Keystore>, String> {
+    Keystore::load(path)
+        .map(Box::new)
+        .map_err(|e| e.to_string())
+}
+
+/// The iced application: the state, and what carries out the commands
+/// [`update`] returns.
+struct WalletGui {
+    state: WalletGuiState,
+    clipboard: Box<dyn Clipboard>,
+    /// Shared with the fetches, so balance refreshes do not ask the node
+    /// for fees every time.
+    fee_estimates: Arc<FeeEstimateCache>,
+    /// Likewise for the virtual DAA score, which refreshes after every
+    /// notification would otherwise ask for in bursts.
+    daa_scores: Arc<DaaScoreCache>,
+}
+
+impl WalletGui {
+    /// The initial state, asking the desktop whether it is light or dark
+    /// and fetching the fiat price.
+    fn boot() -> (Self, Task<Message>) {
+        let app = WalletGui {
+            state: WalletGuiState::new(
+                config::default_data_dir(),
+                gui_settings::default_settings_dir(),
+            ),
+            clipboard: Box::new(SystemClipboard),
+            fee_estimates: Arc::new(FeeEstimateCache::default()),
+            daa_scores: Arc::new(DaaScoreCache::default()),
+        };
+        let prices = app.run_all(app.state.refresh_prices());
+        (
+            app,
+            Task::batch([system::theme().map(Message::SystemThemeChanged), prices]),
+        )
+    }
+
+    fn update(&mut self, message: Message) -> Task<Message> {
+        let commands = update(&mut self.state, message);
+        self.run_all(commands)
+    }
+
+    fn run_all(&self, commands: Vec<Command>) -> Task<Message> {
+        Task::batch(commands.into_iter().map(|command| self.run(command)))
+    }
+
+    fn run(&self, command: Command) -> Task<Message> {
+        match command {
+            Command::Copy { what, text } => self
+                .clipboard
+                .write(text)
+                .map(move |ok| Message::Copied { what, ok }),
+            Command::CopyKey(key) => self
+                .clipboard
+                .write(key.to_string())
+                .map(Message::PrivateKeyCopied),
+            Command::ClearClipboard(text) => self
+                .clipboard
+                .clear_if_holds(text.to_string())
+                .map(Message::ClipboardCleared),
+            Command::Paste(target) => self
+                .clipboard
+                .read()
+                .map(move |text| Message::ClipboardRead(target, text.map(Redacted::from))),
+            Command::SaveSettingsLater(generation) => Task::perform(
+                async { tokio::time::sleep(SETTINGS_SAVE_DELAY).await },
+                move |_| Message::SaveSettings(generation),
+            ),
+            Command::WriteSettings { dir, settings } => Task::perform(
+                off_thread(move || write_settings(&dir, &settings)),
+                Message::SettingsWritten,
+            ),
+            Command::ReadKeystore(path) => Task::perform(
+                async move {
+                    let read = path.clone();
+                    let result = off_thread(move || read_keystore(&read)).await;
+                    (path, result)
+                },
+                |(path, result)| Message::KeystoreRead(path, result),
+            ),
+            Command::ReadDroppedFile(path) => {
+                let read = path.clone();
+                Task::perform(
+                    off_thread(move || Ok(dropped::read(&read))),
+                    move |result| {
+                        let dropped = result.unwrap_or_else(Dropped::Unsupported);
+                        Message::DroppedFileRead(path, dropped)
+                    },
+                )
+            }
+            Command::Wallet(job) => {
+                let backend = Arc::clone(&self.state.backend);
+                let failed = wallet_job_failed(&job);
+                Task::perform(
+                    off_thread(move || Ok(run_wallet_job(backend.as_ref(), job))),
+                    move |result| result.unwrap_or_else(failed),
+                )
+            }
+            Command::FetchPrices { url } => Task::perform(
+                off_thread(move || fetch_prices(&url).map_err(|e| e.to_string())),
+                Message::PricesFetched,
+            ),
+            Command::FetchBalance {
+                url,
+                network,
+                address,
+                addresses,
+            } => {
+                let key = address;
+                let daa_scores = Arc::clone(&self.daa_scores);
+                Task::perform(
+                    off_thread(move || {
+                        fetch_balance(&daa_scores, &url, &network, addresses)
+                            .map_err(|e| e.to_string())
+                    }),
+                    move |result| Message::BalanceFetched {
+                        address: key.clone(),
+                        result,
+                    },
+                )
+            }
+            Command::FetchFeeEstimate { url } => {
+                let fee_estimates = Arc::clone(&self.fee_estimates);
+                Task::perform(
+                    off_thread(move || {
+                        fetch_fee_estimate(&fee_estimates, &url).map_err(|e| e.to_string())
+                    }),
+                    Message::FeeEstimateFetched,
+                )
+            }
+            Command::FetchFundedAddresses {
+                url,
+                network,
+                fingerprint,
+                addresses,
+            } => Task::perform(
+                off_thread(move || {
+                    fetch_funded_addresses(&url, &network, addresses).map_err(|e| e.to_string())
+                }),
+                move |result| Message::DerivedFundsFetched {
+                    fingerprint: fingerprint.clone(),
+                    result,
+                },
+            ),
+            Command::FetchHistory {
+                url,
+                address,
+                before,
+                refresh,
+            } => {
+                let key = address.clone();
+                let data_dir = self.state.data_dir.clone();
+                Task::perform(
+                    off_thread(move || {
+                        fetch_history(&data_dir, &url, &address, before, refresh)
+                            .map_err(|e| e.to_string())
+                    }),
+                    move |result| Message::HistoryFetched {
+                        address: key.clone(),
+                        before,
+                        result,
+                    },
+                )
+            }
+            Command::Broadcast { url, built, force } => Task::perform(
+                async move {
+                    let txid = built.txid.clone();
+                    let outcome = off_thread(move || {
+                        let network = &built.summary.network;
+                        Ok(broadcast_transaction(
+                            &url,
+                            network,
+                            &built.transaction,
+                            force,
+                        ))
+                    })
+                    .await;
+                    let unsynced = matches!(
+                        outcome,
+                        Ok(Err(WalletError::Node(NodeFailure::NotSynced { .. })))
+                    );
+                    let result = outcome.and_then(|submitted| submitted.map_err(describe));
+                    (txid, result, unsynced)
+                },
+                |(txid, result, unsynced)| Message::Broadcasted {
+                    txid,
+                    result,
+                    unsynced,
+                },
+            ),
+            Command::FetchTxStatus { url, txid } => Task::perform(
+                async move {
+                    let lookup = txid.clone();
+                    let result = off_thread(move || {
+                        fetch_tx_status(&url, &lookup).map_err(|e| e.to_string())
+                    })
+                    .await;
+                    (txid, result)
+                },
+                |(txid, result)| Message::TxStatusFetched { txid, result },
+            ),
+            Command::CheckPropagation { url, txid } => Task::perform(
+                async move {
+                    let lookup = txid.clone();
+                    let result = off_thread(move || {
+                        let client = node::connect(&url, RetryPolicy::default())
+                            .map_err(|e| e.to_string())?;
+                        node::check_propagation(
+                            client.as_ref(),
+                            None,
+                            &lookup,
+                            node::PROPAGATION_DELAY,
+                        )
+                        .map_err(|e| e.to_string())
+                    })
+                    .await;
+                    (txid, result)
+                },
+                |(txid, result)| Message::PropagationChecked { txid, result },
+            ),
+            Command::PickWalletFile { save: false } => {
+                Task::perform(pick_wallet_file(false), Message::OpenPathChosen)
+            }
+            Command::PickWalletFile { save: true } => {
+                Task::perform(pick_wallet_file(true), Message::SavePathChosen)
+            }
+            Command::SaveQr(png) => Task::perform(save_qr(png), Message::QrSaved),
+            Command::ScanQr(target) => {
+                Task::perform(scan_qr(), move |result| Message::QrScanned(target, result))
+            }
+            Command::SaveTransaction { json, txid } => {
+                Task::perform(save_pstx(json, txid), Message::ResultSaved)
+            }
+            Command::ExportWalletInfo(info) => {
+                Task::perform(save_wallet_info(info), Message::WalletInfoExported)
+            }
+            Command::DesktopNotification { summary, body } => desktop_notification(summary, body),
+            Command::ShowWindow => show_window(),
+            Command::HideWindow(id) => iced::window::set_mode(id, iced::window::Mode::Hidden),
+            Command::Exit => iced::exit(),
+        }
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        view(&self.state)
+    }
+
+    fn title(&self) -> String {
+        title(&self.state)
+    }
+
+    fn theme(&self) -> Option<Theme> {
+        app_theme(&self.state)
+    }
+
+    fn scale_factor(&self) -> f32 {
+        scale_factor(&self.state)
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        subscription(&self.state)
+    }
+}
+
+pub fn run_gui() -> Result<(), iced::Error> {
+    let settings = iced::Settings {
+        antialiasing: true,
+        ..iced::Settings::default()
+    };
+    let window = iced::window::Settings {
+        size: iced::Size::new(1024.0, 768.0),
+        min_size: Some(iced::Size::new(MIN_WIDTH, MIN_HEIGHT)),
+        // Closing may only hide the window; see `Message::CloseRequested`.
+        exit_on_close_request: false,
+        ..iced::window::Settings::default()
+    };
+
+    iced::application(WalletGui::boot, WalletGui::update, WalletGui::view)
+        .title(WalletGui::title)
+        .theme(WalletGui::theme)
+        .scale_factor(WalletGui::scale_factor)
+        .subscription(WalletGui::subscription)
+        .settings(settings)
+        .window(window)
+        .run()
+}
+
+fn scale_factor(state: &WalletGuiState) -> f32 {
+    state.settings.scale_factor()
+}
+
+fn app_theme(state: &WalletGuiState) -> Option<Theme> {
+    style::theme(state.settings.theme, state.system_mode)
+}
+
+/// Desktop theme changes, a tick while toasts are due to expire, a price
+/// refresh every few minutes, the node's UTXO notifications and a balance
+/// poll behind them, the tray icon, close requests, and key presses for
+/// the shortcuts.
+fn subscription(state: &WalletGuiState) -> Subscription<Message> {
+    let mut subscriptions = vec![
+        system::theme_changes().map(Message::SystemThemeChanged),
+        Subscription::run(tray_events),
+        iced::window::close_requests().map(Message::CloseRequested),
+    ];
+    if state.notifications.expiring()
+        || state.pending_clear.is_some()
+        || state.output_undo.offer(state.now()).is_some()
+        || state.auto_lock_at().is_some()
+    {
+        subscriptions.push(iced::time::every(Duration::from_secs(1)).map(|_| Message::Tick));
+    }
+    if !state.price_url.trim().is_empty() {
+        subscriptions.push(iced::time::every(PRICE_REFRESH).map(|_| Message::RefreshPrices));
+    }
+    if let Some(wallet) = state
+        .wallet
+        .as_ref()
+        .filter(|_| !state.node_url.trim().is_empty() && matches!(state.screen, Screen::Wallet))
+    {
+        subscriptions.push(iced::time::every(BALANCE_POLL).map(|_| Message::PollBalance));
+        let addresses = match state.watched.first() {
+            Some(first) if *first == wallet.address => state.watched.clone(),
+            _ => vec![wallet.address.clone()],
+        };
+        let key = (
+            state.node_url.trim().to_string(),
+            wallet.network_name.clone(),
+            addresses,
+        );
+        subscriptions.push(Subscription::run_with(key, utxo_changes));
+    }
+    if state
+        .result
+        .as_ref()
+        .is_some_and(|panel| matches!(panel.broadcast, Some(Ok(_))))
+    {
+        subscriptions.push(iced::time::every(TX_STATUS_POLL).map(|_| Message::PollTxStatus));
+    }
+    // Every key press, even one a text field took: Escape closes a dialog
+    // from its password field too. Dropped files come the same way.
+    let keys = iced::event::listen_with(|event, _status, _window| match event {
+        iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) => {
+            Some(Message::KeyPressed(key, modifiers))
+        }
+        iced::Event::Window(iced::window::Event::FileDropped(path)) => {
+            Some(Message::FileDropped(path))
+        }
+        _ => None,
+    });
+    subscriptions.push(keys);
+    Subscription::batch(subscriptions)
+}
+
+fn title(state: &WalletGuiState) -> String {
+    match &state.wallet {
+        Some(wallet) => format!(
+            "KaspaRustWallet - {}",
+            wallet_label(wallet, state.wallet_file.as_ref())
+        ),
+        None => "KaspaRustWallet".to_string(),
+    }
+}
+
+fn copy(what: &'static str, text: String) -> Vec<Command> {
+    vec![Command::Copy { what, text }]
+}
+
+/// Carries `message` into the state, and says what the shell is to do
+/// about it.
+fn update(state: &mut WalletGuiState, message: Message) -> Vec<Command> {
+    if !message.is_background() {
+        state.last_interaction = state.now();
+    }
+    if let Screen::Locked(lock) = &mut state.screen {
+        if message.needs_key() {
+            lock.resume = Some(Box::new(message));
+            return Vec::new();
+        }
+    }
+    state.follow_send_focus(&message);
+    match message {
+        Message::PrivateKeyInput(key) => {
+            // Pasted keys often carry a trailing newline or spaces.
+            state.private_key = key.trim().to_string().into();
+            if let Some(Ok(())) = key_feedback(&state.private_key) {
+                state.notify(Severity::Success, "Private key is valid".to_string());
+            }
+        }
+        Message::NetworkSelected(network) => {
+            state.network = network;
+            state.reload_contacts();
+            // A contact picked on the old network is not valid on this one.
+            if state.recipient_label.take().is_some() {
+                state.recipient.clear();
+            }
+            let save = state.settings_changed();
+            // The address depends on the network; load the key again so
+            // the address and its QR code follow.
+            if state.wallet.is_some() {
+                return [save, update(state, Message::LoadWallet)]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+            }
+            return save;
+        }
+        Message::SwitchNetwork(network) => {
+            // Nothing derived on the old network stays on show while the
+            // wallet reloads on the new one.
+            state.address_result.clear();
+            state.copy_address_text.clear();
+            state.balance = None;
+            state.balance_error = None;
+            state.selected_inputs.clear();
+            state.notify(Severity::Info, format!("Switched to {}", network));
+            return update(state, Message::NetworkSelected(network));
+        }
+        Message::RequestAmountInput(amount) => {
+            state.request_amount = amount;
+            state.refresh_qr();
+        }
+        Message::SaveQr => {
+            if let Some(png) = state.qr_png.clone() {
+                return vec![Command::SaveQr(png)];
+            }
+        }
+        Message::QrSaved(result) => match result {
+            Ok(Some(path)) => {
+                state.notify(
+                    Severity::Success,
+                    format!("QR code saved to {}", path.display()),
+                );
+            }
+            Ok(None) => {}
+            Err(e) => state.notify(Severity::Error, format!("Could not save QR code: {}", e)),
+        },
+        Message::NodeUrlInput(url) => {
+            state.node_url = url;
+            return state.settings_changed();
+        }
+        Message::RefreshBalance | Message::PollBalance => return state.refresh_balance(),
+        Message::UtxosChanged { address } => {
+            if state.wallet.as_ref().map(|wallet| &wallet.address) == Some(&address) {
+                return state.refresh_balance();
+            }
+        }
+        Message::BalanceFetched { address, result } => {
+            state.busy.balance = false;
+            let (balance, error, selected) =
+                if state.wallet.as_ref().map(|wallet| &wallet.address) == Some(&address) {
+                    (
+                        &mut state.balance,
+                        &mut state.balance_error,
+                        &mut state.selected_inputs,
+                    )
+                } else if let Some(parked) = state
+                    .open_wallets
+                    .values_mut()
+                    .find(|parked| parked.wallet.address == address)
+                {
+                    (
+                        &mut parked.balance,
+                        &mut parked.balance_error,
+                        &mut parked.selected_inputs,
+                    )
+                } else {
+                    return Vec::new();
+                };
+            match result {
+                Ok(fetched) => {
+                    // Spent outputs drop out of the selection.
+                    selected.retain(|(txid, vout)| fetched.utxos.get(txid, *vout).is_some());
+                    // Outputs new since the last fetch are payments; the
+                    // first fetch only says what was there already.
+                    let incoming = balance.as_ref().map_or_else(Vec::new, |previous| {
+                        PaymentMonitor::new(previous.utxos.iter().cloned().collect())
+                            .update(fetched.utxos.iter().cloned().collect())
+                    });
+                    *balance = Some(fetched);
+                    *error = None;
+                    let mut desktop = Vec::new();
+                    for utxo in incoming {
+                        let received = format!(
+                            "Received {} KAS at {}",
+                            Kas::from_sompi(utxo.amount),
+                            shorten(&utxo.address)
+                        );
+                        desktop.push(Command::DesktopNotification {
+                            summary: format!("Received {} KAS", Kas::from_sompi(utxo.amount)),
+                            body: utxo.address.clone(),
+                        });
+                        state.notify_with(
+                            Severity::Success,
+                            received,
+                            Payload {
+                                what: "Txid",
+                                value: utxo.txid,
+                            },
+                        );
+                    }
+                    state.refresh_max();
+                    return desktop;
+                }
+                Err(e) => *error = Some(e),
+            }
+            state.refresh_max();
+        }
+        Message::CreateWallet => {
+            if state.busy.wallet {
+                return Vec::new();
+            }
+            state.busy.start_wallet("Deriving keys…");
+            return vec![Command::Wallet(WalletJob::Create {
+                network: state.network.to_str(),
+                word_count: state.seed_words,
+            })];
+        }
+        Message::NewAddress => return state.new_address(),
+        Message::CopyDerivedAddress(index) => {
+            if let Some(derived) = state
+                .derived_addresses
+                .iter()
+                .find(|derived| derived.index == index)
+            {
+                return copy("Address", derived.address.clone());
+            }
+        }
+        Message::DerivedFundsFetched {
+            fingerprint,
+            result,
+        } => {
+            let derived =
+                if state.wallet.as_ref().map(|wallet| &wallet.fingerprint) == Some(&fingerprint) {
+                    &mut state.derived_addresses
+                } else if let Some(parked) = state.open_wallets.get_mut(&fingerprint) {
+                    &mut parked.derived_addresses
+                } else {
+                    return Vec::new();
+                };
+            match result {
+                Ok(funded) => {
+                    for address in derived.iter_mut() {
+                        address.funded = Some(funded.contains(&address.address));
+                    }
+                }
+                Err(e) => state.notify(
+                    Severity::Warning,
+                    format!("Could not check the derived addresses: {}", e),
+                ),
+            }
+        }
+        Message::SeedWordsSelected(count) => state.seed_words = count,
+        Message::BackupWordHovered(hovered) => {
+            if let Screen::Backup(backup) = &mut state.screen {
+                backup.hovered = hovered;
+            }
+        }
+        Message::BackupWrittenDown => {
+            if let Screen::Backup(backup) = &mut state.screen {
+                backup.positions =
+                    mnemonic::quiz_positions(backup.phrase.0.word_count(), &mut rand::thread_rng());
+                backup.answers = vec![Redacted::default(); backup.positions.len()];
+                backup.error = None;
+                backup.hovered = None;
+                backup.step = BackupStep::Quiz;
+            }
+        }
+        Message::BackupAnswerInput(question, answer) => {
+            if let Screen::Backup(backup) = &mut state.screen {
+                if let Some(slot) = backup.answers.get_mut(question) {
+                    *slot = answer;
+                    backup.error = None;
+                }
+            }
+        }
+        Message::BackupShowWords => {
+            if let Screen::Backup(backup) = &mut state.screen {
+                backup.answers.clear();
+                backup.error = None;
+                backup.step = BackupStep::Words;
+            }
+        }
+        Message::BackupSubmit => {
+            let Screen::Backup(backup) = &mut state.screen else {
+                return Vec::new();
+            };
+            let wrong = backup
+                .positions
+                .iter()
+                .zip(&backup.answers)
+                .find(|(&position, answer)| {
+                    !mnemonic::word_matches(&backup.phrase.0, position, answer)
+                });
+            if let Some((position, _)) = wrong {
+                backup.error = Some(format!(
+                    "Word #{} does not match. Check your copy, or go back to the words.",
+                    position
+                ));
+                return Vec::new();
+            }
+            return state.finish_backup(true);
+        }
+        Message::BackupSkip => return state.finish_backup(false),
+        Message::LoadWallet => {
+            if state.busy.wallet {
+                return Vec::new();
+            }
+            state.busy.start_wallet("Loading the wallet…");
+            return vec![Command::Wallet(WalletJob::Load {
+                private_key: state.private_key.clone(),
+                network: state.network.to_str(),
+            })];
+        }
+        Message::WalletLoaded(result) => return state.wallet_loaded(result),
+        Message::SendTransaction => {
+            if state.busy.transaction {
+                return Vec::new();
+            }
+            match state.send_summary() {
+                Ok(summary) => {
+                    state.confirm_send = Some(summary);
+                    state.network_acknowledged = false;
+                }
+                Err(e) => state.notify(Severity::Error, e),
+            }
+        }
+        Message::CancelSend => {
+            state.confirm_send = None;
+            state.notify(Severity::Info, "Send cancelled".to_string());
+        }
+        Message::NetworkMismatchAcknowledged(acknowledged) => {
+            state.network_acknowledged = acknowledged;
+        }
+        Message::KeyPressed(key, modifiers) => {
+            let Some(shortcut) = shortcuts::dispatch(&key, modifiers, state.shortcut_context())
+            else {
+                return Vec::new();
+            };
+            let message = match shortcut {
+                Shortcut::Tab(index) => Message::TabSelected(Tab::ALL[index].clone()),
+                Shortcut::AddOutput => Message::AddOutput,
+                Shortcut::Send => Message::SendTransaction,
+                Shortcut::Lock => Message::Lock,
+                Shortcut::Dismiss => Message::DismissModal,
+            };
+            return update(state, message);
+        }
+        Message::ShowShortcuts => state.show_shortcuts = true,
+        Message::DismissModal => {
+            if std::mem::take(&mut state.show_shortcuts) {
+                return Vec::new();
+            }
+            if state.confirm_send.is_some() {
+                return update(state, Message::CancelSend);
+            }
+            if state.key_copy.take().is_some()
+                || std::mem::take(&mut state.confirm_key_export)
+                || state.decoded.take().is_some()
+            {
+                return Vec::new();
+            }
+            if state.prompt.is_none() {
+                if let Some(panel) = &mut state.result {
+                    if panel.confirm_broadcast {
+                        panel.confirm_broadcast = false;
+                    } else {
+                        state.result = None;
+                    }
+                    return Vec::new();
+                }
+            }
+            return update(state, Message::PromptCancel);
+        }
+        Message::CopyPrivateKey => {
+            if state.wallet.is_none() || state.wallet_key.is_empty() {
+                state.notify(Severity::Warning, "No private key to copy");
+            } else {
+                state.key_copy = Some(false);
+            }
+        }
+        Message::KeyCopyAcknowledged(acknowledged) => {
+            if state.key_copy.is_some() {
+                state.key_copy = Some(acknowledged);
+            }
+        }
+        Message::ConfirmKeyCopy => {
+            if state.key_copy != Some(true) {
+                return Vec::new();
+            }
+            state.key_copy = None;
+            return vec![Command::CopyKey(state.wallet_key.clone())];
+        }
+        Message::PrivateKeyCopied(ok) => {
+            if ok {
+                let secs = state.settings.clipboard_clear_secs;
+                state.pending_clear = Some(PendingClear {
+                    at: state.now() + secs * 1000,
+                    text: state.wallet_key.clone(),
+                });
+                state.notify(
+                    Severity::Warning,
+                    format!(
+                        "Private key copied; the clipboard clears in {} seconds",
+                        secs
+                    ),
+                );
+            } else {
+                state.notify(Severity::Error, "Copy failed");
+            }
+        }
+        Message::ClearClipboard => return state.clear_clipboard(),
+        Message::ClipboardCleared(cleared) => {
+            if cleared {
+                state.notify(Severity::Info, "Private key cleared from the clipboard");
+            }
+        }
+        Message::ConfirmSend => {
+            if state.network_mismatch().is_some() && !state.network_acknowledged {
+                return Vec::new();
+            }
+            let Some(summary) = state.confirm_send.take() else {
+                return Vec::new();
+            };
+            if state.busy.transaction {
+                return Vec::new();
+            }
+            let inputs = summary.inputs.iter().map(UtxoEntry::from).collect();
+            let mut outputs: Vec<(String, u64)> = summary
+                .recipients
+                .iter()
+                .map(|(address, amount)| (address.clone(), amount.sompi()))
+                .collect();
+            if let Some(change) = summary.change {
+                outputs.push((summary.change_address.clone(), change.sompi()));
+            }
+            state.busy.transaction = true;
+            return vec![Command::Wallet(WalletJob::Build {
+                secret: state.wallet_secret.clone(),
+                network: state.network.to_str(),
+                next_index: state.next_index(),
+                inputs,
+                outputs,
+                fee_rate: summary.fee_rate,
+                summary: Box::new(summary),
+            })];
+        }
+        Message::TransactionBuilt(result) => {
+            state.busy.transaction = false;
+            match result {
+                Ok(built) => {
+                    // What was sent can no longer be taken back.
+                    state.output_undo.clear();
+                    state.notify_with(
+                        Severity::Success,
+                        format!(
+                            "Transaction {} created ({} bytes)",
+                            built.txid,
+                            built.hex.len() / 2
+                        ),
+                        Payload {
+                            what: "Transaction",
+                            value: built.hex.clone(),
+                        },
+                    );
+                    state.result = Some(ResultPanel {
+                        hex: text_editor::Content::with_text(&built.hex),
+                        built,
+                        confirm_broadcast: false,
+                        broadcast: None,
+                        unsynced_node: false,
+                        status: None,
+                        propagation: None,
+                    });
+                }
+                Err(e) => state.notify(Severity::Error, format!("Transaction error: {}", e)),
+            }
+        }
+        Message::ResultHexAction(action) => {
+            if let Some(panel) = &mut state.result {
+                // Selecting and scrolling only; the hex is what was signed.
+                if !action.is_edit() {
+                    panel.hex.perform(action);
+                }
+            }
+        }
+        Message::CopyResultHex => {
+            if let Some(panel) = &state.result {
+                return copy("Transaction", panel.built.hex.clone());
+            }
+        }
+        Message::SaveResult => {
+            if let Some(panel) = &state.result {
+                match panel.built.pstx_json() {
+                    Ok(json) => {
+                        return vec![Command::SaveTransaction {
+                            json,
+                            txid: panel.built.txid.clone(),
+                        }]
+                    }
+                    Err(e) => state.notify(Severity::Error, describe(e)),
+                }
+            }
+        }
+        Message::ResultSaved(result) => match result {
+            Ok(Some(path)) => state.notify(
+                Severity::Success,
+                format!("Transaction saved to {}", path.display()),
+            ),
+            Ok(None) => {}
+            Err(e) => state.notify(
+                Severity::Error,
+                format!("Could not save transaction: {}", e),
+            ),
+        },
+        Message::Broadcast => {
+            if let Some(panel) = &mut state.result {
+                panel.confirm_broadcast = true;
+            }
+        }
+        Message::CancelBroadcast => {
+            if let Some(panel) = &mut state.result {
+                panel.confirm_broadcast = false;
+            }
+        }
+        Message::ConfirmBroadcast => return state.broadcast(false),
+        Message::BroadcastAnyway => return state.broadcast(true),
+        Message::Broadcasted {
+            txid,
+            result,
+            unsynced,
+        } => {
+            state.busy.broadcast = false;
+            match &result {
+                Ok(accepted) => state.notify(
+                    Severity::Success,
+                    format!("Transaction {} broadcast", accepted),
+                ),
+                Err(e) => state.notify(
+                    Severity::Error,
+                    format!("Broadcast of {} failed: {}", txid, e),
+                ),
+            }
+            let accepted = result.is_ok();
+            if let Some(panel) = state
+                .result
+                .as_mut()
+                .filter(|panel| panel.built.txid == txid)
+            {
+                panel.broadcast = Some(result);
+                panel.unsynced_node = unsynced;
+                panel.propagation = None;
+            }
+            // Its inputs are spent now.
+            let balance = state.refresh_balance();
+            if accepted {
+                return [balance, state.poll_tx_status(), state.check_propagation()]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+            }
+            return balance;
+        }
+        Message::PollTxStatus => return state.poll_tx_status(),
+        Message::TxStatusFetched { txid, result } => {
+            state.busy.tx_status = false;
+            let Some(panel) = state
+                .result
+                .as_mut()
+                .filter(|panel| panel.built.txid == txid)
+            else {
+                return Vec::new();
+            };
+            match result {
+                Ok(status) => panel.status = Some(status),
+                // The last status stands until the node answers again.
+                Err(e) => tracing::debug!(%txid, "no transaction status: {}", e),
+            }
+        }
+        Message::PropagationChecked { txid, result } => {
+            let Some(panel) = state
+                .result
+                .as_mut()
+                .filter(|panel| panel.built.txid == txid)
+            else {
+                return Vec::new();
+            };
+            match result {
+                Ok(propagation) => {
+                    panel.propagation = Some(propagation);
+                    if let Some(warning) = propagation.warning(&txid) {
+                        state.notify(Severity::Warning, warning);
+                    }
+                }
+                Err(e) => tracing::debug!(%txid, "propagation not checked: {}", e),
+            }
+        }
+        Message::CloseResult => state.result = None,
+        Message::ToggleInputs => state.show_inputs = !state.show_inputs,
+        Message::InputToggled(txid, vout) => {
+            let outpoint = (txid, vout);
+            if !state.selected_inputs.remove(&outpoint) {
+                state.selected_inputs.insert(outpoint);
+            }
+            state.refresh_max();
+        }
+        Message::RecipientInput(addr) => {
+            state.recipient = addr;
+            state.recipient_label = None;
+            state.recipient_scan_error = None;
+        }
+        Message::ContactPicked(ContactChoice(contact)) => {
+            state.recipient = contact.address;
+            state.recipient_label = Some(contact.label);
+        }
+        Message::ContactLabelInput(label) => state.contact_label = label,
+        Message::ContactAddressInput(address) => state.contact_address = address,
+        Message::ContactFilterInput(filter) => state.contact_filter = filter,
+        Message::AddContact => {
+            let network = state.network.to_str();
+            let contact = Contact {
+                label: state.contact_label.trim().to_string(),
+                address: state.contact_address.trim().to_string(),
+                network: network.to_string(),
+            };
+            let label = contact.label.clone();
+            let added = NetworkConfig::from_name(network)
+                .and_then(|config| config.check_address(&contact.address))
+                .and_then(|()| state.edit_contacts(|book| book.add(contact)));
+            match added {
+                Ok(()) => {
+                    state.contact_label.clear();
+                    state.contact_address.clear();
+                    state.contact_error = None;
+                    state.notify(Severity::Success, format!("Saved contact {}", label));
+                }
+                Err(e) => state.contact_error = Some(e.to_string()),
+            }
+        }
+        Message::DeleteContact(label) => {
+            let network = state.network.to_str();
+            match state.edit_contacts(|book| {
+                book.remove(network, &label);
+                Ok(())
+            }) {
+                Ok(()) => state.notify(Severity::Success, format!("Deleted contact {}", label)),
+                Err(e) => state.contact_error = Some(e.to_string()),
+            }
+        }
+        Message::AmountInput(amt) => {
+            state.amount = amt;
+            state.send_max = false;
+        }
+        Message::SendMax => match state.max_amount() {
+            Ok(_) => {
+                state.send_max = true;
+                state.refresh_max();
+            }
+            Err(reason) => state.notify(Severity::Warning, reason),
+        },
+        Message::ManualInputChanged(input) => state.manual_input = input,
+        Message::AddManualInput => match state.add_manual_input() {
+            Ok(()) => state.refresh_max(),
+            Err(reason) => state.notify(Severity::Error, reason),
+        },
+        Message::RemoveManualInput(index) => {
+            if index < state.manual_inputs.len() {
+                state.manual_inputs.remove(index);
+                state.refresh_max();
+            }
+        }
+        Message::AmountUnitSelected(unit) => {
+            // Carry a valid amount over so toggling never changes its value.
+            if let Some(Ok(amount)) = state.parsed_amount() {
+                state.amount = amount_text(amount, unit);
+            }
+            if let Some(edit) = &mut state.output_edit {
+                if let Some(Ok(amount)) = parse_output_amount(&edit.amount, state.amount_unit) {
+                    edit.amount = amount_text(amount, unit);
+                }
+            }
+            state.amount_unit = unit;
+        }
+        Message::AddOutput => match (state.recipient_check(), state.parsed_amount()) {
+            (None, _) | (_, None) => {
+                state.notify(Severity::Warning, "Enter recipient and amount".to_string())
+            }
+            (Some(Err(reason)), _) | (_, Some(Err(reason))) => {
+                state.notify(Severity::Error, reason)
+            }
+            (Some(Ok(())), Some(Ok(amount))) => {
+                state.outputs.push(OutputRow {
+                    address: state.recipient.trim().to_string(),
+                    amount,
+                    label: state.recipient_label.take(),
+                });
+                state.recipient.clear();
+                state.amount.clear();
+                state.send_max = false;
+                state.notify(
+                    Severity::Success,
+                    format!(
+                        "Added output {} ({} outputs total)",
+                        state.outputs.len() - 1,
+                        state.outputs.len()
+                    ),
+                );
+            }
+        },
+        Message::RemoveOutput(idx) => {
+            if let Some(rest) = undo::removed(&state.outputs, idx) {
+                // The row being edited keeps pointing at the same output.
+                let edit =
+                    state
+                        .output_edit
+                        .take()
+                        .and_then(|mut edit| match edit.index.cmp(&idx) {
+                            std::cmp::Ordering::Less => Some(edit),
+                            std::cmp::Ordering::Equal => None,
+                            std::cmp::Ordering::Greater => {
+                                edit.index -= 1;
+                                Some(edit)
+                            }
+                        });
+                let what = format!("Removed output {} ({} remaining)", idx, rest.len());
+                state.change_outputs(rest, what);
+                state.output_edit = edit;
+            }
+        }
+        Message::OpenWalletFile => {
+            return vec![Command::PickWalletFile { save: false }];
+        }
+        Message::SaveWalletFile => {
+            if state.wallet.is_none() {
+                state.notify(
+                    Severity::Warning,
+                    "Load or create a wallet first".to_string(),
+                );
+                return Vec::new();
+            }
+            return vec![Command::PickWalletFile { save: true }];
+        }
+        Message::WalletChosen(WalletChoice::Add) => {
+            return update(state, Message::OpenWalletFile);
+        }
+        Message::WalletChosen(WalletChoice::Open { fingerprint, .. }) => {
+            return state.switch_wallet(&fingerprint);
+        }
+        Message::CloseWallet => {
+            let Some(shown) = state.wallet.as_ref() else {
+                return Vec::new();
+            };
+            let fingerprint = shown.fingerprint.clone();
+            let label = wallet_label(shown, state.wallet_file.as_ref());
+            state.park_wallet();
+            // Its key and secret are `Redacted`, which zeroizes them as
+            // they drop here; parking it already emptied the key field.
+            drop(state.open_wallets.remove(&fingerprint));
+            state.notify(Severity::Info, format!("Closed {}", label));
+            if let Some(next) = state.open_wallets.keys().next().cloned() {
+                return state.switch_wallet(&next);
+            }
+        }
+        Message::OpenPathChosen(path) => {
+            let Some(path) = path else {
+                return Vec::new();
+            };
+            return vec![Command::ReadKeystore(path)];
+        }
+        Message::KeystoreRead(path, result) => match result {
+            Ok(keystore) => state.prompt_open(*keystore, path),
+            Err(e) => state.notify(Severity::Error, format!("Error opening wallet: {}", e)),
+        },
+        Message::FileDropped(path) => {
+            let name = dropped::file_name(&path);
+            if !matches!(state.screen, Screen::Wallet) {
+                state.notify(Severity::Warning, format!("Unlock first to open {}", name));
+            } else if state.prompt.is_some() || state.busy.wallet {
+                state.notify(
+                    Severity::Warning,
+                    format!("Finish the open dialog before opening {}", name),
+                );
+            } else {
+                return vec![Command::ReadDroppedFile(path)];
+            }
+        }
+        Message::DroppedFileRead(path, dropped) => match dropped {
+            Dropped::Keystore(keystore) => {
+                if state.prompt.is_none() && matches!(state.screen, Screen::Wallet) {
+                    state.prompt_open(*keystore, path);
+                }
+            }
+            Dropped::Transaction(document) => match document.transaction().txid() {
+                Ok(txid) => {
+                    state.decoded = Some(DecodedPanel {
+                        name: dropped::file_name(&path),
+                        txid,
+                        document: *document,
+                    })
+                }
+                Err(e) => state.notify(Severity::Warning, describe(e)),
+            },
+            Dropped::Unsupported(why) => state.notify(Severity::Warning, why),
+        },
+        Message::CloseDecoded => state.decoded = None,
+        Message::SavePathChosen(path) => {
+            if let Some(path) = path {
+                state.prompt = Some(PasswordPrompt {
+                    action: FileAction::Save,
+                    path,
+                    password: Redacted::default(),
+                    confirm: Redacted::default(),
+                    error: None,
+                });
+            }
+        }
+        Message::PromptPasswordInput(password) => {
+            if let Some(prompt) = &mut state.prompt {
+                prompt.password = password;
+            }
+        }
+        Message::PromptConfirmInput(confirm) => {
+            if let Some(prompt) = &mut state.prompt {
+                prompt.confirm = confirm;
+            }
+        }
+        Message::PromptSubmit => {
+            let Some(prompt) = &mut state.prompt else {
+                return Vec::new();
+            };
+            if state.busy.wallet {
+                return Vec::new();
+            }
+            let password = prompt.password.clone();
+            let path = prompt.path.clone();
+            match &prompt.action {
+                FileAction::Open(keystore) => {
+                    let keystore = keystore.clone();
+                    state.busy.start_wallet("Decrypting the wallet file…");
+                    return vec![Command::Wallet(WalletJob::OpenFile {
+                        keystore,
+                        path,
+                        password,
+                    })];
+                }
+                FileAction::Save => {
+                    if prompt.password != prompt.confirm {
+                        prompt.error = Some("Passwords do not match".to_string());
+                        return Vec::new();
+                    }
+                    if let Err(e) = crypto::check_password_strength(&password) {
+                        prompt.error = Some(e.to_string());
+                        return Vec::new();
+                    }
+                    state.busy.start_wallet("Encrypting the wallet file…");
+                    return vec![Command::Wallet(WalletJob::SaveFile {
+                        secret: state.wallet_secret.clone(),
+                        network: state.network.to_str(),
+                        path,
+                        password,
+                    })];
+                }
+            }
+        }
+        Message::PromptCancel => {
+            if !state.busy.wallet {
+                state.prompt = None;
+            }
+        }
+        Message::WalletFileOpened(result) => {
+            state.busy.wallet = false;
+            match result {
+                Ok((loaded, file)) => {
+                    state.prompt = None;
+                    let commands = state.wallet_loaded(Ok(loaded));
+                    state.notify(Severity::Success, format!("Opened {}", file.name()));
+                    state.settings.last_wallet = Some(file.path.clone());
+                    state.wallet_file = Some(file);
+                    return [commands, state.settings_changed()]
+                        .into_iter()
+                        .flatten()
+                        .collect();
+                }
+                Err(e) => {
+                    if let Some(prompt) = &mut state.prompt {
+                        prompt.error = Some(e);
+                    }
+                }
+            }
+        }
+        Message::WalletFileSaved(result) => {
+            state.busy.wallet = false;
+            match result {
+                Ok(file) => {
+                    state.prompt = None;
+                    state.notify(Severity::Success, format!("Saved {}", file.name()));
+                    state.settings.last_wallet = Some(file.path.clone());
+                    state.wallet_file = Some(file);
+                    return state.settings_changed();
+                }
+                Err(e) => {
+                    if let Some(prompt) = &mut state.prompt {
+                        prompt.error = Some(e);
+                    }
+                }
+            }
+        }
+        Message::EditOutput(idx) => {
+            if let Some(output) = state.outputs.get(idx) {
+                state.output_edit = Some(OutputEdit {
+                    index: idx,
+                    address: output.address.clone(),
+                    amount: amount_text(output.amount, state.amount_unit),
+                });
+            }
+        }
+        Message::EditOutputAddress(address) => {
+            if let Some(edit) = &mut state.output_edit {
+                edit.address = address;
+            }
+        }
+        Message::EditOutputAmount(amount) => {
+            if let Some(edit) = &mut state.output_edit {
+                edit.amount = amount;
+            }
+        }
+        Message::SaveOutputEdit => {
+            let Some(edit) = &state.output_edit else {
+                return Vec::new();
+            };
+            match edit.check(&state.network, state.amount_unit) {
+                Ok((address, amount)) => {
+                    let index = edit.index;
+                    state.output_edit = None;
+                    if let Some(output) = state.outputs.get_mut(index) {
+                        // A new address is no longer the contact it came from.
+                        if output.address != address {
+                            output.label = None;
+                        }
+                        output.address = address;
+                        output.amount = amount;
+                        state.refresh_max();
+                        state.notify(Severity::Success, format!("Updated output {}", index));
+                    }
+                }
+                Err(reason) => state.notify(Severity::Error, reason),
+            }
+        }
+        Message::CancelOutputEdit => state.output_edit = None,
+        Message::ClearOutputs => {
+            if !state.outputs.is_empty() {
+                let what = format!("Cleared {} outputs", state.outputs.len());
+                state.change_outputs(Vec::new(), what);
+            }
+        }
+        Message::UndoOutputs => {
+            if let Some((outputs, what)) = state.output_undo.undo() {
+                state.outputs = outputs;
+                state.output_edit = None;
+                state.refresh_max();
+                state.notify(Severity::Info, format!("Undone: {}", what));
+            }
+        }
+        Message::ValidateAddressInput(addr) => {
+            state.validate_address_input = addr.clone();
+            match validate_address(&addr) {
+                Ok(true) => state.validation_result = "Valid Kaspa address".to_string(),
+                Ok(false) => state.validation_result = "Invalid address format".to_string(),
+                Err(e) => state.validation_result = format!("Error: {}", e),
+            }
+        }
+        Message::Paste(target) => {
+            return vec![Command::Paste(target)];
+        }
+        Message::ScanQr(target) => {
+            return vec![Command::ScanQr(target)];
+        }
+        Message::QrScanned(_, Ok(None)) => {}
+        Message::QrScanned(target, result) => {
+            let scanned = result.and_then(|text| {
+                let text = text.unwrap_or_default();
+                let address = scanned_address(&text)?.to_string();
+                Ok((text, address))
+            });
+            match (target, scanned) {
+                (ScanTarget::Recipient, Ok((text, _))) => {
+                    state.recipient_scan_error = None;
+                    state.paste_recipient(&text);
+                }
+                (ScanTarget::Recipient, Err(e)) => state.recipient_scan_error = Some(e),
+                (ScanTarget::Validation, Ok((_, address))) => {
+                    return update(state, Message::ValidateAddressInput(address));
+                }
+                (ScanTarget::Validation, Err(e)) => {
+                    state.validation_result = format!("Error: {}", e);
+                }
+            }
+        }
+        Message::ClipboardRead(_, None) => {
+            state.notify(Severity::Error, "Could not access clipboard".to_string());
+        }
+        Message::ClipboardRead(PasteTarget::Validation, Some(text)) => {
+            let _ = update(state, Message::ValidateAddressInput(text.to_string()));
+            state.notify(Severity::Success, "Pasted from clipboard!".to_string());
+        }
+        Message::ClipboardRead(PasteTarget::PrivateKey, Some(key)) => {
+            return update(state, Message::PrivateKeyInput(key));
+        }
+        Message::ClipboardRead(PasteTarget::Recipient, Some(text)) => state.paste_recipient(&text),
+        Message::CopyAddress => {
+            if state.copy_address_text.is_empty() {
+                state.notify(Severity::Warning, "No address to copy".to_string());
+            } else {
+                return copy("Address", state.copy_address_text.clone());
+            }
+        }
+        Message::CopyPublicKey => match &state.wallet {
+            Some(wallet) => return copy("Public key", wallet.public_key.clone()),
+            None => state.notify(Severity::Warning, "No public key to copy".to_string()),
+        },
+        Message::ToggleAdvanced => state.show_advanced = !state.show_advanced,
+        Message::ExportKeyToggled(include) => state.export_key = include,
+        Message::ExportWalletInfo => {
+            let Some(info) = state.wallet_info() else {
+                state.notify(Severity::Warning, "Load or create a wallet first");
+                return Vec::new();
+            };
+            if state.export_key {
+                state.confirm_key_export = true;
+                return Vec::new();
+            }
+            return vec![Command::ExportWalletInfo(info)];
+        }
+        Message::ConfirmKeyExport => {
+            if std::mem::take(&mut state.confirm_key_export) {
+                state.export_key = false;
+                return update(state, Message::SaveWalletFile);
+            }
+        }
+        Message::WalletInfoExported(result) => match result {
+            Ok(Some(path)) => state.notify(
+                Severity::Success,
+                format!("Wallet info exported to {}", path.display()),
+            ),
+            Ok(None) => {}
+            Err(e) => state.notify(
+                Severity::Error,
+                format!("Could not export wallet info: {}", e),
+            ),
+        },
+        Message::Copied { what, ok } => {
+            if ok {
+                state.notify(Severity::Success, format!("{} copied to clipboard!", what));
+            } else {
+                state.notify(Severity::Error, "Copy failed");
+            }
+        }
+        Message::TogglePrivateKey => state.show_private_key = !state.show_private_key,
+        Message::TabSelected(tab) => {
+            state.current_tab = tab;
+            state.show_private_key = false;
+            match state.current_tab {
+                Tab::History if !state.history_loaded => return state.load_history(false, false),
+                // Spendable outputs may have changed since the last fetch.
+                Tab::Send => return state.refresh_balance(),
+                Tab::Receive => return state.check_derived_funds(),
+                _ => {}
+            }
+        }
+        Message::ExplorerUrlInput(url) => {
+            state.explorer_url = url;
+            state.history_loaded = false;
+            return state.settings_changed();
+        }
+        Message::LoadMoreHistory => return state.load_history(true, false),
+        Message::RefreshHistory => return state.load_history(false, true),
+        Message::HistoryFetched {
+            address,
+            before,
+            result,
+        } => state.history_fetched(address, before, result),
+        Message::ToggleHistoryEntry(txid) => {
+            state.history_expanded = match state.history_expanded.take() {
+                Some(expanded) if expanded == txid => None,
+                _ => Some(txid),
+            };
+        }
+        Message::FeeChoiceSelected(choice) => {
+            state.fee_choice = choice;
+            state.refresh_max();
+        }
+        Message::CustomFeeRateInput(rate) => {
+            state.custom_fee_rate = rate;
+            state.fee_choice = FeeChoice::Custom;
+            state.refresh_max();
+        }
+        Message::FeeEstimateFetched(result) => {
+            // Without suggestions the static presets apply.
+            match result {
+                Ok(estimate) => {
+                    state.suggested_fees = estimate;
+                    state.fee_estimate_error = None;
+                }
+                Err(e) => {
+                    tracing::debug!("no fee estimate from the node: {}", e);
+                    state.suggested_fees = None;
+                    state.fee_estimate_error = Some(e);
+                }
+            }
+            state.refresh_max();
+        }
+        Message::CopyTxid(txid) => return copy("Txid", txid),
+        Message::CopyContact(address) => return copy("Address", address),
+        Message::ThemeSelected(choice) => {
+            state.settings.theme = choice;
+            return state.settings_changed();
+        }
+        Message::SaveSettings(generation) => {
+            if generation == state.settings_generation {
+                let previous = state.settings.price_url.clone();
+                state.settings = state.current_settings();
+                let write = Command::WriteSettings {
+                    dir: state.settings_dir.clone(),
+                    settings: state.settings.clone(),
+                };
+                // Fetch once the new price API URL has settled.
+                if state.settings.price_url != previous {
+                    return [vec![write], state.refresh_prices()]
+                        .into_iter()
+                        .flatten()
+                        .collect();
+                }
+                return vec![write];
+            }
+        }
+        Message::SettingsWritten(result) => {
+            if let Err(e) = result {
+                state.notify(Severity::Error, format!("Could not save settings: {}", e));
+            }
+        }
+        Message::FiatSelected(fiat) => {
+            state.settings.fiat = fiat;
+            return state.settings_changed();
+        }
+        Message::PriceUrlInput(url) => {
+            state.price_url = url;
+            state.prices = None;
+            return state.settings_changed();
+        }
+        Message::RefreshPrices => return state.refresh_prices(),
+        Message::PricesFetched(result) => {
+            // Old prices are not shown as current while offline.
+            state.prices = result
+                .inspect_err(|e| tracing::debug!("no fiat price: {}", e))
+                .ok();
+        }
+        Message::SystemThemeChanged(mode) => state.system_mode = mode,
+        Message::DismissNotification(id) => state.notifications.dismiss(id),
+        Message::CopyNotification(id) => {
+            if let Some(payload) = state
+                .notifications
+                .get(id)
+                .and_then(|entry| entry.payload.clone())
+            {
+                return copy(payload.what, payload.value);
+            }
+        }
+        Message::ToggleActivity => {
+            state.settings.show_activity = !state.settings.show_activity;
+            return state.settings_changed();
+        }
+        Message::ClearActivity => state.notifications.clear(),
+        Message::CopyLog => {
+            let log = state.activity_log();
+            return copy("Log", log);
+        }
+        Message::Tick => {
+            let now = state.now();
+            state.notifications.expire(now);
+            if state
+                .pending_clear
+                .as_ref()
+                .is_some_and(|pending| pending.at <= now)
+            {
+                return state.clear_clipboard();
+            }
+            if state.auto_lock_at().is_some_and(|at| at <= now) {
+                return state.lock();
+            }
+        }
+        Message::AutoLockSelected(choice) => {
+            state.settings.auto_lock_minutes = choice.0;
+            return state.settings_changed();
+        }
+        Message::UiScaleSelected(choice) => {
+            state.settings.ui_scale = choice.0;
+            return state.settings_changed();
+        }
+        Message::ClipboardClearSelected(choice) => {
+            state.settings.clipboard_clear_secs = choice.0;
+            return state.settings_changed();
+        }
+        Message::Lock => return state.lock(),
+        Message::TrayReady => state.tray_ready = true,
+        Message::Tray(TrayAction::Show) => return vec![Command::ShowWindow],
+        Message::Tray(TrayAction::Lock) => {
+            if state.wallet.is_some() {
+                return state.lock();
+            }
+        }
+        Message::Tray(TrayAction::Quit) => return vec![Command::Exit],
+        Message::MinimizeToTrayToggled(on) => {
+            state.settings.minimize_to_tray = on;
+            return state.settings_changed();
+        }
+        Message::CloseRequested(id) => {
+            if state.settings.minimize_to_tray && state.tray_ready {
+                return vec![Command::HideWindow(id)];
+            }
+            return vec![Command::Exit];
+        }
+        Message::UnlockInput(secret) => {
+            if let Screen::Locked(lock) = &mut state.screen {
+                lock.secret = secret;
+            }
+        }
+        Message::UnlockSubmit => {
+            let (Screen::Locked(lock), Some(wallet)) = (&mut state.screen, &state.wallet) else {
+                return Vec::new();
+            };
+            if state.busy.wallet {
+                return Vec::new();
+            }
+            let secret = std::mem::take(&mut lock.secret);
+            let address = wallet.address.clone();
+            let Some(file) = &state.wallet_file else {
+                // Without a file the key or phrase itself unlocks; checking
+                // it is quick.
+                let result = state
+                    .backend
+                    .unlock_with_key(&secret, &wallet.network_name, &wallet.fingerprint)
+                    .map_err(describe);
+                return state.unlocked(address, result);
+            };
+            state.busy.start_wallet("Decrypting the wallet file…");
+            return vec![Command::Wallet(WalletJob::UnlockFile {
+                path: file.path.clone(),
+                address,
+                password: secret,
+            })];
+        }
+        Message::Unlocked { address, result } => return state.unlocked(address, result),
+    }
+    Vec::new()
+}
+
+/// Opens the last keystore file again, once the current one is closed.
+fn view_reopen(state: &WalletGuiState) -> Option<Element<'_, Message>> {
+    let path = state.settings.last_wallet.as_ref()?;
+    if state
+        .wallet_file
+        .as_ref()
+        .is_some_and(|file| &file.path == path)
+    {
+        return None;
+    }
+    let name = path.file_name()?.to_string_lossy();
+    Some(
+        button(text(format!("Reopen {}", name)))
+            .on_press_maybe(
+                (!state.busy.wallet).then(|| Message::OpenPathChosen(Some(path.clone()))),
+            )
+            .into(),
+    )
+}
+
+fn view(state: &WalletGuiState) -> Element<'_, Message> {
+    if let Screen::Backup(backup) = &state.screen {
+        return stack![view_backup(backup), view_toasts(state)].into();
+    }
+    if let Screen::Locked(lock) = &state.screen {
+        return stack![view_locked(state, lock), view_toasts(state)].into();
+    }
+    let networks = vec![
+        NetworkOption::Mainnet,
+        NetworkOption::Testnet10,
+        NetworkOption::Testnet11,
+        NetworkOption::Simnet,
+    ];
+
+    let wallet_info = if let Some(wallet) = &state.wallet {
+        let fact = |label, value: Element<'static, Message>| {
+            column![text(label).size(style::SMALL), value].spacing(2)
+        };
+        column![
+            text("Wallet Information").size(style::HEADING),
+            row![
+                fact(
+                    "Network",
+                    text(wallet.network_name.clone()).size(style::LARGE).into()
+                ),
+                fact(
+                    "Key type",
+                    text(wallet.key_type()).size(style::LARGE).into()
+                ),
+                fact(
+                    "Fingerprint",
+                    Mono { size: style::LARGE }
+                        .text(wallet.fingerprint.clone())
+                        .into()
+                ),
+            ]
+            .spacing(40),
+            text("Address:").size(style::BODY),
+            row![
+                Mono::ADDRESS.text(&wallet.address).width(Length::Fill),
+                button("Copy").on_press(Message::CopyAddress),
+            ],
+            text("Wallet File:").size(style::BODY),
+            text(match &state.wallet_file {
+                Some(file) => file.name(),
+                None => "(not saved)".to_string(),
+            })
+            .size(style::BODY),
+            row![
+                button("Export…").on_press(Message::ExportWalletInfo),
+                checkbox(state.export_key)
+                    .label("Include private key (as an encrypted keystore)")
+                    .on_toggle(Message::ExportKeyToggled)
+                    .text_size(style::BODY),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            view_advanced(state, wallet),
+            view_balance(state),
+        ]
+        .spacing(5)
+    } else {
+        column![
+            text("No wallet loaded").size(style::HEADING),
+            text("Create a new wallet or load an existing one below").size(style::BODY),
+        ]
+    };
+
+    let key_check = key_feedback(&state.private_key);
+    let settings_info = column![
+        text("Wallet Settings").size(style::HEADING),
+        text("Network:").size(style::BODY),
+        pick_list(
+            networks,
+            Some(state.network.clone()),
+            Message::NetworkSelected
+        ),
+        text("Node URL:").size(style::BODY),
+        text_input("grpc://127.0.0.1:16110", &state.node_url).on_input(Message::NodeUrlInput),
+        text("Explorer URL:").size(style::BODY),
+        text_input("http://localhost:8000", &state.explorer_url)
+            .on_input(Message::ExplorerUrlInput),
+        text("Price API URL (approximate fiat values):").size(style::BODY),
+        text_input("http://localhost:8080/api/v3", &state.price_url)
+            .on_input(Message::PriceUrlInput),
+        text("Fiat currency:").size(style::BODY),
+        pick_list(Fiat::ALL, Some(state.settings.fiat), Message::FiatSelected),
+        text("Theme:").size(style::BODY),
+        pick_list(
+            ThemeChoice::ALL,
+            Some(state.settings.theme),
+            Message::ThemeSelected
+        ),
+        text("Text size:").size(style::BODY),
+        pick_list(
+            UiScale::ALL,
+            Some(UiScale(state.settings.ui_scale)),
+            Message::UiScaleSelected
+        ),
+        text("Lock after inactivity:").size(style::BODY),
+        pick_list(
+            AutoLock::ALL,
+            Some(AutoLock(state.settings.auto_lock_minutes)),
+            Message::AutoLockSelected
+        ),
+        text("Clear a copied private key after:").size(style::BODY),
+        pick_list(
+            ClearAfter::ALL,
+            Some(ClearAfter(state.settings.clipboard_clear_secs)),
+            Message::ClipboardClearSelected
+        ),
+        checkbox(state.settings.minimize_to_tray)
+            .label(if state.tray_ready {
+                "Keep running in the tray when the window is closed"
+            } else {
+                "Keep running in the tray when the window is closed (no tray available)"
+            })
+            .on_toggle(Message::MinimizeToTrayToggled)
+            .text_size(style::BODY),
+        button("Keyboard Shortcuts").on_press(Message::ShowShortcuts),
+        row![
+            button("Open Wallet…")
+                .on_press_maybe((!state.busy.wallet).then_some(Message::OpenWalletFile)),
+            button("Save Wallet…").on_press_maybe(
+                (!state.busy.wallet && state.wallet.is_some()).then_some(Message::SaveWalletFile)
+            ),
+        ]
+        .push(view_reopen(state))
+        .spacing(10),
+        text("Private Key (advanced):").size(style::BODY),
+        row![
+            text_input("Enter private key (hex)", &state.private_key)
+                .secure(!state.show_private_key)
+                .on_input(|key| Message::PrivateKeyInput(key.into()))
+                .style(style::validated_input(
+                    key_check.as_ref().map(Result::is_ok)
+                ))
+                .width(Length::Fill),
+            button("Paste").on_press(Message::Paste(PasteTarget::PrivateKey)),
+            button(if state.show_private_key {
+                "Hide"
+            } else {
+                "Show"
+            })
+            .on_press(Message::TogglePrivateKey),
+            button("Copy Private Key").on_press_maybe(
+                (state.wallet.is_some() && !state.wallet_key.is_empty())
+                    .then_some(Message::CopyPrivateKey)
+            ),
+        ]
+        .spacing(10),
+        match key_check {
+            Some(Err(reason)) => text(reason).size(style::SMALL).style(text::danger),
+            Some(Ok(())) => text("Valid private key")
+                .size(style::SMALL)
+                .style(text::success),
+            None => text(""),
+        },
+        row![
+            button("Load Wallet")
+                .on_press_maybe((!state.busy.wallet).then_some(Message::LoadWallet)),
+            button("Create New Wallet")
+                .on_press_maybe((!state.busy.wallet).then_some(Message::CreateWallet)),
+            pick_list(
+                SUPPORTED_WORD_COUNTS,
+                Some(state.seed_words),
+                Message::SeedWordsSelected
+            ),
+            text("words").size(style::BODY),
+            spinner(state.busy.wallet, state.busy.wallet_task),
+        ]
+        .spacing(10),
+        text("Warning: Never share your private key!").size(style::SMALL),
+    ];
+
+    let combined_section =
+        column![wallet_info, text("---").size(style::SMALL), settings_info,].spacing(15);
+
+    let tab_row = row![
+        button("Send")
+            .on_press(Message::TabSelected(Tab::Send))
+            .style(if state.current_tab == Tab::Send {
+                button::primary
+            } else {
+                button::secondary
+            }),
+        button("Receive")
+            .on_press(Message::TabSelected(Tab::Receive))
+            .style(if state.current_tab == Tab::Receive {
+                button::primary
+            } else {
+                button::secondary
+            }),
+        button("History")
+            .on_press(Message::TabSelected(Tab::History))
+            .style(if state.current_tab == Tab::History {
+                button::primary
+            } else {
+                button::secondary
+            }),
+        button("Contacts")
+            .on_press(Message::TabSelected(Tab::Contacts))
+            .style(if state.current_tab == Tab::Contacts {
+                button::primary
+            } else {
+                button::secondary
+            }),
+    ]
+    .spacing(10);
+
+    let content: Column<Message> = match state.current_tab {
+        Tab::Send => view_send(state),
+        Tab::Receive => view_receive(state),
+        Tab::History => view_history(state),
+        Tab::Contacts => view_contacts(state),
+    };
+
+    // Only the header and the Activity drawer stay put; everything between
+    // scrolls in a small window.
+    let body = scrollable(
+        column![combined_section, tab_row, content]
+            .spacing(20)
+            .padding(iced::Padding::ZERO.right(15)),
+    )
+    .height(Length::Fill);
+    let page = Container::new(
+        column![view_header(state), body, view_activity(state)]
+            .spacing(20)
+            .padding(20),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .center_x(Length::Fill)
+    .center_y(Length::Fill);
+    let page = stack![page, view_toasts(state)];
+
+    if let Some(summary) = &state.confirm_send {
+        modal(page, view_confirm_send(state, summary))
+    } else if let Some(prompt) = &state.prompt {
+        modal(page, view_prompt(state, prompt))
+    } else if state.confirm_key_export {
+        modal(page, view_key_export())
+    } else if let Some(acknowledged) = state.key_copy {
+        modal(
+            page,
+            view_key_copy(acknowledged, state.settings.clipboard_clear_secs),
+        )
+    } else if let Some(panel) = &state.result {
+        modal(page, view_result(state, panel))
+    } else if let Some(panel) = &state.decoded {
+        modal(page, view_decoded(panel))
+    } else if state.show_shortcuts {
+        modal(page, view_shortcuts())
+    } else {
+        page.into()
+    }
+}
+
+/// The wallet's keys, collapsed until Advanced is clicked: they identify
+/// the wallet to other software but are not addresses to pay.
+fn view_advanced<'a>(state: &WalletGuiState, wallet: &'a KaspaGuiWallet) -> Column<'a, Message> {
+    let toggle = button(if state.show_advanced {
+        "Hide Advanced"
+    } else {
+        "Advanced"
+    })
+    .style(button::text)
+    .on_press(Message::ToggleAdvanced);
+    if !state.show_advanced {
+        return column![toggle];
+    }
+    column![
+        toggle,
+        text("Public key (compressed secp256k1, hex). Not an address; do not send funds to it.")
+            .size(style::SMALL),
+        row![
+            Mono::HEX.text(&wallet.public_key).width(Length::Fill),
+            button("Copy").on_press(Message::CopyPublicKey),
+        ]
+        .spacing(10),
+    ]
+    .push(wallet.account_xpub.as_ref().map(|xpub| {
+        column![
+            text("Account extended public key (xpub), for watch-only copies:").size(style::SMALL),
+            Mono::HEX.text(xpub),
+        ]
+    }))
+    .spacing(5)
+}
+
+/// The app name, and the switcher between open wallets.
+fn view_header(state: &WalletGuiState) -> Element<'_, Message> {
+    let badge = container(text(state.network.to_string()).size(style::BODY))
+        .padding([4, 10])
+        .style(state.network.tint().badge());
+    row![
+        text("KaspaRustWallet")
+            .size(style::TITLE)
+            .width(Length::Fill),
+        badge,
+        pick_list(
+            state.wallet_choices(),
+            state.shown_choice(),
+            Message::WalletChosen
+        )
+        .placeholder("Add wallet…"),
+        button("Lock").on_press_maybe(state.wallet.is_some().then_some(Message::Lock)),
+        button("Close").on_press_maybe(state.wallet.is_some().then_some(Message::CloseWallet)),
+    ]
+    .spacing(10)
+    .align_y(iced::Alignment::Center)
+    .into()
+}
+
+/// The newest toasts, stacked in the bottom right corner.
+fn view_toasts(state: &WalletGuiState) -> Container<'_, Message> {
+    let mut toasts = state
+        .notifications
+        .toasts()
+        .fold(column![].spacing(10), |col, toast| {
+            col.push(view_toast(toast))
+        });
+    if let Some(what) = state.output_undo.offer(state.now()) {
+        toasts = toasts.push(
+            container(
+                row![
+                    text(what).size(style::BODY).width(Length::Fill),
+                    button("Undo").on_press(Message::UndoOutputs),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            )
+            .width(360)
+            .padding(10)
+            .style(container::bordered_box),
+        );
+    }
+    if let Some(pending) = &state.pending_clear {
+        let left = pending.at.saturating_sub(state.now()).div_ceil(1000);
+        toasts = toasts.push(
+            container(
+                row![
+                    text(format!("Clipboard clears in {} s", left))
+                        .size(style::BODY)
+                        .style(text::warning)
+                        .width(Length::Fill),
+                    button("Clear now").on_press(Message::ClearClipboard),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            )
+            .width(360)
+            .padding(10)
+            .style(container::bordered_box),
+        );
+    }
+    container(toasts)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(20)
+        .align_right(Length::Fill)
+        .align_bottom(Length::Fill)
+}
+
+fn view_toast(toast: &Notification) -> Container<'_, Message> {
+    let severity = toast.severity;
+    let mut content = row![text(&toast.text)
+        .size(style::BODY)
+        .style(severity.style())
+        .width(Length::Fill)]
+    .spacing(10)
+    .align_y(iced::Alignment::Center);
+    if toast.payload.is_some() {
+        content = content.push(button("Copy").on_press(Message::CopyNotification(toast.id)));
+    }
+    content = content.push(
+        button("×")
+            .on_press(Message::DismissNotification(toast.id))
+            .style(button::text),
+    );
+    container(content)
+        .width(360)
+        .padding(10)
+        .style(move |theme| {
+            let mut style = container::bordered_box(theme);
+            style.border.color = severity.color(theme);
+            style
+        })
+}
+
+/// The Activity drawer along the bottom: every recent notification, newest
+/// first, with the time it came in.
+fn view_activity(state: &WalletGuiState) -> Column<'_, Message> {
+    let open = state.settings.show_activity;
+    let toggle = button(text(format!(
+        "{} Activity ({})",
+        if open { "▾" } else { "▸" },
+        state.notifications.len()
+    )))
+    .on_press(Message::ToggleActivity)
+    .style(button::text);
+    if !open {
+        return column![toggle];
+    }
+    let list = if state.notifications.is_empty() {
+        column![text("Nothing yet").size(style::SMALL)]
+    } else {
+        state
+            .notifications
+            .activity()
+            .fold(column![].spacing(4), |col, entry| {
+                let mut line = row![
+                    text(format_time(entry.time)).size(style::SMALL).width(140),
+                    text(&entry.text)
+                        .size(style::SMALL)
+                        .style(entry.severity.style())
+                        .width(Length::Fill),
+                ]
+                .spacing(10);
+                if entry.payload.is_some() {
+                    line = line.push(button("Copy").on_press(Message::CopyNotification(entry.id)));
+                }
+                col.push(line)
+            })
+    };
+    let has_entries = !state.notifications.is_empty();
+    column![
+        row![
+            container(toggle).width(Length::Fill),
+            button("Copy log").on_press_maybe(has_entries.then_some(Message::CopyLog)),
+            button("Clear").on_press_maybe(has_entries.then_some(Message::ClearActivity)),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center),
+        scrollable(list).height(160),
+    ]
+    .spacing(5)
+}
+
+/// `dialog` over a dimmed `base`; clicking beside it dismisses it.
+fn modal<'a>(
+    base: impl Into<Element<'a, Message>>,
+    dialog: impl Into<Element<'a, Message>>,
+) -> Element<'a, Message> {
+    let backdrop = center(opaque(dialog)).style(|_| container::Style {
+        background: Some(Color::from_rgba(0.0, 0.0, 0.0, 0.6).into()),
+        ..container::Style::default()
+    });
+    stack![
+        base.into(),
+        opaque(mouse_area(backdrop).on_press(Message::DismissModal)),
+    ]
+    .into()
+}
+
+fn view_confirm_send<'a>(
+    state: &WalletGuiState,
+    summary: &'a SendSummary,
+) -> Container<'a, Message> {
+    // Fiat values are shown beside the amounts and never replace them.
+    let with_fiat = |amount: Kas| match state.fiat_value(amount) {
+        Some(value) => format!("{} KAS ({})", amount, value),
+        None => format!("{} KAS", amount),
+    };
+    let recipients =
+        summary
+            .recipients
+            .iter()
+            .fold(column![].spacing(4), |col, (address, amount)| {
+                col.push(
+                    row![
+                        Mono::HEX.text(address).width(Length::Fill),
+                        text(with_fiat(*amount)).size(style::SMALL),
+                    ]
+                    .spacing(10),
+                )
+            });
+    let change = match summary.change {
+        Some(change) => format!("{} KAS to {}", change, summary.change_address),
+        None => "none".to_string(),
+    };
+    let total_in = Kas::from_sompi(summary.inputs.iter().map(|utxo| utxo.amount).sum());
+    let tint = state.network.tint();
+    let mismatch = state.network_mismatch().map(|home| {
+        checkbox(state.network_acknowledged)
+            .label(format!(
+                "This wallet was loaded on {}. Send on {} anyway",
+                home, state.network
+            ))
+            .on_toggle(Message::NetworkMismatchAcknowledged)
+            .text_size(style::BODY)
+    });
+    let confirmable = mismatch.is_none() || state.network_acknowledged;
+    let dialog = column![
+        text("Confirm Transaction").size(style::HEADING),
+        text(format!("Network: {}", state.network))
+            .size(style::LARGE)
+            .style(move |theme: &Theme| text::Style {
+                color: tint.color(theme),
+            }),
+        text(format!("{} recipient(s):", summary.recipients.len())).size(style::BODY),
+        // Long batches scroll so the totals below stay in view.
+        scrollable(recipients).height(if summary.recipients.len() > 8 {
+            Length::Fixed(200.0)
+        } else {
+            Length::Shrink
+        }),
+        text(format!(
+            "Inputs: {} ({} KAS)",
+            summary.inputs.len(),
+            total_in
+        ))
+        .size(style::BODY),
+        text(format!(
+            "Fee: ~{} ({} sompi per 1000 grams)",
+            with_fiat(summary.fee),
+            summary.fee_rate
+        ))
+        .size(style::BODY),
+        text(format!("Change: {}", change)).size(style::BODY),
+        text(format!("Total debit: {}", with_fiat(summary.total))).size(style::LARGE),
+        state.fiat_value(summary.total).map(|_| {
+            text("Fiat values are approximate; the transaction is in KAS only.")
+                .size(style::SMALL)
+                .style(text::warning)
+        }),
+        mismatch,
+        row![
+            button("Confirm")
+                .on_press_maybe(confirmable.then_some(Message::ConfirmSend))
+                .style(tint.button()),
+            button("Cancel").on_press(Message::CancelSend),
+        ]
+        .spacing(10),
+    ]
+    .spacing(8);
+    container(dialog.width(520))
+        .padding(20)
+        .style(tint.dialog())
+}
+
+/// The built transaction: what it pays, its hex, and what to do with it.
+fn view_result<'a>(state: &WalletGuiState, panel: &'a ResultPanel) -> Container<'a, Message> {
+    let built = &panel.built;
+    let summary = &built.summary;
+    let outputs = summary
+        .recipients
+        .iter()
+        .fold(column![].spacing(4), |col, (address, amount)| {
+            col.push(
+                row![
+                    Mono::HEX.text(address).width(Length::Fill),
+                    text(format!("{} KAS", amount)).size(style::SMALL),
+                ]
+                .spacing(10),
+            )
+        });
+    let outputs = match summary.change {
+        Some(change) => outputs.push(
+            row![
+                Mono::HEX.text(&summary.change_address).width(Length::Fill),
+                text(format!("{} KAS change", change)).size(style::SMALL),
+            ]
+            .spacing(10),
+        ),
+        None => outputs,
+    };
+    let hex = text_editor(&panel.hex)
+        .on_action(Message::ResultHexAction)
+        .font(iced::Font::MONOSPACE)
+        .size(Mono::HEX.size)
+        .wrapping(text::Wrapping::WordOrGlyph)
+        .height(Length::Fixed(140.0));
+
+    let node_set = !state.node_url.trim().is_empty();
+    let broadcast: Element<'_, Message> = if state.busy.broadcast {
+        button("Broadcasting…").into()
+    } else if !node_set {
+        with_tip(
+            button("Broadcast"),
+            text("Set a node in Settings to broadcast").size(style::SMALL),
+        )
+    } else if matches!(panel.broadcast, Some(Ok(_))) {
+        button("Broadcast").into()
+    } else {
+        button("Broadcast")
+            .on_press(Message::Broadcast)
+            .style(button::success)
+            .into()
+    };
+    let actions = row![
+        button("Copy Hex").on_press(Message::CopyResultHex),
+        button("Save to file…").on_press(Message::SaveResult),
+        broadcast,
+        button("Close").on_press(Message::CloseResult),
+    ]
+    .spacing(10);
+
+    let status: Option<Element<'_, Message>> = match &panel.broadcast {
+        Some(Ok(txid)) => {
+            let broadcast = match panel.propagation {
+                Some(propagation) if propagation.warning(txid).is_some() => {
+                    text(format!("Broadcast {}; {}", txid, propagation))
+                        .size(style::BODY)
+                        .style(text::warning)
+                }
+                Some(propagation) => text(format!("Broadcast {}; {}", txid, propagation))
+                    .size(style::BODY)
+                    .style(text::success),
+                None => text(format!("Broadcast; the node accepted {}", txid))
+                    .size(style::BODY)
+                    .style(text::success),
+            };
+            // A chip with how far it got, once the node was asked.
+            Some(match &panel.status {
+                Some(status) => row![
+                    broadcast,
+                    container(text(status.to_string()).size(style::SMALL))
+                        .padding([2, 8])
+                        .style(container::bordered_box),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center)
+                .into(),
+                None => broadcast.into(),
+            })
+        }
+        Some(Err(e)) => {
+            let failed = text(format!("Broadcast failed: {}", e))
+                .size(style::BODY)
+                .style(text::danger);
+            Some(if panel.unsynced_node && !state.busy.broadcast {
+                column![
+                    failed,
+                    button("Broadcast Anyway")
+                        .on_press(Message::BroadcastAnyway)
+                        .style(button::danger),
+                ]
+                .spacing(8)
+                .into()
+            } else {
+                failed.into()
+            })
+        }
+        None => None,
+    };
+    let confirm = panel.confirm_broadcast.then(|| {
+        container(
+            column![
+                text(format!(
+                    "Broadcast to {} on {}? Once the node accepts it, it cannot be recalled.",
+                    state.node_url.trim(),
+                    summary.network
+                ))
+                .size(style::BODY),
+                row![
+                    button("Confirm Broadcast")
+                        .on_press(Message::ConfirmBroadcast)
+                        .style(button::danger),
+                    button("Cancel").on_press(Message::CancelBroadcast),
+                ]
+                .spacing(10),
+            ]
+            .spacing(8),
+        )
+        .padding(10)
+        .style(container::bordered_box)
+    });
+
+    let dialog = column![
+        text("Transaction Built").size(style::HEADING),
+        row![text("Txid:").size(style::BODY), Mono::HEX.text(&built.txid)].spacing(10),
+        text(format!("Network: {}", summary.network)).size(style::BODY),
+        text(format!(
+            "Outputs ({}):",
+            summary.recipients.len() + usize::from(summary.change.is_some())
+        ))
+        .size(style::BODY),
+        scrollable(outputs).height(if summary.recipients.len() > 6 {
+            Length::Fixed(160.0)
+        } else {
+            Length::Shrink
+        }),
+        text(format!(
+            "Fee: {} KAS ({} sompi per 1000 grams)",
+            summary.fee, summary.fee_rate
+        ))
+        .size(style::BODY),
+        text(format!("Hex ({} bytes):", built.hex.len() / 2)).size(style::BODY),
+        hex,
+        status,
+        confirm,
+        actions,
+    ]
+    .spacing(8);
+    container(dialog.width(560))
+        .padding(20)
+        .style(container::rounded_box)
+}
+
+fn view_prompt<'a>(
+    state: &'a WalletGuiState,
+    prompt: &'a PasswordPrompt,
+) -> Container<'a, Message> {
+    let saving = matches!(prompt.action, FileAction::Save);
+    let (heading, action) = if saving {
+        ("Save Wallet", "Save")
+    } else {
+        ("Open Wallet", "Open")
+    };
+    let password = text_input("Password", &prompt.password)
+        .secure(true)
+        .on_input(|password| Message::PromptPasswordInput(password.into()))
+        .on_submit(Message::PromptSubmit);
+    let mut fields = column![
+        text(heading).size(style::HEADING),
+        text(prompt.path.display().to_string()).size(style::SMALL),
+        password,
+    ]
+    .spacing(10);
+    if saving {
+        fields = fields.push(
+            text_input("Repeat password", &prompt.confirm)
+                .secure(true)
+                .on_input(|confirm| Message::PromptConfirmInput(confirm.into()))
+                .on_submit(Message::PromptSubmit),
+        );
+    }
+    if let Some(error) = &prompt.error {
+        fields = fields.push(text(error).size(style::SMALL).style(text::danger));
+    }
+    let idle = !state.busy.wallet;
+    fields = fields.push(
+        row![
+            button(action).on_press_maybe(idle.then_some(Message::PromptSubmit)),
+            button("Cancel").on_press_maybe(idle.then_some(Message::PromptCancel)),
+            spinner(state.busy.wallet, state.busy.wallet_task),
+        ]
+        .spacing(10),
+    );
+    container(fields)
+        .padding(20)
+        .width(420)
+        .style(container::rounded_box)
+}
+
+/// The unlock screen: the wallet file's password, or for a wallet with no
+/// file its key.
+fn view_locked<'a>(state: &'a WalletGuiState, lock: &'a LockScreen) -> Container<'a, Message> {
+    let label = state
+        .wallet
+        .as_ref()
+        .map(|wallet| wallet_label(wallet, state.wallet_file.as_ref()))
+        .unwrap_or_default();
+    let (hint, placeholder) = match &state.wallet_file {
+        Some(file) => (
+            format!("Enter the password for {} to unlock it.", file.name()),
+            "Password",
+        ),
+        None if state
+            .wallet
+            .as_ref()
+            .is_some_and(|w| w.account_xpub.is_some()) =>
+        {
+            (
+                "This wallet has no file; enter its seed phrase to unlock it.".to_string(),
+                "Seed phrase",
+            )
+        }
+        None => (
+            "This wallet has no file; enter its private key to unlock it.".to_string(),
+            "Private key (hex)",
+        ),
+    };
+    let idle = !state.busy.wallet;
+    container(
+        column![
+            text("Wallet Locked").size(style::TITLE),
+            text(label).size(style::BODY),
+            text(hint).size(style::BODY),
+            text_input(placeholder, &lock.secret)
+                .secure(true)
+                .on_input(|secret| Message::UnlockInput(secret.into()))
+                .on_submit(Message::UnlockSubmit),
+            lock.error
+                .as_ref()
+                .map(|error| text(error).size(style::SMALL).style(text::danger)),
+            lock.resume
+                .as_ref()
+                .map(|_| text("What you started continues once unlocked.").size(style::SMALL)),
+            row![
+                button("Unlock").on_press_maybe(idle.then_some(Message::UnlockSubmit)),
+                button("Close Wallet").on_press_maybe(idle.then_some(Message::CloseWallet)),
+                spinner(state.busy.wallet, state.busy.wallet_task),
+            ]
+            .spacing(10),
+        ]
+        .spacing(15)
+        .padding(20)
+        .width(420),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .center_x(Length::Fill)
+    .center_y(Length::Fill)
+}
+
+/// The backup screen: the words in a numbered grid, then the quiz.
+fn view_backup(backup: &Backup) -> Container<'_, Message> {
+    let body = match backup.step {
+        BackupStep::Words => {
+            let words: Vec<&str> = backup.phrase.0.words().collect();
+            let grid = words.chunks(3).enumerate().fold(
+                column![].spacing(8),
+                |grid, (row_index, chunk)| {
+                    grid.push(chunk.iter().enumerate().fold(
+                        row![].spacing(8),
+                        |line, (column_index, word)| {
+                            let index = row_index * 3 + column_index;
+                            let shown = if backup.hovered == Some(index) {
+                                *word
+                            } else {
+                                "•••••"
+                            };
+                            line.push(
+                                mouse_area(
+                                    container(Mono::ADDRESS.text(format!(
+                                        "{:>2}. {}",
+                                        index + 1,
+                                        shown
+                                    )))
+                                    .padding(8)
+                                    .width(160)
+                                    .style(container::bordered_box),
+                                )
+                                .on_enter(Message::BackupWordHovered(Some(index)))
+                                .on_exit(Message::BackupWordHovered(None)),
+                            )
+                        },
+                    ))
+                },
+            );
+            column![
+                text(format!(
+                    "Write these {} words down, in order, and keep them offline.",
+                    words.len()
+                ))
+                .size(style::BODY),
+                text("Hover over a word to show it. Anyone who sees them can take your funds.")
+                    .size(style::SMALL)
+                    .style(text::warning),
+                grid,
+                button("I wrote it down").on_press(Message::BackupWrittenDown),
+            ]
+        }
+        BackupStep::Quiz => {
+            let questions = backup
+                .positions
+                .iter()
+                .zip(&backup.answers)
+                .enumerate()
+                .fold(
+                    column![].spacing(8),
+                    |col, (question, (position, answer))| {
+                        col.push(
+                            row![
+                                text(format!("Word #{}", position))
+                                    .size(style::BODY)
+                                    .width(100),
+                                text_input("", answer)
+                                    .on_input(move |answer| {
+                                        Message::BackupAnswerInput(question, answer.into())
+                                    })
+                                    .on_submit(Message::BackupSubmit)
+                                    .width(200),
+                            ]
+                            .spacing(10)
+                            .align_y(iced::Alignment::Center),
+                        )
+                    },
+                );
+            let error = match &backup.error {
+                Some(e) => text(e).size(style::SMALL).style(text::danger),
+                None => text(""),
+            };
+            column![
+                text("Enter these words from your copy to confirm it.").size(style::BODY),
+                questions,
+                error,
+                row![
+                    button("Confirm").on_press(Message::BackupSubmit),
+                    button("Show the words again").on_press(Message::BackupShowWords),
+                ]
+                .spacing(10),
+            ]
+        }
+    };
+    container(
+        column![
+            text("Back Up Your Seed Phrase").size(style::TITLE),
+            body.spacing(15),
+            button(text("Skip (not recommended)").size(style::SMALL))
+                .on_press(Message::BackupSkip)
+                .style(button::text),
+        ]
+        .spacing(20)
+        .padding(20),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .center_x(Length::Fill)
+    .center_y(Length::Fill)
+}
+
+/// The warning before the private key is copied.
+fn view_decoded(panel: &DecodedPanel) -> Container<'_, Message> {
+    let tx = panel.document.transaction();
+    let (kind, network) = match &panel.document {
+        TxDocument::Raw(_) => (
+            "Raw transaction",
+            tx.outputs
+                .first()
+                .and_then(|output| address_network(&output.address))
+                .map(|network| network.to_string()),
+        ),
+        TxDocument::Pstx(pstx) => ("PSTX file", Some(pstx.network.clone())),
+    };
+    let signed = tx
+        .inputs
+        .iter()
+        .filter(|input| input.signature.is_some())
+        .count();
+    let outputs = tx.outputs.iter().fold(column![].spacing(4), |col, output| {
+        col.push(
+            row![
+                Mono::HEX.text(&output.address).width(Length::Fill),
+                text(format!("{} KAS", Kas::from_sompi(output.amount))).size(style::SMALL),
+            ]
+            .spacing(10),
+        )
+    });
+    let fee = match tx.fee() {
+        Some(fee) => format!("Fee: {} KAS", Kas::from_sompi(fee)),
+        None => "Fee: unknown, the amounts spent are not in the file".to_string(),
+    };
+
+    let dialog = column![
+        text(kind).size(style::HEADING),
+        text(&panel.name).size(style::SMALL),
+        row![text("Txid:").size(style::BODY), Mono::HEX.text(&panel.txid)].spacing(10),
+        text(format!(
+            "Network: {}",
+            network.as_deref().unwrap_or("unknown")
+        ))
+        .size(style::BODY),
+        text(format!("Inputs: {} ({} signed)", tx.inputs.len(), signed)).size(style::BODY),
+        text(format!("Outputs ({}):", tx.outputs.len())).size(style::BODY),
+        scrollable(outputs).height(if tx.outputs.len() > 6 {
+            Length::Fixed(160.0)
+        } else {
+            Length::Shrink
+        }),
+        text(format!(
+            "Total out: {} KAS",
+            Kas::from_sompi(tx.total_output_amount())
+        ))
+        .size(style::BODY),
+        text(fee).size(style::BODY),
+        text(format!("Mass: {} grams", tx.mass())).size(style::BODY),
+        row![
+            button("Copy Txid").on_press(Message::CopyTxid(panel.txid.clone())),
+            button("Close").on_press(Message::CloseDecoded),
+        ]
+        .spacing(10),
+    ]
+    .spacing(8);
+    container(dialog.width(560))
+        .padding(20)
+        .style(container::rounded_box)
+}
+
+fn view_shortcuts<'a>() -> Container<'a, Message> {
+    let list = shortcuts::SHORTCUTS
+        .iter()
+        .fold(column![].spacing(8), |col, (keys, action)| {
+            col.push(
+                row![
+                    Mono::ADDRESS.text(*keys).width(140),
+                    text(*action).size(style::BODY).width(Length::Fill),
+                ]
+                .spacing(10),
+            )
+        });
+    container(
+        column![
+            text("Keyboard Shortcuts").size(style::HEADING),
+            list,
+            text("Use Cmd in place of Ctrl on macOS. Only Escape works while a password or phrase is being typed.")
+                .size(style::SMALL),
+            button("Close").on_press(Message::DismissModal),
+        ]
+        .spacing(10),
+    )
+    .padding(20)
+    .width(480)
+    .style(container::rounded_box)
+}
+
+fn view_key_copy<'a>(acknowledged: bool, clear_secs: u64) -> Container<'a, Message> {
+    container(
+        column![
+            text("Copy Private Key").size(style::HEADING),
+            text("Anyone with this key controls your funds.").style(text::danger),
+            text(format!(
+                "Paste it only into software you trust. The clipboard is cleared after {} seconds.",
+                clear_secs
+            ))
+            .size(style::SMALL),
+            checkbox(acknowledged)
+                .label("I understand")
+                .on_toggle(Message::KeyCopyAcknowledged),
+            row![
+                button("Copy").on_press_maybe(acknowledged.then_some(Message::ConfirmKeyCopy)),
+                button("Cancel").on_press(Message::DismissModal),
+            ]
+            .spacing(10),
+        ]
+        .spacing(10),
+    )
+    .padding(20)
+    .width(420)
+    .style(container::rounded_box)
+}
+
+fn view_key_export<'a>() -> Container<'a, Message> {
+    container(
+        column![
+            text("Export Private Key").size(style::HEADING),
+            text("Anyone with the file and its password controls your funds.")
+                .style(text::danger),
+            text("The wallet is saved as a keystore, encrypted with a password you choose next. The key is never written in plain text.")
+                .size(style::SMALL),
+            row![
+                button("Continue")
+                    .on_press(Message::ConfirmKeyExport)
+                    .style(button::danger),
+                button("Cancel").on_press(Message::DismissModal),
+            ]
+            .spacing(10),
+        ]
+        .spacing(10),
+    )
+    .padding(20)
+    .width(420)
+    .style(container::rounded_box)
+}
+
+/// Says what an operation is doing next to its controls while it runs.
+fn spinner(busy: bool, doing: &'static str) -> iced::widget::Text<'static> {
+    text(if busy { doing } else { "" }).size(style::SMALL)
+}
+
+fn view_balance(state: &WalletGuiState) -> Column<'_, Message> {
+    if state.node_url.trim().is_empty() {
+        return column![text("Balance: set a node URL in Settings to fetch it").size(style::BODY)];
+    }
+    let figures = match &state.balance {
+        Some(balance) => column![
+            text(format!("Balance: {} KAS", Kas::from_sompi(balance.total))).size(style::LARGE),
+            state
+                .fiat_value(Kas::from_sompi(balance.total))
+                .map(|value| text(format!("{} (approximate)", value)).size(style::SMALL)),
+            text(format!("Pending: {} KAS", Kas::from_sompi(balance.pending))).size(style::BODY),
+            text(format!("UTXOs: {}", balance.utxo_count)).size(style::BODY),
+            text(format!(
+                "Last updated: {} UTC",
+                format_time(balance.updated)
+            ))
+            .size(style::SMALL),
+        ],
+        None => column![text("Balance: not fetched yet").size(style::BODY)],
+    };
+    let refresh = row![
+        button("Refresh").on_press_maybe((!state.busy.balance).then_some(Message::RefreshBalance)),
+        spinner(state.busy.balance, "Fetching the balance…"),
+    ]
+    .spacing(10);
+    let error = match &state.balance_error {
+        Some(e) => text(format!("Could not fetch balance: {}", e))
+            .size(style::SMALL)
+            .style(text::danger),
+        None => text(""),
+    };
+    column![figures, refresh, error].spacing(5)
+}
+
+fn view_send(state: &WalletGuiState) -> Column<'_, Message> {
+    let outputs_list: Column<Message> = if state.outputs.is_empty() {
+        column![text("No outputs added yet")]
+    } else {
+        state
+            .outputs
+            .iter()
+            .enumerate()
+            .fold(column![].spacing(4), |col, (idx, output)| {
+                if let Some(edit) = state.output_edit.as_ref().filter(|edit| edit.index == idx) {
+                    return col.push(view_output_edit(state, edit));
+                }
+                col.push(
+                    row![
+                        text(match &output.label {
+                            Some(label) => format!("{}: {}", idx, label),
+                            None => format!("{}:", idx),
+                        }),
+                        container(short_address(&output.address)).width(Length::Fill),
+                        text(format!(
+                            "{} KAS ({} sompi)",
+                            output.amount,
+                            thousands(output.amount.sompi())
+                        )),
+                        button("Edit").on_press(Message::EditOutput(idx)),
+                        button("Remove").on_press(Message::RemoveOutput(idx)),
+                    ]
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center),
+                )
+            })
+    };
+
+    // Without a node there is no balance, so nothing to spend unless the
+    // outputs are entered by hand.
+    let no_node = state.node_url.trim().is_empty().then(|| {
+        container(
+            text(
+                "No node is set, so the wallet's outputs cannot be fetched. Add the \
+                 outputs to spend under Inputs, or set a node URL in Settings.",
+            )
+            .size(style::SMALL)
+            .style(text::warning),
+        )
+        .width(Length::Fill)
+        .padding(10)
+        .style(container::bordered_box)
+    });
+    let send = button("Send Transaction").style(state.network.tint().button());
+    let send: Element<Message> = if state.has_inputs() {
+        send.on_press_maybe((!state.busy.transaction).then_some(Message::SendTransaction))
+            .into()
+    } else {
+        with_tip(send, text(state.no_inputs_reason()).size(style::SMALL))
+    };
+
+    column![
+        text("Send Transaction").size(style::HEADING),
+        no_node,
+        text("Add recipients:").size(style::BODY),
+        row![
+            view_recipient(state),
+            pick_list(
+                state
+                    .contacts
+                    .iter()
+                    .cloned()
+                    .map(ContactChoice)
+                    .collect::<Vec<_>>(),
+                state
+                    .contacts
+                    .iter()
+                    .find(|contact| state.recipient_label.as_ref() == Some(&contact.label))
+                    .cloned()
+                    .map(ContactChoice),
+                Message::ContactPicked,
+            )
+            .placeholder("Contacts"),
+        ]
+        .spacing(10),
+        row![
+            view_amount(state),
+            button("Add").on_press_maybe(state.can_add_output().then_some(Message::AddOutput)),
+        ]
+        .spacing(10),
+        // A long batch scrolls on its own so the fee and Send stay close.
+        scrollable(outputs_list).height(if state.outputs.len() > OUTPUTS_SHOWN {
+            Length::Fixed(180.0)
+        } else {
+            Length::Shrink
+        }),
+        view_inputs(state),
+        view_fee(state),
+        row![
+            send,
+            button("Clear All").on_press(Message::ClearOutputs),
+            spinner(state.busy.transaction, "Signing…"),
+        ]
+        .spacing(10),
+        text("Note: 1 KAS = 100,000,000 sompi").size(style::SMALL),
+    ]
+}
+
+/// The collapsible coin control list of the wallet's outputs.
+fn view_inputs(state: &WalletGuiState) -> Column<'_, Message> {
+    let toggle = button(text(if state.show_inputs {
+        "▾ Inputs"
+    } else {
+        "▸ Inputs"
+    }))
+    .on_press(Message::ToggleInputs)
+    .style(button::text);
+    let summary = match state.selected_total() {
+        None => text("Automatic coin selection").size(style::SMALL),
+        Some((selected, required)) => {
+            let line = text(format!(
+                "Selected {} KAS vs required {} KAS",
+                Kas::from_sompi(selected),
+                Kas::from_sompi(required)
+            ))
+            .size(style::SMALL);
+            if selected < required {
+                line.style(text::danger)
+            } else {
+                line
+            }
+        }
+    };
+    let no_node = state.node_url.trim().is_empty();
+    let refresh = (!no_node).then(|| {
+        button(text("Refresh").size(style::SMALL))
+            .on_press_maybe((!state.busy.balance).then_some(Message::RefreshBalance))
+            .style(button::secondary)
+    });
+    let header = row![
+        toggle,
+        summary,
+        refresh,
+        spinner(state.busy.balance, "Fetching the balance…")
+    ]
+    .spacing(10)
+    .align_y(iced::Alignment::Center);
+    if no_node && state.balance.is_none() {
+        // Always open: these are the only inputs there can be.
+        return column![header, view_manual_inputs(state)].spacing(5);
+    }
+    if !state.show_inputs {
+        return column![header];
+    }
+    let Some(balance) = &state.balance else {
+        return column![
+            header,
+            text("Fetch the balance to list the wallet's outputs").size(style::SMALL)
+        ];
+    };
+    let mut utxos: Vec<&Utxo> = balance.utxos.iter().collect();
+    utxos.sort_by_key(|utxo| std::cmp::Reverse(utxo.amount));
+    let list = utxos.into_iter().fold(column![].spacing(4), |col, utxo| {
+        let mature = utxo.is_mature(balance.virtual_daa_score);
+        let age = match utxo.age(balance.virtual_daa_score) {
+            Some(age) => format!("age {} DAA", thousands(age)),
+            None => "age unknown".to_string(),
+        };
+        let label = format!(
+            "{} KAS · {} · {}:{}{}",
+            Kas::from_sompi(utxo.amount),
+            age,
+            shorten(&utxo.txid),
+            utxo.vout,
+            if mature { "" } else { " (immature)" }
+        );
+        let (txid, vout) = utxo.outpoint();
+        col.push(
+            checkbox(state.selected_inputs.contains(&utxo.outpoint()))
+                .label(label)
+                .text_size(style::SMALL)
+                .on_toggle_maybe(
+                    mature.then_some(move |_| Message::InputToggled(txid.clone(), vout)),
+                ),
+        )
+    });
+    column![header, scrollable(list).height(160)].spacing(5)
+}
+
+/// Entry for outputs to spend when no node can list them.
+fn view_manual_inputs(state: &WalletGuiState) -> Column<'_, Message> {
+    let entry = row![
+        text_input("txid:vout:amount", &state.manual_input)
+            .on_input(Message::ManualInputChanged)
+            .on_submit(Message::AddManualInput),
+        button("Add").on_press_maybe(
+            (!state.manual_input.trim().is_empty()).then_some(Message::AddManualInput)
+        ),
+    ]
+    .spacing(10);
+    let list =
+        state
+            .manual_inputs
+            .iter()
+            .enumerate()
+            .fold(column![].spacing(4), |col, (idx, utxo)| {
+                col.push(
+                    row![
+                        text(format!(
+                            "{} KAS · {}:{}",
+                            Kas::from_sompi(utxo.amount),
+                            shorten(&utxo.txid),
+                            utxo.vout
+                        ))
+                        .size(style::SMALL)
+                        .width(Length::Fill),
+                        button(text("Remove").size(style::SMALL))
+                            .on_press(Message::RemoveManualInput(idx)),
+                    ]
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center),
+                )
+            });
+    column![
+        entry,
+        text("All of them are spent. Amounts are sompi, or KAS with a decimal point.")
+            .size(style::SMALL),
+        list
+    ]
+    .spacing(5)
+}
+
+/// The amount field with its KAS/sompi toggle and, below it, the amount in
+/// the other unit or why it does not parse.
+fn view_amount(state: &WalletGuiState) -> Column<'_, Message> {
+    let parsed = state.parsed_amount();
+    let input = text_input(amount_placeholder(state.amount_unit), &state.amount)
+        .on_input(Message::AmountInput)
+        .style(style::validated_input(
+            parsed.as_ref().map(|parsed| parsed.is_ok()),
+        ));
+    let toggle = [(Unit::Kas, "KAS"), (Unit::Sompi, "sompi")]
+        .into_iter()
+        .fold(row![].spacing(2), |row, (unit, label)| {
+            row.push(
+                button(text(label).size(style::SMALL))
+                    .on_press(Message::AmountUnitSelected(unit))
+                    .style(if state.amount_unit == unit {
+                        button::primary
+                    } else {
+                        button::secondary
+                    }),
+            )
+        });
+    // Disabled with the reason on hover when there is nothing to send.
+    let max: Element<Message> = match state.max_amount() {
+        Ok(_) => button(text("Max").size(style::SMALL))
+            .on_press(Message::SendMax)
+            .into(),
+        Err(reason) => with_tip(
+            button(text("Max").size(style::SMALL)),
+            text(reason).size(style::SMALL),
+        ),
+    };
+    let hint = match parsed {
+        None => text(""),
+        Some(Ok(amount)) => match state.amount_unit {
+            Unit::Kas => text(format!("= {} sompi", thousands(amount.sompi()))),
+            Unit::Sompi => text(format!("= {} KAS", amount)),
+        },
+        Some(Err(reason)) => text(reason).style(text::danger),
+    };
+    column![row![input, toggle, max].spacing(5), hint.size(style::SMALL)]
+        .spacing(2)
+        .width(Length::Fill)
+}
+
+/// The recipient field, bordered by whether it holds an address on the
+/// selected network, with the reason below when it does not.
+fn amount_placeholder(unit: Unit) -> &'static str {
+    match unit {
+        Unit::Kas => "Amount (KAS)",
+        Unit::Sompi => "Amount (sompi)",
+    }
+}
+
+/// An output row open for editing, checked as the Add fields are.
+fn view_output_edit<'a>(state: &WalletGuiState, edit: &'a OutputEdit) -> Column<'a, Message> {
+    let address_check = check_recipient(&edit.address, &state.network);
+    let amount_check = parse_output_amount(&edit.amount, state.amount_unit);
+    let error = match (&address_check, &amount_check) {
+        (Some(Err(reason)), _) | (_, Some(Err(reason))) => text(reason.clone()).style(text::danger),
+        _ => text(""),
+    };
+    let valid = edit.check(&state.network, state.amount_unit).is_ok();
+    column![
+        row![
+            text(format!("{}:", edit.index)),
+            text_input("Recipient address", &edit.address)
+                .on_input(Message::EditOutputAddress)
+                .on_submit(Message::SaveOutputEdit)
+                .style(style::validated_input(
+                    address_check.as_ref().map(|check| check.is_ok()),
+                )),
+            text_input(amount_placeholder(state.amount_unit), &edit.amount)
+                .on_input(Message::EditOutputAmount)
+                .on_submit(Message::SaveOutputEdit)
+                .style(style::validated_input(
+                    amount_check.as_ref().map(|check| check.is_ok()),
+                ))
+                .width(160),
+            button("Save").on_press_maybe(valid.then_some(Message::SaveOutputEdit)),
+            button("Cancel").on_press(Message::CancelOutputEdit),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center),
+        error.size(style::SMALL),
+    ]
+    .spacing(2)
+}
+
+fn view_recipient(state: &WalletGuiState) -> Column<'_, Message> {
+    let check = state.recipient_check();
+    // An address on another network is valid, just not here; it reads as
+    // a warning with a way over rather than as a mistake.
+    let other_network = state.recipient_network();
+    let input = text_input("Recipient address", &state.recipient).on_input(Message::RecipientInput);
+    let input = if other_network.is_some() {
+        input.style(style::warned_input)
+    } else {
+        input.style(style::validated_input(
+            check.as_ref().map(|check| check.is_ok()),
+        ))
+    };
+    let hint = match (&state.recipient_scan_error, check) {
+        (Some(reason), _) => text(reason).style(text::danger),
+        (None, Some(Err(reason))) if other_network.is_some() => text(reason).style(text::warning),
+        (None, Some(Err(reason))) => text(reason).style(text::danger),
+        _ => text(""),
+    };
+    let switch = other_network.map(|network| {
+        button(text(format!("Switch to {}", network)).size(style::SMALL))
+            .on_press(Message::SwitchNetwork(network))
+            .style(button::secondary)
+    });
+    column![
+        row![
+            input,
+            button("Paste").on_press(Message::Paste(PasteTarget::Recipient)),
+            button("Scan from image…").on_press(Message::ScanQr(ScanTarget::Recipient)),
+        ]
+        .spacing(5),
+        row![hint.size(style::SMALL)]
+            .push(switch)
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+    ]
+    .spacing(2)
+    .width(Length::Fill)
+}
+
+/// `3120` as `3,120`.
+fn thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
+
+fn view_fee(state: &WalletGuiState) -> Column<'_, Message> {
+    let buckets = state
+        .suggested_fees
+        .map(|estimate| [estimate.low, estimate.normal, estimate.priority]);
+    let presets = FEE_RATE_PRESETS
+        .iter()
+        .zip(state.preset_rates())
+        .enumerate()
+        .fold(row![].spacing(10), |row, (i, ((name, _), rate))| {
+            let choice = FeeChoice::Preset(i);
+            let mut label = name.to_string();
+            label[..1].make_ascii_uppercase();
+            let source = match buckets.map(|buckets| buckets[i].estimated_time_label()) {
+                Some(Some(time)) => format!(" (node, {})", time),
+                Some(None) => " (node)".to_string(),
+                None => String::new(),
+            };
+            row.push(
+                button(text(format!("{} {}{}", label, rate, source)))
+                    .on_press(Message::FeeChoiceSelected(choice))
+                    .style(if state.fee_choice == choice {
+                        button::primary
+                    } else {
+                        button::secondary
+                    }),
+            )
+        });
+    let custom = text_input("Custom rate", &state.custom_fee_rate)
+        .on_input(Message::CustomFeeRateInput)
+        .width(140);
+    let estimate = match state.fee_estimate() {
+        Ok(estimate) => text(format!("Estimated fee: {}", estimate)).size(style::SMALL),
+        Err(e) => text(e).size(style::SMALL).style(text::danger),
+    };
+    let mut fee = column![
+        text("Fee rate (sompi per 1000 grams):").size(style::BODY),
+        presets.push(custom),
+    ]
+    .spacing(5);
+    if let Some(e) = &state.fee_estimate_error {
+        fee = fee.push(
+            text(format!(
+                "Node fee estimate unavailable ({}); using built-in rates",
+                e
+            ))
+            .size(style::SMALL)
+            .style(text::warning),
+        );
+    }
+    fee.push(estimate)
+}
+
+/// `content` with `tip` shown below it on hover.
+fn with_tip<'a>(
+    content: impl Into<Element<'a, Message>>,
+    tip: iced::widget::Text<'a>,
+) -> Element<'a, Message> {
+    tooltip(
+        content,
+        container(tip).padding(5).style(container::rounded_box),
+        tooltip::Position::Bottom,
+    )
+    .into()
+}
+
+/// `address` shortened in the middle, in full on hover.
+fn short_address(address: &str) -> Element<'_, Message> {
+    with_tip(
+        Mono::ADDRESS.text(shorten(address)),
+        Mono::HEX.text(address),
+    )
+}
+
+/// `kaspa:1C6Rc3…LRTaD` style, for addresses in narrow columns.
+fn shorten(address: &str) -> String {
+    let chars: Vec<char> = address.chars().collect();
+    if chars.len() <= 20 {
+        return address.to_string();
+    }
+    let head: String = chars[..12].iter().collect();
+    let tail: String = chars[chars.len() - 6..].iter().collect();
+    format!("{}…{}", head, tail)
+}
+
+fn signed_kas(sompi: i64) -> String {
+    let sign = if sompi < 0 { "-" } else { "+" };
+    format!("{}{} KAS", sign, Kas::from_sompi(sompi.unsigned_abs()))
+}
+
+fn view_history_entry<'a>(
+    state: &'a WalletGuiState,
+    entry: &'a HistoryEntry,
+) -> Column<'a, Message> {
+    let direction = match entry.direction {
+        Direction::In => "in",
+        Direction::Out => "out",
+    };
+    let fee = match (entry.direction, entry.fee) {
+        (Direction::Out, Some(fee)) => format!("fee {} KAS", Kas::from_sompi(fee)),
+        _ => String::new(),
+    };
+    let summary = row![
+        text(format_time(entry.time)).size(style::SMALL).width(130),
+        text(direction).size(style::SMALL).width(30),
+        container(entry.counterparty.as_deref().map(short_address)).width(Length::Fill),
+        text(signed_kas(entry.net_sompi))
+            .size(style::SMALL)
+            .width(140),
+        text(fee).size(style::SMALL).width(120),
+        text(if entry.accepted {
+            "confirmed"
+        } else {
+            "pending"
+        })
+        .size(style::SMALL)
+        .width(80),
+    ]
+    .spacing(10);
+    let mut item = column![button(summary)
+        .style(button::text)
+        .width(Length::Fill)
+        .on_press(Message::ToggleHistoryEntry(entry.txid.clone()))];
+
+    if state.history_expanded.as_ref() == Some(&entry.txid) {
+        let outputs = entry.outputs.iter().fold(column![], |col, output| {
+            col.push(
+                text(format!(
+                    "  {}  {} KAS",
+                    output.address.as_deref().unwrap_or("(non-standard script)"),
+                    Kas::from_sompi(output.amount)
+                ))
+                .size(style::SMALL),
+            )
+        });
+        let mass = entry
+            .mass
+            .map(|mass| mass.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        let fee = entry
+            .fee
+            .map(|fee| format!("{} KAS", Kas::from_sompi(fee)))
+            .unwrap_or_else(|| "unknown".to_string());
+        item = item.push(
+            column![
+                row![
+                    Mono::HEX
+                        .text(format!("Txid: {}", entry.txid))
+                        .width(Length::Fill),
+                    button("Copy").on_press(Message::CopyTxid(entry.txid.clone())),
+                ]
+                .spacing(10),
+                text("Outputs:").size(style::SMALL),
+                outputs,
+                text(format!("Mass: {}  Fee: {}", mass, fee)).size(style::SMALL),
+            ]
+            .spacing(5)
+            .padding(10),
+        );
+    }
+    item
+}
+
+fn view_contacts(state: &WalletGuiState) -> Column<'_, Message> {
+    let contacts = state.filtered_contacts();
+    let list: Column<Message> = if contacts.is_empty() {
+        column![text(if state.contacts.is_empty() {
+            "No contacts on this network yet"
+        } else {
+            "No contacts match"
+        })
+        .size(style::BODY)]
+    } else {
+        contacts
+            .into_iter()
+            .fold(column![].spacing(5), |col, contact| {
+                col.push(
+                    row![
+                        text(&contact.label).size(style::BODY).width(160),
+                        container(short_address(&contact.address)).width(Length::Fill),
+                        button("Copy").on_press(Message::CopyContact(contact.address.clone())),
+                        button("Delete").on_press(Message::DeleteContact(contact.label.clone())),
+                    ]
+                    .spacing(10),
+                )
+            })
+    };
+    let error = match &state.contact_error {
+        Some(e) => text(e).size(style::SMALL).style(text::danger),
+        None => text(""),
+    };
+    column![
+        text(format!("Contacts ({})", state.network)).size(style::HEADING),
+        text_input("Search", &state.contact_filter).on_input(Message::ContactFilterInput),
+        scrollable(list).height(240),
+        text("Add contact:").size(style::BODY),
+        row![
+            text_input("Label", &state.contact_label)
+                .on_input(Message::ContactLabelInput)
+                .width(160),
+            text_input("Address", &state.contact_address)
+                .on_input(Message::ContactAddressInput)
+                .on_submit(Message::AddContact)
+                .width(Length::Fill),
+            button("Add").on_press(Message::AddContact),
+        ]
+        .spacing(10),
+        error,
+    ]
+    .spacing(10)
+}
+
+fn view_history(state: &WalletGuiState) -> Column<'_, Message> {
+    if state.wallet.is_none() {
+        return column![
+            text("History").size(style::HEADING),
+            text("No wallet loaded. Create or load a wallet first.").size(style::BODY),
+        ];
+    }
+    if state.explorer_url.trim().is_empty() {
+        return column![
+            text("History").size(style::HEADING),
+            text("Set an explorer URL in Settings to see the history.").size(style::BODY),
+        ];
+    }
+
+    let entries = if state.history.is_empty() && state.history_loaded {
+        column![text("No transactions").size(style::BODY)]
+    } else {
+        state.history.iter().fold(column![], |col, entry| {
+            col.push(view_history_entry(state, entry))
+        })
+    };
+    let load_more = (!state.busy.history && state.history_loaded && !state.history_complete)
+        .then_some(Message::LoadMoreHistory);
+    let error = match &state.history_error {
+        Some(e) => text(format!("Could not fetch history: {}", e))
+            .size(style::SMALL)
+            .style(text::danger),
+        None => text(""),
+    };
+
+    column![
+        text("History").size(style::HEADING),
+        scrollable(entries).height(320),
+        row![
+            button("Load More").on_press_maybe(load_more),
+            button("Refresh")
+                .on_press_maybe((!state.busy.history).then_some(Message::RefreshHistory)),
+            spinner(state.busy.history, "Fetching history…"),
+        ]
+        .spacing(10),
+        error,
+    ]
+    .spacing(10)
+}
+
+fn view_qr(state: &WalletGuiState) -> Column<'_, Message> {
+    let Some(handle) = &state.qr_image else {
+        return column![];
+    };
+    let error = match &state.qr_error {
+        Some(e) => text(format!("Not in the QR code: {}", e))
+            .size(style::SMALL)
+            .style(text::danger),
+        None => text(""),
+    };
+    column![row![
+        image(handle.clone()).width(200).height(200),
+        column![
+            text("Request amount (KAS, optional):").size(style::BODY),
+            text_input("0.0", &state.request_amount).on_input(Message::RequestAmountInput),
+            button("Save QR…").on_press(Message::SaveQr),
+            error,
+        ]
+        .spacing(10)
+        .width(Length::Fill),
+    ]
+    .spacing(20)]
+}
+
+/// The receive addresses handed out for an HD wallet, with New Address;
+/// for a single-key wallet, why there is only the one.
+fn view_derived_addresses(state: &WalletGuiState) -> Column<'_, Message> {
+    let Some(wallet) = &state.wallet else {
+        return column![];
+    };
+    if wallet.account_xpub.is_none() {
+        return column![text(
+            "This wallet has a single key, so it has one fixed address. \
+             Create a wallet from a seed phrase for a new address per payment."
+        )
+        .size(style::SMALL)];
+    }
+    let checked = !state.node_url.trim().is_empty();
+    let list = state
+        .derived_addresses
+        .iter()
+        .rev()
+        .fold(column![].spacing(5), |col, derived| {
+            let funds = match derived.funded {
+                Some(true) => text("Holds funds").size(style::SMALL).style(text::success),
+                Some(false) => text("No funds").size(style::SMALL),
+                None if checked => text("Checking…").size(style::SMALL),
+                None => text(""),
+            };
+            col.push(
+                row![
+                    text(format!("#{}", derived.index))
+                        .size(style::BODY)
+                        .width(50),
+                    container(short_address(&derived.address)).width(Length::Fill),
+                    container(funds).width(90),
+                    button("Copy").on_press(Message::CopyDerivedAddress(derived.index)),
+                ]
+                .spacing(10),
+            )
+        });
+    column![
+        row![
+            text("Receive addresses:")
+                .size(style::BODY)
+                .width(Length::Fill),
+            button("New Address").on_press(Message::NewAddress),
+        ]
+        .spacing(10),
+        scrollable(list).height(160),
+    ]
+    .spacing(5)
+}
+
+fn view_receive(state: &WalletGuiState) -> Column<'_, Message> {
+    let current_address = if !state.address_result.is_empty() {
+        state.address_result.clone()
+    } else {
+        state
+            .wallet
+            .as_ref()
+            .map(|w| w.address.clone())
+            .unwrap_or_default()
+    };
+
+    let address_section = if !current_address.is_empty() {
+        let addr = current_address.clone();
+        column![
+            text("Address:").size(style::BODY),
+            row![
+                Mono::ADDRESS.text(addr).width(Length::Fill),
+                button("Copy").on_press(Message::CopyAddress),
+            ],
+        ]
+    } else {
+        column![text("No address available. Create or load a wallet first.").size(style::BODY)]
+    };
+
+    column![
+        text("Receive").size(style::HEADING),
+        address_section,
+        view_qr(state),
+        view_derived_addresses(state),
+        text("Validate Address:").size(style::BODY),
+        row![
+            text_input(
+                "Paste address here to validate",
+                &state.validate_address_input
+            )
+            .on_input(Message::ValidateAddressInput)
+            .width(Length::Fill),
+            button("Paste").on_press(Message::Paste(PasteTarget::Validation)),
+            button("Scan from image…").on_press(Message::ScanQr(ScanTarget::Validation)),
+        ]
+        .spacing(5),
+        if !state.validation_result.is_empty() {
+            text(&state.validation_result).size(style::BODY)
+        } else {
+            text("")
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::SOMPI_PER_KAS;
+    use iced::theme::Palette;
+    use std::cell::Cell;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Text of the newest notification.
+    fn last_message(state: &WalletGuiState) -> &str {
+        state
+            .notifications
+            .activity()
+            .next()
+            .map_or("", |entry| entry.text.as_str())
+    }
+
+    fn newest_severity(state: &WalletGuiState) -> Severity {
+        state.notifications.activity().next().unwrap().severity
+    }
+
+    /// A single-key wallet with a random key.
+    fn create_wallet(network: &str) -> Result<LoadedWallet, WalletError> {
+        let secp = secp256k1::Secp256k1::new();
+        let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let wallet = KaspaWallet::with_network(secret_key, network)?;
+        Ok(LoadedWallet {
+            created: true,
+            ..LoadedWallet::new(&wallet)?
+        })
+    }
+
+    /// A clock that moves only when told.
+    #[derive(Debug)]
+    struct ManualClock(Cell<u64>);
+
+    impl ManualClock {
+        fn advance(&self, millis: u64) {
+            self.0.set(self.0.get() + millis);
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> u64 {
+            self.0.get()
+        }
+    }
+
+    /// A data dir and a settings dir of the test's own, so that nothing
+    /// the user saved leaks in and nothing a test saves leaks out.
+    fn temp_dirs() -> (PathBuf, PathBuf) {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let root = std::env::temp_dir().join(format!(
+            "kasparustwallet-gui-state-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        (root.join("data"), root.join("settings"))
+    }
+
+    fn idle_state() -> WalletGuiState {
+        let (data_dir, settings_dir) = temp_dirs();
+        WalletGuiState::new(data_dir, settings_dir)
+    }
+
+    #[test]
+    fn test_state_reads_only_the_dirs_it_is_given() {
+        let (data_dir, settings_dir) = temp_dirs();
+        let config = Config {
+            node_url: Some("grpc://10.0.0.3:16110".to_string()),
+            explorer_url: Some("https://explorer.example".to_string()),
+            ..Config::default()
+        };
+        config.save(&data_dir).unwrap();
+        let settings = GuiSettings {
+            network: Some("testnet-11".to_string()),
+            price_url: Some("https://prices.example".to_string()),
+            ..GuiSettings::default()
+        };
+        settings.save(&settings_dir).unwrap();
+
+        let state = WalletGuiState::new(data_dir.clone(), settings_dir.clone());
+        assert_eq!(state.node_url, "grpc://10.0.0.3:16110");
+        assert_eq!(state.explorer_url, "https://explorer.example");
+        assert_eq!(state.price_url, "https://prices.example");
+        assert_eq!(state.network, NetworkOption::Testnet11);
+        assert_eq!(state.data_dir, data_dir);
+        assert_eq!(state.settings_dir, settings_dir);
+
+        let state = idle_state();
+        assert!(state.node_url.is_empty() && state.price_url.is_empty());
+        assert_eq!(state.settings, GuiSettings::default());
+        assert!(state.contacts.is_empty());
+        let _ = std::fs::remove_dir_all(data_dir.parent().unwrap());
+    }
+
+    /// Carries out commands as the iced shell does, with the clipboard in
+    /// memory and the settings, keystores and wallet work done in place.
+    /// Timers, the network, dialogs and the window are left in `pending`.
+    #[derive(Default)]
+    struct Shell {
+        clipboard: Option<String>,
+        pending: Vec<Command>,
+    }
+
+    impl Shell {
+        fn send(&mut self, state: &mut WalletGuiState, message: Message) {
+            let mut queue = VecDeque::from(update(state, message));
+            while let Some(command) = queue.pop_front() {
+                let reply = match command {
+                    Command::Copy { what, text } => {
+                        self.clipboard = Some(text);
+                        Message::Copied { what, ok: true }
+                    }
+                    Command::CopyKey(key) => {
+                        self.clipboard = Some(key.to_string());
+                        Message::PrivateKeyCopied(true)
+                    }
+                    Command::ClearClipboard(text) => {
+                        let held = self.clipboard.as_deref() == Some(&*text);
+                        if held {
+                            self.clipboard = None;
+                        }
+                        Message::ClipboardCleared(held)
+                    }
+                    Command::Paste(target) => {
+                        Message::ClipboardRead(target, self.clipboard.clone().map(Redacted::from))
+                    }
+                    Command::WriteSettings { dir, settings } => {
+                        Message::SettingsWritten(write_settings(&dir, &settings))
+                    }
+                    Command::ReadKeystore(path) => {
+                        let result = read_keystore(&path);
+                        Message::KeystoreRead(path, result)
+                    }
+                    Command::Wallet(job) => {
+                        let backend = Arc::clone(&state.backend);
+                        run_wallet_job(backend.as_ref(), job)
+                    }
+                    other => {
+                        self.pending.push(other);
+                        continue;
+                    }
+                };
+                queue.extend(update(state, reply));
+            }
+        }
+    }
+
+    /// Refuses everything, as a damaged keystore or a bad key would.
+    #[derive(Debug)]
+    struct RefusingBackend;
+
+    impl RefusingBackend {
+        fn refuse<T>() -> Result<T, WalletError> {
+            Err(WalletError::Storage("refused".to_string()))
+        }
+    }
+
+    impl WalletBackend for RefusingBackend {
+        fn create(&self, _: &str, _: usize) -> Result<LoadedWallet, WalletError> {
+            Self::refuse()
+        }
+
+        fn load(&self, _: &str, _: &str) -> Result<LoadedWallet, WalletError> {
+            Self::refuse()
+        }
+
+        fn build_transaction(
+            &self,
+            _: &str,
+            _: &str,
+            _: [u32; 2],
+            _: Vec<UtxoEntry>,
+            _: Vec<(String, u64)>,
+            _: u64,
+        ) -> Result<Transaction, WalletError> {
+            Self::refuse()
+        }
+
+        fn open_file(
+            &self,
+            _: &Keystore,
+            _: PathBuf,
+            _: &str,
+        ) -> Result<(LoadedWallet, WalletFile), WalletError> {
+            Self::refuse()
+        }
+
+        fn save_file(
+            &self,
+            _: &str,
+            _: &str,
+            _: PathBuf,
+            _: &str,
+        ) -> Result<WalletFile, WalletError> {
+            Self::refuse()
+        }
+
+        fn unlock_file(&self, _: PathBuf, _: &str, _: &str) -> Result<LoadedWallet, WalletError> {
+            Self::refuse()
+        }
+
+        fn unlock_with_key(&self, _: &str, _: &str, _: &str) -> Result<LoadedWallet, WalletError> {
+            Self::refuse()
+        }
+    }
+
+    #[test]
+    fn test_wallet_loads_through_a_task() {
+        let key = create_wallet("mainnet").unwrap().private_key;
+        let mut state = idle_state();
+        state.private_key = key.clone();
+
+        let _ = update(&mut state, Message::LoadWallet);
+        assert!(state.busy.wallet);
+        assert!(state.wallet.is_none());
+
+        let loaded = load_wallet(&key, "mainnet").map_err(|e| e.to_string());
+        let _ = update(&mut state, Message::WalletLoaded(loaded));
+        assert!(!state.busy.wallet);
+        assert!(last_message(&state).starts_with("Wallet loaded: kaspa:"));
+        let address = &state.wallet.as_ref().unwrap().address;
+        assert_eq!(&state.copy_address_text, address);
+    }
+
+    #[test]
+    fn test_private_key_is_masked() {
+        let key = create_wallet("mainnet").unwrap().private_key;
+        let mut state = idle_state();
+        let _ = update(
+            &mut state,
+            Message::PrivateKeyInput(format!(" {}\n", &*key).into()),
+        );
+        assert_eq!(state.private_key, key);
+        assert_eq!(last_message(&state), "Private key is valid");
+        let _ = update(
+            &mut state,
+            Message::SavePathChosen(Some(PathBuf::from("x"))),
+        );
+        let _ = update(
+            &mut state,
+            Message::PromptPasswordInput("hunter2 hunter2".to_string().into()),
+        );
+        let debug = format!("{:?}", state);
+        assert!(!debug.contains(&*key), "{}", debug);
+        assert!(!debug.contains("hunter2"));
+
+        let _ = update(&mut state, Message::TogglePrivateKey);
+        assert!(state.show_private_key);
+        let _ = update(&mut state, Message::TabSelected(Tab::Receive));
+        assert!(!state.show_private_key);
+    }
+
+    #[test]
+    fn test_qr_follows_address_and_amount() {
+        let mut state = idle_state();
+        let _ = update(
+            &mut state,
+            Message::WalletLoaded(create_wallet("mainnet").map_err(|e| e.to_string())),
+        );
+        let address = state.wallet.as_ref().unwrap().address.clone();
+        assert_eq!(state.receive_uri().unwrap(), Some(address.clone()));
+        let bare = state.qr_png.clone().unwrap();
+
+        let _ = update(&mut state, Message::RequestAmountInput("2.5".to_string()));
+        assert_eq!(
+            state.receive_uri().unwrap(),
+            Some(format!("{}?amount=2.5", address))
+        );
+        let with_amount = state.qr_png.clone().unwrap();
+        assert_ne!(bare, with_amount);
+
+        let _ = update(&mut state, Message::RequestAmountInput("abc".to_string()));
+        assert!(state.qr_error.is_some());
+        assert_eq!(state.qr_png.as_ref(), Some(&with_amount));
+
+        let _ = update(
+            &mut state,
+            Message::NetworkSelected(NetworkOption::Testnet10),
+        );
+        assert!(state.busy.wallet);
+    }
+
+    fn entry(txid: &str, time: u64) -> HistoryEntry {
+        HistoryEntry {
+            txid: txid.to_string(),
+            time,
+            direction: Direction::In,
+            net_sompi: 100,
+            accepted: true,
+            counterparty: None,
+            fee: None,
+            mass: None,
+            outputs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_history_is_fetched_once_and_paged() {
+        let mut state = idle_state();
+        state.explorer_url = "http://127.0.0.1:1".to_string();
+        let _ = update(
+            &mut state,
+            Message::WalletLoaded(create_wallet("mainnet").map_err(|e| e.to_string())),
+        );
+        let address = state.wallet.as_ref().unwrap().address.clone();
+
+        let _ = update(&mut state, Message::TabSelected(Tab::History));
+        assert!(state.busy.history);
+        let page: Vec<HistoryEntry> = (0..HISTORY_PAGE as u64)
+            .map(|i| entry(&format!("{:02}", i), 1_000 - i))
+            .collect();
+        let _ = update(
+            &mut state,
+            Message::HistoryFetched {
+                address: "kaspa:someone-else".to_string(),
+                before: None,
+                result: Ok(Vec::new()),
+            },
+        );
+        assert!(state.busy.history, "a stale page must be ignored");
+        let _ = update(
+            &mut state,
+            Message::HistoryFetched {
+                address: address.clone(),
+                before: None,
+                result: Ok(page),
+            },
+        );
+        assert!(!state.busy.history);
+        assert!(!state.history_complete);
+
+        // Coming back to the tab shows the cached entries.
+        let _ = update(&mut state, Message::TabSelected(Tab::Send));
+        let _ = update(&mut state, Message::TabSelected(Tab::History));
+        assert!(!state.busy.history);
+
+        let _ = update(&mut state, Message::LoadMoreHistory);
+        assert!(state.busy.history);
+        let _ = update(
+            &mut state,
+            Message::HistoryFetched {
+                address,
+                before: Some(1_000 - HISTORY_PAGE as u64 + 1),
+                result: Ok(vec![entry("old", 1)]),
+            },
+        );
+        assert_eq!(state.history.len(), HISTORY_PAGE + 1);
+        assert!(state.history_complete);
+
+        let _ = update(&mut state, Message::ToggleHistoryEntry("old".to_string()));
+        assert_eq!(state.history_expanded.as_deref(), Some("old"));
+        let _ = update(&mut state, Message::ToggleHistoryEntry("old".to_string()));
+        assert_eq!(state.history_expanded, None);
+    }
+
+    #[test]
+    fn test_amount_entry() {
+        let mut state = idle_state();
+        let recipient = create_wallet("mainnet").unwrap().wallet.address;
+        state.recipient = recipient.clone();
+
+        for (input, reason) in [
+            ("1.123456789", "more than 8 decimal places"),
+            ("abc", "expected digits"),
+            ("0", "greater than 0"),
+        ] {
+            let _ = update(&mut state, Message::AmountInput(input.to_string()));
+            assert!(state.parsed_amount().unwrap().unwrap_err().contains(reason));
+            let _ = update(&mut state, Message::AddOutput);
+            assert!(state.outputs.is_empty(), "{}", input);
+            assert!(last_message(&state).contains(reason));
+        }
+
+        let _ = update(&mut state, Message::AmountInput("1.5".to_string()));
+        let _ = update(&mut state, Message::AmountUnitSelected(Unit::Sompi));
+        assert_eq!(state.amount, "150000000");
+        let _ = update(&mut state, Message::AmountInput("150000001".to_string()));
+        let _ = update(&mut state, Message::AmountUnitSelected(Unit::Kas));
+        assert_eq!(state.amount, "1.50000001");
+        let _ = update(&mut state, Message::AddOutput);
+        assert_eq!(state.outputs.len(), 1);
+        assert_eq!(state.outputs[0].address, recipient);
+        assert_eq!(state.outputs[0].amount.sompi(), 150_000_001);
+        assert!(state.amount.is_empty());
+    }
+
+    #[test]
+    fn test_lock_and_unlock() {
+        let mut state = idle_state();
+        let first = create_wallet("mainnet").map_err(|e| e.to_string());
+        let first_key = first.as_ref().unwrap().private_key.to_string();
+        let _ = update(&mut state, Message::WalletLoaded(first));
+        let _ = update(
+            &mut state,
+            Message::WalletLoaded(create_wallet("mainnet").map_err(|e| e.to_string())),
+        );
+        let key = state.wallet_key.to_string();
+
+        // Idle past the delay; a Tick from the timer does not count as input.
+        state.last_interaction = 0;
+        let _ = update(&mut state, Message::Tick);
+        assert!(matches!(state.screen, Screen::Locked(_)));
+        assert!(state.wallet_key.is_empty() && state.private_key.is_empty());
+        assert!(state
+            .open_wallets
+            .values()
+            .all(|parked| parked.private_key.is_empty()));
+        assert_eq!(state.auto_lock_at(), None);
+
+        // What needs the key waits for the unlock.
+        let _ = update(&mut state, Message::TogglePrivateKey);
+        assert!(!state.show_private_key);
+        let _ = update(&mut state, Message::UnlockInput(first_key.into()));
+        let _ = update(&mut state, Message::UnlockSubmit);
+        let Screen::Locked(lock) = &state.screen else {
+            panic!("unlocked with another wallet's key");
+        };
+        assert!(lock.error.as_deref().unwrap().contains("different wallet"));
+        let _ = update(&mut state, Message::UnlockInput(key.clone().into()));
+        let _ = update(&mut state, Message::UnlockSubmit);
+        assert!(matches!(state.screen, Screen::Wallet));
+        assert_eq!(&*state.wallet_key, key.as_str());
+        assert!(state.show_private_key);
+
+        // The other wallet was locked too and asks for its own key.
+        let other = state.open_wallets.keys().next().cloned().unwrap();
+        let _ = update(
+            &mut state,
+            Message::WalletChosen(WalletChoice::Open {
+                fingerprint: other,
+                label: String::new(),
+            }),
+        );
+        assert!(matches!(state.screen, Screen::Locked(_)));
+        let _ = update(&mut state, Message::CloseWallet);
+        assert!(matches!(state.screen, Screen::Wallet));
+
+        let _ = update(&mut state, Message::AutoLockSelected(AutoLock(0)));
+        state.last_interaction = 0;
+        let _ = update(&mut state, Message::Tick);
+        assert!(matches!(state.screen, Screen::Wallet));
+    }
+
+    #[test]
+    fn test_unlock_wallet_file() {
+        const PASSWORD: &str = "correct horse battery staple lock";
+        let path = std::env::temp_dir().join(format!(
+            "kasparustwallet-gui-lock-{}.keystore",
+            std::process::id()
+        ));
+        let loaded = create_wallet("mainnet").unwrap();
+        let address = loaded.wallet.address.clone();
+        save_wallet_file(&loaded.secret, "mainnet", path.clone(), PASSWORD).unwrap();
+
+        assert!(unlock_wallet_file(path.clone(), &address, "not the password at all").is_err());
+        assert!(unlock_wallet_file(path.clone(), "kaspa:other", PASSWORD).is_err());
+        let unlocked = unlock_wallet_file(path.clone(), &address, PASSWORD).unwrap();
+        assert_eq!(unlocked.private_key, loaded.private_key);
+        assert_eq!(unlocked.secret, loaded.secret);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_hd_wallet_keeps_its_phrase_and_signs_derived_addresses() {
+        const PASSWORD: &str = "correct horse battery staple hd";
+        let path = std::env::temp_dir().join(format!(
+            "kasparustwallet-gui-hd-{}.keystore",
+            std::process::id()
+        ));
+        let loaded = create_hd_wallet("mainnet", 12).unwrap();
+        let phrase = loaded.phrase.as_ref().unwrap().0.to_string();
+        let address = loaded.wallet.address.clone();
+        let fingerprint = loaded.wallet.fingerprint.clone();
+
+        save_wallet_file(&loaded.secret, "mainnet", path.clone(), PASSWORD).unwrap();
+        let keystore = Keystore::load(&path).unwrap();
+        assert_eq!(keystore.kind, crate::keystore::WalletKind::Hd);
+        let wallet = keystore.decrypt(PASSWORD).unwrap();
+        assert_eq!(wallet.mnemonic().unwrap().0.to_string(), phrase);
+        let unlocked = unlock_wallet_file(path.clone(), &address, PASSWORD).unwrap();
+        assert_eq!(unlocked.secret, loaded.secret);
+        let _ = std::fs::remove_file(&path);
+
+        // Without a file, the phrase unlocks it; its first key alone does not.
+        let by_phrase =
+            unlock_with_key(&format!(" {}\n", phrase), "mainnet", &fingerprint).unwrap();
+        assert_eq!(by_phrase.secret, loaded.secret);
+        let err = unlock_with_key(&loaded.private_key, "mainnet", &fingerprint).unwrap_err();
+        assert!(describe(err).contains("different wallet"));
+
+        let change = wallet.change_address(2).unwrap();
+        let entry = |txid: &str, address: &str| {
+            UtxoEntry::from(&Utxo {
+                txid: txid.repeat(32),
+                vout: 0,
+                address: address.to_string(),
+                amount: 2 * SOMPI_PER_KAS,
+                block_daa_score: 0,
+                is_coinbase: false,
+            })
+        };
+        let tx = build_transaction(
+            &loaded.secret,
+            "mainnet",
+            [0; 2],
+            vec![entry("aa", &address), entry("bb", &change)],
+            vec![(address.clone(), 3 * SOMPI_PER_KAS)],
+            1000,
+        )
+        .unwrap();
+        let xpub = wallet.account_xpub().unwrap();
+        let change_key = xpub.derive_address_key(CHANGE_CHAIN, 2).unwrap();
+        assert_eq!(
+            tx.inputs[0].public_key.as_deref(),
+            Some(wallet.get_public_key().as_str())
+        );
+        assert_eq!(
+            tx.inputs[1].public_key,
+            Some(hex::encode(change_key.serialize()))
+        );
+
+        let mut state = idle_state();
+        let _ = update(&mut state, Message::WalletLoaded(Ok(unlocked)));
+        let addresses = state.watched_addresses().unwrap();
+        assert!(addresses.contains(&address) && addresses.contains(&change));
+    }
+
+    #[test]
+    fn test_wallet_file_roundtrip() {
+        const PASSWORD: &str = "correct horse battery staple gui";
+        let path = std::env::temp_dir().join(format!(
+            "kasparustwallet-gui-{}.keystore",
+            std::process::id()
+        ));
+        let mut state = idle_state();
+        let _ = update(
+            &mut state,
+            Message::WalletLoaded(create_wallet("testnet-10").map_err(|e| e.to_string())),
+        );
+        let address = state.wallet.as_ref().unwrap().address.clone();
+
+        let _ = update(&mut state, Message::SavePathChosen(Some(path.clone())));
+        let _ = update(
+            &mut state,
+            Message::PromptPasswordInput(PASSWORD.to_string().into()),
+        );
+        let _ = update(
+            &mut state,
+            Message::PromptConfirmInput("typo".to_string().into()),
+        );
+        let _ = update(&mut state, Message::PromptSubmit);
+        let prompt = state.prompt.as_ref().unwrap();
+        assert_eq!(prompt.error.as_deref(), Some("Passwords do not match"));
+        assert!(!state.busy.wallet);
+
+        let saved = save_wallet_file(&state.wallet_secret, "testnet-10", path.clone(), PASSWORD)
+            .map_err(|e| e.to_string());
+        let _ = update(&mut state, Message::WalletFileSaved(saved));
+        assert!(state.prompt.is_none());
+        let file = state.wallet_file.clone().unwrap();
+        assert!(title(&state).contains(&file.fingerprint));
+
+        let mut state = idle_state();
+        Shell::default().send(&mut state, Message::OpenPathChosen(Some(path.clone())));
+        let Some(FileAction::Open(keystore)) = state.prompt.as_ref().map(|p| p.action.clone())
+        else {
+            panic!("no open prompt");
+        };
+        let wrong = open_wallet_file(&keystore, path.clone(), "not the password at all")
+            .map_err(|e| e.to_string());
+        let _ = update(&mut state, Message::WalletFileOpened(wrong));
+        assert!(state.prompt.as_ref().unwrap().error.is_some());
+        assert!(state.wallet.is_none());
+
+        let opened = open_wallet_file(&keystore, path.clone(), PASSWORD).map_err(|e| e.to_string());
+        let _ = update(&mut state, Message::WalletFileOpened(opened));
+        assert!(state.prompt.is_none());
+        assert_eq!(state.network, NetworkOption::Testnet10);
+        assert_eq!(state.wallet.as_ref().unwrap().address, address);
+        assert_eq!(state.wallet_file.as_ref(), Some(&file));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_activity_log_masks_keys() {
+        let mut state = idle_state();
+        state.settings_dir =
+            std::env::temp_dir().join(format!("kasparustwallet-gui-log-{}", std::process::id()));
+        let loaded = create_wallet("mainnet").map_err(|e| e.to_string());
+        let key = loaded.as_ref().unwrap().private_key.to_string();
+        let _ = update(&mut state, Message::WalletLoaded(loaded));
+        state.notify(Severity::Error, format!("Rejected {}", key));
+
+        let log = state.activity_log();
+        assert!(!log.contains(&key), "{}", log);
+        assert!(log.contains("Rejected [redacted]"));
+        assert!(log.contains("New wallet created: kaspa:"));
+        assert_eq!(log.lines().count(), state.notifications.len());
+
+        let _ = update(&mut state, Message::ToggleActivity);
+        assert!(state.current_settings().show_activity);
+        let generation = state.settings_generation;
+        Shell::default().send(&mut state, Message::SaveSettings(generation));
+        let (saved, _) = GuiSettings::load_or_recover(&state.settings_dir);
+        assert!(saved.show_activity);
+        let _ = std::fs::remove_dir_all(&state.settings_dir);
+    }
+
+    #[test]
+    fn test_notifications_keep_the_transaction() {
+        let mut state = funded_state(&[("aa", 2 * SOMPI_PER_KAS)]);
+        let built = BuiltTransaction {
+            txid: "1234".to_string(),
+            hex: "abcd".to_string(),
+            ..built_transaction(&mut state)
+        };
+        state.notifications.clear();
+        let _ = update(&mut state, Message::TransactionBuilt(Ok(built)));
+        let _ = update(
+            &mut state,
+            Message::Copied {
+                what: "Address",
+                ok: true,
+            },
+        );
+        let _ = update(
+            &mut state,
+            Message::TransactionBuilt(Err("bad".to_string())),
+        );
+        let built = state
+            .notifications
+            .activity()
+            .find(|entry| entry.payload.is_some())
+            .unwrap();
+        assert_eq!(built.text, "Transaction 1234 created (2 bytes)");
+        assert_eq!(built.payload.as_ref().unwrap().value, "abcd");
+        let (built, failed) = (built.id, state.notifications.activity().next().unwrap().id);
+        assert_eq!(state.notifications.toasts().count(), 3);
+
+        // Successes expire; the error waits for its close button.
+        let _ = update(&mut state, Message::CopyNotification(built));
+        state
+            .notifications
+            .expire(notifications::now_millis() + notifications::TOAST_MILLIS);
+        let shown: Vec<u64> = state.notifications.toasts().map(|toast| toast.id).collect();
+        assert_eq!(shown, vec![failed]);
+        let _ = update(&mut state, Message::DismissNotification(failed));
+        assert_eq!(state.notifications.toasts().count(), 0);
+        assert_eq!(state.notifications.len(), 3);
+    }
+
+    #[test]
+    fn test_key_field_errors_are_readable() {
+        let cases = [
+            ("", "Enter a private key first"),
+            (
+                &format!("ab{}", "zz".repeat(31)),
+                "Character 3 is not hexadecimal (0-9, a-f)",
+            ),
+            (
+                &"ab".repeat(31),
+                "The private key must be 64 hex characters, not 62",
+            ),
+            (
+                &"0".repeat(63),
+                "Odd number of hex characters (63); one is missing or extra",
+            ),
+            (
+                &"00".repeat(32),
+                "The private key is out of range for secp256k1 (zero or too large)",
+            ),
+            (
+                &"ff".repeat(32),
+                "The private key is out of range for secp256k1 (zero or too large)",
+            ),
+            (
+                "abandon ability able about above absent",
+                "This looks like a seed phrase, not a private key. Restore it with \
+                 `kasparustwallet restore` instead",
+            ),
+        ];
+        for (key, expected) in cases {
+            let err = load_wallet(key, "mainnet").err().unwrap();
+            assert_eq!(describe(err), expected);
+        }
+        let key = create_wallet("mainnet").unwrap().private_key;
+        assert!(load_wallet(&format!(" {}\n", &*key), "mainnet").is_ok());
+
+        // The field says what is wrong as it is typed, and nothing once empty.
+        let mut state = idle_state();
+        let _ = update(
+            &mut state,
+            Message::PrivateKeyInput("abc".to_string().into()),
+        );
+        assert_eq!(
+            key_feedback(&state.private_key),
+            Some(Err(
+                "Odd number of hex characters (3); one is missing or extra".to_string()
+            ))
+        );
+        let _ = update(&mut state, Message::PrivateKeyInput(key.clone()));
+        assert_eq!(key_feedback(&state.private_key), Some(Ok(())));
+        let _ = update(&mut state, Message::PrivateKeyInput(" ".to_string().into()));
+        assert_eq!(key_feedback(&state.private_key), None);
+
+        state.private_key = "ab".repeat(31).into();
+        let _ = update(&mut state, Message::LoadWallet);
+        let loaded = load_wallet(&state.private_key, "mainnet").map_err(describe);
+        let _ = update(&mut state, Message::WalletLoaded(loaded));
+        assert_eq!(
+            last_message(&state),
+            "Error loading wallet: The private key must be 64 hex characters, not 62"
+        );
+        assert_eq!(newest_severity(&state), Severity::Error);
+    }
+
+    #[test]
+    fn test_build_transaction_rejects_invalid_outputs() {
+        let key = create_wallet("mainnet").unwrap().secret;
+        let input = || {
+            vec![UtxoEntry {
+                txid: "ab".repeat(32),
+                vout: 0,
+                amount: Some(10 * SOMPI_PER_KAS),
+                script: None,
+            }]
+        };
+        let err = build_transaction(
+            &key,
+            "mainnet",
+            [0; 2],
+            input(),
+            vec![("kaspa:bogus".to_string(), SOMPI_PER_KAS)],
+            1000,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(describe(err), "Invalid recipient address");
+
+        let mut bad_txid = input();
+        bad_txid[0].txid = "xyz".to_string();
+        let address = create_wallet("mainnet").unwrap().wallet.address;
+        let err = build_transaction(
+            &key,
+            "mainnet",
+            [0; 2],
+            bad_txid,
+            vec![(address.clone(), SOMPI_PER_KAS)],
+            1000,
+        )
+        .err()
+        .unwrap();
+        assert!(describe(err).starts_with("Invalid txid xyz"));
+        assert!(build_transaction(
+            &key,
+            "mainnet",
+            [0; 2],
+            input(),
+            vec![(address, SOMPI_PER_KAS)],
+            1000
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_recipient_and_amount_validate_as_you_type() {
+        let mut state = idle_state();
+        let address = create_wallet("mainnet").unwrap().wallet.address;
+        let _ = update(&mut state, Message::AmountInput("0.0001".to_string()));
+        assert!(state
+            .parsed_amount()
+            .unwrap()
+            .unwrap_err()
+            .contains("dust limit"));
+        let _ = update(&mut state, Message::AmountInput("0.1".to_string()));
+        assert!(matches!(state.parsed_amount(), Some(Ok(_))));
+
+        let mut typo: Vec<char> = address.chars().collect();
+        typo[22] = 'l';
+        let _ = update(
+            &mut state,
+            Message::RecipientInput(typo.into_iter().collect()),
+        );
+        assert_eq!(
+            state.recipient_check(),
+            Some(Err(
+                "Invalid address: 'l' at character 23 is not allowed (0, O, I and l never appear)"
+                    .to_string()
+            ))
+        );
+        assert!(!state.can_add_output());
+
+        let public_key = create_wallet("mainnet").unwrap().wallet.public_key;
+        let public_key =
+            secp256k1::PublicKey::from_slice(&hex::decode(public_key).unwrap()).unwrap();
+        let other = crate::address::generate_address(&public_key, "kaspatest");
+        let _ = update(&mut state, Message::RecipientInput(other));
+        assert_eq!(
+            state.recipient_check(),
+            Some(Err(
+                "A valid Testnet-10 address, but Mainnet is selected".to_string()
+            ))
+        );
+        assert_eq!(state.recipient_network(), Some(NetworkOption::Testnet10));
+        let other = crate::address::generate_address(&public_key, "kaspadev");
+        let _ = update(&mut state, Message::RecipientInput(other));
+        assert!(state
+            .recipient_check()
+            .unwrap()
+            .unwrap_err()
+            .contains("expected the kaspa: prefix"));
+        // No known network uses that prefix, so there is nowhere to switch.
+        assert_eq!(state.recipient_network(), None);
+
+        let _ = update(
+            &mut state,
+            Message::RecipientInput(format!(" {}\n", address)),
+        );
+        assert_eq!(state.recipient_check(), Some(Ok(())));
+        assert!(state.can_add_output());
+        let _ = update(&mut state, Message::AddOutput);
+        assert_eq!(state.outputs[0].address, address);
+    }
+
+    #[test]
+    fn test_switching_to_the_recipients_network() {
+        let mut state = idle_state();
+        let _ = update(
+            &mut state,
+            Message::WalletLoaded(create_wallet("mainnet").map_err(|e| e.to_string())),
+        );
+        let address = state.wallet.as_ref().unwrap().address.clone();
+        assert_eq!(address_network(&address), Some(NetworkOption::Mainnet));
+        state.recipient = address.clone();
+        assert_eq!(state.recipient_network(), None);
+
+        let _ = update(&mut state, Message::SwitchNetwork(NetworkOption::Testnet10));
+        assert_eq!(state.network, NetworkOption::Testnet10);
+        assert!(state.copy_address_text.is_empty() && state.address_result.is_empty());
+        assert!(state.busy.wallet, "the wallet reloads on the new network");
+        assert_eq!(state.recipient, address);
+    }
+
+    #[test]
+    fn test_private_key_copy_is_guarded() {
+        let mut state = idle_state();
+        let _ = update(&mut state, Message::CopyPrivateKey);
+        assert_eq!(state.key_copy, None);
+        assert_eq!(last_message(&state), "No private key to copy");
+
+        let loaded = create_wallet("mainnet").map_err(|e| e.to_string());
+        let _ = update(&mut state, Message::WalletLoaded(loaded));
+        let _ = update(&mut state, Message::CopyPrivateKey);
+        let _ = update(&mut state, Message::ConfirmKeyCopy);
+        assert_eq!(state.key_copy, Some(false));
+        let _ = update(&mut state, Message::DismissModal);
+        assert_eq!(state.key_copy, None);
+
+        let _ = update(&mut state, Message::CopyPrivateKey);
+        let _ = update(&mut state, Message::KeyCopyAcknowledged(true));
+        let _ = update(&mut state, Message::ConfirmKeyCopy);
+        assert_eq!(state.key_copy, None);
+        let _ = update(&mut state, Message::PrivateKeyCopied(true));
+        let pending = state.pending_clear.as_ref().unwrap();
+        assert_eq!(pending.text, state.private_key);
+        let left = pending.at - notifications::now_millis();
+        assert!(left > 29_000 && left <= 30_000, "{}", left);
+        assert!(!format!("{:?}", state).contains(&*state.private_key));
+
+        let _ = update(&mut state, Message::Tick);
+        assert!(state.pending_clear.is_some());
+        state.pending_clear.as_mut().unwrap().at = 0;
+        let _ = update(&mut state, Message::Tick);
+        assert!(state.pending_clear.is_none());
+
+        // Addresses and txids are not secret and leave no countdown.
+        let _ = update(
+            &mut state,
+            Message::Copied {
+                what: "Txid",
+                ok: true,
+            },
+        );
+        assert!(state.pending_clear.is_none());
+        let _ = update(&mut state, Message::ClipboardClearSelected(ClearAfter(10)));
+        let _ = update(&mut state, Message::PrivateKeyCopied(true));
+        let left = state.pending_clear.as_ref().unwrap().at - notifications::now_millis();
+        assert!(left > 9_000 && left <= 10_000, "{}", left);
+    }
+
+    #[test]
+    fn test_backup_quiz_gates_a_new_wallet() {
+        let mut state = idle_state();
+        let loaded = create_hd_wallet("mainnet", 12).unwrap();
+        let words: Vec<String> = loaded
+            .phrase
+            .as_ref()
+            .unwrap()
+            .0
+            .words()
+            .map(str::to_string)
+            .collect();
+        let _ = update(&mut state, Message::WalletLoaded(Ok(loaded)));
+        assert!(state.wallet.is_none());
+        let debug = format!("{:?}", state);
+        assert!(words
+            .iter()
+            .all(|word| !debug.contains(&format!("\"{}\"", word))));
+
+        let _ = update(&mut state, Message::BackupWrittenDown);
+        let Screen::Backup(backup) = &state.screen else {
+            panic!("no backup screen");
+        };
+        assert_eq!(backup.step, BackupStep::Quiz);
+        let positions = backup.positions.clone();
+        assert_eq!(positions.len(), mnemonic::QUIZ_WORDS);
+        for (question, &position) in positions.iter().enumerate() {
+            let answer = format!(" {} ", words[position - 1].to_uppercase());
+            let _ = update(
+                &mut state,
+                Message::BackupAnswerInput(question, answer.into()),
+            );
+        }
+        let wrong = if words[positions[2] - 1] == "zoo" {
+            "abandon"
+        } else {
+            "zoo"
+        };
+        let _ = update(
+            &mut state,
+            Message::BackupAnswerInput(2, wrong.to_string().into()),
+        );
+        let _ = update(&mut state, Message::BackupSubmit);
+        let Screen::Backup(backup) = &state.screen else {
+            panic!("a wrong answer ended the backup");
+        };
+        assert_eq!(
+            backup.error.as_deref(),
+            Some(format!(
+                "Word #{} does not match. Check your copy, or go back to the words.",
+                positions[2]
+            ))
+            .as_deref()
+        );
+
+        let answer = words[positions[2] - 1].clone();
+        let _ = update(&mut state, Message::BackupAnswerInput(2, answer.into()));
+        let _ = update(&mut state, Message::BackupSubmit);
+        assert!(matches!(state.screen, Screen::Wallet));
+        assert!(state.wallet.is_some());
+        assert_eq!(
+            last_message(&state),
+            "Seed phrase confirmed. Your wallet is ready."
+        );
+
+        let _ = update(
+            &mut state,
+            Message::WalletLoaded(create_hd_wallet("mainnet", 24).map_err(describe)),
+        );
+        let Screen::Backup(backup) = &state.screen else {
+            panic!("no backup screen");
+        };
+        assert_eq!(backup.phrase.0.word_count(), 24);
+        let _ = update(&mut state, Message::BackupSkip);
+        assert!(matches!(state.screen, Screen::Wallet));
+        assert_eq!(newest_severity(&state), Severity::Warning);
+    }
+
+    #[test]
+    fn test_paste_fills_and_checks_fields() {
+        let mut state = idle_state();
+        let address = create_wallet("mainnet").unwrap().wallet.address;
+        let paste =
+            |target, text: &str| Message::ClipboardRead(target, Some(text.to_string().into()));
+
+        let _ = update(
+            &mut state,
+            paste(
+                PasteTarget::Recipient,
+                &format!("{}?amount=2.5&label=Rent", address),
+            ),
+        );
+        assert_eq!(state.recipient, address);
+        assert_eq!(state.amount, "2.5");
+        assert_eq!(state.amount_unit, Unit::Kas);
+        assert!(state.can_add_output());
+
+        let _ = update(&mut state, paste(PasteTarget::Recipient, "kaspa:bogus"));
+        assert_eq!(state.recipient, "kaspa:bogus");
+        assert!(matches!(state.recipient_check(), Some(Err(_))));
+        let _ = update(
+            &mut state,
+            paste(PasteTarget::Recipient, &format!("{}?amount=lots", address)),
+        );
+        assert_eq!(newest_severity(&state), Severity::Error);
+
+        let key = create_wallet("mainnet").unwrap().private_key;
+        let _ = update(
+            &mut state,
+            paste(PasteTarget::PrivateKey, &format!("{}\n", &*key)),
+        );
+        assert_eq!(state.private_key, key);
+        assert_eq!(last_message(&state), "Private key is valid");
+
+        let _ = update(
+            &mut state,
+            Message::ClipboardRead(PasteTarget::Recipient, None),
+        );
+        assert_eq!(last_message(&state), "Could not access clipboard");
+    }
+
+    #[test]
+    fn test_scanned_qr_fills_fields() {
+        let mut state = idle_state();
+        let address = create_wallet("mainnet").unwrap().wallet.address;
+        let uri = format!("{}?amount=2.5", address);
+        let scanned = |target, text: &str| Message::QrScanned(target, Ok(Some(text.to_string())));
+
+        let _ = update(&mut state, scanned(ScanTarget::Recipient, &uri));
+        assert_eq!(state.recipient, address);
+        assert_eq!(state.amount, "2.5");
+        let _ = update(&mut state, scanned(ScanTarget::Validation, &uri));
+        assert_eq!(state.validate_address_input, address);
+        assert_eq!(state.validation_result, "Valid Kaspa address");
+
+        let _ = update(
+            &mut state,
+            scanned(ScanTarget::Recipient, "https://example.com/pay"),
+        );
+        assert_eq!(state.recipient, address);
+        assert!(state
+            .recipient_scan_error
+            .as_deref()
+            .unwrap()
+            .contains("no Kaspa address"));
+        let _ = update(&mut state, Message::RecipientInput(address.clone()));
+        assert_eq!(state.recipient_scan_error, None);
+
+        let _ = update(
+            &mut state,
+            Message::QrScanned(
+                ScanTarget::Validation,
+                Err("No QR code found in the image".to_string()),
+            ),
+        );
+        assert_eq!(
+            state.validation_result,
+            "Error: No QR code found in the image"
+        );
+        let _ = update(
+            &mut state,
+            Message::QrScanned(ScanTarget::Recipient, Ok(None)),
+        );
+        assert_eq!(state.recipient_scan_error, None);
+    }
+
+    #[test]
+    fn test_theme_and_scale_switch_live_and_persist() {
+        let dir =
+            std::env::temp_dir().join(format!("kasparustwallet-gui-theme-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut state = idle_state();
+        state.settings_dir = dir.clone();
+
+        // System follows the desktop, and leaves it to iced without one.
+        assert_eq!(app_theme(&state), None);
+        let _ = update(&mut state, Message::SystemThemeChanged(theme::Mode::Dark));
+        let dark = app_theme(&state).unwrap();
+        assert_eq!(dark.palette().background, Palette::DARK.background);
+        assert_ne!(dark.palette().success, Palette::DARK.success);
+
+        let _ = update(&mut state, Message::ThemeSelected(ThemeChoice::Light));
+        let light = app_theme(&state).unwrap();
+        assert_eq!(light.palette().background, Palette::LIGHT.background);
+        assert_eq!(scale_factor(&state), 1.0);
+        let _ = update(&mut state, Message::UiScaleSelected(UiScale(125)));
+        assert_eq!(scale_factor(&state), 1.25);
+        let generation = state.settings_generation;
+        Shell::default().send(&mut state, Message::SaveSettings(generation));
+        let saved = GuiSettings::load(&dir).unwrap();
+        assert_eq!(saved.theme, ThemeChoice::Light);
+        assert_eq!(saved.ui_scale, 125);
+
+        let _ = update(&mut state, Message::AmountInput("1".to_string()));
+        let _ = update(&mut state, Message::AddOutput);
+        assert_eq!(newest_severity(&state), Severity::Warning);
+        let _ = update(
+            &mut state,
+            Message::Copied {
+                what: "Txid",
+                ok: true,
+            },
+        );
+        assert_eq!(newest_severity(&state), Severity::Success);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_settings_save_once_typing_stops() {
+        let dir = std::env::temp_dir().join(format!(
+            "kasparustwallet-gui-settings-save-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut state = idle_state();
+        state.settings_dir = dir.clone();
+        state.private_key = create_wallet("mainnet").unwrap().private_key;
+
+        let _ = update(
+            &mut state,
+            Message::NetworkSelected(NetworkOption::Testnet10),
+        );
+        let stale = state.settings_generation;
+        let _ = update(
+            &mut state,
+            Message::NodeUrlInput("grpc://10.0.0.2:16210".to_string()),
+        );
+        let mut shell = Shell::default();
+        shell.send(&mut state, Message::SaveSettings(stale));
+        assert!(!dir.join(gui_settings::GUI_SETTINGS_FILE).exists());
+
+        let generation = state.settings_generation;
+        shell.send(&mut state, Message::SaveSettings(generation));
+        let saved = GuiSettings::load(&dir).unwrap();
+        assert_eq!(saved.version, gui_settings::GUI_SETTINGS_VERSION);
+        assert_eq!(saved.network.as_deref(), Some("testnet-10"));
+        assert_eq!(saved.node_url.as_deref(), Some("grpc://10.0.0.2:16210"));
+        assert_eq!(saved.explorer_url, None);
+        let json = std::fs::read_to_string(dir.join(gui_settings::GUI_SETTINGS_FILE)).unwrap();
+        assert!(!json.contains(&*state.private_key));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_switching_keeps_each_wallets_state() {
+        let mut state = funded_state(&[("aa", 2 * SOMPI_PER_KAS)]);
+        let first = state.wallet.clone().unwrap();
+        let first_key = state.wallet_key.clone();
+        let _ = update(&mut state, Message::InputToggled("aa".repeat(32), 0));
+
+        // A key typed into the field loads as a second wallet.
+        let second = load_wallet(
+            &create_wallet("testnet-10").unwrap().private_key,
+            "testnet-10",
+        );
+        let _ = update(&mut state, Message::PrivateKeyInput("ab".repeat(32).into()));
+        let _ = update(&mut state, Message::WalletLoaded(second.map_err(describe)));
+        let second = state.wallet.clone().unwrap();
+        assert_eq!(state.network, NetworkOption::Testnet10);
+        assert_eq!(state.balance, None);
+        assert_eq!(state.wallet_choices().len(), 3);
+        assert!(title(&state).contains(&second.fingerprint));
+
+        // Late results land on the wallet that asked for them.
+        let _ = update(
+            &mut state,
+            Message::BalanceFetched {
+                address: first.address.clone(),
+                result: Err("refused".to_string()),
+            },
+        );
+        assert_eq!(state.balance_error, None);
+
+        let _ = update(
+            &mut state,
+            Message::WalletChosen(WalletChoice::Open {
+                fingerprint: first.fingerprint.clone(),
+                label: String::new(),
+            }),
+        );
+        assert_eq!(state.wallet.as_ref().unwrap().address, first.address);
+        assert_eq!(state.wallet_key, first_key);
+        assert_eq!(state.network, NetworkOption::Mainnet);
+        assert_eq!(state.balance_error.as_deref(), Some("refused"));
+        assert!(state.balance.is_some());
+        assert_eq!(state.selected_inputs.len(), 1);
+
+        let _ = update(&mut state, Message::CloseWallet);
+        assert_eq!(state.wallet.as_ref().unwrap().address, second.address);
+        assert!(state.open_wallets.is_empty());
+        let _ = update(&mut state, Message::CloseWallet);
+        assert!(state.wallet.is_none());
+        assert!(state.wallet_key.is_empty() && state.private_key.is_empty());
+        assert_eq!(state.wallet_choices(), vec![WalletChoice::Add]);
+        assert_eq!(title(&state), "KaspaRustWallet");
+    }
+
+    #[test]
+    fn test_overview_names_the_key_type() {
+        let single = create_wallet("mainnet").unwrap().wallet;
+        assert_eq!(single.key_type(), "Single key");
+        assert_eq!(single.fingerprint.len(), 8);
+        let hd = create_hd_wallet("mainnet", 12).unwrap().wallet;
+        assert_eq!(hd.key_type(), "HD account 0 (m/44'/111111'/0')");
+
+        let mut state = idle_state();
+        let _ = state.use_wallet(create_wallet("mainnet").unwrap());
+        let _ = update(&mut state, Message::ToggleAdvanced);
+        assert!(state.show_advanced);
+        assert!(!format!("{:?}", state).contains("kaspa:pk:"));
+    }
+
+    #[test]
+    fn test_new_address_derives_the_next_receive_index() {
+        let dir = std::env::temp_dir().join(format!(
+            "kasparustwallet-gui-derived-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut state = idle_state();
+        state.data_dir = dir.clone();
+        let single = create_wallet("mainnet").unwrap();
+        let _ = state.use_wallet(single);
+        let _ = update(&mut state, Message::NewAddress);
+        assert!(state.derived_addresses.is_empty());
+        assert_eq!(newest_severity(&state), Severity::Warning);
+
+        let loaded = create_hd_wallet("mainnet", 12).unwrap();
+        let fingerprint = loaded.wallet.fingerprint.clone();
+        let main = loaded.wallet.address.clone();
+        let _ = state.use_wallet(loaded);
+        assert_eq!(state.derived_addresses.len(), 1);
+        assert_eq!(state.derived_addresses[0].address, main);
+
+        let _ = update(&mut state, Message::NewAddress);
+        let _ = update(&mut state, Message::NewAddress);
+        let indexes: Vec<u32> = state.derived_addresses.iter().map(|a| a.index).collect();
+        assert_eq!(indexes, vec![0, 1, 2]);
+        let newest = state.derived_addresses[2].address.clone();
+        assert_ne!(newest, main);
+        assert_eq!(state.copy_address_text, newest);
+        assert!(state.receive_uri().unwrap().unwrap().contains(&newest));
+        assert_eq!(state.wallet.as_ref().unwrap().address, main);
+
+        let _ = update(
+            &mut state,
+            Message::DerivedFundsFetched {
+                fingerprint,
+                result: Ok(BTreeSet::from([main.clone()])),
+            },
+        );
+        let funded: Vec<Option<bool>> = state.derived_addresses.iter().map(|a| a.funded).collect();
+        assert_eq!(funded, vec![Some(true), Some(false), Some(false)]);
+
+        // Handed-out indexes outlive the session.
+        let wallet = state.wallet.clone().unwrap();
+        state.derived_addresses.clear();
+        state.load_derived_addresses();
+        assert_eq!(state.derived_addresses.len(), 3);
+        assert_eq!(
+            AddressIndex::load(&dir)
+                .unwrap()
+                .next(&wallet.fingerprint, RECEIVE_CHAIN),
+            3
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_contacts_persist_per_network() {
+        let dir = std::env::temp_dir().join(format!(
+            "kasparustwallet-gui-contacts-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let alice = create_wallet("mainnet").unwrap().wallet.address;
+        let mut state = idle_state();
+        state.data_dir = dir.clone();
+        state.reload_contacts();
+
+        let _ = update(&mut state, Message::ContactLabelInput("alice".to_string()));
+        let _ = update(
+            &mut state,
+            Message::ContactAddressInput("kaspa:bogus".to_string()),
+        );
+        let _ = update(&mut state, Message::AddContact);
+        assert!(state.contact_error.is_some());
+        let _ = update(&mut state, Message::ContactAddressInput(alice.clone()));
+        let _ = update(&mut state, Message::AddContact);
+        assert_eq!(state.contact_error, None);
+        assert_eq!(state.contacts.len(), 1);
+
+        let _ = update(&mut state, Message::ContactFilterInput("ALI".to_string()));
+        assert_eq!(state.filtered_contacts().len(), 1);
+        let _ = update(&mut state, Message::ContactFilterInput("bob".to_string()));
+        assert!(state.filtered_contacts().is_empty());
+
+        let mut state = idle_state();
+        state.data_dir = dir.clone();
+        state.reload_contacts();
+        let contact = state.contacts[0].clone();
+        let _ = update(
+            &mut state,
+            Message::ContactPicked(ContactChoice(contact.clone())),
+        );
+        assert_eq!(state.recipient, alice);
+        let _ = update(&mut state, Message::AmountInput("1".to_string()));
+        let _ = update(&mut state, Message::AddOutput);
+        assert_eq!(state.outputs[0].label.as_deref(), Some("alice"));
+
+        let _ = update(&mut state, Message::ContactPicked(ContactChoice(contact)));
+        let _ = update(
+            &mut state,
+            Message::NetworkSelected(NetworkOption::Testnet10),
+        );
+        assert!(state.contacts.is_empty());
+        assert!(state.recipient.is_empty());
+        let _ = update(&mut state, Message::NetworkSelected(NetworkOption::Mainnet));
+        let _ = update(&mut state, Message::DeleteContact("alice".to_string()));
+        assert!(state.contacts.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_fee_selection() {
+        let mut state = idle_state();
+        assert_eq!(state.fee_rate(), Ok(FEE_RATE_PRESETS[1].1));
+
+        let _ = update(&mut state, Message::FeeChoiceSelected(FeeChoice::Preset(2)));
+        assert_eq!(state.fee_rate(), Ok(FEE_RATE_PRESETS[2].1));
+        let _ = update(
+            &mut state,
+            Message::FeeEstimateFetched(Ok(Some(FeeEstimate {
+                low: node::FeeBucket::from_node(1.1, 3600.0),
+                normal: node::FeeBucket::from_node(2.5, 60.0),
+                priority: node::FeeBucket::from_node(9.0, 1.0),
+            }))),
+        );
+        assert_eq!(state.fee_rate(), Ok(9_000));
+        assert!(state.fee_estimate_error.is_none());
+        let _ = update(
+            &mut state,
+            Message::FeeEstimateFetched(Err("refused".to_string())),
+        );
+        assert_eq!(state.fee_rate(), Ok(FEE_RATE_PRESETS[2].1));
+        assert_eq!(state.fee_estimate_error.as_deref(), Some("refused"));
+
+        let _ = update(&mut state, Message::CustomFeeRateInput("1500".to_string()));
+        assert_eq!(state.fee_choice, FeeChoice::Custom);
+        assert_eq!(state.fee_rate(), Ok(1_500));
+        let _ = update(&mut state, Message::CustomFeeRateInput("10".to_string()));
+        assert!(state.fee_rate().is_err());
+
+        let _ = update(&mut state, Message::CustomFeeRateInput("2000".to_string()));
+        let one_output = state.fee_estimate().unwrap();
+        state.outputs.push(OutputRow {
+            address: "kaspa:abc".to_string(),
+            amount: Kas::from_sompi(SOMPI_PER_KAS),
+            label: None,
+        });
+        let two_outputs = state.fee_estimate().unwrap();
+        assert!(one_output.starts_with('~') && one_output.ends_with(" grams"));
+        assert_ne!(one_output, two_outputs);
+        assert_eq!(thousands(3_120), "3,120");
+        assert_eq!(thousands(100), "100");
+    }
+
+    #[test]
+    fn test_failures_clear_busy_flags() {
+        let mut state = idle_state();
+        let _ = update(
+            &mut state,
+            Message::WalletLoaded(create_wallet("mainnet").map_err(|e| e.to_string())),
+        );
+        let address = state.wallet.as_ref().unwrap().address.clone();
+        state.busy = Busy {
+            wallet: true,
+            transaction: true,
+            balance: true,
+            ..Busy::default()
+        };
+        state.balance = Some(Balance {
+            total: 5,
+            pending: 0,
+            utxo_count: 1,
+            updated: 0,
+            utxos: UtxoSet::new(),
+            virtual_daa_score: Some(0),
+        });
+
+        let _ = update(
+            &mut state,
+            Message::WalletLoaded(Err("bad key".to_string())),
+        );
+        assert!(last_message(&state).contains("bad key"));
+        let _ = update(
+            &mut state,
+            Message::TransactionBuilt(Err("no inputs".to_string())),
+        );
+        assert_eq!(last_message(&state), "Transaction error: no inputs");
+        let _ = update(
+            &mut state,
+            Message::BalanceFetched {
+                address,
+                result: Err("refused".to_string()),
+            },
+        );
+        assert_eq!(state.balance_error.as_deref(), Some("refused"));
+        assert!(state.balance.is_some());
+        assert_eq!(state.busy, Busy::default());
+
+        // A task that panics still reports back, so its flag clears too.
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let result = runtime.block_on(off_thread(|| -> Result<(), String> { panic!("boom") }));
+        assert!(result.unwrap_err().contains("failed unexpectedly"));
+    }
+
+    fn utxo(byte: &str, amount: u64) -> Utxo {
+        Utxo {
+            txid: byte.repeat(32),
+            vout: 0,
+            address: String::new(),
+            amount,
+            block_daa_score: 0,
+            is_coinbase: false,
+        }
+    }
+
+    fn funded_state(amounts: &[(&str, u64)]) -> WalletGuiState {
+        let mut state = idle_state();
+        let _ = update(
+            &mut state,
+            Message::WalletLoaded(create_wallet("mainnet").map_err(|e| e.to_string())),
+        );
+        let utxos: UtxoSet = amounts
+            .iter()
+            .map(|&(byte, amount)| utxo(byte, amount))
+            .collect();
+        state.balance = Some(Balance {
+            total: utxos.balance(),
+            pending: 0,
+            utxo_count: utxos.len(),
+            updated: 0,
+            utxos,
+            virtual_daa_score: Some(1_000),
+        });
+        state
+    }
+
+    #[test]
+    fn test_output_rows_edit_in_place() {
+        let mut state = funded_state(&[("aa", 2 * SOMPI_PER_KAS)]);
+        let own = state.wallet.as_ref().unwrap().address.clone();
+        for sompi in [SOMPI_PER_KAS, 2 * SOMPI_PER_KAS, 3 * SOMPI_PER_KAS] {
+            state.outputs.push(OutputRow {
+                address: own.clone(),
+                amount: Kas::from_sompi(sompi),
+                label: Some("Me".to_string()),
+            });
+        }
+        let _ = update(&mut state, Message::EditOutput(1));
+        assert_eq!(state.output_edit.as_ref().unwrap().amount, "2");
+
+        // Rejected as Add would reject it, leaving the row as it was.
+        let _ = update(&mut state, Message::EditOutputAmount("0".to_string()));
+        let _ = update(&mut state, Message::SaveOutputEdit);
+        assert_eq!(last_message(&state), "Amount must be greater than 0");
+        assert_eq!(state.outputs[1].amount.sompi(), 2 * SOMPI_PER_KAS);
+
+        // Removing a row above keeps the edit on the same output.
+        let _ = update(&mut state, Message::RemoveOutput(0));
+        assert_eq!(state.output_edit.as_ref().unwrap().index, 0);
+        let _ = update(&mut state, Message::EditOutputAmount("2.5".to_string()));
+        let _ = update(&mut state, Message::SaveOutputEdit);
+        assert!(state.output_edit.is_none());
+        assert_eq!(state.outputs[0].amount.sompi(), 250_000_000);
+        assert_eq!(state.outputs[0].label.as_deref(), Some("Me"));
+
+        let other = create_wallet("mainnet").unwrap().wallet.address;
+        let _ = update(&mut state, Message::EditOutput(1));
+        let _ = update(&mut state, Message::EditOutputAddress(other.clone()));
+        let _ = update(&mut state, Message::SaveOutputEdit);
+        assert_eq!(state.outputs[1].address, other);
+        assert_eq!(state.outputs[1].label, None);
+
+        let _ = update(&mut state, Message::EditOutput(0));
+        let _ = update(&mut state, Message::RemoveOutput(0));
+        assert!(state.output_edit.is_none());
+        assert_eq!(state.outputs.len(), 1);
+    }
+
+    #[test]
+    fn test_removing_outputs_can_be_undone() {
+        let mut state = funded_state(&[("aa", 20 * SOMPI_PER_KAS)]);
+        let own = state.wallet.as_ref().unwrap().address.clone();
+        for sompi in 1..=4 {
+            state.outputs.push(OutputRow {
+                address: own.clone(),
+                amount: Kas::from_sompi(sompi * SOMPI_PER_KAS),
+                label: None,
+            });
+        }
+        let amounts = |state: &WalletGuiState| -> Vec<u64> {
+            state.outputs.iter().map(|o| o.amount.sompi()).collect()
+        };
+        let all = amounts(&state);
+
+        let _ = update(&mut state, Message::RemoveOutput(1));
+        let _ = update(&mut state, Message::ClearOutputs);
+        assert!(state.outputs.is_empty());
+        let now = notifications::now_millis();
+        assert_eq!(state.output_undo.offer(now), Some("Cleared 3 outputs"));
+        assert!(state.output_undo.offer(now + undo::UNDO_MILLIS).is_none());
+
+        let _ = update(&mut state, Message::UndoOutputs);
+        assert_eq!(amounts(&state), vec![all[0], all[2], all[3]]);
+        let _ = update(&mut state, Message::UndoOutputs);
+        assert_eq!(amounts(&state), all);
+        assert_eq!(
+            last_message(&state),
+            "Undone: Removed output 1 (3 remaining)"
+        );
+
+        // Nothing to undo once a transaction is built.
+        let _ = update(&mut state, Message::RemoveOutput(0));
+        let built = built_transaction(&mut state);
+        let _ = update(&mut state, Message::TransactionBuilt(Ok(built)));
+        assert!(state.output_undo.undo().is_none());
+    }
+
+    #[test]
+    fn test_new_outputs_are_announced_as_payments() {
+        let mut state = funded_state(&[("aa", 2 * SOMPI_PER_KAS)]);
+        let address = state.wallet.as_ref().unwrap().address.clone();
+        let fetched = |state: &WalletGuiState, extra: Option<Utxo>| {
+            let mut utxos = state.balance.as_ref().unwrap().utxos.clone();
+            if let Some(utxo) = extra {
+                utxos.insert(utxo);
+            }
+            Balance {
+                total: utxos.balance(),
+                pending: 0,
+                utxo_count: utxos.len(),
+                updated: 0,
+                utxos,
+                virtual_daa_score: Some(1_000),
+            }
+        };
+
+        let unchanged = fetched(&state, None);
+        let _ = update(
+            &mut state,
+            Message::BalanceFetched {
+                address: address.clone(),
+                result: Ok(unchanged),
+            },
+        );
+        assert!(!last_message(&state).starts_with("Received"));
+
+        let mut payment = utxo("bb", SOMPI_PER_KAS / 2);
+        payment.address = address.clone();
+        let with_payment = fetched(&state, Some(payment));
+        let _ = update(
+            &mut state,
+            Message::BalanceFetched {
+                address,
+                result: Ok(with_payment),
+            },
+        );
+        assert!(
+            last_message(&state).starts_with("Received 0.5 KAS at kaspa:"),
+            "{}",
+            last_message(&state)
+        );
+        assert_eq!(newest_severity(&state), Severity::Success);
+        assert!(Message::PollBalance.is_background());
+
+        // A notification for the shown wallet fetches again at once.
+        state.node_url = "127.0.0.1:16110".to_string();
+        let shown = state.wallet.as_ref().unwrap().address.clone();
+        let _ = update(
+            &mut state,
+            Message::UtxosChanged {
+                address: "kaspa:other".to_string(),
+            },
+        );
+        assert!(!state.busy.balance);
+        let notified = Message::UtxosChanged { address: shown };
+        assert!(notified.is_background());
+        let _ = update(&mut state, notified);
+        assert!(state.busy.balance);
+    }
+
+    #[test]
+    fn test_keyboard_shortcuts_follow_the_send_tab() {
+        let mut state = funded_state(&[("aa", 20 * SOMPI_PER_KAS)]);
+        let own = state.wallet.as_ref().unwrap().address.clone();
+        let ctrl = keyboard::Modifiers::COMMAND;
+        let enter = keyboard::Key::Named(keyboard::key::Named::Enter);
+        let escape = keyboard::Key::Named(keyboard::key::Named::Escape);
+        let press = |state: &mut WalletGuiState, key: &keyboard::Key, modifiers| {
+            let _ = update(state, Message::KeyPressed(key.clone(), modifiers));
+        };
+
+        let _ = update(&mut state, Message::RecipientInput(own));
+        let _ = update(&mut state, Message::AmountInput("1".to_string()));
+        press(&mut state, &enter, ctrl);
+        assert_eq!(state.outputs.len(), 1);
+
+        // Enter after touching the outputs asks to confirm the send.
+        let _ = update(&mut state, Message::EditOutput(0));
+        let _ = update(&mut state, Message::CancelOutputEdit);
+        press(&mut state, &enter, ctrl);
+        assert!(state.confirm_send.is_some());
+        press(&mut state, &keyboard::Key::Character("2".into()), ctrl);
+        assert_eq!(state.current_tab, Tab::Send);
+        press(&mut state, &escape, keyboard::Modifiers::empty());
+        assert!(state.confirm_send.is_none());
+
+        press(&mut state, &keyboard::Key::Character("2".into()), ctrl);
+        assert_eq!(state.current_tab, Tab::Receive);
+        let _ = update(&mut state, Message::ShowShortcuts);
+        press(&mut state, &escape, keyboard::Modifiers::empty());
+        assert!(!state.show_shortcuts);
+
+        press(&mut state, &keyboard::Key::Character("l".into()), ctrl);
+        assert!(matches!(state.screen, Screen::Locked(_)));
+        press(&mut state, &keyboard::Key::Character("1".into()), ctrl);
+        assert_eq!(state.current_tab, Tab::Receive);
+    }
+
+    /// Confirms a send of 1 KAS to the wallet itself and builds it here,
+    /// as ConfirmSend would off the UI thread.
+    fn built_transaction(state: &mut WalletGuiState) -> BuiltTransaction {
+        let own = state.wallet.as_ref().unwrap().address.clone();
+        state.outputs.push(OutputRow {
+            address: own,
+            amount: Kas::from_sompi(SOMPI_PER_KAS),
+            label: None,
+        });
+        let _ = update(state, Message::SendTransaction);
+        let summary = state.confirm_send.take().unwrap();
+        let mut outputs: Vec<(String, u64)> = summary
+            .recipients
+            .iter()
+            .map(|(address, amount)| (address.clone(), amount.sompi()))
+            .collect();
+        if let Some(change) = summary.change {
+            outputs.push((summary.change_address.clone(), change.sompi()));
+        }
+        let inputs = summary.inputs.iter().map(UtxoEntry::from).collect();
+        let transaction = build_transaction(
+            &state.wallet_secret,
+            "mainnet",
+            state.next_index(),
+            inputs,
+            outputs,
+            summary.fee_rate,
+        )
+        .unwrap();
+        BuiltTransaction::new(transaction, summary).unwrap()
+    }
+
+    #[test]
+    fn test_result_panel_stays_until_closed() {
+        let mut state = funded_state(&[("aa", 2 * SOMPI_PER_KAS)]);
+        let built = built_transaction(&mut state);
+        let txid = built.txid.clone();
+        let _ = update(&mut state, Message::TransactionBuilt(Ok(built)));
+        let panel = state.result.as_ref().unwrap();
+        assert_eq!(panel.hex.text(), panel.built.hex);
+        let pstx =
+            PartiallySignedTransaction::from_json(&panel.built.pstx_json().unwrap()).unwrap();
+        assert_eq!(pstx.utxo_entries.len(), 1);
+        assert!(pstx.is_fully_signed());
+
+        // The hex can be selected but not changed.
+        let _ = update(
+            &mut state,
+            Message::ResultHexAction(text_editor::Action::Edit(text_editor::Edit::Insert('x'))),
+        );
+        let panel = state.result.as_ref().unwrap();
+        assert_eq!(panel.hex.text(), panel.built.hex);
+
+        // Without a node there is nothing to broadcast to.
+        let _ = update(&mut state, Message::Broadcast);
+        let _ = update(&mut state, Message::ConfirmBroadcast);
+        assert!(!state.busy.broadcast);
+        let _ = update(&mut state, Message::DismissModal);
+        assert!(!state.result.as_ref().unwrap().confirm_broadcast);
+
+        let _ = update(
+            &mut state,
+            Message::Broadcasted {
+                txid: txid.clone(),
+                result: Err("Transaction rejected".to_string()),
+                unsynced: false,
+            },
+        );
+        assert_eq!(
+            state.result.as_ref().unwrap().broadcast,
+            Some(Err("Transaction rejected".to_string()))
+        );
+        assert!(!state.result.as_ref().unwrap().unsynced_node);
+        assert!(last_message(&state).starts_with(&format!("Broadcast of {} failed", txid)));
+
+        // Refused for an unsynced node, broadcasting anyway is offered.
+        let _ = update(
+            &mut state,
+            Message::Broadcasted {
+                txid: txid.clone(),
+                result: Err("the node is not synced yet".to_string()),
+                unsynced: true,
+            },
+        );
+        assert!(state.result.as_ref().unwrap().unsynced_node);
+
+        // Once broadcast, the status chip follows the node's answers.
+        let _ = update(
+            &mut state,
+            Message::Broadcasted {
+                txid: txid.clone(),
+                result: Ok(txid.clone()),
+                unsynced: false,
+            },
+        );
+        assert!(!state.result.as_ref().unwrap().unsynced_node);
+        let accepted = TxStatus::Accepted {
+            accepting_block: "block".to_string(),
+            blue_score_delta: 120,
+        };
+        for (id, result) in [
+            (txid.clone(), Ok(TxStatus::Pending)),
+            (txid.clone(), Ok(accepted.clone())),
+            (txid.clone(), Err("refused".to_string())),
+            ("other".to_string(), Ok(TxStatus::NotFound)),
+        ] {
+            let _ = update(&mut state, Message::TxStatusFetched { txid: id, result });
+        }
+        assert_eq!(state.result.as_ref().unwrap().status, Some(accepted));
+        assert!(!state.busy.tx_status);
+
+        // A transaction the node dropped again is warned about.
+        let dropped = Propagation {
+            submitter: false,
+            other: None,
+        };
+        let _ = update(
+            &mut state,
+            Message::PropagationChecked {
+                txid: txid.clone(),
+                result: Ok(dropped),
+            },
+        );
+        assert_eq!(state.result.as_ref().unwrap().propagation, Some(dropped));
+        assert!(last_message(&state).contains("no longer has it"));
+
+        let _ = update(&mut state, Message::DismissModal);
+        assert!(state.result.is_none());
+    }
+
+    #[test]
+    fn test_send_waits_for_confirmation() {
+        let mut state = funded_state(&[("aa", 2 * SOMPI_PER_KAS), ("bb", SOMPI_PER_KAS)]);
+        let own = state.wallet.as_ref().unwrap().address.clone();
+        for sompi in [SOMPI_PER_KAS, 50_000_000] {
+            state.outputs.push(OutputRow {
+                address: own.clone(),
+                amount: Kas::from_sompi(sompi),
+                label: None,
+            });
+        }
+
+        let _ = update(&mut state, Message::SendTransaction);
+        assert!(!state.busy.transaction);
+        let summary = state.confirm_send.clone().unwrap();
+        assert_eq!(summary.recipients.len(), 2);
+        // The largest output covers it alone.
+        assert_eq!(summary.inputs, vec![utxo("aa", 2 * SOMPI_PER_KAS)]);
+        assert_eq!(summary.total.sompi(), 150_000_000 + summary.fee.sompi());
+        assert_eq!(
+            summary.change.unwrap().sompi(),
+            2 * SOMPI_PER_KAS - summary.total.sompi()
+        );
+        let _ = update(&mut state, Message::DismissModal);
+        assert!(state.confirm_send.is_none());
+        assert!(!state.busy.transaction);
+
+        let _ = update(&mut state, Message::SendTransaction);
+        let _ = update(&mut state, Message::ConfirmSend);
+        assert!(state.confirm_send.is_none());
+        assert!(state.busy.transaction);
+
+        let mut state = idle_state();
+        let _ = update(
+            &mut state,
+            Message::WalletLoaded(create_wallet("mainnet").map_err(|e| e.to_string())),
+        );
+        state.outputs.push(OutputRow {
+            address: own,
+            amount: Kas::from_sompi(1),
+            label: None,
+        });
+        let _ = update(&mut state, Message::SendTransaction);
+        assert!(state.confirm_send.is_none());
+        assert!(last_message(&state).contains("No node set"));
+    }
+
+    #[test]
+    fn test_coin_control_uses_exactly_the_selection() {
+        let mut state = funded_state(&[("aa", 2 * SOMPI_PER_KAS), ("bb", SOMPI_PER_KAS)]);
+        let own = state.wallet.as_ref().unwrap().address.clone();
+        state.outputs.push(OutputRow {
+            address: own,
+            amount: Kas::from_sompi(SOMPI_PER_KAS),
+            label: None,
+        });
+        assert_eq!(state.selected_total(), None);
+
+        // One KAS cannot pay one KAS plus a fee.
+        let _ = update(&mut state, Message::InputToggled("bb".repeat(32), 0));
+        let (selected, required) = state.selected_total().unwrap();
+        assert!(selected < required);
+        let _ = update(&mut state, Message::SendTransaction);
+        assert!(state.confirm_send.is_none());
+        assert!(last_message(&state).contains("Selected inputs hold 1 KAS"));
+
+        let _ = update(&mut state, Message::InputToggled("aa".repeat(32), 0));
+        let (selected, required) = state.selected_total().unwrap();
+        assert_eq!(selected, 3 * SOMPI_PER_KAS);
+        assert!(selected >= required);
+        let _ = update(&mut state, Message::SendTransaction);
+        assert_eq!(state.confirm_send.as_ref().unwrap().inputs.len(), 2);
+
+        let _ = update(&mut state, Message::InputToggled("aa".repeat(32), 0));
+        let mut spent = state.balance.clone().unwrap();
+        spent.utxos.remove(&"bb".repeat(32), 0);
+        let address = state.wallet.as_ref().unwrap().address.clone();
+        let _ = update(
+            &mut state,
+            Message::BalanceFetched {
+                address,
+                result: Ok(spent),
+            },
+        );
+        assert!(state.selected_inputs.is_empty());
+    }
+
+    #[test]
+    fn test_manual_inputs_without_a_node() {
+        let mut state = idle_state();
+        let _ = update(
+            &mut state,
+            Message::WalletLoaded(create_wallet("mainnet").map_err(|e| e.to_string())),
+        );
+        let own = state.wallet.as_ref().unwrap().address.clone();
+        state.outputs.push(OutputRow {
+            address: own.clone(),
+            amount: Kas::from_sompi(SOMPI_PER_KAS),
+            label: None,
+        });
+        assert!(!state.has_inputs());
+        assert!(state.max_amount().unwrap_err().contains("No node set"));
+        let _ = update(&mut state, Message::TabSelected(Tab::Send));
+        assert!(!state.busy.balance);
+
+        for (input, error) in [
+            (format!("{}:0", "aa".repeat(32)), "Give the amount"),
+            ("bogus".to_string(), "Invalid input"),
+        ] {
+            let _ = update(&mut state, Message::ManualInputChanged(input));
+            let _ = update(&mut state, Message::AddManualInput);
+            assert!(last_message(&state).contains(error));
+            assert!(state.manual_inputs.is_empty());
+        }
+        let entry = format!("{}:1:{}", "aa".repeat(32), SOMPI_PER_KAS);
+        for _ in 0..2 {
+            let _ = update(&mut state, Message::ManualInputChanged(entry.clone()));
+            let _ = update(&mut state, Message::AddManualInput);
+        }
+        assert!(last_message(&state).contains("already added"));
+        assert_eq!(state.manual_inputs.len(), 1);
+        assert_eq!(state.manual_inputs[0].address, own);
+        let _ = update(&mut state, Message::SendTransaction);
+        assert!(last_message(&state).contains("Entered inputs hold 1 KAS"));
+
+        let entry = format!("{}:0:{}", "bb".repeat(32), SOMPI_PER_KAS);
+        let _ = update(&mut state, Message::ManualInputChanged(entry));
+        let _ = update(&mut state, Message::AddManualInput);
+        let _ = update(&mut state, Message::SendTransaction);
+        assert_eq!(state.confirm_send.as_ref().unwrap().inputs.len(), 2);
+        let _ = update(&mut state, Message::DismissModal);
+        let _ = update(&mut state, Message::RemoveManualInput(0));
+        assert_eq!(state.manual_inputs.len(), 1);
+    }
+
+    #[test]
+    fn test_fiat_values_are_display_only() {
+        let mut state = funded_state(&[("aa", 200 * SOMPI_PER_KAS)]);
+        state.settings_dir =
+            std::env::temp_dir().join(format!("kasparustwallet-gui-fiat-{}", std::process::id()));
+        let own = state.wallet.as_ref().unwrap().address.clone();
+        state.outputs.push(OutputRow {
+            address: own,
+            amount: Kas::from_sompi(100 * SOMPI_PER_KAS),
+            label: None,
+        });
+        let before = state.send_summary().unwrap();
+        assert_eq!(state.fiat_value(before.total), None);
+
+        let _ = update(
+            &mut state,
+            Message::PriceUrlInput("http://127.0.0.1:1".to_string()),
+        );
+        let hundred = Kas::from_sompi(100 * SOMPI_PER_KAS);
+        assert_eq!(state.fiat_value(hundred).unwrap(), "price unavailable");
+        let prices = Prices {
+            usd: Some(0.125),
+            eur: Some(0.1),
+        };
+        let _ = update(&mut state, Message::PricesFetched(Ok(prices)));
+        assert_eq!(state.fiat_value(hundred).unwrap(), "≈ 12.50 USD");
+        let _ = update(&mut state, Message::FiatSelected(Fiat::Eur));
+        assert_eq!(state.fiat_value(hundred).unwrap(), "≈ 10.00 EUR");
+        assert_eq!(state.send_summary().unwrap(), before);
+
+        let _ = update(
+            &mut state,
+            Message::PricesFetched(Err("connection refused".to_string())),
+        );
+        assert_eq!(state.fiat_value(hundred).unwrap(), "price unavailable");
+    }
+
+    #[test]
+    fn test_max_follows_fee_and_selection() {
+        let mut state = funded_state(&[("aa", 2 * SOMPI_PER_KAS), ("bb", SOMPI_PER_KAS)]);
+        let _ = update(&mut state, Message::FeeChoiceSelected(FeeChoice::Preset(0)));
+        let _ = update(&mut state, Message::SendMax);
+        let low = state.parsed_amount().unwrap().unwrap();
+        assert!(low.sompi() < 3 * SOMPI_PER_KAS && low.sompi() > 2 * SOMPI_PER_KAS);
+
+        let _ = update(&mut state, Message::FeeChoiceSelected(FeeChoice::Preset(2)));
+        let priority = state.parsed_amount().unwrap().unwrap();
+        assert!(priority < low);
+
+        // Coin control limits it to the picked output.
+        let _ = update(&mut state, Message::InputToggled("bb".repeat(32), 0));
+        let picked = state.parsed_amount().unwrap().unwrap();
+        assert!(picked.sompi() < SOMPI_PER_KAS);
+
+        let _ = update(&mut state, Message::AmountInput("0.5".to_string()));
+        let _ = update(&mut state, Message::InputToggled("bb".repeat(32), 0));
+        assert_eq!(state.amount, "0.5");
+
+        let mut dust = funded_state(&[("aa", DUST_THRESHOLD)]);
+        assert!(dust.max_amount().unwrap_err().contains("dust limit"));
+        let _ = update(&mut dust, Message::SendMax);
+        assert!(dust.amount.is_empty());
+    }
+
+    #[test]
+    fn test_every_tab_lays_out_long_content() {
+        let mut state = funded_state(&[("aa", 2 * SOMPI_PER_KAS)]);
+        let address = state.wallet.as_ref().unwrap().address.clone();
+        for i in 0..30 {
+            state.outputs.push(OutputRow {
+                address: address.clone(),
+                amount: Kas::from_sompi(DUST_THRESHOLD + i),
+                label: (i % 2 == 0).then(|| format!("Contact {}", i)),
+            });
+        }
+        state.history_loaded = true;
+        state.explorer_url = "http://localhost:8000".to_string();
+        state.show_inputs = true;
+        state.settings.show_activity = true;
+        for tab in [Tab::Send, Tab::Receive, Tab::History, Tab::Contacts] {
+            state.current_tab = tab;
+            let _ = view(&state);
+        }
+        assert_eq!(shorten(&address).chars().count(), 19);
+        assert_eq!(shorten("kaspa:short"), "kaspa:short");
+    }
+
+    #[test]
+    fn test_send_without_outputs_stays_idle() {
+        let mut state = idle_state();
+        let _ = update(&mut state, Message::SendTransaction);
+        assert!(!state.busy.transaction);
+        assert_eq!(last_message(&state), "No outputs to send");
+    }
+
+    #[test]
+    fn test_tray_menu() {
+        let mut state = idle_state();
+        assert!(!state.tray_ready);
+        let _ = update(&mut state, Message::Tray(TrayAction::Lock));
+        assert!(!matches!(state.screen, Screen::Locked(_)));
+
+        let _ = update(
+            &mut state,
+            Message::WalletLoaded(create_wallet("mainnet").map_err(|e| e.to_string())),
+        );
+        let _ = update(&mut state, Message::TrayReady);
+        let _ = update(&mut state, Message::MinimizeToTrayToggled(true));
+        assert!(state.tray_ready && state.settings.minimize_to_tray);
+        assert!(state.current_settings().minimize_to_tray);
+        let _ = update(&mut state, Message::Tray(TrayAction::Lock));
+        assert!(matches!(state.screen, Screen::Locked(_)));
+        assert!(state.wallet_key.is_empty());
+    }
+
+    #[test]
+    fn test_dropped_files_open_the_right_way() {
+        let mut state = idle_state();
+        let (secret_key, _) = secp256k1::Secp256k1::new().generate_keypair(&mut rand::rngs::OsRng);
+        let wallet = KaspaWallet::with_network(secret_key, "mainnet").unwrap();
+        let keystore =
+            Keystore::encrypt(&wallet, "main", "correct horse battery staple drop").unwrap();
+        let path = PathBuf::from("main.keystore");
+
+        let _ = update(
+            &mut state,
+            Message::DroppedFileRead(path.clone(), Dropped::Keystore(Box::new(keystore))),
+        );
+        let prompt = state.prompt.as_ref().unwrap();
+        assert!(matches!(prompt.action, FileAction::Open(_)));
+        assert_eq!(prompt.path, path);
+
+        let _ = update(&mut state, Message::FileDropped(PathBuf::from("tx.txt")));
+        assert_eq!(newest_severity(&state), Severity::Warning);
+        assert!(last_message(&state).contains("Finish the open dialog"));
+        let _ = update(&mut state, Message::PromptCancel);
+
+        let mut tx = Transaction::new();
+        tx.add_input("11".repeat(32), 0);
+        tx.add_output(wallet.get_address(), 4_000);
+        let txid = tx.txid().unwrap();
+        let _ = update(
+            &mut state,
+            Message::DroppedFileRead(
+                PathBuf::from("/tmp/tx.txt"),
+                Dropped::Transaction(Box::new(TxDocument::Raw(tx))),
+            ),
+        );
+        let panel = state.decoded.as_ref().unwrap();
+        assert_eq!(
+            (panel.name.as_str(), panel.txid.as_str()),
+            ("tx.txt", txid.as_str())
+        );
+        assert_eq!(state.shortcut_context().modal, Modal::Dialog);
+        let _ = update(&mut state, Message::DismissModal);
+        assert!(state.decoded.is_none());
+
+        let _ = update(
+            &mut state,
+            Message::DroppedFileRead(
+                PathBuf::from("notes.txt"),
+                Dropped::Unsupported("notes.txt is not a wallet".to_string()),
+            ),
+        );
+        assert_eq!(newest_severity(&state), Severity::Warning);
+        assert_eq!(last_message(&state), "notes.txt is not a wallet");
+        assert!(state.prompt.is_none() && state.decoded.is_none());
+    }
+
+    #[test]
+    fn test_timed_flows_follow_the_clock() {
+        let clock = Rc::new(ManualClock(Cell::new(1_000_000)));
+        let mut state = idle_state();
+        state.clock = clock.clone();
+        state.settings.auto_lock_minutes = 5;
+        state.settings.clipboard_clear_secs = 30;
+        let _ = update(
+            &mut state,
+            Message::WalletLoaded(create_wallet("mainnet").map_err(|e| e.to_string())),
+        );
+        assert!(state.notifications.toasts().count() > 0);
+
+        // Toasts other than errors go once they are TOAST_MILLIS old.
+        clock.advance(notifications::TOAST_MILLIS);
+        let _ = update(&mut state, Message::Tick);
+        assert_eq!(state.notifications.toasts().count(), 0);
+
+        // The clipboard clears on the second it was due.
+        let _ = update(&mut state, Message::PrivateKeyCopied(true));
+        assert_eq!(
+            state.pending_clear.as_ref().unwrap().at,
+            clock.now() + 30_000
+        );
+        clock.advance(29_999);
+        let _ = update(&mut state, Message::Tick);
+        assert!(state.pending_clear.is_some());
+        clock.advance(1);
+        let _ = update(&mut state, Message::Tick);
+        assert!(state.pending_clear.is_none());
+
+        // Undo is offered for UNDO_MILLIS after a removal.
+        let own = state.wallet.as_ref().unwrap().address.clone();
+        state.outputs.push(OutputRow {
+            address: own,
+            amount: Kas::from_sompi(SOMPI_PER_KAS),
+            label: None,
+        });
+        let _ = update(&mut state, Message::RemoveOutput(0));
+        assert!(state.output_undo.offer(state.now()).is_some());
+        clock.advance(undo::UNDO_MILLIS);
+        assert!(state.output_undo.offer(state.now()).is_none());
+
+        // Locks five minutes after the removal, the last input; Ticks
+        // in between do not count.
+        let idle = 5 * 60_000 - undo::UNDO_MILLIS;
+        clock.advance(idle - 1);
+        let _ = update(&mut state, Message::Tick);
+        assert!(matches!(state.screen, Screen::Wallet));
+        clock.advance(1);
+        let _ = update(&mut state, Message::Tick);
+        assert!(matches!(state.screen, Screen::Locked(_)));
+    }
+
+    #[test]
+    fn test_sending_off_the_wallets_network_needs_a_tick() {
+        let mut state = funded_state(&[("aa", 2 * SOMPI_PER_KAS)]);
+        assert_eq!(state.network.tint(), NetworkTint::Mainnet);
+        assert_eq!(state.network_mismatch(), None);
+        let balance = state.balance.clone();
+        let key = state.wallet_key.clone();
+
+        // The badge follows the picker at once; the wallet reloads there.
+        let _ = update(
+            &mut state,
+            Message::NetworkSelected(NetworkOption::Testnet10),
+        );
+        assert_eq!(state.network.tint(), NetworkTint::Testnet);
+        let reloaded = load_wallet(&key, "testnet-10").map_err(describe);
+        let _ = update(&mut state, Message::WalletLoaded(reloaded));
+        let wallet = state.wallet.as_ref().unwrap();
+        assert_eq!(
+            (wallet.network_name.as_str(), wallet.home_network.as_str()),
+            ("testnet-10", "mainnet")
+        );
+        assert_eq!(state.network_mismatch().as_deref(), Some("Mainnet"));
+
+        state.balance = balance;
+        let own = wallet.address.clone();
+        state.outputs.push(OutputRow {
+            address: own,
+            amount: Kas::from_sompi(SOMPI_PER_KAS),
+            label: None,
+        });
+        let _ = update(&mut state, Message::SendTransaction);
+        let _ = update(&mut state, Message::ConfirmSend);
+        assert!(state.confirm_send.is_some() && !state.busy.transaction);
+        let _ = update(&mut state, Message::NetworkMismatchAcknowledged(true));
+        let _ = update(&mut state, Message::ConfirmSend);
+        assert!(state.confirm_send.is_none() && state.busy.transaction);
+
+        // Ticked once is not ticked for the next send.
+        state.busy.transaction = false;
+        let _ = update(&mut state, Message::SendTransaction);
+        assert!(!state.network_acknowledged);
+
+        // Back home, nothing to tick.
+        let _ = update(&mut state, Message::CancelSend);
+        let _ = update(&mut state, Message::NetworkSelected(NetworkOption::Mainnet));
+        let reloaded = load_wallet(&key, "mainnet").map_err(describe);
+        let _ = update(&mut state, Message::WalletLoaded(reloaded));
+        assert_eq!(state.network_mismatch(), None);
+        assert_eq!(NetworkOption::Simnet.tint(), NetworkTint::Simnet);
+    }
+
+    #[test]
+    fn test_export_leaves_the_key_out_unless_asked() {
+        let mut state = idle_state();
+        let _ = update(
+            &mut state,
+            Message::WalletLoaded(create_wallet("mainnet").map_err(|e| e.to_string())),
+        );
+        let wallet = state.wallet.clone().unwrap();
+        let info = state.wallet_info().unwrap();
+        assert_eq!(info.name, "Unsaved");
+        assert_eq!(info.fingerprint, wallet.fingerprint);
+        assert_eq!(info.addresses, vec![wallet.address.clone()]);
+        let json = info.to_json().unwrap();
+        assert!(!json.contains(&*state.wallet_key));
+        let descriptor = WalletDescriptor::from_json(&json).unwrap();
+        assert_eq!(descriptor.network, "mainnet");
+
+        // Without the key, straight to the save dialog.
+        let _ = update(&mut state, Message::ExportWalletInfo);
+        assert!(!state.confirm_key_export);
+
+        // With it, a warning first, and then the keystore's own prompt.
+        let _ = update(&mut state, Message::ExportKeyToggled(true));
+        let _ = update(&mut state, Message::ExportWalletInfo);
+        assert!(state.confirm_key_export);
+        assert_eq!(state.shortcut_context().modal, Modal::Dialog);
+        let _ = update(&mut state, Message::DismissModal);
+        assert!(!state.confirm_key_export && state.export_key);
+        let _ = update(&mut state, Message::ExportWalletInfo);
+        let _ = update(&mut state, Message::ConfirmKeyExport);
+        assert!(!state.confirm_key_export && !state.export_key);
+
+        let _ = update(
+            &mut state,
+            Message::WalletInfoExported(Err("disk full".to_string())),
+        );
+        assert_eq!(newest_severity(&state), Severity::Error);
+        assert_eq!(
+            last_message(&state),
+            "Could not export wallet info: disk full"
+        );
+    }
+
+    #[test]
+    fn test_outputs_and_clipboard_run_through_commands() {
+        let mut state = funded_state(&[("aa", 2 * SOMPI_PER_KAS)]);
+        let own = state.wallet.as_ref().unwrap().address.clone();
+        let mut shell = Shell::default();
+
+        // Editing outputs touches nothing outside the state.
+        let _ = update(&mut state, Message::RecipientInput(own.clone()));
+        let _ = update(&mut state, Message::AmountInput("1".to_string()));
+        assert!(update(&mut state, Message::AddOutput).is_empty());
+        let _ = update(&mut state, Message::RecipientInput(own.clone()));
+        let _ = update(&mut state, Message::AmountInput("0.5".to_string()));
+        assert!(update(&mut state, Message::AddOutput).is_empty());
+        assert_eq!(state.outputs.len(), 2);
+        assert!(update(&mut state, Message::RemoveOutput(0)).is_empty());
+        assert_eq!(state.outputs[0].amount.sompi(), 50_000_000);
+        assert!(update(&mut state, Message::ClearOutputs).is_empty());
+        assert!(state.outputs.is_empty());
+        assert_eq!(last_message(&state), "Cleared 1 outputs");
+
+        // Copy and paste go through the shell's clipboard.
+        shell.send(&mut state, Message::CopyAddress);
+        assert_eq!(shell.clipboard.as_deref(), Some(own.as_str()));
+        assert_eq!(last_message(&state), "Address copied to clipboard!");
+        shell.send(&mut state, Message::Paste(PasteTarget::Recipient));
+        assert_eq!(state.recipient, own);
+
+        // The validator moves between verdicts as the text changes.
+        shell.send(&mut state, Message::Paste(PasteTarget::Validation));
+        assert_eq!(state.validation_result, "Valid Kaspa address");
+        let _ = update(
+            &mut state,
+            Message::ValidateAddressInput("kaspa:nope".to_string()),
+        );
+        assert_ne!(state.validation_result, "Valid Kaspa address");
+        shell.clipboard = None;
+        shell.send(&mut state, Message::Paste(PasteTarget::Validation));
+        assert_eq!(last_message(&state), "Could not access clipboard");
+
+        // A copied key is cleared when due, and only if still there.
+        let _ = update(&mut state, Message::CopyPrivateKey);
+        let _ = update(&mut state, Message::KeyCopyAcknowledged(true));
+        shell.send(&mut state, Message::ConfirmKeyCopy);
+        assert_eq!(shell.clipboard.as_deref(), Some(&*state.wallet_key));
+        shell.send(&mut state, Message::ClearClipboard);
+        assert_eq!(shell.clipboard, None);
+        assert_eq!(
+            last_message(&state),
+            "Private key cleared from the clipboard"
+        );
+        assert!(shell.pending.is_empty());
+    }
+
+    #[test]
+    fn test_load_failures_reach_the_user() {
+        let mut state = idle_state();
+        let mut shell = Shell::default();
+        state.private_key = "not a key".to_string().into();
+        shell.send(&mut state, Message::LoadWallet);
+        assert!(!state.busy.wallet && state.wallet.is_none());
+        assert!(last_message(&state).starts_with("Error loading wallet:"));
+        assert_eq!(newest_severity(&state), Severity::Error);
+
+        state.backend = Arc::new(RefusingBackend);
+        state.private_key = create_wallet("mainnet").unwrap().private_key;
+        shell.send(&mut state, Message::LoadWallet);
+        assert!(!state.busy.wallet && state.wallet.is_none());
+        assert_eq!(
+            last_message(&state),
+            "Error loading wallet: Storage error: refused"
+        );
+        shell.send(&mut state, Message::CreateWallet);
+        assert!(!state.busy.wallet && !matches!(state.screen, Screen::Backup(_)));
+
+        // A missing keystore is reported, and no password is asked for.
+        let missing = std::env::temp_dir().join(format!(
+            "kasparustwallet-gui-missing-{}.keystore",
+            std::process::id()
+        ));
+        shell.send(&mut state, Message::OpenPathChosen(Some(missing)));
+        assert!(state.prompt.is_none());
+        assert!(last_message(&state).starts_with("Error opening wallet:"));
+        assert!(shell.pending.is_empty());
+    }
+
+    #[test]
+    fn test_unlock_runs_through_the_wallet_file() {
+        const PASSWORD: &str = "correct horse battery staple unlock";
+        let path = std::env::temp_dir().join(format!(
+            "kasparustwallet-gui-unlock-{}.keystore",
+            std::process::id()
+        ));
+        let mut state = idle_state();
+        let mut shell = Shell::default();
+        let loaded = create_wallet("mainnet").unwrap();
+        let key = loaded.private_key.clone();
+        let secret = loaded.secret.clone();
+        let file = save_wallet_file(&secret, "mainnet", path.clone(), PASSWORD).unwrap();
+        shell.send(&mut state, Message::WalletLoaded(Ok(loaded)));
+        state.wallet_file = Some(file);
+
+        shell.send(&mut state, Message::Lock);
+        assert!(matches!(state.screen, Screen::Locked(_)));
+        let _ = update(
+            &mut state,
+            Message::UnlockInput("not the password at all".to_string().into()),
+        );
+        let commands = update(&mut state, Message::UnlockSubmit);
+        assert!(state.busy.wallet);
+        assert!(matches!(
+            commands.as_slice(),
+            [Command::Wallet(WalletJob::UnlockFile { .. })]
+        ));
+        state.busy.wallet = false;
+        let _ = update(
+            &mut state,
+            Message::UnlockInput("not the password at all".to_string().into()),
+        );
+        shell.send(&mut state, Message::UnlockSubmit);
+        let Screen::Locked(lock) = &state.screen else {
+            panic!("unlocked with the wrong password");
+        };
+        assert!(lock.error.is_some() && !state.busy.wallet);
+
+        let _ = update(
+            &mut state,
+            Message::UnlockInput(PASSWORD.to_string().into()),
+        );
+        shell.send(&mut state, Message::UnlockSubmit);
+        assert!(matches!(state.screen, Screen::Wallet));
+        assert_eq!(state.wallet_key, key);
+        assert_eq!(state.wallet_secret, secret);
+
+        // Without a file the backend checks the key on the spot.
+        state.wallet_file = None;
+        state.backend = Arc::new(RefusingBackend);
+        shell.send(&mut state, Message::Lock);
+        let _ = update(&mut state, Message::UnlockInput(key.clone()));
+        assert!(update(&mut state, Message::UnlockSubmit).is_empty());
+        let Screen::Locked(lock) = &state.screen else {
+            panic!("unlocked past the backend");
+        };
+        assert_eq!(lock.error.as_deref(), Some("Storage error: refused"));
+        assert!(shell.pending.is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_send_confirmation_builds_then_broadcasts() {
+        let mut state = funded_state(&[("aa", 2 * SOMPI_PER_KAS)]);
+        let mut shell = Shell::default();
+        let own = state.wallet.as_ref().unwrap().address.clone();
+        state.outputs.push(OutputRow {
+            address: own,
+            amount: Kas::from_sompi(SOMPI_PER_KAS),
+            label: None,
+        });
+
+        // Nothing is signed until the summary is confirmed.
+        assert!(update(&mut state, Message::SendTransaction).is_empty());
+        assert!(state.confirm_send.is_some());
+        let commands = update(&mut state, Message::ConfirmSend);
+        let [Command::Wallet(WalletJob::Build {
+            outputs, summary, ..
+        })] = commands.as_slice()
+        else {
+            panic!("no transaction to build: {:?}", commands);
+        };
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[0].1, SOMPI_PER_KAS);
+        assert_eq!(summary.fee_rate, state.fee_rate().unwrap());
+        assert!(state.busy.transaction && state.confirm_send.is_none());
+        state.busy.transaction = false;
+
+        shell.send(&mut state, Message::SendTransaction);
+        shell.send(&mut state, Message::ConfirmSend);
+        assert!(!state.busy.transaction);
+        let txid = state.result.as_ref().unwrap().built.txid.clone();
+        assert!(last_message(&state).starts_with(&format!("Transaction {} created", txid)));
+
+        // Broadcasting asks first, and then hands the transaction to the
+        // shell for the node.
+        state.node_url = "grpc://127.0.0.1:16110".to_string();
+        shell.send(&mut state, Message::Broadcast);
+        assert!(shell.pending.is_empty());
+        shell.send(&mut state, Message::ConfirmBroadcast);
+        assert!(state.busy.broadcast);
+        assert!(matches!(
+            shell.pending.as_slice(),
+            [Command::Broadcast { built, force: false, .. }] if built.txid == txid
+        ));
+
+        // A signing failure leaves the outputs for another try.
+        let mut state = funded_state(&[("aa", 2 * SOMPI_PER_KAS)]);
+        state.backend = Arc::new(RefusingBackend);
+        let _ = built_transaction(&mut state);
+        shell.send(&mut state, Message::SendTransaction);
+        shell.send(&mut state, Message::ConfirmSend);
+        assert!(!state.busy.transaction && state.result.is_none());
+        assert_eq!(
+            last_message(&state),
+            "Transaction error: Storage error: refused"
+        );
+        assert_eq!(state.outputs.len(), 1);
+    }
 }
@@ -1,3 +1,13 @@
+mod clipboard;
+mod command;
+mod dropped;
+#[allow(clippy::module_inception)]
 pub mod gui;
+mod notifications;
+mod settings;
+mod shortcuts;
+mod style;
+mod tray;
+mod undo;
 
 pub use gui::run_gui;
@@ -0,0 +1,224 @@
+//! What the GUI tells the user: toasts for recent events, and an Activity
+//! drawer holding the last [`ACTIVITY_LIMIT`] of them.
+
+use super::style::Severity;
+use crate::history::format_time;
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Entries the Activity drawer keeps.
+pub const ACTIVITY_LIMIT: usize = 50;
+/// Milliseconds a toast stays up, unless it reports an error.
+pub const TOAST_MILLIS: u64 = 6_000;
+/// Toasts shown at once; older ones are only in the drawer.
+pub const MAX_TOASTS: usize = 4;
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Where the GUI reads the time, so tests can set it.
+pub trait Clock: fmt::Debug {
+    /// Unix milliseconds.
+    fn now(&self) -> u64;
+}
+
+#[derive(Debug)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        now_millis()
+    }
+}
+
+/// Text a notification offers to copy, such as a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Payload {
+    /// Names it in the Copy confirmation.
+    pub what: &'static str,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub id: u64,
+    pub severity: Severity,
+    pub text: String,
+    /// Unix milliseconds.
+    pub time: u64,
+    pub payload: Option<Payload>,
+    /// Still shown as a toast.
+    pub toast: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Notifications {
+    /// Oldest first.
+    entries: VecDeque<Notification>,
+    next_id: u64,
+}
+
+impl Notifications {
+    /// Adds a notification, shown as a toast, and returns its id.
+    pub fn push(
+        &mut self,
+        severity: Severity,
+        text: impl Into<String>,
+        payload: Option<Payload>,
+        now: u64,
+    ) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entries.push_back(Notification {
+            id,
+            severity,
+            text: text.into(),
+            time: now,
+            payload,
+            toast: true,
+        });
+        if self.entries.len() > ACTIVITY_LIMIT {
+            self.entries.pop_front();
+        }
+        id
+    }
+
+    /// Takes down the toast; the entry stays in the drawer.
+    pub fn dismiss(&mut self, id: u64) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.id == id) {
+            entry.toast = false;
+        }
+    }
+
+    /// Takes down toasts older than [`TOAST_MILLIS`], except errors.
+    pub fn expire(&mut self, now: u64) {
+        for entry in &mut self.entries {
+            if entry.severity != Severity::Error && entry.time + TOAST_MILLIS <= now {
+                entry.toast = false;
+            }
+        }
+    }
+
+    /// Whether a shown toast will expire on its own.
+    pub fn expiring(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.toast && entry.severity != Severity::Error)
+    }
+
+    /// The shown toasts, newest first.
+    pub fn toasts(&self) -> impl Iterator<Item = &Notification> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|entry| entry.toast)
+            .take(MAX_TOASTS)
+    }
+
+    /// Everything in the drawer, newest first.
+    pub fn activity(&self) -> impl Iterator<Item = &Notification> {
+        self.entries.iter().rev()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Notification> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The drawer as text, oldest first, for a bug report. Every non-empty
+    /// string in `secrets` is masked, and payloads are left out.
+    pub fn log(&self, secrets: &[&str]) -> String {
+        self.entries
+            .iter()
+            .map(|entry| {
+                let text = secrets
+                    .iter()
+                    .filter(|secret| !secret.is_empty())
+                    .fold(entry.text.clone(), |text, secret| {
+                        text.replace(secret, "[redacted]")
+                    });
+                format!(
+                    "{}  {:<7}  {}\n",
+                    format_time(entry.time),
+                    entry.severity,
+                    text
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_stay_until_dismissed() {
+        let mut notifications = Notifications::default();
+        let saved = notifications.push(Severity::Success, "Saved", None, 1_000);
+        let failed = notifications.push(Severity::Error, "Failed", None, 2_000);
+        assert!(notifications.expiring());
+
+        notifications.expire(1_000 + TOAST_MILLIS);
+        let shown: Vec<u64> = notifications.toasts().map(|toast| toast.id).collect();
+        assert_eq!(shown, vec![failed]);
+        assert!(!notifications.expiring());
+
+        notifications.expire(u64::MAX / 2);
+        assert_eq!(notifications.toasts().count(), 1);
+        notifications.dismiss(failed);
+        assert_eq!(notifications.toasts().count(), 0);
+        let drawer: Vec<u64> = notifications.activity().map(|entry| entry.id).collect();
+        assert_eq!(drawer, vec![failed, saved]);
+    }
+
+    #[test]
+    fn test_drawer_keeps_the_last_entries() {
+        let mut notifications = Notifications::default();
+        for i in 0..ACTIVITY_LIMIT + 5 {
+            notifications.push(Severity::Info, format!("event {}", i), None, 0);
+        }
+        assert_eq!(notifications.len(), ACTIVITY_LIMIT);
+        assert_eq!(
+            notifications.activity().last().unwrap().text,
+            format!("event {}", 5)
+        );
+        assert_eq!(notifications.toasts().count(), MAX_TOASTS);
+        assert_eq!(
+            notifications.activity().next().unwrap().id,
+            ACTIVITY_LIMIT as u64 + 4
+        );
+    }
+
+    #[test]
+    fn test_log_masks_secrets() {
+        let mut notifications = Notifications::default();
+        notifications.push(Severity::Error, "Bad key 00ff", None, 0);
+        let payload = Payload {
+            what: "Transaction",
+            value: "abcd".to_string(),
+        };
+        notifications.push(Severity::Success, "Built", Some(payload), 1_000);
+        assert_eq!(
+            notifications.log(&["", "00ff"]),
+            "1970-01-01 00:00:00  Error    Bad key [redacted]\n\
+             1970-01-01 00:00:01  Success  Built\n"
+        );
+    }
+}
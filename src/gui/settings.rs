@@ -0,0 +1,239 @@
+//! GUI preferences, kept in `gui.json` under the platform config dir.
+
+use crate::error::{WalletError, WalletResult};
+use crate::price::Fiat;
+use crate::storage::{self, Overwrite};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const GUI_SETTINGS_FILE: &str = "gui.json";
+
+/// Which palette the window uses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeChoice {
+    /// Light or dark, following the desktop.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemeChoice {
+    pub const ALL: [ThemeChoice; 3] = [ThemeChoice::System, ThemeChoice::Light, ThemeChoice::Dark];
+}
+
+impl fmt::Display for ThemeChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ThemeChoice::System => "System",
+            ThemeChoice::Light => "Light",
+            ThemeChoice::Dark => "Dark",
+        })
+    }
+}
+
+/// Written into every settings file. Fields only ever get added, each with
+/// a default, so older files still load; bump this if one must change
+/// meaning.
+pub const GUI_SETTINGS_VERSION: u32 = 1;
+
+/// Minutes without input before the window locks, unless changed.
+pub const DEFAULT_AUTO_LOCK_MINUTES: u32 = 10;
+
+fn default_auto_lock_minutes() -> u32 {
+    DEFAULT_AUTO_LOCK_MINUTES
+}
+
+/// Seconds a copied key stays on the clipboard, unless changed.
+pub const DEFAULT_CLIPBOARD_CLEAR_SECS: u64 = 30;
+
+fn default_clipboard_clear_secs() -> u64 {
+    DEFAULT_CLIPBOARD_CLEAR_SECS
+}
+
+/// Smallest and largest UI scale, in percent.
+pub const UI_SCALE_RANGE: (u32, u32) = (90, 150);
+
+fn default_ui_scale() -> u32 {
+    100
+}
+
+/// Per-user directory for the GUI settings.
+pub fn default_settings_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("kasparustwallet")
+}
+
+/// What the GUI remembers between sessions. Never holds a key or password.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuiSettings {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub theme: ThemeChoice,
+    #[serde(default)]
+    pub network: Option<String>,
+    #[serde(default)]
+    pub node_url: Option<String>,
+    #[serde(default)]
+    pub explorer_url: Option<String>,
+    /// Currency for approximate values.
+    #[serde(default)]
+    pub fiat: Fiat,
+    #[serde(default)]
+    pub price_url: Option<String>,
+    /// Keystore file last opened or saved.
+    #[serde(default)]
+    pub last_wallet: Option<PathBuf>,
+    /// Whether the Activity drawer is open.
+    #[serde(default)]
+    pub show_activity: bool,
+    /// Minutes without input before the window locks; 0 never locks.
+    #[serde(default = "default_auto_lock_minutes")]
+    pub auto_lock_minutes: u32,
+    /// Seconds before a copied key is cleared from the clipboard.
+    #[serde(default = "default_clipboard_clear_secs")]
+    pub clipboard_clear_secs: u64,
+    /// Size of text and controls, in percent.
+    #[serde(default = "default_ui_scale")]
+    pub ui_scale: u32,
+    /// Closing the window hides it in the tray, where there is one.
+    #[serde(default)]
+    pub minimize_to_tray: bool,
+}
+
+impl Default for GuiSettings {
+    fn default() -> Self {
+        Self {
+            version: GUI_SETTINGS_VERSION,
+            theme: ThemeChoice::default(),
+            network: None,
+            node_url: None,
+            explorer_url: None,
+            fiat: Fiat::default(),
+            price_url: None,
+            last_wallet: None,
+            show_activity: false,
+            auto_lock_minutes: DEFAULT_AUTO_LOCK_MINUTES,
+            clipboard_clear_secs: DEFAULT_CLIPBOARD_CLEAR_SECS,
+            ui_scale: default_ui_scale(),
+            minimize_to_tray: false,
+        }
+    }
+}
+
+impl GuiSettings {
+    /// Loads the settings, falling back to defaults when the file is missing.
+    pub fn load(dir: &Path) -> WalletResult<Self> {
+        let path = dir.join(GUI_SETTINGS_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        serde_json::from_str(&fs::read_to_string(&path)?).map_err(|e| {
+            WalletError::Storage(format!("Invalid GUI settings {}: {}", path.display(), e))
+        })
+    }
+
+    /// Like [`GuiSettings::load`], but a file that cannot be read is moved
+    /// aside and the defaults used. The second value says what happened.
+    pub fn load_or_recover(dir: &Path) -> (Self, Option<String>) {
+        let error = match Self::load(dir) {
+            Ok(settings) => return (settings, None),
+            Err(e) => e,
+        };
+        let path = dir.join(GUI_SETTINGS_FILE);
+        let aside = dir.join(format!("{}.corrupt", GUI_SETTINGS_FILE));
+        let note = match fs::rename(&path, &aside) {
+            Ok(()) => format!(
+                "{}. Using default settings; the old file was kept as {}",
+                error,
+                aside.display()
+            ),
+            Err(e) => format!(
+                "{}. Using default settings (could not move it aside: {})",
+                error, e
+            ),
+        };
+        (Self::default(), Some(note))
+    }
+
+    /// What the window multiplies every size by; a hand-edited scale out of
+    /// [`UI_SCALE_RANGE`] is brought back into it.
+    pub fn scale_factor(&self) -> f32 {
+        let (min, max) = UI_SCALE_RANGE;
+        self.ui_scale.clamp(min, max) as f32 / 100.0
+    }
+
+    pub fn save(&self, dir: &Path) -> WalletResult<()> {
+        fs::create_dir_all(dir)?;
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| WalletError::Serialization(format!("GUI settings encode error: {}", e)))?;
+        storage::write_atomic(
+            &dir.join(GUI_SETTINGS_FILE),
+            json.as_bytes(),
+            Overwrite::Allow,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "kasparustwallet-gui-settings-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_old_and_newer_files_load() {
+        let dir = temp_dir("versions");
+        fs::write(dir.join(GUI_SETTINGS_FILE), r#"{"theme": "dark"}"#).unwrap();
+        let settings = GuiSettings::load(&dir).unwrap();
+        assert_eq!(settings.theme, ThemeChoice::Dark);
+        assert_eq!(settings.network, None);
+        assert_eq!(settings.auto_lock_minutes, DEFAULT_AUTO_LOCK_MINUTES);
+        assert_eq!(settings.clipboard_clear_secs, DEFAULT_CLIPBOARD_CLEAR_SECS);
+        assert_eq!(settings.scale_factor(), 1.0);
+
+        fs::write(
+            dir.join(GUI_SETTINGS_FILE),
+            r#"{"version": 9, "network": "testnet-10", "added_later": [1, 2]}"#,
+        )
+        .unwrap();
+        let settings = GuiSettings::load(&dir).unwrap();
+        assert_eq!(settings.network.as_deref(), Some("testnet-10"));
+
+        fs::write(dir.join(GUI_SETTINGS_FILE), r#"{"ui_scale": 400}"#).unwrap();
+        assert_eq!(GuiSettings::load(&dir).unwrap().scale_factor(), 1.5);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_corrupt_file_is_moved_aside() {
+        let dir = temp_dir("corrupt");
+        fs::write(dir.join(GUI_SETTINGS_FILE), "{not json").unwrap();
+        let (settings, note) = GuiSettings::load_or_recover(&dir);
+        assert_eq!(settings, GuiSettings::default());
+        assert!(note.unwrap().contains("gui.json.corrupt"));
+        assert_eq!(
+            fs::read_to_string(dir.join("gui.json.corrupt")).unwrap(),
+            "{not json"
+        );
+        assert!(!dir.join(GUI_SETTINGS_FILE).exists());
+
+        let (_, note) = GuiSettings::load_or_recover(&dir);
+        assert_eq!(note, None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
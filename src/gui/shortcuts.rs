@@ -0,0 +1,167 @@
+//! Keyboard shortcuts: what a key press does, given what the window
+//! shows. Kept apart from the event loop so the mapping can be tested.
+
+use iced::keyboard::{key::Named, Key, Modifiers};
+
+/// Each shortcut and what it does, as the Shortcuts popup lists them.
+/// Ctrl is Cmd on macOS.
+pub const SHORTCUTS: [(&str, &str); 4] = [
+    ("Ctrl+1 … Ctrl+4", "Send, Receive, History or Contacts tab"),
+    (
+        "Ctrl+Enter",
+        "Add the output being typed on the Send tab, or Send once the outputs were edited last",
+    ),
+    ("Ctrl+L", "Lock the window"),
+    ("Escape", "Close the open dialog"),
+];
+
+/// What is in front, as far as shortcuts care.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modal {
+    /// The main page.
+    None,
+    /// A dialog with buttons only, such as the send confirmation.
+    Dialog,
+    /// A dialog or screen waiting for typed input: the password prompt,
+    /// the unlock screen, the seed phrase quiz. Only Escape works, and only
+    /// where there is something to back out of.
+    Typing { dismissable: bool },
+}
+
+/// Which part of the Send tab was used last, standing in for keyboard
+/// focus, which iced does not report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SendFocus {
+    #[default]
+    None,
+    /// The recipient and amount fields.
+    Fields,
+    /// The list of queued outputs.
+    Outputs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Context {
+    pub modal: Modal,
+    pub send_tab: bool,
+    pub focus: SendFocus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shortcut {
+    /// The tab at this position, from 0.
+    Tab(usize),
+    AddOutput,
+    Send,
+    Lock,
+    Dismiss,
+}
+
+/// The shortcut `key` with `modifiers` triggers in `context`, if any.
+pub fn dispatch(key: &Key, modifiers: Modifiers, context: Context) -> Option<Shortcut> {
+    if let Key::Named(Named::Escape) = key {
+        return match context.modal {
+            Modal::Dialog | Modal::Typing { dismissable: true } => Some(Shortcut::Dismiss),
+            Modal::None | Modal::Typing { dismissable: false } => None,
+        };
+    }
+    if !modifiers.command() || modifiers.alt() || matches!(context.modal, Modal::Typing { .. }) {
+        return None;
+    }
+    match key.as_ref() {
+        Key::Character(c) if c.eq_ignore_ascii_case("l") => Some(Shortcut::Lock),
+        _ if context.modal != Modal::None => None,
+        Key::Character(c) => match c {
+            "1" => Some(Shortcut::Tab(0)),
+            "2" => Some(Shortcut::Tab(1)),
+            "3" => Some(Shortcut::Tab(2)),
+            "4" => Some(Shortcut::Tab(3)),
+            _ => None,
+        },
+        Key::Named(Named::Enter) if context.send_tab => match context.focus {
+            SendFocus::Fields => Some(Shortcut::AddOutput),
+            SendFocus::Outputs => Some(Shortcut::Send),
+            SendFocus::None => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(focus: SendFocus) -> Context {
+        Context {
+            modal: Modal::None,
+            send_tab: true,
+            focus,
+        }
+    }
+
+    fn key(c: &str) -> Key {
+        Key::Character(c.into())
+    }
+
+    #[test]
+    fn test_ctrl_shortcuts_on_the_page() {
+        let ctrl = Modifiers::COMMAND;
+        let context = page(SendFocus::Fields);
+        assert_eq!(dispatch(&key("1"), ctrl, context), Some(Shortcut::Tab(0)));
+        assert_eq!(dispatch(&key("4"), ctrl, context), Some(Shortcut::Tab(3)));
+        assert_eq!(dispatch(&key("5"), ctrl, context), None);
+        assert_eq!(dispatch(&key("L"), ctrl, context), Some(Shortcut::Lock));
+        assert_eq!(dispatch(&key("1"), Modifiers::empty(), context), None);
+        assert_eq!(dispatch(&key("1"), ctrl | Modifiers::ALT, context), None);
+
+        let enter = Key::Named(Named::Enter);
+        assert_eq!(dispatch(&enter, ctrl, context), Some(Shortcut::AddOutput));
+        assert_eq!(
+            dispatch(&enter, ctrl, page(SendFocus::Outputs)),
+            Some(Shortcut::Send)
+        );
+        assert_eq!(dispatch(&enter, ctrl, page(SendFocus::None)), None);
+        assert_eq!(dispatch(&enter, Modifiers::empty(), context), None);
+        let receive = Context {
+            send_tab: false,
+            ..context
+        };
+        assert_eq!(dispatch(&enter, ctrl, receive), None);
+        assert_eq!(dispatch(&Key::Named(Named::Escape), ctrl, context), None);
+    }
+
+    #[test]
+    fn test_modals_hold_off_shortcuts() {
+        let ctrl = Modifiers::COMMAND;
+        let escape = Key::Named(Named::Escape);
+        let dialog = Context {
+            modal: Modal::Dialog,
+            ..page(SendFocus::Outputs)
+        };
+        assert_eq!(
+            dispatch(&escape, Modifiers::empty(), dialog),
+            Some(Shortcut::Dismiss)
+        );
+        assert_eq!(dispatch(&key("2"), ctrl, dialog), None);
+        assert_eq!(dispatch(&Key::Named(Named::Enter), ctrl, dialog), None);
+        assert_eq!(dispatch(&key("l"), ctrl, dialog), Some(Shortcut::Lock));
+
+        let prompt = Context {
+            modal: Modal::Typing { dismissable: true },
+            ..dialog
+        };
+        assert_eq!(
+            dispatch(&escape, Modifiers::empty(), prompt),
+            Some(Shortcut::Dismiss)
+        );
+        assert_eq!(dispatch(&key("l"), ctrl, prompt), None);
+        assert_eq!(dispatch(&key("1"), ctrl, prompt), None);
+
+        let unlock = Context {
+            modal: Modal::Typing { dismissable: false },
+            ..dialog
+        };
+        assert_eq!(dispatch(&escape, Modifiers::empty(), unlock), None);
+        assert_eq!(dispatch(&key("l"), ctrl, unlock), None);
+    }
+}
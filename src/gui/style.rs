@@ -0,0 +1,228 @@
+//! Themes and shared text styles for the GUI.
+
+use super::settings::ThemeChoice;
+use iced::theme::{self, palette, Palette};
+use iced::widget::text::{self, IntoFragment, Text, Wrapping};
+use iced::widget::{button, container, text_input};
+use iced::{color, Background, Border, Color, Font, Theme};
+use std::fmt;
+use std::sync::LazyLock;
+
+/// iced's light palette with a darker amber, so warnings read on white.
+static LIGHT: LazyLock<Theme> = LazyLock::new(|| {
+    Theme::custom(
+        "KaspaRustWallet Light",
+        Palette {
+            success: color!(0x1a7f37),
+            warning: color!(0x9a6700),
+            danger: color!(0xc3423f),
+            ..Palette::LIGHT
+        },
+    )
+});
+
+/// iced's dark palette with lighter green and red, which are otherwise too
+/// dim against the background.
+static DARK: LazyLock<Theme> = LazyLock::new(|| {
+    Theme::custom(
+        "KaspaRustWallet Dark",
+        Palette {
+            success: color!(0x4cc38a),
+            warning: color!(0xffc14e),
+            danger: color!(0xf2726f),
+            ..Palette::DARK
+        },
+    )
+});
+
+/// The theme for `choice`; `mode` is the desktop's, which `System`
+/// follows. `None` leaves it to iced when the desktop has no preference.
+pub fn theme(choice: ThemeChoice, mode: theme::Mode) -> Option<Theme> {
+    let dark = match (choice, mode) {
+        (ThemeChoice::Light, _) => false,
+        (ThemeChoice::Dark, _) => true,
+        (ThemeChoice::System, theme::Mode::Light) => false,
+        (ThemeChoice::System, theme::Mode::Dark) => true,
+        (ThemeChoice::System, theme::Mode::None) => return None,
+    };
+    Some(if dark { DARK.clone() } else { LIGHT.clone() })
+}
+
+/// How a status bar message reads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Severity {
+    #[default]
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `pad` so log lines can align it.
+        f.pad(match self {
+            Severity::Info => "Info",
+            Severity::Success => "Success",
+            Severity::Warning => "Warning",
+            Severity::Error => "Error",
+        })
+    }
+}
+
+impl Severity {
+    pub fn style(self) -> fn(&Theme) -> text::Style {
+        match self {
+            Severity::Info => text::base,
+            Severity::Success => text::success,
+            Severity::Warning => text::warning,
+            Severity::Error => text::danger,
+        }
+    }
+
+    /// The colour [`Severity::style`] gives text, for borders and accents.
+    pub fn color(self, theme: &Theme) -> Color {
+        match self {
+            Severity::Info => theme.palette().text,
+            Severity::Success => theme.palette().success,
+            Severity::Warning => theme.palette().warning,
+            Severity::Error => theme.palette().danger,
+        }
+    }
+}
+
+/// A text input styled by whether its content checked out: a green border
+/// when valid, red when not, the default while there is nothing to check.
+pub fn validated_input(
+    valid: Option<bool>,
+) -> impl Fn(&Theme, text_input::Status) -> text_input::Style {
+    move |theme, status| {
+        let mut style = text_input::default(theme, status);
+        match valid {
+            Some(true) => style.border.color = theme.palette().success,
+            Some(false) => style.border.color = theme.palette().danger,
+            None => {}
+        }
+        style
+    }
+}
+
+/// A text input whose content is well formed but not usable as it stands,
+/// such as an address on another network: an amber border.
+pub fn warned_input(theme: &Theme, status: text_input::Status) -> text_input::Style {
+    let mut style = text_input::default(theme, status);
+    style.border.color = theme.palette().warning;
+    style
+}
+
+/// How a network is marked wherever money could move, so a mainnet
+/// send never passes for a test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkTint {
+    /// The theme's own colours.
+    Mainnet,
+    /// Amber.
+    Testnet,
+    /// Purple.
+    Simnet,
+}
+
+impl NetworkTint {
+    /// `None` for mainnet.
+    pub fn color(self, theme: &Theme) -> Option<Color> {
+        match self {
+            NetworkTint::Mainnet => None,
+            NetworkTint::Testnet => Some(theme.palette().warning),
+            NetworkTint::Simnet if theme.extended_palette().is_dark => Some(color!(0xc297ff)),
+            NetworkTint::Simnet => Some(color!(0x8250df)),
+        }
+    }
+
+    /// The header badge: outlined for mainnet, filled otherwise.
+    pub fn badge(self) -> impl Fn(&Theme) -> container::Style {
+        move |theme| match self.color(theme) {
+            None => container::bordered_box(theme),
+            Some(color) => {
+                let pair = palette::Pair::new(color, theme.palette().text);
+                container::Style {
+                    background: Some(Background::Color(pair.color)),
+                    text_color: Some(pair.text),
+                    border: Border {
+                        width: 1.0,
+                        radius: 5.0.into(),
+                        color,
+                    },
+                    ..container::Style::default()
+                }
+            }
+        }
+    }
+
+    /// The Send button and Confirm: primary on mainnet, the tint otherwise.
+    pub fn button(self) -> impl Fn(&Theme, button::Status) -> button::Style {
+        move |theme, status| {
+            let mut style = button::primary(theme, status);
+            if let Some(color) = self.color(theme) {
+                let pair = palette::Pair::new(color, theme.palette().text);
+                let background = match status {
+                    button::Status::Disabled => pair.color.scale_alpha(0.5),
+                    button::Status::Hovered => pair.color.scale_alpha(0.85),
+                    button::Status::Active | button::Status::Pressed => pair.color,
+                };
+                style.background = Some(Background::Color(background));
+                style.text_color = if status == button::Status::Disabled {
+                    pair.text.scale_alpha(0.5)
+                } else {
+                    pair.text
+                };
+            }
+            style
+        }
+    }
+
+    /// A dialog framed in the tint.
+    pub fn dialog(self) -> impl Fn(&Theme) -> container::Style {
+        move |theme| {
+            let mut style = container::rounded_box(theme);
+            if let Some(color) = self.color(theme) {
+                style.border = Border {
+                    width: 3.0,
+                    radius: 5.0.into(),
+                    color,
+                };
+            }
+            style
+        }
+    }
+}
+
+/// Text sizes every view picks from. The UI scale setting multiplies them,
+/// along with spacing and padding, when the window renders.
+pub const SMALL: f32 = 12.0;
+pub const BODY: f32 = 14.0;
+pub const LARGE: f32 = 16.0;
+pub const HEADING: f32 = 20.0;
+pub const TITLE: f32 = 24.0;
+
+/// Monospaced text for addresses, keys and txids, so that long strings
+/// line up when compared by eye.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mono {
+    pub size: f32,
+}
+
+impl Mono {
+    /// Addresses.
+    pub const ADDRESS: Mono = Mono { size: BODY };
+    /// Public keys, txids and other hex.
+    pub const HEX: Mono = Mono { size: SMALL };
+
+    pub fn text<'a>(self, content: impl IntoFragment<'a>) -> Text<'a> {
+        // Addresses and hex have no spaces; let them break anywhere
+        // rather than overflow a narrow window.
+        iced::widget::text(content)
+            .font(Font::MONOSPACE)
+            .size(self.size)
+            .wrapping(Wrapping::WordOrGlyph)
+    }
+}
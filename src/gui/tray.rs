@@ -0,0 +1,337 @@
+//! The system tray icon and desktop notifications, spoken over the D-Bus
+//! session bus: a StatusNotifierItem with a dbusmenu for Show, Lock and
+//! Quit, and `org.freedesktop.Notifications` for payments.
+//!
+//! Built only with the `tray` feature. Without it, or on a desktop with no
+//! session bus or no tray host, [`start`] fails and the window carries on
+//! without a tray.
+
+/// What was picked in the tray's menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayAction {
+    Show,
+    Lock,
+    Quit,
+}
+
+#[cfg(feature = "tray")]
+pub use imp::{notify, start};
+
+#[cfg(not(feature = "tray"))]
+pub use fallback::{notify, start};
+
+#[cfg(not(feature = "tray"))]
+mod fallback {
+    use super::TrayAction;
+    use iced::futures::channel::mpsc::UnboundedSender;
+
+    const UNSUPPORTED: &str = "built without the tray feature";
+
+    /// Never built without the `tray` feature.
+    pub struct Tray;
+
+    pub async fn start(_actions: UnboundedSender<TrayAction>) -> Result<Tray, String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub async fn notify(_summary: String, _body: String) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+}
+
+#[cfg(feature = "tray")]
+mod imp {
+    use super::TrayAction;
+    use iced::futures::channel::mpsc::UnboundedSender;
+    use std::collections::HashMap;
+    use zbus::zvariant::{OwnedObjectPath, OwnedValue, Structure, Value};
+    use zbus::{connection, interface, Connection};
+
+    const ITEM_PATH: &str = "/StatusNotifierItem";
+    const MENU_PATH: &str = "/MenuBar";
+    const APP_ID: &str = "kasparustwallet";
+    const TITLE: &str = "Kaspa Wallet";
+    /// Side of the icon, in pixels.
+    const ICON_SIZE: i32 = 32;
+
+    /// The menu, by dbusmenu item id. Id 0 is the root.
+    const MENU: [(i32, &str, TrayAction); 3] = [
+        (1, "Show", TrayAction::Show),
+        (2, "Lock", TrayAction::Lock),
+        (3, "Quit", TrayAction::Quit),
+    ];
+
+    /// The tray icon; it goes away when this is dropped.
+    pub struct Tray {
+        _connection: Connection,
+    }
+
+    /// Puts the icon in the tray. Menu picks and clicks on the icon are
+    /// sent to `actions`.
+    pub async fn start(actions: UnboundedSender<TrayAction>) -> Result<Tray, String> {
+        let name = format!("org.kde.StatusNotifierItem-{}-1", std::process::id());
+        let connection = connection::Builder::session()
+            .and_then(|builder| builder.name(name.as_str()))
+            .and_then(|builder| {
+                builder.serve_at(
+                    ITEM_PATH,
+                    Item {
+                        actions: actions.clone(),
+                    },
+                )
+            })
+            .and_then(|builder| builder.serve_at(MENU_PATH, Menu { actions }))
+            .map_err(|e| format!("no session bus: {}", e))?
+            .build()
+            .await
+            .map_err(|e| format!("no session bus: {}", e))?;
+        connection
+            .call_method(
+                Some("org.kde.StatusNotifierWatcher"),
+                "/StatusNotifierWatcher",
+                Some("org.kde.StatusNotifierWatcher"),
+                "RegisterStatusNotifierItem",
+                &(name.as_str(),),
+            )
+            .await
+            .map_err(|e| format!("no tray host: {}", e))?;
+        Ok(Tray {
+            _connection: connection,
+        })
+    }
+
+    /// Shows a desktop notification.
+    pub async fn notify(summary: String, body: String) -> Result<(), String> {
+        let connection = Connection::session()
+            .await
+            .map_err(|e| format!("no session bus: {}", e))?;
+        let hints: HashMap<&str, Value<'_>> = HashMap::new();
+        connection
+            .call_method(
+                Some("org.freedesktop.Notifications"),
+                "/org/freedesktop/Notifications",
+                Some("org.freedesktop.Notifications"),
+                "Notify",
+                &(
+                    TITLE,
+                    0u32,
+                    "",
+                    summary.as_str(),
+                    body.as_str(),
+                    Vec::<&str>::new(),
+                    hints,
+                    -1i32,
+                ),
+            )
+            .await
+            .map_err(|e| format!("no notification server: {}", e))?;
+        Ok(())
+    }
+
+    /// A filled disc in the wallet's accent colour, as ARGB32 in network
+    /// byte order.
+    fn icon() -> Vec<u8> {
+        let center = (ICON_SIZE as f32 - 1.0) / 2.0;
+        let radius = ICON_SIZE as f32 / 2.0 - 1.0;
+        (0..ICON_SIZE * ICON_SIZE)
+            .flat_map(|i| {
+                let (x, y) = ((i % ICON_SIZE) as f32, (i / ICON_SIZE) as f32);
+                let inside = (x - center).hypot(y - center) <= radius;
+                if inside {
+                    [0xff, 0x49, 0xea, 0xcb]
+                } else {
+                    [0, 0, 0, 0]
+                }
+            })
+            .collect()
+    }
+
+    struct Item {
+        actions: UnboundedSender<TrayAction>,
+    }
+
+    #[interface(name = "org.kde.StatusNotifierItem")]
+    impl Item {
+        #[zbus(property)]
+        fn category(&self) -> &str {
+            "ApplicationStatus"
+        }
+
+        #[zbus(property)]
+        fn id(&self) -> &str {
+            APP_ID
+        }
+
+        #[zbus(property)]
+        fn title(&self) -> &str {
+            TITLE
+        }
+
+        #[zbus(property)]
+        fn status(&self) -> &str {
+            "Active"
+        }
+
+        #[zbus(property)]
+        fn icon_name(&self) -> &str {
+            ""
+        }
+
+        #[zbus(property)]
+        fn icon_pixmap(&self) -> Vec<(i32, i32, Vec<u8>)> {
+            vec![(ICON_SIZE, ICON_SIZE, icon())]
+        }
+
+        #[zbus(property)]
+        fn item_is_menu(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn menu(&self) -> OwnedObjectPath {
+            OwnedObjectPath::try_from(MENU_PATH).expect("valid object path")
+        }
+
+        fn activate(&self, _x: i32, _y: i32) {
+            let _ = self.actions.unbounded_send(TrayAction::Show);
+        }
+
+        fn secondary_activate(&self, _x: i32, _y: i32) {}
+
+        fn context_menu(&self, _x: i32, _y: i32) {}
+
+        fn scroll(&self, _delta: i32, _orientation: &str) {}
+    }
+
+    struct Menu {
+        actions: UnboundedSender<TrayAction>,
+    }
+
+    fn properties(id: i32) -> HashMap<String, Value<'static>> {
+        let mut properties = HashMap::new();
+        match MENU.iter().find(|(item, _, _)| *item == id) {
+            Some((_, label, _)) => {
+                properties.insert("label".to_string(), Value::from(*label));
+            }
+            None if id == 0 => {
+                properties.insert("children-display".to_string(), Value::from("submenu"));
+            }
+            None => {}
+        }
+        properties
+    }
+
+    type Layout = (i32, HashMap<String, Value<'static>>, Vec<Value<'static>>);
+
+    fn layout() -> Layout {
+        let children = MENU
+            .iter()
+            .map(|(id, _, _)| {
+                Value::from(Structure::from((
+                    *id,
+                    properties(*id),
+                    Vec::<Value<'static>>::new(),
+                )))
+            })
+            .collect();
+        (0, properties(0), children)
+    }
+
+    #[interface(name = "com.canonical.dbusmenu")]
+    impl Menu {
+        #[zbus(property)]
+        fn version(&self) -> u32 {
+            3
+        }
+
+        #[zbus(property)]
+        fn text_direction(&self) -> &str {
+            "ltr"
+        }
+
+        #[zbus(property)]
+        fn status(&self) -> &str {
+            "normal"
+        }
+
+        #[zbus(property)]
+        fn icon_theme_path(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        /// The menu never changes, so it is always revision 1.
+        fn get_layout(
+            &self,
+            _parent_id: i32,
+            _recursion_depth: i32,
+            _property_names: Vec<String>,
+        ) -> (u32, Layout) {
+            (1, layout())
+        }
+
+        fn get_group_properties(
+            &self,
+            ids: Vec<i32>,
+            _property_names: Vec<String>,
+        ) -> Vec<(i32, HashMap<String, Value<'static>>)> {
+            ids.into_iter().map(|id| (id, properties(id))).collect()
+        }
+
+        fn get_property(&self, id: i32, name: &str) -> Value<'static> {
+            properties(id)
+                .remove(name)
+                .unwrap_or_else(|| Value::from(""))
+        }
+
+        fn event(&self, id: i32, event_id: &str, _data: OwnedValue, _timestamp: u32) {
+            if event_id != "clicked" {
+                return;
+            }
+            if let Some((_, _, action)) = MENU.iter().find(|(item, _, _)| *item == id) {
+                let _ = self.actions.unbounded_send(*action);
+            }
+        }
+
+        /// Returns the ids it did not know.
+        fn event_group(&self, events: Vec<(i32, String, OwnedValue, u32)>) -> Vec<i32> {
+            let mut unknown = Vec::new();
+            for (id, event_id, data, timestamp) in events {
+                if MENU.iter().any(|(item, _, _)| *item == id) {
+                    self.event(id, &event_id, data, timestamp);
+                } else {
+                    unknown.push(id);
+                }
+            }
+            unknown
+        }
+
+        fn about_to_show(&self, _id: i32) -> bool {
+            false
+        }
+
+        fn about_to_show_group(&self, _ids: Vec<i32>) -> (Vec<i32>, Vec<i32>) {
+            (Vec::new(), Vec::new())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_layout_lists_the_menu() {
+            let (root, properties, children) = layout();
+            assert_eq!(root, 0);
+            assert_eq!(
+                properties.get("children-display"),
+                Some(&Value::from("submenu"))
+            );
+            assert_eq!(children.len(), MENU.len());
+            assert_eq!(
+                super::properties(2).get("label"),
+                Some(&Value::from("Lock"))
+            );
+            assert_eq!(icon().len(), (ICON_SIZE * ICON_SIZE * 4) as usize);
+        }
+    }
+}
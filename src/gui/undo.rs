@@ -0,0 +1,126 @@
+//! Undo for changes to the Send tab's queued outputs, offered in a toast
+//! for a few seconds after each removal.
+
+/// Changes kept; older ones cannot be undone.
+pub const UNDO_LIMIT: usize = 10;
+/// Milliseconds the Undo toast stays up after a change.
+pub const UNDO_MILLIS: u64 = 10_000;
+
+/// A list as it was before a change, and what the change was.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Snapshot<T> {
+    items: Vec<T>,
+    /// "Removed output 3", as the toast says it.
+    what: String,
+    /// Unix milliseconds.
+    time: u64,
+}
+
+/// Earlier versions of a list, newest last.
+#[derive(Debug, Clone)]
+pub struct UndoStack<T> {
+    snapshots: Vec<Snapshot<T>>,
+}
+
+impl<T> Default for UndoStack<T> {
+    fn default() -> Self {
+        Self {
+            snapshots: Vec::new(),
+        }
+    }
+}
+
+impl<T: Clone> UndoStack<T> {
+    /// Remembers `before`, the list as it was before the change `what`,
+    /// dropping the oldest change beyond [`UNDO_LIMIT`].
+    pub fn record(&mut self, before: &[T], what: impl Into<String>, now: u64) {
+        self.snapshots.push(Snapshot {
+            items: before.to_vec(),
+            what: what.into(),
+            time: now,
+        });
+        if self.snapshots.len() > UNDO_LIMIT {
+            self.snapshots.remove(0);
+        }
+    }
+
+    /// The list before the newest change, exactly as it was, and what that
+    /// change was.
+    pub fn undo(&mut self) -> Option<(Vec<T>, String)> {
+        self.snapshots
+            .pop()
+            .map(|snapshot| (snapshot.items, snapshot.what))
+    }
+
+    /// What the Undo toast offers to take back: the newest change, while it
+    /// is less than [`UNDO_MILLIS`] old.
+    pub fn offer(&self, now: u64) -> Option<&str> {
+        self.snapshots
+            .last()
+            .filter(|snapshot| now < snapshot.time + UNDO_MILLIS)
+            .map(|snapshot| snapshot.what.as_str())
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
+
+/// `items` without the one at `index`, or `None` when there is none.
+pub fn removed<T: Clone>(items: &[T], index: usize) -> Option<Vec<T>> {
+    (index < items.len()).then(|| {
+        let mut rest = items.to_vec();
+        rest.remove(index);
+        rest
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_restores_order() {
+        let mut stack = UndoStack::default();
+        let outputs = vec!["a", "b", "c", "d"];
+
+        let after_remove = removed(&outputs, 1).unwrap();
+        stack.record(&outputs, "Removed output 1", 1_000);
+        assert_eq!(after_remove, vec!["a", "c", "d"]);
+        assert_eq!(removed(&after_remove, 3), None);
+
+        stack.record(&after_remove, "Cleared 3 outputs", 2_000);
+        assert_eq!(stack.offer(2_000), Some("Cleared 3 outputs"));
+
+        let (restored, what) = stack.undo().unwrap();
+        assert_eq!(restored, after_remove);
+        assert_eq!(what, "Cleared 3 outputs");
+        let (restored, _) = stack.undo().unwrap();
+        assert_eq!(restored, outputs);
+        assert!(stack.undo().is_none());
+    }
+
+    #[test]
+    fn test_offer_expires_and_stack_is_capped() {
+        let mut stack = UndoStack::default();
+        stack.record(&[1], "Removed output 0", 0);
+        assert_eq!(stack.offer(UNDO_MILLIS - 1), Some("Removed output 0"));
+        assert_eq!(stack.offer(UNDO_MILLIS), None);
+        // Expired from the toast, but still on the stack.
+        assert_eq!(stack.clone().undo().unwrap().0, vec![1]);
+
+        for i in 0..UNDO_LIMIT + 3 {
+            stack.record(&[i], format!("change {}", i), 0);
+        }
+        let mut undone = 0;
+        while let Some((items, _)) = stack.undo() {
+            undone += 1;
+            assert_eq!(items, vec![UNDO_LIMIT + 3 - undone]);
+        }
+        assert_eq!(undone, UNDO_LIMIT);
+
+        stack.record(&[1], "Removed output 0", 0);
+        stack.clear();
+        assert!(stack.undo().is_none());
+    }
+}
@@ -0,0 +1,481 @@
+use crate::address::{compute_checksum, hash160};
+use crate::error::{WalletError, WalletResult};
+use hmac::{Hmac, Mac};
+use secp256k1::{PublicKey, Scalar, Secp256k1, SecretKey};
+use sha2::Sha512;
+use std::fmt;
+use std::str::FromStr;
+use zeroize::Zeroize;
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// SLIP-44 coin type registered for Kaspa.
+pub const KASPA_COIN_TYPE: u32 = 111111;
+
+/// Number of consecutive unused addresses scanned before giving up.
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+pub const RECEIVE_CHAIN: u32 = 0;
+pub const CHANGE_CHAIN: u32 = 1;
+
+const HARDENED_BIT: u32 = 0x8000_0000;
+
+const KPRV_VERSION: [u8; 4] = [0x03, 0x8f, 0x2e, 0xf4];
+const KPUB_VERSION: [u8; 4] = [0x03, 0x8f, 0x33, 0x2e];
+const XPRV_VERSION: [u8; 4] = [0x04, 0x88, 0xad, 0xe4];
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChildNumber(u32);
+
+impl ChildNumber {
+    pub fn normal(index: u32) -> WalletResult<Self> {
+        if index & HARDENED_BIT != 0 {
+            return Err(WalletError::InvalidParameters(format!(
+                "Child index out of range: {}",
+                index
+            )));
+        }
+        Ok(Self(index))
+    }
+
+    pub fn hardened(index: u32) -> WalletResult<Self> {
+        Ok(Self(Self::normal(index)?.0 | HARDENED_BIT))
+    }
+
+    pub fn is_hardened(&self) -> bool {
+        self.0 & HARDENED_BIT != 0
+    }
+
+    pub fn index(&self) -> u32 {
+        self.0 & !HARDENED_BIT
+    }
+
+    pub fn to_u32(self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Display for ChildNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_hardened() {
+            write!(f, "{}'", self.index())
+        } else {
+            write!(f, "{}", self.index())
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DerivationPath(Vec<ChildNumber>);
+
+impl DerivationPath {
+    /// The standard Kaspa account path `m/44'/111111'/{account}'`.
+    pub fn kaspa_account(account: u32) -> WalletResult<Self> {
+        Ok(Self(vec![
+            ChildNumber::hardened(44)?,
+            ChildNumber::hardened(KASPA_COIN_TYPE)?,
+            ChildNumber::hardened(account)?,
+        ]))
+    }
+
+    pub fn child(&self, child: ChildNumber) -> Self {
+        let mut path = self.0.clone();
+        path.push(child);
+        Self(path)
+    }
+
+    pub fn as_slice(&self) -> &[ChildNumber] {
+        &self.0
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for child in &self.0 {
+            write!(f, "/{}", child)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split('/');
+        if parts.next() != Some("m") {
+            return Err(WalletError::InvalidParameters(format!(
+                "Derivation path must start with 'm': {}",
+                s
+            )));
+        }
+
+        let mut children = Vec::new();
+        for part in parts {
+            let (digits, hardened) = match part.strip_suffix('\'').or(part.strip_suffix('h')) {
+                Some(digits) => (digits, true),
+                None => (part, false),
+            };
+            let index: u32 = digits.parse().map_err(|_| {
+                WalletError::InvalidParameters(format!("Invalid path component: {}", part))
+            })?;
+            children.push(if hardened {
+                ChildNumber::hardened(index)?
+            } else {
+                ChildNumber::normal(index)?
+            });
+        }
+
+        Ok(Self(children))
+    }
+}
+
+#[derive(Clone)]
+pub struct ExtendedPrivateKey {
+    secret_key: SecretKey,
+    chain_code: [u8; 32],
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: ChildNumber,
+}
+
+impl Drop for ExtendedPrivateKey {
+    fn drop(&mut self) {
+        self.secret_key.non_secure_erase();
+        self.chain_code.zeroize();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedPublicKey {
+    public_key: PublicKey,
+    chain_code: [u8; 32],
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: ChildNumber,
+}
+
+pub(crate) fn hmac_sha512(key: &[u8], data: &[u8]) -> [u8; 64] {
+    let mut mac = HmacSha512::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+fn split_hmac(output: [u8; 64]) -> ([u8; 32], [u8; 32]) {
+    let mut left = [0u8; 32];
+    let mut right = [0u8; 32];
+    left.copy_from_slice(&output[..32]);
+    right.copy_from_slice(&output[32..]);
+    (left, right)
+}
+
+fn fingerprint_of(public_key: &PublicKey) -> [u8; 4] {
+    let hash = hash160(&public_key.serialize());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+fn encode_base58check(mut payload: Vec<u8>) -> String {
+    let checksum = compute_checksum(&payload);
+    payload.extend_from_slice(&checksum);
+    bs58::encode(payload).into_string()
+}
+
+fn decode_base58check(encoded: &str) -> WalletResult<Vec<u8>> {
+    let bytes = bs58::decode(encoded.trim())
+        .into_vec()
+        .map_err(|e| WalletError::InvalidParameters(format!("Base58 decode error: {}", e)))?;
+    if bytes.len() < 4 {
+        return Err(WalletError::InvalidParameters(
+            "Extended key too short".to_string(),
+        ));
+    }
+    let (payload, checksum) = bytes.split_at(bytes.len() - 4);
+    if compute_checksum(payload) != checksum {
+        return Err(WalletError::InvalidParameters(
+            "Extended key checksum mismatch".to_string(),
+        ));
+    }
+    Ok(payload.to_vec())
+}
+
+impl ExtendedPrivateKey {
+    /// Master key from a BIP32 seed.
+    pub fn from_seed(seed: &[u8]) -> WalletResult<Self> {
+        let (key, chain_code) = split_hmac(hmac_sha512(b"Bitcoin seed", seed));
+        Ok(Self {
+            secret_key: SecretKey::from_slice(&key)?,
+            chain_code,
+            depth: 0,
+            parent_fingerprint: [0; 4],
+            child_number: ChildNumber(0),
+        })
+    }
+
+    pub fn derive_child(&self, child: ChildNumber) -> WalletResult<Self> {
+        let secp = Secp256k1::new();
+        let public_key = PublicKey::from_secret_key(&secp, &self.secret_key);
+
+        let mut data = Vec::with_capacity(37);
+        if child.is_hardened() {
+            data.push(0);
+            data.extend_from_slice(&self.secret_key.secret_bytes());
+        } else {
+            data.extend_from_slice(&public_key.serialize());
+        }
+        data.extend_from_slice(&child.to_u32().to_be_bytes());
+
+        let (tweak, chain_code) = split_hmac(hmac_sha512(&self.chain_code, &data));
+        let tweak = Scalar::from_be_bytes(tweak)
+            .map_err(|_| WalletError::KeyGeneration("Derived tweak out of range".to_string()))?;
+
+        Ok(Self {
+            secret_key: self.secret_key.add_tweak(&tweak)?,
+            chain_code,
+            depth: self.depth.checked_add(1).ok_or_else(|| {
+                WalletError::KeyGeneration("Maximum derivation depth exceeded".to_string())
+            })?,
+            parent_fingerprint: fingerprint_of(&public_key),
+            child_number: child,
+        })
+    }
+
+    pub fn derive_path(&self, path: &DerivationPath) -> WalletResult<Self> {
+        path.as_slice()
+            .iter()
+            .try_fold(self.clone(), |key, child| key.derive_child(*child))
+    }
+
+    pub fn secret_key(&self) -> &SecretKey {
+        &self.secret_key
+    }
+
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    pub fn public_key(&self) -> ExtendedPublicKey {
+        let secp = Secp256k1::new();
+        ExtendedPublicKey {
+            public_key: PublicKey::from_secret_key(&secp, &self.secret_key),
+            chain_code: self.chain_code,
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_number: self.child_number,
+        }
+    }
+
+    pub fn fingerprint(&self) -> [u8; 4] {
+        self.public_key().fingerprint()
+    }
+}
+
+impl fmt::Display for ExtendedPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut payload = Vec::with_capacity(78);
+        payload.extend_from_slice(&KPRV_VERSION);
+        payload.push(self.depth);
+        payload.extend_from_slice(&self.parent_fingerprint);
+        payload.extend_from_slice(&self.child_number.to_u32().to_be_bytes());
+        payload.extend_from_slice(&self.chain_code);
+        payload.push(0);
+        payload.extend_from_slice(&self.secret_key.secret_bytes());
+        write!(f, "{}", encode_base58check(payload))
+    }
+}
+
+impl fmt::Debug for ExtendedPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtendedPrivateKey")
+            .field("depth", &self.depth)
+            .field("child_number", &self.child_number)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FromStr for ExtendedPrivateKey {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let payload = decode_base58check(s)?;
+        if payload.len() != 78 || (payload[..4] != KPRV_VERSION && payload[..4] != XPRV_VERSION) {
+            return Err(WalletError::InvalidParameters(
+                "Not an extended private key".to_string(),
+            ));
+        }
+        if payload[45] != 0 {
+            return Err(WalletError::InvalidParameters(
+                "Malformed extended private key".to_string(),
+            ));
+        }
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(&payload[13..45]);
+        Ok(Self {
+            secret_key: SecretKey::from_slice(&payload[46..78])?,
+            chain_code,
+            depth: payload[4],
+            parent_fingerprint: [payload[5], payload[6], payload[7], payload[8]],
+            child_number: ChildNumber(u32::from_be_bytes([
+                payload[9],
+                payload[10],
+                payload[11],
+                payload[12],
+            ])),
+        })
+    }
+}
+
+impl ExtendedPublicKey {
+    /// Non-hardened child derivation; hardened children require the private key.
+    pub fn derive_child(&self, child: ChildNumber) -> WalletResult<Self> {
+        if child.is_hardened() {
+            return Err(WalletError::KeyGeneration(
+                "Cannot derive a hardened child from a public key".to_string(),
+            ));
+        }
+
+        let mut data = Vec::with_capacity(37);
+        data.extend_from_slice(&self.public_key.serialize());
+        data.extend_from_slice(&child.to_u32().to_be_bytes());
+
+        let (tweak, chain_code) = split_hmac(hmac_sha512(&self.chain_code, &data));
+        let tweak = Scalar::from_be_bytes(tweak)
+            .map_err(|_| WalletError::KeyGeneration("Derived tweak out of range".to_string()))?;
+
+        let secp = Secp256k1::new();
+        Ok(Self {
+            public_key: self.public_key.add_exp_tweak(&secp, &tweak)?,
+            chain_code,
+            depth: self.depth.checked_add(1).ok_or_else(|| {
+                WalletError::KeyGeneration("Maximum derivation depth exceeded".to_string())
+            })?,
+            parent_fingerprint: self.fingerprint(),
+            child_number: child,
+        })
+    }
+
+    /// Key for `chain/index` below this (account-level) extended key.
+    pub fn derive_address_key(&self, chain: u32, index: u32) -> WalletResult<PublicKey> {
+        Ok(self
+            .derive_child(ChildNumber::normal(chain)?)?
+            .derive_child(ChildNumber::normal(index)?)?
+            .public_key)
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    pub fn fingerprint(&self) -> [u8; 4] {
+        fingerprint_of(&self.public_key)
+    }
+}
+
+impl fmt::Display for ExtendedPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut payload = Vec::with_capacity(78);
+        payload.extend_from_slice(&KPUB_VERSION);
+        payload.push(self.depth);
+        payload.extend_from_slice(&self.parent_fingerprint);
+        payload.extend_from_slice(&self.child_number.to_u32().to_be_bytes());
+        payload.extend_from_slice(&self.chain_code);
+        payload.extend_from_slice(&self.public_key.serialize());
+        write!(f, "{}", encode_base58check(payload))
+    }
+}
+
+impl FromStr for ExtendedPublicKey {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let payload = decode_base58check(s)?;
+        if payload.len() != 78 || (payload[..4] != KPUB_VERSION && payload[..4] != XPUB_VERSION) {
+            return Err(WalletError::InvalidParameters(
+                "Not an extended public key".to_string(),
+            ));
+        }
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(&payload[13..45]);
+        Ok(Self {
+            public_key: PublicKey::from_slice(&payload[45..78])?,
+            chain_code,
+            depth: payload[4],
+            parent_fingerprint: [payload[5], payload[6], payload[7], payload[8]],
+            child_number: ChildNumber(u32::from_be_bytes([
+                payload[9],
+                payload[10],
+                payload[11],
+                payload[12],
+            ])),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vector1_master() -> ExtendedPrivateKey {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        ExtendedPrivateKey::from_seed(&seed).unwrap()
+    }
+
+    #[test]
+    fn test_bip32_vector1() {
+        let master = vector1_master();
+        assert_eq!(
+            hex::encode(master.secret_key().secret_bytes()),
+            "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"
+        );
+
+        let child = master.derive_path(&"m/0'/1".parse().unwrap()).unwrap();
+        assert_eq!(
+            hex::encode(child.secret_key().secret_bytes()),
+            "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368"
+        );
+        assert_eq!(
+            hex::encode(child.chain_code()),
+            "2a7857631386ba23dacac34180dd1983734e444fdbf774041578e9b6adb37c19"
+        );
+    }
+
+    #[test]
+    fn test_public_derivation_matches_private() {
+        let account = vector1_master()
+            .derive_path(&DerivationPath::kaspa_account(0).unwrap())
+            .unwrap();
+        let from_private = account
+            .derive_path(&"m/0/5".parse().unwrap())
+            .unwrap()
+            .public_key();
+        let from_public = account.public_key().derive_address_key(0, 5).unwrap();
+        assert_eq!(from_private.public_key(), &from_public);
+    }
+
+    #[test]
+    fn test_extended_key_roundtrip() {
+        let account = vector1_master()
+            .derive_path(&DerivationPath::kaspa_account(0).unwrap())
+            .unwrap();
+        let xpub = account.public_key();
+        let encoded = xpub.to_string();
+        assert!(encoded.starts_with("kpub"));
+        assert_eq!(encoded.parse::<ExtendedPublicKey>().unwrap(), xpub);
+
+        let xprv: ExtendedPrivateKey = account.to_string().parse().unwrap();
+        assert_eq!(xprv.public_key(), xpub);
+    }
+
+    #[test]
+    fn test_derivation_path_parsing() {
+        let path: DerivationPath = "m/44'/111111'/0'/0/3".parse().unwrap();
+        assert_eq!(path.to_string(), "m/44'/111111'/0'/0/3");
+        assert!("44'/0".parse::<DerivationPath>().is_err());
+        assert!("m/abc".parse::<DerivationPath>().is_err());
+    }
+}
@@ -0,0 +1,448 @@
+//! Address history from a Kaspa REST explorer speaking the api.kaspa.org
+//! API over plain HTTP.
+
+use crate::error::{WalletError, WalletResult};
+use crate::http::{HttpUrl, Service};
+use crate::node::RetryPolicy;
+use crate::storage::{self, Overwrite};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const HISTORY_CACHE_FILE: &str = "history_cache.json";
+/// How long a fetched page is served from the cache.
+pub const CACHE_TTL_SECS: u64 = 60;
+
+/// Explorer request timeout when neither flag nor config sets one.
+pub const HTTP_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    In,
+    Out,
+}
+
+/// One transaction touching an address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub txid: String,
+    /// Block time in milliseconds since the Unix epoch.
+    pub time: u64,
+    pub direction: Direction,
+    /// Received minus spent, in sompi.
+    pub net_sompi: i64,
+    pub accepted: bool,
+    /// The first other address: a sender for incoming transactions, a
+    /// recipient for outgoing ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counterparty: Option<String>,
+    /// Inputs minus outputs, when the explorer gave every input amount.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mass: Option<u64>,
+    #[serde(default)]
+    pub outputs: Vec<HistoryOutput>,
+}
+
+/// One output of a transaction in the history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryOutput {
+    pub address: Option<String>,
+    pub amount: u64,
+}
+
+#[derive(Deserialize)]
+struct ApiTransaction {
+    transaction_id: String,
+    #[serde(default)]
+    block_time: Option<u64>,
+    #[serde(default)]
+    is_accepted: Option<bool>,
+    #[serde(default)]
+    inputs: Option<Vec<ApiInput>>,
+    #[serde(default)]
+    outputs: Option<Vec<ApiOutput>>,
+    /// A number or, from some explorers, a decimal string.
+    #[serde(default)]
+    mass: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct ApiInput {
+    #[serde(default)]
+    previous_outpoint_address: Option<String>,
+    #[serde(default)]
+    previous_outpoint_amount: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct ApiOutput {
+    amount: u64,
+    #[serde(default)]
+    script_public_key_address: Option<String>,
+}
+
+/// Parses an explorer transaction list into entries for `address`,
+/// newest first.
+pub fn parse_transactions(json: &str, address: &str) -> WalletResult<Vec<HistoryEntry>> {
+    let transactions: Vec<ApiTransaction> = serde_json::from_str(json)
+        .map_err(|e| WalletError::Serialization(format!("Invalid explorer response: {}", e)))?;
+    let mut entries: Vec<HistoryEntry> = transactions
+        .into_iter()
+        .map(|tx| {
+            let inputs = tx.inputs.unwrap_or_default();
+            let outputs = tx.outputs.unwrap_or_default();
+            let received: u64 = outputs
+                .iter()
+                .filter(|output| output.script_public_key_address.as_deref() == Some(address))
+                .map(|output| output.amount)
+                .sum();
+            let spent: u64 = inputs
+                .iter()
+                .filter(|input| input.previous_outpoint_address.as_deref() == Some(address))
+                .filter_map(|input| input.previous_outpoint_amount)
+                .sum();
+            let net_sompi = received as i64 - spent as i64;
+            let direction = if net_sompi >= 0 {
+                Direction::In
+            } else {
+                Direction::Out
+            };
+            let counterparty = match direction {
+                Direction::In => inputs
+                    .iter()
+                    .filter_map(|input| input.previous_outpoint_address.as_deref())
+                    .find(|other| *other != address),
+                Direction::Out => outputs
+                    .iter()
+                    .filter_map(|output| output.script_public_key_address.as_deref())
+                    .find(|other| *other != address),
+            };
+            let input_total: Option<u64> = if inputs.is_empty() {
+                None
+            } else {
+                inputs
+                    .iter()
+                    .map(|input| input.previous_outpoint_amount)
+                    .sum()
+            };
+            let output_total: u64 = outputs.iter().map(|output| output.amount).sum();
+            HistoryEntry {
+                txid: tx.transaction_id,
+                time: tx.block_time.unwrap_or(0),
+                direction,
+                net_sompi,
+                accepted: tx.is_accepted.unwrap_or(false),
+                counterparty: counterparty.map(str::to_string),
+                fee: input_total.and_then(|total| total.checked_sub(output_total)),
+                mass: tx.mass.and_then(|mass| match mass {
+                    serde_json::Value::Number(n) => n.as_u64(),
+                    serde_json::Value::String(s) => s.parse().ok(),
+                    _ => None,
+                }),
+                outputs: outputs
+                    .into_iter()
+                    .map(|output| HistoryOutput {
+                        address: output.script_public_key_address,
+                        amount: output.amount,
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.time));
+    Ok(entries)
+}
+
+/// Request path under `base_path` for a page of `address`'s transactions
+/// older than `before` (block time in milliseconds).
+pub(crate) fn transactions_path(
+    base_path: &str,
+    address: &str,
+    limit: usize,
+    before: Option<u64>,
+) -> String {
+    let mut path = format!(
+        "{}/addresses/{}/full-transactions?limit={}&resolve_previous_outpoints=light",
+        base_path,
+        address.replace(':', "%3A"),
+        limit
+    );
+    if let Some(before) = before {
+        path.push_str(&format!("&before={}", before));
+    }
+    path
+}
+
+/// A REST explorer reached over plain HTTP.
+pub struct Explorer {
+    url: HttpUrl,
+    policy: RetryPolicy,
+}
+
+const EXPLORER: Service = Service {
+    name: "explorer",
+    setting: "explorer_url",
+    operation: "history request",
+};
+
+impl Explorer {
+    pub fn new(url: &str, policy: RetryPolicy) -> WalletResult<Self> {
+        Ok(Self {
+            url: HttpUrl::parse(url, EXPLORER)?,
+            policy,
+        })
+    }
+
+    /// Fetches a page of transactions, serving recent pages from `cache`
+    /// unless `refresh` is set.
+    pub fn transactions(
+        &self,
+        cache: &mut HistoryCache,
+        address: &str,
+        limit: usize,
+        before: Option<u64>,
+        refresh: bool,
+    ) -> WalletResult<Vec<HistoryEntry>> {
+        let path = transactions_path(&self.url.base_path, address, limit, before);
+        let key = format!("{}:{}{}", self.url.host, self.url.port, path);
+        let now = unix_time();
+        if !refresh {
+            if let Some(entries) = cache.get(&key, now) {
+                tracing::debug!(address, "history served from cache");
+                return Ok(entries.to_vec());
+            }
+        }
+        let body = self.url.fetch(&path, &self.policy)?;
+        let entries = parse_transactions(&body, address)?;
+        cache.insert(key, now, entries.clone());
+        Ok(entries)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedPage {
+    fetched_at: u64,
+    entries: Vec<HistoryEntry>,
+}
+
+/// Explorer responses persisted in `history_cache.json` under the data dir.
+pub struct HistoryCache {
+    path: PathBuf,
+    pages: HashMap<String, CachedPage>,
+}
+
+impl HistoryCache {
+    /// Loads the cache; a missing or unreadable file starts it empty.
+    pub fn load(data_dir: &Path) -> Self {
+        let path = data_dir.join(HISTORY_CACHE_FILE);
+        let pages = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self { path, pages }
+    }
+
+    pub fn save(&self) -> WalletResult<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string(&self.pages).map_err(|e| {
+            WalletError::Serialization(format!("History cache encode error: {}", e))
+        })?;
+        storage::write_atomic(&self.path, json.as_bytes(), Overwrite::Allow)
+    }
+
+    fn get(&self, key: &str, now: u64) -> Option<&[HistoryEntry]> {
+        self.pages
+            .get(key)
+            .filter(|page| now.saturating_sub(page.fetched_at) < CACHE_TTL_SECS)
+            .map(|page| page.entries.as_slice())
+    }
+
+    fn insert(&mut self, key: String, now: u64, entries: Vec<HistoryEntry>) {
+        self.pages
+            .retain(|_, page| now.saturating_sub(page.fetched_at) < CACHE_TTL_SECS);
+        self.pages.insert(
+            key,
+            CachedPage {
+                fetched_at: now,
+                entries,
+            },
+        );
+    }
+}
+
+fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Formats a millisecond Unix timestamp as `YYYY-MM-DD HH:MM:SS` UTC.
+pub fn format_time(millis: u64) -> String {
+    let secs = millis / 1000;
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    // Civil-from-days, after Howard Hinnant.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    const ADDRESS: &str = "kaspa:me";
+
+    fn sample() -> String {
+        serde_json::json!([
+            {
+                "transaction_id": "aa",
+                "block_time": 1_700_000_000_000u64,
+                "is_accepted": true,
+                "inputs": [
+                    {"previous_outpoint_address": "kaspa:sender", "previous_outpoint_amount": null}
+                ],
+                "outputs": [
+                    {"amount": 500, "script_public_key_address": ADDRESS},
+                    {"amount": 900, "script_public_key_address": "kaspa:other"}
+                ]
+            },
+            {
+                "transaction_id": "bb",
+                "block_time": 1_700_000_100_000u64,
+                "is_accepted": false,
+                "mass": "2036",
+                "inputs": [
+                    {"previous_outpoint_address": ADDRESS, "previous_outpoint_amount": 500}
+                ],
+                "outputs": [
+                    {"amount": 300, "script_public_key_address": "kaspa:other"},
+                    {"amount": 150, "script_public_key_address": ADDRESS}
+                ]
+            }
+        ])
+        .to_string()
+    }
+
+    #[test]
+    fn test_parse_transactions() {
+        let entries = parse_transactions(&sample(), ADDRESS).unwrap();
+        assert_eq!(entries[0].txid, "bb");
+        assert_eq!(entries[0].direction, Direction::Out);
+        assert_eq!(entries[0].net_sompi, -350);
+        assert!(!entries[0].accepted);
+        assert_eq!(entries[0].counterparty.as_deref(), Some("kaspa:other"));
+        assert_eq!(entries[0].fee, Some(50));
+        assert_eq!(entries[0].mass, Some(2036));
+        assert_eq!(entries[0].outputs.len(), 2);
+        assert_eq!(entries[1].direction, Direction::In);
+        assert_eq!(entries[1].net_sompi, 500);
+        assert_eq!(entries[1].counterparty.as_deref(), Some("kaspa:sender"));
+        assert_eq!(entries[1].fee, None);
+        assert!(parse_transactions("{}", ADDRESS).is_err());
+    }
+
+    #[test]
+    fn test_fetch_uses_cache() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let body = sample();
+        let server = std::thread::spawn(move || {
+            // Answer exactly one request; a second fetch would hang the test.
+            let (mut stream, _) = listener.accept().unwrap();
+            // Read the whole request; closing with unread bytes resets the
+            // connection before the client sees the response.
+            let mut request = Vec::new();
+            let mut chunk = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut chunk).unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&chunk[..n]);
+            }
+            let request = String::from_utf8_lossy(&request).to_string();
+            write!(stream, "HTTP/1.0 200 OK\r\n\r\n{}", body).unwrap();
+            request
+        });
+
+        let dir =
+            std::env::temp_dir().join(format!("kasparustwallet-history-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let explorer = Explorer::new(
+            &format!("http://127.0.0.1:{}/", port),
+            RetryPolicy::default(),
+        )
+        .unwrap();
+        let mut cache = HistoryCache::load(&dir);
+        let first = explorer
+            .transactions(&mut cache, ADDRESS, 20, Some(5), false)
+            .unwrap();
+        cache.save().unwrap();
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with(
+            "GET /addresses/kaspa%3Ame/full-transactions?limit=20&resolve_previous_outpoints=light&before=5 "
+        ));
+        let mut cache = HistoryCache::load(&dir);
+        let second = explorer
+            .transactions(&mut cache, ADDRESS, 20, Some(5), false)
+            .unwrap();
+        assert_eq!(first, second);
+        assert!(Explorer::new("https://api.kaspa.org", RetryPolicy::default()).is_err());
+    }
+
+    #[test]
+    fn test_silent_explorer_times_out() {
+        // Accepts the connection but never answers.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = listener.local_addr().unwrap().to_string();
+        let policy = RetryPolicy {
+            timeout: Duration::from_millis(200),
+            retries: 0,
+        };
+        let explorer = Explorer::new(&format!("http://{}", endpoint), policy).unwrap();
+        let dir = std::env::temp_dir().join(format!(
+            "kasparustwallet-history-timeout-{}",
+            std::process::id()
+        ));
+        let error = explorer
+            .transactions(&mut HistoryCache::load(&dir), ADDRESS, 20, None, true)
+            .unwrap_err();
+        assert!(matches!(error, WalletError::Timeout { .. }), "{}", error);
+        assert!(error.to_string().contains(&endpoint));
+        drop(listener);
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(0), "1970-01-01 00:00:00");
+        assert_eq!(format_time(1_700_000_000_000), "2023-11-14 22:13:20");
+        assert_eq!(format_time(951_782_400_000), "2000-02-29 00:00:00");
+    }
+}
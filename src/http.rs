@@ -0,0 +1,179 @@
+//! Plain HTTP GET for the REST services the wallet reads: the explorer and
+//! the price API. TLS is not supported; point them at a local proxy.
+
+use crate::error::{WalletError, WalletResult};
+use crate::node::RetryPolicy;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::Duration;
+
+/// Longest a rate-limited request waits before trying again, whatever the
+/// service's `Retry-After` asks.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Names a service in its errors.
+#[derive(Debug, Clone, Copy)]
+pub struct Service {
+    /// What it is called, such as "explorer".
+    pub name: &'static str,
+    /// Setting that holds its URL, such as "explorer_url".
+    pub setting: &'static str,
+    /// What a failed request was doing, such as "history request".
+    pub operation: &'static str,
+}
+
+/// A parsed `http://host[:port][/path]` base URL.
+#[derive(Debug, Clone)]
+pub struct HttpUrl {
+    pub host: String,
+    pub port: u16,
+    /// Without a trailing slash; empty for the root.
+    pub base_path: String,
+    service: Service,
+}
+
+impl HttpUrl {
+    pub fn parse(url: &str, service: Service) -> WalletResult<Self> {
+        if url.starts_with("https://") {
+            return Err(WalletError::InvalidParameters(format!(
+                "HTTPS {}s are not supported yet, as there is no TLS client; point {} at a plain HTTP endpoint or a local proxy ({})",
+                service.name, service.setting, url
+            )));
+        }
+        let rest = url.strip_prefix("http://").ok_or_else(|| {
+            WalletError::InvalidParameters(format!(
+                "{} must start with http://: {}",
+                service.setting, url
+            ))
+        })?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], rest[i..].trim_end_matches('/')),
+            None => (rest, ""),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse().map_err(|_| {
+                    WalletError::InvalidParameters(format!(
+                        "Invalid port in {}: {}",
+                        service.setting, url
+                    ))
+                })?,
+            ),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(WalletError::InvalidParameters(format!(
+                "Invalid {}: {}",
+                service.setting, url
+            )));
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            base_path: path.to_string(),
+            service,
+        })
+    }
+
+    /// The body of a 200 response to `GET path`; `path` includes the base.
+    pub fn get(&self, path: &str, policy: &RetryPolicy) -> WalletResult<String> {
+        self.send(path, policy)?.body(self.service)
+    }
+
+    /// `get` with `policy`'s retries. A 429 (rate limited) is tried again
+    /// after the service's `Retry-After`, up to a minute, or else the
+    /// usual backoff.
+    pub fn fetch(&self, path: &str, policy: &RetryPolicy) -> WalletResult<String> {
+        let mut limited = 0;
+        loop {
+            let response = policy.run(|_| self.send(path, policy))?;
+            if response.status != 429 || limited >= policy.retries {
+                return response.body(self.service);
+            }
+            limited += 1;
+            let wait = response
+                .retry_after()
+                .unwrap_or_else(|| policy.backoff(limited))
+                .min(MAX_RETRY_AFTER);
+            tracing::debug!(?wait, "{} rate limited; waiting", self.service.name);
+            thread::sleep(wait);
+        }
+    }
+
+    fn send(&self, path: &str, policy: &RetryPolicy) -> WalletResult<Response> {
+        let service = self.service;
+        tracing::debug!(host = %self.host, path, "{} request", service.name);
+        let endpoint = format!("{}:{}", self.host, self.port);
+        let network_error = |e: std::io::Error| policy.io_error(service.operation, &endpoint, e);
+        let addr = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .map_err(network_error)?
+            .next()
+            .ok_or_else(|| WalletError::Network(format!("Cannot resolve {}", self.host)))?;
+        let mut stream =
+            TcpStream::connect_timeout(&addr, policy.timeout).map_err(network_error)?;
+        stream
+            .set_read_timeout(Some(policy.timeout))
+            .map_err(network_error)?;
+        // HTTP/1.0 keeps the body unchunked and the connection closing.
+        write!(
+            stream,
+            "GET {} HTTP/1.0\r\nHost: {}\r\nAccept: application/json\r\nUser-Agent: kasparustwallet\r\n\r\n",
+            path, self.host
+        )
+        .map_err(network_error)?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).map_err(network_error)?;
+        let response = String::from_utf8_lossy(&response);
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .ok_or_else(|| WalletError::Network(format!("Malformed {} response", service.name)))?;
+        let status_line = head.lines().next().unwrap_or_default().trim().to_string();
+        Ok(Response {
+            status: status_line
+                .split_whitespace()
+                .nth(1)
+                .and_then(|status| status.parse().ok())
+                .unwrap_or(0),
+            status_line,
+            head: head.to_string(),
+            body: body.to_string(),
+        })
+    }
+}
+
+struct Response {
+    status: u16,
+    status_line: String,
+    head: String,
+    body: String,
+}
+
+impl Response {
+    fn body(self, service: Service) -> WalletResult<String> {
+        match self.status {
+            200 => Ok(self.body),
+            429 => Err(WalletError::Network(format!(
+                "The {} is rate limiting requests ({}); try again later",
+                service.name, self.status_line
+            ))),
+            _ => Err(WalletError::Network(format!(
+                "The {} returned {}",
+                service.name, self.status_line
+            ))),
+        }
+    }
+
+    /// The wait a `Retry-After` header asks for, when given in seconds.
+    fn retry_after(&self) -> Option<Duration> {
+        self.head.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            if !name.trim().eq_ignore_ascii_case("retry-after") {
+                return None;
+            }
+            value.trim().parse().ok().map(Duration::from_secs)
+        })
+    }
+}
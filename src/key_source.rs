@@ -0,0 +1,121 @@
+use crate::error::{WalletError, WalletResult};
+use secp256k1::SecretKey;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Environment variable consulted for a hex private key.
+pub const PRIVATE_KEY_ENV: &str = "KASPA_PRIVATE_KEY";
+
+/// Environment variable consulted for keystore passwords, for scripts and
+/// CI where no terminal is available.
+pub const WALLET_PASSWORD_ENV: &str = "KASPA_WALLET_PASSWORD";
+
+/// Where a private key was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeySource {
+    /// `--private-key`; visible in shell history and `ps`.
+    Argument,
+    File,
+    Environment,
+    Prompt,
+}
+
+impl fmt::Display for KeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeySource::Argument => write!(f, "--private-key"),
+            KeySource::File => write!(f, "--private-key-file"),
+            KeySource::Environment => write!(f, "{}", PRIVATE_KEY_ENV),
+            KeySource::Prompt => write!(f, "prompt"),
+        }
+    }
+}
+
+/// Parses a hex private key, ignoring surrounding whitespace.
+pub fn parse_private_key(hex_key: &str) -> WalletResult<SecretKey> {
+    let bytes = Zeroizing::new(hex::decode(hex_key.trim())?);
+    Ok(SecretKey::from_slice(&bytes)?)
+}
+
+/// The private key from the first source given, in order: the command-line
+/// argument, the key file, then the environment value. `None` when none of
+/// them is set, leaving the caller to fall back to a wallet or a prompt.
+pub fn resolve_private_key(
+    argument: Option<&str>,
+    file: Option<&Path>,
+    env: Option<&str>,
+) -> WalletResult<Option<(KeySource, SecretKey)>> {
+    if let Some(argument) = argument {
+        return Ok(Some((KeySource::Argument, parse_private_key(argument)?)));
+    }
+    if let Some(file) = file {
+        let contents = Zeroizing::new(fs::read_to_string(file).map_err(|e| {
+            WalletError::Storage(format!("Cannot read key file {}: {}", file.display(), e))
+        })?);
+        return Ok(Some((KeySource::File, parse_private_key(&contents)?)));
+    }
+    if let Some(env) = env.filter(|value| !value.trim().is_empty()) {
+        return Ok(Some((KeySource::Environment, parse_private_key(env)?)));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const KEY_A: &str = "0000000000000000000000000000000000000000000000000000000000000001";
+    const KEY_B: &str = "0000000000000000000000000000000000000000000000000000000000000002";
+    const KEY_C: &str = "0000000000000000000000000000000000000000000000000000000000000003";
+
+    fn key_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "kasparustwallet-{}-{}.key",
+            name,
+            std::process::id()
+        ));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn resolved(
+        argument: Option<&str>,
+        file: Option<&Path>,
+        env: Option<&str>,
+    ) -> Option<(KeySource, String)> {
+        resolve_private_key(argument, file, env)
+            .unwrap()
+            .map(|(source, key)| (source, hex::encode(key.secret_bytes())))
+    }
+
+    #[test]
+    fn test_precedence_order() {
+        let file = key_file("key-precedence", &format!("{}\n", KEY_B));
+
+        assert_eq!(
+            resolved(Some(KEY_A), Some(&file), Some(KEY_C)),
+            Some((KeySource::Argument, KEY_A.to_string()))
+        );
+        assert_eq!(
+            resolved(None, Some(&file), Some(KEY_C)),
+            Some((KeySource::File, KEY_B.to_string()))
+        );
+        assert_eq!(
+            resolved(None, None, Some(KEY_C)),
+            Some((KeySource::Environment, KEY_C.to_string()))
+        );
+        assert_eq!(resolved(None, None, Some("  ")), None);
+        assert_eq!(resolved(None, None, None), None);
+    }
+
+    #[test]
+    fn test_invalid_sources_are_errors() {
+        let file = key_file("key-invalid", "not hex");
+        assert!(resolve_private_key(None, Some(&file), None).is_err());
+        assert!(resolve_private_key(None, Some(Path::new("/nonexistent/key")), None).is_err());
+        assert!(resolve_private_key(None, None, Some("abcd")).is_err());
+    }
+}
@@ -0,0 +1,332 @@
+use crate::crypto::{self, KdfParams, SealedBox, NONCE_LEN, SALT_LEN};
+use crate::error::{WalletError, WalletResult};
+use crate::hd::DerivationPath;
+use crate::storage::{self, Overwrite};
+use crate::wallet::KaspaWallet;
+use bip39::Mnemonic;
+use secp256k1::SecretKey;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+pub const KEYSTORE_FORMAT: &str = "kasparustwallet-keystore";
+pub const KEYSTORE_VERSION: u32 = 1;
+pub const KEYSTORE_EXTENSION: &str = "keystore";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WalletKind {
+    Single,
+    Hd,
+}
+
+impl fmt::Display for WalletKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalletKind::Single => write!(f, "single key"),
+            WalletKind::Hd => write!(f, "HD"),
+        }
+    }
+}
+
+/// The encrypted payload. Only ever exists in memory while decrypting.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum KeystoreSecret {
+    PrivateKey { key: String },
+    ExtendedKey { xprv: String, path: String },
+    Mnemonic { phrase: String, passphrase: String },
+}
+
+impl KeystoreSecret {
+    fn of(wallet: &KaspaWallet) -> (Self, WalletKind) {
+        match (wallet.mnemonic(), wallet.account_xprv()) {
+            (Some((mnemonic, passphrase)), _) => (
+                KeystoreSecret::Mnemonic {
+                    phrase: mnemonic.to_string(),
+                    passphrase: passphrase.to_string(),
+                },
+                WalletKind::Hd,
+            ),
+            (None, Some(xprv)) => (
+                KeystoreSecret::ExtendedKey {
+                    xprv: xprv.to_string(),
+                    path: wallet
+                        .derivation_path()
+                        .map(|path| path.to_string())
+                        .unwrap_or_default(),
+                },
+                WalletKind::Hd,
+            ),
+            (None, None) => (
+                KeystoreSecret::PrivateKey {
+                    key: wallet.get_private_key(),
+                },
+                WalletKind::Single,
+            ),
+        }
+    }
+
+    fn into_wallet(self, network: &str) -> WalletResult<KaspaWallet> {
+        match self {
+            KeystoreSecret::PrivateKey { key } => {
+                let key = Zeroizing::new(key);
+                let secret_key = SecretKey::from_slice(&hex::decode(key.as_str())?)?;
+                KaspaWallet::with_network(secret_key, network)
+            }
+            KeystoreSecret::ExtendedKey { xprv, path } => {
+                let xprv = Zeroizing::new(xprv);
+                let path: DerivationPath = path.parse()?;
+                KaspaWallet::from_account_key(xprv.parse()?, path, network)
+            }
+            KeystoreSecret::Mnemonic { phrase, passphrase } => {
+                let phrase = Zeroizing::new(phrase);
+                let passphrase = Zeroizing::new(passphrase);
+                let mnemonic = Mnemonic::parse_normalized(&phrase)
+                    .map_err(|e| WalletError::Storage(format!("Keystore phrase invalid: {}", e)))?;
+                KaspaWallet::from_mnemonic(&mnemonic, &passphrase, network)
+            }
+        }
+    }
+}
+
+/// `wallet`'s secret as a keystore encrypts it: its key, or the phrase or
+/// account key an HD wallet derives from. Lets a wallet be kept unlocked
+/// without holding on to the `KaspaWallet`.
+pub fn export_secret(wallet: &KaspaWallet) -> WalletResult<Zeroizing<String>> {
+    serde_json::to_string(&KeystoreSecret::of(wallet).0)
+        .map(Zeroizing::new)
+        .map_err(|e| WalletError::Serialization(format!("Keystore encode error: {}", e)))
+}
+
+/// The wallet on `network` whose `export_secret` is `secret`.
+pub fn import_secret(secret: &str, network: &str) -> WalletResult<KaspaWallet> {
+    let secret: KeystoreSecret = serde_json::from_str(secret)
+        .map_err(|e| WalletError::Storage(format!("Wallet secret corrupted: {}", e)))?;
+    secret.into_wallet(network)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreCrypto {
+    pub kdf: String,
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    pub cipher: String,
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+/// Password-encrypted wallet file. Public metadata stays readable so wallets
+/// can be listed without unlocking, and is bound to the ciphertext as
+/// associated data so it can't be edited undetected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    pub format: String,
+    pub version: u32,
+    pub name: String,
+    pub network: String,
+    pub fingerprint: String,
+    pub address: String,
+    pub kind: WalletKind,
+    pub crypto: KeystoreCrypto,
+}
+
+fn decode_fixed<const N: usize>(hex_str: &str, what: &str) -> WalletResult<[u8; N]> {
+    hex::decode(hex_str)?
+        .try_into()
+        .map_err(|_| WalletError::Storage(format!("Keystore {} has the wrong length", what)))
+}
+
+impl Keystore {
+    pub fn encrypt(wallet: &KaspaWallet, name: &str, password: &str) -> WalletResult<Self> {
+        crypto::check_password_strength(password)?;
+
+        let (secret, kind) = KeystoreSecret::of(wallet);
+
+        let mut keystore = Self {
+            format: KEYSTORE_FORMAT.to_string(),
+            version: KEYSTORE_VERSION,
+            name: name.to_string(),
+            network: wallet.get_network_name().to_string(),
+            fingerprint: wallet.fingerprint(),
+            address: wallet.get_address(),
+            kind,
+            crypto: KeystoreCrypto {
+                kdf: "argon2id".to_string(),
+                m_cost: 0,
+                t_cost: 0,
+                p_cost: 0,
+                cipher: "aes-256-gcm".to_string(),
+                salt: String::new(),
+                nonce: String::new(),
+                ciphertext: String::new(),
+            },
+        };
+
+        let plaintext =
+            Zeroizing::new(serde_json::to_vec(&secret).map_err(|e| {
+                WalletError::Serialization(format!("Keystore encode error: {}", e))
+            })?);
+        let sealed = crypto::seal(&plaintext, password, keystore.associated_data().as_bytes())?;
+        keystore.crypto.m_cost = sealed.params.m_cost;
+        keystore.crypto.t_cost = sealed.params.t_cost;
+        keystore.crypto.p_cost = sealed.params.p_cost;
+        keystore.crypto.salt = hex::encode(sealed.salt);
+        keystore.crypto.nonce = hex::encode(sealed.nonce);
+        keystore.crypto.ciphertext = hex::encode(&sealed.ciphertext);
+
+        Ok(keystore)
+    }
+
+    /// Metadata authenticated together with the ciphertext. The display name
+    /// is excluded so wallets can be renamed without re-encrypting.
+    fn associated_data(&self) -> String {
+        format!(
+            "{}:{}:{}:{}:{}",
+            self.format, self.version, self.network, self.fingerprint, self.address
+        )
+    }
+
+    fn sealed_box(&self) -> WalletResult<SealedBox> {
+        Ok(SealedBox {
+            params: KdfParams {
+                m_cost: self.crypto.m_cost,
+                t_cost: self.crypto.t_cost,
+                p_cost: self.crypto.p_cost,
+            },
+            salt: decode_fixed::<SALT_LEN>(&self.crypto.salt, "salt")?,
+            nonce: decode_fixed::<NONCE_LEN>(&self.crypto.nonce, "nonce")?,
+            ciphertext: hex::decode(&self.crypto.ciphertext)?,
+        })
+    }
+
+    pub fn decrypt(&self, password: &str) -> WalletResult<KaspaWallet> {
+        let plaintext = crypto::open(
+            &self.sealed_box()?,
+            password,
+            self.associated_data().as_bytes(),
+        )?;
+        let secret: KeystoreSecret = serde_json::from_slice(&plaintext)
+            .map_err(|e| WalletError::Storage(format!("Keystore payload corrupted: {}", e)))?;
+
+        let wallet = secret.into_wallet(&self.network)?;
+
+        if wallet.fingerprint() != self.fingerprint {
+            return Err(WalletError::Storage(
+                "Keystore fingerprint does not match its key".to_string(),
+            ));
+        }
+        tracing::debug!(
+            name = %self.name,
+            network = %self.network,
+            fingerprint = %self.fingerprint,
+            "keystore decrypted"
+        );
+
+        Ok(wallet)
+    }
+
+    pub fn to_json(&self) -> WalletResult<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| WalletError::Serialization(format!("Keystore encode error: {}", e)))
+    }
+
+    pub fn from_json(json: &str) -> WalletResult<Self> {
+        let keystore: Self = serde_json::from_str(json)
+            .map_err(|e| WalletError::Storage(format!("Not a keystore file: {}", e)))?;
+        if keystore.format != KEYSTORE_FORMAT {
+            return Err(WalletError::Storage(format!(
+                "Unknown keystore format: {}",
+                keystore.format
+            )));
+        }
+        if keystore.version > KEYSTORE_VERSION {
+            return Err(WalletError::Storage(format!(
+                "Keystore version {} is newer than supported version {}",
+                keystore.version, KEYSTORE_VERSION
+            )));
+        }
+        Ok(keystore)
+    }
+
+    pub fn load(path: &Path) -> WalletResult<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &Path, overwrite: Overwrite) -> WalletResult<()> {
+        storage::write_atomic(path, self.to_json()?.as_bytes(), overwrite)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secp256k1::Secp256k1;
+
+    const PASSWORD: &str = "correct horse battery staple";
+
+    #[test]
+    fn test_single_key_roundtrip() {
+        let secp = Secp256k1::new();
+        let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let wallet = KaspaWallet::with_network(secret_key, "testnet-10").unwrap();
+
+        let keystore = Keystore::encrypt(&wallet, "main", PASSWORD).unwrap();
+        assert_eq!(keystore.kind, WalletKind::Single);
+        assert!(!keystore
+            .to_json()
+            .unwrap()
+            .contains(&wallet.get_private_key()));
+
+        let restored = Keystore::from_json(&keystore.to_json().unwrap())
+            .unwrap()
+            .decrypt(PASSWORD)
+            .unwrap();
+        assert_eq!(restored.get_private_key(), wallet.get_private_key());
+        assert_eq!(restored.get_network_name(), "testnet-10");
+        assert!(keystore.decrypt("wrong password entirely").is_err());
+    }
+
+    #[test]
+    fn test_mnemonic_roundtrip() {
+        let mnemonic = Mnemonic::generate(12).unwrap();
+        let wallet = KaspaWallet::from_mnemonic(&mnemonic, "extra", "mainnet").unwrap();
+
+        let keystore = Keystore::encrypt(&wallet, "hd", PASSWORD).unwrap();
+        assert_eq!(keystore.kind, WalletKind::Hd);
+
+        let restored = keystore.decrypt(PASSWORD).unwrap();
+        assert_eq!(restored.mnemonic().unwrap().0, &mnemonic);
+        assert_eq!(
+            restored.receive_address(3).unwrap(),
+            wallet.receive_address(3).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_exported_secret_rebuilds_the_wallet() {
+        let mnemonic = Mnemonic::generate(12).unwrap();
+        let wallet = KaspaWallet::from_mnemonic(&mnemonic, "extra", "mainnet").unwrap();
+
+        let restored = import_secret(&export_secret(&wallet).unwrap(), "testnet-10").unwrap();
+        assert_eq!(restored.mnemonic(), Some((&mnemonic, "extra")));
+        assert_eq!(restored.get_network_name(), "testnet-10");
+        assert_eq!(restored.fingerprint(), wallet.fingerprint());
+        assert!(import_secret(&wallet.get_private_key(), "mainnet").is_err());
+    }
+
+    #[test]
+    fn test_metadata_is_authenticated() {
+        let secp = Secp256k1::new();
+        let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let wallet = KaspaWallet::with_network(secret_key, "mainnet").unwrap();
+
+        let mut keystore = Keystore::encrypt(&wallet, "main", PASSWORD).unwrap();
+        keystore.network = "testnet-10".to_string();
+        assert!(keystore.decrypt(PASSWORD).is_err());
+    }
+}
@@ -0,0 +1,43 @@
+pub mod address;
+pub mod address_book;
+pub mod address_index;
+pub mod amount;
+pub mod backup;
+pub mod batch;
+pub mod bip85;
+pub mod config;
+pub mod crypto;
+pub mod descriptor;
+pub mod discovery;
+pub mod error;
+pub mod event_log;
+#[cfg(feature = "explorer")]
+pub mod explorer;
+#[cfg(feature = "gui")]
+pub mod gui;
+pub mod hd;
+pub mod history;
+pub mod http;
+pub mod key_source;
+pub mod keystore;
+pub mod lockable;
+pub mod logging;
+pub mod mnemonic;
+pub mod monitor;
+pub mod network;
+pub mod node;
+pub mod price;
+pub mod pstx;
+#[cfg(feature = "qr")]
+pub mod qr;
+pub mod self_check;
+pub mod self_test;
+pub mod shared;
+pub mod storage;
+pub mod transaction;
+pub mod uri;
+pub mod utxo;
+pub mod wallet;
+pub mod wallet_collection;
+pub mod wallet_state;
+pub mod watch_only;
@@ -0,0 +1,175 @@
+use crate::error::{WalletError, WalletResult};
+use crate::keystore::Keystore;
+use crate::self_check::SelfCheckReport;
+use crate::wallet::KaspaWallet;
+use crate::wallet_collection::FileLock;
+use std::path::{Path, PathBuf};
+
+/// A wallet whose secret key is only held in memory while unlocked.
+pub struct LockableWallet {
+    keystore: Option<Keystore>,
+    path: Option<PathBuf>,
+    wallet: Option<KaspaWallet>,
+    _file_lock: Option<FileLock>,
+}
+
+impl LockableWallet {
+    /// Locked wallet backed by a keystore file.
+    pub fn from_keystore(keystore: Keystore, path: PathBuf, file_lock: Option<FileLock>) -> Self {
+        Self {
+            keystore: Some(keystore),
+            path: Some(path),
+            wallet: None,
+            _file_lock: file_lock,
+        }
+    }
+
+    /// Unlocked wallet with no keystore behind it; once locked it stays locked.
+    pub fn from_wallet(wallet: KaspaWallet) -> Self {
+        Self {
+            keystore: None,
+            path: None,
+            wallet: Some(wallet),
+            _file_lock: None,
+        }
+    }
+
+    pub fn unlock(&mut self, password: &str) -> WalletResult<()> {
+        let keystore = self.keystore.as_ref().ok_or_else(|| {
+            WalletError::Storage("Wallet has no keystore to unlock from".to_string())
+        })?;
+        self.wallet = Some(keystore.decrypt(password)?);
+        Ok(())
+    }
+
+    /// Installs a wallet decrypted elsewhere from this keystore, e.g. off
+    /// the UI thread; it must belong to the keystore.
+    pub fn set_unlocked(&mut self, wallet: KaspaWallet) -> WalletResult<()> {
+        if let Some(keystore) = &self.keystore {
+            if keystore.fingerprint != wallet.fingerprint() {
+                return Err(WalletError::Storage(
+                    "Decrypted wallet does not match its keystore".to_string(),
+                ));
+            }
+        }
+        self.wallet = Some(wallet);
+        Ok(())
+    }
+
+    /// Drops the unlocked wallet, whose `Drop` wipes its keys.
+    pub fn lock(&mut self) {
+        self.wallet = None;
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.wallet.is_none()
+    }
+
+    pub fn wallet(&self) -> WalletResult<&KaspaWallet> {
+        self.wallet.as_ref().ok_or(WalletError::Locked)
+    }
+
+    pub fn wallet_mut(&mut self) -> WalletResult<&mut KaspaWallet> {
+        self.wallet.as_mut().ok_or(WalletError::Locked)
+    }
+
+    /// Wallet self-check, plus checks of the keystore behind it: the
+    /// ciphertext's MAC (re-verified with `password`) and the cached public
+    /// metadata against the unlocked key.
+    pub fn self_check(&self, password: Option<&str>) -> WalletResult<SelfCheckReport> {
+        let wallet = self.wallet()?;
+        let mut report = SelfCheckReport::default();
+
+        if let Some(keystore) = &self.keystore {
+            if let Some(password) = password {
+                let decrypted = keystore.decrypt(password);
+                report.record(
+                    "keystore MAC",
+                    decrypted.is_ok(),
+                    match &decrypted {
+                        Ok(_) => String::new(),
+                        Err(e) => e.to_string(),
+                    },
+                );
+            }
+            report.record(
+                "cached address",
+                keystore.address == wallet.get_address(),
+                format!("keystore lists {}", keystore.address),
+            );
+            report.record(
+                "fingerprint",
+                keystore.fingerprint == wallet.fingerprint(),
+                format!("keystore lists {}", keystore.fingerprint),
+            );
+        }
+
+        report.extend(wallet.self_check());
+        Ok(report)
+    }
+
+    pub fn keystore(&self) -> Option<&Keystore> {
+        self.keystore.as_ref()
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.keystore
+            .as_ref()
+            .map(|keystore| keystore.name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secp256k1::Secp256k1;
+
+    #[test]
+    fn test_lock_unlock() {
+        let secp = Secp256k1::new();
+        let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let wallet = KaspaWallet::with_network(secret_key, "mainnet").unwrap();
+        let password = "correct horse battery staple";
+        let keystore = Keystore::encrypt(&wallet, "main", password).unwrap();
+
+        let mut lockable = LockableWallet::from_keystore(keystore, PathBuf::from("x"), None);
+        assert!(lockable.is_locked());
+        assert!(matches!(lockable.wallet(), Err(WalletError::Locked)));
+
+        assert!(lockable.unlock("wrong password entirely").is_err());
+        lockable.unlock(password).unwrap();
+        assert_eq!(
+            lockable.wallet().unwrap().get_address(),
+            wallet.get_address()
+        );
+
+        lockable.lock();
+        assert!(lockable.is_locked());
+    }
+
+    #[test]
+    fn test_self_check_detects_corruption() {
+        let mnemonic = bip39::Mnemonic::generate(12).unwrap();
+        let wallet = KaspaWallet::from_mnemonic(&mnemonic, "", "mainnet").unwrap();
+        let password = "correct horse battery staple";
+        let keystore = Keystore::encrypt(&wallet, "main", password).unwrap();
+
+        let mut lockable = LockableWallet::from_keystore(keystore, PathBuf::from("x"), None);
+        lockable.unlock(password).unwrap();
+        let report = lockable.self_check(Some(password)).unwrap();
+        assert!(report.passed(), "{:?}", report);
+        assert_eq!(report.checks.len(), 7);
+
+        let crypto = &mut lockable.keystore.as_mut().unwrap().crypto;
+        let mut ciphertext = hex::decode(&crypto.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        crypto.ciphertext = hex::encode(ciphertext);
+        let report = lockable.self_check(Some(password)).unwrap();
+        assert!(!report.passed());
+        assert!(!report.checks[0].passed);
+    }
+}
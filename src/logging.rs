@@ -0,0 +1,68 @@
+use crate::error::{WalletError, WalletResult};
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer};
+
+/// Filter used when `RUST_LOG` is unset: `quiet` keeps only errors, each
+/// `-v` raises the level one step from warnings.
+pub fn default_directive(verbosity: u8, quiet: bool) -> &'static str {
+    match (quiet, verbosity) {
+        (true, _) => "error",
+        (false, 0) => "warn",
+        (false, 1) => "info",
+        (false, 2) => "debug",
+        (false, _) => "trace",
+    }
+}
+
+/// Installs the global subscriber: human-readable lines on stderr, coloured
+/// with `ansi`, and, with `log_file`, JSON lines appended to that file.
+/// `RUST_LOG` overrides the level derived from the flags.
+pub fn init(verbosity: u8, quiet: bool, ansi: bool, log_file: Option<&Path>) -> WalletResult<()> {
+    let filter = || {
+        EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| EnvFilter::new(default_directive(verbosity, quiet)))
+    };
+
+    let stderr = fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_ansi(ansi)
+        .without_time()
+        .with_target(false)
+        .with_filter(filter());
+
+    let file = match log_file {
+        Some(path) => {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            Some(
+                fmt::layer()
+                    .json()
+                    .with_writer(Mutex::new(file))
+                    .with_filter(filter()),
+            )
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(stderr)
+        .with(file)
+        .try_init()
+        .map_err(|e| WalletError::Io(format!("Cannot start logging: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_directive() {
+        assert_eq!(default_directive(0, false), "warn");
+        assert_eq!(default_directive(2, false), "debug");
+        assert_eq!(default_directive(5, false), "trace");
+        assert_eq!(default_directive(3, true), "error");
+    }
+}
@@ -1,225 +1,2198 @@
-mod address;
-mod error;
-mod gui;
-mod network;
-mod transaction;
-mod wallet;
-
-use crate::error::WalletError;
-use crate::wallet::KaspaWallet;
-use clap::{Parser, Subcommand};
+mod repl;
+mod ui;
+
+use bip39::Mnemonic;
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+use kasparustwallet::address;
+use kasparustwallet::address_book::{AddressBook, Contact};
+use kasparustwallet::address_index::AddressIndex;
+use kasparustwallet::amount::{Kas, Unit, SOMPI_PER_KAS};
+use kasparustwallet::backup;
+use kasparustwallet::batch;
+use kasparustwallet::config;
+use kasparustwallet::descriptor::WalletDescriptor;
+use kasparustwallet::discovery::{self, Discovery};
+use kasparustwallet::error::WalletError;
+use kasparustwallet::event_log::{EventLog, EventSink, WalletEvent, EVENT_LOG_FILE};
+#[cfg(feature = "explorer")]
+use kasparustwallet::explorer::{self, ExplorerClient};
+use kasparustwallet::hd::{CHANGE_CHAIN, RECEIVE_CHAIN};
+use kasparustwallet::history::{self, Explorer, HistoryCache};
+use kasparustwallet::key_source::{self, KeySource, PRIVATE_KEY_ENV, WALLET_PASSWORD_ENV};
+use kasparustwallet::keystore::{Keystore, KEYSTORE_EXTENSION};
+use kasparustwallet::lockable::LockableWallet;
+use kasparustwallet::logging;
+use kasparustwallet::mnemonic;
+use kasparustwallet::monitor::{self, MonitorOutcome};
+use kasparustwallet::network::NetworkConfig;
+use kasparustwallet::node::{
+    self, DaaScoreCache, FailoverClient, FeeEstimate, NodeClient, NodeFailure, RejectReason,
+    RetryPolicy, TxStatus,
+};
+use kasparustwallet::pstx::{PartiallySignedTransaction, TxDocument};
+use kasparustwallet::self_test;
+use kasparustwallet::storage::{self, Overwrite};
+use kasparustwallet::transaction::{
+    build_unsigned_transaction, FeeMode, Transaction, FEE_RATE_PRESETS,
+    MAXIMUM_STANDARD_TRANSACTION_MASS,
+};
+use kasparustwallet::uri::PaymentUri;
+use kasparustwallet::utxo::{UtxoEntry, COINBASE_MATURITY};
+use kasparustwallet::wallet::KaspaWallet;
+use kasparustwallet::wallet_collection::{WalletCollection, DEFAULT_WALLET_NAME};
+use kasparustwallet::wallet_state::{SyncDiff, SyncStore, WalletState};
+use kasparustwallet::watch_only::{WatchOnlyKey, WatchOnlyWallet};
 use secp256k1::SecretKey;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fs;
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use zeroize::{Zeroize, Zeroizing};
 
 #[derive(Parser)]
 #[command(name = "kasparustwallet")]
 #[command(about = "A Kaspa cryptocurrency wallet CLI", long_about = None)]
 #[command(version = "0.2.0")]
+#[command(
+    after_long_help = "Exit status:\n  0  success\n  1  validation failure (bad address, amount, input or transaction)\n  2  usage error\n  3  insufficient funds\n  4  network or node error\n  5  wallet locked or wrong password\n  6  internal error\n 10  rejected by the node: orphan\n 11  rejected by the node: already in the mempool\n 12  rejected by the node: mass too high\n 13  rejected by the node: invalid signature\n 14  rejected by the node for another reason\n 15  node connection refused\n 16  node needs TLS\n 17  node on another network\n 19  rejected by the node: already accepted\n 20  rejected by the node: fee too low\n 21  rejected by the node: otherwise invalid\n 23  node not synced\n 24  monitor --timeout elapsed first"
+)]
 struct Cli {
+    /// Without a command, start the interactive shell
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
+    /// Directory for wallet data (defaults to the platform data dir)
+    #[arg(long, global = true)]
+    data_dir: Option<PathBuf>,
+    /// Record key lifecycle events in an append-only log in the data dir
+    #[arg(long, global = true)]
+    event_log: bool,
+    /// Node RPC address, as host:port or grpc://host:port
+    /// (defaults to node_urls or node_url in the config, then the network's
+    /// local node)
+    #[arg(long, global = true, visible_alias = "node-url")]
+    node: Option<String>,
+    /// Log more detail to stderr; repeat for debug (-vv) and trace (-vvv)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+    /// Print only the command's result and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Also append JSON logs to this file
+    #[arg(long, global = true)]
+    log_file: Option<PathBuf>,
+    /// Answer yes to confirmation prompts, for scripts
+    #[arg(short, long, global = true)]
+    yes: bool,
+    /// Never colour output (also off with NO_COLOR or when piped)
+    #[arg(long, global = true)]
+    no_color: bool,
+    /// Print private keys and seed phrases in full instead of masked
+    #[arg(long, global = true)]
+    show_secrets: bool,
+}
+
+// Where a command gets its signing key from. Kept as a plain comment: a
+// doc comment here would become the `about` of every command that
+// flattens these arguments.
+#[derive(Args, Default)]
+struct KeyArgs {
+    /// Hex private key (insecure: visible in shell history and `ps`)
+    #[arg(short, long)]
+    private_key: Option<String>,
+    /// File containing the hex private key
+    #[arg(long, conflicts_with = "private_key")]
+    private_key_file: Option<PathBuf>,
+    /// Wallet name in the data dir, or path to a keystore file
+    ///
+    /// Without --private-key, --private-key-file or --wallet the key is
+    /// taken from KASPA_PRIVATE_KEY, then the default wallet, then a hidden
+    /// prompt.
+    #[arg(short, long, conflicts_with_all = ["private_key", "private_key_file"])]
+    wallet: Option<String>,
+}
+
+/// Per-request limits for the node and explorer; unset ones come from the
+/// config file.
+#[derive(Args, Clone, Copy, Default)]
+struct RpcArgs {
+    /// Seconds before a node or explorer request times out
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    rpc_timeout: Option<u64>,
+    /// Times to retry a failed request, with exponential backoff
+    #[arg(long, value_name = "N")]
+    retries: Option<u32>,
+}
+
+/// Where a command reads a transaction from; stdin when neither option is
+/// given. Raw hex and PSTX JSON are both accepted.
+#[derive(Args)]
+struct TxSourceArgs {
+    /// Transaction hex
+    #[arg(long)]
+    hex: Option<String>,
+    /// File containing the transaction hex or a PSTX
+    #[arg(long, conflicts_with = "hex")]
+    file: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum NodeCommands {
+    /// Show the node's version, network, sync state and mempool size
+    Info {
+        #[arg(short, long, default_value = "mainnet")]
+        network: String,
+        /// Check every configured node instead of the first usable one
+        #[arg(long)]
+        all: bool,
+    },
+    /// Measure the time to open a connection to the node
+    Ping {
+        #[arg(short, long, default_value = "mainnet")]
+        network: String,
+        /// Number of connections to time
+        #[arg(short, long, default_value = "4", value_parser = clap::value_parser!(u32).range(1..=100))]
+        count: u32,
+    },
+}
+
+#[derive(Subcommand)]
+enum BackupCommands {
+    /// Encrypt a seed phrase into a password-protected backup file
+    ExportMnemonic {
+        #[arg(short, long)]
+        output: String,
+        /// Overwrite the output file if it exists
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum WalletCommands {
+    /// Check the event log's hash chain for tampering or truncation
+    VerifyLog,
+    /// List the keystore wallets in the data dir
+    List,
+    /// Create a new HD wallet in an encrypted keystore
+    Create {
+        /// Name for the wallet in the data dir
+        #[arg(long, default_value = DEFAULT_WALLET_NAME)]
+        name: String,
+        #[arg(short, long, default_value = "mainnet")]
+        network: String,
+    },
+    /// Show the public details of a wallet
+    Info {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[arg(short, long, default_value = "mainnet")]
+        network: String,
+    },
+    /// Print a wallet's private key; always asks for the password again
+    ExportKey {
+        /// Wallet name in the data dir, or path to a keystore file
+        #[arg(short, long)]
+        wallet: Option<String>,
+        /// Confirm that the key will be shown in plain text
+        #[arg(long, required = true)]
+        i_understand_the_risk: bool,
+    },
+    /// Make a wallet the default for commands given no key or --wallet
+    Use { name: String },
+    /// Verify the wallet's keys, cached metadata and keystore integrity
+    Check {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[arg(short, long, default_value = "mainnet")]
+        network: String,
+    },
 }
 
 #[derive(Subcommand)]
 enum Commands {
+    #[command(visible_alias = "new")]
     Create {
         #[arg(short, long, default_value = "mainnet")]
         network: String,
         #[arg(short, long)]
         output: Option<String>,
+        /// Overwrite the output file if it exists
+        #[arg(long, requires = "output")]
+        force: bool,
+        /// Restore a watch-only wallet from an exported descriptor file
+        #[arg(long, conflicts_with = "network")]
+        from_descriptor: Option<String>,
+        /// Generate this many independent wallets into --output-dir
+        #[arg(long, requires = "output_dir", conflicts_with_all = ["output", "from_descriptor"])]
+        count: Option<u32>,
+        /// Directory for the wallets and manifest.csv written by --count
+        #[arg(long, requires = "count")]
+        output_dir: Option<PathBuf>,
+        /// Derive the wallet from a new BIP39 seed phrase of 12 or 24 words
+        #[arg(
+            long,
+            num_args = 0..=1,
+            default_missing_value = "24",
+            conflicts_with_all = ["from_descriptor", "count"]
+        )]
+        mnemonic: Option<usize>,
+        /// Write --count wallets as plaintext keys, or the --mnemonic phrase
+        /// to --output, instead of encrypted keystores
+        #[arg(long)]
+        insecure_plaintext: bool,
     },
+    /// Show a wallet's details from its key or keystore, or a read-only view
+    /// from a public key or address
+    #[command(group(
+        ArgGroup::new("source")
+            .args(["private_key", "private_key_file", "wallet", "public_key", "address"])
+    ))]
     Info {
-        #[arg(short, long)]
-        private_key: String,
+        #[command(flatten)]
+        key: KeyArgs,
+        /// Show the read-only view for a bare public key (hex or kaspa:pk:hex)
+        #[arg(long)]
+        public_key: Option<String>,
+        /// Show the read-only view for an address
+        #[arg(long)]
+        address: Option<String>,
         #[arg(short, long, default_value = "mainnet")]
         network: String,
     },
+    /// Show the wallet's address, or hand out and list derived ones
     Address {
-        #[arg(short, long)]
-        private_key: String,
+        #[command(flatten)]
+        key: KeyArgs,
         #[arg(short, long, default_value = "mainnet")]
         network: String,
+        /// Print the derived address at this index, below 2^31 (the
+        /// indexes above are hardened)
+        #[arg(
+            long,
+            conflicts_with_all = ["count", "all_used"],
+            value_parser = clap::value_parser!(u32).range(..0x8000_0000)
+        )]
+        index: Option<u32>,
+        /// Hand out this many new addresses from the next unused index
+        #[arg(long, conflicts_with = "all_used")]
+        count: Option<u32>,
+        /// Use the internal (change) chain instead of the receive chain
+        #[arg(long)]
+        change: bool,
+        /// List every address handed out so far
+        #[arg(long)]
+        all_used: bool,
     },
+    /// Build and sign a payment from given inputs, or a --batch of them
     Send {
-        #[arg(short, long)]
-        private_key: String,
+        #[command(flatten)]
+        key: KeyArgs,
         #[arg(short, long, default_value = "mainnet")]
         network: String,
+        /// Output to spend as txid:vout[:amount[:script_hex]], the amount in
+        /// sompi or KAS with a decimal point; with amounts, change is returned
         #[arg(short, long)]
         inputs: Vec<String>,
-        #[arg(short, long)]
+        /// Recipient as address:amount, the amount in KAS unless --sompi, or
+        /// a kaspa: payment URI (optionally followed by :amount)
+        #[arg(short, long, conflicts_with = "batch")]
         outputs: Vec<String>,
+        /// Read output amounts as whole sompi instead of KAS
+        #[arg(long, conflicts_with = "batch")]
+        sompi: bool,
         #[arg(short, long, default_value = "1000")]
         fee_rate: u64,
+        /// Pay exactly this many KAS in fees, whatever the mass
+        #[arg(long, conflicts_with_all = ["fee_rate", "batch"])]
+        fee: Option<Kas>,
+        /// Ask before paying more than this many KAS in fees
+        #[arg(long, conflicts_with = "batch")]
+        max_fee: Option<Kas>,
+        /// Pay every row of a CSV file of address,amount_kas[,label]
+        #[arg(long, value_name = "FILE")]
+        batch: Option<PathBuf>,
+        /// Merge repeated batch addresses instead of rejecting them
+        #[arg(long, requires = "batch")]
+        allow_duplicates: bool,
+        /// Submit the signed batch to the node instead of printing it
+        #[arg(long, requires = "batch")]
+        broadcast: bool,
+        /// Broadcast even through a node that is not synced
+        #[arg(long, requires = "broadcast")]
+        force: bool,
+        /// Add labelled payment URI recipients to the address book
+        #[arg(long, conflicts_with = "batch")]
+        save_contact: bool,
+        /// Send change here instead of the wallet's next change address
+        #[arg(long, conflicts_with = "batch")]
+        change_address: Option<String>,
+        /// Fail unless the inputs cover outputs and fee to within a sompi
+        #[arg(long, conflicts_with_all = ["change_address", "batch"])]
+        no_change: bool,
+    },
+    /// Sync the wallet's outputs from the node and show its balance
+    Balance {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[arg(short, long, default_value = "mainnet")]
+        network: String,
+        /// Check this address instead of the wallet's
+        #[arg(short, long)]
+        address: Option<String>,
+        /// Ask the configured explorer instead of the node (third-party data)
+        #[arg(long)]
+        explorer: bool,
+        #[arg(long)]
+        json: bool,
+        #[command(flatten)]
+        rpc: RpcArgs,
+    },
+    /// List unspent outputs of the wallet or of one address
+    Utxos {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[arg(short, long, default_value = "mainnet")]
+        network: String,
+        /// Inspect this address instead of the wallet's
+        #[arg(short, long)]
+        address: Option<String>,
+        /// Ask the configured explorer instead of the node (third-party data)
+        #[arg(long)]
+        explorer: bool,
+        #[arg(long, value_enum, default_value_t = UtxoSort::Amount)]
+        sort: UtxoSort,
+        /// Hide outputs smaller than this many KAS
+        #[arg(long)]
+        min_amount: Option<Kas>,
+        /// Hide coinbase outputs that can't be spent yet
+        #[arg(long)]
+        mature_only: bool,
+        #[arg(long)]
+        json: bool,
+        #[command(flatten)]
+        rpc: RpcArgs,
+    },
+    /// List past transactions of an address from the configured explorer
+    History {
+        #[arg(short, long)]
+        address: String,
+        #[arg(long, default_value = "20", value_parser = clap::value_parser!(u32).range(1..=500))]
+        limit: u32,
+        /// Only transactions older than this block time (ms), for paging
+        #[arg(long)]
+        before: Option<u64>,
+        /// Skip the local cache and ask the explorer again
+        #[arg(long)]
+        refresh: bool,
+        #[arg(long, conflicts_with = "csv")]
+        json: bool,
+        #[arg(long)]
+        csv: bool,
+        #[command(flatten)]
+        rpc: RpcArgs,
+    },
+    /// Wait for incoming payments to an address
+    #[command(
+        after_long_help = "Exit status:\n  0  the expected amount arrived\n  4  the node connection failed\n 24  --timeout elapsed first"
+    )]
+    Monitor {
+        #[arg(short, long, required_unless_present = "wallet")]
+        address: Option<String>,
+        /// Watch a wallet's address (read from its keystore, no unlock)
+        #[arg(short, long, conflicts_with = "address")]
+        wallet: Option<String>,
+        #[arg(short, long, default_value = "mainnet")]
+        network: String,
+        /// Exit once this many KAS have arrived
+        #[arg(long)]
+        expect_amount: Option<Kas>,
+        /// Give up after this many seconds
+        #[arg(long)]
+        timeout: Option<u64>,
+        /// Seconds between polls when the node has no notifications
+        #[arg(long, default_value = "5")]
+        interval: u64,
+        #[command(flatten)]
+        rpc: RpcArgs,
     },
+    /// Estimate fees for a draft transaction, or roughly from counts
     EstimateFee {
+        /// Inputs as txid:vout[:amount[:script_hex]], or just how many for a
+        /// rough estimate
+        #[arg(short, long, conflicts_with = "hex")]
+        inputs: Vec<String>,
+        /// Recipients as address:amount, or just how many for a rough estimate
+        #[arg(short, long, conflicts_with = "hex")]
+        outputs: Vec<String>,
+        /// Read output amounts as whole sompi instead of KAS
+        #[arg(long)]
+        sompi: bool,
+        /// Encoded draft transaction
+        #[arg(long)]
+        hex: Option<String>,
+        /// Also show the fee at this rate (sompi per 1000 mass)
+        #[arg(short, long)]
+        fee_rate: Option<u64>,
+        /// Use the node's current rates instead of the built-in presets
+        #[arg(long)]
+        from_node: bool,
+        /// Network of the node asked with --from-node
+        #[arg(short, long, default_value = "mainnet", requires = "from_node")]
+        network: String,
+        #[command(flatten)]
+        rpc: RpcArgs,
+    },
+    /// Run built-in known-answer tests; needs no network or wallet
+    SelfTest,
+    /// Show an amount in both KAS and sompi
+    #[command(
+        after_long_help = "Examples:\n  convert 1.5kas\n  convert 150000000sompi\n  echo 0.001 | convert --from kas\n\nExit status 2 when an amount is malformed or out of range."
+    )]
+    Convert {
+        /// Amount with a kas or sompi suffix; read from stdin when omitted
+        amount: Option<String>,
+        /// Unit of amounts given without a suffix
+        #[arg(long)]
+        from: Option<Unit>,
+        /// Also show the value at this USD price per KAS
+        #[arg(long)]
+        price: Option<f64>,
+    },
+    /// Check that addresses are well formed
+    #[command(
+        after_long_help = "Exit status:\n  0  every address is valid\n  1  at least one address is invalid\n  2  usage error, or the file could not be read"
+    )]
+    ValidateAddress {
+        /// Addresses to check
+        addresses: Vec<String>,
+        #[arg(short, long)]
+        address: Vec<String>,
+        /// Also check every non-empty line of this file
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+    /// Build an unsigned transaction spending from a watch-only source
+    CreateUnsignedTx {
+        /// Public key (hex or kaspa:pk:hex) whose address funds the inputs
+        #[arg(long)]
+        public_key: String,
+        #[arg(short, long, default_value = "mainnet")]
+        network: String,
+        /// Output to spend as txid:vout[:amount[:script_hex]]
         #[arg(short, long)]
-        inputs: usize,
+        inputs: Vec<String>,
+        /// Recipient as address:amount, the amount in KAS unless --sompi
         #[arg(short, long)]
-        outputs: usize,
+        outputs: Vec<String>,
+        /// Read output amounts as whole sompi instead of KAS
+        #[arg(long)]
+        sompi: bool,
+        /// Write a file for `sign-tx` on an offline machine, or `-` for
+        /// stdout; without --inputs, coins are selected from the node
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Overwrite the output file if it exists
+        #[arg(long, requires = "output")]
+        force: bool,
         #[arg(short, long, default_value = "1000")]
         fee_rate: u64,
     },
-    ValidateAddress {
+    /// Sign a file from `create-unsigned-tx`; needs no network access
+    SignTx {
+        #[command(flatten)]
+        source: TxSourceArgs,
+        #[command(flatten)]
+        key: KeyArgs,
+        /// Network of the key for raw hex input; a PSTX names its own
+        #[arg(short, long, default_value = "mainnet")]
+        network: String,
+        /// Where to write the result (defaults to <file>.signed.json, or
+        /// stdout when the transaction came from --hex or stdin)
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Overwrite the output file if it exists
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print the broadcast hex of a file signed by `sign-tx`
+    FinalizeTx {
+        #[arg(long)]
+        file: PathBuf,
+        /// Write the hex to this file instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Pretty-print an encoded transaction
+    DecodeTx {
+        #[command(flatten)]
+        source: TxSourceArgs,
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Submit an encoded transaction to the node
+    Broadcast {
+        #[command(flatten)]
+        source: TxSourceArgs,
+        #[arg(short, long, default_value = "mainnet")]
+        network: String,
+        /// Check that the transaction reached the mempool, then wait until
+        /// the virtual chain accepts it
+        #[arg(long, visible_alias = "wait-for-acceptance")]
+        wait: bool,
+        /// Seconds to wait with --wait
+        #[arg(long, default_value = "60", requires = "wait")]
+        timeout: u64,
+        /// Broadcast even through a node that is not synced
+        #[arg(long)]
+        force: bool,
+        #[command(flatten)]
+        rpc: RpcArgs,
+    },
+    /// Export a watch-only descriptor of the wallet's public key
+    ExportDescriptor {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[arg(short, long, default_value = "mainnet")]
+        network: String,
         #[arg(short, long)]
-        address: String,
+        output: Option<String>,
+        /// Overwrite the output file if it exists
+        #[arg(long, requires = "output")]
+        force: bool,
+    },
+    /// Write derived addresses for a deposit pool or watchlist
+    ExportAddresses {
+        #[command(flatten)]
+        key: KeyArgs,
+        /// Derive from a watch-only descriptor instead of a key
+        #[arg(long, conflicts_with_all = ["private_key", "private_key_file", "wallet"])]
+        descriptor: Option<PathBuf>,
+        #[arg(short, long, default_value = "mainnet")]
+        network: String,
+        #[arg(long)]
+        count: u32,
+        #[arg(long, default_value = "0")]
+        start: u32,
+        /// Use the internal (change) chain instead of the receive chain
+        #[arg(long)]
+        change: bool,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Overwrite the output file if it exists
+        #[arg(long, requires = "output")]
+        force: bool,
+    },
+    /// Derive a BIP85 child seed phrase from an HD wallet's seed
+    DeriveMnemonic {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[arg(long, default_value = "0")]
+        index: u32,
+        /// Number of words: 12, 18 or 24
+        #[arg(long, default_value = "24")]
+        words: u32,
+    },
+    Wallet {
+        #[command(subcommand)]
+        command: WalletCommands,
+    },
+    Backup {
+        #[command(subcommand)]
+        command: BackupCommands,
+    },
+    /// Print a new BIP39 seed phrase
+    GenerateMnemonic {
+        /// Number of words: 12 or 24
+        #[arg(long, default_value = "24")]
+        words: usize,
+        /// Wordlist language
+        #[arg(long, default_value = "en")]
+        language: String,
+        /// Write the phrase to this file instead of the terminal
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Overwrite the output file if it exists
+        #[arg(long, requires = "output")]
+        force: bool,
+    },
+    /// Restore an HD wallet from its seed phrase into an encrypted keystore
+    Restore {
+        /// Encrypted backup written by `backup export-mnemonic`; without it
+        /// the phrase is entered word by word
+        #[arg(long)]
+        encrypted_backup: Option<String>,
+        #[arg(short, long, default_value = "mainnet")]
+        network: String,
+        /// Name for the restored wallet in the data dir
+        #[arg(long, default_value = DEFAULT_WALLET_NAME)]
+        name: String,
+        /// Then find the addresses already used, asking the node or the
+        /// configured explorer
+        #[arg(long, value_enum)]
+        scan: Option<ScanSource>,
+    },
+    /// Interactive shell with the wallet unlocked for the session
+    Repl {
+        #[command(flatten)]
+        key: KeyArgs,
+        #[arg(short, long, default_value = "mainnet")]
+        network: String,
+    },
+    /// Check the connection to the node
+    Node {
+        #[command(subcommand)]
+        command: NodeCommands,
     },
+    /// Open the desktop wallet (needs a build with `--features gui`)
     Gui,
 }
 
+/// Exit codes shared by every command; `README.md` has the table.
+const EXIT_VALIDATION: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_INSUFFICIENT_FUNDS: i32 = 3;
+const EXIT_NETWORK: i32 = 4;
+const EXIT_AUTH: i32 = 5;
+const EXIT_INTERNAL: i32 = 6;
+const EXIT_TIMED_OUT: i32 = 24;
+
+impl Commands {
+    /// Whether the command was asked for JSON output.
+    fn json_output(&self) -> bool {
+        matches!(
+            self,
+            Commands::Utxos { json: true, .. }
+                | Commands::Balance { json: true, .. }
+                | Commands::History { json: true, .. }
+                | Commands::DecodeTx { json: true, .. }
+        )
+    }
+
+    /// The `--rpc-timeout` and `--retries` given to a network command.
+    fn rpc_args(&self) -> RpcArgs {
+        match self {
+            Commands::Utxos { rpc, .. }
+            | Commands::Balance { rpc, .. }
+            | Commands::History { rpc, .. }
+            | Commands::Monitor { rpc, .. }
+            | Commands::Broadcast { rpc, .. }
+            | Commands::EstimateFee { rpc, .. } => *rpc,
+            _ => RpcArgs::default(),
+        }
+    }
+}
+
 fn main() {
     let cli = Cli::parse();
+    ui::init(cli.no_color);
+    if let Err(e) = logging::init(
+        cli.verbose,
+        cli.quiet,
+        ui::stderr_color(),
+        cli.log_file.as_deref(),
+    ) {
+        eprintln!("Error: {}", e);
+        std::process::exit(exit_code(&e));
+    }
 
+    let json = cli.command.as_ref().is_some_and(Commands::json_output);
     if let Err(e) = run_cli(cli) {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
+        fail(&e, exit_code(&e), json);
+    }
+}
+
+/// Reports `error` on stderr, as JSON for commands run with `--json`, and
+/// exits with `code`.
+fn fail(error: &WalletError, code: i32, json: bool) -> ! {
+    if json {
+        eprintln!(
+            "{}",
+            serde_json::json!({ "error": error.to_string(), "code": code })
+        );
+    } else {
+        eprintln!("Error: {}", error);
+    }
+    std::process::exit(code)
+}
+
+fn exit_code(error: &WalletError) -> i32 {
+    match error {
+        WalletError::InvalidParameters(_)
+        | WalletError::InvalidAddressFormat
+        | WalletError::Serialization(_)
+        | WalletError::Transaction(_)
+        | WalletError::WatchOnly => EXIT_VALIDATION,
+        WalletError::InsufficientBalance => EXIT_INSUFFICIENT_FUNDS,
+        WalletError::Network(_) | WalletError::Timeout { .. } => EXIT_NETWORK,
+        // Rejections narrow EXIT_NETWORK down to the node's reason.
+        WalletError::Rejected { reason, .. } => match reason {
+            RejectReason::Orphan => 10,
+            RejectReason::AlreadyInMempool => 11,
+            RejectReason::MassTooHigh => 12,
+            RejectReason::InvalidSignature => 13,
+            RejectReason::Other => 14,
+            RejectReason::AlreadyAccepted => 19,
+            RejectReason::FeeTooLow => 20,
+            RejectReason::Invalid => 21,
+        },
+        WalletError::Node(NodeFailure::ConnectionRefused(_)) => 15,
+        WalletError::Node(NodeFailure::Tls(_)) => 16,
+        WalletError::Node(NodeFailure::WrongNetwork { .. }) => 17,
+        WalletError::Node(NodeFailure::NotSynced { .. }) => 23,
+        WalletError::WaitTimedOut { .. } => EXIT_TIMED_OUT,
+        WalletError::Locked | WalletError::Authentication(_) => EXIT_AUTH,
+        WalletError::KeyGeneration(_)
+        | WalletError::AddressGeneration(_)
+        | WalletError::Storage(_)
+        | WalletError::Io(_) => EXIT_INTERNAL,
+    }
+}
+
+/// State shared by every command.
+struct Context {
+    data_dir: PathBuf,
+    events: Option<Arc<dyn EventSink>>,
+    node: Option<String>,
+    /// Keystore passwords already entered, by keystore path.
+    passwords: RefCell<HashMap<PathBuf, Zeroizing<String>>>,
+    /// Skip confirmation prompts (`--yes`).
+    assume_yes: bool,
+    /// Print secrets in full (`--show-secrets`).
+    show_secrets: bool,
+    /// Keep output to the bare result (`--quiet`).
+    quiet: bool,
+    /// The command's `--rpc-timeout` and `--retries`.
+    rpc: RpcArgs,
+}
+
+impl Context {
+    /// Node addresses to try in order: `--node`, the config's `node_urls`
+    /// or `node_url`, or the network's default.
+    fn node_urls(&self, network: &str) -> Result<Vec<String>, WalletError> {
+        if let Some(url) = &self.node {
+            return Ok(vec![url.clone()]);
+        }
+        let config = config::Config::load(&self.data_dir)?;
+        match (config.node_urls, config.node_url) {
+            (Some(urls), _) if !urls.is_empty() => Ok(urls),
+            (_, Some(url)) => Ok(vec![url]),
+            _ => Ok(vec![NetworkConfig::from_name(network)?.rpc_url]),
+        }
+    }
+
+    /// Timeout and retries from the command's flags, then the config, then
+    /// the defaults.
+    fn retry_policy(&self, default_timeout: Duration) -> Result<RetryPolicy, WalletError> {
+        let config = config::Config::load(&self.data_dir)?;
+        Ok(RetryPolicy {
+            timeout: self
+                .rpc
+                .rpc_timeout
+                .or(config.timeout_secs)
+                .map_or(default_timeout, Duration::from_secs),
+            retries: self
+                .rpc
+                .retries
+                .or(config.retries)
+                .unwrap_or(node::DEFAULT_RETRIES),
+        })
+    }
+
+    /// Client for the first usable node, refusing any on another network
+    /// and failing over to the next one configured.
+    fn node_client(&self, network: &str) -> Result<Box<dyn NodeClient>, WalletError> {
+        Ok(Box::new(self.failover_client(network)?))
+    }
+
+    /// `node_client` for callers that also ask the other configured nodes.
+    fn failover_client(&self, network: &str) -> Result<FailoverClient, WalletError> {
+        let policy = self.retry_policy(node::CONNECT_TIMEOUT)?;
+        FailoverClient::connect(&self.node_urls(network)?, network, policy)
+    }
+
+    /// The explorer from the config; there is no default.
+    fn explorer_url(&self) -> Result<String, WalletError> {
+        config::Config::load(&self.data_dir)?
+            .explorer_url
+            .ok_or_else(|| {
+                WalletError::InvalidParameters(format!(
+                    "No explorer API configured. Add \"explorer_url\": \"http://<host>[:port]\" to {}",
+                    self.data_dir.join(config::CONFIG_FILE).display()
+                ))
+            })
+    }
+
+    #[cfg(feature = "explorer")]
+    fn explorer_client(&self) -> Result<ExplorerClient, WalletError> {
+        ExplorerClient::new(
+            &self.explorer_url()?,
+            self.retry_policy(history::HTTP_TIMEOUT)?,
+        )
     }
 }
 
 fn run_cli(cli: Cli) -> Result<(), WalletError> {
-    match cli.command {
-        Commands::Create { network, output } => create_new_wallet(&network, output),
+    let data_dir = cli.data_dir.unwrap_or_else(config::default_data_dir);
+    let events: Option<Arc<dyn EventSink>> = if cli.event_log {
+        Some(Arc::new(EventLog::open(data_dir.join(EVENT_LOG_FILE))?))
+    } else {
+        None
+    };
+    let ctx = Context {
+        data_dir,
+        events,
+        node: cli.node,
+        passwords: RefCell::default(),
+        assume_yes: cli.yes,
+        show_secrets: cli.show_secrets,
+        quiet: cli.quiet,
+        rpc: cli
+            .command
+            .as_ref()
+            .map(Commands::rpc_args)
+            .unwrap_or_default(),
+    };
+
+    let Some(command) = cli.command else {
+        return repl::run_repl(&ctx, &KeyArgs::default(), "mainnet");
+    };
+
+    match command {
+        Commands::Create {
+            output,
+            force,
+            from_descriptor: Some(descriptor_path),
+            ..
+        } => restore_watch_only(&descriptor_path, output, force.into()),
+        Commands::Create {
+            network,
+            count: Some(count),
+            output_dir: Some(output_dir),
+            insecure_plaintext,
+            ..
+        } => create_wallet_batch(&ctx, &network, count, &output_dir, insecure_plaintext),
+        Commands::Create {
+            network,
+            output,
+            force,
+            mnemonic: Some(words),
+            insecure_plaintext,
+            ..
+        } => create_mnemonic_wallet(
+            &ctx,
+            &network,
+            words,
+            output.map(PathBuf::from),
+            force.into(),
+            insecure_plaintext,
+        ),
+        Commands::Create {
+            insecure_plaintext: true,
+            ..
+        } => Err(WalletError::InvalidParameters(
+            "--insecure-plaintext only applies to --count or --mnemonic".to_string(),
+        )),
+        Commands::Create {
+            network,
+            output,
+            force,
+            ..
+        } => create_new_wallet(&ctx, &network, output, force.into()),
         Commands::Info {
-            private_key,
+            public_key: Some(public_key),
+            network,
+            ..
+        } => show_watch_only_info(&ctx, &public_key, &network),
+        Commands::Info {
+            address: Some(address),
             network,
-        } => show_wallet_info(&private_key, &network),
+            ..
+        } => show_address_info(&address, &network),
+        Commands::Info { key, network, .. } => show_wallet_info(&ctx, &key, &network),
         Commands::Address {
-            private_key,
+            key,
             network,
-        } => generate_address(&private_key, &network),
+            index,
+            count,
+            change,
+            all_used,
+        } => {
+            let chain = if change { CHANGE_CHAIN } else { RECEIVE_CHAIN };
+            let selection = match (index, count, all_used) {
+                (Some(index), _, _) => AddressSelection::Index(index),
+                (None, Some(count), _) => AddressSelection::Next(count),
+                (None, None, true) => AddressSelection::AllUsed,
+                (None, None, false) if change => AddressSelection::Next(1),
+                (None, None, false) => AddressSelection::Default,
+            };
+            generate_address(&ctx, &key, &network, chain, selection)
+        }
         Commands::Send {
-            private_key,
+            key,
+            network,
+            inputs,
+            fee_rate,
+            batch: Some(batch),
+            allow_duplicates,
+            broadcast,
+            force,
+            ..
+        } => send_batch(
+            &ctx,
+            &key,
+            &network,
+            &inputs,
+            &batch,
+            allow_duplicates,
+            fee_rate,
+            broadcast,
+            force,
+        ),
+        Commands::Send {
+            key,
             network,
             inputs,
             outputs,
+            sompi,
             fee_rate,
-        } => create_transaction(&private_key, &network, inputs, outputs, fee_rate),
+            fee,
+            max_fee,
+            batch: None,
+            save_contact,
+            change_address,
+            no_change,
+            ..
+        } => {
+            let change = match change_address {
+                Some(address) => {
+                    NetworkConfig::from_name(&network)?.check_address(&address)?;
+                    Change::Address(address)
+                }
+                None if no_change => Change::Forbidden,
+                None => Change::Wallet,
+            };
+            create_transaction(
+                &ctx,
+                &key,
+                &network,
+                inputs,
+                parse_recipients(&outputs, sompi)?,
+                match fee {
+                    Some(fee) => FeeMode::Absolute(fee.sompi()),
+                    None => FeeMode::Rate(fee_rate),
+                },
+                max_fee,
+                &change,
+                save_contact,
+            )
+        }
+        Commands::Balance {
+            key,
+            network,
+            address,
+            explorer,
+            json,
+            ..
+        } => show_balance(&ctx, &key, &network, address, explorer, json),
+        Commands::Utxos {
+            key,
+            network,
+            address,
+            explorer,
+            sort,
+            min_amount,
+            mature_only,
+            json,
+            ..
+        } => list_utxos(
+            &ctx,
+            &key,
+            &network,
+            address,
+            UtxoFilter {
+                sort,
+                min_amount,
+                mature_only,
+            },
+            explorer,
+            json,
+        ),
+        Commands::History {
+            address,
+            limit,
+            before,
+            refresh,
+            json,
+            csv,
+            ..
+        } => show_history(&ctx, &address, limit as usize, before, refresh, json, csv),
+        Commands::Monitor {
+            address,
+            wallet,
+            network,
+            expect_amount,
+            timeout,
+            interval,
+            ..
+        } => {
+            let result = monitor_address(
+                &ctx,
+                address,
+                wallet.as_deref(),
+                &network,
+                expect_amount,
+                timeout.map(Duration::from_secs),
+                Duration::from_secs(interval),
+            );
+            match result {
+                Ok(MonitorOutcome::Received(_)) => Ok(()),
+                Ok(MonitorOutcome::TimedOut(received)) => Err(WalletError::WaitTimedOut {
+                    received: Kas::from_sompi(received),
+                }),
+                Err(e) => Err(e),
+            }
+        }
         Commands::EstimateFee {
             inputs,
             outputs,
+            sompi,
+            hex,
+            fee_rate,
+            from_node,
+            network,
+            rpc: _,
+        } => {
+            let node_estimate = if from_node {
+                node_fee_estimate(&ctx, &network)
+            } else {
+                None
+            };
+            estimate_fee(
+                &inputs,
+                &outputs,
+                sompi,
+                hex.as_deref(),
+                fee_rate,
+                node_estimate,
+            )
+        }
+        Commands::SelfTest => run_self_test(),
+        Commands::Convert {
+            amount,
+            from,
+            price,
+        } => {
+            if let Err(e) = convert_amounts(amount, from, price) {
+                fail(&e, EXIT_USAGE, false);
+            }
+            Ok(())
+        }
+        Commands::ValidateAddress {
+            mut addresses,
+            address,
+            file,
+        } => {
+            addresses.extend(address);
+            match validate_addresses(&ctx, addresses, file.as_deref()) {
+                Ok(true) => Ok(()),
+                Ok(false) => std::process::exit(EXIT_VALIDATION),
+                Err(e) => fail(&e, EXIT_USAGE, false),
+            }
+        }
+        Commands::CreateUnsignedTx {
+            public_key,
+            network,
+            inputs,
+            outputs,
+            sompi,
+            output,
+            force,
             fee_rate,
-        } => estimate_fee(inputs, outputs, fee_rate),
-        Commands::ValidateAddress { address } => validate_address(&address),
-        Commands::Gui => gui::run_gui().map_err(|e| WalletError::Network(e.to_string())),
+        } => match output {
+            Some(path) => write_unsigned_pstx(
+                &ctx,
+                &public_key,
+                &network,
+                &inputs,
+                parse_outputs(&outputs, sompi)?,
+                fee_rate,
+                &path,
+                force.into(),
+            ),
+            None => create_unsigned_transaction(
+                &public_key,
+                &network,
+                inputs,
+                parse_outputs(&outputs, sompi)?,
+            ),
+        },
+        Commands::SignTx {
+            source,
+            key,
+            network,
+            output,
+            force,
+        } => sign_tx(&ctx, &source, &key, &network, output, force.into()),
+        Commands::FinalizeTx { file, output } => finalize_pstx(&file, output.as_deref()),
+        Commands::DecodeTx { source, json } => decode_transaction(&source, json),
+        Commands::Broadcast {
+            source,
+            network,
+            wait,
+            timeout,
+            force,
+            ..
+        } => broadcast_transaction(&ctx, &source, &network, wait, timeout, force),
+        Commands::ExportAddresses {
+            key,
+            descriptor,
+            network,
+            count,
+            start,
+            change,
+            format,
+            output,
+            force,
+        } => {
+            let chain = if change { CHANGE_CHAIN } else { RECEIVE_CHAIN };
+            let rows = derive_address_rows(
+                &ctx,
+                &key,
+                descriptor.as_deref(),
+                &network,
+                chain,
+                start,
+                count,
+            )?;
+            export_addresses(&rows, format, output.as_deref(), force.into())
+        }
+        Commands::ExportDescriptor {
+            key,
+            network,
+            output,
+            force,
+        } => export_descriptor(&ctx, &key, &network, output, force.into()),
+        Commands::DeriveMnemonic { key, index, words } => {
+            derive_child_mnemonic(&ctx, &key, index, words)
+        }
+        Commands::Wallet {
+            command: WalletCommands::VerifyLog,
+        } => verify_event_log(&ctx.data_dir),
+        Commands::Wallet {
+            command: WalletCommands::List,
+        } => list_wallets(&ctx),
+        Commands::Wallet {
+            command: WalletCommands::Create { name, network },
+        } => create_keystore_wallet(&ctx, &name, &network),
+        Commands::Wallet {
+            command: WalletCommands::Info { key, network },
+        } => show_keystore_info(&ctx, &key, &network),
+        Commands::Wallet {
+            command: WalletCommands::ExportKey { wallet, .. },
+        } => export_keystore_key(&ctx, wallet.as_deref()),
+        Commands::Wallet {
+            command: WalletCommands::Use { name },
+        } => use_wallet(&ctx, &name),
+        Commands::Wallet {
+            command: WalletCommands::Check { key, network },
+        } => check_wallet(&ctx, &key, &network),
+        Commands::Backup {
+            command: BackupCommands::ExportMnemonic { output, force },
+        } => export_mnemonic_backup(&output, force.into()),
+        Commands::GenerateMnemonic {
+            words,
+            language,
+            output,
+            force,
+        } => generate_mnemonic(&ctx, words, &language, output, force.into()),
+        Commands::Restore {
+            encrypted_backup,
+            network,
+            name,
+            scan,
+        } => restore_wallet(&ctx, encrypted_backup.as_deref(), &network, &name, scan),
+        Commands::Repl { key, network } => repl::run_repl(&ctx, &key, &network),
+        Commands::Node {
+            command: NodeCommands::Info { network, all },
+        } => show_node_info(&ctx, &network, all),
+        Commands::Node {
+            command: NodeCommands::Ping { network, count },
+        } => ping_node(&ctx, &network, count),
+        Commands::Gui => run_gui(),
+    }
+}
+
+/// Prints what the node reports, then fails if it is on another network.
+fn show_node_info(ctx: &Context, network: &str, all: bool) -> Result<(), WalletError> {
+    let urls = ctx.node_urls(network)?;
+    let policy = ctx.retry_policy(node::CONNECT_TIMEOUT)?;
+    if all {
+        return show_node_health(&urls, network, policy);
+    }
+    let (url, info) = match urls.as_slice() {
+        [url] => (url.clone(), node::connect(url, policy)?.get_info()?),
+        _ => {
+            let client = FailoverClient::connect(&urls, network, policy)?;
+            (client.url(), client.get_info()?)
+        }
+    };
+
+    println!("Node Information:");
+    println!("==================");
+    println!("URL: {}", url);
+    println!("Server Version: {}", info.server_version);
+    println!("Network: {}", info.network);
+    println!("Synced: {}", if info.is_synced { "yes" } else { "no" });
+    println!("Virtual DAA Score: {}", info.virtual_daa_score);
+    println!("Mempool Size: {}", info.mempool_size);
+
+    node::check_network(&info, network)
+}
+
+/// One line per node: whether it answers, is on `network` and is synced.
+/// Fails only when none is usable.
+fn show_node_health(
+    urls: &[String],
+    network: &str,
+    policy: RetryPolicy,
+) -> Result<(), WalletError> {
+    println!("Node Health:");
+    println!("==================");
+    let mut usable = 0;
+    for url in urls {
+        let started = Instant::now();
+        let checked = node::connect(url, policy)
+            .and_then(|client| client.get_info())
+            .and_then(|info| node::check_network(&info, network).map(|()| info));
+        let millis = started.elapsed().as_secs_f64() * 1000.0;
+        match checked {
+            Ok(info) => {
+                usable += 1;
+                let synced = if info.is_synced {
+                    ui::green("synced")
+                } else {
+                    ui::yellow("not synced")
+                };
+                println!(
+                    "  {}  {}  {}  {}  DAA {}  {:.0} ms",
+                    ui::green("ok  "),
+                    url,
+                    info.server_version,
+                    synced,
+                    info.virtual_daa_score,
+                    millis
+                );
+            }
+            Err(e) => println!("  {}  {}  {}", ui::red("fail"), url, e),
+        }
+    }
+    println!("Usable: {} of {}", usable, urls.len());
+    if usable == 0 {
+        return Err(WalletError::Network(format!(
+            "None of the {} configured nodes is usable on {}",
+            urls.len(),
+            network
+        )));
+    }
+    Ok(())
+}
+
+fn ping_node(ctx: &Context, network: &str, count: u32) -> Result<(), WalletError> {
+    let url = ctx.node_urls(network)?.remove(0);
+    let timeout = ctx.retry_policy(node::CONNECT_TIMEOUT)?.timeout;
+    println!("Node Ping:");
+    println!("==================");
+    println!("URL: {}", url);
+
+    let millis = |time: Duration| time.as_secs_f64() * 1000.0;
+    let mut times = Vec::new();
+    for attempt in 0..count {
+        if attempt > 0 {
+            std::thread::sleep(Duration::from_millis(200));
+        }
+        let time = node::ping(&url, timeout)?;
+        println!("Connected in {:.2} ms", millis(time));
+        times.push(time);
+    }
+    let total: Duration = times.iter().sum();
+    println!(
+        "Round trip min/avg/max: {:.2}/{:.2}/{:.2} ms",
+        millis(*times.iter().min().expect("count is at least 1")),
+        millis(total / count),
+        millis(*times.iter().max().expect("count is at least 1"))
+    );
+    Ok(())
+}
+
+/// Unlocked wallet for a command, from the first of: `--private-key`,
+/// `--private-key-file`, `--wallet`, KASPA_PRIVATE_KEY, the collection's
+/// default wallet, or a hidden prompt when stdin is a terminal. Keystore
+/// wallets carry their own network and ignore `network`.
+fn open_wallet(ctx: &Context, key: &KeyArgs, network: &str) -> Result<LockableWallet, WalletError> {
+    Ok(unlock_wallet(ctx, key, network)?.0)
+}
+
+/// As `open_wallet`, also returning the password when one was prompted for.
+fn unlock_wallet(
+    ctx: &Context,
+    key: &KeyArgs,
+    network: &str,
+) -> Result<(LockableWallet, Option<Zeroizing<String>>), WalletError> {
+    let (mut lockable, password) = match resolve_key(ctx, key)? {
+        ResolvedKey::Secret(source, secret_key) => {
+            if source == KeySource::Argument {
+                tracing::warn!(
+                    "--private-key exposes the key in shell history and process lists; prefer --private-key-file or {}",
+                    PRIVATE_KEY_ENV
+                );
+            }
+            (
+                LockableWallet::from_wallet(KaspaWallet::with_network(secret_key, network)?),
+                None,
+            )
+        }
+        ResolvedKey::Keystore(collection, name) => {
+            let mut lockable = collection.open_wallet(&name)?;
+            let password = unlock_keystore(ctx, &mut lockable, &name)?;
+            (lockable, Some(password))
+        }
+    };
+
+    let wallet = lockable.wallet_mut()?;
+    if let Some(sink) = &ctx.events {
+        wallet.set_event_sink(sink.clone());
+    }
+    if wallet.is_hd() {
+        let used = AddressIndex::load(&ctx.data_dir)?;
+        let fingerprint = wallet.fingerprint();
+        for chain in [RECEIVE_CHAIN, CHANGE_CHAIN] {
+            wallet.set_next_index(chain, used.next(&fingerprint, chain));
+        }
+    }
+    Ok((lockable, password))
+}
+
+/// Unlocks with KASPA_WALLET_PASSWORD, the password cached earlier in this
+/// process, or a prompt, caching whichever worked.
+fn unlock_keystore(
+    ctx: &Context,
+    lockable: &mut LockableWallet,
+    name: &str,
+) -> Result<Zeroizing<String>, WalletError> {
+    let key = lockable.path().map(Path::to_path_buf).unwrap_or_default();
+    let cached = ctx.passwords.borrow().get(&key).cloned();
+    let password = match (std::env::var(WALLET_PASSWORD_ENV).ok(), cached) {
+        (Some(password), _) => Zeroizing::new(password),
+        (None, Some(password)) => password,
+        (None, None) => prompt_password(&format!("Password for wallet {}: ", name))?,
+    };
+
+    if let Err(e) = lockable.unlock(&password) {
+        ctx.passwords.borrow_mut().remove(&key);
+        return Err(e);
+    }
+    ctx.passwords.borrow_mut().insert(key, password.clone());
+    Ok(password)
+}
+
+enum ResolvedKey {
+    Secret(KeySource, SecretKey),
+    Keystore(Box<WalletCollection>, String),
+}
+
+fn resolve_key(ctx: &Context, key: &KeyArgs) -> Result<ResolvedKey, WalletError> {
+    let collection = WalletCollection::open(&ctx.data_dir)?;
+    if let Some(name) = &key.wallet {
+        return Ok(ResolvedKey::Keystore(Box::new(collection), name.clone()));
+    }
+
+    let env = std::env::var(PRIVATE_KEY_ENV).ok().map(Zeroizing::new);
+    if let Some((source, secret_key)) = key_source::resolve_private_key(
+        key.private_key.as_deref(),
+        key.private_key_file.as_deref(),
+        env.as_deref().map(String::as_str),
+    )? {
+        return Ok(ResolvedKey::Secret(source, secret_key));
+    }
+
+    if let Some(name) = collection.default_wallet() {
+        let name = name.to_string();
+        return Ok(ResolvedKey::Keystore(Box::new(collection), name));
+    }
+
+    if io::stdin().is_terminal() {
+        let entered = prompt_password("Private key (input hidden): ")?;
+        return Ok(ResolvedKey::Secret(
+            KeySource::Prompt,
+            key_source::parse_private_key(&entered)?,
+        ));
+    }
+
+    Err(WalletError::InvalidParameters(format!(
+        "No key given: pass --private-key-file or --wallet, set {}, or select a default with `wallet use`",
+        PRIVATE_KEY_ENV
+    )))
+}
+
+/// A new secret that is neither shown in full nor saved would be lost, so
+/// refuse before generating it.
+fn require_shown_or_saved(ctx: &Context, saved: bool, what: &str) -> Result<(), WalletError> {
+    if saved || ctx.show_secrets {
+        return Ok(());
     }
+    Err(WalletError::InvalidParameters(format!(
+        "{} is only printed with --show-secrets; pass it, or --output to save to a file",
+        what
+    )))
 }
 
-fn create_new_wallet(network: &str, output: Option<String>) -> Result<(), WalletError> {
+fn create_new_wallet(
+    ctx: &Context,
+    network: &str,
+    output: Option<String>,
+    overwrite: Overwrite,
+) -> Result<(), WalletError> {
     let secp = secp256k1::Secp256k1::new();
     let (secret_key, _public_key) = secp.generate_keypair(&mut rand::rngs::OsRng);
 
     let wallet = KaspaWallet::with_network(secret_key, network)?;
+    if output.is_none() && ctx.show_secrets {
+        confirm_or_cancel(ctx, "The new private key will be printed. Proceed?")?;
+    }
+    if let Some(sink) = &ctx.events {
+        sink.record(WalletEvent::WalletCreated {
+            network: wallet.get_network_name().to_string(),
+            address: wallet.get_address(),
+        });
+    }
 
-    let wallet_info = format!(
-        "Network: {}\n\
-         Private Key: {}\n\
-         Public Key: {}\n\
-         Address: {}\n",
-        wallet.get_network_name(),
-        wallet.get_private_key(),
-        wallet.get_public_key(),
-        wallet.get_address()
-    );
+    let private_key = Zeroizing::new(wallet.get_private_key());
+    let wallet_info = |private_key: &str| {
+        Zeroizing::new(format!(
+            "Network: {}\n\
+             Private Key: {}\n\
+             Public Key: {}\n\
+             Address: {}\n",
+            wallet.get_network_name(),
+            private_key,
+            wallet.get_public_key(),
+            wallet.get_address()
+        ))
+    };
 
     if let Some(output_path) = output {
-        fs::write(&output_path, wallet_info)?;
+        let wallet_info = wallet_info(&private_key);
+        storage::write_atomic(Path::new(&output_path), wallet_info.as_bytes(), overwrite)?;
         println!("Wallet created and saved to {}", output_path);
     } else {
         println!("New Wallet Created:");
         println!("==================");
-        print!("{}", wallet_info);
+        let shown = ui::display_secret(&private_key, ctx.show_secrets);
+        print!("{}", wallet_info(&shown).as_str());
 
-        print!("Save this information securely. Press Enter to continue...");
-        io::stdout().flush()?;
-        let _ = io::stdin().read_line(&mut String::new());
+        if ctx.show_secrets {
+            print!("Save this information securely. Press Enter to continue...");
+            io::stdout().flush()?;
+            let _ = io::stdin().read_line(&mut String::new());
+        } else {
+            ui::warn("this key is not saved anywhere. Pass --show-secrets to print it or --output to save it.");
+        }
     }
 
     Ok(())
 }
 
-fn show_wallet_info(private_key: &str, network: &str) -> Result<(), WalletError> {
-    let secret_key_bytes = hex::decode(private_key)?;
-    let secret_key = SecretKey::from_slice(&secret_key_bytes)?;
+/// Creates an HD wallet from a new seed phrase. With --show-secrets the
+/// phrase is shown once and three of its words must be typed back (unless
+/// --yes) before the address is revealed or anything is written; without it
+/// the phrase only goes to --output.
+fn create_mnemonic_wallet(
+    ctx: &Context,
+    network: &str,
+    words: usize,
+    output: Option<PathBuf>,
+    overwrite: Overwrite,
+    insecure_plaintext: bool,
+) -> Result<(), WalletError> {
+    if insecure_plaintext && output.is_none() {
+        return Err(WalletError::InvalidParameters(
+            "--insecure-plaintext with --mnemonic needs --output".to_string(),
+        ));
+    }
+    require_shown_or_saved(ctx, output.is_some(), "The new seed phrase")?;
+    if ctx.show_secrets && !ctx.assume_yes && !io::stdin().is_terminal() {
+        return Err(WalletError::InvalidParameters(
+            "Cannot confirm the seed phrase without a terminal; pass --yes to skip the check"
+                .to_string(),
+        ));
+    }
+    let mnemonic = mnemonic::generate(words)?;
+    let wallet = KaspaWallet::from_mnemonic(&mnemonic, "", network)?;
+    // Ask for the password first so nothing can fail once the phrase is shown.
+    let password = match (&output, insecure_plaintext) {
+        (Some(_), false) => Some(new_wallet_password()?),
+        _ => None,
+    };
 
-    let wallet = KaspaWallet::with_network(secret_key, network)?;
+    if ctx.show_secrets {
+        println!("Seed Phrase:");
+        println!("==================");
+        ui::warn("write these words down, in order, and keep them offline.");
+        eprintln!("They are the only way to recover this wallet; anyone who sees them");
+        eprintln!("can spend its funds.");
+        println!();
+        for (i, word) in mnemonic.words().enumerate() {
+            let word = Zeroizing::new(word.to_string());
+            let column = format!("{:>2}. {}", i + 1, word.as_str());
+            if (i + 1) % 4 == 0 {
+                println!("{}", column);
+            } else {
+                print!("{:<16}", column);
+            }
+        }
+        println!();
 
-    println!("Wallet Information:");
+        if !ctx.assume_yes {
+            confirm_backup(&mnemonic)?;
+        }
+    }
+
+    if let Some(path) = &output {
+        let contents = match &password {
+            Some(password) => {
+                let name = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .unwrap_or("wallet");
+                Zeroizing::new(Keystore::encrypt(&wallet, name, password)?.to_json()?)
+            }
+            None => Zeroizing::new(format!("{}\n", mnemonic)),
+        };
+        storage::write_atomic(path, contents.as_bytes(), overwrite)?;
+    }
+    if let Some(sink) = &ctx.events {
+        sink.record(WalletEvent::WalletCreated {
+            network: wallet.get_network_name().to_string(),
+            address: wallet.get_address(),
+        });
+    }
+
+    println!("Wallet Created:");
     println!("==================");
     println!("Network: {}", wallet.get_network_name());
-    println!("Private Key: {}", wallet.get_private_key());
-    println!("Public Key: {}", wallet.get_public_key());
+    if let Some(path) = wallet.derivation_path() {
+        println!("Derivation Path: {}", path);
+    }
     println!("Address: {}", wallet.get_address());
-
+    match (&output, insecure_plaintext) {
+        (Some(path), true) => {
+            println!("Seed phrase written to {}", path.display());
+            ui::warn("the seed phrase is stored unencrypted.");
+        }
+        (Some(path), false) => println!("Keystore written to {}", path.display()),
+        (None, _) => {}
+    }
     Ok(())
 }
 
-fn generate_address(private_key: &str, network: &str) -> Result<(), WalletError> {
-    let secret_key_bytes = hex::decode(private_key)?;
-    let secret_key = SecretKey::from_slice(&secret_key_bytes)?;
-
-    let wallet = KaspaWallet::with_network(secret_key, network)?;
-
-    println!("Generated Address:");
-    println!("==================");
-    println!("{}", wallet.get_address());
+/// Asks for a few randomly chosen words of the phrase just shown, after
+/// clearing it from the screen.
+fn confirm_backup(mnemonic: &Mnemonic) -> Result<(), WalletError> {
+    print!("Press Enter once the words are written down...");
+    io::stdout().flush()?;
+    io::stdin().read_line(&mut String::new())?;
+    if io::stdout().is_terminal() {
+        print!("\x1b[2J\x1b[H");
+    }
 
+    let word_count = mnemonic.word_count();
+    println!("Confirm your backup:");
+    for position in mnemonic::quiz_positions(word_count, &mut rand::thread_rng()) {
+        let answer = prompt_password(&format!("Word #{} of {}: ", position, word_count))?;
+        if !mnemonic::word_matches(mnemonic, position, &answer) {
+            return Err(WalletError::InvalidParameters(format!(
+                "Word #{} does not match; nothing was saved. Run the command again",
+                position
+            )));
+        }
+    }
+    println!();
     Ok(())
 }
 
-fn create_transaction(
-    private_key: &str,
+const MANIFEST_FILE: &str = "manifest.csv";
+
+/// Writes `count` fresh wallets into `dir`, each in its own file, plus a
+/// manifest of file names, addresses and fingerprints.
+fn create_wallet_batch(
+    ctx: &Context,
     network: &str,
-    inputs: Vec<String>,
-    outputs: Vec<String>,
-    fee_rate: u64,
+    count: u32,
+    dir: &Path,
+    insecure_plaintext: bool,
 ) -> Result<(), WalletError> {
-    let secret_key_bytes = hex::decode(private_key)?;
-    let secret_key = SecretKey::from_slice(&secret_key_bytes)?;
+    let network_config = NetworkConfig::from_name(network)?;
+    fs::create_dir_all(dir)?;
+    let manifest_path = dir.join(MANIFEST_FILE);
+    if manifest_path.exists() {
+        return Err(WalletError::Storage(format!(
+            "{} already exists; choose an empty directory",
+            manifest_path.display()
+        )));
+    }
+    let password = if insecure_plaintext {
+        None
+    } else {
+        Some(new_wallet_password()?)
+    };
 
-    let wallet = KaspaWallet::with_network(secret_key, network)?;
+    let secp = secp256k1::Secp256k1::new();
+    let width = count.to_string().len();
+    let progress_step = (count / 10).max(1);
+    let mut manifest = String::from("filename,address,fingerprint\n");
+    for i in 1..=count {
+        let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let wallet = KaspaWallet::with_context(&secp, secret_key, network_config.clone());
+        let stem = format!(
+            "wallet-{:0width$}-{}",
+            i,
+            wallet.fingerprint(),
+            width = width
+        );
 
-    let parsed_inputs: Result<Vec<(String, u32)>, WalletError> = inputs
-        .iter()
-        .map(|input| {
-            let parts: Vec<&str> = input.split(':').collect();
-            if parts.len() != 2 {
-                return Err(WalletError::InvalidParameters(format!(
-                    "Invalid input format: {}",
-                    input
-                )));
-            }
-            Ok((
-                parts[0].to_string(),
-                parts[1].parse().map_err(|_| {
-                    WalletError::InvalidParameters(format!("Invalid vout in: {}", input))
-                })?,
+        let (contents, extension) = match &password {
+            Some(password) => (
+                Zeroizing::new(Keystore::encrypt(&wallet, &stem, password)?.to_json()?),
+                KEYSTORE_EXTENSION,
+            ),
+            None => (
+                Zeroizing::new(format!(
+                    "Network: {}\nPrivate Key: {}\nAddress: {}\n",
+                    wallet.get_network_name(),
+                    wallet.get_private_key(),
+                    wallet.get_address()
+                )),
+                "txt",
+            ),
+        };
+        // Refusing to overwrite keeps names collision-free even in a
+        // directory that already holds wallets.
+        let mut filename = format!("{}.{}", stem, extension);
+        let mut suffix = 2;
+        while dir.join(&filename).exists() {
+            filename = format!("{}-{}.{}", stem, suffix, extension);
+            suffix += 1;
+        }
+        storage::write_atomic(&dir.join(&filename), contents.as_bytes(), Overwrite::Refuse)?;
+
+        manifest.push_str(&format!(
+            "{},{},{}\n",
+            filename,
+            wallet.get_address(),
+            wallet.fingerprint()
+        ));
+        if let Some(sink) = &ctx.events {
+            sink.record(WalletEvent::WalletCreated {
+                network: wallet.get_network_name().to_string(),
+                address: wallet.get_address(),
+            });
+        }
+        if count >= 10 && (i % progress_step == 0 || i == count) {
+            eprintln!("Created {}/{} wallets", i, count);
+        }
+    }
+    storage::write_atomic(&manifest_path, manifest.as_bytes(), Overwrite::Refuse)?;
+
+    println!("Wallets Created:");
+    println!("==================");
+    println!("Count: {}", count);
+    println!("Directory: {}", dir.display());
+    println!("Manifest: {}", manifest_path.display());
+    if insecure_plaintext {
+        ui::warn("private keys are stored unencrypted");
+    }
+    Ok(())
+}
+
+fn restore_watch_only(
+    descriptor_path: &str,
+    output: Option<String>,
+    overwrite: Overwrite,
+) -> Result<(), WalletError> {
+    let descriptor = WalletDescriptor::from_json(&fs::read_to_string(descriptor_path)?)?;
+
+    let wallet = WatchOnlyWallet::from_descriptor(&descriptor)?;
+    let key_info = match wallet.key() {
+        WatchOnlyKey::Extended(xpub) => format!("Extended Public Key: {}\n", xpub),
+        WatchOnlyKey::Single(public_key) => {
+            format!("Public Key: {}\n", hex::encode(public_key.serialize()))
+        }
+    };
+    let path_info = wallet
+        .derivation_path()
+        .map(|path| format!("Derivation Path: {}\n", path))
+        .unwrap_or_default();
+
+    let wallet_info = format!(
+        "Network: {}\n\
+         Watch-only: true\n\
+         {}{}\
+         Address: {}\n",
+        wallet.get_network_name(),
+        key_info,
+        path_info,
+        wallet.get_address()?
+    );
+
+    if let Some(output_path) = output {
+        storage::write_atomic(Path::new(&output_path), wallet_info.as_bytes(), overwrite)?;
+        println!("Watch-only wallet restored and saved to {}", output_path);
+    } else {
+        println!("Watch-only Wallet Restored:");
+        println!("===========================");
+        print!("{}", wallet_info);
+    }
+
+    Ok(())
+}
+
+fn export_descriptor(
+    ctx: &Context,
+    key: &KeyArgs,
+    network: &str,
+    output: Option<String>,
+    overwrite: Overwrite,
+) -> Result<(), WalletError> {
+    let lockable = open_wallet(ctx, key, network)?;
+    let wallet = lockable.wallet()?;
+    let json = wallet.export_descriptor().to_json()?;
+
+    if let Some(output_path) = output {
+        storage::write_atomic(Path::new(&output_path), json.as_bytes(), overwrite)?;
+        println!("Descriptor exported to {}", output_path);
+    } else {
+        println!("{}", json);
+    }
+
+    Ok(())
+}
+
+fn show_watch_only_info(ctx: &Context, public_key: &str, network: &str) -> Result<(), WalletError> {
+    let wallet = WatchOnlyWallet::from_public_key(public_key, network)?;
+
+    println!("Wallet Information:");
+    println!("==================");
+    println!("Network: {}", wallet.get_network_name());
+    println!("Key Type: watch-only");
+    println!("Fingerprint: {}", wallet.fingerprint());
+    match wallet.key() {
+        WatchOnlyKey::Single(public_key) => {
+            println!("Public Key: {}", hex::encode(public_key.serialize()))
+        }
+        WatchOnlyKey::Extended(xpub) => {
+            println!("Account Public Key: {}", xpub);
+            let used = AddressIndex::load(&ctx.data_dir)?;
+            println!(
+                "Next Receive Index: {}",
+                used.next(&wallet.fingerprint(), RECEIVE_CHAIN)
+            );
+        }
+    }
+    println!("Address: {}", wallet.get_address()?);
+
+    Ok(())
+}
+
+/// An address alone identifies no key, so this only checks and echoes it.
+fn show_address_info(address: &str, network: &str) -> Result<(), WalletError> {
+    let network_config = NetworkConfig::from_name(network)?;
+    if !address::validate_address(address)? {
+        return Err(WalletError::InvalidAddressFormat);
+    }
+
+    println!("Wallet Information:");
+    println!("==================");
+    println!("Network: {}", network_config.name);
+    println!("Key Type: watch-only (address only)");
+    println!("Address: {}", address);
+
+    Ok(())
+}
+
+fn show_wallet_info(ctx: &Context, key: &KeyArgs, network: &str) -> Result<(), WalletError> {
+    let lockable = open_wallet(ctx, key, network)?;
+    let wallet = lockable.wallet()?;
+    if ctx.show_secrets {
+        confirm_or_cancel(ctx, "Print the private key in plain text?")?;
+    }
+
+    println!("Wallet Information:");
+    println!("==================");
+    if let (Some(name), Some(path)) = (lockable.name(), lockable.path()) {
+        println!("Name: {}", name);
+        println!("File: {}", path.display());
+    }
+    println!("Network: {}", wallet.get_network_name());
+    println!("Key Type: {}", if wallet.is_hd() { "HD" } else { "single" });
+    println!("Fingerprint: {}", wallet.fingerprint());
+    println!("Public Key: {}", wallet.get_public_key());
+    if let (Some(xpub), Some(path)) = (wallet.account_xpub(), wallet.derivation_path()) {
+        println!("Account Public Key: {}", xpub);
+        println!("Derivation Path: {}", path);
+        let used = AddressIndex::load(&ctx.data_dir)?;
+        println!(
+            "Next Receive Index: {}",
+            used.next(&wallet.fingerprint(), RECEIVE_CHAIN)
+        );
+    }
+    println!("Address: {}", wallet.get_address());
+    let private_key = Zeroizing::new(if ctx.show_secrets {
+        wallet.export_private_key()
+    } else {
+        wallet.get_private_key()
+    });
+    println!(
+        "Private Key: {}",
+        ui::display_secret(&private_key, ctx.show_secrets).as_str()
+    );
+    if let Some(xprv) = wallet.account_xprv() {
+        let xprv = Zeroizing::new(xprv.to_string());
+        println!(
+            "Account Private Key: {}",
+            ui::display_secret(&xprv, ctx.show_secrets).as_str()
+        );
+    }
+
+    Ok(())
+}
+
+/// Which addresses the `address` command prints.
+enum AddressSelection {
+    /// The wallet's main address.
+    Default,
+    Index(u32),
+    /// Hands out this many from the next unused index.
+    Next(u32),
+    AllUsed,
+}
+
+fn generate_address(
+    ctx: &Context,
+    key: &KeyArgs,
+    network: &str,
+    chain: u32,
+    selection: AddressSelection,
+) -> Result<(), WalletError> {
+    let lockable = open_wallet(ctx, key, network)?;
+    let wallet = lockable.wallet()?;
+
+    let derived = |indexes: std::ops::Range<u32>| -> Result<Vec<(u32, String)>, WalletError> {
+        indexes
+            .map(|index| {
+                let address = if chain == CHANGE_CHAIN {
+                    wallet.change_address(index)?
+                } else {
+                    wallet.receive_address(index)?
+                };
+                Ok((index, address))
+            })
+            .collect()
+    };
+
+    let addresses = match selection {
+        AddressSelection::Default => vec![(0, wallet.get_address())],
+        _ if !wallet.is_hd() => {
+            return Err(WalletError::InvalidParameters(
+                "This wallet has a single key; --index, --count, --change and --all-used need an HD wallet"
+                    .to_string(),
             ))
+        }
+        AddressSelection::Index(index) => derived(index..index + 1)?,
+        AddressSelection::Next(count) => {
+            let mut used = AddressIndex::load(&ctx.data_dir)?;
+            let indexes = used.reserve(&wallet.fingerprint(), chain, count)?;
+            let addresses = derived(indexes)?;
+            used.save()?;
+            addresses
+        }
+        AddressSelection::AllUsed => {
+            let used = AddressIndex::load(&ctx.data_dir)?;
+            derived(0..used.next(&wallet.fingerprint(), chain))?
+        }
+    };
+
+    println!("Generated Address:");
+    println!("==================");
+    if addresses.is_empty() {
+        println!("No addresses handed out yet");
+    } else if let [(_, address)] = addresses.as_slice() {
+        println!("{}", address);
+    } else {
+        for (index, address) in &addresses {
+            println!("  {:>4}  {}", index, address);
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(serde::Serialize)]
+struct AddressRow {
+    index: u32,
+    path: String,
+    address: String,
+}
+
+/// Derives `count` addresses from `start` on `chain`. Keystores are only
+/// opened when their password is in the environment: exporting never
+/// prompts to unlock.
+fn derive_address_rows(
+    ctx: &Context,
+    key: &KeyArgs,
+    descriptor: Option<&Path>,
+    network: &str,
+    chain: u32,
+    start: u32,
+    count: u32,
+) -> Result<Vec<AddressRow>, WalletError> {
+    let end = start.checked_add(count).ok_or_else(|| {
+        WalletError::InvalidParameters("--start plus --count is too large".to_string())
+    })?;
+
+    let (addresses, path) = match descriptor {
+        Some(path) => {
+            let wallet = WatchOnlyWallet::from_descriptor(&WalletDescriptor::from_json(
+                &fs::read_to_string(path)?,
+            )?)?;
+            let addresses = (start..end)
+                .map(|index| {
+                    if chain == CHANGE_CHAIN {
+                        wallet.change_address(index)
+                    } else {
+                        wallet.receive_address(index)
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            (addresses, wallet.derivation_path().cloned())
+        }
+        None => {
+            if let ResolvedKey::Keystore(_, name) = resolve_key(ctx, key)? {
+                if std::env::var_os(WALLET_PASSWORD_ENV).is_none() {
+                    return Err(WalletError::InvalidParameters(format!(
+                        "Wallet {} is locked; set {} or pass --descriptor (see export-descriptor)",
+                        name, WALLET_PASSWORD_ENV
+                    )));
+                }
+            }
+            let lockable = open_wallet(ctx, key, network)?;
+            let wallet = lockable.wallet()?;
+            let addresses = (start..end)
+                .map(|index| {
+                    if chain == CHANGE_CHAIN {
+                        wallet.change_address(index)
+                    } else {
+                        wallet.receive_address(index)
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            (addresses, wallet.derivation_path().cloned())
+        }
+    };
+
+    Ok((start..end)
+        .zip(addresses)
+        .map(|(index, address)| AddressRow {
+            index,
+            path: path
+                .as_ref()
+                .map(|path| format!("{}/{}/{}", path, chain, index))
+                .unwrap_or_default(),
+            address,
         })
-        .collect();
+        .collect())
+}
 
-    let parsed_outputs: Result<Vec<(String, u64)>, WalletError> = outputs
-        .iter()
-        .map(|output| {
-            let parts: Vec<&str> = output.split(':').collect();
-            if parts.len() != 2 {
-                return Err(WalletError::InvalidParameters(format!(
-                    "Invalid output format: {}",
-                    output
-                )));
+fn export_addresses(
+    rows: &[AddressRow],
+    format: ExportFormat,
+    output: Option<&Path>,
+    overwrite: Overwrite,
+) -> Result<(), WalletError> {
+    let text = match format {
+        ExportFormat::Csv => {
+            let mut csv = String::from("index,path,address\n");
+            for row in rows {
+                csv.push_str(&format!("{},{},{}\n", row.index, row.path, row.address));
             }
-            Ok((
-                parts[0].to_string(),
-                parts[1].parse().map_err(|_| {
-                    WalletError::InvalidParameters(format!("Invalid amount in: {}", output))
-                })?,
-            ))
+            csv
+        }
+        ExportFormat::Json => {
+            serde_json::to_string_pretty(rows)
+                .map_err(|e| WalletError::Serialization(e.to_string()))?
+                + "\n"
+        }
+    };
+
+    match output {
+        Some(path) => {
+            storage::write_atomic(path, text.as_bytes(), overwrite)?;
+            eprintln!("Wrote {} addresses to {}", rows.len(), path.display());
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+fn derive_child_mnemonic(
+    ctx: &Context,
+    key: &KeyArgs,
+    index: u32,
+    words: u32,
+) -> Result<(), WalletError> {
+    let lockable = open_wallet(ctx, key, "mainnet")?;
+    let mnemonic = lockable.wallet()?.derive_child_mnemonic(index, words)?;
+
+    println!("Child Mnemonic:");
+    println!("==================");
+    println!("Index: {}", index);
+    println!("Words: {}", words);
+    let phrase = Zeroizing::new(mnemonic.to_string());
+    println!("{}", ui::display_secret(&phrase, ctx.show_secrets).as_str());
+
+    Ok(())
+}
+
+fn parse_inputs(inputs: &[String]) -> Result<Vec<UtxoEntry>, WalletError> {
+    inputs.iter().map(|input| input.parse()).collect()
+}
+
+/// Change needs every input's amount; a plain `txid:vout` among inputs
+/// with amounts is most likely a mistake, so it is refused by name.
+fn require_amounts_if_any(inputs: &[String], entries: &[UtxoEntry]) -> Result<(), WalletError> {
+    if entries.iter().all(|entry| entry.amount.is_none()) {
+        return Ok(());
+    }
+    match inputs
+        .iter()
+        .zip(entries)
+        .find(|(_, entry)| entry.amount.is_none())
+    {
+        Some((input, _)) => Err(WalletError::InvalidParameters(format!(
+            "Input {:?} has no amount; when any input gives one, all must (txid:vout:amount)",
+            input
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// A transaction output as given on the command line.
+struct Recipient {
+    address: String,
+    amount: u64,
+    /// Label from a payment URI.
+    label: Option<String>,
+}
+
+/// `address:amount` pairs, amounts in decimal KAS or, with `sompi`, whole
+/// sompi.
+fn parse_outputs(outputs: &[String], sompi: bool) -> Result<Vec<(String, u64)>, WalletError> {
+    Ok(parse_recipients(outputs, sompi)?
+        .into_iter()
+        .map(|recipient| (recipient.address, recipient.amount))
+        .collect())
+}
+
+/// As `parse_outputs`, also accepting `kaspa:<address>?amount=..&label=..`
+/// payment URIs. An amount after the URI (`<uri>:<amount>`) must agree with
+/// the URI's own.
+fn parse_recipients(outputs: &[String], sompi: bool) -> Result<Vec<Recipient>, WalletError> {
+    outputs
+        .iter()
+        .enumerate()
+        .map(|(i, output)| {
+            let Some((address, query)) = output.split_once('?') else {
+                let (address, amount) = output.rsplit_once(':').ok_or_else(|| {
+                    WalletError::InvalidParameters(format!("Invalid output format: {}", output))
+                })?;
+                return Ok(Recipient {
+                    address: address.to_string(),
+                    amount: parse_output_amount(i, output, amount, sompi)?,
+                    label: None,
+                });
+            };
+
+            let (query, explicit) = match query.rsplit_once(':') {
+                Some((query, amount)) => {
+                    (query, Some(parse_output_amount(i, output, amount, sompi)?))
+                }
+                None => (query, None),
+            };
+            let uri: PaymentUri = format!("{}?{}", address, query).parse()?;
+            let amount = match (uri.amount.map(|amount| amount.sompi()), explicit) {
+                (Some(from_uri), Some(given)) if from_uri != given => {
+                    return Err(WalletError::InvalidParameters(format!(
+                        "Output {}: the URI asks for {} KAS but {} KAS was given",
+                        i + 1,
+                        Kas::from_sompi(from_uri),
+                        Kas::from_sompi(given)
+                    )))
+                }
+                (Some(amount), _) | (None, Some(amount)) => amount,
+                (None, None) => {
+                    return Err(WalletError::InvalidParameters(format!(
+                        "Output {}: the URI has no amount; append :<amount>",
+                        i + 1
+                    )))
+                }
+            };
+            Ok(Recipient {
+                address: uri.address,
+                amount,
+                label: uri.label,
+            })
         })
-        .collect();
+        .collect()
+}
 
-    let transaction = wallet.create_transaction(parsed_inputs?, parsed_outputs?, fee_rate)?;
+fn parse_output_amount(
+    i: usize,
+    output: &str,
+    amount: &str,
+    sompi: bool,
+) -> Result<u64, WalletError> {
+    if sompi {
+        return amount.parse::<u64>().map_err(|_| {
+            WalletError::InvalidParameters(format!(
+                "Invalid sompi amount in output {} ({}): expected a whole number",
+                i + 1,
+                output
+            ))
+        });
+    }
+    amount.parse::<Kas>().map(Kas::sompi).map_err(|e| {
+        let reason = match e {
+            WalletError::InvalidParameters(reason) => reason,
+            e => e.to_string(),
+        };
+        WalletError::InvalidParameters(format!("Output {} ({}): {}", i + 1, output, reason))
+    })
+}
 
-    println!("Transaction Created:");
+fn print_transaction(title: &str, transaction: &Transaction) -> Result<(), WalletError> {
+    println!("{}", title);
     println!("==================");
     println!("Version: {}", transaction.version);
     println!("Inputs:");
@@ -234,35 +2207,1568 @@ fn create_transaction(
     }
     println!("Outputs:");
     for (i, output) in transaction.outputs.iter().enumerate() {
-        println!("  {}: {} ({} sompi)", i, output.address, output.amount);
+        println!(
+            "  {}: {} {} KAS ({} sompi)",
+            i,
+            output.address,
+            Kas::from_sompi(output.amount),
+            output.amount
+        );
+    }
+
+    let encoded = transaction.encode()?;
+    println!("Serialized: {}", hex::encode(&encoded));
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_transaction(
+    ctx: &Context,
+    key: &KeyArgs,
+    network: &str,
+    inputs: Vec<String>,
+    recipients: Vec<Recipient>,
+    fee: FeeMode,
+    max_fee: Option<Kas>,
+    change: &Change,
+    save_contact: bool,
+) -> Result<(), WalletError> {
+    let lockable = open_wallet(ctx, key, network)?;
+    send_transaction(
+        ctx,
+        lockable.wallet()?,
+        &inputs,
+        &recipients,
+        fee,
+        max_fee,
+        change,
+    )?;
+
+    if save_contact {
+        let mut book = AddressBook::load(&ctx.data_dir)?;
+        for recipient in &recipients {
+            let Some(label) = &recipient.label else {
+                continue;
+            };
+            match book.find(network, label) {
+                Some(contact) if contact.address == recipient.address => continue,
+                _ => book.add(Contact {
+                    label: label.clone(),
+                    address: recipient.address.clone(),
+                    network: network.to_string(),
+                })?,
+            }
+            println!("Saved contact {}", label);
+        }
+        book.save()?;
+    }
+    Ok(())
+}
+
+/// Where `send` returns what the inputs leave over, once their amounts are
+/// known.
+enum Change {
+    /// The wallet's next change address, or its only address for a single
+    /// key.
+    Wallet,
+    Address(String),
+    /// No change output: the inputs must cover outputs and fee exactly.
+    Forbidden,
+}
+
+fn send_transaction(
+    ctx: &Context,
+    wallet: &KaspaWallet,
+    inputs: &[String],
+    recipients: &[Recipient],
+    fee: FeeMode,
+    max_fee: Option<Kas>,
+    change: &Change,
+) -> Result<(), WalletError> {
+    let outputs = recipients
+        .iter()
+        .map(|recipient| (recipient.address.clone(), recipient.amount))
+        .collect();
+    let entries = parse_inputs(inputs)?;
+    require_amounts_if_any(inputs, &entries)?;
+    let mut transaction = build_unsigned_transaction(entries, outputs)?;
+    let total_in = transaction
+        .inputs
+        .iter()
+        .map(|input| input.amount)
+        .sum::<Option<u64>>()
+        .filter(|_| !transaction.inputs.is_empty());
+    // Index to mark as used once signed, when change goes to an HD wallet.
+    let mut change_index = None;
+    match (total_in, change) {
+        (None, Change::Wallet) => {}
+        (None, _) => {
+            return Err(WalletError::InvalidParameters(
+                "--change-address and --no-change need input amounts (txid:vout:amount)"
+                    .to_string(),
+            ))
+        }
+        (Some(total_in), Change::Forbidden) => {
+            let needed = transaction.total_output_amount() + fee.fee(&transaction);
+            let left = total_in
+                .checked_sub(needed)
+                .ok_or(WalletError::InsufficientBalance)?;
+            if left > 1 {
+                return Err(WalletError::InvalidParameters(format!(
+                    "--no-change: the inputs exceed outputs and fee by {} KAS ({} sompi)",
+                    Kas::from_sompi(left),
+                    left
+                )));
+            }
+        }
+        (Some(total_in), change) => {
+            let address = match change {
+                Change::Address(address) => address.clone(),
+                _ if wallet.is_hd() => {
+                    let index = AddressIndex::load(&ctx.data_dir)?
+                        .next(&wallet.fingerprint(), CHANGE_CHAIN);
+                    change_index = Some(index);
+                    wallet.change_address(index)?
+                }
+                _ => wallet.get_address(),
+            };
+            // Price the fee with the change output in place. When nothing
+            // is left over, or too little to pay for its own storage mass,
+            // drop it again and leave the rest to the fee.
+            transaction.add_output(address, 0);
+            if !matches!(transaction.settle_change(total_in, fee), Ok(change) if change > 0) {
+                transaction.outputs.pop();
+                change_index = None;
+                let needed = transaction.total_output_amount() + fee.fee(&transaction);
+                if total_in < needed {
+                    return Err(WalletError::InsufficientBalance);
+                }
+            }
+        }
+    }
+    let fee_amount = transaction.fee().unwrap_or_else(|| fee.fee(&transaction));
+    check_relay_fee(&transaction, fee_amount)?;
+    print_send_summary(&transaction, recipients, fee);
+
+    let total = sent_amount(&transaction, recipients);
+    let over_cap = max_fee.is_some_and(|cap| fee_amount > cap.sompi());
+    let over_share =
+        matches!(fee, FeeMode::Absolute(_)) && fee_amount as u128 * 100 > total as u128;
+    if over_cap || over_share {
+        ui::warn(format_args!(
+            "the fee of {} KAS is {}",
+            Kas::from_sompi(fee_amount),
+            if over_cap {
+                "above --max-fee"
+            } else {
+                "more than 1% of the amount sent"
+            }
+        ));
+        confirm_or_cancel(ctx, "Pay this fee?")?;
+    }
+    confirm_or_cancel(ctx, "Proceed?")?;
+    mark_spent_addresses(ctx, wallet, &mut transaction)?;
+    wallet.sign_transaction(&mut transaction, fee)?;
+    if change_index.is_some() {
+        let mut used = AddressIndex::load(&ctx.data_dir)?;
+        used.reserve(&wallet.fingerprint(), CHANGE_CHAIN, 1)?;
+        used.save()?;
+    }
+
+    print_transaction("Transaction Created:", &transaction)
+}
+
+/// Records on each input of `tx` without a script the address it spends,
+/// when the last sync of an HD wallet saw the output, so that it is signed
+/// with that address's key.
+fn mark_spent_addresses(
+    ctx: &Context,
+    wallet: &KaspaWallet,
+    tx: &mut Transaction,
+) -> Result<(), WalletError> {
+    if !wallet.is_hd() {
+        return Ok(());
+    }
+    let addresses: Vec<String> = wallet
+        .known_addresses()?
+        .into_iter()
+        .map(|(_, _, address)| address)
+        .collect();
+    let state = SyncStore::load(&ctx.data_dir).state(&addresses);
+    for input in tx.inputs.iter_mut().filter(|input| input.script.is_none()) {
+        if let Some(utxo) = state
+            .utxos
+            .iter()
+            .find(|utxo| utxo.txid == input.txid && utxo.vout == input.vout)
+        {
+            input.set_spent_address(&utxo.address)?;
+        }
+    }
+    Ok(())
+}
+
+/// Fails when nodes would not relay `tx` for `fee`, whether it came from a
+/// rate or was given outright.
+fn check_relay_fee(tx: &Transaction, fee: u64) -> Result<(), WalletError> {
+    let minimum = tx.minimum_relay_fee();
+    if fee < minimum {
+        return Err(WalletError::InvalidParameters(format!(
+            "Fee {} KAS is below the minimum relay fee of {} KAS for mass {}",
+            Kas::from_sompi(fee),
+            Kas::from_sompi(minimum),
+            tx.mass_breakdown().total()
+        )));
+    }
+    Ok(())
+}
+
+/// Pays every row of a batch file, splitting across transactions when one
+/// would exceed the standard mass limit.
+#[allow(clippy::too_many_arguments)]
+fn send_batch(
+    ctx: &Context,
+    key: &KeyArgs,
+    network: &str,
+    inputs: &[String],
+    path: &Path,
+    allow_duplicates: bool,
+    fee_rate: u64,
+    broadcast: bool,
+    force: bool,
+) -> Result<(), WalletError> {
+    let payments = batch::parse_batch_csv(&fs::read_to_string(path)?, allow_duplicates)?;
+    let inputs = parse_inputs(inputs)?;
+    let groups =
+        batch::split_into_transactions(&payments, inputs.len(), MAXIMUM_STANDARD_TRANSACTION_MASS)?;
+    if groups.len() > 1 && !inputs.is_empty() {
+        return Err(WalletError::InvalidParameters(format!(
+            "The batch needs {} transactions but --inputs can only fund one; split the file",
+            groups.len()
+        )));
+    }
+
+    let mut transactions = groups
+        .iter()
+        .map(|group| {
+            let outputs = group
+                .iter()
+                .map(|payment| (payment.address.clone(), payment.amount.sompi()))
+                .collect();
+            build_unsigned_transaction(inputs.clone(), outputs)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let total = payments
+        .iter()
+        .map(|payment| payment.amount.sompi())
+        .sum::<u64>();
+    let fees = transactions
+        .iter()
+        .map(|tx| {
+            let fee = tx.estimate_fee(fee_rate);
+            check_relay_fee(tx, fee)?;
+            Ok(fee)
+        })
+        .sum::<Result<u64, WalletError>>()?;
+
+    println!("Batch Payment:");
+    println!("==================");
+    for payment in &payments {
+        println!(
+            "  {:>5}  {:<20}  {:>20} KAS  {}",
+            payment.line,
+            payment.label.as_deref().unwrap_or("-"),
+            payment.amount.to_string(),
+            payment.address
+        );
+    }
+    println!("Recipients: {}", payments.len());
+    println!("Total: {} KAS ({} sompi)", Kas::from_sompi(total), total);
+    println!(
+        "Estimated Fees: {} KAS ({} sompi)",
+        Kas::from_sompi(fees),
+        fees
+    );
+    println!("Transactions: {}", transactions.len());
+    println!();
+
+    confirm_or_cancel(ctx, &format!("Sign {} transaction(s)?", transactions.len()))?;
+
+    let lockable = open_wallet(ctx, key, network)?;
+    let wallet = lockable.wallet()?;
+    for tx in &mut transactions {
+        mark_spent_addresses(ctx, wallet, tx)?;
+        wallet.sign_transaction(tx, fee_rate)?;
+    }
+
+    if broadcast {
+        let client = ctx.node_client(network)?;
+        node::check_submission(client.as_ref(), network, force)?;
+        println!("Batch Broadcast:");
+        println!("==================");
+        for tx in &transactions {
+            println!("Txid: {}", client.submit_transaction(tx)?);
+        }
+    } else {
+        println!("Signed Transactions:");
+        println!("==================");
+        for tx in &transactions {
+            println!("{}", hex::encode(tx.encode()?));
+        }
+    }
+
+    Ok(())
+}
+
+/// Sum paid to the recipients, leaving out any change output.
+fn sent_amount(transaction: &Transaction, recipients: &[Recipient]) -> u64 {
+    transaction
+        .outputs
+        .iter()
+        .take(recipients.len())
+        .map(|output| output.amount)
+        .sum()
+}
+
+/// Outputs past the recipients are change. The fee is exact once every
+/// input amount is known.
+fn print_send_summary(transaction: &Transaction, recipients: &[Recipient], fee_mode: FeeMode) {
+    let total = sent_amount(transaction, recipients);
+    let fee = transaction
+        .fee()
+        .unwrap_or_else(|| fee_mode.fee(transaction));
+
+    println!("Sending:");
+    println!("==================");
+    for (output, recipient) in transaction.outputs.iter().zip(recipients) {
+        let label = recipient
+            .label
+            .as_deref()
+            .map(|label| format!(" ({})", label))
+            .unwrap_or_default();
+        println!(
+            "  {} KAS ({} sompi) to {}{}",
+            Kas::from_sompi(output.amount),
+            output.amount,
+            output.address,
+            label
+        );
+    }
+    for change in transaction.outputs.iter().skip(recipients.len()) {
+        println!(
+            "  {} KAS ({} sompi) to {} [change]",
+            Kas::from_sompi(change.amount),
+            change.amount,
+            change.address
+        );
+    }
+    println!("Total: {} KAS ({} sompi)", Kas::from_sompi(total), total);
+    let label = match fee_mode {
+        _ if transaction.fee().is_some() => "Fee",
+        FeeMode::Rate(_) => "Estimated Fee",
+        FeeMode::Absolute(_) => "Fee",
+    };
+    println!("{}: {} KAS ({} sompi)", label, Kas::from_sompi(fee), fee);
+    println!("Fee Mode: {}", fee_mode);
+    println!();
+}
+
+fn create_unsigned_transaction(
+    public_key: &str,
+    network: &str,
+    inputs: Vec<String>,
+    outputs: Vec<(String, u64)>,
+) -> Result<(), WalletError> {
+    let wallet = WatchOnlyWallet::from_public_key(public_key, network)?;
+
+    let transaction = wallet.create_unsigned_transaction(parse_inputs(&inputs)?, outputs)?;
+
+    println!("Source Address: {}", wallet.get_address()?);
+    print_transaction("Unsigned Transaction Created:", &transaction)
+}
+
+/// Builds a transaction from the watch-only source's UTXOs, as listed by
+/// the node, and writes it with those UTXOs embedded for offline signing.
+#[allow(clippy::too_many_arguments)]
+fn write_unsigned_pstx(
+    ctx: &Context,
+    public_key: &str,
+    network: &str,
+    inputs: &[String],
+    outputs: Vec<(String, u64)>,
+    fee_rate: u64,
+    path: &Path,
+    overwrite: Overwrite,
+) -> Result<(), WalletError> {
+    let wallet = WatchOnlyWallet::from_public_key(public_key, network)?;
+    let source = wallet.get_address()?;
+    let (state, _) = sync_wallet(ctx, network, std::slice::from_ref(&source))?;
+    let utxos = state.spendable();
+
+    let total_out: u64 = outputs.iter().map(|(_, amount)| amount).sum();
+    // Fee with room for a change output.
+    let fee_for = |input_count: usize| {
+        KaspaWallet::estimate_transaction_fee(input_count, outputs.len() + 1, fee_rate)
+    };
+    let entries = if inputs.is_empty() {
+        utxos.select_largest_first(total_out, fee_for)?
+    } else {
+        parse_inputs(inputs)?
+            .into_iter()
+            .map(|entry| {
+                let utxo = utxos.get(&entry.txid, entry.vout).ok_or_else(|| {
+                    WalletError::InvalidParameters(format!(
+                        "{}:{} is not a spendable output of {}",
+                        entry.txid, entry.vout, source
+                    ))
+                })?;
+                match entry.amount {
+                    Some(amount) if amount != utxo.amount => {
+                        Err(WalletError::InvalidParameters(format!(
+                            "{}:{} holds {} sompi according to the node, not {}",
+                            entry.txid, entry.vout, utxo.amount, amount
+                        )))
+                    }
+                    _ => Ok(utxo.clone()),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    let total_in: u64 = entries.iter().map(|entry| entry.amount).sum();
+    let change = total_in
+        .checked_sub(total_out + fee_for(entries.len()))
+        .ok_or(WalletError::InsufficientBalance)?;
+    let mut outputs = outputs;
+    if change > 0 {
+        outputs.push((source.clone(), change));
+    }
+
+    let transaction = wallet
+        .create_unsigned_transaction(entries.iter().map(UtxoEntry::from).collect(), outputs)?;
+    let json = PartiallySignedTransaction::new(network, transaction, entries)?.to_json()?;
+    let document = TxDocument::parse(&json)?;
+
+    // `-` pipes the file into `sign-tx`, so the summary moves to stderr.
+    if path == Path::new("-") {
+        println!("{}", json);
+        eprintln!("Source Address: {}", source);
+        write_tx_summary(&mut io::stderr(), &document)?;
+        return Ok(());
     }
+    storage::write_atomic(path, json.as_bytes(), overwrite)?;
+    println!("Source Address: {}", source);
+    write_tx_summary(&mut io::stdout(), &document)?;
+    println!(
+        "Written to {}; sign it offline with `sign-tx`",
+        path.display()
+    );
+    Ok(())
+}
+
+fn write_tx_summary(out: &mut dyn Write, document: &TxDocument) -> io::Result<()> {
+    let tx = document.transaction();
+    writeln!(out, "Offline Transaction:")?;
+    writeln!(out, "==================")?;
+    match document {
+        TxDocument::Pstx(pstx) => {
+            writeln!(out, "Network: {}", pstx.network)?;
+            writeln!(out, "Inputs:")?;
+            for entry in &pstx.utxo_entries {
+                writeln!(
+                    out,
+                    "  {}:{}  {} KAS from {}",
+                    entry.txid,
+                    entry.vout,
+                    Kas::from_sompi(entry.amount),
+                    entry.address
+                )?;
+            }
+        }
+        TxDocument::Raw(tx) => {
+            writeln!(out, "Inputs:")?;
+            for input in &tx.inputs {
+                writeln!(out, "  {}:{}", input.txid, input.vout)?;
+            }
+        }
+    }
+    writeln!(out, "Outputs:")?;
+    for output in &tx.outputs {
+        writeln!(
+            out,
+            "  {} KAS to {}",
+            Kas::from_sompi(output.amount),
+            output.address
+        )?;
+    }
+    if let Some(fee) = tx.fee() {
+        writeln!(out, "Fee: {} KAS ({} sompi)", Kas::from_sompi(fee), fee)?;
+    }
+    let signed = tx.inputs.iter().all(|input| input.signature.is_some());
+    writeln!(out, "Signed: {}", signed)
+}
+
+fn sign_tx(
+    ctx: &Context,
+    source: &TxSourceArgs,
+    key: &KeyArgs,
+    network: &str,
+    output: Option<PathBuf>,
+    overwrite: Overwrite,
+) -> Result<(), WalletError> {
+    let mut document = read_tx_document(source)?;
+    let network = match &document {
+        TxDocument::Pstx(pstx) => pstx.network.clone(),
+        TxDocument::Raw(_) => network.to_string(),
+    };
+    let lockable = open_wallet(ctx, key, &network)?;
+    let wallet = lockable.wallet()?;
+    if let TxDocument::Pstx(pstx) = &mut document {
+        let known = wallet.known_addresses()?;
+        for entry in &pstx.utxo_entries {
+            if !known
+                .iter()
+                .any(|(_, _, address)| *address == entry.address)
+            {
+                return Err(WalletError::InvalidParameters(format!(
+                    "Input {}:{} is paid to {}, not to this wallet ({})",
+                    entry.txid,
+                    entry.vout,
+                    entry.address,
+                    wallet.get_address()
+                )));
+            }
+            if let Some(input) = pstx
+                .transaction
+                .inputs
+                .iter_mut()
+                .find(|input| input.txid == entry.txid && input.vout == entry.vout)
+            {
+                input.set_spent_address(&entry.address)?;
+            }
+        }
+    }
+
+    let output = output.or_else(|| {
+        let file = source.file.as_ref()?;
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        let stem = name.strip_suffix(".json").unwrap_or(&name);
+        Some(file.with_file_name(format!("{}.signed.json", stem)))
+    });
+    // When the result goes to stdout, keep it clean for the next command.
+    if output.is_some() {
+        write_tx_summary(&mut io::stdout(), &document)?;
+    } else {
+        write_tx_summary(&mut io::stderr(), &document)?;
+    }
+    confirm_or_cancel(ctx, "Sign this transaction?")?;
 
-    let serialized = transaction.serialize()?;
-    println!("Serialized: {}", hex::encode(&serialized));
+    let signed = match &mut document {
+        TxDocument::Pstx(pstx) => {
+            // Every input amount is embedded, so the recorded fee is exact.
+            wallet.sign_transaction(&mut pstx.transaction, 0)?;
+            pstx.seal()?;
+            pstx.to_json()?
+        }
+        TxDocument::Raw(tx) => {
+            mark_spent_addresses(ctx, wallet, tx)?;
+            wallet.sign_transaction(tx, 0)?;
+            hex::encode(tx.encode()?)
+        }
+    };
+    match output {
+        Some(path) => {
+            storage::write_atomic(&path, signed.as_bytes(), overwrite)?;
+            println!("Signed transaction written to {}", path.display());
+        }
+        None => println!("{}", signed),
+    }
+    Ok(())
+}
 
+fn finalize_pstx(file: &Path, output: Option<&Path>) -> Result<(), WalletError> {
+    let tx = PartiallySignedTransaction::load(file)?.finalize()?;
+    let hex_str = hex::encode(tx.encode()?);
+    match output {
+        Some(path) => {
+            storage::write_atomic(path, hex_str.as_bytes(), Overwrite::Refuse)?;
+            println!("Transaction {} written to {}", tx.txid()?, path.display());
+        }
+        None => println!("{}", hex_str),
+    }
     Ok(())
 }
 
-fn estimate_fee(inputs: usize, outputs: usize, fee_rate: u64) -> Result<(), WalletError> {
-    let fee = KaspaWallet::estimate_transaction_fee(inputs, outputs, fee_rate);
+fn read_tx_document(source: &TxSourceArgs) -> Result<TxDocument, WalletError> {
+    let text = match (&source.hex, &source.file) {
+        (Some(hex_str), _) => hex_str.clone(),
+        (None, Some(path)) => fs::read_to_string(path)?,
+        (None, None) => {
+            if io::stdin().is_terminal() {
+                return Err(WalletError::InvalidParameters(
+                    "No transaction given: pass --hex or --file, or pipe it on stdin".to_string(),
+                ));
+            }
+            io::read_to_string(io::stdin())?
+        }
+    };
+    TxDocument::parse(&text)
+}
+
+fn decode_transaction(source: &TxSourceArgs, json: bool) -> Result<(), WalletError> {
+    let document = read_tx_document(source)?;
+    ui::emit(&ui::DecodedTransaction::new(document.transaction())?, json)
+}
+
+fn broadcast_transaction(
+    ctx: &Context,
+    source: &TxSourceArgs,
+    network: &str,
+    wait: bool,
+    timeout: u64,
+    force: bool,
+) -> Result<(), WalletError> {
+    let tx = read_tx_document(source)?.finalize()?;
+    let client = ctx.failover_client(network)?;
+    node::check_submission(&client, network, force)?;
+    if let Some(warning) = tx.lock_time_warning(client.virtual_daa_score().ok()) {
+        ui::warn(warning);
+    }
+    let txid = client.submit_transaction(&tx)?;
+    tracing::info!(network, %txid, inputs = tx.inputs.len(), "transaction submitted");
+
+    println!("Transaction Broadcast:");
+    println!("==================");
+    println!("Txid: {}", txid);
+
+    if wait {
+        let other = client.other_node();
+        let propagation =
+            node::check_propagation(&client, other.as_deref(), &txid, node::PROPAGATION_DELAY)?;
+        println!("Propagation: {}", propagation);
+        if let Some(warning) = propagation.warning(&txid) {
+            ui::warn(warning);
+        }
+        let status = node::wait_for_acceptance(
+            &client,
+            &txid,
+            Duration::from_secs(timeout),
+            Duration::from_secs(1),
+        )?;
+        if let TxStatus::Accepted {
+            accepting_block, ..
+        } = &status
+        {
+            println!("Accepting Block: {}", accepting_block);
+        }
+        println!("Status: {}", status);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "gui")]
+fn run_gui() -> Result<(), WalletError> {
+    kasparustwallet::gui::run_gui().map_err(|e| WalletError::Network(e.to_string()))
+}
+
+#[cfg(not(feature = "gui"))]
+fn run_gui() -> Result<(), WalletError> {
+    Err(WalletError::InvalidParameters(
+        "This binary was built without the GUI; rebuild with `cargo build --release --features gui`"
+            .to_string(),
+    ))
+}
+
+fn run_self_test() -> Result<(), WalletError> {
+    let report = self_test::run();
+    ui::emit(
+        &ui::CheckReport::new("Self-Test:", "All tests passed", &report),
+        false,
+    )?;
+    if report.passed() {
+        Ok(())
+    } else {
+        Err(WalletError::KeyGeneration(
+            "Self-test failed; do not trust this binary with keys".to_string(),
+        ))
+    }
+}
+
+fn convert_amounts(
+    amount: Option<String>,
+    from: Option<Unit>,
+    price: Option<f64>,
+) -> Result<(), WalletError> {
+    if price.is_some_and(|price| !price.is_finite() || price < 0.0) {
+        return Err(WalletError::InvalidParameters(
+            "--price must be a non-negative number".to_string(),
+        ));
+    }
+    let inputs = match amount {
+        Some(amount) => vec![amount],
+        None if !io::stdin().is_terminal() => io::stdin()
+            .lines()
+            .map(|line| line.map_err(WalletError::from))
+            .filter(|line| line.as_ref().map_or(true, |l| !l.trim().is_empty()))
+            .collect::<Result<_, _>>()?,
+        None => {
+            return Err(WalletError::InvalidParameters(
+                "Pass an amount, or pipe one on stdin".to_string(),
+            ))
+        }
+    };
+
+    // Parse everything first so a bad line fails before any output.
+    let amounts = inputs
+        .iter()
+        .map(|input| Kas::parse_with_unit(input, from))
+        .collect::<Result<Vec<_>, _>>()?;
+    for amount in amounts {
+        println!("KAS:   {}", amount);
+        println!("Sompi: {}", amount.sompi());
+        if let Some(price) = price {
+            let kas = amount.sompi() as f64 / SOMPI_PER_KAS as f64;
+            println!("USD:   {:.2}", kas * price);
+        }
+    }
+    Ok(())
+}
+
+fn show_history(
+    ctx: &Context,
+    address: &str,
+    limit: usize,
+    before: Option<u64>,
+    refresh: bool,
+    json: bool,
+    csv: bool,
+) -> Result<(), WalletError> {
+    if !address::validate_address(address)? {
+        return Err(WalletError::InvalidAddressFormat);
+    }
+    let explorer = Explorer::new(
+        &ctx.explorer_url()?,
+        ctx.retry_policy(history::HTTP_TIMEOUT)?,
+    )?;
+    let mut cache = HistoryCache::load(&ctx.data_dir);
+    let entries = explorer.transactions(&mut cache, address, limit, before, refresh)?;
+    if let Err(e) = cache.save() {
+        tracing::warn!("could not save history cache: {}", e);
+    }
+
+    let report = ui::HistoryReport { entries };
+    if csv {
+        print!("{}", report.csv());
+    } else {
+        ui::emit(&report, json)?;
+    }
+    if report.entries.len() == limit {
+        if let Some(last) = report.entries.last() {
+            eprintln!("More: --before {}", last.time);
+        }
+    }
+    Ok(())
+}
+
+fn monitor_address(
+    ctx: &Context,
+    address: Option<String>,
+    wallet: Option<&str>,
+    network: &str,
+    expect_amount: Option<Kas>,
+    timeout: Option<Duration>,
+    interval: Duration,
+) -> Result<MonitorOutcome, WalletError> {
+    let address = match (address, wallet) {
+        (Some(address), _) => address,
+        (None, Some(name)) => {
+            let collection = WalletCollection::open(&ctx.data_dir)?;
+            Keystore::load(&collection.resolve(name))?.address
+        }
+        (None, None) => {
+            return Err(WalletError::InvalidParameters(
+                "Pass --address or --wallet".to_string(),
+            ))
+        }
+    };
+    if !address::validate_address(&address)? {
+        return Err(WalletError::InvalidAddressFormat);
+    }
+
+    let client = ctx.node_client(network)?;
+    // Outputs arriving together share one DAA score lookup.
+    let daa_scores = DaaScoreCache::default();
+    println!("Watching {}", address);
+    let outcome = monitor::monitor_payments(
+        client.as_ref(),
+        std::slice::from_ref(&address),
+        expect_amount.map(Kas::sompi),
+        timeout,
+        interval,
+        |utxo, balance| {
+            let virtual_daa_score = daa_scores
+                .get_or_fetch(network, Instant::now(), || client.virtual_daa_score())
+                .ok();
+            let coinbase = match (utxo.is_coinbase, utxo.age(virtual_daa_score)) {
+                (false, _) => String::new(),
+                (true, Some(_)) if utxo.is_mature(virtual_daa_score) => "  coinbase".to_string(),
+                (true, Some(age)) => format!(
+                    "  coinbase, spendable in {} DAA",
+                    COINBASE_MATURITY.saturating_sub(age)
+                ),
+                (true, None) => "  coinbase, maturity unknown".to_string(),
+            };
+            println!(
+                "+{} KAS  {}:{}  balance {} KAS{}",
+                Kas::from_sompi(utxo.amount),
+                utxo.txid,
+                utxo.vout,
+                Kas::from_sompi(balance),
+                coinbase
+            );
+        },
+    )?;
+    if let MonitorOutcome::Received(received) = outcome {
+        println!("Payment received: {} KAS", Kas::from_sompi(received));
+    }
+    Ok(outcome)
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum UtxoSort {
+    /// Largest first
+    Amount,
+    /// Oldest first
+    Age,
+}
+
+struct UtxoFilter {
+    sort: UtxoSort,
+    min_amount: Option<Kas>,
+    mature_only: bool,
+}
+
+/// HD (chain, index) of an address.
+type AddressPosition = (u32, u32);
+
+/// The wallet's addresses on both chains, or just `address`, each with its
+/// HD position when known.
+fn wallet_addresses(
+    ctx: &Context,
+    key: &KeyArgs,
+    network: &str,
+    address: Option<String>,
+) -> Result<Vec<(String, Option<AddressPosition>)>, WalletError> {
+    if let Some(address) = address {
+        return Ok(vec![(address, None)]);
+    }
+    let lockable = open_wallet(ctx, key, network)?;
+    let wallet = lockable.wallet()?;
+    Ok(wallet
+        .known_addresses()?
+        .into_iter()
+        .map(|(chain, index, address)| (address, wallet.is_hd().then_some((chain, index))))
+        .collect())
+}
+
+/// Syncs the outputs of `addresses` from the node against the last sync
+/// saved in the data dir, and saves the result.
+fn sync_wallet(
+    ctx: &Context,
+    network: &str,
+    addresses: &[String],
+) -> Result<(WalletState, SyncDiff), WalletError> {
+    let client = ctx.node_client(network)?;
+    update_wallet_state(ctx, addresses, |state| state.sync(client.as_ref()))
+}
+
+/// Applies `sync` to the last saved state of `addresses`, and saves the
+/// result.
+fn update_wallet_state(
+    ctx: &Context,
+    addresses: &[String],
+    sync: impl FnOnce(&mut WalletState) -> Result<SyncDiff, WalletError>,
+) -> Result<(WalletState, SyncDiff), WalletError> {
+    let mut store = SyncStore::load(&ctx.data_dir);
+    let mut state = store.state(addresses);
+    let diff = sync(&mut state)?;
+    store.put(state.clone());
+    if let Err(e) = store.save() {
+        tracing::warn!("could not save the wallet state: {}", e);
+    }
+    Ok((state, diff))
+}
+
+/// `sync_wallet` from the configured explorer rather than the node, with
+/// the note that says so.
+#[cfg(feature = "explorer")]
+fn sync_from_explorer(
+    ctx: &Context,
+    network: &str,
+    addresses: &[String],
+) -> Result<(WalletState, SyncDiff, String), WalletError> {
+    let client = ctx.explorer_client()?;
+    // Read first, as `WalletState::sync` does.
+    let info = client.info()?;
+    explorer::check_network(&info, network)?;
+    let utxos = client.get_utxos_by_addresses(addresses)?;
+    let (state, diff) = update_wallet_state(ctx, addresses, |state| {
+        Ok(state.apply_snapshot(utxos, info.virtual_daa_score))
+    })?;
+    Ok((state, diff, client.third_party_note()))
+}
+
+#[cfg(not(feature = "explorer"))]
+fn sync_from_explorer(
+    _ctx: &Context,
+    _network: &str,
+    _addresses: &[String],
+) -> Result<(WalletState, SyncDiff, String), WalletError> {
+    Err(without_explorer())
+}
+
+/// `sync_wallet`, or `sync_from_explorer` with its note when `explorer`.
+fn sync_from(
+    ctx: &Context,
+    network: &str,
+    addresses: &[String],
+    explorer: bool,
+) -> Result<(WalletState, SyncDiff, Option<String>), WalletError> {
+    if explorer {
+        let (state, diff, note) = sync_from_explorer(ctx, network, addresses)?;
+        Ok((state, diff, Some(note)))
+    } else {
+        let (state, diff) = sync_wallet(ctx, network, addresses)?;
+        Ok((state, diff, None))
+    }
+}
+
+#[cfg(not(feature = "explorer"))]
+fn without_explorer() -> WalletError {
+    WalletError::InvalidParameters(
+        "This binary was built without the explorer client; rebuild with `cargo build --release --features explorer`"
+            .to_string(),
+    )
+}
+
+fn show_balance(
+    ctx: &Context,
+    key: &KeyArgs,
+    network: &str,
+    address: Option<String>,
+    explorer: bool,
+    json: bool,
+) -> Result<(), WalletError> {
+    let addresses: Vec<String> = wallet_addresses(ctx, key, network, address)?
+        .into_iter()
+        .map(|(address, _)| address)
+        .collect();
+    let (state, diff, third_party) = sync_from(ctx, network, &addresses, explorer)?;
+    let since_last = |sompi: u64| (!diff.first_sync).then_some(sompi);
+    let report = ui::BalanceReport {
+        balance: state.balance(),
+        balance_kas: Kas::from_sompi(state.balance()).to_string(),
+        spendable: state.spendable().balance(),
+        pending: state.pending(),
+        utxo_count: state.utxos.len(),
+        virtual_daa_score: state.virtual_daa_score(),
+        received: since_last(diff.received()),
+        spent: since_last(diff.spent()),
+        third_party,
+    };
+    ui::emit(&report, json)
+}
+
+fn list_utxos(
+    ctx: &Context,
+    key: &KeyArgs,
+    network: &str,
+    address: Option<String>,
+    filter: UtxoFilter,
+    explorer: bool,
+    json: bool,
+) -> Result<(), WalletError> {
+    let addresses = wallet_addresses(ctx, key, network, address)?;
+    let query: Vec<String> = addresses.iter().map(|(a, _)| a.clone()).collect();
+    let (state, _, third_party) = sync_from(ctx, network, &query, explorer)?;
+    if json {
+        if let Some(note) = &third_party {
+            eprintln!("{}", note);
+        }
+    }
+    let virtual_daa_score = state.virtual_daa_score();
+    let mut utxos: Vec<_> = state
+        .utxos
+        .iter()
+        .filter(|utxo| {
+            filter
+                .min_amount
+                .is_none_or(|min| utxo.amount >= min.sompi())
+        })
+        .filter(|utxo| !filter.mature_only || utxo.is_mature(virtual_daa_score))
+        .cloned()
+        .collect();
+    match filter.sort {
+        UtxoSort::Amount => utxos.sort_by_key(|utxo| std::cmp::Reverse(utxo.amount)),
+        UtxoSort::Age => utxos.sort_by_key(|utxo| utxo.block_daa_score),
+    }
+    let index_of = |address: &str| {
+        addresses
+            .iter()
+            .find(|(a, _)| a == address)
+            .and_then(|(_, position)| *position)
+    };
+
+    let report = ui::UtxoReport {
+        utxos: utxos
+            .iter()
+            .map(|utxo| ui::UtxoRow {
+                outpoint: format!("{}:{}", utxo.txid, utxo.vout),
+                address: utxo.address.clone(),
+                address_index: index_of(&utxo.address).map(|(_, index)| index),
+                change: index_of(&utxo.address).is_some_and(|(chain, _)| chain == CHANGE_CHAIN),
+                amount: utxo.amount,
+                amount_kas: Kas::from_sompi(utxo.amount).to_string(),
+                block_daa_score: utxo.block_daa_score,
+                age: utxo.age(virtual_daa_score),
+                is_coinbase: utxo.is_coinbase,
+                mature: utxo.is_mature(virtual_daa_score),
+            })
+            .collect(),
+        third_party,
+    };
+    ui::emit(&report, json)
+}
+
+/// A lone `--inputs`/`--outputs` value without a `:` is a count.
+fn parse_count(values: &[String]) -> Option<usize> {
+    match values {
+        [] => Some(0),
+        [value] if !value.contains(':') => value.parse().ok(),
+        _ => None,
+    }
+}
+
+fn estimate_fee(
+    inputs: &[String],
+    outputs: &[String],
+    sompi: bool,
+    hex: Option<&str>,
+    fee_rate: Option<u64>,
+    node_estimate: Option<FeeEstimate>,
+) -> Result<(), WalletError> {
+    let (tx, approximate) = match (hex, parse_count(inputs), parse_count(outputs)) {
+        (Some(hex_str), _, _) => (Transaction::from_hex(hex_str)?, false),
+        (None, Some(input_count), Some(output_count)) => {
+            let mut tx = Transaction::new();
+            for _ in 0..input_count {
+                tx.add_input(String::new(), 0);
+            }
+            for _ in 0..output_count {
+                tx.add_output(String::new(), 0);
+            }
+            (tx, true)
+        }
+        (None, _, _) => (
+            build_unsigned_transaction(parse_inputs(inputs)?, parse_outputs(outputs, sompi)?)?,
+            false,
+        ),
+    };
+    let breakdown = tx.mass_breakdown();
 
     println!("Estimated Fee:");
     println!("==============");
-    println!("Inputs: {}", inputs);
-    println!("Outputs: {}", outputs);
-    println!("Fee Rate: {} sompkB", fee_rate);
-    println!("Total Fee: {} sompi", fee);
+    if approximate {
+        println!("Approximation from input and output counts only");
+    }
+    println!("Inputs: {}", tx.inputs.len());
+    println!("Outputs: {}", tx.outputs.len());
+    println!("Size: {} bytes", breakdown.size);
+    println!("Compute Mass: {}", breakdown.compute_mass);
+    match breakdown.storage_mass {
+        Some(mass) => println!("Storage Mass: {}", mass),
+        None => println!("Storage Mass: unknown (input amounts not given)"),
+    }
+    println!("Mass: {}", breakdown.total());
+    let presets: Vec<_> = match node_estimate {
+        Some(estimate) => {
+            println!("Fees (node estimate):");
+            FEE_RATE_PRESETS
+                .iter()
+                .zip([estimate.low, estimate.normal, estimate.priority])
+                .map(|(&(name, _), bucket)| (name, bucket.rate, bucket.estimated_time_label()))
+                .collect()
+        }
+        None => {
+            println!("Fees:");
+            FEE_RATE_PRESETS
+                .iter()
+                .map(|&(name, rate)| (name, rate, None))
+                .collect()
+        }
+    };
+    let custom = fee_rate.map(|rate| ("custom", rate, None));
+    for (name, rate, time) in presets.into_iter().chain(custom) {
+        let fee = breakdown.fee(rate);
+        let time = time.map(|time| format!("  {}", time)).unwrap_or_default();
+        println!(
+            "  {:<9} {:>6} sompi/kg  {} KAS ({} sompi){}",
+            name,
+            rate,
+            Kas::from_sompi(fee),
+            fee,
+            time
+        );
+    }
+
+    Ok(())
+}
+
+/// The node's fee rates for `estimate-fee --from-node`. When it cannot
+/// give them, says so and leaves the built-in presets to apply.
+fn node_fee_estimate(ctx: &Context, network: &str) -> Option<FeeEstimate> {
+    match ctx
+        .node_client(network)
+        .and_then(|client| client.get_fee_estimate())
+    {
+        Ok(Some(estimate)) => Some(estimate),
+        Ok(None) => {
+            ui::warn("the node gives no fee estimate; using the built-in rates");
+            None
+        }
+        Err(e) => {
+            ui::warn(format_args!(
+                "node fee estimate unavailable ({}); using the built-in rates",
+                e
+            ));
+            None
+        }
+    }
+}
+
+/// Returns whether every address is valid.
+fn validate_addresses(
+    ctx: &Context,
+    mut addresses: Vec<String>,
+    file: Option<&Path>,
+) -> Result<bool, WalletError> {
+    if let Some(path) = file {
+        addresses.extend(
+            fs::read_to_string(path)?
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string),
+        );
+    }
+    if addresses.is_empty() {
+        return Err(WalletError::InvalidParameters(
+            "No addresses given: pass them as arguments, with --address, or with --file"
+                .to_string(),
+        ));
+    }
+
+    let report = ui::ValidationReport {
+        results: addresses
+            .into_iter()
+            .map(|address| {
+                let valid = address::validate_address(&address)?;
+                Ok(ui::AddressValidity { address, valid })
+            })
+            .collect::<Result<_, WalletError>>()?,
+    };
+    let all_valid = report.results.iter().all(|result| result.valid);
+    if !ctx.quiet {
+        ui::emit(&report, false)?;
+    }
+    Ok(all_valid)
+}
+
+fn verify_event_log(data_dir: &std::path::Path) -> Result<(), WalletError> {
+    let path = data_dir.join(EVENT_LOG_FILE);
+    let (entries, head) = EventLog::verify(&path)?;
+
+    println!("Event Log Verification:");
+    println!("======================");
+    println!("Log: {}", path.display());
+    println!("Entries: {}", entries);
+    println!("Head: {}", head);
+    println!("Intact: true");
 
     Ok(())
 }
 
-fn validate_address(address: &str) -> Result<(), WalletError> {
-    let is_valid = address::validate_address(address)?;
+fn list_wallets(ctx: &Context) -> Result<(), WalletError> {
+    let collection = WalletCollection::open(&ctx.data_dir)?;
+    let entries = collection.list()?;
 
-    println!("Address Validation:");
+    println!("Wallets:");
     println!("==================");
-    println!("Address: {}", address);
-    println!("Valid: {}", is_valid);
+    if entries.is_empty() {
+        println!("No wallets in {}", collection.wallets_dir().display());
+    }
+    for entry in entries {
+        let marker = if collection.default_wallet() == Some(entry.name.as_str()) {
+            "*"
+        } else {
+            " "
+        };
+        println!(
+            "{} {}  {}  {}  {}  {}",
+            marker, entry.name, entry.fingerprint, entry.network, entry.kind, entry.address
+        );
+    }
+
+    Ok(())
+}
 
+fn use_wallet(ctx: &Context, name: &str) -> Result<(), WalletError> {
+    let mut collection = WalletCollection::open(&ctx.data_dir)?;
+    collection.set_default(name)?;
+    println!("Default wallet set to {}", name);
     Ok(())
 }
+
+fn check_wallet(ctx: &Context, key: &KeyArgs, network: &str) -> Result<(), WalletError> {
+    let (lockable, password) = unlock_wallet(ctx, key, network)?;
+    let report = lockable.self_check(password.as_deref().map(String::as_str))?;
+    ui::emit(
+        &ui::CheckReport::new("Wallet Self-Check:", "All checks passed", &report),
+        false,
+    )?;
+    if report.passed() {
+        Ok(())
+    } else {
+        Err(WalletError::Storage(
+            "Wallet self-check failed; do not use this wallet to send funds".to_string(),
+        ))
+    }
+}
+
+/// Hidden prompt on the terminal. Fails with a hint instead of hanging when
+/// there is no terminal to ask on.
+fn prompt_password(prompt: &str) -> Result<Zeroizing<String>, WalletError> {
+    rpassword::prompt_password(prompt)
+        .map(Zeroizing::new)
+        .map_err(|e| {
+            WalletError::InvalidParameters(format!(
+                "Cannot prompt for a password ({}); set {} when running without a terminal",
+                e, WALLET_PASSWORD_ENV
+            ))
+        })
+}
+
+fn prompt_new_password() -> Result<Zeroizing<String>, WalletError> {
+    let password = prompt_password("New password: ")?;
+    let confirm = prompt_password("Repeat password: ")?;
+    if *password != *confirm {
+        return Err(WalletError::InvalidParameters(
+            "Passwords do not match".to_string(),
+        ));
+    }
+    Ok(password)
+}
+
+/// Password for a new keystore: KASPA_WALLET_PASSWORD, or asked twice.
+fn new_wallet_password() -> Result<Zeroizing<String>, WalletError> {
+    match std::env::var(WALLET_PASSWORD_ENV) {
+        Ok(password) => Ok(Zeroizing::new(password)),
+        Err(_) => prompt_new_password(),
+    }
+}
+
+fn create_keystore_wallet(ctx: &Context, name: &str, network: &str) -> Result<(), WalletError> {
+    let collection = WalletCollection::open(&ctx.data_dir)?;
+    if collection.path_for(name).exists() {
+        return Err(WalletError::Storage(format!(
+            "A wallet named {} already exists",
+            name
+        )));
+    }
+
+    let password = new_wallet_password()?;
+    let mnemonic = mnemonic::generate(24)?;
+    let wallet = KaspaWallet::from_mnemonic(&mnemonic, "", network)?;
+    let path = collection.add(&Keystore::encrypt(&wallet, name, &password)?)?;
+    if let Some(sink) = &ctx.events {
+        sink.record(WalletEvent::WalletCreated {
+            network: wallet.get_network_name().to_string(),
+            address: wallet.get_address(),
+        });
+    }
+
+    println!("Wallet Created:");
+    println!("==================");
+    println!("Name: {}", name);
+    println!("File: {}", path.display());
+    println!("Network: {}", wallet.get_network_name());
+    println!("Address: {}", wallet.get_address());
+    println!();
+    println!("Seed phrase (write it down; it is the only way to recover this wallet):");
+    let phrase = Zeroizing::new(mnemonic.to_string());
+    println!("{}", ui::display_secret(&phrase, ctx.show_secrets).as_str());
+
+    Ok(())
+}
+
+fn show_keystore_info(ctx: &Context, key: &KeyArgs, network: &str) -> Result<(), WalletError> {
+    show_wallet_info(ctx, key, network)
+}
+
+fn export_keystore_key(ctx: &Context, wallet: Option<&str>) -> Result<(), WalletError> {
+    if !ctx.show_secrets {
+        return Err(WalletError::InvalidParameters(
+            "Exporting a key prints it in plain text; pass --show-secrets".to_string(),
+        ));
+    }
+    let collection = WalletCollection::open(&ctx.data_dir)?;
+    let name = wallet
+        .or(collection.default_wallet())
+        .ok_or_else(|| {
+            WalletError::InvalidParameters(
+                "No wallet given: pass --wallet or select a default with `wallet use`".to_string(),
+            )
+        })?
+        .to_string();
+
+    let mut lockable = collection.open_wallet(&name)?;
+    // Deliberately not taken from the cache or the environment.
+    let password = prompt_password(&format!("Password for wallet {}: ", name))?;
+    lockable.unlock(&password)?;
+    if let Some(sink) = &ctx.events {
+        lockable.wallet_mut()?.set_event_sink(sink.clone());
+    }
+    let wallet = lockable.wallet()?;
+    confirm_or_cancel(ctx, "Print the private key in plain text?")?;
+
+    println!(
+        "Private Key: {}",
+        Zeroizing::new(wallet.export_private_key()).as_str()
+    );
+    if let Some(xprv) = wallet.account_xprv() {
+        println!(
+            "Account Private Key: {}",
+            Zeroizing::new(xprv.to_string()).as_str()
+        );
+    }
+
+    Ok(())
+}
+
+fn export_mnemonic_backup(output: &str, overwrite: Overwrite) -> Result<(), WalletError> {
+    let phrase = prompt_password("Seed phrase (input hidden): ")?;
+    let mnemonic = Mnemonic::parse_normalized(phrase.trim())
+        .map_err(|e| WalletError::InvalidParameters(format!("Invalid seed phrase: {}", e)))?;
+
+    let password = prompt_new_password()?;
+    let encrypted = backup::export_encrypted_mnemonic(&mnemonic, &password)?;
+    storage::write_atomic(Path::new(output), &encrypted, overwrite)?;
+
+    println!("Encrypted seed backup written to {}", output);
+    Ok(())
+}
+
+fn generate_mnemonic(
+    ctx: &Context,
+    words: usize,
+    language: &str,
+    output: Option<PathBuf>,
+    overwrite: Overwrite,
+) -> Result<(), WalletError> {
+    mnemonic::language(language)?;
+    require_shown_or_saved(ctx, output.is_some(), "The new seed phrase")?;
+    let mnemonic = mnemonic::generate(words)?;
+    let phrase = Zeroizing::new(format!("{}\n", mnemonic));
+
+    ui::warn("this phrase is the only backup of every key derived from it.");
+    eprintln!("Anyone who sees it can spend your funds. Write it down offline and");
+    eprintln!("never store it in plain text, photos, or cloud notes.");
+
+    if let Some(path) = output {
+        storage::write_atomic(&path, phrase.as_bytes(), overwrite)?;
+        println!("Seed phrase written to {}", path.display());
+    } else {
+        println!();
+        print!("{}", phrase.as_str());
+    }
+
+    Ok(())
+}
+
+/// Reads a seed phrase one hidden word at a time, re-asking for words that
+/// are not in the word list.
+fn prompt_mnemonic() -> Result<Mnemonic, WalletError> {
+    print!("Number of words [24]: ");
+    io::stdout().flush()?;
+    let mut count = String::new();
+    io::stdin().read_line(&mut count)?;
+    let count: usize = match count.trim() {
+        "" => 24,
+        count => count
+            .parse()
+            .ok()
+            .filter(|count| [12, 15, 18, 21, 24].contains(count))
+            .ok_or_else(|| {
+                WalletError::InvalidParameters(format!("Invalid word count: {}", count))
+            })?,
+    };
+
+    let mut words = Vec::with_capacity(count);
+    while words.len() < count {
+        let word = prompt_password(&format!("Word {}/{}: ", words.len() + 1, count))?;
+        match mnemonic::check_word(&word) {
+            Ok(()) => words.push(word.trim().to_lowercase()),
+            Err(e) => eprintln!("  {}; try again", e),
+        }
+    }
+
+    let mnemonic = mnemonic::from_words(&words);
+    words.iter_mut().for_each(|word| word.zeroize());
+    mnemonic
+}
+
+/// Asks a yes/no question; only an explicit yes counts. `--yes` answers for
+/// the user, and without a terminal to ask on the answer is an error.
+fn confirm(ctx: &Context, prompt: &str) -> Result<bool, WalletError> {
+    if ctx.assume_yes {
+        return Ok(true);
+    }
+    if !io::stdin().is_terminal() {
+        return Err(WalletError::InvalidParameters(format!(
+            "{} (stdin is not a terminal; pass --yes to confirm)",
+            prompt
+        )));
+    }
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// As `confirm`, failing the command unless the answer is yes.
+fn confirm_or_cancel(ctx: &Context, prompt: &str) -> Result<(), WalletError> {
+    if confirm(ctx, prompt)? {
+        Ok(())
+    } else {
+        Err(WalletError::InvalidParameters("Cancelled".to_string()))
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ScanSource {
+    /// The node, which only sees addresses still holding coins
+    Node,
+    /// The configured explorer, which sees every address ever used
+    Explorer,
+}
+
+fn restore_wallet(
+    ctx: &Context,
+    encrypted_backup: Option<&str>,
+    network: &str,
+    name: &str,
+    scan: Option<ScanSource>,
+) -> Result<(), WalletError> {
+    let mnemonic = match encrypted_backup {
+        Some(path) => {
+            let data = fs::read(path)?;
+            let password = prompt_password("Backup password: ")?;
+            backup::import_encrypted_mnemonic(&data, &password)?
+        }
+        None => prompt_mnemonic()?,
+    };
+    let passphrase = prompt_password("BIP39 passphrase (leave empty for none): ")?;
+
+    let wallet = KaspaWallet::from_mnemonic(&mnemonic, &passphrase, network)?;
+
+    println!("Wallet Restored:");
+    println!("==================");
+    println!("Network: {}", wallet.get_network_name());
+    if let Some(path) = wallet.derivation_path() {
+        println!("Derivation Path: {}", path);
+    }
+    println!("First Receive Address: {}", wallet.receive_address(0)?);
+
+    if !confirm(ctx, "Is this the address you expected? Save the wallet?")? {
+        println!("Nothing written.");
+        return Ok(());
+    }
+
+    let password = new_wallet_password()?;
+    let keystore = Keystore::encrypt(&wallet, name, &password)?;
+    let path = WalletCollection::open(&ctx.data_dir)?.add(&keystore)?;
+    println!("Encrypted wallet {} written to {}", name, path.display());
+
+    if let Some(source) = scan {
+        scan_used_addresses(ctx, &wallet, network, source)?;
+    }
+    Ok(())
+}
+
+/// Finds `wallet`'s used addresses and moves its address index past them,
+/// so new addresses are not ones already handed out.
+fn scan_used_addresses(
+    ctx: &Context,
+    wallet: &KaspaWallet,
+    network: &str,
+    source: ScanSource,
+) -> Result<(), WalletError> {
+    println!(
+        "Scanning for used addresses (gap limit {})...",
+        wallet.gap_limit()
+    );
+    let found = match source {
+        ScanSource::Node => discovery::discover(wallet, ctx.node_client(network)?.as_ref())?,
+        ScanSource::Explorer => discover_with_explorer(ctx, wallet, network)?,
+    };
+    for (chain, used) in [("receive", &found.receive), ("change", &found.change)] {
+        for (index, address) in used {
+            println!("  {:<7} {:>4}  {}", chain, index, address);
+        }
+    }
+    if found == Discovery::default() {
+        println!("No used addresses found.");
+        return Ok(());
+    }
+
+    let fingerprint = wallet.fingerprint();
+    let mut index = AddressIndex::load(&ctx.data_dir)?;
+    for chain in [RECEIVE_CHAIN, CHANGE_CHAIN] {
+        let next = found.next(chain);
+        let handed_out = index.next(&fingerprint, chain);
+        if next > handed_out {
+            index.reserve(&fingerprint, chain, next - handed_out)?;
+        }
+    }
+    index.save()?;
+    println!(
+        "Next receive index {}, next change index {}",
+        index.next(&fingerprint, RECEIVE_CHAIN),
+        index.next(&fingerprint, CHANGE_CHAIN)
+    );
+    Ok(())
+}
+
+#[cfg(feature = "explorer")]
+fn discover_with_explorer(
+    ctx: &Context,
+    wallet: &KaspaWallet,
+    network: &str,
+) -> Result<Discovery, WalletError> {
+    let client = ctx.explorer_client()?;
+    explorer::check_network(&client.info()?, network)?;
+    println!("{}", ui::yellow(client.third_party_note()));
+    discovery::discover(wallet, &client)
+}
+
+#[cfg(not(feature = "explorer"))]
+fn discover_with_explorer(
+    _ctx: &Context,
+    _wallet: &KaspaWallet,
+    _network: &str,
+) -> Result<Discovery, WalletError> {
+    Err(without_explorer())
+}
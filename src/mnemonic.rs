@@ -0,0 +1,171 @@
+use crate::error::{WalletError, WalletResult};
+use bip39::{Language, Mnemonic};
+
+/// Word counts offered for new seed phrases.
+pub const SUPPORTED_WORD_COUNTS: [usize; 2] = [12, 24];
+
+/// Fresh English seed phrase with `word_count` words.
+pub fn generate(word_count: usize) -> WalletResult<Mnemonic> {
+    if !SUPPORTED_WORD_COUNTS.contains(&word_count) {
+        return Err(WalletError::InvalidParameters(format!(
+            "Seed phrases must have 12 or 24 words, not {}",
+            word_count
+        )));
+    }
+    Mnemonic::generate_in(Language::English, word_count)
+        .map_err(|e| WalletError::KeyGeneration(format!("Mnemonic generation error: {}", e)))
+}
+
+/// Wordlist language from a CLI code; only English is built in.
+pub fn language(code: &str) -> WalletResult<Language> {
+    match code.to_lowercase().as_str() {
+        "en" | "english" => Ok(Language::English),
+        _ => Err(WalletError::InvalidParameters(format!(
+            "Unsupported seed phrase language: {} (only en is available)",
+            code
+        ))),
+    }
+}
+
+/// Checks one word against the wordlist. The error never repeats the word,
+/// since it is usually typed at a hidden prompt.
+pub fn check_word(word: &str) -> WalletResult<()> {
+    let word = word.trim().to_lowercase();
+    if Language::English.find_word(&word).is_some() {
+        return Ok(());
+    }
+    let similar = Language::English
+        .words_by_prefix(&word[..word.len().min(3)])
+        .len();
+    Err(WalletError::InvalidParameters(if similar > 0 {
+        format!(
+            "Not in the word list ({} words share its first letters)",
+            similar
+        )
+    } else {
+        "Not in the word list".to_string()
+    }))
+}
+
+/// Assembles a phrase from individually entered words, verifying each word
+/// and the checksum.
+pub fn from_words(words: &[String]) -> WalletResult<Mnemonic> {
+    for (i, word) in words.iter().enumerate() {
+        check_word(word).map_err(|e| match e {
+            WalletError::InvalidParameters(reason) => {
+                WalletError::InvalidParameters(format!("Word {}: {}", i + 1, reason))
+            }
+            e => e,
+        })?;
+    }
+    let phrase = zeroize::Zeroizing::new(
+        words
+            .iter()
+            .map(|word| word.trim().to_lowercase())
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+    Mnemonic::parse_in_normalized(Language::English, &phrase).map_err(|e| match e {
+        bip39::Error::InvalidChecksum => WalletError::InvalidParameters(
+            "Checksum mismatch: at least one word is wrong or out of order".to_string(),
+        ),
+        e => WalletError::InvalidParameters(format!("Invalid seed phrase: {}", e)),
+    })
+}
+
+/// Whether `text` reads as a seed phrase: several words, most of them from
+/// the word list. Used to catch a phrase pasted where a key belongs.
+pub fn looks_like_phrase(text: &str) -> bool {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| word.to_lowercase())
+        .collect();
+    let known = words
+        .iter()
+        .filter(|word| Language::English.find_word(word).is_some())
+        .count();
+    words.len() >= 2 && known * 2 >= words.len()
+}
+
+/// Number of words asked back when confirming a freshly shown phrase.
+pub const QUIZ_WORDS: usize = 3;
+
+/// `QUIZ_WORDS` distinct, randomly chosen word positions (1-based, in
+/// ascending order) to ask back after showing a phrase of `word_count` words.
+pub fn quiz_positions<R: rand::Rng + ?Sized>(word_count: usize, rng: &mut R) -> Vec<usize> {
+    let mut positions: Vec<usize> =
+        rand::seq::index::sample(rng, word_count, QUIZ_WORDS.min(word_count))
+            .into_iter()
+            .map(|i| i + 1)
+            .collect();
+    positions.sort_unstable();
+    positions
+}
+
+/// Whether `answer` is the word at 1-based `position` of `mnemonic`.
+pub fn word_matches(mnemonic: &Mnemonic, position: usize, answer: &str) -> bool {
+    mnemonic
+        .words()
+        .nth(position.wrapping_sub(1))
+        .is_some_and(|word| word == answer.trim().to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(mnemonic: &Mnemonic) -> Vec<String> {
+        mnemonic.words().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_generate_and_reassemble() {
+        let mnemonic = generate(12).unwrap();
+        assert_eq!(from_words(&words(&mnemonic)).unwrap(), mnemonic);
+        assert!(generate(15).is_err());
+        assert!(language("en").is_ok());
+        assert!(language("fr").is_err());
+    }
+
+    #[test]
+    fn test_wrong_word_caught() {
+        let mut phrase: Vec<String> = vec!["abandon".to_string(); 11];
+        phrase.push("about".to_string());
+        assert!(from_words(&phrase).is_ok());
+
+        phrase[4] = "abandoon".to_string();
+        let err = from_words(&phrase).unwrap_err().to_string();
+        assert!(err.contains("Word 5"), "{}", err);
+
+        // A valid but wrong word is only caught by the checksum.
+        phrase[4] = "ability".to_string();
+        let err = from_words(&phrase).unwrap_err().to_string();
+        assert!(err.contains("Checksum"), "{}", err);
+    }
+
+    #[test]
+    fn test_looks_like_phrase() {
+        assert!(looks_like_phrase(&generate(12).unwrap().to_string()));
+        assert!(looks_like_phrase("  Abandon ability ABOUT\n"));
+        assert!(!looks_like_phrase("abandon"));
+        assert!(!looks_like_phrase(&"ab".repeat(32)));
+        assert!(!looks_like_phrase("not really a phrase at all"));
+    }
+
+    #[test]
+    fn test_quiz_positions() {
+        let mut rng = rand::thread_rng();
+        for word_count in SUPPORTED_WORD_COUNTS {
+            let positions = quiz_positions(word_count, &mut rng);
+            assert_eq!(positions.len(), QUIZ_WORDS);
+            assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+            assert!(positions.iter().all(|p| (1..=word_count).contains(p)));
+        }
+
+        let mnemonic = generate(12).unwrap();
+        let third = mnemonic.words().nth(2).unwrap().to_uppercase();
+        assert!(word_matches(&mnemonic, 3, &format!(" {} ", third)));
+        assert!(!word_matches(&mnemonic, 0, &third));
+        assert!(!word_matches(&mnemonic, 13, &third));
+    }
+}
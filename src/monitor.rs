@@ -0,0 +1,303 @@
+use crate::error::{WalletError, WalletResult};
+use crate::node::{NodeClient, UtxoChange};
+use crate::utxo::{Utxo, UtxoSet};
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How a monitor run ended, with the total received while watching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonitorOutcome {
+    Received(u64),
+    TimedOut(u64),
+}
+
+/// Tracks an address set's UTXOs and which of them arrived since watching
+/// began. Outputs already present at the start don't count as received.
+pub struct PaymentMonitor {
+    utxos: UtxoSet,
+    received: u64,
+}
+
+impl PaymentMonitor {
+    pub fn new(initial: Vec<Utxo>) -> Self {
+        Self {
+            utxos: initial.into_iter().collect(),
+            received: 0,
+        }
+    }
+
+    /// Applies a full snapshot, returning the outputs not seen before.
+    pub fn update(&mut self, snapshot: Vec<Utxo>) -> Vec<Utxo> {
+        let previous = std::mem::replace(&mut self.utxos, UtxoSet::new());
+        let mut incoming = Vec::new();
+        for utxo in snapshot {
+            if previous.get(&utxo.txid, utxo.vout).is_none() {
+                incoming.push(utxo.clone());
+            }
+            self.utxos.insert(utxo);
+        }
+        self.received += incoming.iter().map(|utxo| utxo.amount).sum::<u64>();
+        incoming
+    }
+
+    /// Applies a notification, returning the outputs it added.
+    pub fn apply(&mut self, change: UtxoChange) -> Vec<Utxo> {
+        for (txid, vout) in &change.removed {
+            self.utxos.remove(txid, *vout);
+        }
+        let mut incoming = Vec::new();
+        for utxo in change.added {
+            if self.utxos.get(&utxo.txid, utxo.vout).is_none() {
+                self.received += utxo.amount;
+                incoming.push(utxo.clone());
+            }
+            self.utxos.insert(utxo);
+        }
+        incoming
+    }
+
+    pub fn balance(&self) -> u64 {
+        self.utxos.balance()
+    }
+
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+}
+
+/// Watches `addresses` until `expect` sompi has arrived or `timeout`
+/// passes, calling `on_payment` with each new output and the balance after
+/// it. Uses node notifications when available, else polls every
+/// `poll_interval`. Without `expect` or `timeout` it runs until an error.
+pub fn monitor_payments(
+    client: &dyn NodeClient,
+    addresses: &[String],
+    expect: Option<u64>,
+    timeout: Option<Duration>,
+    poll_interval: Duration,
+    mut on_payment: impl FnMut(&Utxo, u64),
+) -> WalletResult<MonitorOutcome> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    // Subscribing first means a payment landing in between shows up in
+    // both, and `apply` counts it once.
+    let subscription = client.subscribe_utxo_changes(addresses)?;
+    let mut monitor = PaymentMonitor::new(client.get_utxos_by_addresses(addresses)?);
+    tracing::info!(
+        addresses = addresses.len(),
+        notifications = subscription.is_some(),
+        "monitoring payments"
+    );
+
+    loop {
+        if expect.is_some_and(|expect| monitor.received() >= expect) {
+            return Ok(MonitorOutcome::Received(monitor.received()));
+        }
+        let wait = match deadline {
+            Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                Some(remaining) => remaining.min(poll_interval),
+                None => return Ok(MonitorOutcome::TimedOut(monitor.received())),
+            },
+            None => poll_interval,
+        };
+
+        let incoming = match &subscription {
+            Some(changes) => match changes.recv_timeout(wait) {
+                Ok(change) => monitor.apply(change),
+                Err(RecvTimeoutError::Timeout) => Vec::new(),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(WalletError::Network(
+                        "Lost the node's UTXO notifications and could not subscribe again"
+                            .to_string(),
+                    ))
+                }
+            },
+            None => {
+                thread::sleep(wait);
+                monitor.update(client.get_utxos_by_addresses(addresses)?)
+            }
+        };
+
+        let mut balance = monitor.balance() - incoming.iter().map(|u| u.amount).sum::<u64>();
+        for utxo in &incoming {
+            balance += utxo.amount;
+            on_payment(utxo, balance);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::{NodeInfo, TxStatus, UtxoChanges};
+    use crate::transaction::Transaction;
+    use std::sync::Mutex;
+
+    fn utxo(txid: &str, amount: u64) -> Utxo {
+        Utxo {
+            txid: txid.to_string(),
+            vout: 0,
+            address: "kaspa:abc".to_string(),
+            amount,
+            block_daa_score: 0,
+            is_coinbase: false,
+        }
+    }
+
+    /// Returns the scripted snapshots in turn, repeating the last one.
+    struct PollingNode {
+        snapshots: Mutex<Vec<Vec<Utxo>>>,
+    }
+
+    impl NodeClient for PollingNode {
+        fn get_info(&self) -> WalletResult<NodeInfo> {
+            Err(WalletError::Network("not scripted".to_string()))
+        }
+
+        fn submit_transaction(&self, tx: &Transaction) -> WalletResult<String> {
+            tx.txid()
+        }
+
+        fn get_transaction_status(&self, _txid: &str) -> WalletResult<TxStatus> {
+            Ok(TxStatus::NotFound)
+        }
+
+        fn get_utxos_by_addresses(&self, _addresses: &[String]) -> WalletResult<Vec<Utxo>> {
+            let mut snapshots = self.snapshots.lock().unwrap();
+            Ok(if snapshots.len() > 1 {
+                snapshots.remove(0)
+            } else {
+                snapshots[0].clone()
+            })
+        }
+
+        fn virtual_daa_score(&self) -> WalletResult<u64> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn test_waits_for_expected_amount() {
+        let node = PollingNode {
+            snapshots: Mutex::new(vec![
+                vec![utxo("aa", 500)],
+                vec![utxo("aa", 500), utxo("bb", 300)],
+                vec![utxo("bb", 300), utxo("cc", 800)],
+            ]),
+        };
+        let mut seen = Vec::new();
+        let outcome = monitor_payments(
+            &node,
+            &["kaspa:abc".to_string()],
+            Some(1_000),
+            Some(Duration::from_secs(5)),
+            Duration::from_millis(1),
+            |utxo, balance| seen.push((utxo.txid.clone(), balance)),
+        )
+        .unwrap();
+
+        assert_eq!(outcome, MonitorOutcome::Received(1_100));
+        assert_eq!(
+            seen,
+            vec![("bb".to_string(), 800), ("cc".to_string(), 1_100)]
+        );
+    }
+
+    #[test]
+    fn test_times_out() {
+        let node = PollingNode {
+            snapshots: Mutex::new(vec![vec![utxo("aa", 500)]]),
+        };
+        let outcome = monitor_payments(
+            &node,
+            &["kaspa:abc".to_string()],
+            Some(1),
+            Some(Duration::from_millis(20)),
+            Duration::from_millis(5),
+            |_, _| {},
+        )
+        .unwrap();
+        assert_eq!(outcome, MonitorOutcome::TimedOut(0));
+    }
+
+    /// Notifies the scripted changes, then stays quiet.
+    struct StreamingNode {
+        initial: Vec<Utxo>,
+        changes: Mutex<Vec<UtxoChange>>,
+        /// Kept open, as a live subscription is.
+        sender: Mutex<Option<std::sync::mpsc::SyncSender<UtxoChange>>>,
+    }
+
+    impl NodeClient for StreamingNode {
+        fn get_info(&self) -> WalletResult<NodeInfo> {
+            Err(WalletError::Network("not scripted".to_string()))
+        }
+
+        fn submit_transaction(&self, tx: &Transaction) -> WalletResult<String> {
+            tx.txid()
+        }
+
+        fn get_transaction_status(&self, _txid: &str) -> WalletResult<TxStatus> {
+            Ok(TxStatus::NotFound)
+        }
+
+        fn get_utxos_by_addresses(&self, _addresses: &[String]) -> WalletResult<Vec<Utxo>> {
+            Ok(self.initial.clone())
+        }
+
+        fn virtual_daa_score(&self) -> WalletResult<u64> {
+            Ok(0)
+        }
+
+        fn subscribe_utxo_changes(
+            &self,
+            _addresses: &[String],
+        ) -> WalletResult<Option<UtxoChanges>> {
+            let (sender, changes) = UtxoChanges::channel();
+            for change in self.changes.lock().unwrap().drain(..) {
+                sender.send(change).unwrap();
+            }
+            *self.sender.lock().unwrap() = Some(sender);
+            Ok(Some(changes))
+        }
+    }
+
+    #[test]
+    fn test_notified_payments_count_once() {
+        let node = StreamingNode {
+            initial: vec![utxo("aa", 500)],
+            changes: Mutex::new(vec![
+                // Already in the snapshot taken after subscribing.
+                UtxoChange {
+                    added: vec![utxo("aa", 500)],
+                    removed: Vec::new(),
+                },
+                UtxoChange {
+                    added: vec![utxo("bb", 300)],
+                    removed: vec![("aa".to_string(), 0)],
+                },
+                UtxoChange {
+                    added: vec![utxo("cc", 800)],
+                    removed: Vec::new(),
+                },
+            ]),
+            sender: Mutex::new(None),
+        };
+        let mut seen = Vec::new();
+        let outcome = monitor_payments(
+            &node,
+            &["kaspa:abc".to_string()],
+            Some(1_000),
+            Some(Duration::from_secs(5)),
+            Duration::from_secs(60),
+            |utxo, balance| seen.push((utxo.txid.clone(), balance)),
+        )
+        .unwrap();
+
+        assert_eq!(outcome, MonitorOutcome::Received(1_100));
+        assert_eq!(
+            seen,
+            vec![("bb".to_string(), 300), ("cc".to_string(), 1_100)]
+        );
+    }
+}
@@ -1,4 +1,5 @@
-use crate::error::WalletResult;
+use crate::address::validate_address;
+use crate::error::{WalletError, WalletResult};
 
 #[derive(Debug, Clone)]
 pub struct NetworkConfig {
@@ -19,7 +20,7 @@ impl NetworkConfig {
     pub fn testnet10() -> Self {
         Self {
             name: "testnet-10".to_string(),
-            prefix: "kaspa".to_string(),
+            prefix: "kaspatest".to_string(),
             rpc_url: "127.0.0.1:16210".to_string(),
         }
     }
@@ -27,7 +28,7 @@ impl NetworkConfig {
     pub fn testnet11() -> Self {
         Self {
             name: "testnet-11".to_string(),
-            prefix: "kaspa".to_string(),
+            prefix: "kaspatest".to_string(),
             rpc_url: "127.0.0.1:16310".to_string(),
         }
     }
@@ -35,11 +36,21 @@ impl NetworkConfig {
     pub fn simnet() -> Self {
         Self {
             name: "simnet".to_string(),
-            prefix: "kaspa".to_string(),
+            prefix: "kaspasim".to_string(),
             rpc_url: "127.0.0.1:16410".to_string(),
         }
     }
 
+    /// Every network, mainnet first.
+    pub fn all() -> [Self; 4] {
+        [
+            Self::mainnet(),
+            Self::testnet10(),
+            Self::testnet11(),
+            Self::simnet(),
+        ]
+    }
+
     pub fn from_name(name: &str) -> WalletResult<Self> {
         match name.trim() {
             "mainnet" => Ok(Self::mainnet()),
@@ -47,14 +58,83 @@ impl NetworkConfig {
             "testnet-11" => Ok(Self::testnet11()),
             "testnet" => Ok(Self::testnet11()),
             "simnet" => Ok(Self::simnet()),
-            _ => Err(crate::error::WalletError::Network(format!(
-                "Unknown network: {}",
-                name
-            ))),
+            _ => Err(WalletError::Network(format!("Unknown network: {}", name))),
         }
     }
 
     pub fn get_prefix(&self) -> &str {
         &self.prefix
     }
+
+    /// Checks that `address` is well formed and uses this network's prefix.
+    pub fn check_address(&self, address: &str) -> WalletResult<()> {
+        if !validate_address(address)? {
+            return Err(WalletError::InvalidAddressFormat);
+        }
+        match address.split_once(':') {
+            Some((prefix, _)) if prefix == self.prefix => Ok(()),
+            _ => Err(WalletError::InvalidParameters(format!(
+                "{} is not a {} address (expected the {}: prefix)",
+                address, self.name, self.prefix
+            ))),
+        }
+    }
+}
+
+/// The first of `networks` whose prefix `address` carries, provided it is
+/// well formed. Networks sharing a prefix cannot be told apart, so the
+/// earlier one wins.
+pub fn detect_network<'a>(
+    address: &str,
+    networks: &'a [NetworkConfig],
+) -> Option<&'a NetworkConfig> {
+    if !validate_address(address).ok()? {
+        return None;
+    }
+    let (prefix, _) = address.split_once(':')?;
+    networks.iter().find(|network| network.prefix == prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_address() {
+        let address = "kaspa:1C6Rc3w25VHud3dLDamutaqfKWqhrLRTaD";
+        let mainnet = NetworkConfig::mainnet();
+        assert!(mainnet.check_address(address).is_ok());
+        assert!(matches!(
+            mainnet.check_address("kaspa:1C6Rc3w25VHud3dLDamutaqfKWqhrLRTaE"),
+            Err(WalletError::InvalidAddressFormat)
+        ));
+
+        let testnet = NetworkConfig::testnet10();
+        assert!(matches!(
+            testnet.check_address(address),
+            Err(WalletError::InvalidParameters(_))
+        ));
+        let other = address.replacen("kaspa:", "kaspatest:", 1);
+        assert!(testnet.check_address(&other).is_ok());
+        assert!(NetworkConfig::simnet().check_address(&other).is_err());
+    }
+
+    #[test]
+    fn test_detect_network() {
+        let address = "kaspa:1C6Rc3w25VHud3dLDamutaqfKWqhrLRTaD";
+        let networks = NetworkConfig::all();
+        assert_eq!(detect_network(address, &networks).unwrap().name, "mainnet");
+        assert!(detect_network("kaspa:1C6Rc3w25VHud3dLDamutaqfKWqhrLRTaE", &networks).is_none());
+
+        // Both testnets use kaspatest; the first listed wins.
+        let other = address.replacen("kaspa:", "kaspatest:", 1);
+        assert_eq!(
+            detect_network(&other, &networks).unwrap().name,
+            "testnet-10"
+        );
+        let other = address.replacen("kaspa:", "kaspasim:", 1);
+        assert_eq!(detect_network(&other, &networks).unwrap().name, "simnet");
+        let other = address.replacen("kaspa:", "kaspadev:", 1);
+        assert!(detect_network(&other, &networks).is_none());
+    }
 }
@@ -0,0 +1,1594 @@
+use crate::error::{WalletError, WalletResult};
+use crate::network::NetworkConfig;
+use crate::transaction::{Transaction, TXID_LEN};
+use crate::utxo::Utxo;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How long to wait for a TCP connection to the node.
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Retries after a failed node request when neither flag nor config says.
+pub const DEFAULT_RETRIES: u32 = 2;
+/// Wait before the first retry; each further retry doubles it.
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// UTXO changes a subscription holds for a slow reader; past that it stops
+/// reading from the node until the reader catches up.
+pub const UTXO_CHANGE_BUFFER: usize = 64;
+/// How far a node's virtual may trail the clock before broadcasting
+/// through it earns a warning. Its past median time trails by well under
+/// a minute on a node that keeps up.
+pub const MAX_NODE_LAG: Duration = Duration::from_secs(10 * 60);
+/// How long after a broadcast the mempool is checked for the transaction:
+/// long enough for a relaying node to have passed it on.
+pub const PROPAGATION_DELAY: Duration = Duration::from_secs(3);
+
+/// Why a node refused a transaction. The node's own message travels
+/// alongside, in `WalletError::Rejected`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RejectReason {
+    /// Spends outputs the node does not know about (yet).
+    Orphan,
+    AlreadyInMempool,
+    /// Already in a block the virtual chain accepted.
+    AlreadyAccepted,
+    MassTooHigh,
+    FeeTooLow,
+    InvalidSignature,
+    /// Breaks a consensus or standardness rule other than the above.
+    Invalid,
+    Other,
+}
+
+impl RejectReason {
+    /// Classifies a node's rejection message.
+    pub fn from_message(message: &str) -> Self {
+        let lower = message.to_lowercase();
+        if lower.contains("orphan") {
+            RejectReason::Orphan
+        } else if lower.contains("already in the mempool") || lower.contains("already exists") {
+            RejectReason::AlreadyInMempool
+        } else if lower.contains("already accepted") {
+            RejectReason::AlreadyAccepted
+        } else if lower.contains("mass") {
+            RejectReason::MassTooHigh
+        } else if lower.contains("fee") {
+            RejectReason::FeeTooLow
+        } else if lower.contains("signature") {
+            RejectReason::InvalidSignature
+        } else if lower.contains("invalid") {
+            RejectReason::Invalid
+        } else {
+            RejectReason::Other
+        }
+    }
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RejectReason::Orphan => write!(f, "orphan transaction (inputs unknown to the node)"),
+            RejectReason::AlreadyInMempool => write!(f, "transaction is already in the mempool"),
+            RejectReason::AlreadyAccepted => write!(f, "transaction was already accepted"),
+            RejectReason::MassTooHigh => write!(f, "transaction mass exceeds the limit"),
+            RejectReason::FeeTooLow => write!(f, "fee too low"),
+            RejectReason::InvalidSignature => write!(f, "invalid signature"),
+            RejectReason::Invalid => write!(f, "invalid transaction"),
+            RejectReason::Other => write!(f, "refused"),
+        }
+    }
+}
+
+/// The error for a node refusing a transaction with `message`.
+pub fn rejected(message: &str) -> WalletError {
+    WalletError::Rejected {
+        reason: RejectReason::from_message(message),
+        message: message.to_string(),
+    }
+}
+
+/// Why the node could not be used at all, as opposed to an error it
+/// returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeFailure {
+    /// Nothing listens at the address.
+    ConnectionRefused(String),
+    Tls(String),
+    /// Still syncing: a transaction sent through it would look like an
+    /// orphan, its inputs being in blocks it does not have yet.
+    NotSynced {
+        headers: u64,
+        blocks: u64,
+    },
+    WrongNetwork {
+        expected: String,
+        actual: String,
+    },
+}
+
+impl fmt::Display for NodeFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeFailure::ConnectionRefused(address) => write!(
+                f,
+                "connection refused by {}; is the node running and listening for RPC?",
+                address
+            ),
+            NodeFailure::Tls(reason) => write!(f, "TLS failure: {}", reason),
+            NodeFailure::NotSynced { headers, blocks } => write!(
+                f,
+                "the node is not synced yet ({} blocks of {} headers); wait for it to catch up, \
+                 use another node or force the broadcast",
+                blocks, headers
+            ),
+            NodeFailure::WrongNetwork { expected, actual } => write!(
+                f,
+                "the node is on {} but {} was requested",
+                actual, expected
+            ),
+        }
+    }
+}
+
+/// What a node reports about itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeInfo {
+    pub server_version: String,
+    pub network: String,
+    pub is_synced: bool,
+    /// Headers and blocks the node has; headers run ahead while it syncs.
+    pub header_count: u64,
+    pub block_count: u64,
+    /// Milliseconds since the epoch: the past median time of the node's
+    /// virtual block, or 0 when it did not say.
+    pub past_median_time: u64,
+    pub virtual_daa_score: u64,
+    pub mempool_size: u64,
+}
+
+impl NodeInfo {
+    /// How far the node's virtual DAA score trails `now`, going by its past
+    /// median time. None when the node did not say or is not behind.
+    pub fn lag(&self, now: SystemTime) -> Option<Duration> {
+        if self.past_median_time == 0 {
+            return None;
+        }
+        let virtual_time = UNIX_EPOCH + Duration::from_millis(self.past_median_time);
+        now.duration_since(virtual_time).ok()
+    }
+}
+
+/// How long a node's fee estimate is reused before asking again.
+pub const FEE_ESTIMATE_TTL: Duration = Duration::from_secs(30);
+/// How long a node's virtual DAA score is reused before asking again.
+pub const DAA_SCORE_TTL: Duration = Duration::from_secs(1);
+
+/// Fee rates a node suggests, matching the low/normal/priority presets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeEstimate {
+    pub low: FeeBucket,
+    pub normal: FeeBucket,
+    pub priority: FeeBucket,
+}
+
+impl FeeEstimate {
+    /// The rates in the order of `FEE_RATE_PRESETS`.
+    pub fn rates(&self) -> [u64; 3] {
+        [self.low.rate, self.normal.rate, self.priority.rate]
+    }
+}
+
+/// One of a node's fee estimate buckets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeBucket {
+    /// Sompi per 1000 mass, like every fee rate in the wallet.
+    pub rate: u64,
+    /// How soon the node expects a transaction paying `rate` to be
+    /// included, when it says.
+    pub estimated_time: Option<Duration>,
+}
+
+impl FeeBucket {
+    /// A bucket from a node's figures: sompi per gram (one unit of mass),
+    /// rounded up, and seconds until inclusion, which are left out unless
+    /// positive.
+    pub fn from_node(sompi_per_gram: f64, estimated_seconds: f64) -> Self {
+        Self {
+            rate: (sompi_per_gram * 1000.0).ceil() as u64,
+            estimated_time: (estimated_seconds.is_finite() && estimated_seconds > 0.0)
+                .then(|| Duration::from_secs_f64(estimated_seconds)),
+        }
+    }
+
+    /// The estimated time as `~10s`, `~4m` or `~2h`, when there is one.
+    pub fn estimated_time_label(&self) -> Option<String> {
+        let seconds = self.estimated_time?.as_secs_f64().ceil() as u64;
+        Some(match seconds {
+            0..=59 => format!("~{}s", seconds),
+            60..=3599 => format!("~{}m", seconds.div_ceil(60)),
+            _ => format!("~{}h", seconds.div_ceil(3600)),
+        })
+    }
+}
+
+/// A node's answers by node URL, each reused for `ttl` after it was
+/// fetched. Only answers are kept, so a failed request is tried again next
+/// time.
+#[derive(Debug)]
+pub struct NodeCache<T> {
+    ttl: Duration,
+    answers: Mutex<HashMap<String, (Instant, T)>>,
+}
+
+/// Fee estimates, which change slowly.
+pub type FeeEstimateCache = NodeCache<Option<FeeEstimate>>;
+/// Virtual DAA scores, for ages and maturity; they move ten times a
+/// second, so are only kept long enough to serve a burst of lookups.
+pub type DaaScoreCache = NodeCache<u64>;
+
+impl Default for FeeEstimateCache {
+    fn default() -> Self {
+        Self::new(FEE_ESTIMATE_TTL)
+    }
+}
+
+impl Default for DaaScoreCache {
+    fn default() -> Self {
+        Self::new(DAA_SCORE_TTL)
+    }
+}
+
+impl<T: Clone> NodeCache<T> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            answers: Default::default(),
+        }
+    }
+
+    /// The answer for `url` fetched less than `ttl` before `now`, or
+    /// `fetch`'s answer, which is then kept.
+    pub fn get_or_fetch(
+        &self,
+        url: &str,
+        now: Instant,
+        fetch: impl FnOnce() -> WalletResult<T>,
+    ) -> WalletResult<T> {
+        let fresh = |fetched_at: &Instant| now.saturating_duration_since(*fetched_at) < self.ttl;
+        if let Some((_, answer)) = self
+            .answers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(url)
+            .filter(|(fetched_at, _)| fresh(fetched_at))
+        {
+            return Ok(answer.clone());
+        }
+        // Not locked while fetching: a slow node must not hold up other URLs.
+        let answer = fetch()?;
+        self.answers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(url.to_string(), (now, answer.clone()));
+        Ok(answer)
+    }
+}
+
+/// Where a submitted transaction is, as far as the node knows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxStatus {
+    /// In the mempool, waiting for a block to accept it.
+    Pending,
+    /// Accepted by `accepting_block` on the virtual chain, with
+    /// `blue_score_delta` blue blocks on top of it so far: its
+    /// confirmations.
+    Accepted {
+        accepting_block: String,
+        blue_score_delta: u64,
+    },
+    /// Never seen, or dropped before a block accepted it. A transaction
+    /// seen accepted stays `Accepted` after the node prunes it.
+    NotFound,
+}
+
+impl fmt::Display for TxStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxStatus::Pending => write!(f, "pending"),
+            TxStatus::Accepted {
+                blue_score_delta: 1,
+                ..
+            } => write!(f, "accepted, 1 confirmation"),
+            TxStatus::Accepted {
+                blue_score_delta, ..
+            } => write!(f, "accepted, {} confirmations", blue_score_delta),
+            TxStatus::NotFound => write!(f, "not found"),
+        }
+    }
+}
+
+/// Connection to a Kaspa node.
+pub trait NodeClient: Send + Sync {
+    /// Version, network and sync state of the node.
+    fn get_info(&self) -> WalletResult<NodeInfo>;
+
+    /// Submits `tx`, returning the txid the node accepted it under.
+    /// Refusals are reported as `WalletError::Rejected`.
+    fn submit_transaction(&self, tx: &Transaction) -> WalletResult<String>;
+
+    /// Whether `txid` is pending, accepted and how deep, or unknown to the
+    /// node. Acceptance is only followed for transactions submitted from
+    /// this process.
+    fn get_transaction_status(&self, txid: &str) -> WalletResult<TxStatus>;
+
+    /// Unspent outputs paying to any of `addresses`.
+    fn get_utxos_by_addresses(&self, addresses: &[String]) -> WalletResult<Vec<Utxo>>;
+
+    /// DAA score of the node's virtual block, used to age outputs and
+    /// check lock times. Callers asking repeatedly should go through a
+    /// `DaaScoreCache`.
+    fn virtual_daa_score(&self) -> WalletResult<u64>;
+
+    /// Streams UTXO changes for `addresses`, or `None` when the transport
+    /// has no notifications and callers should poll instead. The stream
+    /// subscribes again by itself after the connection breaks.
+    fn subscribe_utxo_changes(&self, _addresses: &[String]) -> WalletResult<Option<UtxoChanges>> {
+        Ok(None)
+    }
+
+    /// Fee rates the node suggests, or `None` when it offers none and the
+    /// static presets apply. Callers asking repeatedly should go through a
+    /// `FeeEstimateCache`.
+    fn get_fee_estimate(&self) -> WalletResult<Option<FeeEstimate>> {
+        Ok(None)
+    }
+}
+
+/// A UTXO change notification.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UtxoChange {
+    pub added: Vec<Utxo>,
+    /// Outpoints spent, as (txid, vout).
+    pub removed: Vec<(String, u32)>,
+}
+
+impl UtxoChange {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// The changes a subscription delivers, in the order the node sent them.
+/// At most [`UTXO_CHANGE_BUFFER`] wait unread. Ends when the subscription
+/// broke and could not be renewed; dropping it unsubscribes.
+pub struct UtxoChanges {
+    receiver: Receiver<UtxoChange>,
+    closed: Arc<AtomicBool>,
+}
+
+impl UtxoChanges {
+    /// A stream fed by `sender`'s changes, for transports and tests that
+    /// produce them some other way.
+    pub fn channel() -> (mpsc::SyncSender<UtxoChange>, Self) {
+        let (sender, receiver) = mpsc::sync_channel(UTXO_CHANGE_BUFFER);
+        let changes = Self {
+            receiver,
+            closed: Arc::new(AtomicBool::new(false)),
+        };
+        (sender, changes)
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<UtxoChange, RecvTimeoutError> {
+        self.receiver.recv_timeout(timeout)
+    }
+}
+
+impl Iterator for UtxoChanges {
+    type Item = UtxoChange;
+
+    /// Blocks for the next change; `None` once the stream ended.
+    fn next(&mut self) -> Option<UtxoChange> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl Drop for UtxoChanges {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Per-request timeout and retry budget for node and explorer requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub timeout: Duration,
+    pub retries: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: CONNECT_TIMEOUT,
+            retries: DEFAULT_RETRIES,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry `attempt` (1-based): 0.5s, 1s, 2s, ... up to 30s.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        RETRY_BACKOFF
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(MAX_BACKOFF)
+    }
+
+    /// Runs `request`, retrying timeouts and connection failures with
+    /// exponential backoff. `request` is given the attempt number, from 0.
+    pub fn run<T>(&self, mut request: impl FnMut(u32) -> WalletResult<T>) -> WalletResult<T> {
+        let mut attempt = 0;
+        loop {
+            match request(attempt) {
+                Err(e) if is_transient(&e) && attempt < self.retries => {
+                    attempt += 1;
+                    let delay = self.backoff(attempt);
+                    tracing::debug!(attempt, ?delay, "retrying after: {}", e);
+                    thread::sleep(delay);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// The error for an I/O failure of `operation` on `endpoint`.
+    pub fn io_error(&self, operation: &str, endpoint: &str, e: io::Error) -> WalletError {
+        match e.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => WalletError::Timeout {
+                operation: operation.to_string(),
+                endpoint: endpoint.to_string(),
+                secs: self.timeout.as_secs(),
+            },
+            io::ErrorKind::ConnectionRefused => {
+                WalletError::Node(NodeFailure::ConnectionRefused(endpoint.to_string()))
+            }
+            _ => WalletError::Network(format!("{} on {}: {}", operation, endpoint, e)),
+        }
+    }
+}
+
+/// Whether a failed request may succeed if simply sent again.
+fn is_transient(error: &WalletError) -> bool {
+    matches!(
+        error,
+        WalletError::Network(_)
+            | WalletError::Timeout { .. }
+            | WalletError::Node(NodeFailure::ConnectionRefused(_))
+    )
+}
+
+/// The id a node gave a transaction it refused only because it already
+/// has it, as its message names it.
+fn already_known(error: &WalletError) -> Option<String> {
+    let WalletError::Rejected {
+        reason: RejectReason::AlreadyInMempool | RejectReason::AlreadyAccepted,
+        message,
+    } = error
+    else {
+        return None;
+    };
+    let txid = message
+        .split(|c: char| !c.is_ascii_hexdigit())
+        .find(|word| word.len() == TXID_LEN * 2)?
+        .to_lowercase();
+    tracing::debug!(%txid, "the node already has it");
+    Some(txid)
+}
+
+/// Applies a `RetryPolicy` to every request of another client.
+pub struct RetryingClient {
+    inner: Box<dyn NodeClient>,
+    policy: RetryPolicy,
+}
+
+impl RetryingClient {
+    pub fn new(inner: Box<dyn NodeClient>, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+}
+
+impl NodeClient for RetryingClient {
+    fn get_info(&self) -> WalletResult<NodeInfo> {
+        self.policy.run(|_| self.inner.get_info())
+    }
+
+    /// A retry sends the transaction again. When the node took it before
+    /// its reply was lost, it refuses it as one it already has and names
+    /// it; that id is the answer.
+    fn submit_transaction(&self, tx: &Transaction) -> WalletResult<String> {
+        self.policy
+            .run(|attempt| match self.inner.submit_transaction(tx) {
+                Err(e) if attempt > 0 => already_known(&e).ok_or(e),
+                result => result,
+            })
+    }
+
+    fn get_transaction_status(&self, txid: &str) -> WalletResult<TxStatus> {
+        self.policy.run(|_| self.inner.get_transaction_status(txid))
+    }
+
+    fn get_utxos_by_addresses(&self, addresses: &[String]) -> WalletResult<Vec<Utxo>> {
+        self.policy
+            .run(|_| self.inner.get_utxos_by_addresses(addresses))
+    }
+
+    fn virtual_daa_score(&self) -> WalletResult<u64> {
+        self.policy.run(|_| self.inner.virtual_daa_score())
+    }
+
+    fn subscribe_utxo_changes(&self, addresses: &[String]) -> WalletResult<Option<UtxoChanges>> {
+        self.policy
+            .run(|_| self.inner.subscribe_utxo_changes(addresses))
+    }
+
+    fn get_fee_estimate(&self) -> WalletResult<Option<FeeEstimate>> {
+        self.policy.run(|_| self.inner.get_fee_estimate())
+    }
+}
+
+/// The protocol a node endpoint speaks, chosen by the URL's scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    /// kaspad's gRPC interface; also the default without a scheme.
+    Grpc,
+    /// The JSON flavour of kaspad's WebSocket RPC.
+    Wrpc,
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transport::Grpc => write!(f, "gRPC"),
+            Transport::Wrpc => write!(f, "wRPC"),
+        }
+    }
+}
+
+/// A node address: `host:port`, optionally behind a `grpc://`,
+/// `wrpc://`, `ws://` or (TLS) `grpcs://`, `wrpcs://`, `wss://`,
+/// `https://` scheme.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    pub address: String,
+    pub tls: bool,
+    pub transport: Transport,
+}
+
+impl Endpoint {
+    pub fn parse(url: &str) -> WalletResult<Self> {
+        let url = url.trim().trim_end_matches('/');
+        let (tls, transport, address) = match url.split_once("://") {
+            None => (false, Transport::Grpc, url),
+            Some(("grpc" | "http", address)) => (false, Transport::Grpc, address),
+            Some(("grpcs" | "https", address)) => (true, Transport::Grpc, address),
+            Some(("wrpc" | "ws", address)) => (false, Transport::Wrpc, address),
+            Some(("wrpcs" | "wss", address)) => (true, Transport::Wrpc, address),
+            Some((scheme, _)) => {
+                return Err(WalletError::InvalidParameters(format!(
+                    "Unsupported node URL scheme {}:// in {}",
+                    scheme, url
+                )))
+            }
+        };
+        if address
+            .rsplit_once(':')
+            .is_none_or(|(host, port)| host.is_empty() || port.parse::<u16>().is_err())
+        {
+            return Err(WalletError::InvalidParameters(format!(
+                "Node URL {} needs a host and port",
+                url
+            )));
+        }
+        Ok(Self {
+            address: address.to_string(),
+            tls,
+            transport,
+        })
+    }
+
+    /// Opens a TCP connection, telling a refused or timed out connection
+    /// apart from other failures.
+    pub(crate) fn open(&self, policy: &RetryPolicy) -> WalletResult<TcpStream> {
+        let addresses = self
+            .address
+            .to_socket_addrs()
+            .map_err(|e| WalletError::Network(format!("Cannot resolve {}: {}", self.address, e)))?;
+        let mut last_error = None;
+        for address in addresses {
+            match TcpStream::connect_timeout(&address, policy.timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(match last_error {
+            Some(e) => policy.io_error("connect", &self.address, e),
+            None => WalletError::Network(format!("{} resolves to no address", self.address)),
+        })
+    }
+}
+
+/// Client for the node at `url`, retrying requests as `policy` allows.
+pub fn connect(url: &str, policy: RetryPolicy) -> WalletResult<Box<dyn NodeClient>> {
+    tracing::debug!(url, "connecting to node");
+    let endpoint = Endpoint::parse(url)?;
+    if endpoint.tls {
+        return Err(WalletError::Node(NodeFailure::Tls(format!(
+            "{} needs TLS, which this build cannot negotiate; use a plain grpc:// endpoint",
+            url
+        ))));
+    }
+    let client = open_client(&endpoint, policy)?;
+    Ok(Box::new(RetryingClient::new(client, policy)))
+}
+
+/// A client for `endpoint` over its transport. This build has none yet:
+/// the gRPC and wRPC clients wait on tonic and a maintained WebSocket
+/// crate, so once the node is reached this fails.
+fn open_client(endpoint: &Endpoint, policy: RetryPolicy) -> WalletResult<Box<dyn NodeClient>> {
+    policy.run(|_| endpoint.open(&policy))?;
+    Err(WalletError::Network(format!(
+        "Reached {} but this build has no {} transport yet",
+        endpoint.address, endpoint.transport
+    )))
+}
+
+/// The node that last answered, tried first by later connections in this
+/// process. Kept here rather than in a client so that each command of a
+/// REPL session, which connects afresh, still starts with it.
+static LAST_GOOD_NODE: Mutex<Option<String>> = Mutex::new(None);
+
+fn last_good_node() -> std::sync::MutexGuard<'static, Option<String>> {
+    LAST_GOOD_NODE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Opens a client for a node URL.
+type Connector = Box<dyn Fn(&str) -> WalletResult<Box<dyn NodeClient>> + Send + Sync>;
+
+/// A client for the first usable node of several, moving on to the next
+/// when one stops answering. Every node must be on the expected network
+/// before it is used.
+pub struct FailoverClient {
+    urls: Vec<String>,
+    network: String,
+    connector: Connector,
+    /// Index into `urls` of the node in use, and its client.
+    current: Mutex<(usize, Arc<dyn NodeClient>)>,
+}
+
+impl FailoverClient {
+    /// Connects to the first of `urls` that answers and is on `network`,
+    /// starting with the last good node when it is among them.
+    pub fn connect(urls: &[String], network: &str, policy: RetryPolicy) -> WalletResult<Self> {
+        Self::with_connector(
+            urls,
+            network,
+            Box::new(move |url: &str| connect(url, policy)),
+        )
+    }
+
+    fn with_connector(urls: &[String], network: &str, connector: Connector) -> WalletResult<Self> {
+        if urls.is_empty() {
+            return Err(WalletError::InvalidParameters(
+                "No node URL configured".to_string(),
+            ));
+        }
+        let start = last_good_node()
+            .as_ref()
+            .and_then(|good| urls.iter().position(|url| url == good))
+            .unwrap_or(0);
+        let order: Vec<usize> = (0..urls.len()).map(|i| (start + i) % urls.len()).collect();
+        let current = open_first(urls, network, &connector, &order)?;
+        Ok(Self {
+            urls: urls.to_vec(),
+            network: network.to_string(),
+            connector,
+            current: Mutex::new(current),
+        })
+    }
+
+    /// URL of the node in use.
+    pub fn url(&self) -> String {
+        self.urls[self.current().0].clone()
+    }
+
+    /// A client for the first node after the one in use that answers on
+    /// the network, to see whether a transaction got that far; `None` with
+    /// a single node or when no other one is usable. The node in use stays
+    /// the same.
+    pub fn other_node(&self) -> Option<Box<dyn NodeClient>> {
+        let index = self.current().0;
+        (1..self.urls.len())
+            .map(|i| &self.urls[(index + i) % self.urls.len()])
+            .find_map(|url| {
+                let opened = (self.connector)(url).and_then(|client| {
+                    check_network(&client.get_info()?, &self.network)?;
+                    Ok(client)
+                });
+                opened
+                    .inspect_err(|e| tracing::debug!(url, "not asking this node: {}", e))
+                    .ok()
+            })
+    }
+
+    fn current(&self) -> (usize, Arc<dyn NodeClient>) {
+        let current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        (current.0, current.1.clone())
+    }
+
+    /// Moves from the node at `failed` to the next usable one after it.
+    fn fail_over(&self, failed: usize) -> WalletResult<Arc<dyn NodeClient>> {
+        let order: Vec<usize> = (1..self.urls.len())
+            .map(|i| (failed + i) % self.urls.len())
+            .collect();
+        let (index, client) = open_first(&self.urls, &self.network, &self.connector, &order)?;
+        *self.current.lock().unwrap_or_else(|e| e.into_inner()) = (index, client.clone());
+        Ok(client)
+    }
+
+    /// Runs a read on the node in use, and once more on the next node if
+    /// that one fails. Reads are safe to repeat.
+    fn read<T>(&self, request: impl Fn(&dyn NodeClient) -> WalletResult<T>) -> WalletResult<T> {
+        let (index, client) = self.current();
+        match request(client.as_ref()) {
+            Err(e) if is_transient(&e) && self.urls.len() > 1 => {
+                tracing::warn!(url = %self.urls[index], "node failed: {}", e);
+                match self.fail_over(index) {
+                    Ok(next) => request(next.as_ref()),
+                    Err(_) => Err(e),
+                }
+            }
+            result => result,
+        }
+    }
+}
+
+/// Connects to the first of `urls`, taken in `order`, that is on `network`.
+fn open_first(
+    urls: &[String],
+    network: &str,
+    connector: &Connector,
+    order: &[usize],
+) -> WalletResult<(usize, Arc<dyn NodeClient>)> {
+    let mut last_error = None;
+    for (tried, &index) in order.iter().enumerate() {
+        let url = &urls[index];
+        let opened = connector(url).and_then(|client| {
+            check_network(&client.get_info()?, network)?;
+            Ok(client)
+        });
+        match opened {
+            Ok(client) => {
+                tracing::debug!(url, "using node");
+                *last_good_node() = Some(url.clone());
+                return Ok((index, Arc::from(client)));
+            }
+            // The last one's error is the caller's to report.
+            Err(e) if tried + 1 == order.len() => last_error = Some(e),
+            Err(e) => tracing::warn!(url, "skipping node: {}", e),
+        }
+    }
+    Err(last_error.expect("order names at least one node"))
+}
+
+impl NodeClient for FailoverClient {
+    fn get_info(&self) -> WalletResult<NodeInfo> {
+        self.read(|client| client.get_info())
+    }
+
+    /// When the node fails mid-submission, the transaction goes to the
+    /// next node. Should that one have it already, from the failed node,
+    /// it refuses it as known and names it, and that id is the answer.
+    /// Sending it again is safe either way: it is the same transaction and
+    /// cannot be paid twice.
+    fn submit_transaction(&self, tx: &Transaction) -> WalletResult<String> {
+        let (index, client) = self.current();
+        match client.submit_transaction(tx) {
+            Err(e) if is_transient(&e) && self.urls.len() > 1 => {
+                tracing::warn!(url = %self.urls[index], "node failed during submission: {}", e);
+                let next = match self.fail_over(index) {
+                    Ok(next) => next,
+                    Err(_) => return Err(e),
+                };
+                next.submit_transaction(tx)
+                    .or_else(|e| already_known(&e).ok_or(e))
+            }
+            result => result,
+        }
+    }
+
+    fn get_transaction_status(&self, txid: &str) -> WalletResult<TxStatus> {
+        self.read(|client| client.get_transaction_status(txid))
+    }
+
+    fn get_utxos_by_addresses(&self, addresses: &[String]) -> WalletResult<Vec<Utxo>> {
+        self.read(|client| client.get_utxos_by_addresses(addresses))
+    }
+
+    fn virtual_daa_score(&self) -> WalletResult<u64> {
+        self.read(|client| client.virtual_daa_score())
+    }
+
+    fn subscribe_utxo_changes(&self, addresses: &[String]) -> WalletResult<Option<UtxoChanges>> {
+        self.read(|client| client.subscribe_utxo_changes(addresses))
+    }
+
+    fn get_fee_estimate(&self) -> WalletResult<Option<FeeEstimate>> {
+        self.read(|client| client.get_fee_estimate())
+    }
+}
+
+/// Time to open a TCP connection to the node at `url`.
+pub fn ping(url: &str, timeout: Duration) -> WalletResult<Duration> {
+    let endpoint = Endpoint::parse(url)?;
+    let policy = RetryPolicy {
+        timeout,
+        retries: 0,
+    };
+    let start = Instant::now();
+    endpoint.open(&policy)?;
+    Ok(start.elapsed())
+}
+
+/// Fails unless `info` is from a node on `expected`.
+pub fn check_network(info: &NodeInfo, expected: &str) -> WalletResult<()> {
+    let expected = NetworkConfig::from_name(expected)?.name;
+    let actual = NetworkConfig::from_name(&info.network)
+        .map(|config| config.name)
+        .unwrap_or_else(|_| info.network.clone());
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(WalletError::Node(NodeFailure::WrongNetwork {
+            expected,
+            actual,
+        }))
+    }
+}
+
+/// Fails unless the node behind `client` is on `network` and, without
+/// `force`, synced: checked before every broadcast. Warns when its virtual
+/// trails the clock by more than [`MAX_NODE_LAG`].
+pub fn check_submission(client: &dyn NodeClient, network: &str, force: bool) -> WalletResult<()> {
+    let info = client.get_info()?;
+    check_network(&info, network)?;
+    if !info.is_synced {
+        if !force {
+            return Err(WalletError::Node(NodeFailure::NotSynced {
+                headers: info.header_count,
+                blocks: info.block_count,
+            }));
+        }
+        tracing::warn!(
+            headers = info.header_count,
+            blocks = info.block_count,
+            "broadcasting through a node that is not synced"
+        );
+    }
+    if let Some(lag) = info
+        .lag(SystemTime::now())
+        .filter(|lag| *lag > MAX_NODE_LAG)
+    {
+        tracing::warn!(
+            virtual_daa_score = info.virtual_daa_score,
+            "the node's virtual is {} minutes behind the clock; it may be stalled or cut off",
+            lag.as_secs() / 60
+        );
+    }
+    Ok(())
+}
+
+/// Polls until `txid` is accepted by the virtual chain, returning its
+/// `TxStatus::Accepted`, or fails once `timeout` has elapsed.
+pub fn wait_for_acceptance(
+    client: &dyn NodeClient,
+    txid: &str,
+    timeout: Duration,
+    poll_interval: Duration,
+) -> WalletResult<TxStatus> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let status = client.get_transaction_status(txid)?;
+        tracing::trace!(txid, ?status, "polled transaction status");
+        if let TxStatus::Accepted { .. } = status {
+            return Ok(status);
+        }
+        if Instant::now() + poll_interval > deadline {
+            return Err(WalletError::Network(format!(
+                "Transaction {} not accepted within {}s",
+                txid,
+                timeout.as_secs()
+            )));
+        }
+        thread::sleep(poll_interval);
+    }
+}
+
+/// Whether a submitted transaction could be found a little later. A node
+/// that takes a transaction but then drops it, or never relays it, leaves
+/// it stranded even though the submission succeeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Propagation {
+    /// The submitting node still has it, in its mempool or in a block.
+    pub submitter: bool,
+    /// The same for another node, when there was one to ask and it
+    /// answered.
+    pub other: Option<bool>,
+}
+
+impl Propagation {
+    /// What the user should hear when the transaction went missing.
+    pub fn warning(&self, txid: &str) -> Option<String> {
+        if !self.submitter {
+            Some(format!(
+                "The node took transaction {} but no longer has it in its mempool; it may have been dropped",
+                txid
+            ))
+        } else if self.other == Some(false) {
+            Some(format!(
+                "Transaction {} has not reached another node; the submitting node may not be relaying it",
+                txid
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Propagation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.submitter, self.other) {
+            (true, Some(true)) => write!(f, "submitted and seen in mempool by two nodes"),
+            (true, _) => write!(f, "submitted and seen in mempool"),
+            (false, _) => write!(f, "submitted, not seen in mempool"),
+        }
+    }
+}
+
+/// Waits `delay`, then asks `submitter` and `other`, when given, whether
+/// they have `txid`. Mempool lookups come first; a transaction already
+/// accepted into a block counts as seen. Only the submitting node's
+/// failure to answer is an error.
+pub fn check_propagation(
+    submitter: &dyn NodeClient,
+    other: Option<&dyn NodeClient>,
+    txid: &str,
+    delay: Duration,
+) -> WalletResult<Propagation> {
+    thread::sleep(delay);
+    let seen = |status: TxStatus| status != TxStatus::NotFound;
+    let propagation = Propagation {
+        submitter: seen(submitter.get_transaction_status(txid)?),
+        other: other.and_then(|node| {
+            node.get_transaction_status(txid)
+                .inspect_err(|e| tracing::debug!(txid, "the other node did not answer: {}", e))
+                .ok()
+                .map(seen)
+        }),
+    };
+    tracing::debug!(txid, ?propagation, "checked propagation");
+    Ok(propagation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct ScriptedNode {
+        statuses: Mutex<Vec<TxStatus>>,
+    }
+
+    impl NodeClient for ScriptedNode {
+        fn get_info(&self) -> WalletResult<NodeInfo> {
+            Ok(NodeInfo {
+                server_version: "1.0.0".to_string(),
+                network: "testnet-11".to_string(),
+                is_synced: true,
+                header_count: 0,
+                block_count: 0,
+                past_median_time: 0,
+                virtual_daa_score: 0,
+                mempool_size: 0,
+            })
+        }
+
+        fn submit_transaction(&self, tx: &Transaction) -> WalletResult<String> {
+            tx.txid()
+        }
+
+        fn get_transaction_status(&self, _txid: &str) -> WalletResult<TxStatus> {
+            let mut statuses = self.statuses.lock().unwrap();
+            Ok(if statuses.len() > 1 {
+                statuses.remove(0)
+            } else {
+                statuses[0].clone()
+            })
+        }
+
+        fn get_utxos_by_addresses(&self, _addresses: &[String]) -> WalletResult<Vec<Utxo>> {
+            Ok(Vec::new())
+        }
+
+        fn virtual_daa_score(&self) -> WalletResult<u64> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn test_reject_reasons() {
+        assert_eq!(
+            RejectReason::from_message("transaction abc is an orphan"),
+            RejectReason::Orphan
+        );
+        assert_eq!(
+            RejectReason::from_message("transaction abc is already in the mempool"),
+            RejectReason::AlreadyInMempool
+        );
+        assert_eq!(
+            RejectReason::from_message("transaction has mass 120000 which is higher than 100000"),
+            RejectReason::MassTooHigh
+        );
+        assert_eq!(
+            RejectReason::from_message("signature verification failed"),
+            RejectReason::InvalidSignature
+        );
+        assert_eq!(
+            RejectReason::from_message("transaction abc was already accepted by the consensus"),
+            RejectReason::AlreadyAccepted
+        );
+        assert_eq!(
+            RejectReason::from_message(
+                "transaction abc has 10 fees which is under the required amount of 2036"
+            ),
+            RejectReason::FeeTooLow
+        );
+        assert_eq!(
+            RejectReason::from_message("transaction abc is invalid: output 0 is dust"),
+            RejectReason::Invalid
+        );
+        assert_eq!(
+            RejectReason::from_message("node is shutting down"),
+            RejectReason::Other
+        );
+        let err = rejected("transaction abc is an orphan");
+        assert_eq!(
+            err.to_string(),
+            "Transaction rejected by node (orphan transaction (inputs unknown to the node)): transaction abc is an orphan"
+        );
+    }
+
+    #[test]
+    fn test_wait_for_acceptance() {
+        let node = ScriptedNode {
+            statuses: Mutex::new(vec![
+                TxStatus::NotFound,
+                TxStatus::Pending,
+                TxStatus::Accepted {
+                    accepting_block: "block".to_string(),
+                    blue_score_delta: 1,
+                },
+            ]),
+        };
+        let status = wait_for_acceptance(
+            &node,
+            "aa",
+            Duration::from_secs(5),
+            Duration::from_millis(1),
+        )
+        .unwrap();
+        assert_eq!(status.to_string(), "accepted, 1 confirmation");
+
+        let stuck = ScriptedNode {
+            statuses: Mutex::new(vec![TxStatus::Pending]),
+        };
+        assert!(wait_for_acceptance(
+            &stuck,
+            "aa",
+            Duration::from_millis(20),
+            Duration::from_millis(5)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_propagation() {
+        let scripted = |status: TxStatus| ScriptedNode {
+            statuses: Mutex::new(vec![status]),
+        };
+        let pending = scripted(TxStatus::Pending);
+        let missing = scripted(TxStatus::NotFound);
+        let accepted = scripted(TxStatus::Accepted {
+            accepting_block: "block".to_string(),
+            blue_score_delta: 3,
+        });
+
+        let alone = check_propagation(&pending, None, "aa", Duration::ZERO).unwrap();
+        assert_eq!(alone.to_string(), "submitted and seen in mempool");
+        assert_eq!(alone.warning("aa"), None);
+
+        let both = check_propagation(&accepted, Some(&pending), "aa", Duration::ZERO).unwrap();
+        assert_eq!(
+            both.to_string(),
+            "submitted and seen in mempool by two nodes"
+        );
+
+        let stuck = check_propagation(&pending, Some(&missing), "aa", Duration::ZERO).unwrap();
+        assert!(stuck
+            .warning("aa")
+            .unwrap()
+            .contains("not reached another node"));
+
+        let dropped = check_propagation(&missing, None, "aa", Duration::ZERO).unwrap();
+        assert_eq!(dropped.to_string(), "submitted, not seen in mempool");
+        assert!(dropped.warning("aa").unwrap().contains("no longer has it"));
+    }
+
+    #[test]
+    fn test_endpoint_parsing() {
+        let plain = Endpoint::parse("grpc://127.0.0.1:16110/").unwrap();
+        assert_eq!(plain.address, "127.0.0.1:16110");
+        assert!(!plain.tls);
+        assert_eq!(plain.transport, Transport::Grpc);
+        let wrpc = Endpoint::parse("wrpc://127.0.0.1:18110").unwrap();
+        assert_eq!((wrpc.tls, wrpc.transport), (false, Transport::Wrpc));
+        let wss = Endpoint::parse("wss://node.example:443").unwrap();
+        assert_eq!((wss.tls, wss.transport), (true, Transport::Wrpc));
+        assert_eq!(
+            Endpoint::parse("127.0.0.1:16110").unwrap().transport,
+            Transport::Grpc
+        );
+        assert!(Endpoint::parse("ftp://node.example:21").is_err());
+        assert!(Endpoint::parse("node.example").is_err());
+        assert!(matches!(
+            connect("grpcs://127.0.0.1:16110", RetryPolicy::default()),
+            Err(WalletError::Node(NodeFailure::Tls(_)))
+        ));
+    }
+
+    #[test]
+    fn test_ping_and_refusal() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = listener.local_addr().unwrap().to_string();
+        assert!(ping(&url, CONNECT_TIMEOUT).is_ok());
+
+        drop(listener);
+        assert!(matches!(
+            ping(&url, CONNECT_TIMEOUT),
+            Err(WalletError::Node(NodeFailure::ConnectionRefused(_)))
+        ));
+    }
+
+    /// The id the fake nodes give a transaction, unlike any it hashes to
+    /// locally.
+    const NODE_TXID: &str = "5e1f0c3a9b7d2e8f4a6c1b3d5e7f9a0b2c4d6e8f1a3b5c7d9e0f2a4b6c8d0e1f";
+
+    /// Refuses a transaction as one it already has, naming it `NODE_TXID`.
+    fn already_in_mempool() -> WalletError {
+        WalletError::Rejected {
+            reason: RejectReason::AlreadyInMempool,
+            message: format!("transaction {} is already in the mempool", NODE_TXID),
+        }
+    }
+
+    /// Takes the first submission but loses the reply, then refuses the
+    /// rest as known.
+    struct LossyNode {
+        submissions: std::sync::Arc<Mutex<u32>>,
+    }
+
+    impl NodeClient for LossyNode {
+        fn get_info(&self) -> WalletResult<NodeInfo> {
+            unimplemented!()
+        }
+
+        fn submit_transaction(&self, _tx: &Transaction) -> WalletResult<String> {
+            let mut submissions = self.submissions.lock().unwrap();
+            *submissions += 1;
+            if *submissions == 1 {
+                return Err(WalletError::Timeout {
+                    operation: "submit_transaction".to_string(),
+                    endpoint: "127.0.0.1:16110".to_string(),
+                    secs: 1,
+                });
+            }
+            Err(already_in_mempool())
+        }
+
+        fn get_transaction_status(&self, _txid: &str) -> WalletResult<TxStatus> {
+            unimplemented!()
+        }
+
+        fn get_utxos_by_addresses(&self, _addresses: &[String]) -> WalletResult<Vec<Utxo>> {
+            Ok(Vec::new())
+        }
+
+        fn virtual_daa_score(&self) -> WalletResult<u64> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn test_retries_take_the_node_id_of_a_lost_submission() {
+        let policy = RetryPolicy {
+            timeout: Duration::from_secs(1),
+            retries: 3,
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff(3), Duration::from_secs(2));
+        assert_eq!(policy.backoff(20), MAX_BACKOFF);
+
+        let submissions = std::sync::Arc::new(Mutex::new(0));
+        let node = LossyNode {
+            submissions: submissions.clone(),
+        };
+        let client = RetryingClient::new(Box::new(node), policy);
+        let tx = Transaction::default();
+        assert_eq!(client.submit_transaction(&tx).unwrap(), NODE_TXID);
+        assert_eq!(*submissions.lock().unwrap(), 2);
+
+        // A first attempt refused as known is not a lost reply.
+        *submissions.lock().unwrap() = 1;
+        let node = LossyNode { submissions };
+        let client = RetryingClient::new(Box::new(node), policy);
+        assert!(matches!(
+            client.submit_transaction(&tx),
+            Err(WalletError::Rejected { .. })
+        ));
+    }
+
+    #[test]
+    fn test_already_known_reads_the_id_from_the_message() {
+        assert_eq!(already_known(&already_in_mempool()).unwrap(), NODE_TXID);
+        let accepted = WalletError::Rejected {
+            reason: RejectReason::AlreadyAccepted,
+            message: format!(
+                "transaction {} is already accepted",
+                NODE_TXID.to_uppercase()
+            ),
+        };
+        assert_eq!(already_known(&accepted).unwrap(), NODE_TXID);
+        let unnamed = WalletError::Rejected {
+            reason: RejectReason::AlreadyInMempool,
+            message: "already in the mempool".to_string(),
+        };
+        assert!(already_known(&unnamed).is_none());
+        let orphan = WalletError::Rejected {
+            reason: RejectReason::Orphan,
+            message: format!("transaction {} is an orphan", NODE_TXID),
+        };
+        assert!(already_known(&orphan).is_none());
+    }
+
+    /// Reports `info` and nothing else.
+    struct Reporting(NodeInfo);
+
+    impl NodeClient for Reporting {
+        fn get_info(&self) -> WalletResult<NodeInfo> {
+            Ok(self.0.clone())
+        }
+
+        fn submit_transaction(&self, _tx: &Transaction) -> WalletResult<String> {
+            unreachable!()
+        }
+
+        fn get_transaction_status(&self, _txid: &str) -> WalletResult<TxStatus> {
+            unreachable!()
+        }
+
+        fn get_utxos_by_addresses(&self, _addresses: &[String]) -> WalletResult<Vec<Utxo>> {
+            unreachable!()
+        }
+
+        fn virtual_daa_score(&self) -> WalletResult<u64> {
+            Ok(self.0.virtual_daa_score)
+        }
+    }
+
+    #[test]
+    fn test_submission_needs_a_synced_node_on_the_network() {
+        let now = SystemTime::now();
+        let millis = |time: SystemTime| time.duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+        let mut info = NodeInfo {
+            server_version: "1.0.0".to_string(),
+            network: "mainnet".to_string(),
+            is_synced: false,
+            header_count: 900,
+            block_count: 400,
+            past_median_time: millis(now - Duration::from_secs(3600)),
+            virtual_daa_score: 12_345,
+            mempool_size: 0,
+        };
+        let Err(WalletError::Node(NodeFailure::NotSynced { headers, blocks })) =
+            check_submission(&Reporting(info.clone()), "mainnet", false)
+        else {
+            panic!("unsynced node not refused");
+        };
+        assert_eq!((headers, blocks), (900, 400));
+        // Forced, it only warns, about the sync and the hour behind.
+        check_submission(&Reporting(info.clone()), "mainnet", true).unwrap();
+        assert!(matches!(
+            check_submission(&Reporting(info.clone()), "testnet-10", true),
+            Err(WalletError::Node(NodeFailure::WrongNetwork { .. }))
+        ));
+
+        assert!(info.lag(now).unwrap() >= Duration::from_secs(3600));
+        info.is_synced = true;
+        info.past_median_time = millis(now + Duration::from_secs(1));
+        assert_eq!(info.lag(now), None);
+        check_submission(&Reporting(info.clone()), "mainnet", false).unwrap();
+        info.past_median_time = 0;
+        assert_eq!(info.lag(now), None);
+    }
+
+    /// A node for the failover tests.
+    #[derive(Clone)]
+    struct FakeNode {
+        name: &'static str,
+        network: &'static str,
+        /// Reads and submissions fail as if the connection broke.
+        broken: bool,
+        /// Has the transaction already, so refuses it as known.
+        known: bool,
+        submitted: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    fn broken_pipe() -> WalletError {
+        WalletError::Network("connection reset".to_string())
+    }
+
+    impl NodeClient for FakeNode {
+        fn get_info(&self) -> WalletResult<NodeInfo> {
+            Ok(NodeInfo {
+                server_version: "1.0.0".to_string(),
+                network: self.network.to_string(),
+                is_synced: true,
+                header_count: 0,
+                block_count: 0,
+                past_median_time: 0,
+                virtual_daa_score: 0,
+                mempool_size: 0,
+            })
+        }
+
+        fn submit_transaction(&self, tx: &Transaction) -> WalletResult<String> {
+            if self.broken {
+                return Err(broken_pipe());
+            }
+            if self.known {
+                return Err(already_in_mempool());
+            }
+            self.submitted.lock().unwrap().push(self.name);
+            tx.txid()
+        }
+
+        fn get_transaction_status(&self, _txid: &str) -> WalletResult<TxStatus> {
+            Err(broken_pipe())
+        }
+
+        fn get_utxos_by_addresses(&self, _addresses: &[String]) -> WalletResult<Vec<Utxo>> {
+            if self.broken {
+                return Err(broken_pipe());
+            }
+            Ok(Vec::new())
+        }
+
+        fn virtual_daa_score(&self) -> WalletResult<u64> {
+            Ok(0)
+        }
+    }
+
+    /// Connects to `nodes` by name, refusing any other, and logs each try.
+    fn connector(nodes: &[FakeNode], log: &Arc<Mutex<Vec<String>>>) -> Connector {
+        let nodes = nodes.to_vec();
+        let log = log.clone();
+        Box::new(move |url: &str| {
+            log.lock().unwrap().push(url.to_string());
+            match nodes.iter().find(|node| node.name == url) {
+                Some(node) => Ok(Box::new(node.clone())),
+                None => Err(WalletError::Node(NodeFailure::ConnectionRefused(
+                    url.to_string(),
+                ))),
+            }
+        })
+    }
+
+    fn fake(name: &'static str, network: &'static str, broken: bool) -> FakeNode {
+        FakeNode {
+            name,
+            network,
+            broken,
+            known: false,
+            submitted: Arc::default(),
+        }
+    }
+
+    fn urls(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    /// The failover tests share the remembered last good node.
+    static FAILOVER_TESTS: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_failover_skips_unusable_nodes() {
+        let _serial = FAILOVER_TESTS.lock().unwrap_or_else(|e| e.into_inner());
+        let nodes = [
+            fake("other", "testnet-10", false),
+            fake("broken", "testnet-11", true),
+            fake("good", "testnet-11", false),
+        ];
+        let log = Arc::default();
+        let urls = urls(&["down", "other", "broken", "good"]);
+        let client =
+            FailoverClient::with_connector(&urls, "testnet-11", connector(&nodes, &log)).unwrap();
+        assert_eq!(client.url(), "broken");
+        // The read fails on `broken` and is repeated on the next node.
+        assert!(client
+            .get_utxos_by_addresses(&["kaspa:me".to_string()])
+            .unwrap()
+            .is_empty());
+        assert_eq!(client.url(), "good");
+        assert_eq!(*log.lock().unwrap(), ["down", "other", "broken", "good"]);
+
+        // The last good node is tried first from then on.
+        log.lock().unwrap().clear();
+        let again =
+            FailoverClient::with_connector(&urls, "testnet-11", connector(&nodes, &log)).unwrap();
+        assert_eq!(again.url(), "good");
+        assert_eq!(*log.lock().unwrap(), ["good"]);
+
+        // Another node to check propagation with, leaving `good` in use.
+        log.lock().unwrap().clear();
+        assert!(again.other_node().is_some());
+        assert_eq!(*log.lock().unwrap(), ["down", "other", "broken"]);
+        assert_eq!(again.url(), "good");
+
+        let unusable =
+            FailoverClient::with_connector(&urls[..2], "testnet-11", connector(&nodes, &log));
+        assert!(matches!(
+            unusable.err(),
+            Some(WalletError::Node(NodeFailure::WrongNetwork { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_failover_takes_the_next_node_id() {
+        let _serial = FAILOVER_TESTS.lock().unwrap_or_else(|e| e.into_inner());
+        let tx = Transaction::default();
+        let submit = |known: bool| {
+            let submitted = Arc::default();
+            let nodes = [
+                FakeNode {
+                    submitted: Arc::clone(&submitted),
+                    ..fake("first", "testnet-11", true)
+                },
+                FakeNode {
+                    known,
+                    submitted: Arc::clone(&submitted),
+                    ..fake("second", "testnet-11", false)
+                },
+            ];
+            *last_good_node() = None;
+            let client = FailoverClient::with_connector(
+                &urls(&["first", "second"]),
+                "testnet-11",
+                connector(&nodes, &Arc::default()),
+            )
+            .unwrap();
+            let txid = client.submit_transaction(&tx).unwrap();
+            let submitted = submitted.lock().unwrap().clone();
+            (txid, submitted)
+        };
+        // The failed node took it and passed it on before the connection
+        // broke: the next node names it.
+        assert_eq!(submit(true), (NODE_TXID.to_string(), Vec::new()));
+        // The next node has not seen it and takes it, once.
+        assert_eq!(submit(false), (tx.txid().unwrap(), vec!["second"]));
+    }
+
+    #[test]
+    fn test_timeouts_name_the_endpoint() {
+        let policy = RetryPolicy::default();
+        let error = policy.io_error(
+            "connect",
+            "node.example:16110",
+            io::Error::from(io::ErrorKind::TimedOut),
+        );
+        assert!(matches!(error, WalletError::Timeout { .. }));
+        assert!(error.to_string().contains("connect on node.example:16110"));
+    }
+
+    #[test]
+    fn test_check_network() {
+        let node = ScriptedNode {
+            statuses: Mutex::new(vec![TxStatus::NotFound]),
+        };
+        let info = node.get_info().unwrap();
+        assert!(check_network(&info, "testnet").is_ok());
+        assert!(matches!(
+            check_network(&info, "mainnet"),
+            Err(WalletError::Node(NodeFailure::WrongNetwork { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_fee_buckets_convert_to_wallet_rates() {
+        let bucket = FeeBucket::from_node(1.0001, 2.5);
+        assert_eq!(bucket.rate, 1_001);
+        assert_eq!(bucket.estimated_time, Some(Duration::from_millis(2_500)));
+        assert_eq!(FeeBucket::from_node(2.0, 0.0).estimated_time, None);
+        assert_eq!(FeeBucket::from_node(2.0, f64::NAN).estimated_time, None);
+        assert_eq!(bucket.estimated_time_label().as_deref(), Some("~3s"));
+        assert_eq!(
+            FeeBucket::from_node(1.0, 61.0)
+                .estimated_time_label()
+                .as_deref(),
+            Some("~2m")
+        );
+        assert_eq!(
+            FeeBucket::from_node(1.0, 3600.0)
+                .estimated_time_label()
+                .as_deref(),
+            Some("~1h")
+        );
+        assert_eq!(FeeBucket::from_node(1.0, 0.0).estimated_time_label(), None);
+    }
+
+    #[test]
+    fn test_fee_estimate_cache() {
+        let bucket = |rate| FeeBucket {
+            rate,
+            estimated_time: None,
+        };
+        let estimate = FeeEstimate {
+            low: bucket(1_000),
+            normal: bucket(2_000),
+            priority: bucket(3_000),
+        };
+        let cache = FeeEstimateCache::new(Duration::from_secs(10));
+        let start = Instant::now();
+        let fetches = Mutex::new(0);
+        let fetch = || {
+            *fetches.lock().unwrap() += 1;
+            Ok(Some(estimate))
+        };
+
+        assert_eq!(
+            cache.get_or_fetch("a", start, fetch).unwrap(),
+            Some(estimate)
+        );
+        let later = start + Duration::from_secs(9);
+        assert_eq!(
+            cache.get_or_fetch("a", later, fetch).unwrap(),
+            Some(estimate)
+        );
+        assert_eq!(*fetches.lock().unwrap(), 1);
+
+        // Another node, and the same one once the estimate is stale.
+        cache.get_or_fetch("b", later, fetch).unwrap();
+        cache
+            .get_or_fetch("a", start + Duration::from_secs(10), fetch)
+            .unwrap();
+        assert_eq!(*fetches.lock().unwrap(), 3);
+
+        // Failures are not kept.
+        let refused = || Err(WalletError::Network("refused".to_string()));
+        assert!(cache.get_or_fetch("c", start, refused).is_err());
+        cache.get_or_fetch("c", start, fetch).unwrap();
+        assert_eq!(*fetches.lock().unwrap(), 4);
+    }
+}
@@ -0,0 +1,150 @@
+//! KAS prices from a CoinGecko-style `simple/price` endpoint, for showing
+//! approximate fiat values. Only ever displayed; nothing signed uses them.
+
+use crate::amount::{Kas, SOMPI_PER_KAS};
+use crate::error::{WalletError, WalletResult};
+use crate::http::{HttpUrl, Service};
+use crate::node::RetryPolicy;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Fiat {
+    #[default]
+    Usd,
+    Eur,
+}
+
+impl Fiat {
+    pub const ALL: [Fiat; 2] = [Fiat::Usd, Fiat::Eur];
+
+    /// The API's name for it.
+    fn code(self) -> &'static str {
+        match self {
+            Fiat::Usd => "usd",
+            Fiat::Eur => "eur",
+        }
+    }
+}
+
+impl fmt::Display for Fiat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Fiat::Usd => "USD",
+            Fiat::Eur => "EUR",
+        })
+    }
+}
+
+/// The price of one KAS in each currency the API quoted.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Prices {
+    pub usd: Option<f64>,
+    pub eur: Option<f64>,
+}
+
+impl Prices {
+    pub fn get(&self, fiat: Fiat) -> Option<f64> {
+        match fiat {
+            Fiat::Usd => self.usd,
+            Fiat::Eur => self.eur,
+        }
+    }
+
+    /// "≈ 12.34 USD" for `amount`, or `None` without a price in `fiat`.
+    pub fn approximate(&self, amount: Kas, fiat: Fiat) -> Option<String> {
+        let price = self.get(fiat)?;
+        let value = amount.sompi() as f64 / SOMPI_PER_KAS as f64 * price;
+        Some(format!("≈ {:.2} {}", value, fiat))
+    }
+}
+
+/// Reads `{"kaspa": {"usd": 0.1, "eur": 0.09}}`. Negative or non-finite
+/// prices are dropped, and a response with none usable is an error.
+pub fn parse_prices(json: &str) -> WalletResult<Prices> {
+    #[derive(Deserialize)]
+    struct Response {
+        kaspa: Quote,
+    }
+    #[derive(Deserialize)]
+    struct Quote {
+        usd: Option<f64>,
+        eur: Option<f64>,
+    }
+    let quote = serde_json::from_str::<Response>(json)
+        .map_err(|e| WalletError::Serialization(format!("Invalid price response: {}", e)))?
+        .kaspa;
+    let usable = |price: Option<f64>| price.filter(|price| price.is_finite() && *price >= 0.0);
+    let prices = Prices {
+        usd: usable(quote.usd),
+        eur: usable(quote.eur),
+    };
+    if prices == Prices::default() {
+        return Err(WalletError::Serialization(
+            "Price response quoted no usable price".to_string(),
+        ));
+    }
+    Ok(prices)
+}
+
+const PRICE_API: Service = Service {
+    name: "price API",
+    setting: "price_url",
+    operation: "price request",
+};
+
+/// A price API reached over plain HTTP.
+pub struct PriceApi {
+    url: HttpUrl,
+    policy: RetryPolicy,
+}
+
+impl PriceApi {
+    pub fn new(url: &str, policy: RetryPolicy) -> WalletResult<Self> {
+        Ok(Self {
+            url: HttpUrl::parse(url, PRICE_API)?,
+            policy,
+        })
+    }
+
+    pub fn prices(&self) -> WalletResult<Prices> {
+        let codes: Vec<&str> = Fiat::ALL.iter().map(|fiat| fiat.code()).collect();
+        let path = format!(
+            "{}/simple/price?ids=kaspa&vs_currencies={}",
+            self.url.base_path,
+            codes.join(",")
+        );
+        let body = self.url.fetch(&path, &self.policy)?;
+        parse_prices(&body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_prices() {
+        let prices = parse_prices(r#"{"kaspa": {"usd": 0.125, "eur": -1}}"#).unwrap();
+        assert_eq!(prices.get(Fiat::Usd), Some(0.125));
+        assert_eq!(prices.get(Fiat::Eur), None);
+        assert!(parse_prices(r#"{"kaspa": {}}"#).is_err());
+        assert!(parse_prices("[]").is_err());
+    }
+
+    #[test]
+    fn test_approximate() {
+        let prices = Prices {
+            usd: Some(0.125),
+            eur: None,
+        };
+        let amount = Kas::from_sompi(150 * SOMPI_PER_KAS);
+        assert_eq!(
+            prices.approximate(amount, Fiat::Usd).as_deref(),
+            Some("≈ 18.75 USD")
+        );
+        assert_eq!(prices.approximate(amount, Fiat::Eur), None);
+        assert!(PriceApi::new("https://api.coingecko.com/api/v3", RetryPolicy::default()).is_err());
+    }
+}
@@ -0,0 +1,245 @@
+use crate::error::{WalletError, WalletResult};
+use crate::transaction::Transaction;
+use crate::utxo::Utxo;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+pub const PSTX_FORMAT: &str = "kasparustwallet-pstx";
+pub const PSTX_VERSION: u32 = 1;
+
+/// A transaction in transit between an online machine that builds it and
+/// an offline one that signs it, with the outputs it spends embedded so
+/// the signer can check amounts without a node. `hash` covers everything
+/// else and catches files damaged or edited on the way.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartiallySignedTransaction {
+    pub format: String,
+    pub version: u32,
+    pub network: String,
+    pub transaction: Transaction,
+    pub utxo_entries: Vec<Utxo>,
+    pub hash: String,
+}
+
+impl PartiallySignedTransaction {
+    /// Wraps `transaction`, which must spend exactly `utxo_entries` in order.
+    pub fn new(
+        network: &str,
+        mut transaction: Transaction,
+        utxo_entries: Vec<Utxo>,
+    ) -> WalletResult<Self> {
+        if transaction.inputs.len() != utxo_entries.len() {
+            return Err(WalletError::Transaction(format!(
+                "{} input(s) but {} UTXO entries",
+                transaction.inputs.len(),
+                utxo_entries.len()
+            )));
+        }
+        for (input, entry) in transaction.inputs.iter_mut().zip(&utxo_entries) {
+            if (input.txid.as_str(), input.vout) != (entry.txid.as_str(), entry.vout) {
+                return Err(WalletError::Transaction(format!(
+                    "No UTXO entry for input {}:{}",
+                    input.txid, input.vout
+                )));
+            }
+            input.amount = Some(entry.amount);
+        }
+
+        let mut pstx = Self {
+            format: PSTX_FORMAT.to_string(),
+            version: PSTX_VERSION,
+            network: network.to_string(),
+            transaction,
+            utxo_entries,
+            hash: String::new(),
+        };
+        pstx.seal()?;
+        Ok(pstx)
+    }
+
+    fn compute_hash(&self) -> WalletResult<String> {
+        let body = serde_json::to_vec(&(
+            &self.format,
+            self.version,
+            &self.network,
+            &self.transaction,
+            &self.utxo_entries,
+        ))
+        .map_err(|e| WalletError::Serialization(format!("PSTX encode error: {}", e)))?;
+        Ok(hex::encode(Sha256::digest(body)))
+    }
+
+    /// Recomputes `hash` after the transaction changed, e.g. once signed.
+    pub fn seal(&mut self) -> WalletResult<()> {
+        self.hash = self.compute_hash()?;
+        Ok(())
+    }
+
+    pub fn is_fully_signed(&self) -> bool {
+        self.transaction
+            .inputs
+            .iter()
+            .all(|input| input.signature.is_some())
+    }
+
+    /// The transaction ready to broadcast; fails while any input is unsigned.
+    pub fn finalize(&self) -> WalletResult<Transaction> {
+        if let Some(index) = self
+            .transaction
+            .inputs
+            .iter()
+            .position(|input| input.signature.is_none())
+        {
+            return Err(WalletError::Transaction(format!(
+                "Input {} is not signed yet",
+                index
+            )));
+        }
+        Ok(self.transaction.clone())
+    }
+
+    pub fn to_json(&self) -> WalletResult<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| WalletError::Serialization(format!("PSTX encode error: {}", e)))
+    }
+
+    pub fn from_json(json: &str) -> WalletResult<Self> {
+        let pstx: Self = serde_json::from_str(json)
+            .map_err(|e| WalletError::Serialization(format!("Not a PSTX file: {}", e)))?;
+        if pstx.format != PSTX_FORMAT {
+            return Err(WalletError::Serialization(format!(
+                "Unknown PSTX format: {}",
+                pstx.format
+            )));
+        }
+        if pstx.version > PSTX_VERSION {
+            return Err(WalletError::Serialization(format!(
+                "PSTX version {} is newer than supported version {}",
+                pstx.version, PSTX_VERSION
+            )));
+        }
+        if pstx.compute_hash()? != pstx.hash {
+            return Err(WalletError::Serialization(
+                "PSTX integrity hash does not match; the file was damaged or edited".to_string(),
+            ));
+        }
+        Ok(pstx)
+    }
+
+    pub fn load(path: &Path) -> WalletResult<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+/// A transaction as passed between commands: raw hex or a PSTX file.
+#[derive(Debug, Clone)]
+pub enum TxDocument {
+    Raw(Transaction),
+    Pstx(PartiallySignedTransaction),
+}
+
+impl TxDocument {
+    /// Parses either format, told apart by the first non-whitespace
+    /// character (`{` for PSTX JSON).
+    pub fn parse(text: &str) -> WalletResult<Self> {
+        let text = text.trim();
+        if text.starts_with('{') {
+            PartiallySignedTransaction::from_json(text).map(TxDocument::Pstx)
+        } else {
+            Transaction::from_hex(text).map(TxDocument::Raw)
+        }
+    }
+
+    pub fn transaction(&self) -> &Transaction {
+        match self {
+            TxDocument::Raw(tx) => tx,
+            TxDocument::Pstx(pstx) => &pstx.transaction,
+        }
+    }
+
+    /// The transaction ready to broadcast; a PSTX must be fully signed.
+    pub fn finalize(self) -> WalletResult<Transaction> {
+        match self {
+            TxDocument::Raw(tx) => Ok(tx),
+            TxDocument::Pstx(pstx) => pstx.finalize(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::KaspaWallet;
+    use secp256k1::Secp256k1;
+
+    fn pstx() -> (KaspaWallet, PartiallySignedTransaction) {
+        let secp = Secp256k1::new();
+        let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let wallet = KaspaWallet::with_network(secret_key, "mainnet").unwrap();
+
+        let entry = Utxo {
+            txid: "11".repeat(32),
+            vout: 0,
+            address: wallet.get_address(),
+            amount: 5_000,
+            block_daa_score: 10,
+            is_coinbase: false,
+        };
+        let mut tx = Transaction::new();
+        tx.add_input(entry.txid.clone(), 0);
+        tx.add_output(wallet.get_address(), 4_000);
+        let pstx = PartiallySignedTransaction::new("mainnet", tx, vec![entry]).unwrap();
+        (wallet, pstx)
+    }
+
+    #[test]
+    fn test_sign_and_finalize() {
+        let (wallet, mut pstx) = pstx();
+        assert_eq!(pstx.transaction.fee(), Some(1_000));
+        assert!(pstx.finalize().is_err());
+
+        let mut reloaded = PartiallySignedTransaction::from_json(&pstx.to_json().unwrap()).unwrap();
+        wallet
+            .sign_transaction(&mut reloaded.transaction, 1000)
+            .unwrap();
+        reloaded.seal().unwrap();
+        pstx = PartiallySignedTransaction::from_json(&reloaded.to_json().unwrap()).unwrap();
+
+        assert!(pstx.is_fully_signed());
+        assert!(pstx.finalize().is_ok());
+    }
+
+    #[test]
+    fn test_rejects_tampering_and_newer_versions() {
+        let (_, pstx) = pstx();
+
+        let mut edited = pstx.clone();
+        edited.transaction.outputs[0].amount = 4_999;
+        assert!(PartiallySignedTransaction::from_json(&edited.to_json().unwrap()).is_err());
+
+        let mut newer = pstx;
+        newer.version = PSTX_VERSION + 1;
+        newer.seal().unwrap();
+        let err = PartiallySignedTransaction::from_json(&newer.to_json().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("newer"));
+    }
+
+    #[test]
+    fn test_document_detects_format() {
+        let (_, pstx) = pstx();
+        let json = format!("\n  {}\n", pstx.to_json().unwrap());
+        let document = TxDocument::parse(&json).unwrap();
+        assert!(matches!(document, TxDocument::Pstx(_)));
+        assert!(document.finalize().is_err());
+
+        let hex_str = hex::encode(pstx.transaction.encode().unwrap());
+        let document = TxDocument::parse(&format!("{}\r\n", hex_str)).unwrap();
+        assert_eq!(
+            document.transaction().txid().unwrap(),
+            pstx.transaction.txid().unwrap()
+        );
+        assert!(TxDocument::parse("{not json").is_err());
+    }
+}
@@ -0,0 +1,85 @@
+//! QR codes for addresses and payment URIs, as PNG images, and reading
+//! them back from PNG or JPEG.
+
+use crate::error::{WalletError, WalletResult};
+use image::{ImageFormat, Luma};
+use qrcode::QrCode;
+use std::io::Cursor;
+
+/// Pixels per QR module, so a typical address code is about 300px wide.
+const MODULE_SIZE: u32 = 8;
+
+/// Encodes `data`, such as an address or payment URI, as a PNG QR code.
+pub fn png(data: &str) -> WalletResult<Vec<u8>> {
+    let code = QrCode::new(data.as_bytes())
+        .map_err(|e| WalletError::Serialization(format!("QR encode error: {}", e)))?;
+    let image = code
+        .render::<Luma<u8>>()
+        .module_dimensions(MODULE_SIZE, MODULE_SIZE)
+        .build();
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| WalletError::Serialization(format!("PNG encode error: {}", e)))?;
+    Ok(png)
+}
+
+/// The text of the first readable QR code in a PNG or JPEG image.
+pub fn decode(bytes: &[u8]) -> WalletResult<String> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| WalletError::Serialization(format!("Unreadable image: {}", e)))?
+        .into_luma8();
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        image.width() as usize,
+        image.height() as usize,
+        |x, y| image.get_pixel(x as u32, y as u32)[0],
+    );
+    let grids = prepared.detect_grids();
+    if grids.is_empty() {
+        return Err(WalletError::InvalidParameters(
+            "No QR code found in the image".to_string(),
+        ));
+    }
+    let mut last_error = None;
+    for grid in &grids {
+        match grid.decode() {
+            Ok((_, text)) => return Ok(text),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(WalletError::Serialization(format!(
+        "QR code could not be read: {}",
+        last_error.map(|e| e.to_string()).unwrap_or_default()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_png_round_trip() {
+        let png = png("kaspa:1C6Rc3w25VHud3dLDamutaqfKWqhrLRTaD?amount=2.5").unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        let image = image::load_from_memory_with_format(&png, ImageFormat::Png).unwrap();
+        assert_eq!(image.width(), image.height());
+        assert_eq!(image.width() % MODULE_SIZE, 0);
+    }
+
+    #[test]
+    fn test_decode() {
+        let uri = "kaspa:1C6Rc3w25VHud3dLDamutaqfKWqhrLRTaD?amount=2.5";
+        assert_eq!(decode(&png(uri).unwrap()).unwrap(), uri);
+
+        let blank = image::GrayImage::from_pixel(64, 64, Luma([255]));
+        let mut bytes = Vec::new();
+        blank
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        assert!(decode(&bytes)
+            .unwrap_err()
+            .to_string()
+            .contains("No QR code"));
+        assert!(decode(b"not an image").is_err());
+    }
+}
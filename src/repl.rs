@@ -0,0 +1,320 @@
+//! Interactive shell. The wallet is unlocked once and kept for the session.
+
+use crate::{
+    parse_recipients, send_transaction, sync_wallet, unlock_keystore, unlock_wallet, Change,
+    Context, KeyArgs,
+};
+use clap::{Parser, Subcommand};
+use kasparustwallet::address;
+use kasparustwallet::address_book::{AddressBook, Contact};
+use kasparustwallet::amount::Kas;
+use kasparustwallet::error::WalletError;
+use kasparustwallet::lockable::LockableWallet;
+use kasparustwallet::transaction::FeeMode;
+use kasparustwallet::wallet_state::{SyncDiff, WalletState};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Editor, Helper};
+
+const HISTORY_FILE: &str = "repl_history.txt";
+
+#[derive(Parser)]
+#[command(name = "", no_binary_name = true, disable_version_flag = true)]
+struct ReplLine {
+    #[command(subcommand)]
+    command: ReplCommand,
+}
+
+#[derive(Subcommand)]
+enum ReplCommand {
+    /// Show the wallet address, or the HD receive address at an index
+    Address { index: Option<u32> },
+    /// Show the balance of the wallet address
+    Balance,
+    /// List the wallet's unspent outputs
+    Utxos,
+    /// Sign a transaction; recipients are address:amount or label:amount
+    Send {
+        #[arg(required = true)]
+        outputs: Vec<String>,
+        #[arg(short, long)]
+        inputs: Vec<String>,
+        #[arg(long)]
+        sompi: bool,
+        #[arg(short, long, default_value = "1000")]
+        fee_rate: u64,
+    },
+    /// List address book contacts for this network
+    Contacts,
+    /// Add an address book contact
+    ContactAdd { label: String, address: String },
+    /// Remove an address book contact
+    ContactRemove { label: String },
+    /// Forget the decrypted keys until `unlock`
+    Lock,
+    /// Decrypt the wallet again after `lock`
+    Unlock,
+    /// Lock the wallet and leave the shell
+    #[command(visible_alias = "quit")]
+    Exit,
+}
+
+const COMMANDS: &[&str] = &[
+    "address",
+    "balance",
+    "utxos",
+    "send",
+    "contacts",
+    "contact-add",
+    "contact-remove",
+    "lock",
+    "unlock",
+    "exit",
+    "quit",
+    "help",
+];
+
+/// Completes command names in the first word and contact labels after it.
+struct ReplHelper {
+    labels: Vec<String>,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos].rfind(' ').map(|i| i + 1).unwrap_or(0);
+        let prefix = &line[start..pos];
+        let candidates: Vec<&str> = if start == 0 {
+            COMMANDS.to_vec()
+        } else {
+            self.labels.iter().map(String::as_str).collect()
+        };
+        let matches = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(prefix))
+            .map(|candidate| Pair {
+                display: candidate.to_string(),
+                replacement: candidate.to_string(),
+            })
+            .collect();
+        Ok((start, matches))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+struct Session<'a> {
+    ctx: &'a Context,
+    lockable: LockableWallet,
+    network: String,
+    book: AddressBook,
+}
+
+impl Session<'_> {
+    fn labels(&self) -> Vec<String> {
+        self.book
+            .contacts(&self.network)
+            .map(|contact| contact.label.clone())
+            .collect()
+    }
+
+    fn address(&self) -> Result<String, WalletError> {
+        match self.lockable.keystore() {
+            Some(keystore) => Ok(keystore.address.clone()),
+            None => Ok(self.lockable.wallet()?.get_address()),
+        }
+    }
+
+    fn sync(&self) -> Result<(WalletState, SyncDiff), WalletError> {
+        sync_wallet(self.ctx, &self.network, &[self.address()?])
+    }
+
+    /// Replaces contact labels in `label:amount` recipients with addresses.
+    fn resolve_recipients(&self, outputs: &[String]) -> Result<Vec<String>, WalletError> {
+        outputs
+            .iter()
+            .map(|output| {
+                let Some((recipient, amount)) = output.rsplit_once(':') else {
+                    return Ok(output.clone());
+                };
+                if address::validate_address(recipient)? {
+                    return Ok(output.clone());
+                }
+                match self.book.find(&self.network, recipient) {
+                    Some(contact) => Ok(format!("{}:{}", contact.address, amount)),
+                    None => Ok(output.clone()),
+                }
+            })
+            .collect()
+    }
+
+    /// Runs one command; returns false when the session should end.
+    fn run(&mut self, command: ReplCommand) -> Result<bool, WalletError> {
+        match command {
+            ReplCommand::Address { index: None } => println!("{}", self.address()?),
+            ReplCommand::Address { index: Some(index) } => {
+                println!("{}", self.lockable.wallet()?.receive_address(index)?)
+            }
+            ReplCommand::Balance => {
+                let (state, diff) = self.sync()?;
+                let balance = state.balance();
+                println!("{} KAS ({} sompi)", Kas::from_sompi(balance), balance);
+                if !diff.first_sync && diff.received() > 0 {
+                    println!(
+                        "Received {} KAS since last sync",
+                        Kas::from_sompi(diff.received())
+                    );
+                }
+            }
+            ReplCommand::Utxos => {
+                for utxo in self.sync()?.0.utxos.iter() {
+                    println!(
+                        "{}:{}  {} KAS",
+                        utxo.txid,
+                        utxo.vout,
+                        Kas::from_sompi(utxo.amount)
+                    );
+                }
+            }
+            ReplCommand::Send {
+                outputs,
+                inputs,
+                sompi,
+                fee_rate,
+            } => {
+                let recipients = parse_recipients(&self.resolve_recipients(&outputs)?, sompi)?;
+                send_transaction(
+                    self.ctx,
+                    self.lockable.wallet()?,
+                    &inputs,
+                    &recipients,
+                    FeeMode::Rate(fee_rate),
+                    None,
+                    &Change::Wallet,
+                )?;
+            }
+            ReplCommand::Contacts => {
+                for contact in self.book.contacts(&self.network) {
+                    println!("{}  {}", contact.label, contact.address);
+                }
+            }
+            ReplCommand::ContactAdd { label, address } => {
+                self.book.add(Contact {
+                    label,
+                    address,
+                    network: self.network.clone(),
+                })?;
+                self.book.save()?;
+            }
+            ReplCommand::ContactRemove { label } => {
+                if self.book.remove(&self.network, &label).is_none() {
+                    return Err(WalletError::InvalidParameters(format!(
+                        "No contact named {}",
+                        label
+                    )));
+                }
+                self.book.save()?;
+            }
+            ReplCommand::Lock => {
+                self.lockable.lock();
+                println!("Wallet locked");
+            }
+            ReplCommand::Unlock => {
+                let name = self.lockable.name().unwrap_or_default().to_string();
+                unlock_keystore(self.ctx, &mut self.lockable, &name)?;
+                println!("Wallet unlocked");
+            }
+            ReplCommand::Exit => {
+                self.lockable.lock();
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+pub(crate) fn run_repl(ctx: &Context, key: &KeyArgs, network: &str) -> Result<(), WalletError> {
+    let (lockable, _) = unlock_wallet(ctx, key, network)?;
+    let network = match lockable.keystore() {
+        Some(keystore) => keystore.network.clone(),
+        None => lockable.wallet()?.get_network_name().to_string(),
+    };
+    let mut session = Session {
+        ctx,
+        lockable,
+        network,
+        book: AddressBook::load(&ctx.data_dir)?,
+    };
+
+    let mut editor: Editor<ReplHelper, DefaultHistory> =
+        Editor::new().map_err(|e| WalletError::Io(e.to_string()))?;
+    editor.set_helper(Some(ReplHelper {
+        labels: session.labels(),
+    }));
+    let history = ctx.data_dir.join(HISTORY_FILE);
+    let _ = editor.load_history(&history);
+
+    println!(
+        "Wallet {} on {}. Type `help` for commands, `exit` to quit.",
+        session.address()?,
+        session.network
+    );
+
+    loop {
+        let prompt = if session.lockable.is_locked() {
+            "kaspa (locked)> "
+        } else {
+            "kaspa> "
+        };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(WalletError::Io(e.to_string())),
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line.as_str());
+
+        let command = match ReplLine::try_parse_from(line.split_whitespace()) {
+            Ok(parsed) => parsed.command,
+            Err(e) => {
+                let _ = e.print();
+                continue;
+            }
+        };
+        match session.run(command) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => eprintln!("Error: {}", e),
+        }
+        if let Some(helper) = editor.helper_mut() {
+            helper.labels = session.labels();
+        }
+    }
+
+    session.lockable.lock();
+    if std::fs::create_dir_all(&ctx.data_dir).is_ok() {
+        let _ = editor.save_history(&history);
+    }
+    Ok(())
+}
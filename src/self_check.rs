@@ -0,0 +1,44 @@
+use std::fmt;
+
+/// Outcome of one integrity check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = if self.passed { "ok" } else { "FAILED" };
+        write!(f, "[{}] {}", status, self.name)?;
+        if !self.detail.is_empty() {
+            write!(f, ": {}", self.detail)?;
+        }
+        Ok(())
+    }
+}
+
+/// Results of a wallet self-check, in the order the checks ran.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelfCheckReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl SelfCheckReport {
+    pub fn record(&mut self, name: &'static str, passed: bool, detail: impl Into<String>) {
+        self.checks.push(CheckResult {
+            name,
+            passed,
+            detail: detail.into(),
+        });
+    }
+
+    pub fn extend(&mut self, other: SelfCheckReport) {
+        self.checks.extend(other.checks);
+    }
+
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+}
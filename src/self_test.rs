@@ -0,0 +1,194 @@
+//! Known-answer tests for the cryptography a release build relies on. They
+//! need no network or data dir, so a downloaded binary can be checked on
+//! an air-gapped machine before it is trusted with keys.
+
+use crate::address::{generate_address, validate_address};
+use crate::network::NetworkConfig;
+use crate::self_check::SelfCheckReport;
+use crate::transaction::Transaction;
+use crate::wallet::KaspaWallet;
+use bip39::Mnemonic;
+use secp256k1::{ecdsa, Keypair, Message, PublicKey, Secp256k1, SecretKey};
+use sha2::{Digest, Sha256};
+
+/// BIP39 reference vector: the all-`abandon` phrase with passphrase TREZOR.
+const BIP39_PHRASE: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+const BIP39_SEED: &str = "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04";
+/// First receive address of the wallet restored from `BIP39_SEED`.
+const HD_ADDRESS: &str = "kaspa:1Nv8CCpz1ZM2gzdZ8uM2t3cJHeT8i17nPw";
+
+/// Secret key 0x01…01 and its address on every network.
+const KEY: [u8; 32] = [1; 32];
+const KEY_ADDRESSES: [(&str, &str); 4] = [
+    ("mainnet", "kaspa:1C6Rc3w25VHud3dLDamutaqfKWqhrLRTaD"),
+    ("testnet-10", "kaspatest:1C6Rc3w25VHud3dLDamutaqfKWqhrLRTaD"),
+    ("testnet-11", "kaspatest:1C6Rc3w25VHud3dLDamutaqfKWqhrLRTaD"),
+    ("simnet", "kaspasim:1C6Rc3w25VHud3dLDamutaqfKWqhrLRTaD"),
+];
+
+/// BIP340 vector 0: secret key 3, zero message and auxiliary randomness.
+const SCHNORR_SECRET_KEY: &str = "0000000000000000000000000000000000000000000000000000000000000003";
+const SCHNORR_SIGNATURE: &str = "e907831f80848d1069a5371b402410364bdf1c5f8307b0084c55f1ce2dca821525f66a4a85ea8b71e482a74f382d2ce5ebeee8fdb2172f477df4900d310536c0";
+
+/// The fixed transaction: its txid, RFC 6979 signature of input 0 by
+/// `KEY`, and the SHA-256 of its signed wire encoding.
+const TX_TXID: &str = "32ce52091dd690a7e7b0492dcea69f4737934c5117876aa8bcfc41af4dc8418e";
+const TX_SIGNATURE: &str = "3044022063614d6e87fbb222edd306e1e7359ad3fc482ec834dcc5aa48451be1dda107d902205881cfb6e72fa601fc0475b125dae1824d7ecdb1fe25190762d849c4e44280c4";
+const TX_ENCODING_SHA256: &str = "16309f01a380ceb436f9d02f7e0b429db8171c9957f1673fb1aa0dd8dc50a113";
+
+/// Runs every vector group; one result per group, in a fixed order.
+pub fn run() -> SelfCheckReport {
+    let mut report = SelfCheckReport::default();
+    let (passed, detail) = outcome(bip39_seed());
+    report.record("BIP39 seed derivation", passed, detail);
+    let (passed, detail) = outcome(addresses());
+    report.record("key to address", passed, detail);
+    let (passed, detail) = outcome(signing());
+    report.record("signing", passed, detail);
+    let (passed, detail) = outcome(transaction());
+    report.record("transaction serialization", passed, detail);
+    let (passed, detail) = outcome(checksum());
+    report.record("address checksum", passed, detail);
+    report
+}
+
+/// A group either passes or names the first value that differed.
+type Group = Result<(), String>;
+
+fn outcome(result: Group) -> (bool, String) {
+    match result {
+        Ok(()) => (true, String::new()),
+        Err(detail) => (false, detail),
+    }
+}
+
+fn expect(what: &str, actual: &str, expected: &str) -> Group {
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(format!("{}: expected {}, got {}", what, expected, actual))
+    }
+}
+
+fn bip39_seed() -> Group {
+    let mnemonic = Mnemonic::parse(BIP39_PHRASE).map_err(|e| e.to_string())?;
+    let seed = mnemonic.to_seed("TREZOR");
+    expect("seed", &hex::encode(seed), BIP39_SEED)?;
+
+    let seed = mnemonic.to_seed("");
+    let wallet = KaspaWallet::from_seed(&seed, "mainnet").map_err(|e| e.to_string())?;
+    expect("HD receive address 0", &wallet.get_address(), HD_ADDRESS)
+}
+
+fn addresses() -> Group {
+    let secp = Secp256k1::new();
+    let secret_key = SecretKey::from_slice(&KEY).map_err(|e| e.to_string())?;
+    let public_key = PublicKey::from_secret_key(&secp, &secret_key);
+    for (network, expected) in KEY_ADDRESSES {
+        let config = NetworkConfig::from_name(network).map_err(|e| e.to_string())?;
+        let address = generate_address(&public_key, config.get_prefix());
+        expect(network, &address, expected)?;
+    }
+    Ok(())
+}
+
+fn fixed_transaction() -> Transaction {
+    let mut tx = Transaction::new();
+    tx.add_input("11".repeat(32), 0);
+    tx.add_input("22".repeat(32), 3);
+    tx.add_output(KEY_ADDRESSES[0].1.to_string(), 150_000_000);
+    tx.add_output(KEY_ADDRESSES[0].1.to_string(), 1);
+    tx.inputs[0].amount = Some(200_000_000);
+    tx
+}
+
+fn signing() -> Group {
+    let secp = Secp256k1::new();
+    let secret_key =
+        SecretKey::from_slice(&hex::decode(SCHNORR_SECRET_KEY).map_err(|e| e.to_string())?)
+            .map_err(|e| e.to_string())?;
+    let keypair = Keypair::from_secret_key(&secp, &secret_key);
+    let message = Message::from_digest([0; 32]);
+    let signature = secp.sign_schnorr_with_aux_rand(&message, &keypair, &[0; 32]);
+    expect(
+        "schnorr signature",
+        &signature.to_string(),
+        SCHNORR_SIGNATURE,
+    )?;
+
+    let secret_key = SecretKey::from_slice(&KEY).map_err(|e| e.to_string())?;
+    let public_key = PublicKey::from_secret_key(&secp, &secret_key);
+    let mut tx = fixed_transaction();
+    tx.sign_input(0, &secret_key, &public_key)
+        .map_err(|e| e.to_string())?;
+    let der = tx.inputs[0].signature.clone().unwrap_or_default();
+    expect("ECDSA input signature", &der, TX_SIGNATURE)?;
+
+    let digest = tx.get_signature_hash(0).map_err(|e| e.to_string())?;
+    let message = Message::from_digest_slice(&digest).map_err(|e| e.to_string())?;
+    let signature = ecdsa::Signature::from_der(&hex::decode(&der).map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+    secp.verify_ecdsa(&message, &signature, &public_key)
+        .map_err(|_| "ECDSA input signature does not verify".to_string())
+}
+
+fn transaction() -> Group {
+    let tx = fixed_transaction();
+    expect("txid", &tx.txid().map_err(|e| e.to_string())?, TX_TXID)?;
+
+    let secp = Secp256k1::new();
+    let secret_key = SecretKey::from_slice(&KEY).map_err(|e| e.to_string())?;
+    let mut signed = tx.clone();
+    signed
+        .sign_input(
+            0,
+            &secret_key,
+            &PublicKey::from_secret_key(&secp, &secret_key),
+        )
+        .map_err(|e| e.to_string())?;
+    let encoded = signed.encode().map_err(|e| e.to_string())?;
+    expect(
+        "encoding",
+        &hex::encode(Sha256::digest(&encoded)),
+        TX_ENCODING_SHA256,
+    )?;
+
+    let decoded = Transaction::decode(&encoded).map_err(|e| e.to_string())?;
+    expect(
+        "decoded txid",
+        &decoded.txid().map_err(|e| e.to_string())?,
+        TX_TXID,
+    )
+}
+
+fn checksum() -> Group {
+    let (_, address) = KEY_ADDRESSES[0];
+    if !matches!(validate_address(address), Ok(true)) {
+        return Err(format!("{} was rejected", address));
+    }
+    // Change one base58 character in the payload; the checksum must catch it.
+    let (prefix, payload) = address.split_once(':').unwrap_or_default();
+    let mut corrupted: Vec<char> = payload.chars().collect();
+    let middle = corrupted.len() / 2;
+    corrupted[middle] = if corrupted[middle] == '2' { '3' } else { '2' };
+    let corrupted = format!("{}:{}", prefix, corrupted.into_iter().collect::<String>());
+    if !matches!(validate_address(&corrupted), Ok(false)) {
+        return Err(format!("corrupted address {} was accepted", corrupted));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vectors_pass() {
+        let report = run();
+        for check in &report.checks {
+            assert!(check.passed, "{}", check);
+        }
+        assert_eq!(report.checks.len(), 5);
+    }
+}
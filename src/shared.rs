@@ -0,0 +1,197 @@
+use crate::error::{WalletError, WalletResult};
+use crate::lockable::LockableWallet;
+use crate::transaction::Transaction;
+use crate::utxo::UtxoSet;
+use crate::wallet::KaspaWallet;
+use std::sync::Arc;
+use tokio::sync::{watch, RwLock};
+use zeroize::Zeroizing;
+
+// Handles are moved into background tasks, so everything they hold must be
+// shareable across threads.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<KaspaWallet>();
+    assert_send_sync::<LockableWallet>();
+};
+
+/// What changed in a `SharedWallet`; sent on its notification channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalletChange {
+    Opened,
+    Locked,
+    Unlocked,
+    UtxosUpdated,
+}
+
+struct State {
+    wallet: LockableWallet,
+    utxos: UtxoSet,
+}
+
+/// Cloneable handle to one wallet for UIs and background tasks. Readers
+/// share the lock; the slow part of unlocking (key derivation) runs outside
+/// it, so readers are never held up by a password check.
+#[derive(Clone)]
+pub struct SharedWallet {
+    state: Arc<RwLock<State>>,
+    changes: watch::Sender<WalletChange>,
+}
+
+impl SharedWallet {
+    pub fn new(wallet: LockableWallet) -> Self {
+        let (changes, _) = watch::channel(WalletChange::Opened);
+        Self {
+            state: Arc::new(RwLock::new(State {
+                wallet,
+                utxos: UtxoSet::new(),
+            })),
+            changes,
+        }
+    }
+
+    /// Receiver that wakes whenever the lock state or UTXO set changes.
+    pub fn subscribe(&self) -> watch::Receiver<WalletChange> {
+        self.changes.subscribe()
+    }
+
+    fn notify(&self, change: WalletChange) {
+        self.changes.send_replace(change);
+    }
+
+    pub async fn address(&self) -> WalletResult<String> {
+        let state = self.state.read().await;
+        match state.wallet.keystore() {
+            Some(keystore) => Ok(keystore.address.clone()),
+            None => Ok(state.wallet.wallet()?.get_address()),
+        }
+    }
+
+    /// Sum of the known UTXOs in sompi; available while locked.
+    pub async fn balance(&self) -> u64 {
+        self.state.read().await.utxos.balance()
+    }
+
+    pub async fn utxos(&self) -> UtxoSet {
+        self.state.read().await.utxos.clone()
+    }
+
+    pub async fn set_utxos(&self, utxos: UtxoSet) {
+        self.state.write().await.utxos = utxos;
+        self.notify(WalletChange::UtxosUpdated);
+    }
+
+    pub async fn is_locked(&self) -> bool {
+        self.state.read().await.wallet.is_locked()
+    }
+
+    /// Signs `tx`, each input spending a known UTXO with the key of its
+    /// address.
+    pub async fn sign(&self, mut tx: Transaction, fee_rate: u64) -> WalletResult<Transaction> {
+        let state = self.state.read().await;
+        for input in tx.inputs.iter_mut().filter(|input| input.script.is_none()) {
+            if let Some(utxo) = state.utxos.get(&input.txid, input.vout) {
+                input.set_spent_address(&utxo.address)?;
+            }
+        }
+        state.wallet.wallet()?.sign_transaction(&mut tx, fee_rate)?;
+        Ok(tx)
+    }
+
+    pub async fn lock(&self) {
+        self.state.write().await.wallet.lock();
+        self.notify(WalletChange::Locked);
+    }
+
+    pub async fn unlock(&self, password: &str) -> WalletResult<()> {
+        let Some(keystore) = self.state.read().await.wallet.keystore().cloned() else {
+            // No keystore: let the wallet report why it can't be unlocked.
+            return self.state.write().await.wallet.unlock(password);
+        };
+
+        let password = Zeroizing::new(password.to_string());
+        let wallet = tokio::task::spawn_blocking(move || keystore.decrypt(&password))
+            .await
+            .map_err(|e| WalletError::Storage(format!("Unlock task failed: {}", e)))??;
+
+        self.state.write().await.wallet.set_unlocked(wallet)?;
+        self.notify(WalletChange::Unlocked);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keystore::Keystore;
+    use crate::utxo::Utxo;
+    use secp256k1::Secp256k1;
+    use std::path::PathBuf;
+
+    const PASSWORD: &str = "correct horse battery staple";
+
+    fn shared() -> (SharedWallet, String) {
+        let secp = Secp256k1::new();
+        let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let wallet = KaspaWallet::with_network(secret_key, "mainnet").unwrap();
+        let keystore = Keystore::encrypt(&wallet, "main", PASSWORD).unwrap();
+        let lockable = LockableWallet::from_keystore(keystore, PathBuf::from("x"), None);
+        (SharedWallet::new(lockable), wallet.get_address())
+    }
+
+    #[tokio::test]
+    async fn test_notifications() {
+        let (wallet, _) = shared();
+        let mut changes = wallet.subscribe();
+
+        wallet
+            .set_utxos(UtxoSet::from_iter([Utxo {
+                txid: "aa".repeat(32),
+                vout: 0,
+                address: "kaspa:abc".to_string(),
+                amount: 500,
+                block_daa_score: 0,
+                is_coinbase: false,
+            }]))
+            .await;
+        changes.changed().await.unwrap();
+        assert_eq!(*changes.borrow_and_update(), WalletChange::UtxosUpdated);
+        assert_eq!(wallet.balance().await, 500);
+
+        wallet.unlock(PASSWORD).await.unwrap();
+        changes.changed().await.unwrap();
+        assert_eq!(*changes.borrow_and_update(), WalletChange::Unlocked);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_lock_unlock_race() {
+        let (wallet, address) = shared();
+
+        let mut readers = Vec::new();
+        for _ in 0..8 {
+            let wallet = wallet.clone();
+            let address = address.clone();
+            readers.push(tokio::spawn(async move {
+                for _ in 0..200 {
+                    assert_eq!(wallet.address().await.unwrap(), address);
+                    match wallet.sign(Transaction::new(), 1000).await {
+                        Ok(_) | Err(WalletError::Locked) => {}
+                        Err(e) => panic!("unexpected error: {}", e),
+                    }
+                    tokio::task::yield_now().await;
+                }
+            }));
+        }
+
+        for _ in 0..3 {
+            wallet.unlock(PASSWORD).await.unwrap();
+            assert!(!wallet.is_locked().await);
+            wallet.lock().await;
+            assert!(wallet.is_locked().await);
+        }
+
+        for reader in readers {
+            reader.await.unwrap();
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::error::{WalletError, WalletResult};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Whether a write may replace an existing file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overwrite {
+    Refuse,
+    Allow,
+}
+
+impl From<bool> for Overwrite {
+    fn from(force: bool) -> Self {
+        if force {
+            Overwrite::Allow
+        } else {
+            Overwrite::Refuse
+        }
+    }
+}
+
+/// Writes `contents` to `path` so that readers see either the old file or
+/// the complete new one: the data goes to a temp file in the same directory,
+/// is fsynced, and is renamed over the target. On Unix the file is created
+/// readable by the owner only.
+pub fn write_atomic(path: &Path, contents: &[u8], overwrite: Overwrite) -> WalletResult<()> {
+    write_atomic_with(path, contents, overwrite, |_| Ok(()))
+}
+
+fn temp_path(path: &Path) -> WalletResult<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| WalletError::Storage(format!("Not a file path: {}", path.display())))?;
+    let mut temp = std::ffi::OsString::from(".");
+    temp.push(name);
+    temp.push(format!(".tmp-{}", std::process::id()));
+    Ok(path.with_file_name(temp))
+}
+
+fn create_private(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+/// `write_atomic` with a hook run between the fsync and the rename.
+fn write_atomic_with(
+    path: &Path,
+    contents: &[u8],
+    overwrite: Overwrite,
+    before_rename: impl FnOnce(&Path) -> io::Result<()>,
+) -> WalletResult<()> {
+    if overwrite == Overwrite::Refuse && path.exists() {
+        return Err(WalletError::Storage(format!(
+            "{} already exists (use --force to overwrite)",
+            path.display()
+        )));
+    }
+
+    let temp = temp_path(path)?;
+    let _ = fs::remove_file(&temp);
+    let result = (|| {
+        let mut file = create_private(&temp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        drop(file);
+        before_rename(&temp)?;
+        fs::rename(&temp, path)?;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            // Persist the rename itself; not supported on every platform.
+            let _ = File::open(dir).and_then(|dir| dir.sync_all());
+        }
+        Ok::<_, io::Error>(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    Ok(result?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("kasparustwallet-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_refuses_existing_file_without_force() {
+        let path = temp_dir("storage-force").join("wallet.txt");
+        write_atomic(&path, b"first", Overwrite::Refuse).unwrap();
+
+        assert!(write_atomic(&path, b"second", Overwrite::Refuse).is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"first");
+
+        write_atomic(&path, b"second", Overwrite::Allow).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_failure_before_rename_keeps_original() {
+        let dir = temp_dir("storage-crash");
+        let path = dir.join("wallet.txt");
+        write_atomic(&path, b"original", Overwrite::Refuse).unwrap();
+
+        let result = write_atomic_with(&path, b"replacement", Overwrite::Allow, |temp| {
+            assert_eq!(fs::read(temp).unwrap(), b"replacement");
+            Err(io::Error::other("simulated crash"))
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"original");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+}
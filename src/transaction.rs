@@ -1,7 +1,11 @@
-use crate::error::WalletError;
+use crate::address::{self, validate_address};
+use crate::amount::SOMPI_PER_KAS;
+use crate::error::{WalletError, WalletResult};
+use crate::utxo::UtxoEntry;
 use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::fmt;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TxInput {
@@ -9,6 +13,58 @@ pub struct TxInput {
     pub vout: u32,
     pub signature: Option<String>,
     pub public_key: Option<String>,
+    /// Value of the output being spent, when known.
+    #[serde(default)]
+    pub amount: Option<u64>,
+    /// Hex script public key of the output being spent, when known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+}
+
+/// Subnetwork of ordinary transactions, as a node expects it in hex.
+pub const NATIVE_SUBNETWORK_ID: &str = "0000000000000000000000000000000000000000";
+/// Sequence of an input with no relative lock time.
+pub const MAX_SEQUENCE: u64 = u64::MAX;
+/// Largest push a single opcode byte can announce the length of.
+const MAX_DIRECT_PUSH: usize = 75;
+const OP_PUSHDATA1: u8 = 0x4c;
+
+impl TxInput {
+    /// The script unlocking the spent P2PKH output: pushes of the DER
+    /// signature and then the public key.
+    pub fn signature_script(&self) -> WalletResult<Vec<u8>> {
+        let (Some(signature), Some(public_key)) = (&self.signature, &self.public_key) else {
+            return Err(WalletError::InvalidParameters(format!(
+                "Input {}:{} is not signed",
+                self.txid, self.vout
+            )));
+        };
+        let mut script = Vec::new();
+        for data in [signature, public_key] {
+            let data = hex::decode(data).map_err(|_| {
+                WalletError::InvalidParameters(format!(
+                    "Input {}:{} has a signature or key that is not hex",
+                    self.txid, self.vout
+                ))
+            })?;
+            if data.len() > MAX_DIRECT_PUSH {
+                script.push(OP_PUSHDATA1);
+            }
+            script.push(data.len() as u8);
+            script.extend_from_slice(&data);
+        }
+        Ok(script)
+    }
+
+    /// Records the address the spent output pays, so the wallet knows which
+    /// key signs the input.
+    pub fn set_spent_address(&mut self, address: &str) -> WalletResult<()> {
+        let script = address::script_public_key(address).map_err(|e| {
+            WalletError::InvalidParameters(format!("Input address {}: {}", address, e))
+        })?;
+        self.script = Some(hex::encode(script));
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +73,15 @@ pub struct TxOutput {
     pub amount: u64,
 }
 
+impl TxOutput {
+    /// The P2PKH script locking the output to its address.
+    pub fn script_public_key(&self) -> WalletResult<Vec<u8>> {
+        address::script_public_key(&self.address).map_err(|e| {
+            WalletError::InvalidParameters(format!("Output address {}: {}", self.address, e))
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
     pub version: u32,
@@ -25,6 +90,12 @@ pub struct Transaction {
     pub lock_time: u32,
 }
 
+impl Default for Transaction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Transaction {
     pub fn new() -> Self {
         Self {
@@ -41,6 +112,8 @@ impl Transaction {
             vout,
             signature: None,
             public_key: None,
+            amount: None,
+            script: None,
         });
     }
 
@@ -73,6 +146,38 @@ impl Transaction {
         Ok(buffer)
     }
 
+    /// Hash of the serialized transaction; signatures are not serialized so
+    /// the id is stable across signing.
+    pub fn txid(&self) -> Result<String, WalletError> {
+        let serialized = self.serialize()?;
+        Ok(hex::encode(Sha256::digest(&serialized)))
+    }
+
+    pub fn total_output_amount(&self) -> u64 {
+        self.outputs.iter().map(|output| output.amount).sum()
+    }
+
+    /// Why a nonzero lock time may keep the transaction out of the
+    /// mempool: it is a DAA score the network has not reached, or one that
+    /// cannot be checked without the node's virtual DAA score.
+    pub fn lock_time_warning(&self, virtual_daa_score: Option<u64>) -> Option<String> {
+        let lock_time = u64::from(self.lock_time);
+        match virtual_daa_score {
+            _ if lock_time == 0 => None,
+            Some(score) if score >= lock_time => None,
+            Some(score) => Some(format!(
+                "Locked until DAA score {}, {} past the current {}; nodes will reject it until then",
+                lock_time,
+                lock_time - score,
+                score
+            )),
+            None => Some(format!(
+                "Locked until DAA score {}; without a node the current score is unknown",
+                lock_time
+            )),
+        }
+    }
+
     pub fn get_signature_hash(&self, input_index: usize) -> Result<Vec<u8>, WalletError> {
         let mut tx_copy = self.clone();
 
@@ -117,17 +222,382 @@ impl Transaction {
         Ok(())
     }
 
-    pub fn estimate_fee(&self, fee_rate: u64) -> u64 {
+    /// Wire encoding: the signed-over `serialize` bytes, then for each input
+    /// its signature, public key and (optional) spent amount.
+    pub fn encode(&self) -> WalletResult<Vec<u8>> {
+        let mut buffer = self.serialize()?;
+
+        for input in &self.inputs {
+            for field in [&input.signature, &input.public_key] {
+                let bytes = match field {
+                    Some(hex_str) => hex::decode(hex_str)?,
+                    None => Vec::new(),
+                };
+                let len = u8::try_from(bytes.len()).map_err(|_| {
+                    WalletError::Transaction("Input witness field too long".to_string())
+                })?;
+                buffer.push(len);
+                buffer.extend_from_slice(&bytes);
+            }
+            match input.amount {
+                Some(amount) => {
+                    buffer.push(1);
+                    buffer.extend_from_slice(&amount.to_le_bytes());
+                }
+                None => buffer.push(0),
+            }
+        }
+
+        Ok(buffer)
+    }
+
+    /// Parses `encode` output. Bare `serialize` output (no witness section)
+    /// is accepted as an unsigned transaction.
+    pub fn decode(bytes: &[u8]) -> WalletResult<Self> {
+        let mut reader = Reader { bytes, offset: 0 };
+        let mut tx = Transaction::new();
+
+        tx.version = u32::from_le_bytes(reader.array("version")?);
+        let input_count = reader.byte("input count")?;
+        for _ in 0..input_count {
+            let txid = hex::encode(reader.take(TXID_LEN, "input txid")?);
+            let vout = u32::from_le_bytes(reader.array("input index")?);
+            tx.add_input(txid, vout);
+        }
+
+        let output_count = reader.byte("output count")?;
+        for _ in 0..output_count {
+            let len = reader.byte("address length")? as usize;
+            let at = reader.offset;
+            let address =
+                String::from_utf8(reader.take(len, "address")?.to_vec()).map_err(|_| {
+                    WalletError::Serialization(format!(
+                        "Address at byte offset {} is not UTF-8",
+                        at
+                    ))
+                })?;
+            let amount = u64::from_le_bytes(reader.array("amount")?);
+            tx.add_output(address, amount);
+        }
+        tx.lock_time = u32::from_le_bytes(reader.array("lock time")?);
+
+        if !reader.is_empty() {
+            for input in &mut tx.inputs {
+                let len = reader.byte("signature length")? as usize;
+                let signature = reader.take(len, "signature")?;
+                input.signature = (!signature.is_empty()).then(|| hex::encode(signature));
+                let len = reader.byte("public key length")? as usize;
+                let public_key = reader.take(len, "public key")?;
+                input.public_key = (!public_key.is_empty()).then(|| hex::encode(public_key));
+                input.amount = match reader.byte("amount flag")? {
+                    0 => None,
+                    1 => Some(u64::from_le_bytes(reader.array("input amount")?)),
+                    flag => {
+                        return Err(WalletError::Serialization(format!(
+                            "Invalid amount flag {} at byte offset {}",
+                            flag,
+                            reader.offset - 1
+                        )))
+                    }
+                };
+            }
+        }
+
+        if !reader.is_empty() {
+            return Err(WalletError::Serialization(format!(
+                "Unexpected trailing data at byte offset {}",
+                reader.offset
+            )));
+        }
+        Ok(tx)
+    }
+
+    /// `decode` from hex, reporting bad characters by byte offset.
+    pub fn from_hex(hex_str: &str) -> WalletResult<Self> {
+        let bytes = hex::decode(hex_str.trim()).map_err(|e| match e {
+            hex::FromHexError::InvalidHexCharacter { c, index } => WalletError::Serialization(
+                format!("Invalid hex character {:?} at byte offset {}", c, index / 2),
+            ),
+            hex::FromHexError::OddLength => {
+                WalletError::Serialization("Transaction hex has an odd length".to_string())
+            }
+            e => WalletError::Serialization(e.to_string()),
+        })?;
+        Self::decode(&bytes)
+    }
+
+    /// Fee paid, when every input's spent amount is known.
+    pub fn fee(&self) -> Option<u64> {
+        let total_in = self
+            .inputs
+            .iter()
+            .map(|input| input.amount)
+            .sum::<Option<u64>>()?;
+        total_in.checked_sub(self.total_output_amount())
+    }
+
+    /// Mass of the signed transaction: its estimated size plus a fixed
+    /// charge per signature operation (one per input).
+    pub fn mass(&self) -> u64 {
+        self.estimated_size() * MASS_PER_TX_BYTE + self.inputs.len() as u64 * MASS_PER_SIG_OP
+    }
+
+    fn estimated_size(&self) -> u64 {
         let base_size = 10;
         let input_size = 32 + 4 + 73 + 33;
         let output_size = 8 + 1 + 34;
 
-        let total_size =
-            base_size + (self.inputs.len() * input_size) + (self.outputs.len() * output_size);
-        (total_size as u64 + 999) / 1000 * fee_rate
+        (base_size + (self.inputs.len() * input_size) + (self.outputs.len() * output_size)) as u64
+    }
+
+    /// Fee at `fee_rate` sompi per 1000 mass, charged on the full mass:
+    /// size, signature operations and, with known input amounts, storage.
+    pub fn estimate_fee(&self, fee_rate: u64) -> u64 {
+        self.mass_breakdown().fee(fee_rate)
+    }
+
+    /// Sets the last output, the change, to what `total_in` leaves after
+    /// the other outputs and the fee, and returns it. A smaller change
+    /// costs more storage mass, so the fee is priced again until the two
+    /// agree; each round can only lower the change, so this ends.
+    pub fn settle_change(&mut self, total_in: u64, fee: FeeMode) -> WalletResult<u64> {
+        let Some(last) = self.outputs.last() else {
+            return Err(WalletError::InvalidParameters(
+                "No change output to settle".to_string(),
+            ));
+        };
+        let others = self.total_output_amount() - last.amount;
+        let mut change = total_in
+            .checked_sub(others)
+            .ok_or(WalletError::InsufficientBalance)?;
+        loop {
+            self.outputs.last_mut().expect("change output").amount = change;
+            let settled = others
+                .checked_add(fee.fee(self))
+                .and_then(|needed| total_in.checked_sub(needed))
+                .ok_or(WalletError::InsufficientBalance)?;
+            if settled == change {
+                return Ok(change);
+            }
+            change = settled;
+        }
+    }
+
+    /// Lowest fee a node will relay this transaction for.
+    pub fn minimum_relay_fee(&self) -> u64 {
+        self.mass_breakdown().fee(MINIMUM_RELAY_FEE_RATE)
+    }
+
+    /// Size and both kinds of mass. Storage mass needs every input amount
+    /// and is `None` when any is unknown.
+    pub fn mass_breakdown(&self) -> MassBreakdown {
+        let input_amounts = self
+            .inputs
+            .iter()
+            .map(|input| input.amount)
+            .collect::<Option<Vec<u64>>>();
+        MassBreakdown {
+            size: self.estimated_size(),
+            compute_mass: self.mass(),
+            storage_mass: input_amounts.map(|inputs| {
+                let outputs: Vec<u64> = self.outputs.iter().map(|o| o.amount).collect();
+                storage_mass(&inputs, &outputs)
+            }),
+        }
     }
 }
 
+/// Storage mass (KIP-9): `C * (sum(1 / output) - inputs^2 / sum(input))`,
+/// floored at zero. Many small outputs make it large.
+pub fn storage_mass(inputs: &[u64], outputs: &[u64]) -> u64 {
+    let c = STORAGE_MASS_PARAMETER as u128;
+    let output_term = outputs
+        .iter()
+        .map(|&amount| c.checked_div(amount as u128).unwrap_or(u64::MAX as u128))
+        .sum::<u128>();
+    let total_in = inputs.iter().map(|&amount| amount as u128).sum::<u128>();
+    let count = inputs.len() as u128;
+    let input_term = (c * count * count).checked_div(total_in).unwrap_or(0);
+    output_term.saturating_sub(input_term).min(u64::MAX as u128) as u64
+}
+
+/// Largest amount one more output can carry when `inputs` pay for it and
+/// `outputs` with no change, the fee at `fee_rate` included. `None` when
+/// nothing is left once the fee is paid.
+pub fn max_sendable(inputs: &[u64], outputs: &[u64], fee_rate: u64) -> Option<u64> {
+    let total_in = inputs
+        .iter()
+        .try_fold(0u64, |sum, &amount| sum.checked_add(amount))?;
+    let total_out = outputs
+        .iter()
+        .try_fold(0u64, |sum, &amount| sum.checked_add(amount))?;
+    let available = total_in.checked_sub(total_out)?;
+    let mut tx = Transaction::new();
+    for &amount in inputs {
+        tx.add_input(String::new(), 0);
+        tx.inputs.last_mut().expect("input just added").amount = Some(amount);
+    }
+    for &amount in outputs {
+        tx.add_output(String::new(), amount);
+    }
+    tx.add_output(String::new(), available);
+    // Paying the fee shrinks the output, which can raise its storage mass
+    // and so the fee; repeat until the fee fits.
+    for _ in 0..32 {
+        let amount = available
+            .checked_sub(tx.mass_breakdown().fee(fee_rate))
+            .filter(|&amount| amount > 0)?;
+        let output = tx.outputs.last_mut().expect("output just added");
+        if output.amount == amount {
+            return Some(amount);
+        }
+        output.amount = amount;
+    }
+    None
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MassBreakdown {
+    pub size: u64,
+    pub compute_mass: u64,
+    pub storage_mass: Option<u64>,
+}
+
+impl MassBreakdown {
+    /// The mass nodes charge for: the larger of the two.
+    pub fn total(&self) -> u64 {
+        self.compute_mass.max(self.storage_mass.unwrap_or(0))
+    }
+
+    /// Fee at `fee_rate` sompi per 1000 mass.
+    pub fn fee(&self, fee_rate: u64) -> u64 {
+        self.total().div_ceil(1000) * fee_rate
+    }
+}
+
+/// How the fee of a transaction is chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeMode {
+    /// Sompi per 1000 mass.
+    Rate(u64),
+    /// A fixed fee in sompi, whatever the mass.
+    Absolute(u64),
+}
+
+impl FeeMode {
+    pub fn fee(&self, tx: &Transaction) -> u64 {
+        match *self {
+            FeeMode::Rate(fee_rate) => tx.mass_breakdown().fee(fee_rate),
+            FeeMode::Absolute(fee) => fee,
+        }
+    }
+}
+
+impl From<u64> for FeeMode {
+    fn from(fee_rate: u64) -> Self {
+        FeeMode::Rate(fee_rate)
+    }
+}
+
+impl fmt::Display for FeeMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeeMode::Rate(fee_rate) => write!(f, "rate, {} sompi per 1000 mass", fee_rate),
+            FeeMode::Absolute(_) => write!(f, "absolute"),
+        }
+    }
+}
+
+/// Named fee rates in sompi per 1000 mass, cheapest first.
+pub const FEE_RATE_PRESETS: [(&str, u64); 3] =
+    [("low", 1000), ("normal", 2000), ("priority", 5000)];
+
+pub(crate) const TXID_LEN: usize = 32;
+pub const MASS_PER_TX_BYTE: u64 = 1;
+pub const MASS_PER_SIG_OP: u64 = 1000;
+/// Fee rate, in sompi per 1000 mass, below which nodes don't relay.
+pub const MINIMUM_RELAY_FEE_RATE: u64 = 1000;
+/// Largest mass a node will relay for a standard transaction.
+pub const MAXIMUM_STANDARD_TRANSACTION_MASS: u64 = 100_000;
+pub const STORAGE_MASS_PARAMETER: u64 = SOMPI_PER_KAS * 10_000;
+/// Smallest output a standard transaction can carry: below it the output's
+/// storage mass alone exceeds [`MAXIMUM_STANDARD_TRANSACTION_MASS`].
+pub const DUST_THRESHOLD: u64 = STORAGE_MASS_PARAMETER / MAXIMUM_STANDARD_TRANSACTION_MASS;
+
+/// Cursor over encoded bytes whose errors name the failing offset.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize, what: &str) -> WalletResult<&'a [u8]> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len());
+        let Some(end) = end else {
+            return Err(WalletError::Serialization(format!(
+                "Transaction truncated: expected {} byte(s) of {} at byte offset {}",
+                len, what, self.offset
+            )));
+        };
+        let slice = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self, what: &str) -> WalletResult<[u8; N]> {
+        Ok(self.take(N, what)?.try_into().expect("slice has length N"))
+    }
+
+    fn byte(&mut self, what: &str) -> WalletResult<u8> {
+        Ok(self.take(1, what)?[0])
+    }
+
+    fn is_empty(&self) -> bool {
+        self.offset == self.bytes.len()
+    }
+}
+
+/// Validated but unsigned transaction spending `inputs` to `outputs`. Input
+/// amounts and scripts, where given, are kept on the inputs.
+pub fn build_unsigned_transaction(
+    inputs: Vec<UtxoEntry>,
+    outputs: Vec<(String, u64)>,
+) -> Result<Transaction, WalletError> {
+    let mut tx = Transaction::new();
+
+    for entry in inputs {
+        if hex::decode(&entry.txid).map(|bytes| bytes.len()) != Ok(TXID_LEN) {
+            return Err(WalletError::InvalidParameters(format!(
+                "Invalid txid {}: expected {} hex characters",
+                entry.txid,
+                TXID_LEN * 2
+            )));
+        }
+        tx.add_input(entry.txid, entry.vout);
+        let input = tx.inputs.last_mut().expect("input just added");
+        input.amount = entry.amount;
+        input.script = entry.script;
+    }
+
+    for (address, amount) in outputs {
+        if !validate_address(&address)? {
+            return Err(WalletError::InvalidAddressFormat);
+        }
+        tx.add_output(address, amount);
+    }
+
+    tracing::debug!(
+        inputs = tx.inputs.len(),
+        outputs = tx.outputs.len(),
+        total_out = tx.total_output_amount(),
+        "built unsigned transaction"
+    );
+    Ok(tx)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,6 +610,17 @@ mod tests {
         assert!(tx.outputs.is_empty());
     }
 
+    #[test]
+    fn test_lock_time_warning() {
+        let mut tx = Transaction::new();
+        assert_eq!(tx.lock_time_warning(None), None);
+        tx.lock_time = 5_000;
+        assert_eq!(tx.lock_time_warning(Some(5_000)), None);
+        let early = tx.lock_time_warning(Some(4_000)).unwrap();
+        assert!(early.contains("1000 past the current 4000"), "{}", early);
+        assert!(tx.lock_time_warning(None).unwrap().contains("unknown"));
+    }
+
     #[test]
     fn test_add_input_output() {
         let mut tx = Transaction::new();
@@ -149,4 +630,121 @@ mod tests {
         assert_eq!(tx.inputs.len(), 1);
         assert_eq!(tx.outputs.len(), 1);
     }
+
+    #[test]
+    fn test_mass_breakdown() {
+        let mut tx = Transaction::new();
+        tx.add_input("11".repeat(32), 0);
+        tx.add_output("kaspa:xyz".to_string(), SOMPI_PER_KAS);
+        assert_eq!(tx.mass_breakdown().storage_mass, None);
+        assert_eq!(tx.mass_breakdown().total(), tx.mass());
+
+        tx.inputs[0].amount = Some(2 * SOMPI_PER_KAS);
+        assert_eq!(tx.mass_breakdown().storage_mass, Some(5_000));
+
+        // Dust outputs are dominated by storage mass.
+        tx.outputs[0].amount = 1_000;
+        let breakdown = tx.mass_breakdown();
+        assert!(breakdown.storage_mass.unwrap() > breakdown.compute_mass);
+        assert_eq!(breakdown.total(), breakdown.storage_mass.unwrap());
+        assert_eq!(breakdown.fee(1000), breakdown.total().div_ceil(1000) * 1000);
+    }
+
+    #[test]
+    fn test_max_sendable() {
+        let inputs = [3 * SOMPI_PER_KAS, SOMPI_PER_KAS];
+        let max = max_sendable(&inputs, &[SOMPI_PER_KAS], 2000).unwrap();
+        let mut tx = Transaction::new();
+        for amount in inputs {
+            tx.add_input("11".repeat(32), 0);
+            tx.inputs.last_mut().unwrap().amount = Some(amount);
+        }
+        tx.add_output("kaspa:xyz".to_string(), SOMPI_PER_KAS);
+        tx.add_output("kaspa:xyz".to_string(), max);
+        assert_eq!(tx.fee(), Some(tx.mass_breakdown().fee(2000)));
+
+        // The fee alone is more than the input holds.
+        assert_eq!(max_sendable(&[1_000], &[], 1000), None);
+        assert_eq!(
+            max_sendable(&[SOMPI_PER_KAS], &[2 * SOMPI_PER_KAS], 1000),
+            None
+        );
+    }
+
+    #[test]
+    fn test_fee_mode() {
+        let mut tx = Transaction::new();
+        tx.add_input("11".repeat(32), 0);
+        tx.add_output("kaspa:xyz".to_string(), SOMPI_PER_KAS);
+        assert_eq!(FeeMode::from(2000).fee(&tx), tx.estimate_fee(2000));
+        assert_eq!(FeeMode::Absolute(10_000).fee(&tx), 10_000);
+        // One input and output: 1195 mass rounds up to two units of 1000.
+        assert_eq!(tx.minimum_relay_fee(), 2 * MINIMUM_RELAY_FEE_RATE);
+        // The default rate pays for signature operations, not size alone.
+        assert_eq!(
+            FeeMode::Rate(MINIMUM_RELAY_FEE_RATE).fee(&tx),
+            tx.minimum_relay_fee()
+        );
+        // With input amounts known, storage mass is charged too.
+        tx.inputs[0].amount = Some(SOMPI_PER_KAS + 1);
+        tx.outputs[0].amount = 1;
+        assert_eq!(tx.estimate_fee(1000), tx.mass_breakdown().fee(1000));
+        assert!(tx.estimate_fee(1000) > tx.mass().div_ceil(1000) * 1000);
+    }
+
+    #[test]
+    fn test_settle_change() {
+        let mut tx = Transaction::new();
+        tx.add_input("11".repeat(32), 0);
+        tx.inputs[0].amount = Some(10 * SOMPI_PER_KAS);
+        tx.add_output("kaspa:xyz".to_string(), SOMPI_PER_KAS);
+        tx.add_output("kaspa:change".to_string(), 0);
+        let change = tx
+            .settle_change(10 * SOMPI_PER_KAS, FeeMode::Rate(1000))
+            .unwrap();
+        assert_eq!(tx.outputs[1].amount, change);
+        assert_eq!(tx.fee(), Some(tx.estimate_fee(1000)));
+        assert!(tx.fee().unwrap() >= tx.minimum_relay_fee());
+
+        // Change so small its storage mass costs more than it holds.
+        tx.outputs[0].amount = 10 * SOMPI_PER_KAS - 1_000;
+        assert!(tx
+            .settle_change(10 * SOMPI_PER_KAS, FeeMode::Rate(1000))
+            .is_err());
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let secp = Secp256k1::new();
+        let (secret_key, public_key) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let mut tx = Transaction::new();
+        tx.add_input("11".repeat(32), 1);
+        tx.add_input("22".repeat(32), 0);
+        tx.add_output("kaspa:xyz".to_string(), 1000);
+        tx.inputs[0].amount = Some(1500);
+        tx.sign_input(0, &secret_key, &public_key).unwrap();
+
+        let decoded = Transaction::from_hex(&hex::encode(tx.encode().unwrap())).unwrap();
+        assert_eq!(decoded.txid().unwrap(), tx.txid().unwrap());
+        assert_eq!(decoded.inputs[0].signature, tx.inputs[0].signature);
+        assert_eq!(decoded.inputs[0].amount, Some(1500));
+        assert!(decoded.inputs[1].signature.is_none());
+        assert_eq!(decoded.fee(), None);
+
+        let unsigned = Transaction::decode(&tx.serialize().unwrap()).unwrap();
+        assert!(unsigned.inputs[0].signature.is_none());
+    }
+
+    #[test]
+    fn test_decode_errors_name_offset() {
+        let mut tx = Transaction::new();
+        tx.add_input("11".repeat(32), 0);
+        let bytes = tx.encode().unwrap();
+
+        let err = Transaction::decode(&bytes[..20]).unwrap_err().to_string();
+        assert!(err.contains("byte offset 5"), "{}", err);
+
+        let err = Transaction::from_hex("0100zz").unwrap_err().to_string();
+        assert!(err.contains("byte offset 2"), "{}", err);
+    }
 }
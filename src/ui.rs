@@ -0,0 +1,613 @@
+//! Terminal output. Colour switches itself off for `--no-color`, `NO_COLOR`
+//! and streams that aren't terminals; reports render as text or JSON.
+
+use kasparustwallet::amount::Kas;
+use kasparustwallet::error::WalletError;
+use kasparustwallet::history::{self, Direction, HistoryEntry};
+use kasparustwallet::self_check::SelfCheckReport;
+use kasparustwallet::transaction::Transaction;
+use serde::Serialize;
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use zeroize::Zeroizing;
+
+static STDOUT_COLOR: AtomicBool = AtomicBool::new(false);
+static STDERR_COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Decides, once per process, whether each stream gets colour.
+pub fn init(no_color: bool) {
+    let allowed = !no_color && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty());
+    STDOUT_COLOR.store(allowed && io::stdout().is_terminal(), Ordering::Relaxed);
+    STDERR_COLOR.store(allowed && io::stderr().is_terminal(), Ordering::Relaxed);
+}
+
+/// Whether stderr gets colour, for the log layer.
+pub fn stderr_color() -> bool {
+    STDERR_COLOR.load(Ordering::Relaxed)
+}
+
+#[derive(Clone, Copy)]
+enum Style {
+    Bold,
+    Dim,
+    Green,
+    Red,
+    Yellow,
+}
+
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Style::Bold => "1",
+            Style::Dim => "2",
+            Style::Green => "32",
+            Style::Red => "31",
+            Style::Yellow => "33",
+        }
+    }
+}
+
+/// A value shown in a style when its stream has colour. Width and
+/// alignment apply to the text, not the escape codes.
+pub struct Styled<T> {
+    value: T,
+    style: Style,
+    stderr: bool,
+}
+
+impl<T: fmt::Display> fmt::Display for Styled<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = self.value.to_string();
+        let enabled = if self.stderr {
+            &STDERR_COLOR
+        } else {
+            &STDOUT_COLOR
+        };
+        if !enabled.load(Ordering::Relaxed) {
+            return f.pad(&text);
+        }
+        write!(f, "\x1b[{}m", self.style.code())?;
+        f.pad(&text)?;
+        write!(f, "\x1b[0m")
+    }
+}
+
+fn styled<T>(value: T, style: Style) -> Styled<T> {
+    Styled {
+        value,
+        style,
+        stderr: false,
+    }
+}
+
+pub fn bold<T: fmt::Display>(value: T) -> Styled<T> {
+    styled(value, Style::Bold)
+}
+
+/// Secondary detail such as sompi amounts and indexes.
+pub fn dim<T: fmt::Display>(value: T) -> Styled<T> {
+    styled(value, Style::Dim)
+}
+
+pub fn green<T: fmt::Display>(value: T) -> Styled<T> {
+    styled(value, Style::Green)
+}
+
+pub fn red<T: fmt::Display>(value: T) -> Styled<T> {
+    styled(value, Style::Red)
+}
+
+pub fn yellow<T: fmt::Display>(value: T) -> Styled<T> {
+    styled(value, Style::Yellow)
+}
+
+/// Prints a `WARNING:` line on stderr.
+pub fn warn(message: impl fmt::Display) {
+    let warning = Styled {
+        value: format!("WARNING: {}", message),
+        style: Style::Yellow,
+        stderr: true,
+    };
+    eprintln!("{}", warning);
+}
+
+/// How a private key or seed phrase is printed. Only `--show-secrets`
+/// (`show`) prints it in full; otherwise a terminal sees it masked and a
+/// pipe or file sees nothing of it.
+pub fn display_secret(secret: &str, show: bool) -> Zeroizing<String> {
+    Zeroizing::new(if show {
+        secret.to_string()
+    } else if io::stdout().is_terminal() {
+        format!("{} (pass --show-secrets to reveal)", mask(secret))
+    } else {
+        "(hidden, pass --show-secrets)".to_string()
+    })
+}
+
+/// First and last four characters, e.g. `c0ff…beef`.
+fn mask(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() <= 8 {
+        return "…".to_string();
+    }
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", head, tail)
+}
+
+/// Command output that can be shown to a person or handed to a script.
+pub trait Report: Serialize {
+    fn render(&self, out: &mut dyn Write) -> io::Result<()>;
+}
+
+/// Writes `report` to stdout, as pretty JSON with `json`.
+pub fn emit(report: &impl Report, json: bool) -> Result<(), WalletError> {
+    let mut out = io::stdout().lock();
+    if json {
+        serde_json::to_writer_pretty(&mut out, report)
+            .map_err(|e| WalletError::Serialization(e.to_string()))?;
+        writeln!(out)?;
+    } else {
+        report.render(&mut out)?;
+    }
+    Ok(())
+}
+
+pub fn heading(out: &mut dyn Write, title: &str) -> io::Result<()> {
+    writeln!(out, "{}", bold(title))?;
+    writeln!(out, "==================")
+}
+
+/// `1.5 KAS (150000000 sompi)`, the sompi dimmed.
+fn kas_and_sompi(sompi: u64) -> String {
+    format!(
+        "{} KAS {}",
+        Kas::from_sompi(sompi),
+        dim(format!("({} sompi)", sompi))
+    )
+}
+
+#[derive(Serialize)]
+pub struct AddressValidity {
+    pub address: String,
+    pub valid: bool,
+}
+
+#[derive(Serialize)]
+#[serde(transparent)]
+pub struct ValidationReport {
+    pub results: Vec<AddressValidity>,
+}
+
+impl Report for ValidationReport {
+    fn render(&self, out: &mut dyn Write) -> io::Result<()> {
+        let verdict = |valid: bool| {
+            if valid {
+                green("valid")
+            } else {
+                red("invalid")
+            }
+        };
+        heading(out, "Address Validation:")?;
+        if let [result] = self.results.as_slice() {
+            writeln!(out, "Address: {}", result.address)?;
+            let valid = if result.valid {
+                green("true")
+            } else {
+                red("false")
+            };
+            return writeln!(out, "Valid: {}", valid);
+        }
+        for result in &self.results {
+            writeln!(out, "  {:<7}  {}", verdict(result.valid), result.address)?;
+        }
+        writeln!(
+            out,
+            "Valid: {} of {}",
+            self.results.iter().filter(|result| result.valid).count(),
+            self.results.len()
+        )
+    }
+}
+
+#[derive(Serialize)]
+pub struct UtxoRow {
+    pub outpoint: String,
+    pub address: String,
+    pub address_index: Option<u32>,
+    /// Whether `address_index` is on the change chain.
+    pub change: bool,
+    pub amount: u64,
+    pub amount_kas: String,
+    pub block_daa_score: u64,
+    /// None without a virtual DAA score to measure against.
+    pub age: Option<u64>,
+    pub is_coinbase: bool,
+    pub mature: bool,
+}
+
+#[derive(Serialize)]
+#[serde(transparent)]
+pub struct UtxoReport {
+    pub utxos: Vec<UtxoRow>,
+    /// Where the outputs came from when not the node; shown in text only,
+    /// as the JSON is a bare array.
+    #[serde(skip)]
+    pub third_party: Option<String>,
+}
+
+impl Report for UtxoReport {
+    fn render(&self, out: &mut dyn Write) -> io::Result<()> {
+        heading(out, "Unspent Outputs:")?;
+        for utxo in &self.utxos {
+            let index = utxo
+                .address_index
+                .map(|index| index.to_string())
+                .unwrap_or_else(|| "-".to_string());
+            let coinbase = match (utxo.is_coinbase, utxo.mature) {
+                (false, _) => String::from("        "),
+                (true, true) => dim("coinbase").to_string(),
+                (true, false) => yellow("coinbase").to_string(),
+            };
+            writeln!(
+                out,
+                "  {}  {:>20} KAS  {}  {}  {}",
+                utxo.outpoint,
+                utxo.amount_kas,
+                dim(match utxo.age {
+                    Some(age) => format!("age {:>8}", age),
+                    None => "age unknown ".to_string(),
+                }),
+                coinbase,
+                dim(format!(
+                    "{} {}",
+                    if utxo.change { "change" } else { "index" },
+                    index
+                ))
+            )?;
+        }
+        let total: u64 = self.utxos.iter().map(|utxo| utxo.amount).sum();
+        writeln!(out, "Count: {}", self.utxos.len())?;
+        writeln!(out, "Total: {}", kas_and_sompi(total))?;
+        if let Some(note) = &self.third_party {
+            writeln!(out, "{}", yellow(note))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+pub struct BalanceReport {
+    pub balance: u64,
+    pub balance_kas: String,
+    pub spendable: u64,
+    /// Immature coinbase outputs.
+    pub pending: u64,
+    pub utxo_count: usize,
+    /// None when no node or explorer has reported one.
+    pub virtual_daa_score: Option<u64>,
+    /// Sompi received and spent since the previous sync; None on the first.
+    pub received: Option<u64>,
+    pub spent: Option<u64>,
+    /// Where the figures came from when not the node.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub third_party: Option<String>,
+}
+
+impl Report for BalanceReport {
+    fn render(&self, out: &mut dyn Write) -> io::Result<()> {
+        heading(out, "Balance:")?;
+        writeln!(out, "  Total:     {}", kas_and_sompi(self.balance))?;
+        writeln!(out, "  Spendable: {}", kas_and_sompi(self.spendable))?;
+        if self.pending > 0 {
+            writeln!(
+                out,
+                "  Pending:   {} {}",
+                yellow(Kas::from_sompi(self.pending)),
+                dim("KAS of coinbase outputs not yet mature")
+            )?;
+        }
+        writeln!(out, "  UTXOs:     {}", self.utxo_count)?;
+        match self.virtual_daa_score {
+            Some(score) => writeln!(out, "  {}", dim(format!("As of DAA score {}", score)))?,
+            None => writeln!(out, "  {}", dim("DAA score unknown; ages not checked"))?,
+        }
+        if let Some(received) = self.received.filter(|&sompi| sompi > 0) {
+            writeln!(
+                out,
+                "{}",
+                green(format!(
+                    "Received {} KAS since last sync",
+                    Kas::from_sompi(received)
+                ))
+            )?;
+        }
+        if let Some(spent) = self.spent.filter(|&sompi| sompi > 0) {
+            writeln!(out, "Spent {} KAS since last sync", Kas::from_sompi(spent))?;
+        }
+        if let Some(note) = &self.third_party {
+            writeln!(out, "{}", yellow(note))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+pub struct DecodedInput {
+    pub txid: String,
+    pub vout: u32,
+    pub signed: bool,
+    pub amount: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct DecodedOutput {
+    pub address: String,
+    pub amount: u64,
+    pub amount_kas: String,
+}
+
+#[derive(Serialize)]
+pub struct DecodedTransaction {
+    pub txid: String,
+    pub version: u32,
+    pub inputs: Vec<DecodedInput>,
+    pub outputs: Vec<DecodedOutput>,
+    pub lock_time: u32,
+    pub mass: u64,
+    pub fee: Option<u64>,
+}
+
+impl DecodedTransaction {
+    pub fn new(tx: &Transaction) -> Result<Self, WalletError> {
+        Ok(Self {
+            txid: tx.txid()?,
+            version: tx.version,
+            inputs: tx
+                .inputs
+                .iter()
+                .map(|input| DecodedInput {
+                    txid: input.txid.clone(),
+                    vout: input.vout,
+                    signed: input.signature.is_some(),
+                    amount: input.amount,
+                })
+                .collect(),
+            outputs: tx
+                .outputs
+                .iter()
+                .map(|output| DecodedOutput {
+                    address: output.address.clone(),
+                    amount: output.amount,
+                    amount_kas: Kas::from_sompi(output.amount).to_string(),
+                })
+                .collect(),
+            lock_time: tx.lock_time,
+            mass: tx.mass(),
+            fee: tx.fee(),
+        })
+    }
+}
+
+impl Report for DecodedTransaction {
+    fn render(&self, out: &mut dyn Write) -> io::Result<()> {
+        heading(out, "Decoded Transaction:")?;
+        writeln!(out, "Txid: {}", self.txid)?;
+        writeln!(out, "Version: {}", self.version)?;
+        writeln!(out, "Inputs:")?;
+        for (i, input) in self.inputs.iter().enumerate() {
+            let amount = input
+                .amount
+                .map(|amount| format!(", {} KAS", Kas::from_sompi(amount)))
+                .unwrap_or_default();
+            let signed = if input.signed {
+                green("true")
+            } else {
+                yellow("false")
+            };
+            writeln!(
+                out,
+                "  {}: {}:{} (signed: {}{})",
+                i, input.txid, input.vout, signed, amount
+            )?;
+        }
+        writeln!(out, "Outputs:")?;
+        for (i, output) in self.outputs.iter().enumerate() {
+            writeln!(
+                out,
+                "  {}: {} {}",
+                i,
+                output.address,
+                kas_and_sompi(output.amount)
+            )?;
+        }
+        writeln!(out, "Lock Time: {}", self.lock_time)?;
+        writeln!(out, "Mass: {}", self.mass)?;
+        if let Some(fee) = self.fee {
+            writeln!(out, "Fee: {}", kas_and_sompi(fee))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(transparent)]
+pub struct HistoryReport {
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl HistoryReport {
+    fn signed_kas(sompi: i64) -> String {
+        let sign = if sompi < 0 { "-" } else { "+" };
+        format!("{}{}", sign, Kas::from_sompi(sompi.unsigned_abs()))
+    }
+
+    fn status(accepted: bool) -> &'static str {
+        if accepted {
+            "confirmed"
+        } else {
+            "pending"
+        }
+    }
+
+    fn direction(direction: Direction) -> &'static str {
+        match direction {
+            Direction::In => "in",
+            Direction::Out => "out",
+        }
+    }
+
+    /// Plain CSV for spreadsheets; never coloured.
+    pub fn csv(&self) -> String {
+        let mut csv = String::from("time,txid,direction,net_kas,status\n");
+        for entry in &self.entries {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                history::format_time(entry.time),
+                entry.txid,
+                Self::direction(entry.direction),
+                Self::signed_kas(entry.net_sompi),
+                Self::status(entry.accepted)
+            ));
+        }
+        csv
+    }
+}
+
+impl Report for HistoryReport {
+    fn render(&self, out: &mut dyn Write) -> io::Result<()> {
+        heading(out, "History:")?;
+        for entry in &self.entries {
+            let net = Self::signed_kas(entry.net_sompi);
+            let net = match entry.direction {
+                Direction::In => green(format!("{:>20}", net)),
+                Direction::Out => red(format!("{:>20}", net)),
+            };
+            let status = if entry.accepted {
+                dim(Self::status(true))
+            } else {
+                yellow(Self::status(false))
+            };
+            writeln!(
+                out,
+                "{}  {}  {:<3}  {} KAS  {}",
+                dim(history::format_time(entry.time)),
+                entry.txid,
+                Self::direction(entry.direction),
+                net,
+                status
+            )?;
+        }
+        if self.entries.is_empty() {
+            writeln!(out, "No transactions")?;
+        }
+        Ok(())
+    }
+}
+
+/// Results of `self-test` or `wallet check`.
+#[derive(Serialize)]
+pub struct CheckReport {
+    #[serde(skip)]
+    pub title: &'static str,
+    /// Closing line when every check passed.
+    #[serde(skip)]
+    pub success: &'static str,
+    pub checks: Vec<CheckRow>,
+    pub passed: bool,
+}
+
+#[derive(Serialize)]
+pub struct CheckRow {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl CheckReport {
+    pub fn new(title: &'static str, success: &'static str, report: &SelfCheckReport) -> Self {
+        Self {
+            title,
+            success,
+            checks: report
+                .checks
+                .iter()
+                .map(|check| CheckRow {
+                    name: check.name,
+                    passed: check.passed,
+                    detail: check.detail.clone(),
+                })
+                .collect(),
+            passed: report.passed(),
+        }
+    }
+}
+
+impl Report for CheckReport {
+    fn render(&self, out: &mut dyn Write) -> io::Result<()> {
+        heading(out, self.title)?;
+        for check in &self.checks {
+            let status = if check.passed {
+                green("ok")
+            } else {
+                red("FAILED")
+            };
+            write!(out, "[{}] {}", status, check.name)?;
+            if !check.detail.is_empty() {
+                write!(out, ": {}", dim(&check.detail))?;
+            }
+            writeln!(out)?;
+        }
+        if self.passed {
+            writeln!(out, "{}", green(self.success))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_styles_pad_text_only() {
+        STDOUT_COLOR.store(true, Ordering::Relaxed);
+        assert_eq!(format!("{:<5}|", green("ok")), "\x1b[32mok   \x1b[0m|");
+        STDOUT_COLOR.store(false, Ordering::Relaxed);
+        assert_eq!(format!("{:<5}|", green("ok")), "ok   |");
+    }
+
+    #[test]
+    fn test_json_matches_text_fields() {
+        let report = ValidationReport {
+            results: vec![AddressValidity {
+                address: "kaspa:x".to_string(),
+                valid: false,
+            }],
+        };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json[0]["valid"], false);
+
+        let mut text = Vec::new();
+        report.render(&mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("Address: kaspa:x"));
+        assert!(text.contains("false"));
+    }
+
+    #[test]
+    fn test_secrets_are_masked_by_default() {
+        let key = format!("c0ffee{}beef", "00".repeat(28));
+        assert_eq!(mask(&key), "c0ff…beef");
+        assert_eq!(mask("short"), "…");
+        assert_eq!(display_secret(&key, true).as_str(), key);
+        let shown = display_secret(&key, false);
+        if io::stdout().is_terminal() {
+            assert!(shown.starts_with("c0ff…beef "), "{}", shown.as_str());
+        } else {
+            assert!(!shown.contains("c0ff") && !shown.contains("beef"));
+        }
+    }
+}
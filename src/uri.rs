@@ -0,0 +1,161 @@
+use crate::address::validate_address;
+use crate::amount::Kas;
+use crate::error::{WalletError, WalletResult};
+use crate::network::NetworkConfig;
+use std::fmt;
+use std::str::FromStr;
+
+/// A payment request such as `kaspa:<address>?amount=2.5&label=Invoice42`.
+/// The scheme is the address's network prefix, so a testnet request
+/// starts `kaspatest:`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentUri {
+    pub address: String,
+    pub amount: Option<Kas>,
+    pub label: Option<String>,
+    pub message: Option<String>,
+}
+
+fn percent_decode(value: &str) -> WalletResult<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let byte = value
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| {
+                        WalletError::InvalidParameters(format!("Bad escape in URI: {}", value))
+                    })?;
+                decoded.push(byte);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded)
+        .map_err(|_| WalletError::InvalidParameters(format!("URI is not UTF-8: {}", value)))
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+impl FromStr for PaymentUri {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, query) = s.split_once('?').unwrap_or((s, ""));
+        let known = NetworkConfig::all()
+            .iter()
+            .any(|network| address.starts_with(&format!("{}:", network.prefix)));
+        if !known || !validate_address(address)? {
+            return Err(WalletError::InvalidParameters(format!(
+                "Not a payment URI: {}",
+                s
+            )));
+        }
+
+        let mut uri = PaymentUri {
+            address: address.to_string(),
+            amount: None,
+            label: None,
+            message: None,
+        };
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value)?;
+            match key {
+                "amount" => uri.amount = Some(value.parse()?),
+                "label" => uri.label = Some(value),
+                "message" => uri.message = Some(value),
+                // BIP21: unknown required parameters must be refused.
+                key if key.starts_with("req-") => {
+                    return Err(WalletError::InvalidParameters(format!(
+                        "Unsupported required URI parameter: {}",
+                        key
+                    )))
+                }
+                _ => {}
+            }
+        }
+        Ok(uri)
+    }
+}
+
+impl fmt::Display for PaymentUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.address)?;
+        let mut params = Vec::new();
+        if let Some(amount) = self.amount {
+            params.push(format!("amount={}", amount));
+        }
+        if let Some(label) = &self.label {
+            params.push(format!("label={}", percent_encode(label)));
+        }
+        if let Some(message) = &self.message {
+            params.push(format!("message={}", percent_encode(message)));
+        }
+        if !params.is_empty() {
+            write!(f, "?{}", params.join("&"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::KaspaWallet;
+    use secp256k1::Secp256k1;
+
+    #[test]
+    fn test_parse_and_roundtrip() {
+        let secp = Secp256k1::new();
+        let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let address = KaspaWallet::with_network(secret_key, "mainnet")
+            .unwrap()
+            .get_address();
+
+        let uri: PaymentUri = format!("{}?amount=2.5&label=Invoice%2042&foo=bar", address)
+            .parse()
+            .unwrap();
+        assert_eq!(uri.address, address);
+        assert_eq!(uri.amount, Some("2.5".parse().unwrap()));
+        assert_eq!(uri.label.as_deref(), Some("Invoice 42"));
+        assert_eq!(uri.to_string().parse::<PaymentUri>().unwrap(), uri);
+
+        assert!(format!("{}?req-memo=x", address)
+            .parse::<PaymentUri>()
+            .is_err());
+        assert!(format!("{}?amount=abc", address)
+            .parse::<PaymentUri>()
+            .is_err());
+        assert!("kaspa:bogus?amount=1".parse::<PaymentUri>().is_err());
+
+        let testnet = KaspaWallet::with_network(secret_key, "testnet-10")
+            .unwrap()
+            .get_address();
+        let uri: PaymentUri = format!("{}?amount=1", testnet).parse().unwrap();
+        assert!(uri.to_string().starts_with("kaspatest:"));
+        let unknown = address.replacen("kaspa:", "bitcoin:", 1);
+        assert!(unknown.parse::<PaymentUri>().is_err());
+    }
+}
@@ -0,0 +1,342 @@
+use crate::address;
+use crate::amount::Kas;
+use crate::error::{WalletError, WalletResult};
+use crate::transaction::TXID_LEN;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// DAA score a coinbase output must age before it can be spent.
+pub const COINBASE_MATURITY: u64 = 100;
+
+/// An unspent output owned by the wallet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Utxo {
+    pub txid: String,
+    pub vout: u32,
+    pub address: String,
+    pub amount: u64,
+    /// DAA score of the block that created the output.
+    #[serde(default)]
+    pub block_daa_score: u64,
+    #[serde(default)]
+    pub is_coinbase: bool,
+}
+
+impl Utxo {
+    pub fn outpoint(&self) -> (String, u32) {
+        (self.txid.clone(), self.vout)
+    }
+
+    /// DAA score elapsed since the output was created; unknown without
+    /// the virtual DAA score.
+    pub fn age(&self, virtual_daa_score: Option<u64>) -> Option<u64> {
+        virtual_daa_score.map(|score| score.saturating_sub(self.block_daa_score))
+    }
+
+    /// Whether the output can be spent; only coinbase outputs have to wait,
+    /// and without the virtual DAA score none is taken to have waited long
+    /// enough.
+    pub fn is_mature(&self, virtual_daa_score: Option<u64>) -> bool {
+        !self.is_coinbase
+            || self
+                .age(virtual_daa_score)
+                .is_some_and(|age| age >= COINBASE_MATURITY)
+    }
+}
+
+/// An output to spend, as given with `--inputs`: the outpoint and, when
+/// known, the amount and locking script of the output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtxoEntry {
+    pub txid: String,
+    pub vout: u32,
+    pub amount: Option<u64>,
+    /// Hex script public key of the output.
+    pub script: Option<String>,
+}
+
+impl From<(String, u32)> for UtxoEntry {
+    fn from((txid, vout): (String, u32)) -> Self {
+        Self {
+            txid,
+            vout,
+            amount: None,
+            script: None,
+        }
+    }
+}
+
+/// The entry spending `utxo`, with its amount and the script of its
+/// address.
+impl From<&Utxo> for UtxoEntry {
+    fn from(utxo: &Utxo) -> Self {
+        Self {
+            amount: Some(utxo.amount),
+            script: address::script_public_key(&utxo.address)
+                .ok()
+                .map(hex::encode),
+            ..utxo.outpoint().into()
+        }
+    }
+}
+
+/// Parses `txid:vout[:amount[:script_hex]]`. An amount with a decimal point
+/// is KAS, otherwise whole sompi. Errors quote the argument and name the
+/// field that failed.
+impl FromStr for UtxoEntry {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: String| {
+            WalletError::InvalidParameters(format!("Invalid input {:?}: {}", s, reason))
+        };
+
+        let fields: Vec<&str> = s.split(':').collect();
+        if !(2..=4).contains(&fields.len()) {
+            return Err(invalid(format!(
+                "expected txid:vout[:amount[:script_hex]], got {} field(s)",
+                fields.len()
+            )));
+        }
+        let txid = fields[0];
+        if hex::decode(txid).map(|bytes| bytes.len()) != Ok(TXID_LEN) {
+            return Err(invalid(format!(
+                "txid {:?} is not {} hex characters",
+                txid,
+                TXID_LEN * 2
+            )));
+        }
+        let vout = fields[1]
+            .parse()
+            .map_err(|_| invalid(format!("vout {:?} is not a whole number", fields[1])))?;
+        let amount = match fields.get(2) {
+            None => None,
+            Some(amount) if amount.contains('.') => Some(
+                Kas::from_str(amount)
+                    .map_err(|_| invalid(format!("amount {:?} is not a KAS amount", amount)))?
+                    .sompi(),
+            ),
+            Some(amount) => Some(amount.parse().map_err(|_| {
+                invalid(format!(
+                    "amount {:?} is neither whole sompi nor KAS with a decimal point",
+                    amount
+                ))
+            })?),
+        };
+        let script = match fields.get(3) {
+            None => None,
+            Some(script) if !script.is_empty() && hex::decode(script).is_ok() => {
+                Some(script.to_lowercase())
+            }
+            Some(script) => return Err(invalid(format!("script {:?} is not hex", script))),
+        };
+
+        Ok(Self {
+            txid: txid.to_lowercase(),
+            vout,
+            amount,
+            script,
+        })
+    }
+}
+
+/// The wallet's spendable outputs, keyed by outpoint. Serialized as a list.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "Vec<Utxo>", into = "Vec<Utxo>")]
+pub struct UtxoSet {
+    utxos: BTreeMap<(String, u32), Utxo>,
+}
+
+impl UtxoSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, utxo: Utxo) {
+        self.utxos.insert(utxo.outpoint(), utxo);
+    }
+
+    pub fn remove(&mut self, txid: &str, vout: u32) -> Option<Utxo> {
+        self.utxos.remove(&(txid.to_string(), vout))
+    }
+
+    pub fn get(&self, txid: &str, vout: u32) -> Option<&Utxo> {
+        self.utxos.get(&(txid.to_string(), vout))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Utxo> {
+        self.utxos.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.utxos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.utxos.is_empty()
+    }
+
+    /// Sum of all outputs in sompi.
+    pub fn balance(&self) -> u64 {
+        self.utxos.values().map(|utxo| utxo.amount).sum()
+    }
+
+    /// Part of the balance that cannot be spent yet: immature coinbase
+    /// outputs.
+    pub fn pending(&self, virtual_daa_score: Option<u64>) -> u64 {
+        self.utxos
+            .values()
+            .filter(|utxo| !utxo.is_mature(virtual_daa_score))
+            .map(|utxo| utxo.amount)
+            .sum()
+    }
+
+    /// Picks the largest outputs until they cover `amount` plus
+    /// `fee_for(inputs picked)`.
+    pub fn select_largest_first(
+        &self,
+        amount: u64,
+        fee_for: impl Fn(usize) -> u64,
+    ) -> WalletResult<Vec<Utxo>> {
+        let mut candidates: Vec<&Utxo> = self.utxos.values().collect();
+        candidates.sort_by_key(|utxo| std::cmp::Reverse(utxo.amount));
+
+        let mut selected = Vec::new();
+        let mut total = 0u64;
+        for utxo in candidates {
+            if total >= amount.saturating_add(fee_for(selected.len())) && !selected.is_empty() {
+                break;
+            }
+            total = total.saturating_add(utxo.amount);
+            selected.push(utxo.clone());
+        }
+        if total < amount.saturating_add(fee_for(selected.len())) {
+            return Err(WalletError::InsufficientBalance);
+        }
+        Ok(selected)
+    }
+}
+
+impl From<Vec<Utxo>> for UtxoSet {
+    fn from(utxos: Vec<Utxo>) -> Self {
+        utxos.into_iter().collect()
+    }
+}
+
+impl From<UtxoSet> for Vec<Utxo> {
+    fn from(set: UtxoSet) -> Self {
+        set.utxos.into_values().collect()
+    }
+}
+
+impl FromIterator<Utxo> for UtxoSet {
+    fn from_iter<I: IntoIterator<Item = Utxo>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for utxo in iter {
+            set.insert(utxo);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utxo(txid: &str, vout: u32, amount: u64) -> Utxo {
+        Utxo {
+            txid: txid.to_string(),
+            vout,
+            address: "kaspa:abc".to_string(),
+            amount,
+            block_daa_score: 1_000,
+            is_coinbase: false,
+        }
+    }
+
+    #[test]
+    fn test_balance_and_dedup() {
+        let mut set: UtxoSet = [utxo("aa", 0, 100), utxo("aa", 1, 50)]
+            .into_iter()
+            .collect();
+        set.insert(utxo("aa", 0, 100));
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.balance(), 150);
+
+        assert!(set.remove("aa", 1).is_some());
+        assert_eq!(set.balance(), 100);
+    }
+
+    #[test]
+    fn test_select_largest_first() {
+        let set: UtxoSet = [utxo("aa", 0, 100), utxo("bb", 0, 300), utxo("cc", 0, 50)]
+            .into_iter()
+            .collect();
+
+        let selected = set
+            .select_largest_first(250, |inputs| inputs as u64 * 10)
+            .unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].txid, "bb");
+
+        let selected = set
+            .select_largest_first(300, |inputs| inputs as u64 * 10)
+            .unwrap();
+        assert_eq!(selected.len(), 2);
+
+        assert!(matches!(
+            set.select_largest_first(440, |_| 20),
+            Err(WalletError::InsufficientBalance)
+        ));
+    }
+
+    #[test]
+    fn test_coinbase_maturity() {
+        let regular = utxo("aa", 0, 100);
+        assert!(regular.is_mature(Some(1_000)));
+        assert!(regular.is_mature(None));
+
+        let coinbase = Utxo {
+            is_coinbase: true,
+            ..regular
+        };
+        assert_eq!(coinbase.age(Some(1_050)), Some(50));
+        assert!(!coinbase.is_mature(Some(1_050)));
+        assert!(coinbase.is_mature(Some(1_000 + COINBASE_MATURITY)));
+        assert_eq!(coinbase.age(Some(10)), Some(0));
+        // Without a node's DAA score the age is unknown, not guessed.
+        assert_eq!(coinbase.age(None), None);
+        assert!(!coinbase.is_mature(None));
+
+        let set: UtxoSet = [utxo("bb", 0, 300), coinbase].into_iter().collect();
+        assert_eq!(set.pending(Some(1_050)), 100);
+        assert_eq!(set.pending(Some(1_000 + COINBASE_MATURITY)), 0);
+        assert_eq!(set.pending(None), 100);
+    }
+
+    #[test]
+    fn test_parse_utxo_entry() {
+        let txid = "ab".repeat(32);
+        let entry: UtxoEntry = format!("{}:1", txid).parse().unwrap();
+        assert_eq!(entry, UtxoEntry::from((txid.clone(), 1)));
+
+        let entry: UtxoEntry = format!("{}:0:150000000", txid).parse().unwrap();
+        assert_eq!(entry.amount, Some(150_000_000));
+        let entry: UtxoEntry = format!("{}:0:1.5:76A914", txid).parse().unwrap();
+        assert_eq!(entry.amount, Some(150_000_000));
+        assert_eq!(entry.script.as_deref(), Some("76a914"));
+
+        for (input, field) in [
+            (txid.clone(), "got 1 field"),
+            ("abcd:0".to_string(), "txid \"abcd\""),
+            (format!("{}:x", txid), "vout \"x\""),
+            (format!("{}:0:1,5", txid), "amount \"1,5\""),
+            (format!("{}:0:1.5:zz", txid), "script \"zz\""),
+        ] {
+            let err = input.parse::<UtxoEntry>().unwrap_err().to_string();
+            assert!(err.contains(&format!("{:?}", input)), "{}", err);
+            assert!(err.contains(field), "{}", err);
+        }
+    }
+}
@@ -1,27 +1,146 @@
-use crate::address::{generate_address, validate_address};
-use crate::error::WalletError;
+use crate::address::{generate_address, hash160, script_public_key, ADDRESS_VERSION_P2PKH};
+use crate::bip85;
+use crate::descriptor::{DescriptorKey, WalletDescriptor};
+use crate::error::{WalletError, WalletResult};
+use crate::event_log::{EventSink, WalletEvent};
+use crate::hd::{
+    ChildNumber, DerivationPath, ExtendedPrivateKey, ExtendedPublicKey, CHANGE_CHAIN,
+    DEFAULT_GAP_LIMIT, RECEIVE_CHAIN,
+};
 use crate::network::NetworkConfig;
-use crate::transaction::Transaction;
-use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use crate::self_check::SelfCheckReport;
+use crate::transaction::{build_unsigned_transaction, FeeMode, Transaction};
+use crate::utxo::UtxoEntry;
+use bip39::Mnemonic;
+use secp256k1::{Keypair, Message, PublicKey, Secp256k1, SecretKey, Signing};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::sync::Arc;
+use tracing::debug;
+use zeroize::Zeroizing;
+
+const SELF_CHECK_MESSAGE: &[u8] = b"kasparustwallet self-check";
 
 pub struct KaspaWallet {
     secret_key: SecretKey,
     public_key: PublicKey,
     network_config: NetworkConfig,
+    account: Option<HdAccount>,
+    gap_limit: u32,
+    /// First unused index of the receive and change chains.
+    next_index: [u32; 2],
+    event_sink: Option<Arc<dyn EventSink>>,
+}
+
+/// Wipes the key when the wallet goes away: locking, closing it in the
+/// GUI or the end of a command. The account keys wipe themselves, and the
+/// phrase is zeroized by `bip39`.
+impl Drop for KaspaWallet {
+    fn drop(&mut self) {
+        self.secret_key.non_secure_erase();
+    }
+}
+
+/// Account-level extended key of an HD wallet, plus the master key and
+/// phrase it came from when the wallet was restored from a seed.
+struct HdAccount {
+    key: ExtendedPrivateKey,
+    path: DerivationPath,
+    master: Option<ExtendedPrivateKey>,
+    mnemonic: Option<MnemonicSource>,
+}
+
+struct MnemonicSource {
+    mnemonic: Mnemonic,
+    passphrase: Zeroizing<String>,
 }
 
 impl KaspaWallet {
     pub fn new(secret_key: SecretKey, network_config: NetworkConfig) -> Self {
-        let secp = Secp256k1::new();
-        let public_key = PublicKey::from_secret_key(&secp, &secret_key);
+        Self::with_context(&Secp256k1::new(), secret_key, network_config)
+    }
+
+    /// As `new`, reusing `secp` when creating many wallets.
+    pub fn with_context<C: Signing>(
+        secp: &Secp256k1<C>,
+        secret_key: SecretKey,
+        network_config: NetworkConfig,
+    ) -> Self {
+        let public_key = PublicKey::from_secret_key(secp, &secret_key);
 
         Self {
             secret_key,
             public_key,
             network_config,
+            account: None,
+            gap_limit: DEFAULT_GAP_LIMIT,
+            next_index: [0; 2],
+            event_sink: None,
+        }
+    }
+
+    /// Route lifecycle events (derivations, signatures) to `sink`.
+    pub fn set_event_sink(&mut self, sink: Arc<dyn EventSink>) {
+        self.event_sink = Some(sink);
+    }
+
+    fn emit(&self, event: WalletEvent) {
+        if let Some(sink) = &self.event_sink {
+            sink.record(event);
         }
     }
 
+    /// HD wallet for a BIP39 phrase and optional passphrase.
+    pub fn from_mnemonic(
+        mnemonic: &Mnemonic,
+        passphrase: &str,
+        network: &str,
+    ) -> WalletResult<Self> {
+        let seed = Zeroizing::new(mnemonic.to_seed(passphrase));
+        let mut wallet = Self::from_seed(seed.as_ref(), network)?;
+        if let Some(account) = wallet.account.as_mut() {
+            account.mnemonic = Some(MnemonicSource {
+                mnemonic: mnemonic.clone(),
+                passphrase: Zeroizing::new(passphrase.to_string()),
+            });
+        }
+        Ok(wallet)
+    }
+
+    /// HD wallet for account 0 at `m/44'/111111'/0'`; the primary key is the
+    /// first receive address key.
+    pub fn from_seed(seed: &[u8], network: &str) -> WalletResult<Self> {
+        let path = DerivationPath::kaspa_account(0)?;
+        let master = ExtendedPrivateKey::from_seed(seed)?;
+        let key = master.derive_path(&path)?;
+        let mut wallet = Self::from_account_key(key, path, network)?;
+        if let Some(account) = wallet.account.as_mut() {
+            account.master = Some(master);
+        }
+        Ok(wallet)
+    }
+
+    /// HD wallet from an already-derived account key found at `path`.
+    pub fn from_account_key(
+        key: ExtendedPrivateKey,
+        path: DerivationPath,
+        network: &str,
+    ) -> WalletResult<Self> {
+        let network_config = NetworkConfig::from_name(network)?;
+        let first = key
+            .derive_child(ChildNumber::normal(RECEIVE_CHAIN)?)?
+            .derive_child(ChildNumber::normal(0)?)?;
+
+        let mut wallet = Self::new(*first.secret_key(), network_config);
+        wallet.account = Some(HdAccount {
+            key,
+            path,
+            master: None,
+            mnemonic: None,
+        });
+        Ok(wallet)
+    }
+
     pub fn with_network(secret_key: SecretKey, network: &str) -> Result<Self, WalletError> {
         let network_config = NetworkConfig::from_name(network)?;
         Ok(Self::new(secret_key, network_config))
@@ -39,30 +158,80 @@ impl KaspaWallet {
         hex::encode(self.secret_key.secret_bytes())
     }
 
+    /// Same as `get_private_key`, but recorded as a key export event.
+    pub fn export_private_key(&self) -> String {
+        self.emit(WalletEvent::KeyExported {
+            address: self.get_address(),
+        });
+        self.get_private_key()
+    }
+
     pub fn create_transaction(
         &self,
-        inputs: Vec<(String, u32)>,
+        inputs: Vec<UtxoEntry>,
         outputs: Vec<(String, u64)>,
-        _fee_rate: u64,
+        fee_rate: u64,
     ) -> Result<Transaction, WalletError> {
-        let mut tx = Transaction::new();
-
-        for (txid, vout) in inputs {
-            tx.add_input(txid, vout);
-        }
+        let mut tx = build_unsigned_transaction(inputs, outputs)?;
+        self.sign_transaction(&mut tx, fee_rate)?;
+        Ok(tx)
+    }
 
-        for (address, amount) in outputs {
-            if !validate_address(&address)? {
-                return Err(crate::error::WalletError::InvalidAddressFormat);
+    /// Signs every input of `tx` with the key of the address it spends: the
+    /// one whose script is on the input, or the primary key when the input
+    /// has no script. An input paying none of `known_addresses` is refused.
+    #[tracing::instrument(
+        skip_all,
+        fields(
+            network = self.get_network_name(),
+            inputs = tx.inputs.len(),
+            outputs = tx.outputs.len()
+        )
+    )]
+    pub fn sign_transaction(
+        &self,
+        tx: &mut Transaction,
+        fee: impl Into<FeeMode>,
+    ) -> WalletResult<()> {
+        let fee = fee.into();
+        let primary = address_script(&self.get_address())?;
+        let mut known: Option<Vec<(u32, u32, String)>> = None;
+        for i in 0..tx.inputs.len() {
+            let script = match &tx.inputs[i].script {
+                Some(script) if !script.eq_ignore_ascii_case(&primary) => script.to_lowercase(),
+                _ => {
+                    tx.sign_input(i, &self.secret_key, &self.public_key)?;
+                    continue;
+                }
+            };
+            if known.is_none() {
+                known = Some(self.known_scripts()?);
             }
-            tx.add_output(address, amount);
+            let (chain, index) = known
+                .iter()
+                .flatten()
+                .find(|(_, _, known)| *known == script)
+                .map(|(chain, index, _)| (*chain, *index))
+                .ok_or_else(|| {
+                    WalletError::Transaction(format!(
+                        "Input {}:{} does not spend from an address of this wallet",
+                        tx.inputs[i].txid, tx.inputs[i].vout
+                    ))
+                })?;
+            let key = self.address_key(chain, index)?;
+            tx.sign_input(i, key.secret_key(), key.public_key().public_key())?;
         }
+        debug!(mass = tx.mass(), "signed transaction");
 
-        for i in 0..tx.inputs.len() {
-            tx.sign_input(i, &self.secret_key, &self.public_key)?;
+        if !tx.inputs.is_empty() {
+            self.emit(WalletEvent::TransactionSigned {
+                txid: tx.txid()?,
+                total_out: tx.total_output_amount(),
+                fee: tx.fee().unwrap_or_else(|| fee.fee(tx)),
+            });
         }
 
-        Ok(tx)
+        Ok(())
     }
 
     pub fn estimate_transaction_fee(input_count: usize, output_count: usize, fee_rate: u64) -> u64 {
@@ -100,12 +269,237 @@ impl KaspaWallet {
     pub fn get_network_name(&self) -> &str {
         &self.network_config.name
     }
+
+    pub fn is_hd(&self) -> bool {
+        self.account.is_some()
+    }
+
+    pub fn network_config(&self) -> &NetworkConfig {
+        &self.network_config
+    }
+
+    /// Account extended private key, for HD wallets.
+    pub fn account_xprv(&self) -> Option<&ExtendedPrivateKey> {
+        self.account.as_ref().map(|account| &account.key)
+    }
+
+    /// Seed phrase and passphrase, when the wallet was restored from one.
+    pub fn mnemonic(&self) -> Option<(&Mnemonic, &str)> {
+        self.account
+            .as_ref()
+            .and_then(|account| account.mnemonic.as_ref())
+            .map(|source| (&source.mnemonic, source.passphrase.as_str()))
+    }
+
+    /// Short identifier for the wallet: the first four bytes of the hash of
+    /// the account xpub (HD) or of the public key (single key).
+    pub fn fingerprint(&self) -> String {
+        match self.account_xpub() {
+            Some(xpub) => hex::encode(xpub.fingerprint()),
+            None => hex::encode(&hash160(&self.public_key.serialize())[..4]),
+        }
+    }
+
+    /// BIP85 child seed phrase `index` with `word_count` words, recoverable
+    /// from this wallet's seed alone.
+    pub fn derive_child_mnemonic(&self, index: u32, word_count: u32) -> WalletResult<Mnemonic> {
+        let master = self
+            .account
+            .as_ref()
+            .and_then(|account| account.master.as_ref())
+            .ok_or_else(|| {
+                WalletError::InvalidParameters(
+                    "Child mnemonics need a wallet restored from its seed".to_string(),
+                )
+            })?;
+        bip85::derive_mnemonic(master, word_count, index)
+    }
+
+    /// Recomputes everything derived from the secret material and checks it
+    /// against what the wallet holds, plus a sign/verify round trip.
+    pub fn self_check(&self) -> SelfCheckReport {
+        let secp = Secp256k1::new();
+        let mut report = SelfCheckReport::default();
+
+        let derived = PublicKey::from_secret_key(&secp, &self.secret_key);
+        report.record(
+            "public key",
+            derived == self.public_key,
+            "re-derived from the secret key",
+        );
+
+        if let Some(account) = &self.account {
+            let first = ChildNumber::normal(RECEIVE_CHAIN)
+                .and_then(|chain| account.key.derive_child(chain))
+                .and_then(|chain| chain.derive_child(ChildNumber::normal(0)?));
+            report.record(
+                "account key",
+                matches!(&first, Ok(key) if *key.secret_key() == self.secret_key),
+                "index 0 re-derived from the account key",
+            );
+        }
+
+        if let Some((mnemonic, passphrase)) = self.mnemonic() {
+            let seed = Zeroizing::new(mnemonic.to_seed(passphrase));
+            let matches = Self::from_seed(seed.as_ref(), &self.network_config.name)
+                .map(|wallet| wallet.secret_key == self.secret_key)
+                .unwrap_or(false);
+            report.record(
+                "seed phrase",
+                matches,
+                "index 0 re-derived from the mnemonic",
+            );
+        }
+
+        let digest = Sha256::digest(SELF_CHECK_MESSAGE);
+        let message = Message::from_digest_slice(&digest).expect("SHA-256 digest is 32 bytes");
+        let keypair = Keypair::from_secret_key(&secp, &self.secret_key);
+        let signature = secp.sign_schnorr(&message, &keypair);
+        let verified = secp
+            .verify_schnorr(&signature, &message, &self.public_key.x_only_public_key().0)
+            .is_ok();
+        report.record("signature", verified, "schnorr sign/verify round trip");
+
+        report
+    }
+
+    pub fn account_xpub(&self) -> Option<ExtendedPublicKey> {
+        self.account
+            .as_ref()
+            .map(|account| account.key.public_key())
+    }
+
+    pub fn derivation_path(&self) -> Option<&DerivationPath> {
+        self.account.as_ref().map(|account| &account.path)
+    }
+
+    pub fn gap_limit(&self) -> u32 {
+        self.gap_limit
+    }
+
+    /// Receive address at `index`; single-key wallets only have index 0.
+    pub fn receive_address(&self, index: u32) -> WalletResult<String> {
+        self.chain_address(RECEIVE_CHAIN, index)
+    }
+
+    /// Internal (change) address at `index`; HD wallets only.
+    pub fn change_address(&self, index: u32) -> WalletResult<String> {
+        self.chain_address(CHANGE_CHAIN, index)
+    }
+
+    /// Sets the first unused index of `chain`, which bounds `known_addresses`.
+    pub fn set_next_index(&mut self, chain: u32, index: u32) {
+        if let Some(next) = self.next_index.get_mut(chain as usize) {
+            *next = index;
+        }
+    }
+
+    /// Every address the wallet watches and signs for, as (chain, index,
+    /// address): on both chains, up to `gap_limit` past the first unused
+    /// index. A single-key wallet has only its one address.
+    pub fn known_addresses(&self) -> WalletResult<Vec<(u32, u32, String)>> {
+        if self.account.is_none() {
+            return Ok(vec![(RECEIVE_CHAIN, 0, self.get_address())]);
+        }
+        let mut addresses = Vec::new();
+        for chain in [RECEIVE_CHAIN, CHANGE_CHAIN] {
+            let end = self.next_index[chain as usize].saturating_add(self.gap_limit);
+            for index in 0..end {
+                addresses.push((chain, index, self.chain_address(chain, index)?));
+            }
+        }
+        Ok(addresses)
+    }
+
+    fn known_scripts(&self) -> WalletResult<Vec<(u32, u32, String)>> {
+        self.known_addresses()?
+            .into_iter()
+            .map(|(chain, index, address)| Ok((chain, index, address_script(&address)?)))
+            .collect()
+    }
+
+    /// Private key of the HD address at (`chain`, `index`).
+    fn address_key(&self, chain: u32, index: u32) -> WalletResult<ExtendedPrivateKey> {
+        let account = self.account.as_ref().ok_or_else(|| {
+            WalletError::InvalidParameters("This wallet has a single key".to_string())
+        })?;
+        account
+            .key
+            .derive_child(ChildNumber::normal(chain)?)?
+            .derive_child(ChildNumber::normal(index)?)
+    }
+
+    fn chain_address(&self, chain: u32, index: u32) -> WalletResult<String> {
+        match &self.account {
+            Some(account) => {
+                let public_key = account.key.public_key().derive_address_key(chain, index)?;
+                Ok(generate_address(
+                    &public_key,
+                    self.network_config.get_prefix(),
+                ))
+            }
+            None if chain == RECEIVE_CHAIN && index == 0 => Ok(self.get_address()),
+            None => Err(WalletError::InvalidParameters(
+                "This wallet has a single key".to_string(),
+            )),
+        }
+    }
+
+    /// Public description of the wallet sufficient to rebuild a watch-only copy.
+    pub fn export_descriptor(&self) -> WalletDescriptor {
+        let key = match self.account_xpub() {
+            Some(xpub) => DescriptorKey::Xpub {
+                xpub: xpub.to_string(),
+            },
+            None => DescriptorKey::PublicKey {
+                public_key: self.get_public_key(),
+            },
+        };
+
+        WalletDescriptor::new(
+            &self.network_config,
+            ADDRESS_VERSION_P2PKH,
+            key,
+            self.derivation_path().map(|path| path.to_string()),
+            self.gap_limit,
+        )
+    }
+}
+
+/// Hex script public key paying `address`, as kept on transaction inputs.
+fn address_script(address: &str) -> WalletResult<String> {
+    script_public_key(address)
+        .map(hex::encode)
+        .map_err(|e| WalletError::InvalidParameters(format!("Address {}: {}", address, e)))
+}
+
+/// Shows only public details so wallets can be logged at any level.
+impl fmt::Debug for KaspaWallet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KaspaWallet")
+            .field("network", &self.get_network_name())
+            .field("address", &self.get_address())
+            .field("fingerprint", &self.fingerprint())
+            .field("hd", &self.is_hd())
+            .finish_non_exhaustive()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_debug_is_redacted() {
+        let mnemonic = Mnemonic::generate(12).unwrap();
+        let wallet = KaspaWallet::from_mnemonic(&mnemonic, "", "mainnet").unwrap();
+
+        let debug = format!("{:?}", wallet);
+        assert!(debug.contains(&wallet.get_address()));
+        assert!(!debug.contains(&wallet.get_private_key()));
+        assert!(!debug.contains(&mnemonic.to_string()));
+    }
+
     #[test]
     fn test_wallet_creation() {
         let secp = Secp256k1::new();
@@ -126,6 +520,101 @@ mod tests {
         let address = wallet.get_address();
 
         assert!(address.starts_with("kaspa:"));
+        assert_eq!(wallet.receive_address(0).unwrap(), address);
+        assert!(wallet.receive_address(1).is_err());
+        assert!(wallet.change_address(0).is_err());
+
+        let mnemonic = Mnemonic::generate(12).unwrap();
+        let hd = KaspaWallet::from_mnemonic(&mnemonic, "", "mainnet").unwrap();
+        assert_ne!(
+            hd.change_address(0).unwrap(),
+            hd.receive_address(0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_inputs_are_signed_with_their_address_key() {
+        let mnemonic = Mnemonic::generate(12).unwrap();
+        let mut wallet = KaspaWallet::from_mnemonic(&mnemonic, "", "mainnet").unwrap();
+        let xpub = wallet.account_xpub().unwrap();
+        let far = wallet.gap_limit() + 2;
+
+        let mut tx = Transaction::new();
+        for (i, address) in [
+            Some(wallet.change_address(3).unwrap()),
+            None,
+            Some(wallet.change_address(far).unwrap()),
+        ]
+        .iter()
+        .enumerate()
+        {
+            tx.add_input(format!("{:02x}", i).repeat(32), 0);
+            if let Some(address) = address {
+                tx.inputs[i].set_spent_address(address).unwrap();
+            }
+        }
+        tx.add_output(wallet.get_address(), 1_000);
+
+        // Past the gap limit until the index says the chain got that far.
+        assert!(wallet.sign_transaction(&mut tx, 1).is_err());
+        wallet.set_next_index(CHANGE_CHAIN, far);
+        wallet.sign_transaction(&mut tx, 1).unwrap();
+
+        let secp = Secp256k1::new();
+        let keys = [
+            xpub.derive_address_key(CHANGE_CHAIN, 3).unwrap(),
+            wallet.public_key,
+            xpub.derive_address_key(CHANGE_CHAIN, far).unwrap(),
+        ];
+        for (i, key) in keys.iter().enumerate() {
+            let input = &tx.inputs[i];
+            assert_eq!(input.public_key, Some(hex::encode(key.serialize())));
+            let message = Message::from_digest_slice(&tx.get_signature_hash(i).unwrap()).unwrap();
+            let signature = secp256k1::ecdsa::Signature::from_der(
+                &hex::decode(input.signature.as_ref().unwrap()).unwrap(),
+            )
+            .unwrap();
+            assert!(secp.verify_ecdsa(&message, &signature, key).is_ok());
+        }
+
+        let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let stranger = KaspaWallet::with_network(secret_key, "mainnet").unwrap();
+        tx.inputs[1]
+            .set_spent_address(&stranger.get_address())
+            .unwrap();
+        let err = wallet.sign_transaction(&mut tx, 1).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("not spend from an address of this wallet"));
+        assert!(stranger.sign_transaction(&mut tx, 1).is_err());
+    }
+
+    #[test]
+    fn test_known_addresses_cover_both_chains() {
+        let mnemonic = Mnemonic::generate(12).unwrap();
+        let mut wallet = KaspaWallet::from_mnemonic(&mnemonic, "", "mainnet").unwrap();
+        wallet.set_next_index(RECEIVE_CHAIN, 5);
+        let known = wallet.known_addresses().unwrap();
+        let gap = wallet.gap_limit();
+        assert_eq!(known.len() as u32, 5 + gap + gap);
+        assert!(known.contains(&(
+            CHANGE_CHAIN,
+            gap - 1,
+            wallet.change_address(gap - 1).unwrap()
+        )));
+        assert!(known.contains(&(
+            RECEIVE_CHAIN,
+            gap + 4,
+            wallet.receive_address(gap + 4).unwrap()
+        )));
+
+        let secp = Secp256k1::new();
+        let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let single = KaspaWallet::with_network(secret_key, "mainnet").unwrap();
+        assert_eq!(
+            single.known_addresses().unwrap(),
+            vec![(RECEIVE_CHAIN, 0, single.get_address())]
+        );
     }
 
     #[test]
@@ -137,4 +626,20 @@ mod tests {
             "0000000000000000000000000000000000000000000000000000000000000001"
         ));
     }
+
+    #[test]
+    fn test_child_mnemonics() {
+        let mnemonic = Mnemonic::generate(24).unwrap();
+        let wallet = KaspaWallet::from_mnemonic(&mnemonic, "", "mainnet").unwrap();
+
+        let first = wallet.derive_child_mnemonic(3, 12).unwrap();
+        assert_eq!(first.word_count(), 12);
+        assert_eq!(first, wallet.derive_child_mnemonic(3, 12).unwrap());
+        assert_ne!(first, wallet.derive_child_mnemonic(4, 12).unwrap());
+
+        let secp = Secp256k1::new();
+        let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let single = KaspaWallet::with_network(secret_key, "mainnet").unwrap();
+        assert!(single.derive_child_mnemonic(0, 12).is_err());
+    }
 }
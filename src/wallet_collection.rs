@@ -0,0 +1,229 @@
+use crate::config::Config;
+use crate::error::{WalletError, WalletResult};
+use crate::keystore::{Keystore, WalletKind, KEYSTORE_EXTENSION};
+use crate::lockable::LockableWallet;
+use crate::storage::Overwrite;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub const WALLETS_DIR: &str = "wallets";
+/// Name given to a wallet when none is chosen.
+pub const DEFAULT_WALLET_NAME: &str = "main";
+
+/// Exclusive lock on a wallet file, held as `<file>.lock` and released on drop.
+#[derive(Debug)]
+pub struct FileLock {
+    path: PathBuf,
+}
+
+impl FileLock {
+    pub fn acquire(target: &Path) -> WalletResult<Self> {
+        let mut path = target.as_os_str().to_owned();
+        path.push(".lock");
+        let path = PathBuf::from(path);
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::AlreadyExists => WalletError::Storage(format!(
+                    "{} is in use by another process (remove {} if it is stale)",
+                    target.display(),
+                    path.display()
+                )),
+                _ => e.into(),
+            })?;
+        write!(file, "{}", std::process::id())?;
+
+        Ok(Self { path })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalletEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub fingerprint: String,
+    pub network: String,
+    pub kind: WalletKind,
+    pub address: String,
+}
+
+/// The keystore files under `<data dir>/wallets`, plus which one is the
+/// default.
+pub struct WalletCollection {
+    data_dir: PathBuf,
+    config: Config,
+}
+
+impl WalletCollection {
+    pub fn open(data_dir: impl Into<PathBuf>) -> WalletResult<Self> {
+        let data_dir = data_dir.into();
+        let config = Config::load(&data_dir)?;
+        Ok(Self { data_dir, config })
+    }
+
+    pub fn wallets_dir(&self) -> PathBuf {
+        self.data_dir.join(WALLETS_DIR)
+    }
+
+    pub fn path_for(&self, name: &str) -> PathBuf {
+        self.wallets_dir()
+            .join(format!("{}.{}", name, KEYSTORE_EXTENSION))
+    }
+
+    /// Keystores found in the wallets dir, sorted by name. Files that fail to
+    /// parse are skipped.
+    pub fn list(&self) -> WalletResult<Vec<WalletEntry>> {
+        let dir = self.wallets_dir();
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries = Vec::new();
+        for item in fs::read_dir(&dir)? {
+            let path = item?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(KEYSTORE_EXTENSION) {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            if let Ok(keystore) = Keystore::load(&path) {
+                entries.push(WalletEntry {
+                    name: name.to_string(),
+                    path: path.clone(),
+                    fingerprint: keystore.fingerprint,
+                    network: keystore.network,
+                    kind: keystore.kind,
+                    address: keystore.address,
+                });
+            }
+        }
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
+    /// Resolves a wallet name in the collection, or a path to any keystore.
+    pub fn resolve(&self, name_or_path: &str) -> PathBuf {
+        let named = self.path_for(name_or_path);
+        if named.exists() {
+            named
+        } else {
+            PathBuf::from(name_or_path)
+        }
+    }
+
+    /// Opens a wallet (locked) and holds its file lock until dropped.
+    pub fn open_wallet(&self, name_or_path: &str) -> WalletResult<LockableWallet> {
+        let path = self.resolve(name_or_path);
+        if !path.exists() {
+            return Err(WalletError::Storage(format!(
+                "No wallet named {} in {}",
+                name_or_path,
+                self.wallets_dir().display()
+            )));
+        }
+        let file_lock = FileLock::acquire(&path)?;
+        let keystore = Keystore::load(&path)?;
+        Ok(LockableWallet::from_keystore(
+            keystore,
+            path,
+            Some(file_lock),
+        ))
+    }
+
+    /// Writes a new keystore into the collection; never overwrites.
+    pub fn add(&self, keystore: &Keystore) -> WalletResult<PathBuf> {
+        let path = self.path_for(&keystore.name);
+        if path.exists() {
+            return Err(WalletError::Storage(format!(
+                "A wallet named {} already exists",
+                keystore.name
+            )));
+        }
+        fs::create_dir_all(self.wallets_dir())?;
+        keystore.save(&path, Overwrite::Refuse)?;
+        Ok(path)
+    }
+
+    pub fn default_wallet(&self) -> Option<&str> {
+        self.config.default_wallet.as_deref()
+    }
+
+    pub fn set_default(&mut self, name: &str) -> WalletResult<()> {
+        if !self.path_for(name).exists() {
+            return Err(WalletError::Storage(format!("No wallet named {}", name)));
+        }
+        self.config.default_wallet = Some(name.to_string());
+        self.config.save(&self.data_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::KaspaWallet;
+    use secp256k1::Secp256k1;
+
+    const PASSWORD: &str = "correct horse battery staple";
+
+    fn temp_collection(name: &str) -> WalletCollection {
+        let dir =
+            std::env::temp_dir().join(format!("kasparustwallet-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        WalletCollection::open(dir).unwrap()
+    }
+
+    fn add_wallet(collection: &WalletCollection, name: &str) -> KaspaWallet {
+        let secp = Secp256k1::new();
+        let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let wallet = KaspaWallet::with_network(secret_key, "mainnet").unwrap();
+        collection
+            .add(&Keystore::encrypt(&wallet, name, PASSWORD).unwrap())
+            .unwrap();
+        wallet
+    }
+
+    #[test]
+    fn test_list_and_default() {
+        let mut collection = temp_collection("collection-list");
+        let first = add_wallet(&collection, "savings");
+        add_wallet(&collection, "daily");
+
+        let entries = collection.list().unwrap();
+        assert_eq!(
+            entries.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(),
+            vec!["daily", "savings"]
+        );
+        assert_eq!(entries[1].fingerprint, first.fingerprint());
+
+        assert!(collection.set_default("missing").is_err());
+        collection.set_default("savings").unwrap();
+        let reopened = WalletCollection::open(collection.data_dir.clone()).unwrap();
+        assert_eq!(reopened.default_wallet(), Some("savings"));
+    }
+
+    #[test]
+    fn test_concurrent_open_is_refused() {
+        let collection = temp_collection("collection-lock");
+        let wallet = add_wallet(&collection, "main");
+
+        let mut opened = collection.open_wallet("main").unwrap();
+        assert!(collection.open_wallet("main").is_err());
+
+        opened.unlock(PASSWORD).unwrap();
+        assert_eq!(opened.wallet().unwrap().get_address(), wallet.get_address());
+
+        drop(opened);
+        assert!(collection.open_wallet("main").is_ok());
+    }
+}
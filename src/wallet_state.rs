@@ -0,0 +1,293 @@
+use crate::error::{WalletError, WalletResult};
+use crate::node::NodeClient;
+use crate::storage::{self, Overwrite};
+use crate::utxo::{Utxo, UtxoSet};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const WALLET_STATE_FILE: &str = "wallet_state.json";
+
+/// A wallet's addresses and their outputs as of the last sync with the
+/// node.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletState {
+    pub addresses: Vec<String>,
+    pub utxos: UtxoSet,
+    /// The node's virtual DAA score when the outputs were fetched; None
+    /// before the first sync.
+    pub synced_daa_score: Option<u64>,
+}
+
+/// What a sync changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncDiff {
+    pub added: Vec<Utxo>,
+    pub removed: Vec<Utxo>,
+    /// Whether there was no earlier sync to compare with, in which case
+    /// everything is `added`.
+    pub first_sync: bool,
+}
+
+impl SyncDiff {
+    /// Sompi that arrived since the last sync.
+    pub fn received(&self) -> u64 {
+        self.added.iter().map(|utxo| utxo.amount).sum()
+    }
+
+    /// Sompi that left since the last sync.
+    pub fn spent(&self) -> u64 {
+        self.removed.iter().map(|utxo| utxo.amount).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl WalletState {
+    pub fn new(addresses: Vec<String>) -> Self {
+        Self {
+            addresses,
+            ..Self::default()
+        }
+    }
+
+    /// Replaces the outputs with the node's current ones for `addresses`
+    /// and records the node's virtual DAA score, which maturity is then
+    /// judged against.
+    pub fn sync(&mut self, client: &dyn NodeClient) -> WalletResult<SyncDiff> {
+        // Read first: an output created in between is then too young, never
+        // too old.
+        let virtual_daa_score = client.virtual_daa_score()?;
+        let utxos = client.get_utxos_by_addresses(&self.addresses)?;
+        Ok(self.apply_snapshot(utxos, virtual_daa_score))
+    }
+
+    /// Replaces the outputs with `utxos`, read at `virtual_daa_score`, from
+    /// whichever source the caller trusts.
+    pub fn apply_snapshot(&mut self, utxos: Vec<Utxo>, virtual_daa_score: u64) -> SyncDiff {
+        let previous = std::mem::replace(&mut self.utxos, utxos.into_iter().collect());
+        let diff = SyncDiff {
+            added: self
+                .utxos
+                .iter()
+                .filter(|utxo| previous.get(&utxo.txid, utxo.vout).is_none())
+                .cloned()
+                .collect(),
+            removed: previous
+                .iter()
+                .filter(|utxo| self.utxos.get(&utxo.txid, utxo.vout).is_none())
+                .cloned()
+                .collect(),
+            first_sync: self.synced_daa_score.is_none(),
+        };
+        self.synced_daa_score = Some(virtual_daa_score);
+        tracing::debug!(
+            virtual_daa_score,
+            added = diff.added.len(),
+            removed = diff.removed.len(),
+            "synced UTXOs"
+        );
+        diff
+    }
+
+    /// The DAA score ages and maturity are judged against: the last
+    /// sync's, or None before there was one.
+    pub fn virtual_daa_score(&self) -> Option<u64> {
+        self.synced_daa_score
+    }
+
+    pub fn balance(&self) -> u64 {
+        self.utxos.balance()
+    }
+
+    /// Immature coinbase outputs, in sompi.
+    pub fn pending(&self) -> u64 {
+        self.utxos.pending(self.virtual_daa_score())
+    }
+
+    /// The outputs that can be spent now.
+    pub fn spendable(&self) -> UtxoSet {
+        self.utxos
+            .iter()
+            .filter(|utxo| utxo.is_mature(self.virtual_daa_score()))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Every wallet's last sync, persisted in `wallet_state.json` under the
+/// data dir and keyed by the wallet's first address.
+pub struct SyncStore {
+    path: PathBuf,
+    wallets: BTreeMap<String, WalletState>,
+}
+
+impl SyncStore {
+    /// Loads the store; a missing or unreadable file starts it empty, as
+    /// the next sync rebuilds it.
+    pub fn load(data_dir: &Path) -> Self {
+        let path = data_dir.join(WALLET_STATE_FILE);
+        let wallets = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self { path, wallets }
+    }
+
+    pub fn save(&self) -> WalletResult<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string(&self.wallets)
+            .map_err(|e| WalletError::Serialization(format!("Wallet state encode error: {}", e)))?;
+        storage::write_atomic(&self.path, json.as_bytes(), Overwrite::Allow)
+    }
+
+    /// The state last saved for the wallet with `addresses`, or a fresh
+    /// one. Outputs of addresses no longer listed are dropped.
+    pub fn state(&self, addresses: &[String]) -> WalletState {
+        let mut state = addresses
+            .first()
+            .and_then(|first| self.wallets.get(first))
+            .cloned()
+            .unwrap_or_default();
+        state.utxos = state
+            .utxos
+            .iter()
+            .filter(|utxo| addresses.contains(&utxo.address))
+            .cloned()
+            .collect();
+        state.addresses = addresses.to_vec();
+        state
+    }
+
+    pub fn put(&mut self, state: WalletState) {
+        if let Some(first) = state.addresses.first() {
+            self.wallets.insert(first.clone(), state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::{NodeInfo, TxStatus};
+    use crate::transaction::Transaction;
+    use crate::utxo::COINBASE_MATURITY;
+    use std::sync::Mutex;
+
+    /// A node whose outputs and DAA score the test sets.
+    #[derive(Default)]
+    struct Node {
+        utxos: Mutex<Vec<Utxo>>,
+        daa_score: Mutex<u64>,
+    }
+
+    impl NodeClient for Node {
+        fn get_info(&self) -> WalletResult<NodeInfo> {
+            unimplemented!()
+        }
+
+        fn submit_transaction(&self, _tx: &Transaction) -> WalletResult<String> {
+            unimplemented!()
+        }
+
+        fn get_transaction_status(&self, _txid: &str) -> WalletResult<TxStatus> {
+            unimplemented!()
+        }
+
+        fn get_utxos_by_addresses(&self, addresses: &[String]) -> WalletResult<Vec<Utxo>> {
+            Ok(self
+                .utxos
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|utxo| addresses.contains(&utxo.address))
+                .cloned()
+                .collect())
+        }
+
+        fn virtual_daa_score(&self) -> WalletResult<u64> {
+            Ok(*self.daa_score.lock().unwrap())
+        }
+    }
+
+    fn utxo(txid: &str, amount: u64, is_coinbase: bool) -> Utxo {
+        Utxo {
+            txid: txid.repeat(32),
+            vout: 0,
+            address: "kaspa:a".to_string(),
+            amount,
+            block_daa_score: 1_000,
+            is_coinbase,
+        }
+    }
+
+    #[test]
+    fn test_sync_reports_what_changed() {
+        let node = Node::default();
+        *node.utxos.lock().unwrap() = vec![utxo("aa", 100, false), utxo("bb", 500, true)];
+        *node.daa_score.lock().unwrap() = 1_050;
+
+        let mut state = WalletState::new(vec!["kaspa:a".to_string()]);
+        assert_eq!(state.virtual_daa_score(), None);
+        let diff = state.sync(&node).unwrap();
+        assert!(diff.first_sync);
+        assert_eq!(state.virtual_daa_score(), Some(1_050));
+        assert_eq!(diff.received(), 600);
+        assert_eq!(state.balance(), 600);
+        assert_eq!(state.pending(), 500);
+        assert_eq!(state.spendable().balance(), 100);
+
+        *node.utxos.lock().unwrap() = vec![utxo("bb", 500, true), utxo("cc", 120_000_000, false)];
+        *node.daa_score.lock().unwrap() = 1_000 + COINBASE_MATURITY;
+        let diff = state.sync(&node).unwrap();
+        assert!(!diff.first_sync);
+        assert_eq!(diff.added, vec![utxo("cc", 120_000_000, false)]);
+        assert_eq!(diff.removed, vec![utxo("aa", 100, false)]);
+        assert_eq!((diff.received(), diff.spent()), (120_000_000, 100));
+        assert_eq!(state.synced_daa_score, Some(1_000 + COINBASE_MATURITY));
+        assert_eq!(state.pending(), 0);
+
+        assert!(state.sync(&node).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_store_keeps_each_wallets_last_sync() {
+        let dir = std::env::temp_dir().join(format!(
+            "kasparustwallet-wallet-state-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        let addresses = vec!["kaspa:a".to_string(), "kaspa:b".to_string()];
+
+        let mut store = SyncStore::load(&dir);
+        assert_eq!(store.state(&addresses), WalletState::new(addresses.clone()));
+        let mut state = WalletState::new(addresses.clone());
+        state.utxos.insert(utxo("aa", 100, false));
+        state.utxos.insert(Utxo {
+            address: "kaspa:b".to_string(),
+            ..utxo("bb", 200, false)
+        });
+        state.synced_daa_score = Some(7);
+        store.put(state.clone());
+        store.save().unwrap();
+
+        let store = SyncStore::load(&dir);
+        assert_eq!(store.state(&addresses), state);
+        // One address fewer: its outputs are no longer the wallet's.
+        let narrowed = store.state(&addresses[..1]);
+        assert_eq!(narrowed.balance(), 100);
+        assert_eq!(narrowed.synced_daa_score, Some(7));
+
+        fs::write(dir.join(WALLET_STATE_FILE), "not json").unwrap();
+        assert_eq!(
+            SyncStore::load(&dir).state(&addresses).synced_daa_score,
+            None
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
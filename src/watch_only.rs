@@ -0,0 +1,210 @@
+use crate::address::{generate_address, hash160, ADDRESS_VERSION_P2PKH};
+use crate::descriptor::{DescriptorKey, WalletDescriptor};
+use crate::error::{WalletError, WalletResult};
+use crate::hd::{
+    DerivationPath, ExtendedPublicKey, CHANGE_CHAIN, DEFAULT_GAP_LIMIT, RECEIVE_CHAIN,
+};
+use crate::network::NetworkConfig;
+use crate::transaction::{build_unsigned_transaction, Transaction};
+use crate::utxo::UtxoEntry;
+use secp256k1::PublicKey;
+
+/// Prefix the GUI adds when copying a public key.
+pub const PUBLIC_KEY_PREFIX: &str = "kaspa:pk:";
+
+#[derive(Debug, Clone)]
+pub enum WatchOnlyKey {
+    Extended(ExtendedPublicKey),
+    Single(PublicKey),
+}
+
+/// A wallet that knows its public keys but holds no secrets, so it can
+/// derive addresses but never sign.
+#[derive(Debug, Clone)]
+pub struct WatchOnlyWallet {
+    key: WatchOnlyKey,
+    network_config: NetworkConfig,
+    derivation_path: Option<DerivationPath>,
+    gap_limit: u32,
+}
+
+impl WatchOnlyWallet {
+    pub fn from_descriptor(descriptor: &WalletDescriptor) -> WalletResult<Self> {
+        let network_config = NetworkConfig::from_name(&descriptor.network)?;
+        if descriptor.address_prefix != network_config.get_prefix() {
+            return Err(WalletError::InvalidParameters(format!(
+                "Descriptor prefix {} does not match network {}",
+                descriptor.address_prefix, network_config.name
+            )));
+        }
+        if descriptor.address_version != ADDRESS_VERSION_P2PKH {
+            return Err(WalletError::InvalidParameters(format!(
+                "Unsupported address version: {}",
+                descriptor.address_version
+            )));
+        }
+
+        let key = match &descriptor.key {
+            DescriptorKey::Xpub { xpub } => WatchOnlyKey::Extended(xpub.parse()?),
+            DescriptorKey::PublicKey { public_key } => {
+                WatchOnlyKey::Single(PublicKey::from_slice(&hex::decode(public_key)?)?)
+            }
+        };
+        let derivation_path = descriptor
+            .derivation_path
+            .as_deref()
+            .map(str::parse)
+            .transpose()?;
+
+        Ok(Self {
+            key,
+            network_config,
+            derivation_path,
+            gap_limit: descriptor.gap_limit,
+        })
+    }
+
+    /// Watch-only wallet for a single compressed public key, given as hex with
+    /// or without the `kaspa:pk:` prefix.
+    pub fn from_public_key(pubkey_hex: &str, network: &str) -> WalletResult<Self> {
+        let pubkey_hex = pubkey_hex.trim();
+        let pubkey_hex = pubkey_hex
+            .strip_prefix(PUBLIC_KEY_PREFIX)
+            .unwrap_or(pubkey_hex);
+        let public_key = PublicKey::from_slice(&hex::decode(pubkey_hex)?)?;
+
+        Ok(Self {
+            key: WatchOnlyKey::Single(public_key),
+            network_config: NetworkConfig::from_name(network)?,
+            derivation_path: None,
+            gap_limit: DEFAULT_GAP_LIMIT,
+        })
+    }
+
+    pub fn key(&self) -> &WatchOnlyKey {
+        &self.key
+    }
+
+    pub fn get_address(&self) -> WalletResult<String> {
+        self.receive_address(0)
+    }
+
+    /// Receive address at `index`; single-key wallets only have index 0.
+    pub fn receive_address(&self, index: u32) -> WalletResult<String> {
+        self.chain_address(RECEIVE_CHAIN, index)
+    }
+
+    /// Internal (change) address at `index`; HD wallets only.
+    pub fn change_address(&self, index: u32) -> WalletResult<String> {
+        self.chain_address(CHANGE_CHAIN, index)
+    }
+
+    fn chain_address(&self, chain: u32, index: u32) -> WalletResult<String> {
+        let public_key = match &self.key {
+            WatchOnlyKey::Extended(xpub) => xpub.derive_address_key(chain, index)?,
+            WatchOnlyKey::Single(public_key) if chain == RECEIVE_CHAIN && index == 0 => *public_key,
+            WatchOnlyKey::Single(_) => {
+                return Err(WalletError::InvalidParameters(
+                    "This wallet has a single key".to_string(),
+                ))
+            }
+        };
+        Ok(generate_address(
+            &public_key,
+            self.network_config.get_prefix(),
+        ))
+    }
+
+    /// Unsigned transaction spending this wallet's UTXOs; sign it elsewhere.
+    pub fn create_unsigned_transaction(
+        &self,
+        inputs: Vec<UtxoEntry>,
+        outputs: Vec<(String, u64)>,
+    ) -> WalletResult<Transaction> {
+        build_unsigned_transaction(inputs, outputs)
+    }
+
+    /// Always fails: a watch-only wallet holds no secret key.
+    pub fn sign_transaction(&self, _tx: &mut Transaction) -> WalletResult<()> {
+        Err(WalletError::WatchOnly)
+    }
+
+    pub fn get_network_name(&self) -> &str {
+        &self.network_config.name
+    }
+
+    pub fn derivation_path(&self) -> Option<&DerivationPath> {
+        self.derivation_path.as_ref()
+    }
+
+    /// Same identifier as `KaspaWallet::fingerprint` gives the full wallet.
+    pub fn fingerprint(&self) -> String {
+        match &self.key {
+            WatchOnlyKey::Extended(xpub) => hex::encode(xpub.fingerprint()),
+            WatchOnlyKey::Single(public_key) => hex::encode(&hash160(&public_key.serialize())[..4]),
+        }
+    }
+
+    pub fn gap_limit(&self) -> u32 {
+        self.gap_limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::KaspaWallet;
+    use secp256k1::Secp256k1;
+
+    #[test]
+    fn test_descriptor_restores_receive_addresses() {
+        let wallet = KaspaWallet::from_seed(&[42u8; 64], "testnet-10").unwrap();
+        let descriptor =
+            WalletDescriptor::from_json(&wallet.export_descriptor().to_json().unwrap()).unwrap();
+        let watch_only = WatchOnlyWallet::from_descriptor(&descriptor).unwrap();
+
+        assert_eq!(watch_only.get_network_name(), "testnet-10");
+        assert_eq!(watch_only.fingerprint(), wallet.fingerprint());
+        for index in 0..20 {
+            assert_eq!(
+                watch_only.receive_address(index).unwrap(),
+                wallet.receive_address(index).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_single_key_descriptor() {
+        let secp = Secp256k1::new();
+        let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let wallet = KaspaWallet::with_network(secret_key, "mainnet").unwrap();
+        let watch_only = WatchOnlyWallet::from_descriptor(&wallet.export_descriptor()).unwrap();
+
+        assert_eq!(watch_only.get_address().unwrap(), wallet.get_address());
+        assert_eq!(watch_only.fingerprint(), wallet.fingerprint());
+        assert!(watch_only.receive_address(1).is_err());
+    }
+
+    #[test]
+    fn test_from_public_key() {
+        let secp = Secp256k1::new();
+        let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+        let wallet = KaspaWallet::with_network(secret_key, "mainnet").unwrap();
+
+        let prefixed = format!("{}{}", PUBLIC_KEY_PREFIX, wallet.get_public_key());
+        let watch_only = WatchOnlyWallet::from_public_key(&prefixed, "mainnet").unwrap();
+        assert_eq!(watch_only.get_address().unwrap(), wallet.get_address());
+
+        let mut tx = watch_only
+            .create_unsigned_transaction(
+                vec![("00".repeat(32), 0).into()],
+                vec![(wallet.get_address(), 1000)],
+            )
+            .unwrap();
+        assert!(tx.inputs[0].signature.is_none());
+        assert!(matches!(
+            watch_only.sign_transaction(&mut tx),
+            Err(WalletError::WatchOnly)
+        ));
+    }
+}
@@ -0,0 +1,40 @@
+//! Fixtures shared by the CLI tests. Each test binary uses only some of them.
+#![allow(dead_code)]
+
+use assert_cmd::Command;
+use std::path::PathBuf;
+
+pub const PASSWORD: &str = "correct horse battery staple tests";
+
+/// A path under the temp dir, unique to this test binary and process.
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "kasparustwallet-{}-{}-{}",
+        env!("CARGO_CRATE_NAME"),
+        name,
+        std::process::id()
+    ))
+}
+
+/// The CLI with its own data dir, no key in the environment and
+/// `PASSWORD` for any keystore.
+pub fn wallet(data_dir: &str) -> Command {
+    let mut command = Command::cargo_bin("kasparustwallet").unwrap();
+    command
+        .arg("--data-dir")
+        .arg(temp_path(data_dir))
+        .env_remove("KASPA_PRIVATE_KEY")
+        .env("KASPA_WALLET_PASSWORD", PASSWORD);
+    command
+}
+
+/// Runs `command`, which must succeed, and returns its stdout.
+pub fn stdout(command: &mut Command) -> String {
+    let output = command.output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
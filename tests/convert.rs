@@ -0,0 +1,34 @@
+mod common;
+
+use assert_cmd::Command;
+use common::stdout;
+
+fn convert() -> Command {
+    let mut command = common::wallet("convert");
+    command.arg("convert");
+    command
+}
+
+#[test]
+fn converts_both_ways() {
+    let out = stdout(convert().arg("1.5kas"));
+    assert!(out.contains("KAS:   1.5\n"));
+    assert!(out.contains("Sompi: 150000000\n"));
+
+    let out = stdout(convert().args(["150000000sompi", "--price", "0.10"]));
+    assert!(out.contains("KAS:   1.5\n"));
+    assert!(out.contains("USD:   0.15\n"));
+}
+
+#[test]
+fn reads_stdin() {
+    let out = stdout(convert().args(["--from", "kas"]).write_stdin("0.001\n"));
+    assert!(out.contains("Sompi: 100000\n"));
+}
+
+#[test]
+fn bad_amounts_exit_2() {
+    convert().arg("0.000000001kas").assert().code(2);
+    convert().arg("18446744073709551616sompi").assert().code(2);
+    convert().arg("42").assert().code(2);
+}
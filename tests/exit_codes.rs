@@ -0,0 +1,348 @@
+mod common;
+
+use common::{temp_path, wallet};
+use kasparustwallet::wallet::KaspaWallet;
+use secp256k1::Secp256k1;
+use std::path::PathBuf;
+
+fn key_file(name: &str) -> (PathBuf, String) {
+    let secp = Secp256k1::new();
+    let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+    let address = KaspaWallet::with_network(secret_key, "mainnet")
+        .unwrap()
+        .get_address();
+    let path = temp_path(&format!("{}.key", name));
+    std::fs::write(&path, hex::encode(secret_key.secret_bytes())).unwrap();
+    (path, address)
+}
+
+#[test]
+fn validation_usage_and_funds() {
+    wallet("codes")
+        .args(["info", "--address", "kaspa:bogus"])
+        .assert()
+        .code(1);
+    wallet("codes")
+        .args(["info", "--bogus-flag"])
+        .assert()
+        .code(2);
+
+    let (path, address) = key_file("funds");
+    let input = format!("{}:0:1000", "ab".repeat(32));
+    wallet("codes")
+        .args(["--yes", "send", "--private-key-file"])
+        .arg(&path)
+        .args(["--inputs", &input, "--outputs", &format!("{}:1", address)])
+        .assert()
+        .code(3);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn address_index_stays_below_hardened() {
+    for index in ["2147483648", "4294967295"] {
+        let stderr = wallet("codes")
+            .args(["address", "--index", index])
+            .assert()
+            .code(2)
+            .get_output()
+            .stderr
+            .clone();
+        let stderr = String::from_utf8(stderr).unwrap();
+        assert!(stderr.contains("--index"), "{}", stderr);
+        assert!(!stderr.contains("panicked"), "{}", stderr);
+    }
+}
+
+/// A local address nothing listens on.
+fn closed_port() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+#[test]
+fn network_auth_and_internal() {
+    let (path, address) = key_file("network");
+    let _ = std::fs::remove_file(&path);
+    // Something listens, but it is no node this build can talk to.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let open = listener.local_addr().unwrap().to_string();
+    wallet("codes")
+        .args(["utxos", "--node", &open, "--address", &address])
+        .assert()
+        .code(4);
+    wallet("codes")
+        .args(["node", "ping", "--count", "1", "--node-url", &open])
+        .assert()
+        .success();
+    wallet("codes")
+        .args(["utxos", "--node", &closed_port(), "--address", &address])
+        .assert()
+        .code(15);
+    wallet("codes")
+        .args(["balance", "--node", &closed_port(), "--address", &address])
+        .assert()
+        .code(15);
+    wallet("codes")
+        .args(["node", "info", "--node", "wss://127.0.0.1:17110"])
+        .assert()
+        .code(16);
+    // Fee estimates fall back to the built-in rates instead of failing.
+    let output = wallet("codes")
+        .args([
+            "estimate-fee",
+            "--inputs",
+            "1",
+            "--outputs",
+            "2",
+            "--from-node",
+        ])
+        .args(["--node", &closed_port()])
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    assert!(String::from_utf8_lossy(&output.stderr).contains("using the built-in rates"));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("normal      2000 sompi/kg"));
+
+    wallet("auth")
+        .args(["wallet", "create", "--name", "main"])
+        .assert()
+        .success();
+    wallet("auth")
+        .env("KASPA_WALLET_PASSWORD", "a different but long passphrase")
+        .args(["info", "--wallet", "main"])
+        .assert()
+        .code(5);
+    let _ = std::fs::remove_dir_all(temp_path("auth"));
+
+    // A data dir that is a regular file cannot hold wallets.
+    let file = temp_path("not-a-dir");
+    std::fs::write(&file, "").unwrap();
+    wallet("not-a-dir")
+        .args(["wallet", "create", "--name", "main"])
+        .assert()
+        .code(6);
+    let _ = std::fs::remove_file(&file);
+}
+
+#[test]
+fn explorer_must_be_configured_over_http() {
+    let (_, address) = key_file("explorer");
+    let output = wallet("explorer")
+        .args(["history", "--address", &address])
+        .assert()
+        .code(1)
+        .get_output()
+        .clone();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("No explorer API configured"), "{}", stderr);
+
+    let dir = temp_path("explorer");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("config.json"),
+        "{\"explorer_url\": \"https://api.kaspa.org\"}",
+    )
+    .unwrap();
+    let output = wallet("explorer")
+        .args(["history", "--address", &address])
+        .assert()
+        .code(1)
+        .get_output()
+        .clone();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("HTTPS explorers are not supported"),
+        "{}",
+        stderr
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn timeouts_are_network_errors() {
+    // Accepts connections but never answers.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let silent = listener.local_addr().unwrap().to_string();
+    let dir = temp_path("timeout");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("config.json"),
+        format!("{{\"explorer_url\": \"http://{}\"}}", silent),
+    )
+    .unwrap();
+    let (path, address) = key_file("timeout");
+    let _ = std::fs::remove_file(&path);
+
+    let output = wallet("timeout")
+        .args(["history", "--address", &address])
+        .args(["--rpc-timeout", "1", "--retries", "0"])
+        .assert()
+        .code(4)
+        .get_output()
+        .clone();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!("history request on {}", silent)),
+        "{}",
+        stderr
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn every_configured_node_is_tried() {
+    let dir = temp_path("failover");
+    std::fs::create_dir_all(&dir).unwrap();
+    let (first, second) = (closed_port(), closed_port());
+    std::fs::write(
+        dir.join("config.json"),
+        serde_json::json!({ "node_urls": [first, second] }).to_string(),
+    )
+    .unwrap();
+    let (path, address) = key_file("failover");
+    let _ = std::fs::remove_file(&path);
+
+    let output = wallet("failover")
+        .args(["balance", "--address", &address, "--retries", "0"])
+        .assert()
+        .code(15)
+        .get_output()
+        .clone();
+    // The refusal reported is the last node's, after the first was skipped.
+    assert!(String::from_utf8_lossy(&output.stderr).contains(&second));
+
+    let output = wallet("failover")
+        .args(["node", "info", "--all"])
+        .assert()
+        .code(4)
+        .get_output()
+        .clone();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(&first) && stdout.contains(&second),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("Usable: 0 of 2"), "{}", stdout);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn json_errors_carry_the_code() {
+    let (path, address) = key_file("json");
+    let _ = std::fs::remove_file(&path);
+    let output = wallet("json")
+        .args([
+            "utxos",
+            "--json",
+            "--node",
+            &closed_port(),
+            "--address",
+            &address,
+        ])
+        .assert()
+        .code(15)
+        .get_output()
+        .clone();
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["code"], 15);
+    assert!(error["error"]
+        .as_str()
+        .unwrap()
+        .contains("connection refused"));
+}
+
+#[test]
+fn monitor_timeout_has_its_own_code() {
+    let output = wallet("monitor-help")
+        .args(["monitor", "--help"])
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    let help = String::from_utf8_lossy(&output.stdout);
+    assert!(help.contains("24  --timeout elapsed first"), "{}", help);
+    assert!(!help.contains("  3  "), "{}", help);
+}
+
+#[test]
+fn descriptor_export_keeps_existing_files() {
+    let (key, _) = key_file("descriptor");
+    let output = temp_path("descriptor.json");
+    std::fs::write(&output, "keep me").unwrap();
+
+    let export = |force: bool| {
+        let mut command = wallet("descriptor");
+        command
+            .args(["export-descriptor", "--private-key-file"])
+            .arg(&key)
+            .arg("--output")
+            .arg(&output);
+        if force {
+            command.arg("--force");
+        }
+        command.assert()
+    };
+    let refused = export(false).code(6).get_output().clone();
+    assert!(String::from_utf8_lossy(&refused.stderr).contains("--force"));
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "keep me");
+
+    export(true).success();
+    let descriptor = std::fs::read_to_string(&output).unwrap();
+    let _ = std::fs::remove_file(&key);
+    let _ = std::fs::remove_file(&output);
+    assert!(descriptor.contains("\"public_key\""), "{}", descriptor);
+}
+
+#[test]
+fn help_lists_every_documented_code() {
+    let readme =
+        std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md")).unwrap();
+    let help = common::stdout(wallet("help").arg("--help"));
+    let table = readme
+        .lines()
+        .skip_while(|line| *line != "#### Exit codes")
+        .skip(1)
+        .take_while(|line| !line.starts_with("####"))
+        .filter_map(|line| {
+            line.strip_prefix("| ")?
+                .split(' ')
+                .next()?
+                .parse::<u8>()
+                .ok()
+        })
+        .collect::<Vec<_>>();
+    assert!(table.len() >= 20, "{:?}", table);
+    for code in table {
+        assert!(
+            help.contains(&format!("\n{:>3}  ", code)),
+            "{} missing:\n{}",
+            code,
+            help
+        );
+    }
+}
+
+#[test]
+fn oversized_kdf_params_are_refused() {
+    wallet("kdf")
+        .args(["wallet", "create", "--name", "main"])
+        .assert()
+        .success();
+    let path = temp_path("kdf").join("wallets").join("main.keystore");
+    let mut keystore: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    keystore["crypto"]["m_cost"] = u32::MAX.into();
+    std::fs::write(&path, keystore.to_string()).unwrap();
+
+    let output = wallet("kdf")
+        .args(["info", "--wallet", "main"])
+        .assert()
+        .code(6)
+        .get_output()
+        .clone();
+    assert!(String::from_utf8_lossy(&output.stderr).contains("out of range"));
+    let _ = std::fs::remove_dir_all(temp_path("kdf"));
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use common::{stdout, temp_path};
+use kasparustwallet::batch::parse_batch_csv;
+use kasparustwallet::wallet::KaspaWallet;
+
+#[test]
+fn csv_round_trips_through_batch_validator() {
+    let wallet = KaspaWallet::from_seed(&[7u8; 64], "mainnet").unwrap();
+    let path = temp_path("descriptor.json");
+    std::fs::write(&path, wallet.export_descriptor().to_json().unwrap()).unwrap();
+
+    let csv = stdout(
+        common::wallet("addresses")
+            .args([
+                "export-addresses",
+                "--count",
+                "5",
+                "--start",
+                "3",
+                "--descriptor",
+            ])
+            .arg(&path),
+    );
+    let _ = std::fs::remove_file(&path);
+
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("index,path,address"));
+    let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+    assert_eq!(rows.len(), 5);
+    assert_eq!(rows[0][0], "3");
+    assert!(rows[0][1].ends_with("/0/3"));
+    assert_eq!(rows[4][2], wallet.receive_address(7).unwrap());
+
+    let batch: String = rows
+        .iter()
+        .map(|row| format!("{},1,{}\n", row[2], row[1]))
+        .collect();
+    let payments = parse_batch_csv(&batch, false).unwrap();
+    assert_eq!(payments.len(), 5);
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::{stdout, temp_path, wallet};
+use kasparustwallet::wallet::KaspaWallet;
+use secp256k1::Secp256k1;
+
+fn key() -> (String, KaspaWallet) {
+    let secp = Secp256k1::new();
+    let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+    (
+        hex::encode(secret_key.secret_bytes()),
+        KaspaWallet::with_network(secret_key, "mainnet").unwrap(),
+    )
+}
+
+#[test]
+fn private_key_mode() {
+    let (secret, expected) = key();
+    let path = temp_path("key.txt");
+    std::fs::write(&path, &secret).unwrap();
+
+    let out = stdout(
+        wallet("key")
+            .args(["info", "--private-key-file"])
+            .arg(&path),
+    );
+    assert!(out.contains("Key Type: single"), "{}", out);
+    assert!(out.contains(&format!("Fingerprint: {}", expected.fingerprint())));
+    assert!(out.contains(&format!("Address: {}", expected.get_address())));
+    assert!(!out.contains(&secret));
+
+    let out = stdout(
+        wallet("key")
+            .args(["--yes", "info", "--show-secrets", "--private-key-file"])
+            .arg(&path),
+    );
+    assert!(out.contains(&format!("Private Key: {}", secret)), "{}", out);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn wallet_mode() {
+    stdout(wallet("keystore").args(["wallet", "create", "--name", "savings"]));
+
+    let out = stdout(wallet("keystore").args(["info", "--wallet", "savings"]));
+    assert!(out.contains("Name: savings"), "{}", out);
+    assert!(out.contains("Key Type: HD"));
+    assert!(out.contains("Derivation Path: m/44'/111111'/0'"));
+    assert!(out.contains("Next Receive Index: 0"));
+    assert!(out.contains("Private Key: (hidden"));
+
+    let out =
+        stdout(wallet("keystore").args(["--yes", "info", "--wallet", "savings", "--show-secrets"]));
+    assert!(out.contains("Account Private Key: "), "{}", out);
+
+    let _ = std::fs::remove_dir_all(temp_path("keystore"));
+}
+
+#[test]
+fn read_only_modes() {
+    let (_, expected) = key();
+
+    let out = stdout(wallet("public").args(["info", "--public-key", &expected.get_public_key()]));
+    assert!(out.contains("Key Type: watch-only"), "{}", out);
+    assert!(out.contains(&format!("Fingerprint: {}", expected.fingerprint())));
+    assert!(out.contains(&format!("Address: {}", expected.get_address())));
+
+    let out = stdout(wallet("address").args(["info", "--address", &expected.get_address()]));
+    assert!(
+        out.contains("Key Type: watch-only (address only)"),
+        "{}",
+        out
+    );
+    assert!(out.contains(&format!("Address: {}", expected.get_address())));
+
+    wallet("address")
+        .args(["info", "--address", "kaspa:bogus"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn modes_are_exclusive() {
+    let (_, expected) = key();
+    let address = expected.get_address();
+    let public_key = expected.get_public_key();
+
+    for args in [
+        vec!["--wallet", "savings", "--address", &address],
+        vec!["--public-key", &public_key, "--address", &address],
+        vec!["--private-key-file", "key.txt", "--public-key", &public_key],
+    ] {
+        wallet("exclusive").arg("info").args(&args).assert().code(2);
+    }
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::{stdout, wallet};
+use kasparustwallet::pstx::PartiallySignedTransaction;
+use kasparustwallet::transaction::Transaction;
+use kasparustwallet::utxo::Utxo;
+use kasparustwallet::wallet::KaspaWallet;
+use secp256k1::Secp256k1;
+
+/// A fresh key and an unsigned PSTX spending one of its outputs.
+fn unsigned_pstx() -> (String, String) {
+    let secp = Secp256k1::new();
+    let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+    let wallet = KaspaWallet::with_network(secret_key, "mainnet").unwrap();
+    let entry = Utxo {
+        txid: "11".repeat(32),
+        vout: 0,
+        address: wallet.get_address(),
+        amount: 50_000,
+        block_daa_score: 0,
+        is_coinbase: false,
+    };
+    let mut tx = Transaction::new();
+    tx.add_input(entry.txid.clone(), 0);
+    tx.add_output(wallet.get_address(), 40_000);
+    let pstx = PartiallySignedTransaction::new("mainnet", tx, vec![entry]).unwrap();
+    (
+        hex::encode(secret_key.secret_bytes()),
+        pstx.to_json().unwrap(),
+    )
+}
+
+#[test]
+fn sign_tx_reads_stdin() {
+    let (key, json) = unsigned_pstx();
+    let signed = stdout(
+        wallet("sign")
+            .args(["--yes", "sign-tx"])
+            .env("KASPA_PRIVATE_KEY", &key)
+            .write_stdin(json),
+    );
+    let signed = PartiallySignedTransaction::from_json(&signed).unwrap();
+    assert!(signed.is_fully_signed());
+
+    // Raw hex comes back as signed hex.
+    let hex_str = hex::encode(signed.transaction.encode().unwrap());
+    let resigned = stdout(
+        wallet("sign-hex")
+            .args(["--yes", "sign-tx"])
+            .env("KASPA_PRIVATE_KEY", &key)
+            .write_stdin(format!("  {}\n", hex_str)),
+    );
+    assert!(Transaction::from_hex(resigned.trim()).is_ok());
+}
+
+#[test]
+fn decode_tx_reads_stdin() {
+    let (_, json) = unsigned_pstx();
+    let pstx = PartiallySignedTransaction::from_json(&json).unwrap();
+    let txid = pstx.transaction.txid().unwrap();
+
+    let decoded = stdout(wallet("decode").arg("decode-tx").write_stdin(json));
+    assert!(decoded.contains(&txid));
+
+    let hex_str = hex::encode(pstx.transaction.encode().unwrap());
+    let decoded = stdout(
+        wallet("decode-hex")
+            .arg("decode-tx")
+            .write_stdin(format!("\n{}\n", hex_str)),
+    );
+    assert!(decoded.contains(&txid));
+}
+
+#[test]
+fn broadcast_reads_stdin() {
+    let (_, json) = unsigned_pstx();
+    let output = wallet("broadcast")
+        .arg("broadcast")
+        .write_stdin(json)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not signed"));
+
+    // A well-formed transaction gets as far as the node connection.
+    let (key, json) = unsigned_pstx();
+    let signed = stdout(
+        wallet("broadcast-sign")
+            .args(["--yes", "sign-tx"])
+            .env("KASPA_PRIVATE_KEY", &key)
+            .write_stdin(json),
+    );
+    let output = wallet("broadcast-signed")
+        .args(["--node", "127.0.0.1:1", "broadcast"])
+        .write_stdin(signed)
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&output.stderr).contains("connection refused"));
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::{temp_path, wallet};
+
+#[test]
+fn piped_output_hides_secrets_by_default() {
+    let output = wallet("hidden")
+        .args(["wallet", "create", "--name", "main"])
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("(hidden, pass --show-secrets)"),
+        "{}",
+        stdout
+    );
+
+    let output = wallet("hidden")
+        .args(["--yes", "--show-secrets", "info", "--wallet", "main"])
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout
+        .lines()
+        .find(|line| line.starts_with("Private Key: "))
+        .unwrap();
+    assert_eq!(line.len(), "Private Key: ".len() + 64, "{}", stdout);
+
+    wallet("hidden")
+        .args(["wallet", "export-key", "--i-understand-the-risk"])
+        .assert()
+        .code(1);
+    let _ = std::fs::remove_dir_all(temp_path("hidden"));
+}
+
+#[test]
+fn fresh_secrets_must_be_shown_or_saved() {
+    let output = wallet("fresh")
+        .args(["new"])
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Private Key: (hidden, pass --show-secrets)"),
+        "{}",
+        stdout
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--output"));
+    wallet("fresh").args(["generate-mnemonic"]).assert().code(1);
+    wallet("fresh").args(["new", "--mnemonic"]).assert().code(1);
+
+    let path = temp_path("fresh.txt");
+    wallet("fresh")
+        .args(["generate-mnemonic", "--output"])
+        .arg(&path)
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read_to_string(&path)
+            .unwrap()
+            .split_whitespace()
+            .count(),
+        24
+    );
+    let _ = std::fs::remove_file(&path);
+
+    let output = wallet("fresh")
+        .args(["--yes", "--show-secrets", "new"])
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Private Key: "));
+}
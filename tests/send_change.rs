@@ -0,0 +1,102 @@
+mod common;
+
+use common::wallet;
+use kasparustwallet::transaction::Transaction;
+use kasparustwallet::wallet::KaspaWallet;
+use secp256k1::Secp256k1;
+
+const PAYEE: &str = "kaspa:1C6Rc3w25VHud3dLDamutaqfKWqhrLRTaD";
+
+fn send(name: &str, args: &[&str]) -> assert_cmd::assert::Assert {
+    let secp = Secp256k1::new();
+    let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+    wallet(name)
+        .env("KASPA_PRIVATE_KEY", hex::encode(secret_key.secret_bytes()))
+        .args(["--yes", "send", "--outputs", &format!("{}:1", PAYEE)])
+        .args(args)
+        .assert()
+}
+
+fn input(amount: &str) -> String {
+    format!("{}:0:{}", "ab".repeat(32), amount)
+}
+
+#[test]
+fn change_is_labelled() {
+    let secp = Secp256k1::new();
+    let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+    let elsewhere = KaspaWallet::with_network(secret_key, "mainnet")
+        .unwrap()
+        .get_address();
+
+    let output = send(
+        "explicit",
+        &["--inputs", &input("2.5"), "--change-address", &elsewhere],
+    )
+    .success()
+    .get_output()
+    .clone();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(&format!("to {} [change]", elsewhere)),
+        "{}",
+        stdout
+    );
+
+    send(
+        "invalid",
+        &["--inputs", &input("2.5"), "--change-address", "kaspa:bogus"],
+    )
+    .code(1);
+
+    // Testnet change on a mainnet send would strand the coins.
+    let testnet = KaspaWallet::with_network(secret_key, "testnet-10")
+        .unwrap()
+        .get_address();
+    assert!(testnet.starts_with("kaspatest:"));
+    let output = send(
+        "other-network",
+        &["--inputs", &input("2.5"), "--change-address", &testnet],
+    )
+    .code(1)
+    .get_output()
+    .clone();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("is not a mainnet address"), "{}", stderr);
+}
+
+#[test]
+fn no_change_needs_an_exact_cover() {
+    // One input and one output: 1195 mass, so 2000 sompi at the default rate.
+    send("exact", &["--inputs", &input("1.00002"), "--no-change"]).success();
+    send("excess", &["--inputs", &input("2.5"), "--no-change"]).code(1);
+    send("short", &["--inputs", &input("1"), "--no-change"]).code(3);
+    send(
+        "plain",
+        &["--inputs", &format!("{}:0", "ab".repeat(32)), "--no-change"],
+    )
+    .code(1);
+}
+
+#[test]
+fn default_rate_meets_the_relay_minimum() {
+    let output = send("default-fee", &["--inputs", &input("10.0")])
+        .success()
+        .get_output()
+        .clone();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let hex = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("Serialized: "))
+        .expect("serialized transaction");
+    let tx = Transaction::from_hex(hex).unwrap();
+    let fee = tx.fee().unwrap();
+    assert!(
+        fee >= tx.minimum_relay_fee(),
+        "fee {} for mass {:?}",
+        fee,
+        tx.mass_breakdown()
+    );
+    // Charged on the mass, not the size alone.
+    assert!(fee > 1000, "{}", fee);
+}
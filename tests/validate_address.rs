@@ -0,0 +1,63 @@
+mod common;
+
+use common::temp_path;
+use kasparustwallet::wallet::KaspaWallet;
+use secp256k1::Secp256k1;
+
+fn address() -> String {
+    let secp = Secp256k1::new();
+    let (secret_key, _) = secp.generate_keypair(&mut rand::rngs::OsRng);
+    KaspaWallet::with_network(secret_key, "mainnet")
+        .unwrap()
+        .get_address()
+}
+
+fn wallet() -> assert_cmd::Command {
+    common::wallet("validate")
+}
+
+#[test]
+fn exit_codes() {
+    let valid = address();
+
+    wallet().args(["validate-address", &valid]).assert().code(0);
+    wallet()
+        .args(["validate-address", &valid, "kaspa:bogus"])
+        .assert()
+        .code(1);
+    wallet().args(["validate-address"]).assert().code(2);
+    wallet()
+        .args(["validate-address", "--bogus-flag"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn quiet_and_file() {
+    let path = temp_path("addresses.txt");
+    std::fs::write(
+        &path,
+        format!("{}\n\n# comment\n{}\n", address(), address()),
+    )
+    .unwrap();
+
+    let output = wallet()
+        .args(["--quiet", "validate-address", "--file"])
+        .arg(&path)
+        .assert()
+        .code(0)
+        .get_output()
+        .clone();
+    assert!(output.stdout.is_empty());
+
+    let output = wallet()
+        .args(["validate-address", "kaspa:bogus", "--file"])
+        .arg(&path)
+        .assert()
+        .code(1)
+        .get_output()
+        .clone();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Valid: 2 of 3"));
+
+    let _ = std::fs::remove_file(&path);
+}